@@ -0,0 +1,431 @@
+//! Core wait graph model, usable without `std`.
+//!
+//! Agents that cannot afford the full `moire-types` surface pre-normalize what
+//! they observe into [`NodeRow`]s and [`EdgeRow`]s and build a
+//! [`WaitGraphCore`] from them. The result is canonical: nodes and edges are
+//! kept in ordered collections and deduplicated, so two agents that saw the
+//! same state produce equal graphs. `moire-web` turns a core graph back into
+//! its own wait graph with `WaitGraph::from_core`, and its wait graphs into
+//! core graphs with `WaitGraph::to_core`.
+//!
+//! Only `alloc` is required.
+
+#![no_std]
+
+extern crate alloc;
+
+use alloc::collections::BTreeMap;
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt;
+
+/// An entity within one process.
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct NodeId {
+    pub process_id: String,
+    pub entity_id: String,
+}
+
+impl NodeId {
+    pub fn new(process_id: impl Into<String>, entity_id: impl Into<String>) -> Self {
+        Self {
+            process_id: process_id.into(),
+            entity_id: entity_id.into(),
+        }
+    }
+
+    /// `process_id::entity_id`, the key `moire-web` uses for wait graph nodes.
+    pub fn key(&self) -> String {
+        format!("{self}")
+    }
+}
+
+impl fmt::Display for NodeId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}::{}", self.process_id, self.entity_id)
+    }
+}
+
+/// Relationship kinds, mirroring `moire_types::EdgeKind`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum EdgeKind {
+    Polls,
+    WaitingOn,
+    PairedWith,
+    HeldBy,
+}
+
+impl EdgeKind {
+    /// Wire name, as serialized by `moire-types`.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Polls => "polls",
+            Self::WaitingOn => "waiting_on",
+            Self::PairedWith => "paired_with",
+            Self::HeldBy => "held_by",
+        }
+    }
+
+    pub fn from_wire(name: &str) -> Option<Self> {
+        match name {
+            "polls" => Some(Self::Polls),
+            "waiting_on" => Some(Self::WaitingOn),
+            "paired_with" => Some(Self::PairedWith),
+            "held_by" => Some(Self::HeldBy),
+            _ => None,
+        }
+    }
+}
+
+/// Wire name of an entity kind from its variant name: `MpscTx` -> `mpsc_tx`.
+pub fn entity_kind_wire_name(variant: &str) -> String {
+    let mut out = String::with_capacity(variant.len() + 4);
+    for (i, c) in variant.char_indices() {
+        if c.is_ascii_uppercase() {
+            if i > 0 {
+                out.push('_');
+            }
+            out.push(c.to_ascii_lowercase());
+        } else {
+            out.push(c);
+        }
+    }
+    out
+}
+
+/// Whether a node of entity kind `kind` (a wire name) can be woken by
+/// something outside the wait graph, such as a peer process or the network.
+pub fn has_external_wake_source(kind: &str) -> bool {
+    matches!(
+        kind,
+        "mpsc_rx"
+            | "broadcast_rx"
+            | "watch_rx"
+            | "oneshot_rx"
+            | "notify"
+            | "semaphore"
+            | "net_accept"
+            | "net_read"
+            | "request"
+            | "response"
+    )
+}
+
+/// Severity bonus, 0 to 3, for a wait that has lasted `age_ms`.
+pub fn wait_age_bonus(age_ms: u64) -> u32 {
+    match age_ms {
+        0..1_000 => 0,
+        1_000..10_000 => 1,
+        10_000..60_000 => 2,
+        _ => 3,
+    }
+}
+
+/// A node as reported by an agent.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct NodeRow {
+    pub id: NodeId,
+    pub name: String,
+    /// Entity kind name, e.g. `future` or `lock`.
+    pub kind: String,
+    pub birth_ms: u64,
+    /// The process's clock when the row was taken.
+    pub ptime_now_ms: u64,
+}
+
+/// An edge as reported by an agent. Both endpoints live in the same process.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct EdgeRow {
+    pub src: NodeId,
+    pub dst: NodeId,
+    pub kind: EdgeKind,
+    /// When the edge was created, if the agent knows.
+    pub since_ms: Option<u64>,
+}
+
+/// One `waiting_on` edge of a [`WaitGraphCore`].
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct WaitEdge {
+    pub src: NodeId,
+    pub dst: NodeId,
+    pub since_ms: Option<u64>,
+}
+
+/// Canonical waiting-on graph.
+///
+/// Only `waiting_on` edges participate, and only nodes they touch are kept.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct WaitGraphCore {
+    pub nodes: BTreeMap<NodeId, NodeRow>,
+    /// Sorted by `(src, dst)`, one edge per pair carrying the earliest known `since_ms`.
+    pub edges: Vec<WaitEdge>,
+}
+
+impl WaitGraphCore {
+    pub fn from_rows(
+        nodes: impl IntoIterator<Item = NodeRow>,
+        edges: impl IntoIterator<Item = EdgeRow>,
+    ) -> Result<Self, String> {
+        let mut rows: BTreeMap<NodeId, NodeRow> = BTreeMap::new();
+        for row in nodes {
+            if let Some(existing) = rows.get(&row.id) {
+                if *existing != row {
+                    return Err(format!(
+                        "invariant violated: conflicting rows for node {}",
+                        row.id
+                    ));
+                }
+                continue;
+            }
+            rows.insert(row.id.clone(), row);
+        }
+
+        let mut waits: BTreeMap<(NodeId, NodeId), Option<u64>> = BTreeMap::new();
+        for edge in edges {
+            if edge.kind != EdgeKind::WaitingOn {
+                continue;
+            }
+            if !rows.contains_key(&edge.src) {
+                return Err(format!(
+                    "invariant violated: missing src node {} for waiting_on edge",
+                    edge.src
+                ));
+            }
+            if !rows.contains_key(&edge.dst) {
+                return Err(format!(
+                    "invariant violated: missing dst node {} for waiting_on edge",
+                    edge.dst
+                ));
+            }
+            if edge.src.process_id != edge.dst.process_id {
+                return Err(format!(
+                    "invariant violated: waiting_on edge {} -> {} crosses processes",
+                    edge.src, edge.dst
+                ));
+            }
+            let since_ms = waits.entry((edge.src, edge.dst)).or_insert(edge.since_ms);
+            *since_ms = match (*since_ms, edge.since_ms) {
+                (Some(a), Some(b)) => Some(a.min(b)),
+                (a, b) => a.or(b),
+            };
+        }
+
+        let mut nodes = BTreeMap::new();
+        let mut wait_edges = Vec::with_capacity(waits.len());
+        for ((src, dst), since_ms) in waits {
+            for id in [&src, &dst] {
+                if let Some(row) = rows.remove(id) {
+                    nodes.insert(id.clone(), row);
+                }
+            }
+            wait_edges.push(WaitEdge { src, dst, since_ms });
+        }
+
+        Ok(Self {
+            nodes,
+            edges: wait_edges,
+        })
+    }
+
+    /// Nodes `id` waits on, sorted.
+    pub fn waits_on<'a>(&'a self, id: &'a NodeId) -> impl Iterator<Item = &'a NodeId> + 'a {
+        self.edges
+            .iter()
+            .filter(move |edge| edge.src == *id)
+            .map(|edge| &edge.dst)
+    }
+
+    /// Number of distinct nodes waiting on `id`.
+    pub fn indegree(&self, id: &NodeId) -> usize {
+        self.edges.iter().filter(|edge| edge.dst == *id).count()
+    }
+
+    /// Wait cycles: strongly connected components of more than one node, and
+    /// nodes waiting on themselves. Each cycle is sorted, and cycles are
+    /// ordered by their first node.
+    pub fn cycles(&self) -> Vec<Vec<NodeId>> {
+        // Iterative Tarjan, so deep wait chains cannot overflow the stack.
+        struct Visit {
+            index: usize,
+            lowlink: usize,
+            on_stack: bool,
+        }
+
+        let mut visits: BTreeMap<&NodeId, Visit> = BTreeMap::new();
+        let mut stack: Vec<&NodeId> = Vec::new();
+        let mut cycles = Vec::new();
+        for root in self.nodes.keys() {
+            if visits.contains_key(root) {
+                continue;
+            }
+            let mut work: Vec<(&NodeId, Vec<&NodeId>)> = Vec::new();
+            let index = visits.len();
+            visits.insert(
+                root,
+                Visit {
+                    index,
+                    lowlink: index,
+                    on_stack: true,
+                },
+            );
+            stack.push(root);
+            work.push((root, self.waits_on(root).collect()));
+
+            while let Some((node, pending)) = work.last_mut() {
+                let node = *node;
+                if let Some(next) = pending.pop() {
+                    match visits.get(next) {
+                        None => {
+                            let index = visits.len();
+                            visits.insert(
+                                next,
+                                Visit {
+                                    index,
+                                    lowlink: index,
+                                    on_stack: true,
+                                },
+                            );
+                            stack.push(next);
+                            work.push((next, self.waits_on(next).collect()));
+                        }
+                        Some(visit) if visit.on_stack => {
+                            let next_index = visit.index;
+                            if let Some(visit) = visits.get_mut(node) {
+                                visit.lowlink = visit.lowlink.min(next_index);
+                            }
+                        }
+                        Some(_) => {}
+                    }
+                    continue;
+                }
+
+                work.pop();
+                let (index, lowlink) = match visits.get(node) {
+                    Some(visit) => (visit.index, visit.lowlink),
+                    None => continue,
+                };
+                if let Some((parent, _)) = work.last()
+                    && let Some(visit) = visits.get_mut(*parent)
+                {
+                    visit.lowlink = visit.lowlink.min(lowlink);
+                }
+                if lowlink != index {
+                    continue;
+                }
+                let mut component = Vec::new();
+                while let Some(member) = stack.pop() {
+                    if let Some(visit) = visits.get_mut(member) {
+                        visit.on_stack = false;
+                    }
+                    component.push(member.clone());
+                    if member == node {
+                        break;
+                    }
+                }
+                let self_wait = component.len() == 1 && self.waits_on(node).any(|dst| dst == node);
+                if component.len() > 1 || self_wait {
+                    component.sort();
+                    cycles.push(component);
+                }
+            }
+        }
+        cycles.sort();
+        cycles
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec;
+
+    use super::*;
+
+    fn row(process_id: &str, entity_id: &str) -> NodeRow {
+        NodeRow {
+            id: NodeId::new(process_id, entity_id),
+            name: String::from(entity_id),
+            kind: String::from("future"),
+            birth_ms: 0,
+            ptime_now_ms: 1_000,
+        }
+    }
+
+    fn wait(src: &str, dst: &str, since_ms: Option<u64>) -> EdgeRow {
+        EdgeRow {
+            src: NodeId::new("p", src),
+            dst: NodeId::new("p", dst),
+            kind: EdgeKind::WaitingOn,
+            since_ms,
+        }
+    }
+
+    #[test]
+    fn kind_helpers_match_the_wire_names() {
+        assert_eq!(entity_kind_wire_name("MpscTx"), "mpsc_tx");
+        assert_eq!(entity_kind_wire_name("Future"), "future");
+        assert!(has_external_wake_source("mpsc_rx"));
+        assert!(has_external_wake_source("net_read"));
+        assert!(!has_external_wake_source("future"));
+        assert!(!has_external_wake_source("mpsc_tx"));
+        assert_eq!(wait_age_bonus(999), 0);
+        assert_eq!(wait_age_bonus(60_000), 3);
+    }
+
+    #[test]
+    fn rows_in_any_order_build_the_same_graph() {
+        let nodes = vec![
+            row("p", "a"),
+            row("p", "b"),
+            row("p", "c"),
+            row("p", "idle"),
+        ];
+        let edges = vec![
+            wait("b", "a", Some(20)),
+            wait("a", "b", None),
+            wait("b", "a", Some(10)),
+            EdgeRow {
+                kind: EdgeKind::HeldBy,
+                ..wait("c", "a", None)
+            },
+        ];
+        let graph = WaitGraphCore::from_rows(nodes.clone(), edges.clone()).unwrap();
+        let reversed =
+            WaitGraphCore::from_rows(nodes.into_iter().rev(), edges.into_iter().rev()).unwrap();
+        assert_eq!(graph, reversed);
+
+        assert_eq!(graph.nodes.len(), 2);
+        assert_eq!(graph.edges.len(), 2);
+        assert_eq!(graph.edges[1].src.key(), "p::b");
+        assert_eq!(graph.edges[1].since_ms, Some(10));
+        assert_eq!(graph.indegree(&NodeId::new("p", "a")), 1);
+
+        let missing = WaitGraphCore::from_rows([row("p", "a")], [wait("a", "b", None)]);
+        assert!(missing.is_err());
+    }
+
+    #[test]
+    fn cycles_are_components_and_self_waits() {
+        let nodes = ["a", "b", "c", "d", "e", "f"].map(|id| row("p", id));
+        let edges = vec![
+            wait("a", "b", None),
+            wait("b", "c", None),
+            wait("c", "a", None),
+            wait("c", "d", None),
+            wait("e", "e", None),
+            wait("f", "a", None),
+        ];
+        let graph = WaitGraphCore::from_rows(nodes, edges).unwrap();
+        let cycles = graph
+            .cycles()
+            .into_iter()
+            .map(|cycle| {
+                cycle
+                    .iter()
+                    .map(|id| id.entity_id.clone())
+                    .collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(cycles, vec![vec!["a", "b", "c"], vec!["e"]]);
+    }
+}
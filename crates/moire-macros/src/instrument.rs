@@ -1,3 +1,6 @@
+// Parsers generated by `keyword!` return `unsynn::Error` as is.
+#![allow(clippy::result_large_err)]
+
 use proc_macro::TokenStream;
 use proc_macro2::{Span, TokenStream as TokenStream2, TokenTree};
 use quote::{quote, quote_spanned};
@@ -36,6 +39,7 @@ pub(crate) fn expand(attr: TokenStream, item: TokenStream) -> TokenStream {
     expand_impl(attr.into(), item.into()).into()
 }
 
+// r[impl api.instrument-attr]
 fn expand_impl(attr: TokenStream2, item: TokenStream2) -> TokenStream2 {
     if !attr.is_empty() {
         return compile_error(
@@ -74,7 +78,8 @@ fn expand_impl(attr: TokenStream2, item: TokenStream2) -> TokenStream2 {
     };
 
     // Use the span of the function name so backtraces point at the original fn,
-    // not at the #[moire::instrument] attribute.
+    // not at the #[moire::instrument] attribute. It also makes `line!()` in the
+    // recorded callsite report the fn's own line.
     let fn_span = name.span();
 
     if is_async {
@@ -85,7 +90,7 @@ fn expand_impl(attr: TokenStream2, item: TokenStream2) -> TokenStream2 {
             fn_span =>
             #attributes_tokens
             #pre_fn_without_async fn #name #pre_params_tokens #params_tokens -> impl ::core::future::Future<Output = #output_ty> #where_clause {
-                ::moire::__internal::instrument_future(#fn_name, async move #body_tokens, None, None).skip_entry_frames(1)
+                ::moire::__internal::instrument_future_at(#fn_name, async move #body_tokens, env!("CARGO_PKG_NAME"), module_path!(), file!(), line!()).skip_entry_frames(1)
             }
         };
     }
@@ -108,7 +113,7 @@ fn expand_impl(attr: TokenStream2, item: TokenStream2) -> TokenStream2 {
         fn_span =>
         #attributes_tokens
         #pre_fn_without_async fn #name #pre_params_tokens #params_tokens #tail_tokens {
-            ::moire::__internal::instrument_future(#fn_name, #body_tokens, None, None).skip_entry_frames(1)
+            ::moire::__internal::instrument_future_at(#fn_name, #body_tokens, env!("CARGO_PKG_NAME"), module_path!(), file!(), line!()).skip_entry_frames(1)
         }
     }
 }
@@ -221,9 +226,9 @@ mod tests {
         let output = expand_impl(TokenStream2::new(), input);
         let expected = quote! {
             pub fn fetch_data(id: u64) -> impl ::core::future::Future<Output = String> {
-                ::moire::__internal::instrument_future("fetch_data", async move {
+                ::moire::__internal::instrument_future_at("fetch_data", async move {
                     id.to_string()
-                }, None, None).skip_entry_frames(1)
+                }, env!("CARGO_PKG_NAME"), module_path!(), file!(), line!()).skip_entry_frames(1)
             }
         };
 
@@ -241,9 +246,9 @@ mod tests {
         let output = expand_impl(TokenStream2::new(), input);
         let expected = quote! {
             fn make_future() -> impl ::core::future::Future<Output = usize> {
-                ::moire::__internal::instrument_future("make_future", {
+                ::moire::__internal::instrument_future_at("make_future", {
                     async { 42 }
-                }, None, None).skip_entry_frames(1)
+                }, env!("CARGO_PKG_NAME"), module_path!(), file!(), line!()).skip_entry_frames(1)
             }
         };
 
@@ -1,8 +1,10 @@
 use moire_types::{
     CutAck, CutId, Edge, Entity, Event, PullChangesResponse, Scope, SeqNo, StreamCursor,
 };
+use std::time::Instant;
 
-use super::db::{runtime_db, runtime_stream_id};
+use super::db::{RuntimeDb, encode_snapshot_reply_json, lock_runtime_db, lock_until, runtime_db};
+use super::error::Error;
 
 pub trait SnapshotSink {
     fn entity(&mut self, entity: &Entity);
@@ -15,9 +17,22 @@ pub fn write_snapshot_to<S>(sink: &mut S)
 where
     S: SnapshotSink,
 {
-    let Ok(db) = runtime_db().lock() else {
-        return;
-    };
+    write_db_to(&lock_runtime_db(), sink);
+}
+
+/// Like [`write_snapshot_to`], giving up at `deadline` rather than waiting
+/// behind instrumentation that holds the runtime state. Nothing is written on
+/// timeout.
+pub fn try_write_snapshot_to<S>(sink: &mut S, deadline: Instant) -> Result<(), Error>
+where
+    S: SnapshotSink,
+{
+    let db = lock_until(runtime_db(), deadline).ok_or(Error::Timeout)?;
+    write_db_to(&db, sink);
+    Ok(())
+}
+
+fn write_db_to<S: SnapshotSink>(db: &RuntimeDb, sink: &mut S) {
     for entity in db.entities.values() {
         sink.entity(entity);
     }
@@ -32,30 +47,19 @@ where
     }
 }
 
+/// This process's snapshot as JSON, in the shape the dashboard receives.
+/// Sections that stay locked past `deadline` are left out and listed in
+/// `timed_out_sections`.
+pub fn snapshot_json(deadline: Instant) -> Result<Vec<u8>, Error> {
+    encode_snapshot_reply_json(deadline)
+}
+
 pub fn pull_changes_since(from_seq_no: SeqNo, max_changes: u32) -> PullChangesResponse {
-    let stream_id = runtime_stream_id();
-    let Ok(db) = runtime_db().lock() else {
-        return PullChangesResponse {
-            stream_id,
-            from_seq_no,
-            next_seq_no: from_seq_no,
-            changes: Vec::new(),
-            truncated: false,
-            compacted_before_seq_no: None,
-        };
-    };
-    db.pull_changes_since(from_seq_no, max_changes)
+    lock_runtime_db().pull_changes_since(from_seq_no, max_changes)
 }
 
 pub fn current_cursor() -> StreamCursor {
-    let stream_id = runtime_stream_id();
-    let Ok(db) = runtime_db().lock() else {
-        return StreamCursor {
-            stream_id,
-            next_seq_no: SeqNo::ZERO,
-        };
-    };
-    db.current_cursor()
+    lock_runtime_db().current_cursor()
 }
 
 pub fn ack_cut(cut_id: CutId) -> CutAck {
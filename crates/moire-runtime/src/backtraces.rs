@@ -0,0 +1,201 @@
+//! Backtrace capture and interning.
+//!
+//! Every instrumented boundary carries a backtrace id. Captured frames are
+//! remapped onto process-wide module ids, and a stack seen before is
+//! answered with the id of its first record, so each distinct stack is sent
+//! to the dashboard and symbolicated once.
+use moire_trace_capture::{CaptureOptions, CapturedBacktrace, capture_current};
+use moire_trace_types::{BacktraceId, FrameKey, ModuleId, RelPc, RuntimeBase};
+use std::collections::{BTreeMap, HashMap};
+use std::ops::Bound;
+use std::sync::{Mutex as StdMutex, OnceLock};
+
+use super::config::{self, BacktraceCapture};
+use super::db;
+
+static BACKTRACE_RECORDS: OnceLock<StdMutex<BacktraceRecords>> = OnceLock::new();
+static MODULE_STATE: OnceLock<StdMutex<ModuleState>> = OnceLock::new();
+
+#[derive(Default)]
+struct ModuleState {
+    revision: u64,
+    by_key: BTreeMap<(RuntimeBase, String), ModuleId>,
+    by_id: BTreeMap<ModuleId, moire_wire::ModuleManifestEntry>,
+}
+
+#[derive(Default)]
+struct BacktraceRecords {
+    by_id: BTreeMap<BacktraceId, moire_wire::BacktraceRecord>,
+    /// Interning index: a call site hit in a loop yields one record.
+    by_frames: HashMap<Vec<FrameKey>, BacktraceId>,
+}
+
+/// Backtrace for an instrumented API boundary, under the configured
+/// [`BacktraceCapture`].
+pub(crate) fn capture_backtrace_id() -> BacktraceId {
+    capture_backtrace_id_at(BacktraceCapture::Always)
+}
+
+/// Backtrace for a newly created entity, scope or instrumented future.
+pub(crate) fn capture_spawn_backtrace_id() -> BacktraceId {
+    capture_backtrace_id_at(BacktraceCapture::OnSpawn)
+}
+
+pub(crate) fn capture_long_poll_backtrace_id() -> BacktraceId {
+    capture_backtrace_id_at(BacktraceCapture::OnLongPoll)
+}
+
+fn capture_backtrace_id_at(needs: BacktraceCapture) -> BacktraceId {
+    if !config::is_enabled() || config::backtrace_capture() < needs {
+        return shared_backtrace_id();
+    }
+    capture_fresh_backtrace_id()
+}
+
+/// Captured once, and carried by every boundary the configuration says not
+/// to capture at.
+fn shared_backtrace_id() -> BacktraceId {
+    static SHARED: OnceLock<BacktraceId> = OnceLock::new();
+    *SHARED.get_or_init(capture_fresh_backtrace_id)
+}
+
+fn capture_fresh_backtrace_id() -> BacktraceId {
+    let backtrace_id = BacktraceId::next()
+        .expect("backtrace id invariant violated: generated id must be valid and JS-safe");
+
+    let captured = capture_current(backtrace_id, CaptureOptions::default()).unwrap_or_else(|err| {
+        panic!("failed to capture backtrace for enabled API boundary: {err}")
+    });
+    // r[impl wire.backtrace-record]
+    let remapped = remap_and_register_backtrace(captured);
+    remember_backtrace_record(remapped)
+}
+
+fn module_state() -> &'static StdMutex<ModuleState> {
+    MODULE_STATE.get_or_init(|| StdMutex::new(ModuleState::default()))
+}
+
+fn module_identity_for(path: &str, runtime_base: RuntimeBase) -> moire_wire::ModuleIdentity {
+    // Deterministic runtime identity until build-id/debug-id extraction is wired.
+    moire_wire::ModuleIdentity::DebugId(format!("runtime:{:x}:{path}", runtime_base.get()))
+}
+
+fn remap_and_register_backtrace(captured: CapturedBacktrace) -> moire_wire::BacktraceRecord {
+    let mut modules = db::lock_recovering(module_state());
+
+    let mut local_to_global: BTreeMap<ModuleId, ModuleId> = BTreeMap::new();
+    for module in &captured.modules {
+        let key = (module.runtime_base, module.path.as_str().to_string());
+        let global = if let Some(existing) = modules.by_key.get(&key).copied() {
+            existing
+        } else {
+            let global = ModuleId::next()
+                .expect("invariant violated: generated module id must be valid and JS-safe");
+            modules.by_key.insert(key.clone(), global);
+            modules.by_id.insert(
+                global,
+                moire_wire::ModuleManifestEntry {
+                    module_id: global,
+                    module_path: key.1.clone(),
+                    runtime_base: key.0,
+                    identity: module_identity_for(&key.1, key.0),
+                    arch: std::env::consts::ARCH.to_string(),
+                },
+            );
+            modules.revision = modules.revision.saturating_add(1);
+            global
+        };
+        local_to_global.insert(module.id, global);
+    }
+
+    let remapped_frames = captured
+        .backtrace
+        .frames
+        .iter()
+        .map(|frame| {
+            let module_id = local_to_global
+                .get(&frame.module_id)
+                .copied()
+                .unwrap_or_else(|| {
+                    panic!(
+                        "invariant violated: missing local module mapping for module_id {}",
+                        frame.module_id
+                    )
+                });
+            FrameKey {
+                module_id,
+                rel_pc: RelPc::new(frame.rel_pc.get())
+                    .expect("invariant violated: rel_pc must be JS-safe"),
+            }
+        })
+        .collect();
+
+    moire_wire::BacktraceRecord::new(captured.backtrace.id, remapped_frames)
+        .expect("invariant violated: remapped backtrace must be valid")
+}
+
+pub(crate) fn module_manifest_snapshot() -> (u64, Vec<moire_wire::ModuleManifestEntry>) {
+    let modules = db::lock_recovering(module_state());
+    (
+        modules.revision,
+        modules.by_id.values().cloned().collect::<Vec<_>>(),
+    )
+}
+
+fn backtrace_records() -> &'static StdMutex<BacktraceRecords> {
+    BACKTRACE_RECORDS.get_or_init(|| StdMutex::new(BacktraceRecords::default()))
+}
+
+/// Stores `record` and returns the id callers should reference: the id of an
+/// earlier record with the same frames if there is one, so each distinct
+/// stack is sent and symbolicated once.
+// r[impl wire.backtrace-record]
+// r[impl process.backtrace-capture.interning]
+pub(crate) fn remember_backtrace_record(record: moire_wire::BacktraceRecord) -> BacktraceId {
+    let mut records = db::lock_recovering(backtrace_records());
+    let record_id = record.id;
+    match records.by_id.get(&record_id) {
+        Some(existing) if existing == &record => return record_id,
+        Some(_) => panic!(
+            "backtrace record invariant violated: conflicting payload for id {}",
+            record_id
+        ),
+        None => {}
+    }
+    if let Some(interned) = records.by_frames.get(&record.frames).copied() {
+        return interned;
+    }
+    records.by_frames.insert(record.frames.clone(), record_id);
+    records.by_id.insert(record_id, record);
+    record_id
+}
+
+pub(crate) fn backtrace_records_after(
+    last_sent_backtrace_id: Option<BacktraceId>,
+) -> Vec<moire_wire::BacktraceRecord> {
+    let records = db::lock_recovering(backtrace_records());
+    let lower = match last_sent_backtrace_id {
+        Some(id) => Bound::Excluded(id),
+        None => Bound::Unbounded,
+    };
+    records
+        .by_id
+        .range((lower, Bound::Unbounded))
+        .map(|(_, record)| record.clone())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // r[verify process.backtrace-capture.interning]
+    #[test]
+    fn repeated_call_site_shares_one_backtrace_record() {
+        let ids: Vec<BacktraceId> = (0..3).map(|_| capture_fresh_backtrace_id()).collect();
+        assert_eq!(ids[0], ids[1]);
+        assert_eq!(ids[1], ids[2]);
+        let sent = backtrace_records_after(None);
+        assert_eq!(sent.iter().filter(|record| record.id == ids[0]).count(), 1);
+    }
+}
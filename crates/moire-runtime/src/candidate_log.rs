@@ -0,0 +1,99 @@
+// r[impl config.log-candidates]
+//! Reports wait cycles to the process's own logs.
+//!
+//! Teams without a dashboard still want deadlocks to reach their log
+//! pipeline. A process built with the `tracing` feature and started with
+//! `MOIRE_LOG_CANDIDATES=<seconds>` looks for wait cycles from a plain thread
+//! at that interval and emits a `tracing` event for each one scoring at least
+//! `MOIRE_LOG_CANDIDATES_MIN_SCORE` (default 4: nothing outside the cycle can
+//! wake it, and it has waited a second). Cycles scoring 5 or more are logged
+//! at `ERROR`, the rest at `WARN`. A cycle is logged once, and again only if
+//! it forms anew.
+use std::collections::HashSet;
+use std::sync::OnceLock;
+use std::time::{Duration, Instant};
+
+use super::config::{env_parse, positive_secs};
+
+const DEFAULT_MIN_SCORE: u32 = 4;
+/// Nothing outside the cycle can wake it and it has waited ten seconds.
+const ERROR_SCORE: u32 = 5;
+/// Budget for building the wait graph, so the analyzer never waits long
+/// behind instrumentation that holds the runtime state.
+const SCAN_BUDGET: Duration = Duration::from_millis(100);
+
+pub(super) fn init_candidate_log() {
+    static STARTED: OnceLock<()> = OnceLock::new();
+    if STARTED.set(()).is_err() {
+        return;
+    }
+
+    let Some(interval) = env_parse(
+        "MOIRE_LOG_CANDIDATES",
+        "a positive number of seconds",
+        positive_secs,
+    ) else {
+        return;
+    };
+    let min_score = env_parse(
+        "MOIRE_LOG_CANDIDATES_MIN_SCORE",
+        "a whole number",
+        |value| value.parse::<u32>().ok(),
+    )
+    .unwrap_or(DEFAULT_MIN_SCORE);
+
+    let spawned = std::thread::Builder::new()
+        .name(String::from("moire-candidate-log"))
+        .spawn(move || {
+            let mut logged: HashSet<Vec<String>> = HashSet::new();
+            loop {
+                std::thread::sleep(interval);
+                if !super::is_enabled() {
+                    continue;
+                }
+                let candidates =
+                    match super::graph::deadlock_candidates(Instant::now() + SCAN_BUDGET) {
+                        Ok(candidates) => candidates.candidates,
+                        Err(e) => {
+                            tracing::debug!(target: "moire", error = %e, "skipped wait cycle scan");
+                            continue;
+                        }
+                    };
+                let mut current = HashSet::new();
+                for candidate in candidates {
+                    if candidate.score < min_score {
+                        continue;
+                    }
+                    current.insert(candidate.node_keys.clone());
+                    if logged.contains(&candidate.node_keys) {
+                        continue;
+                    }
+                    let cycle = candidate.names.join(" -> ");
+                    let nodes = candidate.node_keys.join(",");
+                    if candidate.score >= ERROR_SCORE {
+                        tracing::error!(
+                            target: "moire",
+                            cycle = %cycle,
+                            nodes = %nodes,
+                            worst_wait_ms = candidate.worst_wait_ms,
+                            score = candidate.score,
+                            "wait cycle detected"
+                        );
+                    } else {
+                        tracing::warn!(
+                            target: "moire",
+                            cycle = %cycle,
+                            nodes = %nodes,
+                            worst_wait_ms = candidate.worst_wait_ms,
+                            score = candidate.score,
+                            "wait cycle detected"
+                        );
+                    }
+                }
+                logged = current;
+            }
+        });
+    if let Err(e) = spawned {
+        eprintln!("moire: cannot start MOIRE_LOG_CANDIDATES thread: {e}");
+    }
+}
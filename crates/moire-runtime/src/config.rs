@@ -0,0 +1,326 @@
+// r[impl config.runtime]
+use std::sync::OnceLock;
+use std::sync::atomic::{AtomicBool, AtomicU8, AtomicU32, AtomicU64, AtomicUsize, Ordering};
+use std::time::Duration;
+
+use super::db::lock_runtime_db;
+
+const DEFAULT_MAX_EVENTS: usize = 16_384;
+const DEFAULT_LONG_POLLS_KEPT: usize = 64;
+const DEFAULT_WAKE_GAP_THRESHOLD: Duration = Duration::from_secs(1);
+/// Stored in place of a retention to mean "until no event references it".
+const NO_RETENTION: u64 = u64::MAX;
+
+/// Which API boundaries capture a fresh backtrace. Each mode also captures
+/// everything the modes before it capture.
+///
+/// Boundaries that do not capture carry a single backtrace captured once per
+/// process, so every entity, edge and event still points at a valid record.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum BacktraceCapture {
+    /// Never capture.
+    Never,
+    /// Capture for long polls only.
+    OnLongPoll,
+    /// Also capture when entities, scopes and instrumented futures are
+    /// created.
+    OnSpawn,
+    /// Capture at every instrumented API boundary.
+    Always,
+}
+
+impl BacktraceCapture {
+    fn from_u8(value: u8) -> Self {
+        match value {
+            0 => Self::Never,
+            1 => Self::OnLongPoll,
+            2 => Self::OnSpawn,
+            _ => Self::Always,
+        }
+    }
+
+    fn parse(value: &str) -> Option<Self> {
+        match value {
+            "" | "always" => Some(Self::Always),
+            "on-spawn" => Some(Self::OnSpawn),
+            "on-long-poll" => Some(Self::OnLongPoll),
+            "never" => Some(Self::Never),
+            _ => None,
+        }
+    }
+}
+
+/// How much diagnostic state the runtime keeps and how much it costs to
+/// collect.
+///
+/// Start from [`RuntimeConfig::from_env`] (or [`runtime_config`]) and
+/// override fields:
+///
+/// ```ignore
+/// moire::RuntimeConfig {
+///     backtraces: moire::BacktraceCapture::OnSpawn,
+///     wake_sample_every: 16,
+///     ..moire::RuntimeConfig::from_env()
+/// }
+/// .apply();
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RuntimeConfig {
+    /// How long a dead entity stays visible. `None` keeps it until the last
+    /// event referencing it leaves the event ring.
+    pub dead_entity_retention: Option<Duration>,
+    /// Size of the event ring.
+    pub max_events: usize,
+    /// Long polls kept for snapshots; older ones are dropped first.
+    pub long_polls_kept: usize,
+    pub backtraces: BacktraceCapture,
+    /// Attribute one wake in this many to the future that fired it. Wake
+    /// counts stay exact; only `last_woken_by` is sampled. `1` attributes
+    /// every wake.
+    pub wake_sample_every: u32,
+    /// A future woken this long ago and not polled since is reported with
+    /// `wake_to_poll_gap_ms`. Zero disables the check.
+    pub wake_gap_threshold: Duration,
+    /// Samples of channel depth and handed-out semaphore permits kept per
+    /// primitive, at most one per second. Zero keeps none.
+    pub stat_history: usize,
+}
+
+impl Default for RuntimeConfig {
+    fn default() -> Self {
+        Self {
+            dead_entity_retention: None,
+            max_events: DEFAULT_MAX_EVENTS,
+            long_polls_kept: DEFAULT_LONG_POLLS_KEPT,
+            backtraces: BacktraceCapture::Always,
+            wake_sample_every: 1,
+            wake_gap_threshold: DEFAULT_WAKE_GAP_THRESHOLD,
+            stat_history: 0,
+        }
+    }
+}
+
+impl RuntimeConfig {
+    /// The defaults, overridden by `MOIRE_RETENTION_MS`, `MOIRE_MAX_EVENTS`,
+    /// `MOIRE_LONG_POLLS_KEPT`, `MOIRE_BACKTRACES`, `MOIRE_WAKE_SAMPLE_EVERY`,
+    /// `MOIRE_WAKE_GAP_MS` and `MOIRE_STAT_HISTORY`.
+    ///
+    /// # Panics
+    ///
+    /// If any of them is set to a value it cannot be parsed as.
+    pub fn from_env() -> Self {
+        let mut config = Self::default();
+        if let Some(ms) = env_number("MOIRE_RETENTION_MS") {
+            config.dead_entity_retention = Some(Duration::from_millis(ms));
+        }
+        if let Some(max_events) = env_number("MOIRE_MAX_EVENTS") {
+            config.max_events = max_events as usize;
+        }
+        if let Some(kept) = env_number("MOIRE_LONG_POLLS_KEPT") {
+            config.long_polls_kept = kept as usize;
+        }
+        if let Some(backtraces) = env_parse(
+            "MOIRE_BACKTRACES",
+            "never, on-long-poll, on-spawn, or always",
+            BacktraceCapture::parse,
+        ) {
+            config.backtraces = backtraces;
+        }
+        if let Some(every) = env_parse(
+            "MOIRE_WAKE_SAMPLE_EVERY",
+            "a number from 1 to 4294967295",
+            |value| value.parse::<u32>().ok().filter(|&every| every > 0),
+        ) {
+            config.wake_sample_every = every;
+        }
+        if let Some(ms) = env_number("MOIRE_WAKE_GAP_MS") {
+            config.wake_gap_threshold = Duration::from_millis(ms);
+        }
+        if let Some(kept) = env_number("MOIRE_STAT_HISTORY") {
+            config.stat_history = kept as usize;
+        }
+        config
+    }
+
+    /// Makes this the configuration of the running process. A smaller event
+    /// ring or retention takes effect immediately; backtraces and wake
+    /// sampling apply from the next capture or wake.
+    pub fn apply(self) {
+        let cells = cells();
+        cells
+            .retention_ms
+            .store(retention_ms(self.dead_entity_retention), Ordering::Relaxed);
+        cells.max_events.store(self.max_events, Ordering::Relaxed);
+        cells
+            .long_polls_kept
+            .store(self.long_polls_kept, Ordering::Relaxed);
+        cells
+            .backtraces
+            .store(self.backtraces as u8, Ordering::Relaxed);
+        cells
+            .wake_sample_every
+            .store(self.wake_sample_every.max(1), Ordering::Relaxed);
+        cells
+            .wake_gap_threshold_ms
+            .store(duration_ms(self.wake_gap_threshold), Ordering::Relaxed);
+        cells
+            .stat_history
+            .store(self.stat_history, Ordering::Relaxed);
+        lock_runtime_db().configure(self.max_events, self.dead_entity_retention);
+    }
+}
+
+/// `retention` as stored in its cell. Retentions too long for a `u64` of
+/// milliseconds saturate one short of [`NO_RETENTION`], so they stay
+/// retentions.
+fn retention_ms(retention: Option<Duration>) -> u64 {
+    retention.map_or(NO_RETENTION, |retention| {
+        retention.as_millis().min(u128::from(NO_RETENTION - 1)) as u64
+    })
+}
+
+fn duration_ms(duration: Duration) -> u64 {
+    duration.as_millis().min(u128::from(u64::MAX)) as u64
+}
+
+/// Reads the variable `name` through `parse`. Unset or blank, it is `None`.
+///
+/// A value `parse` rejects panics with the variable's name and what it
+/// expects, so a typo stops the process at startup instead of quietly running
+/// it with the default.
+pub(crate) fn env_parse<T>(
+    name: &str,
+    expected: &str,
+    parse: impl FnOnce(&str) -> Option<T>,
+) -> Option<T> {
+    let value = std::env::var(name).ok()?;
+    let trimmed = value.trim();
+    if trimmed.is_empty() {
+        return None;
+    }
+    match parse(trimmed) {
+        Some(parsed) => Some(parsed),
+        None => panic!("moire: invalid {name}={value:?}; expected {expected}"),
+    }
+}
+
+/// Parses a positive number of seconds, as the watchdog and the candidate log
+/// take their periods.
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) fn positive_secs(value: &str) -> Option<Duration> {
+    value
+        .parse::<f64>()
+        .ok()
+        .and_then(|secs| Duration::try_from_secs_f64(secs).ok())
+        .filter(|period| !period.is_zero())
+}
+
+fn env_number(name: &str) -> Option<u64> {
+    env_parse(name, "a number", |value| value.parse().ok())
+}
+
+struct Cells {
+    retention_ms: AtomicU64,
+    max_events: AtomicUsize,
+    long_polls_kept: AtomicUsize,
+    backtraces: AtomicU8,
+    wake_sample_every: AtomicU32,
+    wake_gap_threshold_ms: AtomicU64,
+    stat_history: AtomicUsize,
+}
+
+fn cells() -> &'static Cells {
+    static CELLS: OnceLock<Cells> = OnceLock::new();
+    CELLS.get_or_init(|| {
+        let config = RuntimeConfig::from_env();
+        Cells {
+            retention_ms: AtomicU64::new(retention_ms(config.dead_entity_retention)),
+            max_events: AtomicUsize::new(config.max_events),
+            long_polls_kept: AtomicUsize::new(config.long_polls_kept),
+            backtraces: AtomicU8::new(config.backtraces as u8),
+            wake_sample_every: AtomicU32::new(config.wake_sample_every),
+            wake_gap_threshold_ms: AtomicU64::new(duration_ms(config.wake_gap_threshold)),
+            stat_history: AtomicUsize::new(config.stat_history),
+        }
+    })
+}
+
+/// The configuration in effect: [`RuntimeConfig::from_env`] until
+/// [`RuntimeConfig::apply`] is called.
+pub fn runtime_config() -> RuntimeConfig {
+    RuntimeConfig {
+        dead_entity_retention: dead_entity_retention(),
+        max_events: max_events(),
+        long_polls_kept: long_polls_kept(),
+        backtraces: backtrace_capture(),
+        wake_sample_every: wake_sample_every(),
+        wake_gap_threshold: wake_gap_threshold(),
+        stat_history: stat_history(),
+    }
+}
+
+pub(crate) fn dead_entity_retention() -> Option<Duration> {
+    match cells().retention_ms.load(Ordering::Relaxed) {
+        NO_RETENTION => None,
+        ms => Some(Duration::from_millis(ms)),
+    }
+}
+
+pub(crate) fn max_events() -> usize {
+    cells().max_events.load(Ordering::Relaxed)
+}
+
+pub(crate) fn long_polls_kept() -> usize {
+    cells().long_polls_kept.load(Ordering::Relaxed)
+}
+
+pub(crate) fn backtrace_capture() -> BacktraceCapture {
+    BacktraceCapture::from_u8(cells().backtraces.load(Ordering::Relaxed))
+}
+
+pub(crate) fn wake_sample_every() -> u32 {
+    cells().wake_sample_every.load(Ordering::Relaxed)
+}
+
+pub(crate) fn wake_gap_threshold() -> Duration {
+    Duration::from_millis(cells().wake_gap_threshold_ms.load(Ordering::Relaxed))
+}
+
+pub(crate) fn stat_history() -> usize {
+    cells().stat_history.load(Ordering::Relaxed)
+}
+
+// r[impl config.runtime-switch]
+fn enabled_cell() -> &'static AtomicBool {
+    static ENABLED: OnceLock<AtomicBool> = OnceLock::new();
+    ENABLED.get_or_init(|| {
+        let enabled = env_parse("MOIRE_DIAGNOSTICS", "on or off", |value| match value {
+            "on" => Some(true),
+            "off" => Some(false),
+            _ => None,
+        })
+        .unwrap_or(true);
+        AtomicBool::new(enabled)
+    })
+}
+
+/// Whether the runtime is collecting. Starts as `MOIRE_DIAGNOSTICS` says,
+/// on unless it is `off`.
+pub fn is_enabled() -> bool {
+    enabled_cell().load(Ordering::Relaxed)
+}
+
+/// Starts collecting. Only resources created from now on are tracked; those
+/// created while collection was off stay invisible for their whole life.
+pub fn enable() {
+    enabled_cell().store(true, Ordering::Relaxed);
+}
+
+/// Stops collecting and drops every entity, edge and event collected so far,
+/// so nothing goes stale while collection is off. Instrumented futures then
+/// poll straight through and API boundaries stop capturing backtraces.
+pub fn disable() {
+    if enabled_cell().swap(false, Ordering::Relaxed) {
+        lock_runtime_db().clear_collected();
+    }
+}
@@ -1,7 +1,7 @@
 use moire_types::SeqNo;
 use std::sync::OnceLock;
-use std::time::Duration;
-use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use std::time::{Duration, Instant};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
 use tokio::net::TcpStream;
 use tokio::time::MissedTickBehavior;
 
@@ -120,6 +120,7 @@ async fn run_dashboard_session(addr: &str, process_name: String) -> Result<(), S
                         write_client_message(&mut writer, &ClientMessage::CutAck(ack)).await?;
                     }
                     ServerMessage::SnapshotRequest(request) => {
+                        let deadline = snapshot_deadline(request.timeout_ms);
                         flush_backtrace_records(
                             &mut writer,
                             process_name.as_str(),
@@ -127,7 +128,11 @@ async fn run_dashboard_session(addr: &str, process_name: String) -> Result<(), S
                             &mut last_sent_backtrace_id,
                         )
                         .await?;
-                        let frame = super::db::encode_snapshot_reply_frame(request.snapshot_id)?;
+                        let frame = super::db::encode_snapshot_reply_frame(
+                            request.snapshot_id,
+                            deadline,
+                            request.encoding,
+                        )?;
                         writer
                             .write_all(&frame)
                             .await
@@ -139,9 +144,16 @@ async fn run_dashboard_session(addr: &str, process_name: String) -> Result<(), S
     }
 }
 
+/// When snapshot assembly has to give up. Half of the server's timeout is left
+/// for the reply to reach it.
+pub(super) fn snapshot_deadline(timeout_ms: i64) -> Instant {
+    let budget_ms = u64::try_from(timeout_ms).unwrap_or(0) / 2;
+    Instant::now() + Duration::from_millis(budget_ms)
+}
+
 // r[impl wire.backtrace-record]
-async fn flush_backtrace_records(
-    writer: &mut tokio::net::tcp::OwnedWriteHalf,
+pub(super) async fn flush_backtrace_records(
+    writer: &mut (impl AsyncWrite + Unpin),
     process_name: &str,
     last_sent_manifest_revision: &mut u64,
     last_sent_backtrace_id: &mut Option<moire_trace_types::BacktraceId>,
@@ -156,8 +168,8 @@ async fn flush_backtrace_records(
     Ok(())
 }
 
-async fn send_handshake_if_manifest_changed(
-    writer: &mut tokio::net::tcp::OwnedWriteHalf,
+pub(super) async fn send_handshake_if_manifest_changed(
+    writer: &mut (impl AsyncWrite + Unpin),
     process_name: &str,
     last_sent_manifest_revision: &mut u64,
 ) -> Result<(), String> {
@@ -165,14 +177,17 @@ async fn send_handshake_if_manifest_changed(
     if revision == *last_sent_manifest_revision {
         return Ok(());
     }
+    let mut args = std::env::args().collect();
+    let mut env = std::env::vars()
+        .map(|(key, value)| format!("{key}={value}"))
+        .collect();
+    super::redact::redact_process(&mut args, &mut env);
     let handshake = ClientMessage::Handshake(moire_wire::Handshake {
         process_id: super::runtime_process_id(),
         process_name: process_name.to_string(),
         pid: std::process::id(),
-        args: std::env::args().collect(),
-        env: std::env::vars()
-            .map(|(key, value)| format!("{key}={value}"))
-            .collect(),
+        args,
+        env,
         module_manifest,
     });
     write_client_message(writer, &handshake).await?;
@@ -180,8 +195,8 @@ async fn send_handshake_if_manifest_changed(
     Ok(())
 }
 
-async fn write_client_message(
-    writer: &mut tokio::net::tcp::OwnedWriteHalf,
+pub(super) async fn write_client_message(
+    writer: &mut (impl AsyncWrite + Unpin),
     message: &ClientMessage,
 ) -> Result<(), String> {
     let frame = encode_client_message_default(message)
@@ -193,8 +208,8 @@ async fn write_client_message(
     Ok(())
 }
 
-async fn read_server_message(
-    reader: &mut tokio::net::tcp::OwnedReadHalf,
+pub(super) async fn read_server_message(
+    reader: &mut (impl AsyncRead + Unpin),
 ) -> Result<Option<ServerMessage>, String> {
     let mut len_buf = [0u8; 4];
     if let Err(e) = reader.read_exact(&mut len_buf).await {
@@ -1,22 +1,50 @@
 use facet::Facet;
 use moire_trace_types::BacktraceId;
 use moire_types::{
-    Change, Edge, EdgeKind, Entity, EntityBody, EntityId, Event, EventTarget, PTime,
-    PullChangesResponse, Scope, ScopeBody, ScopeId, SeqNo, StampedChange, StreamCursor, StreamId,
-    TaskScopeBody,
+    Change, Coverage, Edge, EdgeKind, EdgeReason, Entity, EntityBody, EntityId, Event, EventTarget,
+    LongPoll, PTime, PullChangesResponse, ResponseStatus, RpcMethodStats, RuntimeStats,
+    RuntimeWorker, Scope, ScopeBody, ScopeId, SeqNo, StampedChange, StreamCursor, StreamId,
+    TaskScopeBody, WorkerLoad,
 };
-use std::collections::{BTreeMap, VecDeque, hash_map::DefaultHasher};
+use moire_wire::SnapshotEncoding;
+use std::collections::{BTreeMap, BTreeSet, VecDeque, hash_map::DefaultHasher};
 use std::hash::{Hash, Hasher};
-use std::sync::{Mutex as StdMutex, OnceLock};
+use std::sync::{Mutex as StdMutex, MutexGuard, OnceLock, PoisonError, TryLockError};
+use std::time::{Duration, Instant};
 
+use super::error::Error;
+use super::futures::{poll_worker_counts, unpolled_wakes};
+use super::polls::recent_long_polls;
+use super::redact::redact_entity;
+use super::rpc_stats::{note_completion, rpc_method_stats};
 use super::{
     COMPACT_TARGET_CHANGES, MAX_CHANGES_BEFORE_COMPACT, current_process_scope_id,
-    current_tokio_task_key,
+    current_task_or_thread_key, current_tokio_task_key,
 };
 
 pub(crate) fn runtime_db() -> &'static StdMutex<RuntimeDb> {
     static DB: OnceLock<StdMutex<RuntimeDb>> = OnceLock::new();
-    DB.get_or_init(|| StdMutex::new(RuntimeDb::new(runtime_stream_id(), super::MAX_EVENTS)))
+    DB.get_or_init(|| {
+        let mut db = RuntimeDb::new(runtime_stream_id(), super::config::max_events());
+        db.configure(
+            super::config::max_events(),
+            super::config::dead_entity_retention(),
+        );
+        StdMutex::new(db)
+    })
+}
+
+/// The runtime db, locked. See [`lock_recovering`].
+pub(crate) fn lock_runtime_db() -> MutexGuard<'static, RuntimeDb> {
+    lock_recovering(runtime_db())
+}
+
+/// Locks `mutex`, recovering it if a panic poisoned it. Everything the runtime
+/// keeps behind a mutex is diagnostic state: a half-applied update is better
+/// than instrumentation going dark, or panicking the host, for the rest of the
+/// process.
+pub(crate) fn lock_recovering<T>(mutex: &StdMutex<T>) -> MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(PoisonError::into_inner)
 }
 
 pub(crate) fn runtime_stream_id() -> StreamId {
@@ -39,11 +67,17 @@ pub(crate) struct RuntimeDb {
     task_scope_ids: BTreeMap<String, ScopeId>,
     pub(super) entity_scope_links: BTreeMap<(EntityId, ScopeId), ()>,
     pub(super) edges: BTreeMap<EdgeKey, Edge>,
+    /// Resources with a `held_by` edge, keyed by holder; kept in step with `edges`.
+    holds_by_holder: BTreeMap<EntityId, BTreeSet<EntityId>>,
     pub(super) events: VecDeque<Event>,
     changes: VecDeque<InternalStampedChange>,
     max_events: usize,
     /// Reference counts for entity IDs referenced by events in the ring buffer.
     event_entity_refs: BTreeMap<EntityId, usize>,
+    dead_entity_retention: Option<Duration>,
+    /// Dead entities kept alive by events, in order of death. Only tracked
+    /// while a retention is set.
+    dead_entities: VecDeque<(PTime, EntityId)>,
 }
 
 impl RuntimeDb {
@@ -63,6 +97,7 @@ impl RuntimeDb {
                 | EntityBody::Semaphore(_)
                 | EntityBody::Notify(_)
                 | EntityBody::OnceCell(_)
+                | EntityBody::Barrier(_)
         )
     }
 
@@ -76,13 +111,39 @@ impl RuntimeDb {
             task_scope_ids: BTreeMap::new(),
             entity_scope_links: BTreeMap::new(),
             edges: BTreeMap::new(),
+            holds_by_holder: BTreeMap::new(),
             events: VecDeque::with_capacity(max_events.min(256)),
             changes: VecDeque::new(),
             max_events,
             event_entity_refs: BTreeMap::new(),
+            dead_entity_retention: None,
+            dead_entities: VecDeque::new(),
         }
     }
 
+    /// Applies a new event ring size and dead entity retention, evicting
+    /// whatever no longer fits.
+    pub(crate) fn configure(&mut self, max_events: usize, retention: Option<Duration>) {
+        self.max_events = max_events;
+        self.evict_events();
+        match (self.dead_entity_retention, retention) {
+            (_, None) => self.dead_entities.clear(),
+            (None, Some(_)) => {
+                // Dead entities were not tracked until now.
+                let mut dead = self
+                    .entities
+                    .values()
+                    .filter_map(|entity| Some((entity.removed_at?, entity.id.clone())))
+                    .collect::<Vec<_>>();
+                dead.sort_by_key(|(removed_at, _)| removed_at.as_millis());
+                self.dead_entities = dead.into();
+            }
+            (Some(_), Some(_)) => {}
+        }
+        self.dead_entity_retention = retention;
+        self.sweep_expired_dead_entities();
+    }
+
     fn push_change(&mut self, change: InternalChange) {
         let seq_no = self.next_seq_no;
         self.next_seq_no = self.next_seq_no.next();
@@ -178,7 +239,11 @@ impl RuntimeDb {
         // checkpoint materialization and replay handoff.
     }
 
-    pub(crate) fn upsert_entity(&mut self, entity: Entity) {
+    pub(crate) fn upsert_entity(&mut self, mut entity: Entity) {
+        if !super::config::is_enabled() {
+            return;
+        }
+        redact_entity(&mut entity);
         let entity_id = EntityId::new(entity.id.as_str());
         let should_link_task_scope = Self::should_link_entity_to_creation_task_scope(&entity.body);
         let require_real_tokio_task_for_creation_link =
@@ -217,6 +282,23 @@ impl RuntimeDb {
         }
     }
 
+    pub(crate) fn mutate_scope_body_and_upsert(
+        &mut self,
+        id: &ScopeId,
+        f: impl FnOnce(&mut ScopeBody),
+    ) {
+        let Some(scope) = self.scopes.get_mut(id) else {
+            return;
+        };
+        f(&mut scope.body);
+        if let Ok(scope_json) = facet_json::to_vec(scope) {
+            self.push_change(InternalChange::UpsertScope {
+                id: ScopeId::new(id.as_str()),
+                scope_json,
+            });
+        }
+    }
+
     pub(crate) fn register_task_scope_id(&mut self, task_key: &str, scope_id: &ScopeId) {
         self.task_scope_ids
             .insert(String::from(task_key), ScopeId::new(scope_id.as_str()));
@@ -233,7 +315,7 @@ impl RuntimeDb {
     }
 
     fn ensure_current_task_scope_id(&mut self) -> Option<ScopeId> {
-        let task_key = current_tokio_task_key().unwrap_or_else(|| String::from("main"));
+        let task_key = current_task_or_thread_key();
         if let Some(existing_scope_id) = self.task_scope_ids.get(&task_key).cloned() {
             if self.scopes.contains_key(&existing_scope_id) {
                 return Some(existing_scope_id);
@@ -253,6 +335,7 @@ impl RuntimeDb {
             format!("task.{task_key}"),
             ScopeBody::Task(TaskScopeBody {
                 task_key: task_key.clone(),
+                logical_id: None,
             }),
         );
         let scope_id = ScopeId::new(scope.id.as_str());
@@ -292,6 +375,7 @@ impl RuntimeDb {
                 return false;
             }
             entity.name = name;
+            redact_entity(entity);
             facet_json::to_vec(entity).ok()
         };
 
@@ -317,7 +401,25 @@ impl RuntimeDb {
                 return false;
             }
             let before = Self::body_fingerprint(&entity.body);
+            let was_pending = matches!(
+                &entity.body,
+                EntityBody::Response(response) if matches!(response.status, ResponseStatus::Pending)
+            );
             mutate(&mut entity.body);
+            if let EntityBody::Response(response) = &entity.body
+                && was_pending
+                && !matches!(response.status, ResponseStatus::Pending)
+            {
+                note_completion(
+                    &response.service_name,
+                    &response.method_name,
+                    &response.status,
+                    PTime::now()
+                        .as_millis()
+                        .saturating_sub(entity.birth.as_millis()),
+                );
+            }
+            redact_entity(entity);
             let after = Self::body_fingerprint(&entity.body);
             if before == after {
                 return false;
@@ -331,6 +433,23 @@ impl RuntimeDb {
         true
     }
 
+    /// Runs the installed redactor over every recorded entity, for a
+    /// redactor installed after they were recorded.
+    pub(crate) fn redact_entities(&mut self) {
+        let mut changed = Vec::new();
+        for (id, entity) in &mut self.entities {
+            let before = facet_json::to_vec(&*entity).ok();
+            redact_entity(entity);
+            let after = facet_json::to_vec(&*entity).ok();
+            if let Some(entity_json) = after.filter(|after| Some(after) != before.as_ref()) {
+                changed.push((EntityId::new(id.as_str()), entity_json));
+            }
+        }
+        for (id, entity_json) in changed {
+            self.push_change(InternalChange::UpsertEntity { id, entity_json });
+        }
+    }
+
     pub(crate) fn remove_entity(&mut self, id: &EntityId) {
         let Some(entity) = self.entities.get_mut(id) else {
             return;
@@ -370,6 +489,9 @@ impl RuntimeDb {
             !remove
         });
         for (src, dst, kind) in removed_edges {
+            if kind == EdgeKind::HeldBy {
+                self.unindex_hold(&src, &dst);
+            }
             self.push_change(InternalChange::RemoveEdge { src, dst, kind });
         }
 
@@ -386,6 +508,82 @@ impl RuntimeDb {
             self.push_change(InternalChange::RemoveEntity {
                 id: EntityId::new(id.as_str()),
             });
+        } else if self.dead_entity_retention.is_some() {
+            self.dead_entities
+                .push_back((PTime::now(), EntityId::new(id.as_str())));
+        }
+        self.sweep_expired_dead_entities();
+    }
+
+    // r[impl model.future.wake-gap]
+    /// Sets `wake_to_poll_gap_ms` on the futures in `gaps`, see
+    /// [`wake_to_poll_gaps`], and clears it on the rest.
+    fn refresh_wake_to_poll_gaps(&mut self, gaps: &BTreeMap<EntityId, u64>) {
+        let stale = self
+            .entities
+            .values()
+            .filter_map(|entity| match &entity.body {
+                EntityBody::Future(future)
+                    if future.wake_to_poll_gap_ms != gaps.get(&entity.id).copied() =>
+                {
+                    Some(entity.id.clone())
+                }
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        for id in stale {
+            let gap_ms = gaps.get(&id).copied();
+            self.mutate_entity_body_and_maybe_upsert(&id, |body| {
+                if let EntityBody::Future(future) = body {
+                    future.wake_to_poll_gap_ms = gap_ms;
+                }
+            });
+        }
+    }
+
+    /// Forgets every entity, edge and event, keeping scopes, and tells
+    /// stream consumers they are gone.
+    pub(crate) fn clear_collected(&mut self) {
+        let edges = std::mem::take(&mut self.edges);
+        self.holds_by_holder.clear();
+        for key in edges.into_keys() {
+            self.push_change(InternalChange::RemoveEdge {
+                src: key.src,
+                dst: key.dst,
+                kind: key.kind,
+            });
+        }
+        let links = std::mem::take(&mut self.entity_scope_links);
+        for (entity_id, scope_id) in links.into_keys() {
+            self.push_change(InternalChange::RemoveEntityScopeLink {
+                entity_id,
+                scope_id,
+            });
+        }
+        let entities = std::mem::take(&mut self.entities);
+        for id in entities.into_keys() {
+            self.push_change(InternalChange::RemoveEntity { id });
+        }
+        self.events.clear();
+        self.event_entity_refs.clear();
+        self.dead_entities.clear();
+    }
+
+    /// Sweeps dead entities older than the retention, even if events still
+    /// reference them.
+    fn sweep_expired_dead_entities(&mut self) {
+        let Some(retention) = self.dead_entity_retention else {
+            return;
+        };
+        let now_ms = PTime::now().as_millis();
+        let retention_ms = retention.as_millis().min(u128::from(u64::MAX)) as u64;
+        while let Some((removed_at, _)) = self.dead_entities.front() {
+            if now_ms.saturating_sub(removed_at.as_millis()) < retention_ms {
+                break;
+            }
+            if let Some((_, id)) = self.dead_entities.pop_front() {
+                self.sweep_dead_entity(&id);
+            }
         }
     }
 
@@ -453,6 +651,23 @@ impl RuntimeDb {
         kind: EdgeKind,
         backtrace: BacktraceId,
     ) {
+        self.upsert_edge_with_reason(src, dst, kind, backtrace, None);
+    }
+
+    /// Like [`Self::upsert_edge`], with `reason` in place of the one implied
+    /// by `dst`'s kind.
+    // r[impl model.edge.reason]
+    pub(crate) fn upsert_edge_with_reason(
+        &mut self,
+        src: &EntityId,
+        dst: &EntityId,
+        kind: EdgeKind,
+        backtrace: BacktraceId,
+        reason: Option<EdgeReason>,
+    ) {
+        if !super::config::is_enabled() {
+            return;
+        }
         // Skip if either endpoint is dead.
         if self
             .entities
@@ -481,14 +696,22 @@ impl RuntimeDb {
         if self.edges.contains_key(&key) {
             return;
         }
-        let edge = Edge::new(
+        let mut edge = Edge::new(
             EntityId::new(src.as_str()),
             EntityId::new(dst.as_str()),
             kind,
             backtrace,
         );
+        edge.reason = reason.or_else(|| {
+            self.entities
+                .get(dst)
+                .and_then(|dst| EdgeReason::infer(kind, &dst.body))
+        });
         let edge_json = facet_json::to_vec(&edge).ok();
         self.edges.insert(key, edge);
+        if kind == EdgeKind::HeldBy {
+            self.index_hold(src, dst);
+        }
         if let Some(edge_json) = edge_json {
             self.push_change(InternalChange::UpsertEdge {
                 src: EntityId::new(src.as_str()),
@@ -499,22 +722,120 @@ impl RuntimeDb {
         }
     }
 
+    /// Points the `src -> old_dst` edge at `new_dst`, keeping its backtrace and
+    /// `since`. Unlike removing and re-adding it, this does not end a hold.
+    pub(crate) fn retarget_edge(
+        &mut self,
+        src: &EntityId,
+        old_dst: &EntityId,
+        new_dst: &EntityId,
+        kind: EdgeKind,
+    ) {
+        if self
+            .entities
+            .get(new_dst)
+            .is_some_and(|e| e.removed_at.is_some())
+        {
+            return;
+        }
+        let Some(mut edge) = self.edges.remove(&EdgeKey {
+            src: EntityId::new(src.as_str()),
+            dst: EntityId::new(old_dst.as_str()),
+            kind,
+        }) else {
+            return;
+        };
+        self.push_change(InternalChange::RemoveEdge {
+            src: EntityId::new(src.as_str()),
+            dst: EntityId::new(old_dst.as_str()),
+            kind,
+        });
+        if kind == EdgeKind::HeldBy {
+            self.unindex_hold(src, old_dst);
+            self.index_hold(src, new_dst);
+        }
+        edge.dst = EntityId::new(new_dst.as_str());
+        let edge_json = facet_json::to_vec(&edge).ok();
+        self.edges.insert(
+            EdgeKey {
+                src: EntityId::new(src.as_str()),
+                dst: EntityId::new(new_dst.as_str()),
+                kind,
+            },
+            edge,
+        );
+        if let Some(edge_json) = edge_json {
+            self.push_change(InternalChange::UpsertEdge {
+                src: EntityId::new(src.as_str()),
+                dst: EntityId::new(new_dst.as_str()),
+                kind,
+                edge_json,
+            });
+        }
+    }
+
+    /// Live entities with a `held_by` edge to `holder`.
+    pub(crate) fn held_by(&self, holder: &EntityId) -> Vec<&Entity> {
+        self.holds_by_holder
+            .get(holder)
+            .into_iter()
+            .flatten()
+            .filter_map(|resource| self.entities.get(resource))
+            .filter(|entity| entity.removed_at.is_none())
+            .collect()
+    }
+
+    fn index_hold(&mut self, resource: &EntityId, holder: &EntityId) {
+        self.holds_by_holder
+            .entry(EntityId::new(holder.as_str()))
+            .or_default()
+            .insert(EntityId::new(resource.as_str()));
+    }
+
+    fn unindex_hold(&mut self, resource: &EntityId, holder: &EntityId) {
+        if let Some(resources) = self.holds_by_holder.get_mut(holder) {
+            resources.remove(resource);
+            if resources.is_empty() {
+                self.holds_by_holder.remove(holder);
+            }
+        }
+    }
+
     pub(crate) fn remove_edge(&mut self, src: &EntityId, dst: &EntityId, kind: EdgeKind) {
         let removed = self.edges.remove(&EdgeKey {
             src: EntityId::new(src.as_str()),
             dst: EntityId::new(dst.as_str()),
             kind,
         });
-        if removed.is_some() {
-            self.push_change(InternalChange::RemoveEdge {
-                src: EntityId::new(src.as_str()),
-                dst: EntityId::new(dst.as_str()),
-                kind,
+        let Some(removed) = removed else {
+            return;
+        };
+        self.push_change(InternalChange::RemoveEdge {
+            src: EntityId::new(src.as_str()),
+            dst: EntityId::new(dst.as_str()),
+            kind,
+        });
+        if kind != EdgeKind::HeldBy {
+            return;
+        }
+        self.unindex_hold(src, dst);
+        if let Some(since) = removed.since {
+            let held_ms = PTime::now().as_millis().saturating_sub(since.as_millis());
+            self.mutate_entity_body_and_maybe_upsert(src, |body| {
+                let holds = match body {
+                    EntityBody::Lock(lock) => &mut lock.holds,
+                    EntityBody::Semaphore(semaphore) => &mut semaphore.holds,
+                    _ => return,
+                };
+                holds.get_or_insert_with(Default::default).record(held_ms);
             });
         }
     }
 
     pub(crate) fn record_event(&mut self, event: Event) {
+        if !super::config::is_enabled() {
+            return;
+        }
         // Increment ref count for entity-targeted events.
         if let EventTarget::Entity(ref id) = event.target {
             *self
@@ -524,7 +845,16 @@ impl RuntimeDb {
         }
         let event_json = facet_json::to_vec(&event).ok();
         self.events.push_back(event);
-        // Evict old events and decrement ref counts.
+        self.evict_events();
+        self.sweep_expired_dead_entities();
+        if let Some(event_json) = event_json {
+            self.push_change(InternalChange::AppendEvent { event_json });
+        }
+    }
+
+    /// Evicts the oldest events past `max_events` and sweeps dead entities
+    /// they were the last reference to.
+    fn evict_events(&mut self) {
         while self.events.len() > self.max_events {
             if let Some(evicted) = self.events.pop_front()
                 && let EventTarget::Entity(ref id) = evicted.target
@@ -540,9 +870,6 @@ impl RuntimeDb {
                 }
             }
         }
-        if let Some(event_json) = event_json {
-            self.push_change(InternalChange::AppendEvent { event_json });
-        }
     }
 
     /// Remove a dead entity from the map and emit a `RemoveEntity` change.
@@ -678,6 +1005,11 @@ struct SnapshotRef<'a> {
     scopes: Vec<&'a Scope>,
     edges: Vec<&'a Edge>,
     events: Vec<&'a Event>,
+    workers: Vec<WorkerLoad>,
+    long_polls: Vec<LongPoll>,
+    rpc_methods: Vec<RpcMethodStats>,
+    coverage: Option<Coverage>,
+    runtime_stats: Option<RuntimeStats>,
 }
 
 #[derive(Facet)]
@@ -687,6 +1019,8 @@ struct SnapshotReplyRef<'a> {
     ptime_now_ms: u64,
     #[facet(skip_unless_truthy)]
     snapshot: Option<SnapshotRef<'a>>,
+    #[facet(skip_unless_truthy)]
+    timed_out_sections: Option<Vec<String>>,
 }
 
 #[derive(Facet)]
@@ -745,23 +1079,252 @@ impl InternalStampedChange {
     }
 }
 
-pub(crate) fn encode_snapshot_reply_frame(snapshot_id: i64) -> Result<Vec<u8>, String> {
+/// How long to sleep between attempts while waiting for a snapshot section lock.
+const SNAPSHOT_LOCK_RETRY_INTERVAL: Duration = Duration::from_millis(1);
+
+/// Locks `mutex`, giving up at `deadline`. `None` on timeout; a poisoned lock
+/// is recovered, since snapshots only read.
+pub(crate) fn lock_until<T>(mutex: &StdMutex<T>, deadline: Instant) -> Option<MutexGuard<'_, T>> {
+    loop {
+        match mutex.try_lock() {
+            Ok(guard) => return Some(guard),
+            Err(TryLockError::Poisoned(poisoned)) => return Some(poisoned.into_inner()),
+            Err(TryLockError::WouldBlock) => {
+                if Instant::now() >= deadline {
+                    return None;
+                }
+                std::thread::sleep(SNAPSHOT_LOCK_RETRY_INTERVAL);
+            }
+        }
+    }
+}
+
+/// How long a snapshot waits for each registry other than the runtime
+/// database, so one contended registry cannot use up the whole budget.
+const SNAPSHOT_SECTION_LOCK_BUDGET: Duration = Duration::from_millis(50);
+
+fn section_deadline(deadline: Instant) -> Instant {
+    deadline.min(Instant::now() + SNAPSHOT_SECTION_LOCK_BUDGET)
+}
+
+/// Futures woken longer than the configured threshold ago and not polled
+/// since, with their gaps. `None` if the wake registry stayed locked past
+/// `deadline`.
+fn wake_to_poll_gaps(now_ms: u64, deadline: Instant) -> Option<BTreeMap<EntityId, u64>> {
+    let threshold = super::config::wake_gap_threshold();
+    if threshold.is_zero() {
+        return Some(BTreeMap::new());
+    }
+    let threshold_ms = threshold.as_millis().min(u128::from(u64::MAX)) as u64;
+    Some(
+        unpolled_wakes(now_ms, threshold_ms, deadline)?
+            .into_iter()
+            .collect(),
+    )
+}
+
+// r[impl wire.snapshot-workers]
+fn worker_loads(db: &RuntimeDb, counts: Vec<(u64, Option<String>, u64)>) -> Vec<WorkerLoad> {
+    let mut task_counts: BTreeMap<u64, u64> = BTreeMap::new();
+    for entity in db.entities.values() {
+        if let EntityBody::Future(future) = &entity.body
+            && let Some(worker) = future.poll_worker
+        {
+            *task_counts.entry(worker).or_default() += 1;
+        }
+    }
+    counts
+        .into_iter()
+        .map(|(worker, thread_name, poll_count)| WorkerLoad {
+            worker,
+            thread_name,
+            task_count: task_counts.get(&worker).copied().unwrap_or(0),
+            poll_count,
+        })
+        .collect()
+}
+
+// r[impl wire.snapshot-coverage]
+fn coverage(db: &RuntimeDb) -> Coverage {
+    let tracked_tasks = db
+        .entities
+        .values()
+        .filter(|entity| {
+            entity.removed_at.is_none()
+                && matches!(&entity.body, EntityBody::Future(future) if future.logical_id.is_some())
+        })
+        .count() as u64;
+    let runtime_alive_tasks = tokio::runtime::Handle::try_current()
+        .ok()
+        .map(|handle| handle.metrics().num_alive_tasks() as u64);
+    Coverage {
+        tracked_tasks,
+        runtime_alive_tasks,
+    }
+}
+
+// r[impl wire.snapshot-runtime-stats]
+fn runtime_stats() -> RuntimeStats {
+    let metrics = tokio::runtime::Handle::try_current()
+        .ok()
+        .map(|handle| handle.metrics());
+    RuntimeStats {
+        workers: metrics.as_ref().map(|m| m.num_workers() as u64),
+        global_queue_depth: metrics.as_ref().map(|m| m.global_queue_depth() as u64),
+        rss_bytes: rss_bytes(),
+        open_fds: open_fds(),
+        runtime_workers: metrics.as_ref().map_or_else(Vec::new, runtime_workers),
+    }
+}
+
+fn runtime_workers(metrics: &tokio::runtime::RuntimeMetrics) -> Vec<RuntimeWorker> {
+    (0..metrics.num_workers())
+        .map(|worker| RuntimeWorker {
+            index: worker as u64,
+            park_count: metrics.worker_park_count(worker),
+            busy_us: metrics
+                .worker_total_busy_duration(worker)
+                .as_micros()
+                .min(u128::from(u64::MAX)) as u64,
+            local_queue_depth: local_queue_depth(metrics, worker),
+            steal_count: steal_count(metrics, worker),
+        })
+        .collect()
+}
+
+#[cfg(tokio_unstable)]
+fn local_queue_depth(metrics: &tokio::runtime::RuntimeMetrics, worker: usize) -> Option<u64> {
+    Some(metrics.worker_local_queue_depth(worker) as u64)
+}
+
+#[cfg(not(tokio_unstable))]
+fn local_queue_depth(_metrics: &tokio::runtime::RuntimeMetrics, _worker: usize) -> Option<u64> {
+    None
+}
+
+#[cfg(tokio_unstable)]
+fn steal_count(metrics: &tokio::runtime::RuntimeMetrics, worker: usize) -> Option<u64> {
+    Some(metrics.worker_steal_count(worker))
+}
+
+#[cfg(not(tokio_unstable))]
+fn steal_count(_metrics: &tokio::runtime::RuntimeMetrics, _worker: usize) -> Option<u64> {
+    None
+}
+
+#[cfg(target_os = "linux")]
+fn rss_bytes() -> Option<u64> {
+    let status = std::fs::read_to_string("/proc/self/status").ok()?;
+    let kib = status
+        .lines()
+        .find_map(|line| line.strip_prefix("VmRSS:"))?
+        .trim()
+        .strip_suffix("kB")?
+        .trim()
+        .parse::<u64>()
+        .ok()?;
+    Some(kib * 1024)
+}
+
+#[cfg(not(target_os = "linux"))]
+fn rss_bytes() -> Option<u64> {
+    None
+}
+
+#[cfg(target_os = "linux")]
+fn open_fds() -> Option<u64> {
+    // The count includes the descriptor `read_dir` holds open on the
+    // directory itself.
+    let entries = std::fs::read_dir("/proc/self/fd").ok()?;
+    Some((entries.count() as u64).saturating_sub(1))
+}
+
+#[cfg(not(target_os = "linux"))]
+fn open_fds() -> Option<u64> {
+    None
+}
+
+// r[impl wire.snapshot-deadline]
+// r[impl wire.snapshot-encoding]
+/// The reply is serialized straight from the runtime state, in the encoding
+/// the request asked for.
+pub(crate) fn encode_snapshot_reply_frame(
+    snapshot_id: i64,
+    deadline: Instant,
+    encoding: Option<SnapshotEncoding>,
+) -> Result<Vec<u8>, String> {
+    let payload = match encoding {
+        None | Some(SnapshotEncoding::Json) => {
+            with_snapshot_reply(snapshot_id, deadline, |reply| {
+                facet_json::to_vec(&SnapshotClientMessageRef::SnapshotReply(reply))
+            })
+            .map_err(|e| format!("encode snapshot reply json: {e}"))?
+        }
+        Some(SnapshotEncoding::Msgpack) => with_snapshot_reply(snapshot_id, deadline, |reply| {
+            facet_msgpack::to_vec(&SnapshotClientMessageRef::SnapshotReply(reply))
+        })
+        .map_err(|e| format!("encode snapshot reply msgpack: {e}"))?,
+    };
+    moire_wire::encode_frame_default(&payload)
+        .map_err(|e| format!("encode snapshot reply frame: {e}"))
+}
+
+/// The snapshot reply on its own, as served over HTTP. `snapshot_id` is
+/// always zero since nothing requested it.
+pub(crate) fn encode_snapshot_reply_json(deadline: Instant) -> Result<Vec<u8>, Error> {
+    with_snapshot_reply(0, deadline, |reply| facet_json::to_vec(&reply))
+        .map_err(|e| Error::Encode(format!("snapshot reply json: {e}")))
+}
+
+fn with_snapshot_reply<R>(
+    snapshot_id: i64,
+    deadline: Instant,
+    f: impl FnOnce(SnapshotReplyRef<'_>) -> R,
+) -> R {
     // Capture process-relative now before locking the db, so the timestamp
     // represents the moment this snapshot was requested.
     let ptime_now_ms = PTime::now().as_millis();
-    let Ok(db) = runtime_db().lock() else {
-        let payload =
-            facet_json::to_vec(&SnapshotClientMessageRef::SnapshotReply(SnapshotReplyRef {
-                snapshot_id,
-                ptime_now_ms,
-                snapshot: None,
-            }))
-            .map_err(|e| format!("encode snapshot reply json: {e}"))?;
-        return moire_wire::encode_frame_default(&payload)
-            .map_err(|e| format!("encode snapshot reply frame: {e}"));
+    // Read outside the db lock: the OS figures come from procfs.
+    let runtime_stats = runtime_stats();
+    // The other registries are read before the db is locked, each under its
+    // own budget, so instrumentation is never held up behind the db while a
+    // snapshot waits on one of them.
+    let mut timed_out_sections = Vec::new();
+    let wake_gaps = wake_to_poll_gaps(ptime_now_ms, section_deadline(deadline));
+    if wake_gaps.is_none() {
+        timed_out_sections.push(String::from("wake_gaps"));
+    }
+    let worker_counts = poll_worker_counts(section_deadline(deadline));
+    if worker_counts.is_none() {
+        timed_out_sections.push(String::from("workers"));
+    }
+    // r[impl wire.snapshot-long-polls]
+    let long_polls = recent_long_polls(section_deadline(deadline)).unwrap_or_else(|| {
+        timed_out_sections.push(String::from("long_polls"));
+        Vec::new()
+    });
+    // r[impl wire.snapshot-rpc-methods]
+    let rpc_methods = rpc_method_stats(section_deadline(deadline)).unwrap_or_else(|| {
+        timed_out_sections.push(String::from("rpc_methods"));
+        Vec::new()
+    });
+    let Some(mut db) = lock_until(runtime_db(), deadline) else {
+        timed_out_sections.insert(0, String::from("runtime_db"));
+        return f(SnapshotReplyRef {
+            snapshot_id,
+            ptime_now_ms,
+            snapshot: None,
+            timed_out_sections: Some(timed_out_sections),
+        });
     };
 
-    let message = SnapshotClientMessageRef::SnapshotReply(SnapshotReplyRef {
+    if let Some(gaps) = &wake_gaps {
+        db.refresh_wake_to_poll_gaps(gaps);
+    }
+    let workers = worker_counts
+        .map(|counts| worker_loads(&db, counts))
+        .unwrap_or_default();
+    f(SnapshotReplyRef {
         snapshot_id,
         ptime_now_ms,
         snapshot: Some(SnapshotRef {
@@ -769,10 +1332,149 @@ pub(crate) fn encode_snapshot_reply_frame(snapshot_id: i64) -> Result<Vec<u8>, S
             scopes: db.scopes.values().collect(),
             edges: db.edges.values().collect(),
             events: db.events.iter().collect(),
+            workers,
+            long_polls,
+            rpc_methods,
+            coverage: Some(coverage(&db)),
+            runtime_stats: Some(runtime_stats),
         }),
-    });
-    let payload =
-        facet_json::to_vec(&message).map_err(|e| format!("encode snapshot reply json: {e}"))?;
-    moire_wire::encode_frame_default(&payload)
-        .map_err(|e| format!("encode snapshot reply frame: {e}"))
+        timed_out_sections: (!timed_out_sections.is_empty()).then_some(timed_out_sections),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use moire_types::{EventKind, FutureEntity, LockEntity, LockKind, ProcessScopeBody};
+
+    use super::*;
+
+    fn lock() -> EntityBody {
+        EntityBody::Lock(LockEntity {
+            kind: LockKind::Mutex,
+            holds: None,
+            rwlock: None,
+        })
+    }
+
+    fn future() -> EntityBody {
+        EntityBody::Future(FutureEntity::default())
+    }
+
+    /// A fresh db holding `entities`, with the backtrace they were created
+    /// under and their ids in order. It holds a stand-in for the process
+    /// scope too, since task scopes are created from it.
+    fn db_with<const N: usize>(
+        entities: [(&str, EntityBody); N],
+    ) -> (RuntimeDb, BacktraceId, [EntityId; N]) {
+        let backtrace = BacktraceId::next().expect("backtrace id");
+        let mut db = RuntimeDb::new(StreamId(String::from("test")), 16);
+        if let Some(scope_id) = current_process_scope_id() {
+            let mut scope = Scope::new(
+                backtrace,
+                "process",
+                ScopeBody::Process(ProcessScopeBody { pid: 1 }),
+            );
+            scope.id = scope_id;
+            db.upsert_scope(scope);
+        }
+        let ids = entities.map(|(name, body)| {
+            let entity = Entity::new(backtrace, name, body);
+            let id = entity.id.clone();
+            db.upsert_entity(entity);
+            id
+        });
+        (db, backtrace, ids)
+    }
+
+    // r[verify wire.snapshot-deadline]
+    #[test]
+    fn snapshot_locks_give_up_at_the_deadline() {
+        let mutex = StdMutex::new(1);
+        let held = mutex.lock().unwrap();
+        let deadline = Instant::now() + Duration::from_millis(5);
+        assert!(lock_until(&mutex, deadline).is_none());
+        drop(held);
+        assert_eq!(lock_until(&mutex, deadline).as_deref(), Some(&1));
+    }
+
+    // r[verify api.mutex]
+    #[test]
+    fn retargeting_a_hold_does_not_complete_it() {
+        let (mut db, backtrace, [lock_id, acquirer_id, receiver_id]) = db_with([
+            ("lock", lock()),
+            ("acquirer", future()),
+            ("receiver", future()),
+        ]);
+
+        db.upsert_edge(&lock_id, &acquirer_id, EdgeKind::HeldBy, backtrace);
+        db.retarget_edge(&lock_id, &acquirer_id, &receiver_id, EdgeKind::HeldBy);
+        assert!(db.held_by(&acquirer_id).is_empty());
+        assert_eq!(db.held_by(&receiver_id).len(), 1);
+        let hold_count = |db: &RuntimeDb| match &db.entities[&lock_id].body {
+            EntityBody::Lock(lock) => lock.holds.map(|holds| holds.hold_count),
+            _ => None,
+        };
+        assert_eq!(hold_count(&db), None);
+
+        db.remove_edge(&lock_id, &receiver_id, EdgeKind::HeldBy);
+        assert_eq!(hold_count(&db), Some(1));
+    }
+
+    // r[verify model.event.cancelled-holder]
+    #[test]
+    fn holds_of_a_removed_holder_are_forgotten() {
+        let (mut db, backtrace, [lock_id, holder_id]) =
+            db_with([("lock", lock()), ("holder", future())]);
+
+        db.upsert_edge(&lock_id, &holder_id, EdgeKind::HeldBy, backtrace);
+        assert_eq!(db.held_by(&holder_id).len(), 1);
+        db.remove_entity(&holder_id);
+        assert!(db.held_by(&holder_id).is_empty());
+    }
+
+    // r[verify config.runtime]
+    #[test]
+    fn retention_sweeps_dead_entities_that_events_still_reference() {
+        let (mut db, backtrace, [future_id]) = db_with([("future", future())]);
+        db.record_event(Event::new(
+            EventTarget::Entity(future_id.clone()),
+            EventKind::StateChanged,
+            backtrace,
+        ));
+
+        // Without a retention, the event keeps the dead entity around.
+        db.remove_entity(&future_id);
+        assert!(db.entities.contains_key(&future_id));
+
+        db.configure(16, Some(Duration::ZERO));
+        assert!(!db.entities.contains_key(&future_id));
+        assert_eq!(db.events.len(), 1);
+    }
+
+    // r[verify config.runtime-switch]
+    #[test]
+    fn clearing_forgets_entities_edges_and_events_but_keeps_scopes() {
+        let (mut db, backtrace, [a, b]) = db_with([("a", future()), ("b", future())]);
+        let scope = Scope::new(
+            backtrace,
+            "process",
+            ScopeBody::Process(ProcessScopeBody { pid: 1 }),
+        );
+        let scope_id = ScopeId::new(scope.id.as_str());
+        db.upsert_scope(scope);
+        db.link_entity_to_scope(&a, &scope_id);
+        db.upsert_edge(&a, &b, EdgeKind::WaitingOn, backtrace);
+        db.record_event(Event::new(
+            EventTarget::Entity(a.clone()),
+            EventKind::StateChanged,
+            backtrace,
+        ));
+
+        db.clear_collected();
+        assert!(db.entities.is_empty());
+        assert!(db.edges.is_empty());
+        assert!(db.events.is_empty());
+        assert!(db.entity_scope_links.is_empty());
+        assert!(db.scopes.contains_key(&scope_id));
+    }
 }
@@ -0,0 +1,23 @@
+/// Why a fallible runtime API gave up.
+///
+/// Instrumentation itself never fails: a primitive that cannot record its
+/// state keeps working uninstrumented. Only APIs that hand collected state back
+/// to the caller report errors.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Error {
+    /// The runtime state stayed locked past the caller's deadline.
+    Timeout,
+    /// Collected state could not be encoded.
+    Encode(String),
+}
+
+impl core::fmt::Display for Error {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Error::Timeout => write!(f, "runtime state stayed locked past the deadline"),
+            Error::Encode(e) => write!(f, "encode runtime state: {e}"),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
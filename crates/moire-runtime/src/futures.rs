@@ -1,20 +1,191 @@
 use moire_trace_types::BacktraceId;
-use moire_types::{EdgeKind, EntityId, FutureEntity};
-use std::cell::RefCell;
+use moire_types::{
+    CANCELLED_WHILE_HOLDING_EVENT, Callsite, CancelledWhileHoldingPayload, CustomEventKind,
+    EdgeKind, EdgeReason, EntityId, EventKind, EventTarget, FutureEntity, HOLDER_CANCELLED_EVENT,
+    HeldResource, HolderCancelledPayload, Json, PTime, PollHistogram,
+};
+use std::cell::{Cell, RefCell};
 use std::future::{Future, IntoFuture};
+use std::mem::ManuallyDrop;
 use std::pin::Pin;
-use std::task::{Context, Poll};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, Weak};
+use std::task::{Context, Poll, Wake, Waker};
+use std::time::Instant;
 
 use super::FUTURE_CAUSAL_STACK;
-use super::db::runtime_db;
+use super::db::{lock_recovering, lock_runtime_db, lock_until};
 use super::handles::{EntityHandle, EntityRef, current_causal_target_from_stack};
+use super::polls::PollTimer;
+
+/// Wake counters are written to the future entity on powers of two and then
+/// every this many wakes, so a hot wake loop does not flood the change log.
+const WAKE_FLUSH_INTERVAL: u64 = 64;
+
+thread_local! {
+    /// Bumped whenever an instrumented operation or future completes. A poll that
+    /// leaves it unchanged and returns `Pending` made no observable progress.
+    static READY_TRANSITIONS: Cell<u64> = const { Cell::new(0) };
+}
+
+/// `PendingWake::woken_at` of a future that has not been woken since its last
+/// poll.
+const NOT_WOKEN: u64 = 0;
+/// `PendingWake::woken_at` of a future that completed or was dropped; later
+/// wakes through stale wakers are ignored.
+const NO_MORE_POLLS: u64 = u64::MAX;
+
+/// When an instrumented future was last woken without being polled since.
+/// Shared by the future and every waker it handed out, and registered in
+/// [`PENDING_WAKES`] so snapshots can spot wakes that never led to a poll.
+struct PendingWake {
+    future_id: EntityId,
+    /// `PTime` milliseconds plus one of the first wake since the last poll,
+    /// or one of [`NOT_WOKEN`] and [`NO_MORE_POLLS`].
+    woken_at: AtomicU64,
+}
+
+/// Shards of the pending-wake registry. Every instrumented future registers
+/// on its first poll, so a single lock would serialize spawns across workers;
+/// each worker thread registers in its own shard instead.
+const PENDING_WAKE_SHARDS: usize = 16;
+
+static PENDING_WAKES: [Mutex<Vec<Weak<PendingWake>>>; PENDING_WAKE_SHARDS] =
+    [const { Mutex::new(Vec::new()) }; PENDING_WAKE_SHARDS];
+
+/// Registers `pending` in the current worker thread's shard.
+fn register_pending_wake(pending: &Arc<PendingWake>) {
+    let ordinal = POLL_WORKER.with(|worker| worker.ordinal);
+    let shard = &PENDING_WAKES[ordinal as usize % PENDING_WAKE_SHARDS];
+    let mut registry = lock_recovering(shard);
+    // Snapshots prune the registry too; this keeps it bounded when nobody
+    // takes any.
+    if registry.len().is_power_of_two() {
+        registry.retain(|pending| pending.strong_count() > 0);
+    }
+    registry.push(Arc::downgrade(pending));
+}
+
+/// `(future, ms since it was woken)` for every live instrumented future woken
+/// at least `threshold_ms` before `now_ms` and not polled since. `None` if a
+/// registry shard stayed locked past `deadline`.
+pub(crate) fn unpolled_wakes(
+    now_ms: u64,
+    threshold_ms: u64,
+    deadline: Instant,
+) -> Option<Vec<(EntityId, u64)>> {
+    let mut gaps = Vec::new();
+    for shard in &PENDING_WAKES {
+        let mut pending = lock_until(shard, deadline)?;
+        pending.retain(|pending| {
+            let Some(pending) = pending.upgrade() else {
+                return false;
+            };
+            let woken_at = pending.woken_at.load(Ordering::Relaxed);
+            if woken_at == NO_MORE_POLLS {
+                return false;
+            }
+            if woken_at != NOT_WOKEN {
+                let gap_ms = now_ms.saturating_sub(woken_at - 1);
+                if gap_ms >= threshold_ms {
+                    gaps.push((pending.future_id.clone(), gap_ms));
+                }
+            }
+            true
+        });
+    }
+    Some(gaps)
+}
+
+/// A thread that has polled at least one instrumented future.
+struct PollWorker {
+    ordinal: u64,
+    thread_name: Option<String>,
+    polls: AtomicU64,
+}
+
+static NEXT_POLL_WORKER: AtomicU64 = AtomicU64::new(1);
+static POLL_WORKERS: Mutex<Vec<Arc<PollWorker>>> = Mutex::new(Vec::new());
+
+thread_local! {
+    static POLL_WORKER: Arc<PollWorker> = {
+        let worker = Arc::new(PollWorker {
+            ordinal: NEXT_POLL_WORKER.fetch_add(1, Ordering::Relaxed),
+            thread_name: std::thread::current().name().map(String::from),
+            polls: AtomicU64::new(0),
+        });
+        lock_recovering(&POLL_WORKERS).push(Arc::clone(&worker));
+        worker
+    };
+}
+
+/// Counts a poll against the current thread and returns its ordinal.
+fn note_poll_on_current_worker() -> u64 {
+    POLL_WORKER.with(|worker| {
+        worker.polls.fetch_add(1, Ordering::Relaxed);
+        worker.ordinal
+    })
+}
+
+/// `(ordinal, thread name, polls so far)` for every thread that has polled an
+/// instrumented future, in registration order. `None` if the registry stayed
+/// locked past `deadline`.
+pub(crate) fn poll_worker_counts(deadline: Instant) -> Option<Vec<(u64, Option<String>, u64)>> {
+    let workers = lock_until(&POLL_WORKERS, deadline)?;
+    let counts = workers
+        .iter()
+        .map(|worker| {
+            (
+                worker.ordinal,
+                worker.thread_name.clone(),
+                worker.polls.load(Ordering::Relaxed),
+            )
+        })
+        .collect();
+    Some(counts)
+}
+
+static NEXT_POLL_EPOCH: AtomicU64 = AtomicU64::new(1);
+
+thread_local! {
+    static POLL_EPOCH: Cell<u64> = const { Cell::new(0) };
+}
+
+/// Changes whenever an instrumented future starts or finishes a poll on this
+/// thread, and is unique across threads; 0 before the thread's first one.
+/// While it stays the same, the top of the causal stack does too, so code
+/// attributing work to the current future only needs to look it up again
+/// when the epoch moves.
+pub fn current_poll_epoch() -> u64 {
+    POLL_EPOCH.try_with(Cell::get).unwrap_or(0)
+}
+
+fn advance_poll_epoch() {
+    let _ = POLL_EPOCH.try_with(|epoch| epoch.set(NEXT_POLL_EPOCH.fetch_add(1, Ordering::Relaxed)));
+}
+
+fn note_ready_transition() {
+    READY_TRANSITIONS.with(|count| count.set(count.get().wrapping_add(1)));
+    super::watchdog::note_completion();
+}
+
+fn ready_transitions() -> u64 {
+    READY_TRANSITIONS.with(Cell::get)
+}
 
 pub struct OperationFuture<F> {
     inner: F,
     actor_id: Option<EntityId>,
     resource_id: EntityId,
     current_edge: Option<EdgeKind>,
-    backtrace: BacktraceId,
+    /// Captured on the first edge when the operation was created ahead of the
+    /// task that awaits it.
+    backtrace: Option<BacktraceId>,
+    /// Whether the actor is resolved from the causal stack on first poll
+    /// rather than at construction.
+    actor_at_poll: bool,
+    /// Reason recorded on the `waiting_on` edge instead of the inferred one.
+    reason: Option<EdgeReason>,
 }
 
 impl<F> OperationFuture<F> {
@@ -27,30 +198,79 @@ impl<F> OperationFuture<F> {
     }
 
     fn new_with_actor(inner: F, resource_id: EntityId, actor_id: Option<EntityId>) -> Self {
+        Self::new_with_actor_and_backtrace(
+            inner,
+            resource_id,
+            actor_id,
+            super::capture_backtrace_id(),
+        )
+    }
+
+    fn new_with_actor_and_backtrace(
+        inner: F,
+        resource_id: EntityId,
+        actor_id: Option<EntityId>,
+        backtrace: BacktraceId,
+    ) -> Self {
         Self {
             inner,
             actor_id,
             resource_id,
             current_edge: None,
-            backtrace: super::capture_backtrace_id(),
+            backtrace: Some(backtrace),
+            actor_at_poll: false,
+            reason: None,
+        }
+    }
+
+    fn new_with_actor_at_poll(inner: F, resource_id: EntityId) -> Self {
+        Self {
+            inner,
+            actor_id: None,
+            resource_id,
+            current_edge: None,
+            backtrace: None,
+            actor_at_poll: true,
+            reason: None,
         }
     }
 
+    /// Records `reason` on the `waiting_on` edge, for operations whose
+    /// resource kind alone does not say why they wait.
+    pub fn with_reason(mut self, reason: EdgeReason) -> Self {
+        self.reason = Some(reason);
+        self
+    }
+
+    /// The wrapped future.
+    pub fn get_ref(&self) -> &F {
+        &self.inner
+    }
+
     fn transition_edge(&mut self, next: Option<EdgeKind>) {
         if self.current_edge == next {
             return;
         }
+        if !super::config::is_enabled() {
+            self.current_edge = next;
+            return;
+        }
         let Some(actor_id) = self.actor_id.as_ref() else {
             self.current_edge = next;
             return;
         };
-        if let Ok(mut db) = runtime_db().lock() {
-            if let Some(current) = self.current_edge {
-                db.remove_edge(actor_id, &self.resource_id, current);
-            }
-            if let Some(edge) = next {
-                db.upsert_edge(actor_id, &self.resource_id, edge, self.backtrace);
-            }
+        let backtrace = next.map(|_| {
+            *self
+                .backtrace
+                .get_or_insert_with(super::capture_backtrace_id)
+        });
+        let mut db = lock_runtime_db();
+        if let Some(current) = self.current_edge {
+            db.remove_edge(actor_id, &self.resource_id, current);
+        }
+        if let (Some(edge), Some(backtrace)) = (next, backtrace) {
+            let reason = self.reason.filter(|_| edge == EdgeKind::WaitingOn);
+            db.upsert_edge_with_reason(actor_id, &self.resource_id, edge, backtrace, reason);
         }
         self.current_edge = next;
     }
@@ -64,6 +284,10 @@ where
 
     fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
         let this = unsafe { self.get_unchecked_mut() };
+        if this.actor_at_poll {
+            this.actor_at_poll = false;
+            this.actor_id = current_causal_target_from_stack().map(|target| target.id().clone());
+        }
         if this.current_edge.is_none() {
             this.transition_edge(Some(EdgeKind::Polls));
         }
@@ -75,6 +299,7 @@ where
             }
             Poll::Ready(output) => {
                 this.transition_edge(None);
+                note_ready_transition();
                 Poll::Ready(output)
             }
         }
@@ -94,6 +319,19 @@ where
     OperationFuture::new(fut.into_future(), EntityId::new(on.id().as_str()))
 }
 
+/// Like [`instrument_operation_on`], but the waiting side is whichever
+/// instrumented future first polls the operation. For handles created long
+/// before they are awaited, such as a spawned task's `JoinHandle`.
+pub fn instrument_operation_on_at_poll<F, S>(
+    on: &EntityHandle<S>,
+    fut: F,
+) -> OperationFuture<F::IntoFuture>
+where
+    F: IntoFuture,
+{
+    OperationFuture::new_with_actor_at_poll(fut.into_future(), EntityId::new(on.id().as_str()))
+}
+
 pub fn instrument_operation_on_with_actor<F, S>(
     on: &EntityHandle<S>,
     actor: Option<&EntityRef>,
@@ -109,12 +347,187 @@ where
     )
 }
 
+/// Like [`instrument_operation_on_with_actor`], but attaches `backtrace` to the
+/// operation's edges instead of capturing one.
+pub fn instrument_operation_on_with_actor_and_backtrace<F, S>(
+    on: &EntityHandle<S>,
+    actor: Option<&EntityRef>,
+    fut: F,
+    backtrace: BacktraceId,
+) -> OperationFuture<F::IntoFuture>
+where
+    F: IntoFuture,
+{
+    OperationFuture::new_with_actor_and_backtrace(
+        fut.into_future(),
+        EntityId::new(on.id().as_str()),
+        actor.map(|target| target.id().clone()),
+        backtrace,
+    )
+}
+
 pub struct InstrumentedFuture<F> {
-    inner: F,
+    /// Dropped by hand in `Drop`, before the holds it leaves behind are checked.
+    inner: ManuallyDrop<F>,
     pub(super) future_handle: EntityHandle<FutureEntity>,
     backtrace: BacktraceId,
     awaited_by: Option<FutureEdgeRelation>,
     waits_on: Option<FutureEdgeRelation>,
+    wakes: WakeStats,
+    polls: PollStats,
+    completed: bool,
+}
+
+/// Waker wrapper that remembers which instrumented future was being polled when
+/// it fired.
+struct WakeProbe {
+    inner: Waker,
+    woken_by: Mutex<Option<EntityId>>,
+    /// Wakes seen, for sampling which ones get attributed.
+    wakes: AtomicU64,
+    pending: Arc<PendingWake>,
+}
+
+impl Wake for WakeProbe {
+    fn wake(self: Arc<Self>) {
+        self.wake_by_ref();
+    }
+
+    fn wake_by_ref(self: &Arc<Self>) {
+        // Keeps the first wake since the last poll; fails harmlessly if one is
+        // already pending or the future is done.
+        let _ = self.pending.woken_at.compare_exchange(
+            NOT_WOKEN,
+            PTime::now().as_millis().saturating_add(1),
+            Ordering::Relaxed,
+            Ordering::Relaxed,
+        );
+        let seen = self.wakes.fetch_add(1, Ordering::Relaxed);
+        if seen.is_multiple_of(u64::from(super::config::wake_sample_every())) {
+            let waker = current_causal_target_from_stack().map(|target| target.id().clone());
+            *lock_recovering(&self.woken_by) = waker;
+        }
+        self.inner.wake_by_ref();
+    }
+}
+
+#[derive(Default)]
+struct WakeStats {
+    probe: Option<Arc<WakeProbe>>,
+    pending: Option<Arc<PendingWake>>,
+    polled: bool,
+    wake_count: u64,
+    unproductive_wake_count: u64,
+    last_woken_by: Option<EntityId>,
+}
+
+impl WakeStats {
+    fn waker_for(&mut self, future_id: &EntityId, waker: &Waker) -> Waker {
+        let probe = match &self.probe {
+            Some(probe) if probe.inner.will_wake(waker) => Arc::clone(probe),
+            _ => {
+                let pending = self.pending.get_or_insert_with(|| {
+                    let pending = Arc::new(PendingWake {
+                        future_id: future_id.clone(),
+                        woken_at: AtomicU64::new(NOT_WOKEN),
+                    });
+                    register_pending_wake(&pending);
+                    pending
+                });
+                let probe = Arc::new(WakeProbe {
+                    inner: waker.clone(),
+                    woken_by: Mutex::new(None),
+                    wakes: AtomicU64::new(0),
+                    pending: Arc::clone(pending),
+                });
+                self.probe = Some(Arc::clone(&probe));
+                probe
+            }
+        };
+        Waker::from(probe)
+    }
+
+    /// Clears the pending wake as a poll starts, or for good once the future
+    /// will not be polled again.
+    fn note_polled(&self, done: bool) {
+        if let Some(pending) = &self.pending {
+            pending.woken_at.store(
+                if done { NO_MORE_POLLS } else { NOT_WOKEN },
+                Ordering::Relaxed,
+            );
+        }
+    }
+
+    fn take_woken_by(&self) -> Option<EntityId> {
+        self.probe
+            .as_ref()
+            .and_then(|probe| lock_recovering(&probe.woken_by).take())
+    }
+
+    fn record(
+        &mut self,
+        handle: &EntityHandle<FutureEntity>,
+        productive: bool,
+        woken_by: Option<EntityId>,
+    ) {
+        self.wake_count += 1;
+        if !productive {
+            self.unproductive_wake_count += 1;
+        }
+        if woken_by.is_some() {
+            self.last_woken_by = woken_by;
+        }
+        if self.wake_count.is_power_of_two() || self.wake_count.is_multiple_of(WAKE_FLUSH_INTERVAL)
+        {
+            handle.mutate(|future| {
+                future.wake_count = Some(self.wake_count);
+                future.unproductive_wake_count = Some(self.unproductive_wake_count);
+                future.last_woken_by = self.last_woken_by.clone();
+            });
+        }
+    }
+}
+
+/// Which threads a future has been polled on and how long its polls took.
+/// Flushed on the same cadence as [`WakeStats`], keyed on the poll count, and
+/// once more when the future completes.
+#[derive(Default)]
+struct PollStats {
+    poll_count: u64,
+    last_worker: Option<u64>,
+    worker_migrations: u64,
+    busy_us: u64,
+    histogram: PollHistogram,
+}
+
+impl PollStats {
+    fn record(
+        &mut self,
+        handle: &EntityHandle<FutureEntity>,
+        worker: u64,
+        duration_us: u64,
+        completed: bool,
+    ) {
+        self.poll_count += 1;
+        if self.last_worker.is_some_and(|last| last != worker) {
+            self.worker_migrations += 1;
+        }
+        self.last_worker = Some(worker);
+        self.busy_us = self.busy_us.saturating_add(duration_us);
+        self.histogram.record(duration_us);
+        if completed
+            || self.poll_count.is_power_of_two()
+            || self.poll_count.is_multiple_of(WAKE_FLUSH_INTERVAL)
+        {
+            handle.mutate(|future| {
+                future.poll_count = Some(self.poll_count);
+                future.poll_worker = self.last_worker;
+                future.worker_migrations = Some(self.worker_migrations);
+                future.busy_us = Some(self.busy_us);
+                future.poll_histogram = Some(self.histogram.clone());
+            });
+        }
+    }
 }
 
 #[derive(Clone, Copy)]
@@ -154,11 +567,14 @@ impl<F> InstrumentedFuture<F> {
         let waits_on = target
             .map(|target| FutureEdgeRelation::new(target, FutureEdgeDirection::ChildToTarget));
         Self {
-            inner,
+            inner: ManuallyDrop::new(inner),
             future_handle,
-            backtrace: super::capture_backtrace_id(),
+            backtrace: super::capture_spawn_backtrace_id(),
             awaited_by,
             waits_on,
+            wakes: WakeStats::default(),
+            polls: PollStats::default(),
+            completed: false,
         }
     }
 
@@ -168,6 +584,15 @@ impl<F> InstrumentedFuture<F> {
         self
     }
 
+    /// Marks this future as an intentional, possibly endless wait, such as a
+    /// shutdown signal. `reason` is shown in the dashboard, and the wait is not
+    /// ranked as a potential hang.
+    pub fn idle(self, reason: impl Into<String>) -> Self {
+        let reason = reason.into();
+        self.future_handle.mutate(|f| f.idle = Some(reason));
+        self
+    }
+
     /// Sets the entity this future is waiting on, for dashboard edge display.
     pub fn on(mut self, target: EntityRef) -> Self {
         self.waits_on = Some(FutureEdgeRelation::new(
@@ -198,13 +623,12 @@ fn transition_relation_edge(
             EntityId::new(relation.target.id().as_str()),
         ),
     };
-    if let Ok(mut db) = runtime_db().lock() {
-        if let Some(current_edge) = relation.current_edge {
-            db.remove_edge(&src, &dst, current_edge);
-        }
-        if let Some(edge) = next_edge {
-            db.upsert_edge(&src, &dst, edge, backtrace);
-        }
+    let mut db = lock_runtime_db();
+    if let Some(current_edge) = relation.current_edge {
+        db.remove_edge(&src, &dst, current_edge);
+    }
+    if let Some(edge) = next_edge {
+        db.upsert_edge(&src, &dst, edge, backtrace);
     }
     relation.current_edge = next_edge;
 }
@@ -212,12 +636,11 @@ fn transition_relation_edge(
 impl<F: Future> InstrumentedFuture<F> {
     fn poll_inner(&mut self, cx: &mut Context<'_>) -> Poll<F::Output> {
         let future_id = EntityId::new(self.future_handle.id().as_str());
-        if let Ok(mut db) = runtime_db().lock() {
-            let _ = db.link_entity_to_current_task_scope(&future_id);
-        }
+        let _ = lock_runtime_db().link_entity_to_current_task_scope(&future_id);
         FUTURE_CAUSAL_STACK.with(|stack| {
             stack.borrow_mut().push(EntityId::new(future_id.as_str()));
         });
+        advance_poll_epoch();
 
         if let Some(relation) = self.awaited_by.as_mut() {
             transition_relation_edge(&future_id, self.backtrace, relation, Some(EdgeKind::Polls));
@@ -226,10 +649,32 @@ impl<F: Future> InstrumentedFuture<F> {
             transition_relation_edge(&future_id, self.backtrace, relation, Some(EdgeKind::Polls));
         }
 
-        let poll = unsafe { Pin::new_unchecked(&mut self.inner) }.poll(cx);
+        let worker = note_poll_on_current_worker();
+
+        // Any poll after the first one follows a `Pending`, so it was a wake.
+        let woken = std::mem::replace(&mut self.wakes.polled, true);
+        let woken_by = if woken {
+            self.wakes.take_woken_by()
+        } else {
+            None
+        };
+        let waker = self.wakes.waker_for(&future_id, cx.waker());
+        self.wakes.note_polled(false);
+        let ready_before = ready_transitions();
+        let timer = PollTimer::start();
+        let poll =
+            unsafe { Pin::new_unchecked(&mut *self.inner) }.poll(&mut Context::from_waker(&waker));
+        let duration_us = timer.finish(&future_id, worker);
         FUTURE_CAUSAL_STACK.with(|stack| {
             stack.borrow_mut().pop();
         });
+        advance_poll_epoch();
+        self.polls
+            .record(&self.future_handle, worker, duration_us, poll.is_ready());
+        if woken {
+            let productive = poll.is_ready() || ready_transitions() != ready_before;
+            self.wakes.record(&self.future_handle, productive, woken_by);
+        }
 
         match poll {
             Poll::Pending => {
@@ -258,6 +703,9 @@ impl<F: Future> InstrumentedFuture<F> {
                 if let Some(relation) = self.waits_on.as_mut() {
                     transition_relation_edge(&future_id, self.backtrace, relation, None);
                 }
+                note_ready_transition();
+                self.wakes.note_polled(true);
+                self.completed = true;
                 Poll::Ready(output)
             }
         }
@@ -272,6 +720,13 @@ where
 
     fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
         let this = unsafe { self.get_unchecked_mut() };
+        if !super::config::is_enabled() {
+            let poll = unsafe { Pin::new_unchecked(&mut *this.inner) }.poll(cx);
+            if poll.is_ready() {
+                this.completed = true;
+            }
+            return poll;
+        }
         let has_stack = FUTURE_CAUSAL_STACK.try_with(|_| ()).is_ok();
         if has_stack {
             this.poll_inner(cx)
@@ -283,7 +738,20 @@ where
 
 impl<F> Drop for InstrumentedFuture<F> {
     fn drop(&mut self) {
+        self.wakes.note_polled(true);
+        // SAFETY: `inner` is dropped in place and never touched again, so the
+        // pinning guarantee holds. Guards and permits inside it release their
+        // holds here, before `record_cancelled_holds` looks for leftovers.
+        unsafe { ManuallyDrop::drop(&mut self.inner) };
+        if !super::config::is_enabled() {
+            // Collection was switched off, and whatever this future recorded
+            // with it.
+            return;
+        }
         let future_id = EntityId::new(self.future_handle.id().as_str());
+        if !self.completed {
+            record_cancelled_holds(&future_id);
+        }
         if let Some(relation) = self.awaited_by.as_mut() {
             transition_relation_edge(&future_id, self.backtrace, relation, None);
         }
@@ -293,6 +761,76 @@ impl<F> Drop for InstrumentedFuture<F> {
     }
 }
 
+// r[impl model.event.cancelled-holder]
+/// Records what a future dropped before completion still holds.
+///
+/// Runs after the inner future, and the guards or permits inside it, were
+/// dropped, so a hold those guards released on the way out is not reported. A
+/// `held_by` edge that survives belongs to a guard that outlives the future,
+/// such as one moved into a spawned task or leaked. Each such resource gets a
+/// [`HOLDER_CANCELLED_EVENT`] naming the future, and the future gets a
+/// [`CANCELLED_WHILE_HOLDING_EVENT`] listing the resources; the latter keeps the
+/// future around as a tombstone so waiters that hang later can be traced to it.
+fn record_cancelled_holds(future_id: &EntityId) {
+    let (holder_name, held) = {
+        let db = lock_runtime_db();
+        let held = db
+            .held_by(future_id)
+            .into_iter()
+            .map(|entity| HeldResource {
+                entity_id: entity.id.clone(),
+                name: entity.name.clone(),
+                kind: String::from(entity.body.kind_name()),
+            })
+            .collect::<Vec<_>>();
+        let holder_name = db
+            .entities
+            .get(future_id)
+            .map(|entity| entity.name.clone())
+            .unwrap_or_default();
+        (holder_name, held)
+    };
+    if held.is_empty() {
+        return;
+    }
+
+    for resource in &held {
+        let payload = HolderCancelledPayload {
+            holder_id: future_id.clone(),
+            holder_name: holder_name.clone(),
+        };
+        record_custom_payload_event(
+            EventTarget::Entity(resource.entity_id.clone()),
+            HOLDER_CANCELLED_EVENT,
+            "Holder Cancelled",
+            &payload,
+        );
+    }
+    record_custom_payload_event(
+        EventTarget::Entity(future_id.clone()),
+        CANCELLED_WHILE_HOLDING_EVENT,
+        "Cancelled While Holding",
+        &CancelledWhileHoldingPayload { held },
+    );
+}
+
+fn record_custom_payload_event<T>(target: EventTarget, kind: &str, display_name: &str, payload: &T)
+where
+    T: for<'facet> facet::Facet<'facet>,
+{
+    let payload = facet_json::to_string(payload)
+        .expect("invariant violated: runtime event payload must serialize");
+    let event = super::new_event(
+        target,
+        EventKind::Custom(CustomEventKind {
+            kind: String::from(kind),
+            display_name: String::from(display_name),
+            payload: Json::new(payload),
+        }),
+    );
+    super::record_event(event);
+}
+
 pub fn instrument_future<F>(
     name: impl Into<String>,
     fut: F,
@@ -306,6 +844,35 @@ where
     instrument_future_with_handle(handle, fut, on, None)
 }
 
+/// Like [`instrument_future`], recording where the future was named. Backs
+/// `moire::named!` and `#[moire::instrument]`, which fill in the callsite.
+pub fn instrument_future_at<F>(
+    name: impl Into<String>,
+    fut: F,
+    crate_name: &str,
+    module_path: &str,
+    file: &str,
+    line: u32,
+) -> InstrumentedFuture<F::IntoFuture>
+where
+    F: IntoFuture,
+{
+    let callsite = Callsite {
+        crate_name: String::from(crate_name),
+        module_path: String::from(module_path),
+        file: String::from(file),
+        line,
+    };
+    let handle = EntityHandle::new(
+        name,
+        FutureEntity {
+            callsite: Some(callsite),
+            ..FutureEntity::default()
+        },
+    );
+    instrument_future_with_handle(handle, fut, None, None)
+}
+
 pub fn instrument_future_with_handle<F>(
     handle: EntityHandle<FutureEntity>,
     fut: F,
@@ -0,0 +1,132 @@
+//! This process's waiting-on graph and its wait cycles, as served over HTTP,
+//! dumped by the watchdog and logged by the candidate log.
+use facet::Facet;
+use moire_graph_core::{
+    EdgeKind as CoreEdgeKind, EdgeRow, NodeId, NodeRow, WaitGraphCore, entity_kind_wire_name,
+    has_external_wake_source, wait_age_bonus,
+};
+use moire_types::{EdgeKind, PTime};
+use std::time::Instant;
+
+use super::db::{lock_until, runtime_db};
+
+/// One wait cycle of this process.
+///
+/// Only `waiting_on` edges are followed: a future waiting on a lock is not
+/// linked to the lock's `held_by` holder, so a cycle closed through a lock
+/// holder shows up in moire-web but not here.
+#[derive(Facet)]
+pub(crate) struct CandidateJson {
+    /// Node keys (`process_id::entity_id`) of the wait cycle, sorted.
+    pub(crate) node_keys: Vec<String>,
+    pub(crate) names: Vec<String>,
+    /// Age of the youngest member: the cycle cannot be older than that.
+    pub(crate) blocked_duration_hint_ms: u64,
+    /// Age of the longest wait between members.
+    pub(crate) worst_wait_ms: u64,
+    /// Severity by the rules of moire-web's default policy: 3 when no member
+    /// can be woken from outside the cycle (1 otherwise), plus 0 to 3 for the
+    /// age of the longest wait.
+    pub(crate) score: u32,
+}
+
+#[derive(Facet)]
+pub(crate) struct CandidatesJson {
+    pub(crate) ptime_now_ms: u64,
+    /// Longest-blocked first.
+    pub(crate) candidates: Vec<CandidateJson>,
+}
+
+/// Builds this process's waiting-on graph with the same core builder
+/// lightweight agents use.
+pub(crate) fn wait_graph(deadline: Instant) -> Result<(u64, WaitGraphCore), String> {
+    let ptime_now_ms = PTime::now().as_millis();
+    let Some(db) = lock_until(runtime_db(), deadline) else {
+        return Err(String::from(
+            "runtime db stayed locked past the snapshot deadline",
+        ));
+    };
+    let process_id = super::runtime_process_id();
+    let node_id = |id: &moire_types::EntityId| NodeId::new(process_id.as_str(), id.as_str());
+    let nodes = db
+        .entities
+        .values()
+        .map(|entity| NodeRow {
+            id: node_id(&entity.id),
+            name: entity.name.clone(),
+            kind: entity_kind_wire_name(entity.body.kind_name()),
+            birth_ms: entity.birth.as_millis(),
+            ptime_now_ms,
+        })
+        .collect::<Vec<_>>();
+    let edges = db
+        .edges
+        .values()
+        .filter(|edge| edge.kind == EdgeKind::WaitingOn)
+        .filter(|edge| db.entities.contains_key(&edge.src) && db.entities.contains_key(&edge.dst))
+        .map(|edge| EdgeRow {
+            src: node_id(&edge.src),
+            dst: node_id(&edge.dst),
+            kind: CoreEdgeKind::WaitingOn,
+            since_ms: edge.since.map(|since| since.as_millis()),
+        })
+        .collect::<Vec<_>>();
+    drop(db);
+    WaitGraphCore::from_rows(nodes, edges).map(|graph| (ptime_now_ms, graph))
+}
+
+/// Wait cycles of this process's graph, i.e. deadlock candidates.
+pub(crate) fn deadlock_candidates(deadline: Instant) -> Result<CandidatesJson, String> {
+    let (ptime_now_ms, graph) = wait_graph(deadline)?;
+    let mut candidates = graph
+        .cycles()
+        .into_iter()
+        .map(|cycle| {
+            let rows = cycle
+                .iter()
+                .filter_map(|id| graph.nodes.get(id))
+                .collect::<Vec<_>>();
+            let worst_wait_ms = graph
+                .edges
+                .iter()
+                .filter(|edge| cycle.binary_search(&edge.src).is_ok())
+                .filter(|edge| cycle.binary_search(&edge.dst).is_ok())
+                .map(|edge| {
+                    let since_ms = edge.since_ms.unwrap_or_else(|| {
+                        graph
+                            .nodes
+                            .get(&edge.src)
+                            .map_or(ptime_now_ms, |row| row.birth_ms)
+                    });
+                    ptime_now_ms.saturating_sub(since_ms)
+                })
+                .max()
+                .unwrap_or(0);
+            let wake_source = if rows.iter().any(|row| has_external_wake_source(&row.kind)) {
+                1
+            } else {
+                3
+            };
+            CandidateJson {
+                node_keys: cycle.iter().map(NodeId::key).collect(),
+                names: rows.iter().map(|row| row.name.clone()).collect(),
+                blocked_duration_hint_ms: rows
+                    .iter()
+                    .map(|row| ptime_now_ms.saturating_sub(row.birth_ms))
+                    .min()
+                    .unwrap_or(0),
+                worst_wait_ms,
+                score: wake_source + wait_age_bonus(worst_wait_ms),
+            }
+        })
+        .collect::<Vec<_>>();
+    candidates.sort_by(|a, b| {
+        b.blocked_duration_hint_ms
+            .cmp(&a.blocked_duration_hint_ms)
+            .then_with(|| a.node_keys.cmp(&b.node_keys))
+    });
+    Ok(CandidatesJson {
+        ptime_now_ms,
+        candidates,
+    })
+}
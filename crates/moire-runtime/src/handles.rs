@@ -1,11 +1,12 @@
+use moire_trace_types::BacktraceId;
 use moire_types::{
-    CustomEventKind, EdgeKind, Entity, EntityBody, EntityBodySlot, EntityId, EventKind,
+    CustomEventKind, EdgeKind, EdgeReason, Entity, EntityBody, EntityBodySlot, EntityId, EventKind,
     EventTarget, Json, Scope, ScopeBody, ScopeId,
 };
 use std::marker::PhantomData;
 use std::sync::{Arc, Weak};
 
-use super::db::runtime_db;
+use super::db::lock_runtime_db;
 
 #[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub struct EntityRef {
@@ -39,6 +40,20 @@ pub fn current_causal_target_from_stack() -> Option<EntityRef> {
         .flatten()
 }
 
+/// Root future of the task currently being polled, i.e. the bottom of the
+/// causal stack, or the task's aether entity when nothing is instrumented.
+pub fn current_task_target() -> Option<EntityRef> {
+    super::FUTURE_CAUSAL_STACK
+        .try_with(|stack| {
+            stack.borrow().first().map(|id| EntityRef {
+                id: EntityId::new(id.as_str()),
+            })
+        })
+        .ok()
+        .flatten()
+        .or_else(|| super::aether_entity_for_current_task().map(|id| EntityRef { id }))
+}
+
 pub fn current_causal_target_with_task_fallback() -> Option<EntityRef> {
     current_causal_target_from_stack()
         .or_else(|| super::aether_entity_for_current_task().map(|id| EntityRef { id }))
@@ -61,9 +76,7 @@ struct ScopeHandleInner {
 
 impl Drop for ScopeHandleInner {
     fn drop(&mut self) {
-        if let Ok(mut db) = runtime_db().lock() {
-            db.remove_scope(&self.id);
-        }
+        lock_runtime_db().remove_scope(&self.id);
     }
 }
 
@@ -74,12 +87,10 @@ pub struct ScopeHandle {
 
 impl ScopeHandle {
     pub fn new(name: impl Into<String>, body: ScopeBody) -> Self {
-        let scope = Scope::new(super::capture_backtrace_id(), name, body);
+        let scope = Scope::new(super::capture_spawn_backtrace_id(), name, body);
         let id = ScopeId::new(scope.id.as_str());
 
-        if let Ok(mut db) = runtime_db().lock() {
-            db.upsert_scope(scope);
-        }
+        lock_runtime_db().upsert_scope(scope);
 
         Self {
             inner: Arc::new(ScopeHandleInner { id }),
@@ -95,18 +106,27 @@ impl ScopeHandle {
             id: ScopeId::new(self.inner.id.as_str()),
         }
     }
+
+    /// Links an entity to this scope. The link goes away with either side.
+    pub fn link_entity(&self, target: &impl AsEntityRef) {
+        lock_runtime_db().link_entity_to_scope(target.as_entity_ref().id(), self.id());
+    }
+
+    /// Updates the scope's body in place.
+    pub fn mutate(&self, f: impl FnOnce(&mut ScopeBody)) {
+        lock_runtime_db().mutate_scope_body_and_upsert(self.id(), f);
+    }
 }
 
 struct HandleInner {
     id: EntityId,
     kind_name: &'static str,
+    backtrace: BacktraceId,
 }
 
 impl Drop for HandleInner {
     fn drop(&mut self) {
-        if let Ok(mut db) = runtime_db().lock() {
-            db.remove_entity(&self.id);
-        }
+        lock_runtime_db().remove_entity(&self.id);
     }
 }
 
@@ -128,13 +148,16 @@ impl<S> EntityHandle<S> {
     fn from_entity(entity: Entity) -> Self {
         let kind_name = entity.body.kind_name();
         let id = EntityId::new(entity.id.as_str());
+        let backtrace = entity.backtrace;
 
-        if let Ok(mut db) = runtime_db().lock() {
-            db.upsert_entity(entity);
-        }
+        lock_runtime_db().upsert_entity(entity);
 
         Self {
-            inner: Arc::new(HandleInner { id, kind_name }),
+            inner: Arc::new(HandleInner {
+                id,
+                kind_name,
+                backtrace,
+            }),
             _slot: PhantomData,
         }
     }
@@ -145,7 +168,7 @@ where
     S: EntityBodySlot<Value = S> + Into<EntityBody>,
 {
     pub fn new(name: impl Into<String>, body: S) -> Self {
-        let entity = Entity::new(super::capture_backtrace_id(), name, body.into());
+        let entity = Entity::new(super::capture_spawn_backtrace_id(), name, body.into());
         Self::from_entity(entity)
     }
 }
@@ -156,16 +179,18 @@ impl<S> EntityHandle<S> {
     }
 
     pub fn rename(&self, name: impl Into<String>) -> bool {
-        let mut db = runtime_db()
-            .lock()
-            .expect("runtime db lock poisoned during entity rename");
-        db.rename_entity_and_maybe_upsert(self.id(), name)
+        lock_runtime_db().rename_entity_and_maybe_upsert(self.id(), name)
     }
 
     pub fn kind_name(&self) -> &'static str {
         self.inner.kind_name
     }
 
+    /// Backtrace captured when the entity was created.
+    pub fn backtrace_id(&self) -> BacktraceId {
+        self.inner.backtrace
+    }
+
     pub fn entity_ref(&self) -> EntityRef {
         EntityRef {
             id: EntityId::new(self.inner.id.as_str()),
@@ -173,9 +198,8 @@ impl<S> EntityHandle<S> {
     }
 
     pub fn link_to(&self, target: &EntityRef, kind: EdgeKind) {
-        if let Ok(mut db) = runtime_db().lock() {
-            db.upsert_edge(self.id(), target.id(), kind, super::capture_backtrace_id());
-        }
+        let backtrace = super::capture_backtrace_id();
+        lock_runtime_db().upsert_edge(self.id(), target.id(), kind, backtrace);
     }
 
     pub fn link_to_handle<T>(&self, target: &EntityHandle<T>, kind: EdgeKind) {
@@ -197,10 +221,7 @@ where
             );
         }
 
-        let mut db = runtime_db()
-            .lock()
-            .expect("runtime db lock poisoned during entity mutate");
-        db.mutate_entity_body_and_maybe_upsert(self.id(), |body| {
+        lock_runtime_db().mutate_entity_body_and_maybe_upsert(self.id(), |body| {
             let slot = S::project_mut(body).unwrap_or_else(|| {
                 panic!(
                     "entity body projection failed after kind check: kind={} entity_id={}",
@@ -268,20 +289,14 @@ where
         let Some(inner) = self.inner.upgrade() else {
             return false;
         };
-        let mut db = runtime_db()
-            .lock()
-            .expect("runtime db lock poisoned during weak entity rename");
-        db.rename_entity_and_maybe_upsert(&inner.id, name)
+        lock_runtime_db().rename_entity_and_maybe_upsert(&inner.id, name)
     }
 
     pub fn mutate(&self, f: impl FnOnce(&mut S::Value)) -> bool {
         let Some(inner) = self.inner.upgrade() else {
             return false;
         };
-        let mut db = runtime_db()
-            .lock()
-            .expect("runtime db lock poisoned during weak entity mutate");
-        db.mutate_entity_body_and_maybe_upsert(&inner.id, |body| {
+        lock_runtime_db().mutate_entity_body_and_maybe_upsert(&inner.id, |body| {
             let slot = S::project_mut(body).unwrap_or_else(|| {
                 panic!(
                     "entity body projection failed: kind={} entity_id={}",
@@ -305,11 +320,26 @@ pub struct EdgeHandle {
     kind: EdgeKind,
 }
 
+impl EdgeHandle {
+    pub fn dst(&self) -> &EntityId {
+        &self.dst
+    }
+
+    /// Points the edge at `target` instead, keeping its backtrace and when it
+    /// was created.
+    pub fn retarget(&mut self, target: &impl AsEntityRef) {
+        let dst = target.as_entity_ref().id().clone();
+        if dst == self.dst {
+            return;
+        }
+        lock_runtime_db().retarget_edge(&self.src, &self.dst, &dst, self.kind);
+        self.dst = dst;
+    }
+}
+
 impl Drop for EdgeHandle {
     fn drop(&mut self) {
-        if let Ok(mut db) = runtime_db().lock() {
-            db.remove_edge(&self.src, &self.dst, self.kind);
-        }
+        lock_runtime_db().remove_edge(&self.src, &self.dst, self.kind);
     }
 }
 
@@ -317,15 +347,49 @@ impl<S> EntityHandle<S> {
     pub fn link_to_owned(&self, target: &impl AsEntityRef, kind: EdgeKind) -> EdgeHandle {
         self.as_entity_ref().link_to_owned(target, kind)
     }
+
+    pub fn link_to_owned_with_backtrace(
+        &self,
+        target: &impl AsEntityRef,
+        kind: EdgeKind,
+        backtrace: BacktraceId,
+    ) -> EdgeHandle {
+        self.as_entity_ref()
+            .link_to_owned_with_backtrace(target, kind, backtrace)
+    }
 }
 
 impl EntityRef {
     pub fn link_to_owned(&self, target: &impl AsEntityRef, kind: EdgeKind) -> EdgeHandle {
+        self.link_to_owned_with_backtrace(target, kind, super::capture_backtrace_id())
+    }
+
+    /// Like [`EntityRef::link_to_owned`], but attaches `backtrace` instead of
+    /// capturing one at the call site.
+    pub fn link_to_owned_with_backtrace(
+        &self,
+        target: &impl AsEntityRef,
+        kind: EdgeKind,
+        backtrace: BacktraceId,
+    ) -> EdgeHandle {
         let src = self.id().clone();
         let dst = target.as_entity_ref().id().clone();
-        if let Ok(mut db) = runtime_db().lock() {
-            db.upsert_edge(&src, &dst, kind, super::capture_backtrace_id());
-        }
+        lock_runtime_db().upsert_edge(&src, &dst, kind, backtrace);
+        EdgeHandle { src, dst, kind }
+    }
+
+    /// Like [`EntityRef::link_to_owned_with_backtrace`], with `reason` in
+    /// place of the one implied by the target's kind.
+    pub fn link_to_owned_with_reason(
+        &self,
+        target: &impl AsEntityRef,
+        kind: EdgeKind,
+        backtrace: BacktraceId,
+        reason: EdgeReason,
+    ) -> EdgeHandle {
+        let src = self.id().clone();
+        let dst = target.as_entity_ref().id().clone();
+        lock_runtime_db().upsert_edge_with_reason(&src, &dst, kind, backtrace, Some(reason));
         EdgeHandle { src, dst, kind }
     }
 }
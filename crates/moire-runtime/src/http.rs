@@ -0,0 +1,208 @@
+// r[impl config.http-addr]
+use axum::Router;
+use axum::http::{StatusCode, header};
+use axum::response::{IntoResponse, Response};
+use axum::routing::{get, post};
+use facet::Facet;
+use moire_graph_core::WaitGraphCore;
+use std::sync::OnceLock;
+use std::time::{Duration, Instant};
+
+use super::HTTP_SNAPSHOT_BUDGET_MS;
+use super::db::encode_snapshot_reply_json;
+use super::graph::deadlock_candidates;
+
+#[derive(Facet)]
+struct GraphNodeJson {
+    key: String,
+    name: String,
+    kind: String,
+    birth_ms: u64,
+}
+
+#[derive(Facet)]
+struct GraphEdgeJson {
+    src: String,
+    dst: String,
+    #[facet(skip_unless_truthy)]
+    since_ms: Option<u64>,
+}
+
+#[derive(Facet)]
+struct GraphJson {
+    ptime_now_ms: u64,
+    nodes: Vec<GraphNodeJson>,
+    edges: Vec<GraphEdgeJson>,
+}
+
+/// Routes serving this process's state as JSON, for mounting into an existing
+/// axum app:
+///
+/// - `/snapshot.json`: the snapshot the dashboard would receive
+/// - `/graph.json`: the waiting-on graph
+/// - `/candidates.json`: wait cycles, i.e. deadlock candidates
+/// - `/metrics`: [`render_prometheus`](crate::render_prometheus) output
+/// - `/diagnostics`: `on` or `off`; `POST /diagnostics/on` and
+///   `POST /diagnostics/off` call [`enable`](crate::enable) and
+///   [`disable`](crate::disable)
+pub fn http_router() -> Router {
+    Router::new()
+        .route("/snapshot.json", get(snapshot_json))
+        .route("/graph.json", get(graph_json))
+        .route("/candidates.json", get(candidates_json))
+        .route("/metrics", get(metrics))
+        .route("/diagnostics", get(diagnostics_state))
+        .route("/diagnostics/on", post(enable_diagnostics))
+        .route("/diagnostics/off", post(disable_diagnostics))
+}
+
+/// Serves [`http_router`] on `listener` until the listener fails.
+pub async fn serve_http(listener: tokio::net::TcpListener) -> std::io::Result<()> {
+    axum::serve(listener, http_router()).await
+}
+
+pub(super) fn init_http_server() {
+    static STARTED: OnceLock<()> = OnceLock::new();
+    if STARTED.set(()).is_err() {
+        return;
+    }
+
+    let Some(addr) = std::env::var("MOIRE_HTTP")
+        .ok()
+        .map(|s| s.trim().to_string())
+        .filter(|s| !s.is_empty())
+    else {
+        return;
+    };
+
+    let serve = async move {
+        let listener = match tokio::net::TcpListener::bind(&addr).await {
+            Ok(listener) => listener,
+            Err(e) => {
+                eprintln!("moire: cannot serve MOIRE_HTTP={addr:?}: {e}");
+                return;
+            }
+        };
+        if let Err(e) = serve_http(listener).await {
+            eprintln!("moire: http server on {addr} stopped: {e}");
+        }
+    };
+
+    if tokio::runtime::Handle::try_current().is_ok() {
+        tokio::spawn(serve);
+        return;
+    }
+
+    std::thread::spawn(move || {
+        if let Ok(rt) = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+        {
+            rt.block_on(serve);
+        }
+    });
+}
+
+fn deadline() -> Instant {
+    Instant::now() + Duration::from_millis(HTTP_SNAPSHOT_BUDGET_MS)
+}
+
+fn json_response(body: Vec<u8>) -> Response {
+    ([(header::CONTENT_TYPE, "application/json")], body).into_response()
+}
+
+fn error_response(status: StatusCode, message: String) -> Response {
+    (status, message).into_response()
+}
+
+/// Runs `f` off the async workers: snapshot assembly may block on the
+/// runtime db lock until its deadline.
+async fn blocking(f: impl FnOnce() -> Result<Vec<u8>, Response> + Send + 'static) -> Response {
+    match tokio::task::spawn_blocking(f).await {
+        Ok(Ok(body)) => json_response(body),
+        Ok(Err(response)) => response,
+        Err(e) => error_response(StatusCode::INTERNAL_SERVER_ERROR, format!("{e}")),
+    }
+}
+
+async fn snapshot_json() -> Response {
+    blocking(|| {
+        encode_snapshot_reply_json(deadline())
+            .map_err(|e| error_response(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))
+    })
+    .await
+}
+
+async fn metrics() -> Response {
+    match tokio::task::spawn_blocking(super::render_prometheus).await {
+        Ok(body) => ([(header::CONTENT_TYPE, "text/plain; version=0.0.4")], body).into_response(),
+        Err(e) => error_response(StatusCode::INTERNAL_SERVER_ERROR, format!("{e}")),
+    }
+}
+
+fn diagnostics_state_text() -> &'static str {
+    if super::is_enabled() { "on" } else { "off" }
+}
+
+async fn diagnostics_state() -> &'static str {
+    diagnostics_state_text()
+}
+
+async fn enable_diagnostics() -> &'static str {
+    super::enable();
+    diagnostics_state_text()
+}
+
+async fn disable_diagnostics() -> Response {
+    // Clearing takes the runtime db lock.
+    match tokio::task::spawn_blocking(super::disable).await {
+        Ok(()) => diagnostics_state_text().into_response(),
+        Err(e) => error_response(StatusCode::INTERNAL_SERVER_ERROR, format!("{e}")),
+    }
+}
+
+async fn graph_json() -> Response {
+    blocking(|| {
+        let (ptime_now_ms, graph) = wait_graph()?;
+        let graph = GraphJson {
+            ptime_now_ms,
+            nodes: graph
+                .nodes
+                .values()
+                .map(|row| GraphNodeJson {
+                    key: row.id.key(),
+                    name: row.name.clone(),
+                    kind: row.kind.clone(),
+                    birth_ms: row.birth_ms,
+                })
+                .collect(),
+            edges: graph
+                .edges
+                .iter()
+                .map(|edge| GraphEdgeJson {
+                    src: edge.src.key(),
+                    dst: edge.dst.key(),
+                    since_ms: edge.since_ms,
+                })
+                .collect(),
+        };
+        facet_json::to_vec(&graph)
+            .map_err(|e| error_response(StatusCode::INTERNAL_SERVER_ERROR, format!("{e}")))
+    })
+    .await
+}
+
+async fn candidates_json() -> Response {
+    blocking(|| {
+        let candidates = deadlock_candidates(deadline())
+            .map_err(|e| error_response(StatusCode::SERVICE_UNAVAILABLE, e))?;
+        facet_json::to_vec(&candidates)
+            .map_err(|e| error_response(StatusCode::INTERNAL_SERVER_ERROR, format!("{e}")))
+    })
+    .await
+}
+
+fn wait_graph() -> Result<(u64, WaitGraphCore), Response> {
+    super::graph::wait_graph(deadline())
+        .map_err(|e| error_response(StatusCode::SERVICE_UNAVAILABLE, e))
+}
@@ -0,0 +1,73 @@
+// r[impl config.lock-backtraces]
+use moire_trace_types::BacktraceId;
+use std::sync::OnceLock;
+use std::sync::atomic::{AtomicU8, Ordering};
+
+use super::config::{BacktraceCapture, env_parse};
+use super::handles::EntityHandle;
+
+/// When lock wrappers capture a fresh backtrace for their wait and hold edges.
+///
+/// Capture only records raw frames; symbolication happens lazily on the
+/// server, so the cost is the frame-pointer walk itself.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LockBacktracePolicy {
+    /// Lock edges reuse the backtrace captured when the lock was created.
+    Off,
+    /// Capture only for acquisitions that had to wait.
+    OnContention,
+    /// Capture for every acquisition.
+    Always,
+}
+
+impl LockBacktracePolicy {
+    fn from_u8(value: u8) -> Self {
+        match value {
+            0 => Self::Off,
+            1 => Self::OnContention,
+            _ => Self::Always,
+        }
+    }
+
+    fn from_env() -> Self {
+        env_parse(
+            "MOIRE_LOCK_BACKTRACES",
+            "off, on-contention, or always",
+            |value| match value {
+                "always" => Some(Self::Always),
+                "on-contention" => Some(Self::OnContention),
+                "off" => Some(Self::Off),
+                _ => None,
+            },
+        )
+        .unwrap_or(Self::Always)
+    }
+}
+
+fn policy_cell() -> &'static AtomicU8 {
+    static POLICY: OnceLock<AtomicU8> = OnceLock::new();
+    POLICY.get_or_init(|| AtomicU8::new(LockBacktracePolicy::from_env() as u8))
+}
+
+pub fn lock_backtrace_policy() -> LockBacktracePolicy {
+    LockBacktracePolicy::from_u8(policy_cell().load(Ordering::Relaxed))
+}
+
+/// Overrides the policy read from `MOIRE_LOCK_BACKTRACES`.
+pub fn set_lock_backtrace_policy(policy: LockBacktracePolicy) {
+    policy_cell().store(policy as u8, Ordering::Relaxed);
+}
+
+/// Backtrace to attach to a wait or hold edge on `lock` under the current policy.
+/// Below [`BacktraceCapture::Always`], lock edges never capture.
+pub fn lock_edge_backtrace<S>(lock: &EntityHandle<S>, contended: bool) -> BacktraceId {
+    if super::config::backtrace_capture() < BacktraceCapture::Always {
+        return lock.backtrace_id();
+    }
+    match (lock_backtrace_policy(), contended) {
+        (LockBacktracePolicy::Always, _) | (LockBacktracePolicy::OnContention, true) => {
+            super::capture_backtrace_id()
+        }
+        _ => lock.backtrace_id(),
+    }
+}
@@ -0,0 +1,171 @@
+// r[impl api.metrics]
+use moire_types::{EdgeKind, EntityBody, EntityId};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt::Write as _;
+use std::time::{Duration, Instant};
+
+use super::db::{lock_until, runtime_db};
+
+/// How long rendering waits for the runtime db before giving up.
+const METRICS_BUDGET: Duration = Duration::from_millis(250);
+
+struct Sample {
+    labels: String,
+    value: u64,
+}
+
+#[derive(Default)]
+struct Family {
+    help: &'static str,
+    kind: &'static str,
+    samples: Vec<Sample>,
+}
+
+/// Renders the current state of this process's instrumented primitives in the
+/// Prometheus text exposition format: channel depths, semaphore and lock
+/// waiters, acquire counts and task counts by state. Resources are labelled
+/// with their name and entity id.
+///
+/// Returns only a comment if the runtime db stayed locked for too long.
+pub fn render_prometheus() -> String {
+    let Some(db) = lock_until(runtime_db(), Instant::now() + METRICS_BUDGET) else {
+        return String::from("# moire: runtime db stayed locked; no metrics this scrape\n");
+    };
+
+    let mut waiters: BTreeMap<&EntityId, u64> = BTreeMap::new();
+    let mut waiting: BTreeSet<&EntityId> = BTreeSet::new();
+    for edge in db.edges.values() {
+        if edge.kind == EdgeKind::WaitingOn {
+            *waiters.entry(&edge.dst).or_default() += 1;
+            waiting.insert(&edge.src);
+        }
+    }
+
+    let mut families: BTreeMap<&'static str, Family> = BTreeMap::new();
+    let mut push =
+        |name: &'static str, help: &'static str, kind: &'static str, labels: String, value: u64| {
+            let family = families.entry(name).or_default();
+            family.help = help;
+            family.kind = kind;
+            family.samples.push(Sample { labels, value });
+        };
+    let mut tasks_by_state: BTreeMap<&'static str, u64> = BTreeMap::new();
+
+    for entity in db.entities.values() {
+        let labels = format!(
+            "name=\"{}\",entity_id=\"{}\"",
+            escape_label(&entity.name),
+            escape_label(entity.id.as_str())
+        );
+        let waiter_count = waiters.get(&entity.id).copied().unwrap_or(0);
+        match &entity.body {
+            EntityBody::MpscTx(tx) => {
+                push(
+                    "moire_channel_queue_depth",
+                    "Messages queued in an mpsc channel.",
+                    "gauge",
+                    labels.clone(),
+                    u64::from(tx.queue_len),
+                );
+                if let Some(capacity) = tx.capacity {
+                    push(
+                        "moire_channel_capacity",
+                        "Buffer size of a bounded mpsc channel.",
+                        "gauge",
+                        labels,
+                        u64::from(capacity),
+                    );
+                }
+            }
+            EntityBody::Semaphore(semaphore) => {
+                push(
+                    "moire_semaphore_permits_in_use",
+                    "Permits currently handed out by a semaphore.",
+                    "gauge",
+                    labels.clone(),
+                    u64::from(semaphore.handed_out_permits),
+                );
+                push(
+                    "moire_semaphore_permits",
+                    "Permits a semaphore was created with.",
+                    "gauge",
+                    labels.clone(),
+                    u64::from(semaphore.max_permits),
+                );
+                push(
+                    "moire_semaphore_waiters",
+                    "Futures waiting to acquire a semaphore.",
+                    "gauge",
+                    labels.clone(),
+                    waiter_count,
+                );
+                if let Some(holds) = &semaphore.holds {
+                    push(
+                        "moire_semaphore_acquires_total",
+                        "Completed semaphore holds.",
+                        "counter",
+                        labels,
+                        holds.hold_count,
+                    );
+                }
+            }
+            EntityBody::Lock(lock) => {
+                push(
+                    "moire_lock_waiters",
+                    "Futures waiting to acquire a lock.",
+                    "gauge",
+                    labels.clone(),
+                    waiter_count,
+                );
+                if let Some(holds) = &lock.holds {
+                    push(
+                        "moire_lock_acquires_total",
+                        "Completed lock holds.",
+                        "counter",
+                        labels,
+                        holds.hold_count,
+                    );
+                }
+            }
+            EntityBody::Future(future) if future.logical_id.is_some() => {
+                let state = if future.idle.is_some() {
+                    "idle"
+                } else if waiting.contains(&entity.id) {
+                    "waiting"
+                } else {
+                    "active"
+                };
+                *tasks_by_state.entry(state).or_default() += 1;
+            }
+            _ => {}
+        }
+    }
+    drop(db);
+
+    for state in ["active", "idle", "waiting"] {
+        push(
+            "moire_tasks",
+            "Tracked tasks by state: waiting on a resource, marked idle, or neither.",
+            "gauge",
+            format!("state=\"{state}\""),
+            tasks_by_state.get(state).copied().unwrap_or(0),
+        );
+    }
+
+    let mut out = String::new();
+    for (name, family) in &families {
+        let _ = writeln!(out, "# HELP {name} {}", family.help);
+        let _ = writeln!(out, "# TYPE {name} {}", family.kind);
+        for sample in &family.samples {
+            let _ = writeln!(out, "{name}{{{}}} {}", sample.labels, sample.value);
+        }
+    }
+    out
+}
+
+fn escape_label(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
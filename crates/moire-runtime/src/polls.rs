@@ -0,0 +1,101 @@
+// r[impl config.long-polls]
+use moire_types::{EntityId, LongPoll, PTime};
+use std::cell::Cell;
+use std::collections::VecDeque;
+use std::sync::Mutex;
+use std::sync::OnceLock;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+
+use super::config::env_parse;
+use super::db::{lock_recovering, lock_until};
+
+const DEFAULT_LONG_POLL_THRESHOLD: Duration = Duration::from_millis(100);
+
+static LONG_POLLS: Mutex<VecDeque<LongPoll>> = Mutex::new(VecDeque::new());
+
+thread_local! {
+    /// Bumped whenever a long poll is recorded on this thread, so a future
+    /// wrapping an already-reported one does not report the same stall again.
+    static LONG_POLLS_NOTED: Cell<u64> = const { Cell::new(0) };
+}
+
+fn threshold_from_env() -> u64 {
+    env_parse("MOIRE_LONG_POLL_MS", "a number of milliseconds", |value| {
+        value.parse::<u64>().ok()
+    })
+    .map_or(DEFAULT_LONG_POLL_THRESHOLD.as_micros() as u64, |ms| {
+        ms.saturating_mul(1_000)
+    })
+}
+
+fn threshold_cell() -> &'static AtomicU64 {
+    static THRESHOLD_US: OnceLock<AtomicU64> = OnceLock::new();
+    THRESHOLD_US.get_or_init(|| AtomicU64::new(threshold_from_env()))
+}
+
+/// Single polls at least this long are recorded as long polls. Zero disables
+/// detection.
+pub fn long_poll_threshold() -> Duration {
+    Duration::from_micros(threshold_cell().load(Ordering::Relaxed))
+}
+
+/// Overrides the threshold read from `MOIRE_LONG_POLL_MS`.
+pub fn set_long_poll_threshold(threshold: Duration) {
+    threshold_cell().store(
+        threshold.as_micros().min(u64::MAX as u128) as u64,
+        Ordering::Relaxed,
+    );
+}
+
+/// Times one poll of an instrumented future.
+pub(crate) struct PollTimer {
+    started: Instant,
+    noted_before: u64,
+}
+
+impl PollTimer {
+    pub(crate) fn start() -> Self {
+        Self {
+            started: Instant::now(),
+            noted_before: LONG_POLLS_NOTED.with(Cell::get),
+        }
+    }
+
+    /// Records the poll if it ran past the threshold and no instrumented
+    /// future it polled already did. Returns how long the poll took, in
+    /// microseconds.
+    pub(crate) fn finish(self, future_id: &EntityId, worker: u64) -> u64 {
+        let duration_us = self.started.elapsed().as_micros().min(u64::MAX as u128) as u64;
+        let threshold = threshold_cell().load(Ordering::Relaxed);
+        if threshold == 0
+            || duration_us < threshold
+            || LONG_POLLS_NOTED.with(Cell::get) != self.noted_before
+        {
+            return duration_us;
+        }
+        LONG_POLLS_NOTED.with(|noted| noted.set(noted.get().wrapping_add(1)));
+
+        let long_poll = LongPoll {
+            future_id: future_id.clone(),
+            at: PTime::now(),
+            duration_us,
+            worker,
+            backtrace: super::capture_long_poll_backtrace_id(),
+        };
+        let mut long_polls = lock_recovering(&LONG_POLLS);
+        long_polls.push_back(long_poll);
+        let kept = super::config::long_polls_kept();
+        while long_polls.len() > kept {
+            long_polls.pop_front();
+        }
+        duration_us
+    }
+}
+
+/// Recorded long polls, oldest first. `None` if the buffer stayed locked past
+/// `deadline`.
+pub(crate) fn recent_long_polls(deadline: Instant) -> Option<Vec<LongPoll>> {
+    let long_polls = lock_until(&LONG_POLLS, deadline)?;
+    Some(long_polls.iter().cloned().collect())
+}
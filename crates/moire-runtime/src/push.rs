@@ -0,0 +1,144 @@
+// r[impl config.snapshot-push]
+//! Periodic snapshot push to a collector.
+//!
+//! `MOIRE_DASHBOARD` keeps one connection open and streams every change;
+//! `MOIRE_PUSH=<host>:<port>` instead has the process connect every
+//! `MOIRE_PUSH_SECS` seconds and send a single snapshot nobody asked for:
+//! the protocol magic, the handshake, the backtrace records, and a
+//! `SnapshotReply` with id 0. The connection is closed after each, so a
+//! collector only has to accept and decode.
+use std::sync::OnceLock;
+use std::time::{Duration, Instant};
+
+use tokio::io::AsyncWriteExt;
+use tokio::net::TcpStream;
+use tokio::time::{MissedTickBehavior, timeout};
+
+use super::config::{env_parse, positive_secs};
+use super::dashboard::flush_backtrace_records;
+
+const DEFAULT_PUSH_PERIOD: Duration = Duration::from_secs(10);
+
+/// How long assembling one pushed snapshot may take.
+const PUSH_SNAPSHOT_BUDGET: Duration = Duration::from_secs(1);
+
+pub(super) fn init_snapshot_push(process_name: &str) {
+    static STARTED: OnceLock<()> = OnceLock::new();
+    if STARTED.set(()).is_err() {
+        return;
+    }
+
+    let Some(addr) = std::env::var("MOIRE_PUSH")
+        .ok()
+        .map(|s| s.trim().to_string())
+        .filter(|s| !s.is_empty())
+    else {
+        return;
+    };
+    let period = env_parse(
+        "MOIRE_PUSH_SECS",
+        "a positive number of seconds",
+        positive_secs,
+    )
+    .unwrap_or(DEFAULT_PUSH_PERIOD);
+
+    let process_name = String::from(process_name);
+    let push = async move {
+        let mut ticker = tokio::time::interval(period);
+        ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
+        loop {
+            ticker.tick().await;
+            if !super::is_enabled() {
+                continue;
+            }
+            // A collector that is down or slow only costs this round.
+            let _ = timeout(period, push_snapshot(&addr, &process_name)).await;
+        }
+    };
+
+    if tokio::runtime::Handle::try_current().is_ok() {
+        tokio::spawn(push);
+        return;
+    }
+
+    std::thread::spawn(move || {
+        if let Ok(rt) = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+        {
+            rt.block_on(push);
+        }
+    });
+}
+
+async fn push_snapshot(addr: &str, process_name: &str) -> Result<(), String> {
+    let mut stream = TcpStream::connect(addr)
+        .await
+        .map_err(|e| format!("push connect: {e}"))?;
+    stream
+        .write_all(&moire_wire::encode_protocol_magic())
+        .await
+        .map_err(|e| format!("write protocol magic: {e}"))?;
+    let mut last_sent_manifest_revision = u64::MAX;
+    let mut last_sent_backtrace_id = None;
+    flush_backtrace_records(
+        &mut stream,
+        process_name,
+        &mut last_sent_manifest_revision,
+        &mut last_sent_backtrace_id,
+    )
+    .await?;
+    let frame =
+        super::db::encode_snapshot_reply_frame(0, Instant::now() + PUSH_SNAPSHOT_BUDGET, None)?;
+    stream
+        .write_all(&frame)
+        .await
+        .map_err(|e| format!("write frame: {e}"))?;
+    stream
+        .shutdown()
+        .await
+        .map_err(|e| format!("close push connection: {e}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use moire_wire::{ClientMessage, decode_client_message_default, decode_protocol_magic};
+    use std::io::Read;
+
+    #[test]
+    fn pushed_snapshot_is_a_handshake_then_a_reply() {
+        let collector = std::net::TcpListener::bind("127.0.0.1:0").expect("bind");
+        let addr = collector.local_addr().expect("addr").to_string();
+        let received = std::thread::spawn(move || {
+            let (mut stream, _) = collector.accept().expect("accept");
+            let mut bytes = Vec::new();
+            stream.read_to_end(&mut bytes).expect("read");
+            bytes
+        });
+
+        tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .expect("runtime")
+            .block_on(push_snapshot(&addr, "pusher"))
+            .expect("push");
+
+        let bytes = received.join().expect("collector");
+        decode_protocol_magic(bytes[..4].try_into().expect("magic")).expect("protocol magic");
+        let mut rest = &bytes[4..];
+        let mut messages = Vec::new();
+        while !rest.is_empty() {
+            let len = u32::from_be_bytes(rest[..4].try_into().expect("len")) as usize;
+            let (frame, tail) = rest.split_at(4 + len);
+            messages.push(decode_client_message_default(frame).expect("client message"));
+            rest = tail;
+        }
+        assert!(
+            matches!(messages.first(), Some(ClientMessage::Handshake(handshake)) if handshake.process_name == "pusher")
+        );
+        assert!(
+            matches!(messages.last(), Some(ClientMessage::SnapshotReply(reply)) if reply.snapshot_id == 0)
+        );
+    }
+}
@@ -0,0 +1,210 @@
+// r[impl api.redaction]
+//! Scrubbing what the process reports before any of it leaves the process.
+//!
+//! The installed [`Redactor`] sees every entity as it is recorded and again
+//! each time it changes, before it is stored, so snapshot replies, streamed
+//! changes, `/snapshot.json` and watchdog dumps only ever carry the scrubbed
+//! version. It also sees the command line and environment the process
+//! announces itself with.
+use moire_types::{Entity, EntityBody, Json, ResponseError, ResponseStatus};
+use std::sync::{Arc, PoisonError, RwLock};
+
+use super::db::lock_runtime_db;
+
+/// Scrubs what the process reports.
+pub trait Redactor: Send + Sync + 'static {
+    /// Scrubs an entity as it is recorded or changed.
+    fn redact_entity(&self, entity: &mut Entity);
+
+    /// Scrubs the command line and `KEY=VALUE` environment sent to the
+    /// dashboard when the process connects.
+    fn redact_process(&self, args: &mut Vec<String>, env: &mut Vec<String>) {
+        let _ = (args, env);
+    }
+}
+
+impl<F> Redactor for F
+where
+    F: Fn(&mut Entity) + Send + Sync + 'static,
+{
+    fn redact_entity(&self, entity: &mut Entity) {
+        self(entity)
+    }
+}
+
+/// Drops payloads: RPC arguments and results, child process arguments and
+/// environments, custom entity attributes, and the process's own command
+/// line and environment. Names, paths and addresses are kept.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct DropArgs;
+
+impl Redactor for DropArgs {
+    fn redact_entity(&self, entity: &mut Entity) {
+        match &mut entity.body {
+            EntityBody::Request(request) => request.args_json = Json::new("[]"),
+            EntityBody::Response(response) => match &mut response.status {
+                ResponseStatus::Ok(result) => *result = Json::new("null"),
+                ResponseStatus::Error(ResponseError::Internal(message)) => message.clear(),
+                ResponseStatus::Error(ResponseError::UserJson(error)) => *error = Json::new("null"),
+                ResponseStatus::Pending | ResponseStatus::Cancelled => {}
+            },
+            EntityBody::Command(command) => {
+                command.args.clear();
+                command.env.clear();
+            }
+            EntityBody::Custom(custom) => custom.attrs = Json::new("{}"),
+            _ => {}
+        }
+    }
+
+    fn redact_process(&self, args: &mut Vec<String>, env: &mut Vec<String>) {
+        args.truncate(1);
+        env.clear();
+    }
+}
+
+/// Replaces the strings [`DropArgs`] drops, plus file paths and network
+/// addresses, with `redacted:<hash>` digests, so equal values can still be
+/// matched up across entities and snapshots without being readable.
+/// Environment entries keep their key.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct HashStrings;
+
+impl HashStrings {
+    fn json(json: &mut Json) {
+        if !json.as_str().starts_with("\"redacted:") {
+            *json = Json::new(format!("\"{}\"", digest(json.as_str())));
+        }
+    }
+
+    fn string(value: &mut String) {
+        *value = digest(value);
+    }
+
+    fn env_entry(entry: &mut String) {
+        *entry = match entry.split_once('=') {
+            Some((key, value)) => format!("{key}={}", digest(value)),
+            None => digest(entry),
+        };
+    }
+}
+
+impl Redactor for HashStrings {
+    fn redact_entity(&self, entity: &mut Entity) {
+        match &mut entity.body {
+            EntityBody::Request(request) => Self::json(&mut request.args_json),
+            EntityBody::Response(response) => match &mut response.status {
+                ResponseStatus::Ok(result) => Self::json(result),
+                ResponseStatus::Error(ResponseError::Internal(message)) => Self::string(message),
+                ResponseStatus::Error(ResponseError::UserJson(error)) => Self::json(error),
+                ResponseStatus::Pending | ResponseStatus::Cancelled => {}
+            },
+            EntityBody::Command(command) => {
+                command.args.iter_mut().for_each(Self::string);
+                command.env.iter_mut().for_each(Self::env_entry);
+            }
+            EntityBody::FileOp(file_op) => Self::string(&mut file_op.path),
+            EntityBody::NetConnect(net) => Self::string(&mut net.addr),
+            EntityBody::NetAccept(net) => Self::string(&mut net.addr),
+            EntityBody::NetRead(net) => Self::string(&mut net.addr),
+            EntityBody::NetWrite(net) => Self::string(&mut net.addr),
+            EntityBody::Custom(custom) => Self::json(&mut custom.attrs),
+            _ => {}
+        }
+    }
+
+    fn redact_process(&self, args: &mut Vec<String>, env: &mut Vec<String>) {
+        args.iter_mut().skip(1).for_each(Self::string);
+        env.iter_mut().for_each(Self::env_entry);
+    }
+}
+
+/// `redacted:` and 16 hex characters of FNV-1a over `value`. Values already
+/// redacted are left alone, since entities are scrubbed again on every
+/// change.
+fn digest(value: &str) -> String {
+    if value.starts_with("redacted:") {
+        return String::from(value);
+    }
+    const OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+    const PRIME: u64 = 0x0000_0100_0000_01b3;
+    let hash = value.bytes().fold(OFFSET_BASIS, |hash, byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(PRIME)
+    });
+    format!("redacted:{hash:016x}")
+}
+
+static REDACTOR: RwLock<Option<Arc<dyn Redactor>>> = RwLock::new(None);
+
+fn current() -> Option<Arc<dyn Redactor>> {
+    REDACTOR
+        .read()
+        .unwrap_or_else(PoisonError::into_inner)
+        .clone()
+}
+
+/// Installs `redactor`, replacing any previous one. Entities already
+/// recorded are scrubbed as it is installed; what was sent before then is
+/// out of reach, so install it before instrumented code runs.
+pub fn set_redactor(redactor: impl Redactor) {
+    *REDACTOR.write().unwrap_or_else(PoisonError::into_inner) = Some(Arc::new(redactor));
+    lock_runtime_db().redact_entities();
+}
+
+/// Removes the installed redactor. Entities recorded from then on are
+/// reported as they are.
+pub fn clear_redactor() {
+    *REDACTOR.write().unwrap_or_else(PoisonError::into_inner) = None;
+}
+
+pub(crate) fn redact_entity(entity: &mut Entity) {
+    if let Some(redactor) = current() {
+        redactor.redact_entity(entity);
+    }
+}
+
+pub(crate) fn redact_process(args: &mut Vec<String>, env: &mut Vec<String>) {
+    if let Some(redactor) = current() {
+        redactor.redact_process(args, env);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use moire_trace_types::BacktraceId;
+    use moire_types::RequestEntity;
+
+    use super::*;
+
+    // r[verify api.redaction]
+    #[test]
+    fn hashed_strings_match_up_and_are_hashed_once() {
+        let request = |args: &str| {
+            let mut entity = Entity::new(
+                BacktraceId::next().expect("backtrace id"),
+                "vfs.lookupItem",
+                EntityBody::Request(RequestEntity {
+                    service_name: String::from("vfs"),
+                    method_name: String::from("lookupItem"),
+                    args_json: Json::new(args),
+                    connection_generation: None,
+                }),
+            );
+            HashStrings.redact_entity(&mut entity);
+            entity
+        };
+        let args_of = |entity: &Entity| match &entity.body {
+            EntityBody::Request(request) => String::from(request.args_json.as_str()),
+            _ => unreachable!(),
+        };
+
+        let mut alice = request(r#"["alice@example.com"]"#);
+        let hashed = args_of(&alice);
+        assert!(hashed.starts_with("\"redacted:"));
+        assert!(facet_json::from_str::<String>(&hashed).is_ok());
+        assert_eq!(hashed, args_of(&request(r#"["alice@example.com"]"#)));
+        assert_ne!(hashed, args_of(&request(r#"["bob@example.com"]"#)));
+        HashStrings.redact_entity(&mut alice);
+        assert_eq!(args_of(&alice), hashed);
+    }
+}
@@ -0,0 +1,107 @@
+// r[impl wire.snapshot-rpc-methods]
+//! Latency of the RPC methods this process serves.
+//!
+//! Every response that leaves `pending` is counted against its method as it
+//! completes, so a snapshot carries ready-made percentiles instead of leaving
+//! readers to work them out from response entities, most of which are gone
+//! by then anyway.
+use moire_types::{ResponseStatus, RpcMethodStats};
+use std::collections::{BTreeMap, VecDeque};
+use std::sync::Mutex;
+use std::time::Instant;
+
+use super::db::{lock_recovering, lock_until};
+
+/// Completions per method the percentiles are computed over.
+const RECENT_COMPLETIONS_KEPT: usize = 256;
+
+#[derive(Default)]
+struct MethodCompletions {
+    count: u64,
+    error_count: u64,
+    /// Durations of the most recent completions, in milliseconds, oldest
+    /// first.
+    recent_ms: VecDeque<u64>,
+}
+
+static METHODS: Mutex<BTreeMap<(String, String), MethodCompletions>> = Mutex::new(BTreeMap::new());
+
+/// Counts a response to `service_name.method_name` that reached `status`
+/// `duration_ms` after it was created.
+pub(crate) fn note_completion(
+    service_name: &str,
+    method_name: &str,
+    status: &ResponseStatus,
+    duration_ms: u64,
+) {
+    let mut methods = lock_recovering(&METHODS);
+    let method = methods
+        .entry((String::from(service_name), String::from(method_name)))
+        .or_default();
+    method.count += 1;
+    if matches!(status, ResponseStatus::Error(_)) {
+        method.error_count += 1;
+    }
+    if method.recent_ms.len() == RECENT_COMPLETIONS_KEPT {
+        method.recent_ms.pop_front();
+    }
+    method.recent_ms.push_back(duration_ms);
+}
+
+pub(crate) fn rpc_method_stats(deadline: Instant) -> Option<Vec<RpcMethodStats>> {
+    let methods = lock_until(&METHODS, deadline)?;
+    Some(
+        methods
+            .iter()
+            .map(|((service_name, method_name), method)| {
+                let mut sorted = method.recent_ms.iter().copied().collect::<Vec<_>>();
+                sorted.sort_unstable();
+                RpcMethodStats {
+                    service_name: service_name.clone(),
+                    method_name: method_name.clone(),
+                    count: method.count,
+                    error_count: method.error_count,
+                    p50_ms: percentile(&sorted, 50),
+                    p95_ms: percentile(&sorted, 95),
+                    p99_ms: percentile(&sorted, 99),
+                    window: sorted.len() as u32,
+                }
+            })
+            .collect(),
+    )
+}
+
+/// Nearest-rank percentile of `sorted`, zero when it is empty.
+fn percentile(sorted: &[u64], p: usize) -> u64 {
+    match sorted.len() {
+        0 => 0,
+        len => sorted[(len * p).div_ceil(100).max(1) - 1],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use moire_types::{Json, ResponseError};
+    use std::time::Duration;
+
+    use super::*;
+
+    // r[verify wire.snapshot-rpc-methods]
+    #[test]
+    fn rpc_method_percentiles_cover_recent_completions() {
+        let ok = ResponseStatus::Ok(Json::new("null"));
+        let failed = ResponseStatus::Error(ResponseError::Internal(String::from("boom")));
+        for duration_ms in 1..=300 {
+            let status = if duration_ms % 100 == 0 { &failed } else { &ok };
+            note_completion("percentiles", "get", status, duration_ms);
+        }
+        let stats = rpc_method_stats(Instant::now() + Duration::from_secs(1)).expect("stats lock");
+        let get = stats
+            .iter()
+            .find(|stats| stats.service_name == "percentiles")
+            .expect("method stats");
+        assert_eq!((get.count, get.error_count, get.window), (300, 3, 256));
+        // Only the last 256 completions, 45ms to 300ms, are ranked.
+        assert_eq!((get.p50_ms, get.p95_ms, get.p99_ms), (172, 288, 298));
+    }
+}
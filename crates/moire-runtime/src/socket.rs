@@ -0,0 +1,339 @@
+// r[impl config.socket-path]
+//! Pull-mode snapshot agent on a Unix socket, or on TCP behind a token.
+//!
+//! Where `MOIRE_DASHBOARD` has the process connect out and push, a process
+//! started with `MOIRE_SOCKET=<path>` listens on that path and waits to be
+//! asked. A collector speaks the dashboard protocol in the other direction:
+//! it connects, reads the protocol magic and handshake, and sends
+//! `SnapshotRequest`s and `CutRequest`s; each is answered with the backtrace
+//! records the answer refers to, then the `SnapshotReply` (assembled within
+//! half the request's timeout) or `CutAck`. Changes are never pushed.
+//!
+//! `MOIRE_SOCKET=tcp:<host>:<port>` listens on TCP instead. Anyone who can
+//! reach the port could read the process's state, so this needs
+//! `MOIRE_SOCKET_TOKEN`, which the collector sends, followed by a newline,
+//! before anything else.
+use std::os::unix::fs::{DirBuilderExt, FileTypeExt, PermissionsExt};
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+use std::time::Duration;
+
+use moire_wire::{ClientMessage, ServerMessage};
+use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt, BufReader};
+use tokio::net::{TcpListener, UnixListener};
+use tokio::time::timeout;
+
+use super::api::ack_cut;
+use super::dashboard::{
+    flush_backtrace_records, read_server_message, send_handshake_if_manifest_changed,
+    snapshot_deadline, write_client_message,
+};
+
+/// How long a collector may stay silent before it is disconnected, so a
+/// stalled one doesn't hold its connection forever.
+const COLLECTOR_IDLE_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// How long writing one answer may take before the collector, which has
+/// stopped reading, is disconnected.
+const COLLECTOR_WRITE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Longest token line read from a TCP collector.
+const MAX_TOKEN_LINE: u64 = 1024;
+
+enum Listen {
+    Unix(PathBuf),
+    Tcp { addr: String, token: String },
+}
+
+pub(super) fn init_socket_server(process_name: &str) {
+    static STARTED: OnceLock<()> = OnceLock::new();
+    if STARTED.set(()).is_err() {
+        return;
+    }
+
+    let Some(target) = std::env::var("MOIRE_SOCKET")
+        .ok()
+        .map(|s| s.trim().to_string())
+        .filter(|s| !s.is_empty())
+    else {
+        return;
+    };
+    let listen = match target.strip_prefix("tcp:") {
+        None => Listen::Unix(PathBuf::from(&target)),
+        // r[impl config.socket-tcp]
+        Some(addr) => {
+            let Some(token) = std::env::var("MOIRE_SOCKET_TOKEN")
+                .ok()
+                .map(|s| s.trim().to_string())
+                .filter(|s| !s.is_empty())
+            else {
+                eprintln!(
+                    "moire: not listening on MOIRE_SOCKET={target:?}: TCP needs MOIRE_SOCKET_TOKEN"
+                );
+                return;
+            };
+            Listen::Tcp {
+                addr: addr.to_string(),
+                token,
+            }
+        }
+    };
+
+    let process_name = String::from(process_name);
+    let serve = async move {
+        match listen {
+            Listen::Unix(path) => {
+                let listener = match bind(&path) {
+                    Ok(listener) => listener,
+                    Err(e) => {
+                        eprintln!("moire: cannot listen on MOIRE_SOCKET={target:?}: {e}");
+                        return;
+                    }
+                };
+                loop {
+                    let stream = match listener.accept().await {
+                        Ok((stream, _)) => stream,
+                        Err(e) => {
+                            eprintln!("moire: socket agent on {target} stopped: {e}");
+                            return;
+                        }
+                    };
+                    let process_name = process_name.clone();
+                    tokio::spawn(async move {
+                        let (reader, writer) = stream.into_split();
+                        let _ = serve_collector(reader, writer, process_name).await;
+                    });
+                }
+            }
+            Listen::Tcp { addr, token } => {
+                let listener = match TcpListener::bind(&addr).await {
+                    Ok(listener) => listener,
+                    Err(e) => {
+                        eprintln!("moire: cannot listen on MOIRE_SOCKET={target:?}: {e}");
+                        return;
+                    }
+                };
+                loop {
+                    let stream = match listener.accept().await {
+                        Ok((stream, _)) => stream,
+                        Err(e) => {
+                            eprintln!("moire: socket agent on {target} stopped: {e}");
+                            return;
+                        }
+                    };
+                    let process_name = process_name.clone();
+                    let token = token.clone();
+                    tokio::spawn(async move {
+                        let (reader, writer) = stream.into_split();
+                        let Ok(reader) = authenticate(reader, &token).await else {
+                            return;
+                        };
+                        let _ = serve_collector(reader, writer, process_name).await;
+                    });
+                }
+            }
+        }
+    };
+
+    if tokio::runtime::Handle::try_current().is_ok() {
+        tokio::spawn(serve);
+        return;
+    }
+
+    std::thread::spawn(move || {
+        if let Ok(rt) = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+        {
+            rt.block_on(serve);
+        }
+    });
+}
+
+/// Binds `path`, replacing a socket left behind by an earlier run but never
+/// any other kind of file. Snapshots name tasks and resources, so the socket
+/// is bound inside a directory only the owner can enter, restricted to the
+/// owner, and only then moved into place.
+fn bind(path: &Path) -> std::io::Result<UnixListener> {
+    if let Ok(metadata) = std::fs::symlink_metadata(path) {
+        if !metadata.file_type().is_socket() {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::AlreadyExists,
+                "a file that is not a socket is in the way",
+            ));
+        }
+        std::fs::remove_file(path)?;
+    }
+    let private_dir = path
+        .parent()
+        .unwrap_or(Path::new("."))
+        .join(format!(".moire-socket-{}", std::process::id()));
+    std::fs::DirBuilder::new()
+        .mode(0o700)
+        .create(&private_dir)?;
+    let staged = private_dir.join("socket");
+    let bound = UnixListener::bind(&staged).and_then(|listener| {
+        std::fs::set_permissions(&staged, std::fs::Permissions::from_mode(0o600))?;
+        std::fs::rename(&staged, path)?;
+        Ok(listener)
+    });
+    let _ = std::fs::remove_file(&staged);
+    let _ = std::fs::remove_dir(&private_dir);
+    bound
+}
+
+/// Reads the token line a TCP collector opens with, and hands back the rest
+/// of the stream if it matches `token`.
+async fn authenticate<R: AsyncRead + Unpin>(
+    reader: R,
+    token: &str,
+) -> Result<BufReader<R>, String> {
+    let mut reader = BufReader::new(reader);
+    let mut line = Vec::new();
+    timeout(
+        COLLECTOR_IDLE_TIMEOUT,
+        (&mut reader)
+            .take(MAX_TOKEN_LINE)
+            .read_until(b'\n', &mut line),
+    )
+    .await
+    .map_err(|_| String::from("collector sent no token"))?
+    .map_err(|e| format!("read token: {e}"))?;
+    let sent = line.strip_suffix(b"\n").unwrap_or(&line);
+    let sent = sent.strip_suffix(b"\r").unwrap_or(sent);
+    if !tokens_match(sent, token.as_bytes()) {
+        return Err(String::from("collector sent a wrong token"));
+    }
+    Ok(reader)
+}
+
+/// Compares tokens in time that depends only on their lengths.
+fn tokens_match(sent: &[u8], expected: &[u8]) -> bool {
+    sent.len() == expected.len()
+        && sent
+            .iter()
+            .zip(expected)
+            .fold(0, |diff, (a, b)| diff | (a ^ b))
+            == 0
+}
+
+async fn serve_collector(
+    mut reader: impl AsyncRead + Unpin,
+    mut writer: impl AsyncWrite + Unpin,
+    process_name: String,
+) -> Result<(), String> {
+    let mut last_sent_manifest_revision = u64::MAX;
+    let mut last_sent_backtrace_id = None;
+    timeout(COLLECTOR_WRITE_TIMEOUT, async {
+        writer
+            .write_all(&moire_wire::encode_protocol_magic())
+            .await
+            .map_err(|e| format!("write protocol magic: {e}"))?;
+        send_handshake_if_manifest_changed(
+            &mut writer,
+            process_name.as_str(),
+            &mut last_sent_manifest_revision,
+        )
+        .await
+    })
+    .await
+    .map_err(|_| String::from("collector stopped reading"))??;
+
+    loop {
+        let message = timeout(COLLECTOR_IDLE_TIMEOUT, read_server_message(&mut reader))
+            .await
+            .map_err(|_| String::from("collector went quiet"))??;
+        let Some(message) = message else {
+            return Ok(());
+        };
+        timeout(COLLECTOR_WRITE_TIMEOUT, async {
+            flush_backtrace_records(
+                &mut writer,
+                process_name.as_str(),
+                &mut last_sent_manifest_revision,
+                &mut last_sent_backtrace_id,
+            )
+            .await?;
+            match message {
+                ServerMessage::CutRequest(request) => {
+                    let ack = ack_cut(request.cut_id.clone());
+                    write_client_message(&mut writer, &ClientMessage::CutAck(ack)).await
+                }
+                ServerMessage::SnapshotRequest(request) => {
+                    let deadline = snapshot_deadline(request.timeout_ms);
+                    let frame = super::db::encode_snapshot_reply_frame(
+                        request.snapshot_id,
+                        deadline,
+                        request.encoding,
+                    )?;
+                    writer
+                        .write_all(&frame)
+                        .await
+                        .map_err(|e| format!("write frame: {e}"))
+                }
+            }
+        })
+        .await
+        .map_err(|_| String::from("collector stopped reading"))??;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn runtime() -> tokio::runtime::Runtime {
+        tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .expect("runtime")
+    }
+
+    #[test]
+    fn socket_is_owner_only_and_never_replaces_other_files() {
+        let dir = std::env::temp_dir().join(format!("moire-socket-test-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).expect("test dir");
+        let path = dir.join("agent.sock");
+        let regular = dir.join("notes.txt");
+        std::fs::write(&regular, "keep me").expect("regular file");
+
+        runtime().block_on(async {
+            let listener = bind(&path).expect("bind");
+            let mode = std::fs::metadata(&path)
+                .expect("socket")
+                .permissions()
+                .mode();
+            assert_eq!(mode & 0o777, 0o600);
+            drop(listener);
+            bind(&path).expect("replaces the stale socket");
+            assert!(bind(&regular).is_err());
+        });
+
+        assert_eq!(std::fs::read_to_string(&regular).expect("kept"), "keep me");
+        let leftovers = std::fs::read_dir(&dir)
+            .expect("test dir")
+            .filter_map(|entry| entry.ok()?.file_name().into_string().ok())
+            .filter(|name| name.starts_with(".moire-socket-"))
+            .count();
+        assert_eq!(leftovers, 0);
+        std::fs::remove_dir_all(&dir).expect("clean up");
+    }
+
+    #[test]
+    fn tcp_collectors_must_open_with_the_token() {
+        runtime().block_on(async {
+            let (mut collector, agent) = tokio::io::duplex(64);
+            collector.write_all(b"s3cret\r\nrest").await.expect("write");
+            let mut rest = authenticate(agent, "s3cret").await.expect("accepted");
+            let mut buf = [0u8; 4];
+            rest.read_exact(&mut buf).await.expect("rest of the stream");
+            assert_eq!(&buf, b"rest");
+
+            for sent in [&b"s3cres\n"[..], b"s3cret2\n", b"\n"] {
+                let (mut collector, agent) = tokio::io::duplex(64);
+                collector.write_all(sent).await.expect("write");
+                assert!(authenticate(agent, "s3cret").await.is_err());
+            }
+        });
+    }
+}
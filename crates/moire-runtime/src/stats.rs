@@ -0,0 +1,57 @@
+// r[impl model.stat-history]
+//! Short histories of channel depth and semaphore permits.
+//!
+//! A snapshot shows where a queue stands, not where it is heading. Processes
+//! configured with `MOIRE_STAT_HISTORY` keep the last few samples of each
+//! channel's and semaphore's main stat in its entity, so a queue that only
+//! ever grows shows up as such.
+use moire_types::{PTime, StatSample};
+
+use super::config::stat_history;
+
+/// Samples closer together than this are folded into one.
+const SAMPLE_PERIOD_MS: u64 = 1_000;
+
+/// Records `value` as the latest sample of `history`, if the process keeps
+/// stat history.
+pub fn record_stat_sample(history: &mut Vec<StatSample>, value: u32) {
+    let kept = stat_history();
+    if kept == 0 {
+        history.clear();
+        return;
+    }
+    push_sample(history, value, PTime::now(), kept);
+}
+
+/// A value within a sample period of the last sample replaces its value, so
+/// each sample holds where the stat ended its period.
+fn push_sample(history: &mut Vec<StatSample>, value: u32, at: PTime, kept: usize) {
+    match history.last_mut() {
+        Some(last) if at.as_millis().saturating_sub(last.at.as_millis()) < SAMPLE_PERIOD_MS => {
+            last.value = value;
+        }
+        _ => history.push(StatSample { at, value }),
+    }
+    if history.len() > kept {
+        history.drain(..history.len() - kept);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // r[verify model.stat-history]
+    #[test]
+    fn stat_history_keeps_one_sample_per_period_and_drops_the_oldest() {
+        let mut history = Vec::new();
+        for (at_ms, value) in [(0, 1), (400, 2), (1_000, 3), (2_500, 4), (3_600, 5)] {
+            push_sample(&mut history, value, PTime::from_millis(at_ms), 3);
+        }
+        let samples = history
+            .iter()
+            .map(|sample| (sample.at.as_millis(), sample.value))
+            .collect::<Vec<_>>();
+        assert_eq!(samples, vec![(1_000, 3), (2_500, 4), (3_600, 5)]);
+    }
+}
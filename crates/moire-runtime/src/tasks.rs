@@ -0,0 +1,113 @@
+//! Who the current task is: its Tokio task key, a key for plain threads, and
+//! an id that stays the same for the same spawn site across restarts.
+use moire_types::{ScopeBody, TaskScopeBody};
+use std::panic::Location;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use super::ScopeHandle;
+use super::db;
+
+pub fn current_tokio_task_key() -> Option<String> {
+    tokio::task::try_id().map(|id| id.to_string())
+}
+
+/// [`current_tokio_task_key`], or outside any Tokio task a key for the current
+/// thread: `main` for the main thread, `thread-<n>` or `thread-<n>-<name>` for
+/// others. Plain threads blocking on sync locks then show up as separate
+/// waiters and holders instead of all being one.
+pub(crate) fn current_task_or_thread_key() -> String {
+    static NEXT_THREAD: AtomicU64 = AtomicU64::new(1);
+    thread_local! {
+        static THREAD_KEY: String = {
+            let thread = std::thread::current();
+            match thread.name() {
+                Some("main") => String::from("main"),
+                Some(name) => format!(
+                    "thread-{}-{name}",
+                    NEXT_THREAD.fetch_add(1, Ordering::Relaxed)
+                ),
+                None => format!("thread-{}", NEXT_THREAD.fetch_add(1, Ordering::Relaxed)),
+            }
+        };
+    }
+    current_tokio_task_key().unwrap_or_else(|| THREAD_KEY.with(String::clone))
+}
+
+pub struct TaskScopeRegistration {
+    task_key: String,
+    scope: ScopeHandle,
+}
+
+impl Drop for TaskScopeRegistration {
+    fn drop(&mut self) {
+        db::lock_runtime_db().unregister_task_scope_id(&self.task_key, self.scope.id());
+    }
+}
+
+// r[impl model.task.logical-id]
+/// Identity of a task that survives process restarts: a hash of its name and
+/// the source location it was spawned from. Unlike Tokio task ids, the same
+/// spawn site in the same code yields the same value in every process
+/// generation.
+pub fn logical_task_id(name: &str, spawned_at: &Location<'_>) -> String {
+    // FNV-1a, so the value does not depend on the std hasher of the build.
+    const OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+    const PRIME: u64 = 0x0000_0100_0000_01b3;
+    let mut hash = OFFSET_BASIS;
+    let mut feed = |bytes: &[u8]| {
+        for byte in bytes {
+            hash ^= u64::from(*byte);
+            hash = hash.wrapping_mul(PRIME);
+        }
+    };
+    feed(name.as_bytes());
+    feed(&[0]);
+    feed(spawned_at.file().as_bytes());
+    feed(&[0]);
+    feed(&spawned_at.line().to_le_bytes());
+    feed(&spawned_at.column().to_le_bytes());
+    format!("{hash:016x}")
+}
+
+/// Registers a scope for the current Tokio task. `logical_id` is the task's
+/// [`logical_task_id`], when the spawn site is known.
+pub fn register_current_task_scope(
+    task_name: &str,
+    logical_id: Option<String>,
+) -> Option<TaskScopeRegistration> {
+    let task_key = current_tokio_task_key()?;
+    let scope = ScopeHandle::new(
+        format!("task.{task_name}#{task_key}"),
+        ScopeBody::Task(TaskScopeBody {
+            task_key: task_key.clone(),
+            logical_id,
+        }),
+    );
+    db::lock_runtime_db().register_task_scope_id(&task_key, scope.id());
+    Some(TaskScopeRegistration { task_key, scope })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // r[verify model.task.logical-id]
+    #[test]
+    fn logical_task_id_depends_on_name_and_callsite_only() {
+        fn here() -> &'static Location<'static> {
+            Location::caller()
+        }
+        #[track_caller]
+        fn caller() -> &'static Location<'static> {
+            Location::caller()
+        }
+
+        let site = caller();
+        let same_site = logical_task_id("task.spawn", site);
+        assert_eq!(same_site, logical_task_id("task.spawn", site));
+        assert_eq!(same_site.len(), 16);
+        assert_ne!(same_site, logical_task_id("joinset.task", site));
+        assert_ne!(same_site, logical_task_id("task.spawn", caller()));
+        assert_ne!(same_site, logical_task_id("task.spawn", here()));
+    }
+}
@@ -0,0 +1,131 @@
+// r[impl config.watchdog]
+//! Dumps the process's state when it stops making progress.
+//!
+//! Most hangs happen when nobody is around to ask for a snapshot. A process
+//! started with `MOIRE_WATCHDOG=<seconds>` watches a progress counter from a
+//! plain thread, which keeps running when every runtime worker is stuck. When
+//! the counter has not moved for that long, it writes the snapshot and the
+//! process's wait cycles to `MOIRE_WATCHDOG_DIR` (the temp dir by default)
+//! and reports the files on stderr, once per stall.
+//!
+//! Progress is counted in `moire::liveness::heartbeat` beats once the process
+//! has beaten at all, and in instrumented futures completing until then.
+use std::sync::atomic::{AtomicU64, Ordering};
+
+static HEARTBEATS: AtomicU64 = AtomicU64::new(0);
+static COMPLETIONS: AtomicU64 = AtomicU64::new(0);
+
+/// Counts a `moire::liveness::heartbeat` beat as progress.
+pub fn note_heartbeat() {
+    HEARTBEATS.fetch_add(1, Ordering::Relaxed);
+}
+
+pub(crate) fn note_completion() {
+    COMPLETIONS.fetch_add(1, Ordering::Relaxed);
+}
+
+fn progress() -> u64 {
+    match HEARTBEATS.load(Ordering::Relaxed) {
+        0 => COMPLETIONS.load(Ordering::Relaxed),
+        beats => beats,
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+pub(super) fn init_watchdog() {
+    use super::config::{env_parse, positive_secs};
+    use std::path::PathBuf;
+    use std::sync::OnceLock;
+    use std::time::Instant;
+
+    static STARTED: OnceLock<()> = OnceLock::new();
+    if STARTED.set(()).is_err() {
+        return;
+    }
+
+    let Some(stall) = env_parse(
+        "MOIRE_WATCHDOG",
+        "a positive number of seconds",
+        positive_secs,
+    ) else {
+        return;
+    };
+    let dir = std::env::var_os("MOIRE_WATCHDOG_DIR")
+        .filter(|dir| !dir.is_empty())
+        .map_or_else(std::env::temp_dir, PathBuf::from);
+
+    let spawned = std::thread::Builder::new()
+        .name(String::from("moire-watchdog"))
+        .spawn(move || {
+            let check_every = stall / 4;
+            let mut last_progress = progress();
+            let mut last_change = Instant::now();
+            let mut dumped = false;
+            loop {
+                std::thread::sleep(check_every);
+                let now = progress();
+                if now != last_progress {
+                    last_progress = now;
+                    last_change = Instant::now();
+                    dumped = false;
+                    continue;
+                }
+                let stalled_for = last_change.elapsed();
+                if dumped || stalled_for < stall || !super::is_enabled() {
+                    continue;
+                }
+                dumped = true;
+                match dump(&dir, stalled_for) {
+                    Ok(report) => eprintln!("moire: watchdog: {report}"),
+                    Err(e) => eprintln!("moire: watchdog dump failed: {e}"),
+                }
+            }
+        });
+    if let Err(e) = spawned {
+        eprintln!("moire: cannot start MOIRE_WATCHDOG thread: {e}");
+    }
+}
+
+/// Writes `moire-watchdog-<pid>-<unix ms>.snapshot.json` and
+/// `.candidates.json` into `dir` and describes them in one line.
+#[cfg(not(target_arch = "wasm32"))]
+fn dump(dir: &std::path::Path, stalled_for: std::time::Duration) -> Result<String, String> {
+    use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+    /// Budget for each of the snapshot and the wait graph, so a dump never
+    /// waits forever behind instrumentation that holds the runtime state.
+    const DUMP_BUDGET: Duration = Duration::from_secs(1);
+
+    let unix_ms = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |since| since.as_millis());
+    let stem = dir.join(format!("moire-watchdog-{}-{unix_ms}", std::process::id()));
+
+    let snapshot_path = stem.with_extension("snapshot.json");
+    let snapshot = super::db::encode_snapshot_reply_json(Instant::now() + DUMP_BUDGET)
+        .map_err(|e| format!("encode snapshot: {e}"))?;
+    std::fs::write(&snapshot_path, snapshot)
+        .map_err(|e| format!("write {}: {e}", snapshot_path.display()))?;
+
+    let mut report = format!(
+        "no progress for {:.1}s, wrote {}",
+        stalled_for.as_secs_f64(),
+        snapshot_path.display()
+    );
+    match super::graph::deadlock_candidates(Instant::now() + DUMP_BUDGET) {
+        Ok(candidates) => {
+            let candidates_path = stem.with_extension("candidates.json");
+            let json = facet_json::to_vec(&candidates)
+                .map_err(|e| format!("encode deadlock candidates: {e}"))?;
+            std::fs::write(&candidates_path, json)
+                .map_err(|e| format!("write {}: {e}", candidates_path.display()))?;
+            report.push_str(&format!(
+                " and {} ({} wait cycle(s))",
+                candidates_path.display(),
+                candidates.candidates.len()
+            ));
+        }
+        Err(e) => report.push_str(&format!("; no deadlock candidates: {e}")),
+    }
+    Ok(report)
+}
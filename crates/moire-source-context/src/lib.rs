@@ -98,6 +98,158 @@ pub fn cut_source_compact(
     )
 }
 
+/// Lines kept on each side of the target by the plain-window fallback.
+pub const FALLBACK_WINDOW_RADIUS: u32 = 3;
+
+/// [`cut_source`], falling back to [`window_source`] when no scope can be
+/// resolved.
+///
+/// Backtrace lines sometimes point into macro-generated code, or into files
+/// tree-sitter cannot parse for the given language; the fallback still gives
+/// every frame a ±[`FALLBACK_WINDOW_RADIUS`]-line excerpt.
+pub fn cut_source_or_window(
+    content: &str,
+    lang_name: &str,
+    target_line: u32,
+    target_col: Option<u32>,
+) -> Option<CutResult> {
+    cut_source(content, lang_name, target_line, target_col)
+        .or_else(|| window_source(content, target_line, FALLBACK_WINDOW_RADIUS))
+}
+
+/// [`cut_source_compact`], falling back to [`window_source`] when no scope can
+/// be resolved.
+pub fn cut_source_compact_or_window(
+    content: &str,
+    lang_name: &str,
+    target_line: u32,
+    target_col: Option<u32>,
+) -> Option<CutResult> {
+    cut_source_compact(content, lang_name, target_line, target_col)
+        .or_else(|| window_source(content, target_line, FALLBACK_WINDOW_RADIUS))
+}
+
+/// [`cut_source_or_window`] addressed by byte offset into `content` instead of
+/// line/column.
+pub fn cut_source_at_byte_offset(
+    content: &str,
+    lang_name: &str,
+    byte_offset: usize,
+) -> Option<CutResult> {
+    let (target_line, target_col) = line_col_for_byte_offset(content, byte_offset)?;
+    cut_source_or_window(content, lang_name, target_line, Some(target_col))
+}
+
+/// Plain window of `radius` lines on each side of `target_line`, without any
+/// syntax analysis. Returns `None` only when `target_line` is outside the file.
+pub fn window_source(content: &str, target_line: u32, radius: u32) -> Option<CutResult> {
+    let source_lines: Vec<&str> = content.lines().collect();
+    let scope_cut = window_scope_cut(source_lines.len(), target_line, radius)?;
+    Some(render_scope_cut(&source_lines, &scope_cut))
+}
+
+fn window_scope_cut(line_count: usize, target_line: u32, radius: u32) -> Option<ScopeCut> {
+    let row = (target_line as usize).checked_sub(1)?;
+    if row >= line_count {
+        return None;
+    }
+    Some(ScopeCut {
+        start_row: row.saturating_sub(radius as usize),
+        end_row: (row + radius as usize).min(line_count - 1),
+        cut_ranges: Vec::new(),
+    })
+}
+
+/// Result of [`cut_source_multi`]: one excerpt covering several targets.
+pub struct MultiCutResult {
+    pub cut: CutResult,
+    /// 1-based lines of the targets that were resolved, sorted and deduplicated.
+    pub target_lines: Vec<u32>,
+}
+
+/// Context for several targets in the same file, as one excerpt.
+///
+/// Each `(line, col)` target is cut as by [`cut_source_or_window`]. The
+/// excerpts are then merged: a line is kept when any excerpt covering it keeps
+/// it, and lines between disjoint excerpts are cut. Useful when several frames
+/// of one wait chain land in the same file. Returns `None` when no target
+/// resolves.
+pub fn cut_source_multi(
+    content: &str,
+    lang_name: &str,
+    targets: &[(u32, Option<u32>)],
+) -> Option<MultiCutResult> {
+    let source_lines: Vec<&str> = content.lines().collect();
+
+    let mut cuts = Vec::with_capacity(targets.len());
+    let mut target_lines = Vec::with_capacity(targets.len());
+    for &(target_line, target_col) in targets {
+        let cut = resolve_scope_cut(content, lang_name, target_line, target_col, NEIGHBOR_COUNT)
+            .or_else(|| window_scope_cut(source_lines.len(), target_line, FALLBACK_WINDOW_RADIUS));
+        if let Some(cut) = cut {
+            cuts.push(cut);
+            target_lines.push(target_line);
+        }
+    }
+    target_lines.sort_unstable();
+    target_lines.dedup();
+
+    let start_row = cuts.iter().map(|cut| cut.start_row).min()?;
+    let end_row = cuts.iter().map(|cut| cut.end_row).max()?;
+
+    let mut cut_ranges = Vec::new();
+    let mut run_start: Option<usize> = None;
+    for row in start_row..=end_row {
+        let kept = cuts.iter().any(|cut| {
+            row >= cut.start_row
+                && row <= cut.end_row
+                && !cut.cut_ranges.iter().any(|&(s, e)| row >= s && row <= e)
+        });
+        match (kept, run_start) {
+            (false, None) => run_start = Some(row),
+            (true, Some(start)) => {
+                cut_ranges.push((start, row - 1));
+                run_start = None;
+            }
+            _ => {}
+        }
+    }
+    if let Some(start) = run_start {
+        cut_ranges.push((start, end_row));
+    }
+
+    let merged = ScopeCut {
+        start_row,
+        end_row,
+        cut_ranges,
+    };
+    Some(MultiCutResult {
+        cut: render_scope_cut(&source_lines, &merged),
+        target_lines,
+    })
+}
+
+/// Converts a byte offset into a 1-based line and a 0-based byte column, the
+/// coordinates the rest of this crate takes. Returns `None` past the end of
+/// `content`.
+pub fn line_col_for_byte_offset(content: &str, byte_offset: usize) -> Option<(u32, u32)> {
+    let before = content.as_bytes().get(..byte_offset)?;
+    let line = before.iter().filter(|&&b| b == b'\n').count() as u32 + 1;
+    let line_start = before
+        .iter()
+        .rposition(|&b| b == b'\n')
+        .map_or(0, |idx| idx + 1);
+    Some((line, (byte_offset - line_start) as u32))
+}
+
+/// One resolved scope excerpt: 0-based inclusive rows, plus the row ranges
+/// inside it that render as `/* ... */`.
+struct ScopeCut {
+    start_row: usize,
+    end_row: usize,
+    cut_ranges: Vec<(usize, usize)>,
+}
+
 fn cut_source_with_neighbor_count(
     content: &str,
     lang_name: &str,
@@ -105,6 +257,18 @@ fn cut_source_with_neighbor_count(
     target_col: Option<u32>,
     neighbor_count: usize,
 ) -> Option<CutResult> {
+    let scope_cut = resolve_scope_cut(content, lang_name, target_line, target_col, neighbor_count)?;
+    let source_lines: Vec<&str> = content.lines().collect();
+    Some(render_scope_cut(&source_lines, &scope_cut))
+}
+
+fn resolve_scope_cut(
+    content: &str,
+    lang_name: &str,
+    target_line: u32,
+    target_col: Option<u32>,
+    neighbor_count: usize,
+) -> Option<ScopeCut> {
     let ts_lang = arborium::get_language(lang_name)?;
     let mut parser = tree_sitter::Parser::new();
     parser.set_language(&ts_lang).ok()?;
@@ -122,8 +286,6 @@ fn cut_source_with_neighbor_count(
     // Walk up to nearest scope node
     let scope = find_scope(node, point)?;
 
-    let source_lines: Vec<&str> = content.lines().collect();
-
     // Find the body child
     let body_kind = body_kind_for_scope(scope.kind());
     let is_source_file = scope.kind() == "source_file";
@@ -156,15 +318,10 @@ fn cut_source_with_neighbor_count(
     // Skip in compact mode: collect_compact_block_elision_ranges may still
     // need to elide block interiors within the single kept child.
     if body_children.len() <= (neighbor_count * 2 + 1) && neighbor_count != COMPACT_NEIGHBOR_COUNT {
-        let scope_start = scope_start_row as u32 + 1;
-        let scope_end = scope_end_row as u32 + 1;
-        let cut = source_lines[(scope_start - 1) as usize..scope_end as usize].join("\n");
-        return Some(CutResult {
-            cut_source: cut,
-            scope_range: LineRange {
-                start: scope_start,
-                end: scope_end,
-            },
+        return Some(ScopeCut {
+            start_row: scope_start_row,
+            end_row: scope_end_row,
+            cut_ranges: Vec::new(),
         });
     }
 
@@ -201,9 +358,6 @@ fn cut_source_with_neighbor_count(
         scope_end_row = body_children[keep_end - 1].end_position().row;
     }
 
-    // Build cut_source by processing lines
-    let mut result_lines: Vec<String> = Vec::with_capacity(scope_end_row - scope_start_row + 1);
-
     // Determine which line ranges to cut (0-based rows)
     let mut cut_ranges: Vec<(usize, usize)> = Vec::new(); // inclusive start/end rows
 
@@ -247,10 +401,22 @@ fn cut_source_with_neighbor_count(
         }
     }
 
-    cut_ranges = merge_line_ranges(cut_ranges);
+    Some(ScopeCut {
+        start_row: scope_start_row,
+        end_row: scope_end_row,
+        cut_ranges: merge_line_ranges(cut_ranges),
+    })
+}
+
+/// Build `cut_source` for a resolved excerpt, replacing the first line of each
+/// cut range with `/* ... */` and blanking the rest so line numbers stay stable.
+fn render_scope_cut(source_lines: &[&str], scope_cut: &ScopeCut) -> CutResult {
+    let (start_row, end_row) = (scope_cut.start_row, scope_cut.end_row);
+    let mut result_lines: Vec<String> = Vec::with_capacity(end_row - start_row + 1);
 
-    for row_idx in scope_start_row..=scope_end_row {
-        let in_cut = cut_ranges
+    for row_idx in start_row..=end_row {
+        let in_cut = scope_cut
+            .cut_ranges
             .iter()
             .find(|(s, e)| row_idx >= *s && row_idx <= *e);
         if let Some(&(cut_start, _cut_end)) = in_cut {
@@ -275,13 +441,13 @@ fn cut_source_with_neighbor_count(
         }
     }
 
-    Some(CutResult {
+    CutResult {
         cut_source: result_lines.join("\n"),
         scope_range: LineRange {
-            start: scope_start_row as u32 + 1,
-            end: scope_end_row as u32 + 1,
+            start: start_row as u32 + 1,
+            end: end_row as u32 + 1,
         },
-    })
+    }
 }
 
 fn collect_compact_block_elision_ranges(
@@ -540,6 +706,20 @@ pub fn extract_target_statement(
     Some(snippet)
 }
 
+/// Length budget for the signature part of [`extract_enclosing_fn_with_budget`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SignatureBudget {
+    /// Maximum length, in chars, of `name<generics>(params) -> ret where …`.
+    /// Modifiers and module/impl qualifiers are not counted.
+    pub max_len: usize,
+}
+
+impl Default for SignatureBudget {
+    fn default() -> Self {
+        Self { max_len: 80 }
+    }
+}
+
 /// Extract the collapsed enclosing-function context for compact display.
 ///
 /// Walks up the syntax tree from the target location to find the nearest
@@ -548,7 +728,7 @@ pub fn extract_target_statement(
 /// Returns a compact single-line signature context that includes:
 /// - enclosing module path (if any)
 /// - enclosing impl type (if any)
-/// - full function signature (with parameter + return types)
+/// - function signature, compacted to the default [`SignatureBudget`]
 ///
 /// Currently only implemented for Rust; returns `None` for other languages.
 pub fn extract_enclosing_fn(
@@ -556,6 +736,28 @@ pub fn extract_enclosing_fn(
     lang_name: &str,
     target_line: u32,
     target_col: Option<u32>,
+) -> Option<String> {
+    extract_enclosing_fn_with_budget(
+        content,
+        lang_name,
+        target_line,
+        target_col,
+        SignatureBudget::default(),
+    )
+}
+
+/// [`extract_enclosing_fn`] with an explicit signature budget.
+///
+/// Where-clauses are always elided to `where …`. When the signature does not
+/// fit, compaction drops generic bounds first, then parameter types, then
+/// trailing parameters (replaced by `…`). The receiver, fn name and return
+/// type are always kept, so the header stays one stable line.
+pub fn extract_enclosing_fn_with_budget(
+    content: &str,
+    lang_name: &str,
+    target_line: u32,
+    target_col: Option<u32>,
+    budget: SignatureBudget,
 ) -> Option<String> {
     if lang_name != "rust" {
         return None;
@@ -591,7 +793,8 @@ pub fn extract_enclosing_fn(
         }
     }?;
 
-    let (modifiers, sig_without_modifiers) = extract_function_signature_text(content, &fn_node)?;
+    let (modifiers, sig_without_modifiers) =
+        extract_function_signature_text(content, &fn_node, budget)?;
     let mut qualifiers = collect_module_qualifiers(&fn_node, bytes);
     if let Some(impl_type) = find_enclosing_impl_type_name(&fn_node, bytes) {
         qualifiers.push(impl_type);
@@ -620,6 +823,7 @@ fn collapse_ws_inline(text: &str) -> String {
 fn extract_function_signature_text(
     content: &str,
     fn_node: &tree_sitter::Node<'_>,
+    budget: SignatureBudget,
 ) -> Option<(String, String)> {
     let bytes = content.as_bytes();
 
@@ -640,9 +844,29 @@ fn extract_function_signature_text(
 
     let name = fn_node.child_by_field_name("name")?.utf8_text(bytes).ok()?;
 
+    // Generics in two forms: full (with bounds) and slim (parameter names only).
+    let mut generics_full = String::new();
+    let mut generics_slim = String::new();
+    if let Some(type_params) = fn_node.child_by_field_name("type_parameters") {
+        generics_full = collapse_ws_inline(type_params.utf8_text(bytes).ok()?);
+        let mut names: Vec<String> = Vec::new();
+        for i in 0..type_params.named_child_count() {
+            let child = type_params.named_child(i)?;
+            if child.kind() == "attribute_item" {
+                continue;
+            }
+            let text = collapse_ws_inline(child.utf8_text(bytes).ok()?);
+            let text = text.strip_prefix("const ").unwrap_or(&text);
+            let end = text.find([':', '=']).unwrap_or(text.len());
+            names.push(text[..end].trim().to_string());
+        }
+        generics_slim = format!("<{}>", names.join(", "));
+    }
+
     let params_node = fn_node.child_by_field_name("parameters")?;
 
     // Collect params in two forms: full (name: type) and slim (name only).
+    let mut receiver: Option<String> = None;
     let mut params_full: Vec<String> = Vec::new();
     let mut params_slim: Vec<String> = Vec::new();
     for i in 0..params_node.child_count() {
@@ -656,9 +880,7 @@ fn extract_function_signature_text(
                 }
             }
             "self_parameter" | "shorthand_self" => {
-                let self_text = collapse_ws_inline(child.utf8_text(bytes).ok()?);
-                params_full.push(self_text.clone());
-                params_slim.push(self_text);
+                receiver = Some(collapse_ws_inline(child.utf8_text(bytes).ok()?));
             }
             _ => {}
         }
@@ -671,19 +893,52 @@ fn extract_function_signature_text(
         .map(|t| format!(" -> {t}"))
         .unwrap_or_default();
 
-    // Use full params if short enough, otherwise fall back to names only.
-    // The length budget is checked against the full qualified form, but we only
-    // have the local sig here — use a generous threshold.
-    const MAX_LEN: usize = 80;
-    let params_str = params_full.join(", ");
-    let candidate = format!("{name}({params_str}){ret}");
-    let sig = if candidate.len() <= MAX_LEN {
-        candidate
+    // Where-clauses are never worth their width in a one-line header.
+    let mut cursor = fn_node.walk();
+    let where_suffix = if fn_node
+        .children(&mut cursor)
+        .any(|child| child.kind() == "where_clause")
+    {
+        " where …"
     } else {
-        format!("{name}({}){ret}", params_slim.join(", "))
+        ""
+    };
+
+    let render = |generics: &str, params: &[String]| {
+        let params = receiver.iter().chain(params).cloned().collect::<Vec<_>>();
+        format!("{name}{generics}({}){ret}{where_suffix}", params.join(", "))
+    };
+    let fits = |sig: &str| sig.chars().count() <= budget.max_len;
+
+    // Compaction ladder: full → generic names only → parameter names only →
+    // leading parameter names followed by `…`.
+    let full = render(&generics_full, &params_full);
+    if fits(&full) {
+        return Some((modifiers, full));
+    }
+    let slim_generics = render(&generics_slim, &params_full);
+    if fits(&slim_generics) {
+        return Some((modifiers, slim_generics));
+    }
+    let slim = render(&generics_slim, &params_slim);
+    if fits(&slim) {
+        return Some((modifiers, slim));
+    }
+    if params_slim.is_empty() {
+        return Some((modifiers, slim));
+    }
+    let mut kept = params_slim.len() - 1;
+    let truncated = loop {
+        let mut params = params_slim[..kept].to_vec();
+        params.push(String::from("…"));
+        let sig = render(&generics_slim, &params);
+        if kept == 0 || fits(&sig) {
+            break sig;
+        }
+        kept -= 1;
     };
 
-    Some((modifiers, sig))
+    Some((modifiers, truncated))
 }
 
 fn find_enclosing_impl_type_name(fn_node: &tree_sitter::Node<'_>, bytes: &[u8]) -> Option<String> {
@@ -81,6 +81,83 @@ datatest_mini::harness! {
     { test = run_fixture, root = "src/tests", pattern = r"^[^/]+\.(rs|js)$" },
 }
 
+#[test]
+fn enclosing_fn_compacts_generics_and_where_clause() {
+    let source = "fn merge<K: Ord + Clone + std::fmt::Debug, V, const N: usize>(
+    left: BTreeMap<K, V>,
+    right: BTreeMap<K, V>,
+) -> BTreeMap<K, V>
+where
+    V: Clone,
+{
+    left
+}";
+
+    assert_eq!(
+        extract_enclosing_fn(source, "rust", 8, None).as_deref(),
+        Some("merge<K, V, N>(left, right) -> BTreeMap<K, V> where …")
+    );
+    assert_eq!(
+        extract_enclosing_fn_with_budget(source, "rust", 8, None, SignatureBudget { max_len: 200 })
+            .as_deref(),
+        Some(
+            "merge<K: Ord + Clone + std::fmt::Debug, V, const N: usize>(left: BTreeMap<K, V>, right: BTreeMap<K, V>) -> BTreeMap<K, V> where …"
+        )
+    );
+    assert_eq!(
+        extract_enclosing_fn_with_budget(source, "rust", 8, None, SignatureBudget { max_len: 50 })
+            .as_deref(),
+        Some("merge<K, V, N>(left, …) -> BTreeMap<K, V> where …")
+    );
+}
+
+#[test]
+fn unresolvable_scope_falls_back_to_line_window() {
+    let source = (1..=20)
+        .map(|n| format!("line {n}"))
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    assert!(cut_source(&source, "no-such-language", 10, None).is_none());
+    let cut = cut_source_or_window(&source, "no-such-language", 10, None).unwrap();
+    assert_eq!(cut.scope_range.start, 7);
+    assert_eq!(cut.scope_range.end, 13);
+    assert!(cut.cut_source.starts_with("line 7\n"));
+
+    let edge = window_source(&source, 2, FALLBACK_WINDOW_RADIUS).unwrap();
+    assert_eq!((edge.scope_range.start, edge.scope_range.end), (1, 5));
+    assert!(window_source(&source, 21, FALLBACK_WINDOW_RADIUS).is_none());
+}
+
+#[test]
+fn multi_target_excerpts_merge_into_one() {
+    let source = "fn a() {
+    one();
+}
+
+fn b() {
+    two();
+}";
+
+    let multi = cut_source_multi(source, "rust", &[(6, None), (2, None), (2, None)]).unwrap();
+    assert_eq!(multi.target_lines, vec![2, 6]);
+    assert_eq!(multi.cut.scope_range.start, 2);
+    assert_eq!(multi.cut.scope_range.end, 6);
+    assert_eq!(
+        multi.cut.cut_source,
+        "    one();\n/* ... */\n\n\n    two();"
+    );
+}
+
+#[test]
+fn byte_offsets_map_to_line_and_column() {
+    let source = "fn main() {\n    let x = 1;\n}\n";
+    assert_eq!(line_col_for_byte_offset(source, 0), Some((1, 0)));
+    assert_eq!(line_col_for_byte_offset(source, 16), Some((2, 4)));
+    assert_eq!(line_col_for_byte_offset(source, source.len()), Some((4, 0)));
+    assert_eq!(line_col_for_byte_offset(source, source.len() + 1), None);
+}
+
 /// Dump tree-sitter node structure for a parsed statement. Call from any
 /// test when debugging — e.g. `dump_node_tree(src, "rust", 3, Some(0));`
 #[allow(dead_code)]
@@ -0,0 +1,362 @@
+//! Snapshot dumps built from a handful of calls.
+
+use moire_types::{
+    ActorScopeBody, BacktraceId, Edge, EdgeKind, EdgeReason, Entity, EntityBody, EntityId,
+    FutureEntity, Json, LockEntity, LockKind, MpscRxEntity, MpscSendWaiter, MpscTxEntity, PTime,
+    ProcessId, ProcessSnapshotView, RequestEntity, ResponseEntity, ResponseStatus, Scope,
+    ScopeBody, ScopeEntityLink, ScopeId, SemaphoreEntity, SemaphoreHolder, Snapshot,
+    SnapshotCutResponse,
+};
+
+/// Where every fixture process's clock stands unless set with
+/// [`ProcessBuilder::now`].
+const DEFAULT_NOW_MS: u64 = 60_000;
+
+/// Builds a [`SnapshotCutResponse`] out of processes assembled with
+/// [`ProcessBuilder`].
+pub struct DumpBuilder {
+    snapshot_id: i64,
+    processes: Vec<ProcessSnapshotView>,
+    backtrace: BacktraceId,
+}
+
+impl Default for DumpBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl DumpBuilder {
+    pub fn new() -> Self {
+        Self {
+            snapshot_id: 1,
+            processes: Vec::new(),
+            backtrace: BacktraceId::next().expect("backtrace id"),
+        }
+    }
+
+    pub fn snapshot_id(mut self, snapshot_id: i64) -> Self {
+        self.snapshot_id = snapshot_id;
+        self
+    }
+
+    /// Adds a process whose id and name are both `name`.
+    pub fn process(
+        mut self,
+        name: &str,
+        build: impl FnOnce(ProcessBuilder) -> ProcessBuilder,
+    ) -> Self {
+        let process = build(ProcessBuilder {
+            name: name.to_string(),
+            pid: self.processes.len() as u32 + 1,
+            now_ms: DEFAULT_NOW_MS,
+            entities: Vec::new(),
+            edges: Vec::new(),
+            scopes: Vec::new(),
+            scope_entity_links: Vec::new(),
+            backtrace: self.backtrace,
+        });
+        self.processes.push(ProcessSnapshotView {
+            process_id: ProcessId::new(process.name.as_str()),
+            process_name: process.name,
+            pid: process.pid,
+            ptime_now_ms: process.now_ms,
+            skew_ms: None,
+            snapshot: Snapshot {
+                entities: process.entities,
+                scopes: process.scopes,
+                edges: process.edges,
+                events: Vec::new(),
+                workers: Vec::new(),
+                long_polls: Vec::new(),
+                rpc_methods: Vec::new(),
+                coverage: None,
+                runtime_stats: None,
+            },
+            scope_entity_links: process.scope_entity_links,
+            timed_out_sections: None,
+            violations: Vec::new(),
+        });
+        self
+    }
+
+    pub fn build(self) -> SnapshotCutResponse {
+        SnapshotCutResponse {
+            snapshot_id: self.snapshot_id,
+            captured_at_unix_ms: 0,
+            max_skew_ms: None,
+            processes: self.processes,
+            timed_out_processes: Vec::new(),
+            backtraces: Vec::new(),
+            frames: Vec::new(),
+            findings: Vec::new(),
+            annotations: Vec::new(),
+            sizing_hints: Vec::new(),
+            detection_config_version: None,
+            analysis_error: None,
+        }
+    }
+}
+
+/// One process of a fixture. Entities are named after their ids and born at
+/// time zero; times are milliseconds on the process's clock, which stands at
+/// [`ProcessBuilder::now`].
+///
+/// Edges may name entities of other processes, as RPC responses pair with
+/// requests made elsewhere. Ids must then be unique across the dump.
+pub struct ProcessBuilder {
+    name: String,
+    pid: u32,
+    now_ms: u64,
+    entities: Vec<Entity>,
+    edges: Vec<Edge>,
+    scopes: Vec<Scope>,
+    scope_entity_links: Vec<ScopeEntityLink>,
+    backtrace: BacktraceId,
+}
+
+impl ProcessBuilder {
+    /// Sets the process's clock, 60 seconds by default.
+    pub fn now(mut self, now_ms: u64) -> Self {
+        self.now_ms = now_ms;
+        self
+    }
+
+    pub fn entity(mut self, id: &str, body: EntityBody) -> Self {
+        let mut entity = Entity::new_at(self.backtrace, id, body, PTime::from_millis(0));
+        entity.id = EntityId::new(id);
+        self.entities.push(entity);
+        self
+    }
+
+    /// Changes the body of entity `id`, added earlier.
+    pub fn update(mut self, id: &str, change: impl FnOnce(&mut EntityBody)) -> Self {
+        let entity = self.find_entity(id);
+        change(&mut entity.body);
+        self
+    }
+
+    /// Sets when entity `id`, added earlier, was born.
+    pub fn born_at(mut self, id: &str, birth_ms: u64) -> Self {
+        let entity = self.find_entity(id);
+        entity.birth = PTime::from_millis(birth_ms);
+        self
+    }
+
+    /// Marks entity `id`, added earlier, as removed at `removed_ms`.
+    pub fn removed_at(mut self, id: &str, removed_ms: u64) -> Self {
+        let entity = self.find_entity(id);
+        entity.removed_at = Some(PTime::from_millis(removed_ms));
+        self
+    }
+
+    /// Entity `id`, added earlier.
+    fn find_entity(&mut self, id: &str) -> &mut Entity {
+        self.entities
+            .iter_mut()
+            .find(|entity| entity.id.as_str() == id)
+            .unwrap_or_else(|| panic!("no entity {id} in process {}", self.name))
+    }
+
+    /// A spawned task.
+    pub fn task(self, id: &str) -> Self {
+        self.entity(
+            id,
+            EntityBody::Future(FutureEntity {
+                logical_id: Some(id.to_string()),
+                ..FutureEntity::default()
+            }),
+        )
+    }
+
+    /// An instrumented future that is not a task of its own.
+    pub fn future(self, id: &str) -> Self {
+        self.entity(id, EntityBody::Future(FutureEntity::default()))
+    }
+
+    pub fn lock(self, id: &str) -> Self {
+        self.entity(
+            id,
+            EntityBody::Lock(LockEntity {
+                kind: LockKind::Mutex,
+                holds: None,
+                rwlock: None,
+            }),
+        )
+    }
+
+    pub fn semaphore(self, id: &str, max_permits: u32) -> Self {
+        self.entity(
+            id,
+            EntityBody::Semaphore(SemaphoreEntity {
+                max_permits,
+                handed_out_permits: 0,
+                holds: None,
+                holders: Vec::new(),
+                history: Vec::new(),
+            }),
+        )
+    }
+
+    /// An mpsc channel: sender `id` holding `queue_len` messages, paired with
+    /// receiver `{id}:rx`. `capacity` is `None` for an unbounded channel.
+    pub fn channel(self, id: &str, capacity: Option<u32>, queue_len: u32) -> Self {
+        let rx = format!("{id}:rx");
+        self.entity(
+            id,
+            EntityBody::MpscTx(MpscTxEntity {
+                queue_len,
+                capacity,
+                send_waiters: Vec::new(),
+                history: Vec::new(),
+            }),
+        )
+        .entity(&rx, EntityBody::MpscRx(MpscRxEntity { receiver: None }))
+        .edge(id, &rx, EdgeKind::PairedWith, None)
+    }
+
+    /// Records `future` as the one receiving from channel `channel`.
+    pub fn receiver(self, channel: &str, future: &str) -> Self {
+        let future = EntityId::new(future);
+        self.update(&format!("{channel}:rx"), |body| match body {
+            EntityBody::MpscRx(rx) => rx.receiver = Some(future),
+            _ => panic!("{channel} is not a channel"),
+        })
+    }
+
+    /// `future` is blocked sending to the full channel `channel` since
+    /// `since_ms`.
+    pub fn blocked_sender(self, channel: &str, future: &str, since_ms: u64) -> Self {
+        let waiter = MpscSendWaiter {
+            future: EntityId::new(future),
+            since: PTime::from_millis(since_ms),
+        };
+        self.update(channel, |body| match body {
+            EntityBody::MpscTx(tx) => tx.send_waiters.push(waiter),
+            _ => panic!("{channel} is not a channel"),
+        })
+    }
+
+    /// Actor `name`, born at time zero: the task `driver` draining the
+    /// channel `mailbox`, both added earlier, linked to scope `actor.{name}`.
+    pub fn actor(mut self, name: &str, driver: &str, mailbox: &str) -> Self {
+        let mailbox_capacity = self
+            .entities
+            .iter()
+            .find_map(|entity| match &entity.body {
+                EntityBody::MpscTx(tx) if entity.id.as_str() == mailbox => Some(tx.capacity),
+                _ => None,
+            })
+            .unwrap_or_else(|| panic!("no channel {mailbox} in process {}", self.name));
+        let scope_id = format!("actor.{name}");
+        let mut scope = Scope::new(
+            self.backtrace,
+            scope_id.as_str(),
+            ScopeBody::Actor(ActorScopeBody { mailbox_capacity }),
+        );
+        scope.id = ScopeId::new(scope_id.as_str());
+        scope.birth = PTime::from_millis(0);
+        self.scopes.push(scope);
+        for member in [
+            driver.to_string(),
+            mailbox.to_string(),
+            format!("{mailbox}:rx"),
+        ] {
+            self.scope_entity_links.push(ScopeEntityLink {
+                scope_id: scope_id.clone(),
+                entity_id: member,
+            });
+        }
+        self
+    }
+
+    /// An outgoing RPC request to `method`, written `service.method`.
+    pub fn request(self, id: &str, method: &str) -> Self {
+        let (service_name, method_name) = split_method(method);
+        self.entity(
+            id,
+            EntityBody::Request(RequestEntity {
+                service_name,
+                method_name,
+                args_json: Json::new("[]"),
+                connection_generation: None,
+            }),
+        )
+    }
+
+    /// A pending response to `request`, which may have been made by another
+    /// process.
+    pub fn response(self, id: &str, method: &str, request: &str) -> Self {
+        let (service_name, method_name) = split_method(method);
+        self.entity(
+            id,
+            EntityBody::Response(ResponseEntity {
+                service_name,
+                method_name,
+                status: ResponseStatus::Pending,
+                connection_generation: None,
+            }),
+        )
+        .edge(id, request, EdgeKind::PairedWith, None)
+    }
+
+    /// `src` waits on `dst` since `since_ms`, for the reason `dst`'s kind
+    /// implies.
+    pub fn waits_on(self, src: &str, dst: &str, since_ms: u64) -> Self {
+        self.edge(src, dst, EdgeKind::WaitingOn, Some(since_ms))
+    }
+
+    /// `resource` is held by `holder` since `since_ms`. A semaphore also
+    /// lists the holder, with one permit.
+    pub fn held_by(self, resource: &str, holder: &str, since_ms: u64) -> Self {
+        let is_semaphore = self.entities.iter().any(|entity| {
+            entity.id.as_str() == resource && matches!(entity.body, EntityBody::Semaphore(_))
+        });
+        let this = self.edge(resource, holder, EdgeKind::HeldBy, Some(since_ms));
+        if !is_semaphore {
+            return this;
+        }
+        let holder = SemaphoreHolder {
+            holder: EntityId::new(holder),
+            permits: 1,
+            since: PTime::from_millis(since_ms),
+        };
+        this.update(resource, |body| {
+            if let EntityBody::Semaphore(semaphore) = body {
+                semaphore.handed_out_permits += 1;
+                semaphore.holders.push(holder);
+            }
+        })
+    }
+
+    /// `src` polls `dst`.
+    pub fn polls(self, src: &str, dst: &str) -> Self {
+        self.edge(src, dst, EdgeKind::Polls, None)
+    }
+
+    /// An edge of any kind. `waiting_on` edges get the reason `dst`'s kind
+    /// implies when `dst` is in this process.
+    pub fn edge(mut self, src: &str, dst: &str, kind: EdgeKind, since_ms: Option<u64>) -> Self {
+        let reason = self
+            .entities
+            .iter()
+            .find(|entity| entity.id.as_str() == dst)
+            .and_then(|dst| EdgeReason::infer(kind, &dst.body));
+        self.edges.push(Edge {
+            src: EntityId::new(src),
+            dst: EntityId::new(dst),
+            backtrace: self.backtrace,
+            kind,
+            since: since_ms.map(PTime::from_millis),
+            reason,
+        });
+        self
+    }
+}
+
+fn split_method(method: &str) -> (String, String) {
+    match method.split_once('.') {
+        Some((service, method)) => (service.to_string(), method.to_string()),
+        None => (String::new(), method.to_string()),
+    }
+}
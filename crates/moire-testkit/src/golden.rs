@@ -0,0 +1,97 @@
+//! Golden files: expected output checked in next to the tests that produce
+//! it.
+
+use std::fmt::Write as _;
+use std::path::Path;
+
+use moire_types::{AnalysisFinding, FindingSeverity, SnapshotCutResponse, snapshot_from_dump};
+
+/// Set to `1` to have [`assert_golden`] write the output it is given instead
+/// of comparing it.
+pub const BLESS_VAR: &str = "MOIRE_BLESS";
+
+/// Panics unless `actual` matches the contents of the file at `path`, naming
+/// the first line that differs. With `MOIRE_BLESS=1`, writes `actual` to
+/// `path` instead, creating missing directories. Relative paths are resolved
+/// from the working directory, which `cargo test` sets to the crate root.
+#[track_caller]
+pub fn assert_golden(path: impl AsRef<Path>, actual: &str) {
+    let path = path.as_ref();
+    if std::env::var(BLESS_VAR).is_ok_and(|value| value == "1") {
+        if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+            std::fs::create_dir_all(dir)
+                .unwrap_or_else(|e| panic!("create {}: {e}", dir.display()));
+        }
+        std::fs::write(path, actual).unwrap_or_else(|e| panic!("write {}: {e}", path.display()));
+        return;
+    }
+
+    let expected = match std::fs::read_to_string(path) {
+        Ok(expected) => expected,
+        Err(e) => panic!(
+            "read golden file {}: {e}\nrerun with {BLESS_VAR}=1 to create it",
+            path.display()
+        ),
+    };
+    if expected == actual {
+        return;
+    }
+    let mut expected_lines = expected.lines();
+    let mut actual_lines = actual.lines();
+    let mut line = 1;
+    loop {
+        match (expected_lines.next(), actual_lines.next()) {
+            (Some(e), Some(a)) if e == a => line += 1,
+            (None, None) => panic!(
+                "output differs from golden file {} only in line endings\nrerun with {BLESS_VAR}=1 to accept the new output",
+                path.display()
+            ),
+            (e, a) => panic!(
+                "output differs from golden file {} at line {line}\n  expected: {}\n  actual:   {}\nrerun with {BLESS_VAR}=1 to accept the new output",
+                path.display(),
+                e.unwrap_or("<end of file>"),
+                a.unwrap_or("<end of output>"),
+            ),
+        }
+    }
+}
+
+/// Findings as plain text, one block per finding in the order given:
+/// severity, analysis and title, then the rationale, subjects and hints.
+/// Scores are left out, so tuning them does not churn every golden file.
+pub fn render_findings(findings: &[AnalysisFinding]) -> String {
+    let mut out = String::new();
+    for finding in findings {
+        let severity = match finding.severity {
+            FindingSeverity::Info => "info",
+            FindingSeverity::Warning => "warning",
+            FindingSeverity::Critical => "critical",
+        };
+        let _ = writeln!(out, "{severity} {}: {}", finding.analysis, finding.title);
+        let _ = writeln!(out, "  {}", finding.rationale);
+        let subjects = finding
+            .subjects
+            .iter()
+            .map(|subject| {
+                format!(
+                    "{}::{}",
+                    subject.process_id.as_str(),
+                    subject.entity_id.as_str()
+                )
+            })
+            .collect::<Vec<_>>();
+        let _ = writeln!(out, "  subjects: {}", subjects.join(", "));
+        for hint in &finding.hints {
+            let _ = writeln!(out, "  hint: {hint}");
+        }
+    }
+    out
+}
+
+/// Reads a dump, JSON or MessagePack, such as `moire snapshot --out` writes,
+/// to replay it in a test.
+pub fn load_dump(path: impl AsRef<Path>) -> Result<SnapshotCutResponse, String> {
+    let path = path.as_ref();
+    let bytes = std::fs::read(path).map_err(|e| format!("read {}: {e}", path.display()))?;
+    snapshot_from_dump(&bytes).map_err(|e| format!("{}: {e}", path.display()))
+}
@@ -0,0 +1,93 @@
+// r[impl testkit]
+//! Fixtures and golden files for testing wait graph analyses.
+//!
+//! [`DumpBuilder`] assembles a snapshot dump entity by entity, with ids and
+//! times the test chooses, so the same fixture always yields the same graph.
+//! [`assert_golden`] compares rendered output (for example
+//! [`render_findings`] over the findings of an analysis run on the fixture)
+//! with a file checked in next to the test, and rewrites the file instead when
+//! `MOIRE_BLESS=1` is set:
+//!
+//! ```ignore
+//! let dump = DumpBuilder::new()
+//!     .process("app", |p| {
+//!         p.task("a")
+//!             .task("b")
+//!             .lock("l1")
+//!             .lock("l2")
+//!             .held_by("l1", "a", 1_000)
+//!             .held_by("l2", "b", 1_000)
+//!             .waits_on("a", "l2", 2_000)
+//!             .waits_on("b", "l1", 2_000)
+//!     })
+//!     .build();
+//! let findings = AnalysisRegistry::default().run_all(&dump).unwrap();
+//! assert_golden("tests/golden/lock_order.txt", &render_findings(&findings));
+//! ```
+
+mod dump;
+mod golden;
+
+pub use dump::{DumpBuilder, ProcessBuilder};
+pub use golden::{BLESS_VAR, assert_golden, load_dump, render_findings};
+
+#[cfg(test)]
+mod tests {
+    use moire_types::{EdgeKind, EdgeReason, EntityBody};
+
+    use super::*;
+
+    // r[verify testkit]
+    #[test]
+    fn builder_pairs_channels_and_infers_wait_reasons() {
+        let dump = DumpBuilder::new()
+            .process("app", |p| {
+                p.task("producer")
+                    .task("worker")
+                    .channel("jobs", Some(1), 1)
+                    .receiver("jobs", "worker")
+                    .blocked_sender("jobs", "producer", 1_000)
+                    .waits_on("producer", "jobs", 1_000)
+            })
+            .build();
+        let process = &dump.processes[0];
+        assert_eq!(process.process_id.as_str(), "app");
+        assert_eq!(process.snapshot.entities.len(), 4);
+        let kinds = process
+            .snapshot
+            .edges
+            .iter()
+            .map(|edge| (edge.src.as_str(), edge.dst.as_str(), edge.kind, edge.reason))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            kinds,
+            [
+                ("jobs", "jobs:rx", EdgeKind::PairedWith, None),
+                (
+                    "producer",
+                    "jobs",
+                    EdgeKind::WaitingOn,
+                    Some(EdgeReason::MpscFull)
+                ),
+            ]
+        );
+        let EntityBody::MpscTx(tx) = &process.snapshot.entities[2].body else {
+            panic!("jobs is not a channel sender");
+        };
+        assert_eq!(tx.send_waiters[0].future.as_str(), "producer");
+    }
+
+    #[test]
+    fn golden_files_compare_line_by_line() {
+        let dir = std::env::temp_dir().join(format!("moire-testkit-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("findings.txt");
+        std::fs::write(&path, "warning a: b\n  c\n").unwrap();
+        assert_golden(&path, "warning a: b\n  c\n");
+        let mismatch = std::panic::catch_unwind(|| assert_golden(&path, "warning a: b\n  d\n"));
+        std::fs::remove_dir_all(&dir).unwrap();
+        let message = mismatch.unwrap_err();
+        let message = message.downcast_ref::<String>().unwrap();
+        assert!(message.contains("at line 2"), "{message}");
+    }
+}
@@ -0,0 +1,140 @@
+//! RPC argument capture policies.
+//!
+//! What a policy records is a pure function of the arguments, so it behaves
+//! the same with and without the `diagnostics` feature; only whether a
+//! connection records anything differs.
+
+/// Bytes a [`ArgsCapture::Truncated`] capture keeps at least, whatever its
+/// limit, so the truncation marker always fits.
+const MIN_TRUNCATED_ARGS_BYTES: usize = 16;
+
+// r[impl api.rpc-args-capture]
+/// How much of a request's arguments a connection records.
+///
+/// Arguments can be large or sensitive; pick a policy per connection with
+/// [`RpcConnection::set_args_capture`](crate::rpc::RpcConnection::set_args_capture).
+/// Every policy leaves valid JSON behind, in both backends.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ArgsCapture {
+    /// Record the arguments as given.
+    #[default]
+    Full,
+    /// Record `[]` in place of the arguments.
+    Off,
+    /// Keep object keys and nesting, with every other value replaced by
+    /// `null`.
+    KeysOnly,
+    /// Arguments longer than `max_bytes` (at least 16) are replaced by a JSON
+    /// string holding their beginning, marked with a trailing `…`, that fits
+    /// in `max_bytes`.
+    Truncated { max_bytes: usize },
+}
+
+impl ArgsCapture {
+    /// What this policy records for `args_json`. Applying a policy to its
+    /// own output changes nothing.
+    pub fn apply(self, args_json: &str) -> String {
+        match self {
+            Self::Full => String::from(args_json),
+            Self::Off => String::from("[]"),
+            Self::KeysOnly => keys_only(args_json),
+            Self::Truncated { max_bytes } => truncated(args_json, max_bytes),
+        }
+    }
+}
+
+fn keys_only(args_json: &str) -> String {
+    let mut out = String::with_capacity(args_json.len());
+    let mut chars = args_json.char_indices().peekable();
+    while let Some((start, c)) = chars.next() {
+        match c {
+            '{' | '}' | '[' | ']' | ',' | ':' => out.push(c),
+            c if c.is_whitespace() => {}
+            '"' => {
+                let mut end = args_json.len();
+                let mut escaped = false;
+                for (i, c) in chars.by_ref() {
+                    if escaped {
+                        escaped = false;
+                    } else if c == '\\' {
+                        escaped = true;
+                    } else if c == '"' {
+                        end = i + 1;
+                        break;
+                    }
+                }
+                if args_json[end..].trim_start().starts_with(':') {
+                    out.push_str(&args_json[start..end]);
+                } else {
+                    out.push_str("null");
+                }
+            }
+            _ => {
+                while chars
+                    .peek()
+                    .is_some_and(|&(_, c)| !matches!(c, ',' | ']' | '}') && !c.is_whitespace())
+                {
+                    chars.next();
+                }
+                out.push_str("null");
+            }
+        }
+    }
+    out
+}
+
+fn truncated(args_json: &str, max_bytes: usize) -> String {
+    let max_bytes = max_bytes.max(MIN_TRUNCATED_ARGS_BYTES);
+    if args_json.len() <= max_bytes {
+        return String::from(args_json);
+    }
+    // Escaping can grow the kept prefix, so shrink it until the result fits.
+    let mut end = max_bytes;
+    loop {
+        while !args_json.is_char_boundary(end) {
+            end -= 1;
+        }
+        let out = facet_json::to_string(&format!("{}…", &args_json[..end]))
+            .expect("invariant violated: a string must serialize");
+        if out.len() <= max_bytes {
+            return out;
+        }
+        end -= (out.len() - max_bytes).min(end);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ArgsCapture;
+
+    // r[verify api.rpc-args-capture]
+    #[test]
+    fn every_policy_records_what_it_promises_and_is_idempotent() {
+        let args = r#"{"user": {"name": "ferris", "tags": ["a", "b"]}, "count": 3}"#;
+        let cases = [
+            (ArgsCapture::Full, String::from(args)),
+            (ArgsCapture::Off, String::from("[]")),
+            (
+                ArgsCapture::KeysOnly,
+                String::from(r#"{"user":{"name":null,"tags":[null,null]},"count":null}"#),
+            ),
+            (
+                ArgsCapture::Truncated { max_bytes: 24 },
+                String::from(r#""{\"user\": {\"nam…""#),
+            ),
+        ];
+        for (capture, expected) in cases {
+            let recorded = capture.apply(args);
+            assert_eq!(recorded, expected, "{capture:?}");
+            assert_eq!(capture.apply(&recorded), recorded, "{capture:?}");
+        }
+    }
+
+    #[test]
+    fn truncation_keeps_its_minimum_and_short_args() {
+        let capture = ArgsCapture::Truncated { max_bytes: 1 };
+        assert_eq!(capture.apply("[1,2]"), "[1,2]");
+        let long = format!("[{}]", "1,".repeat(32).trim_end_matches(','));
+        assert!(capture.apply(&long).len() <= 16);
+    }
+}
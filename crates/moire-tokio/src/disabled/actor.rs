@@ -0,0 +1,36 @@
+use std::future::Future;
+
+use crate::sync::mpsc::{self, Receiver, Sender, UnboundedReceiver, UnboundedSender};
+use crate::task::{JoinHandle, spawn};
+
+/// Pass-through equivalent of the enabled `spawn_actor`: creates the mailbox
+/// and spawns the driver task without registering an actor scope.
+pub fn spawn_actor<M, T, F, Fut>(
+    name: impl Into<String>,
+    mailbox_capacity: usize,
+    driver: F,
+) -> (Sender<M>, JoinHandle<T>)
+where
+    M: Send + 'static,
+    T: Send + 'static,
+    F: FnOnce(Receiver<M>) -> Fut,
+    Fut: Future<Output = T> + Send + 'static,
+{
+    let (tx, rx) = mpsc::channel(name, mailbox_capacity);
+    (tx, spawn(driver(rx)))
+}
+
+/// Pass-through equivalent of the enabled `spawn_unbounded_actor`.
+pub fn spawn_unbounded_actor<M, T, F, Fut>(
+    name: impl Into<String>,
+    driver: F,
+) -> (UnboundedSender<M>, JoinHandle<T>)
+where
+    M: Send + 'static,
+    T: Send + 'static,
+    F: FnOnce(UnboundedReceiver<M>) -> Fut,
+    Fut: Future<Output = T> + Send + 'static,
+{
+    let (tx, rx) = mpsc::unbounded_channel(name);
+    (tx, spawn(driver(rx)))
+}
@@ -0,0 +1,25 @@
+use std::fmt;
+use std::future::Future;
+use std::time::Duration;
+
+use super::time::{self, Interval};
+
+pub fn heartbeat(_name: impl Into<String>, _interval: Duration) {}
+
+pub struct Watchdog(Interval);
+
+impl Watchdog {
+    pub fn tick(&mut self) -> impl Future<Output = time::Instant> + '_ {
+        self.0.tick()
+    }
+}
+
+impl fmt::Debug for Watchdog {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+pub fn watchdog(_name: impl Into<String>, period: Duration) -> Watchdog {
+    Watchdog(time::interval(period))
+}
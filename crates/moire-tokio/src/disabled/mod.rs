@@ -1,7 +1,11 @@
 use ctor::ctor;
 use std::sync::Once;
+use std::time::Duration;
 
+pub mod actor;
 pub mod custom;
+pub mod liveness;
+pub mod net;
 pub mod process;
 pub mod rpc;
 pub mod sync;
@@ -10,6 +14,107 @@ pub mod time;
 
 pub use task::{spawn, spawn_blocking};
 
+/// Diagnostics are disabled: there is nothing to report.
+pub fn render_prometheus() -> String {
+    String::new()
+}
+
+/// No-op mirror of the enabled `BacktraceCapture`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum BacktraceCapture {
+    Never,
+    OnLongPoll,
+    OnSpawn,
+    Always,
+}
+
+/// No-op mirror of the enabled `RuntimeConfig`; nothing is collected, so
+/// applying it has no effect.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RuntimeConfig {
+    pub dead_entity_retention: Option<Duration>,
+    pub max_events: usize,
+    pub long_polls_kept: usize,
+    pub backtraces: BacktraceCapture,
+    pub wake_sample_every: u32,
+    pub wake_gap_threshold: Duration,
+    pub stat_history: usize,
+}
+
+impl Default for RuntimeConfig {
+    fn default() -> Self {
+        Self {
+            dead_entity_retention: None,
+            max_events: 0,
+            long_polls_kept: 0,
+            backtraces: BacktraceCapture::Never,
+            wake_sample_every: 1,
+            wake_gap_threshold: Duration::ZERO,
+            stat_history: 0,
+        }
+    }
+}
+
+impl RuntimeConfig {
+    pub fn from_env() -> Self {
+        Self::default()
+    }
+
+    pub fn apply(self) {}
+}
+
+pub fn runtime_config() -> RuntimeConfig {
+    RuntimeConfig::default()
+}
+
+/// Diagnostics are compiled out; collection cannot be switched on.
+pub fn is_enabled() -> bool {
+    false
+}
+
+pub fn enable() {}
+
+pub fn disable() {}
+
+/// No-op mirror of the enabled `Redactor`; nothing is reported, so there is
+/// nothing to scrub.
+pub trait Redactor: Send + Sync + 'static {
+    fn redact_entity(&self, entity: &mut moire_types::Entity);
+
+    fn redact_process(&self, args: &mut Vec<String>, env: &mut Vec<String>) {
+        let _ = (args, env);
+    }
+}
+
+impl<F> Redactor for F
+where
+    F: Fn(&mut moire_types::Entity) + Send + Sync + 'static,
+{
+    fn redact_entity(&self, entity: &mut moire_types::Entity) {
+        self(entity)
+    }
+}
+
+/// No-op mirror of the enabled `DropArgs`.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct DropArgs;
+
+impl Redactor for DropArgs {
+    fn redact_entity(&self, _entity: &mut moire_types::Entity) {}
+}
+
+/// No-op mirror of the enabled `HashStrings`.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct HashStrings;
+
+impl Redactor for HashStrings {
+    fn redact_entity(&self, _entity: &mut moire_types::Entity) {}
+}
+
+pub fn set_redactor(_redactor: impl Redactor) {}
+
+pub fn clear_redactor() {}
+
 static DASHBOARD_DISABLED_WARNING_ONCE: Once = Once::new();
 
 #[ctor]
@@ -0,0 +1 @@
+pub use tokio::net::{TcpListener, TcpStream, UdpSocket};
@@ -1,5 +1,7 @@
 use moire_types::{RequestEntity, ResponseEntity};
 
+pub use crate::args_capture::ArgsCapture;
+
 /// No-op RPC request handle for the disabled (no-instrumentation) backend.
 #[derive(Clone, Debug)]
 pub struct RpcRequestHandle {
@@ -34,6 +36,7 @@ pub fn rpc_request(method: impl Into<String>, args_json: impl Into<String>) -> R
             service_name,
             method_name,
             args_json: moire_types::Json::new(args_json),
+            connection_generation: None,
         },
     )
 }
@@ -53,6 +56,7 @@ pub fn rpc_response(method: impl Into<String>) -> RpcResponseHandle {
             service_name,
             method_name,
             status: moire_types::ResponseStatus::Pending,
+            connection_generation: None,
         },
     )
 }
@@ -79,6 +83,7 @@ pub fn rpc_response_for(
             service_name,
             method_name,
             status: moire_types::ResponseStatus::Pending,
+            connection_generation: None,
         },
     )
 }
@@ -91,6 +96,48 @@ pub fn rpc_response_for_with_body(
     RpcResponseHandle
 }
 
+/// No-op RPC connection for the disabled backend.
+#[derive(Clone, Debug)]
+pub struct RpcConnection;
+
+pub fn rpc_connection(
+    _name: impl Into<String>,
+    _local_addr: Option<String>,
+    _peer_addr: Option<String>,
+) -> RpcConnection {
+    RpcConnection
+}
+
+impl RpcConnection {
+    pub fn generation(&self) -> u64 {
+        0
+    }
+
+    pub fn opened(&self) -> u64 {
+        0
+    }
+
+    pub fn closed(&self, _reason: Option<String>) {}
+
+    pub fn args_capture(&self) -> ArgsCapture {
+        ArgsCapture::Full
+    }
+
+    pub fn set_args_capture(&self, _capture: ArgsCapture) {}
+
+    pub fn request(
+        &self,
+        method: impl Into<String>,
+        args_json: impl Into<String>,
+    ) -> RpcRequestHandle {
+        rpc_request(method, args_json)
+    }
+
+    pub fn bind_request(&self, _request: &RpcRequestHandle) {}
+
+    pub fn bind_response(&self, _response: &RpcResponseHandle) {}
+}
+
 fn split_method_parts(full_method: &str) -> (&str, &str) {
     if let Some((service, method)) = full_method.rsplit_once('.') {
         (service, method)
@@ -0,0 +1,24 @@
+use std::fmt;
+use std::sync::Arc;
+
+pub use tokio::sync::BarrierWaitResult;
+
+/// Pass-through `tokio::sync::Barrier` wrapper, accepting a name parameter for API parity.
+#[derive(Clone)]
+pub struct Barrier(Arc<tokio::sync::Barrier>);
+
+impl Barrier {
+    pub fn new(_name: impl Into<String>, n: usize) -> Self {
+        Self(Arc::new(tokio::sync::Barrier::new(n)))
+    }
+
+    pub async fn wait(&self) -> BarrierWaitResult {
+        self.0.wait().await
+    }
+}
+
+impl fmt::Debug for Barrier {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
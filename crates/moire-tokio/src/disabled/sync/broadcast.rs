@@ -1,4 +1,4 @@
-pub use tokio::sync::broadcast::{error, Receiver, Sender};
+pub use tokio::sync::broadcast::{Receiver, Sender, error};
 
 pub fn channel<T: Clone>(_name: impl Into<String>, capacity: usize) -> (Sender<T>, Receiver<T>) {
     tokio::sync::broadcast::channel(capacity)
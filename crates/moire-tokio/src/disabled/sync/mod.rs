@@ -3,9 +3,26 @@ pub mod mpsc;
 pub mod oneshot;
 pub mod watch;
 
+mod barrier;
+pub use barrier::*;
+
 mod mutex;
 pub use mutex::*;
 
+/// Pass-through equivalent of the enabled lock backtrace policy; has no effect.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LockBacktracePolicy {
+    Off,
+    OnContention,
+    Always,
+}
+
+pub fn lock_backtrace_policy() -> LockBacktracePolicy {
+    LockBacktracePolicy::Off
+}
+
+pub fn set_lock_backtrace_policy(_policy: LockBacktracePolicy) {}
+
 mod notify;
 pub use notify::*;
 
@@ -1,10 +1,11 @@
 use std::fmt;
 use std::ops::Deref;
+use std::sync::Arc;
 
 /// Pass-through `tokio::sync::Mutex` wrapper, accepting a name parameter for API parity.
-pub struct Mutex<T>(tokio::sync::Mutex<T>);
+pub struct Mutex<T>(Arc<tokio::sync::Mutex<T>>);
 
-pub use tokio::sync::MutexGuard;
+pub use tokio::sync::{MutexGuard, OwnedMutexGuard};
 
 /// Pass-through `parking_lot::Mutex` wrapper, accepting a name parameter for API parity.
 pub struct SyncMutex<T>(parking_lot::Mutex<T>);
@@ -13,7 +14,7 @@ pub use parking_lot::MutexGuard as SyncMutexGuard;
 
 impl<T> Mutex<T> {
     pub fn new(_name: &'static str, value: T) -> Self {
-        Self(tokio::sync::Mutex::new(value))
+        Self(Arc::new(tokio::sync::Mutex::new(value)))
     }
 
     pub async fn lock(&self) -> MutexGuard<'_, T> {
@@ -23,6 +24,16 @@ impl<T> Mutex<T> {
     pub fn try_lock(&self) -> Result<MutexGuard<'_, T>, tokio::sync::TryLockError> {
         self.0.try_lock()
     }
+
+    pub async fn lock_owned(self: Arc<Self>) -> OwnedMutexGuard<T> {
+        Arc::clone(&self.0).lock_owned().await
+    }
+
+    pub fn try_lock_owned(
+        self: Arc<Self>,
+    ) -> Result<OwnedMutexGuard<T>, tokio::sync::TryLockError> {
+        Arc::clone(&self.0).try_lock_owned()
+    }
 }
 
 impl<T> SyncMutex<T> {
@@ -1,5 +1,6 @@
 use std::fmt;
 use std::future::Future;
+use std::time::Duration;
 
 /// Pass-through `tokio::sync::OnceCell` wrapper, accepting a name parameter for API parity.
 pub struct OnceCell<T>(tokio::sync::OnceCell<T>);
@@ -25,6 +26,19 @@ impl<T> OnceCell<T> {
         self.0.get_or_init(f).await
     }
 
+    pub async fn get_or_init_timeout<'a, F, Fut>(
+        &'a self,
+        _caller: impl Into<String>,
+        _timeout: Duration,
+        f: F,
+    ) -> &'a T
+    where
+        F: FnOnce() -> Fut + 'a,
+        Fut: Future<Output = T> + 'a,
+    {
+        self.0.get_or_init(f).await
+    }
+
     pub async fn get_or_try_init<'a, F, Fut, E>(&'a self, f: F) -> Result<&'a T, E>
     where
         F: FnOnce() -> Fut + 'a,
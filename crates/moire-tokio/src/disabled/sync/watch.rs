@@ -1,4 +1,4 @@
-pub use tokio::sync::watch::{error, Receiver, Ref, Sender};
+pub use tokio::sync::watch::{Receiver, Ref, Sender, error};
 
 pub fn channel<T: Clone>(_name: impl Into<String>, initial: T) -> (Sender<T>, Receiver<T>) {
     tokio::sync::watch::channel(initial)
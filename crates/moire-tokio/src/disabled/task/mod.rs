@@ -1,16 +1,23 @@
 pub use join_handle::JoinHandle;
 pub use joinset::JoinSet;
+pub use task_scope::{TaskScope, scope};
 
 pub mod join_handle;
 pub mod joinset;
+pub mod task_scope;
 
 use std::future::IntoFuture;
+use std::time::Duration;
 
 /// No-op extension trait matching the enabled `FutureExt`.
 pub trait FutureExt: IntoFuture + Sized {
     fn named(self, _name: impl Into<String>) -> Self {
         self
     }
+
+    fn idle(self, _reason: impl Into<String>) -> Self {
+        self
+    }
 }
 
 impl<F: IntoFuture + Sized> FutureExt for F {}
@@ -32,3 +39,9 @@ where
 {
     JoinHandle(tokio::task::spawn_blocking(f))
 }
+
+pub fn long_poll_threshold() -> Duration {
+    Duration::ZERO
+}
+
+pub fn set_long_poll_threshold(_threshold: Duration) {}
@@ -0,0 +1,80 @@
+use std::fmt;
+use std::future::Future;
+use std::panic::resume_unwind;
+
+/// Pass-through equivalent of the enabled `scope`: runs `body`, then joins
+/// the tasks it left in the scope.
+pub async fn scope<T, R>(
+    name: impl Into<String>,
+    body: impl AsyncFnOnce(&mut TaskScope<T>) -> R,
+) -> R
+where
+    T: Send + 'static,
+{
+    let mut tasks = TaskScope::new(name);
+    let result = body(&mut tasks).await;
+    tasks.join_all().await;
+    result
+}
+
+/// Pass-through equivalent of the enabled `TaskScope`, backed by a
+/// [`tokio::task::JoinSet`] that aborts its tasks when dropped.
+pub struct TaskScope<T>(tokio::task::JoinSet<T>);
+
+impl<T> TaskScope<T>
+where
+    T: Send + 'static,
+{
+    /// Accepted for API compatibility with the enabled backend; name is ignored.
+    pub fn new(_name: impl Into<String>) -> Self {
+        Self(tokio::task::JoinSet::new())
+    }
+
+    pub fn spawn<F>(&mut self, future: F)
+    where
+        F: Future<Output = T> + Send + 'static,
+    {
+        self.0.spawn(future);
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn abort_all(&mut self) {
+        self.0.abort_all();
+    }
+
+    pub fn join_next(
+        &mut self,
+    ) -> impl Future<Output = Option<Result<T, tokio::task::JoinError>>> + '_ {
+        self.0.join_next()
+    }
+
+    pub async fn join_all(mut self) -> Vec<T> {
+        let mut outputs = Vec::with_capacity(self.len());
+        while let Some(joined) = self.join_next().await {
+            match joined {
+                Ok(output) => outputs.push(output),
+                Err(e) if e.is_panic() => resume_unwind(e.into_panic()),
+                Err(_) => {}
+            }
+        }
+        outputs
+    }
+}
+
+impl<T> fmt::Debug for TaskScope<T>
+where
+    T: Send + 'static,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TaskScope")
+            .field("len", &self.len())
+            .finish()
+    }
+}
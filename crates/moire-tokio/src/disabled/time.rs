@@ -8,6 +8,10 @@ pub fn sleep(duration: Duration) -> impl Future<Output = ()> {
     tokio::time::sleep(duration)
 }
 
+pub fn sleep_until(deadline: Instant) -> impl Future<Output = ()> {
+    tokio::time::sleep_until(deadline)
+}
+
 pub struct Interval(tokio::time::Interval);
 
 impl Interval {
@@ -0,0 +1,212 @@
+//! Actor integration: a named mailbox plus the driver task that drains it.
+//!
+//! Actor frameworks usually build each actor out of an `mpsc` mailbox and a
+//! task that loops over it. Instrumented separately, those show up as a
+//! disjoint task and channel pair. [`spawn_actor`] links both mailbox ends and
+//! the driver future to one `actor` scope, so `moire-web` can render the actor
+//! as a single composite node with its mailbox depth and processing state.
+//!
+//! ```ignore
+//! use moire::actor::spawn_actor;
+//!
+//! let (mailbox, driver) = spawn_actor("session_manager", 256, |mut rx| async move {
+//!     while let Some(msg) = rx.recv().await {
+//!         handle(msg).await;
+//!     }
+//! });
+//! ```
+
+// r[impl api.actor]
+
+use std::future::Future;
+
+use moire_runtime::ScopeHandle;
+use moire_types::{ActorScopeBody, ScopeBody};
+
+use crate::sync::mpsc::{self, Receiver, Sender, UnboundedReceiver, UnboundedSender};
+use crate::task::{JoinHandle, spawn};
+
+/// Spawns an actor: creates its mailbox, hands the receiver to `driver`, and
+/// spawns the returned future as the actor's driver task.
+///
+/// Returns the mailbox sender and the driver task's [`JoinHandle`]. The actor
+/// scope lives as long as the driver task.
+///
+/// # Panics
+///
+/// If `mailbox_capacity` is zero, as [`mpsc::channel`] does, or does not fit
+/// in the `u32` the actor scope records it as.
+pub fn spawn_actor<M, T, F, Fut>(
+    name: impl Into<String>,
+    mailbox_capacity: usize,
+    driver: F,
+) -> (Sender<M>, JoinHandle<T>)
+where
+    M: Send + 'static,
+    T: Send + 'static,
+    F: FnOnce(Receiver<M>) -> Fut,
+    Fut: Future<Output = T> + Send + 'static,
+{
+    let name = name.into();
+    let recorded_capacity = u32::try_from(mailbox_capacity).unwrap_or_else(|_| {
+        panic!("actor {name}: mailbox capacity {mailbox_capacity} does not fit in a u32")
+    });
+    let scope = actor_scope(&name, Some(recorded_capacity));
+
+    let (tx, rx) = mpsc::channel(format!("{name}.mailbox"), mailbox_capacity);
+    scope.link_entity(tx.handle());
+    scope.link_entity(rx.handle());
+
+    let join = spawn_driver(scope, name, driver(rx));
+    (tx, join)
+}
+
+/// Like [`spawn_actor`], with an unbounded mailbox. The actor scope records
+/// no mailbox capacity.
+pub fn spawn_unbounded_actor<M, T, F, Fut>(
+    name: impl Into<String>,
+    driver: F,
+) -> (UnboundedSender<M>, JoinHandle<T>)
+where
+    M: Send + 'static,
+    T: Send + 'static,
+    F: FnOnce(UnboundedReceiver<M>) -> Fut,
+    Fut: Future<Output = T> + Send + 'static,
+{
+    let name = name.into();
+    let scope = actor_scope(&name, None);
+
+    let (tx, rx) = mpsc::unbounded_channel(format!("{name}.mailbox"));
+    scope.link_entity(tx.handle());
+    scope.link_entity(rx.handle());
+
+    let join = spawn_driver(scope, name, driver(rx));
+    (tx, join)
+}
+
+fn actor_scope(name: &str, mailbox_capacity: Option<u32>) -> ScopeHandle {
+    ScopeHandle::new(
+        format!("actor.{name}"),
+        ScopeBody::Actor(ActorScopeBody { mailbox_capacity }),
+    )
+}
+
+/// Spawns `driver` as the actor's driver task, keeping `scope` alive for as
+/// long as it runs.
+fn spawn_driver<T, Fut>(scope: ScopeHandle, name: String, driver: Fut) -> JoinHandle<T>
+where
+    T: Send + 'static,
+    Fut: Future<Output = T> + Send + 'static,
+{
+    let actor_scope = scope.clone();
+    let join = spawn(async move {
+        let _actor_scope = actor_scope;
+        driver.await
+    })
+    .named(name);
+    scope.link_entity(join.entity_handle());
+    join
+}
+
+#[cfg(test)]
+mod tests {
+    use moire_runtime::{SnapshotSink, pull_changes_since, write_snapshot_to};
+    use moire_types::{Change, Edge, Entity, EntityId, Event, Scope, ScopeId, SeqNo};
+
+    use super::*;
+
+    /// Entity names and actor scopes (id, name, mailbox capacity).
+    #[derive(Default)]
+    struct Recorded {
+        entities: Vec<(EntityId, String)>,
+        actors: Vec<(ScopeId, String, Option<u32>)>,
+    }
+
+    impl SnapshotSink for Recorded {
+        fn entity(&mut self, entity: &Entity) {
+            self.entities.push((entity.id.clone(), entity.name.clone()));
+        }
+        fn scope(&mut self, scope: &Scope) {
+            if let ScopeBody::Actor(body) = &scope.body {
+                self.actors
+                    .push((scope.id.clone(), scope.name.clone(), body.mailbox_capacity));
+            }
+        }
+        fn edge(&mut self, _edge: &Edge) {}
+        fn event(&mut self, _event: &Event) {}
+    }
+
+    /// The recorded mailbox capacity and the names of the linked entities of
+    /// the actor scope called `scope_name`, if it is alive.
+    fn actor(scope_name: &str) -> Option<(Option<u32>, Vec<String>)> {
+        let mut recorded = Recorded::default();
+        write_snapshot_to(&mut recorded);
+        let (actor_id, _, capacity) = recorded
+            .actors
+            .iter()
+            .find(|(_, name, _)| name == scope_name)?;
+        let linked = pull_changes_since(SeqNo::ZERO, u32::MAX)
+            .changes
+            .into_iter()
+            .filter_map(|stamped| match stamped.change {
+                Change::UpsertEntityScopeLink {
+                    entity_id,
+                    scope_id,
+                } if &scope_id == actor_id => Some(entity_id),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        let mut members = recorded
+            .entities
+            .iter()
+            .filter(|(id, _)| linked.contains(id))
+            .map(|(_, name)| name.clone())
+            .collect::<Vec<_>>();
+        members.sort();
+        Some((*capacity, members))
+    }
+
+    #[tokio::test]
+    async fn actor_scope_links_mailbox_and_driver_while_it_runs() {
+        let (mailbox, driver) = spawn_actor("adder", 8, |mut rx: Receiver<u32>| async move {
+            let mut total = 0;
+            while let Some(n) = rx.recv().await {
+                total += n;
+            }
+            total
+        });
+        mailbox.send(2).await.expect("send");
+        mailbox.send(3).await.expect("send");
+
+        assert_eq!(
+            actor("actor.adder"),
+            Some((
+                Some(8),
+                vec![
+                    String::from("adder"),
+                    String::from("adder.mailbox:rx"),
+                    String::from("adder.mailbox:tx"),
+                ]
+            ))
+        );
+
+        drop(mailbox);
+        assert_eq!(driver.await.expect("driver"), 5);
+        assert_eq!(actor("actor.adder"), None);
+    }
+
+    #[tokio::test]
+    async fn unbounded_actor_records_no_capacity() {
+        let (mailbox, driver) =
+            spawn_unbounded_actor("logger", |mut rx: UnboundedReceiver<String>| async move {
+                rx.recv().await
+            });
+        assert_eq!(
+            actor("actor.logger").map(|(capacity, _)| capacity),
+            Some(None)
+        );
+
+        mailbox.send(String::from("hello")).expect("send");
+        assert_eq!(driver.await.expect("driver").as_deref(), Some("hello"));
+    }
+}
@@ -0,0 +1,166 @@
+// r[impl api.liveness]
+//! Liveness heartbeats for long-running loops.
+//!
+//! Call [`heartbeat`] once per iteration of a main loop, or pace a loop with a
+//! [`watchdog`] interval, which beats on every tick. Each name becomes a
+//! `heartbeat` entity recording when the loop last beat, so a dashboard or
+//! `moire-web`'s `stale_heartbeat` analysis can tell a loop that stopped from
+//! one that is merely waiting, without inferring it from the wait graph.
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::{Mutex, OnceLock, PoisonError};
+use std::time::{Duration, Instant};
+
+use moire_runtime::EntityHandle;
+use moire_types::{HeartbeatEntity, PTime};
+
+use super::time::{self, Interval};
+
+/// Beats are written to the entity at most this many times per interval, so a
+/// tight loop does not flood the change log.
+const WRITES_PER_INTERVAL: u32 = 4;
+
+struct Beat {
+    handle: EntityHandle<HeartbeatEntity>,
+    interval: Duration,
+    beats: u64,
+    written_at: Option<Instant>,
+}
+
+fn heartbeats() -> &'static Mutex<HashMap<String, Beat>> {
+    static HEARTBEATS: OnceLock<Mutex<HashMap<String, Beat>>> = OnceLock::new();
+    HEARTBEATS.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+/// Records that the loop called `name` is alive and expected to beat again
+/// within `interval`. The first call for a name creates its entity, which
+/// lives for the rest of the process; a later call with a different
+/// `interval` updates it.
+pub fn heartbeat(name: impl Into<String>, interval: Duration) {
+    moire_runtime::note_heartbeat();
+    let name = name.into();
+    let now = Instant::now();
+    let mut heartbeats = heartbeats().lock().unwrap_or_else(PoisonError::into_inner);
+    let beat = heartbeats.entry(name).or_insert_with_key(|name| Beat {
+        handle: EntityHandle::new(
+            name.clone(),
+            HeartbeatEntity {
+                interval_ms: interval.as_millis().min(u128::from(u64::MAX)) as u64,
+                beats: 0,
+                last_beat_at: None,
+            },
+        ),
+        interval,
+        beats: 0,
+        written_at: None,
+    });
+    beat.beats += 1;
+    let resized = beat.interval != interval;
+    let due = beat.written_at.is_none_or(|written_at| {
+        now.duration_since(written_at) >= beat.interval / WRITES_PER_INTERVAL
+    });
+    if !resized && !due {
+        return;
+    }
+    beat.interval = interval;
+    beat.written_at = Some(now);
+    let beats = beat.beats;
+    let _ = beat.handle.mutate(|body| {
+        body.interval_ms = interval.as_millis().min(u128::from(u64::MAX)) as u64;
+        body.beats = beats;
+        body.last_beat_at = Some(PTime::now());
+    });
+}
+
+/// An instrumented [`Interval`] that beats the heartbeat `name` every time a
+/// tick resumes the loop. A loop paced by it goes stale as soon as its body
+/// stops coming back for the next tick.
+pub struct Watchdog {
+    name: String,
+    period: Duration,
+    interval: Interval,
+}
+
+impl Watchdog {
+    /// Waits for the next tick, then beats.
+    pub async fn tick(&mut self) -> time::Instant {
+        let scheduled = self.interval.tick().await;
+        heartbeat(self.name.as_str(), self.period);
+        scheduled
+    }
+}
+
+impl fmt::Debug for Watchdog {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Watchdog")
+            .field("name", &self.name)
+            .field("interval", &self.interval)
+            .finish()
+    }
+}
+
+/// Creates a [`Watchdog`] ticking every `period`, expected to beat the
+/// heartbeat `name` that often.
+pub fn watchdog(name: impl Into<String>, period: Duration) -> Watchdog {
+    Watchdog {
+        name: name.into(),
+        period,
+        interval: time::interval(period),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use moire_runtime::{SnapshotSink, write_snapshot_to};
+    use moire_types::{Edge, Entity, EntityBody, Event};
+
+    use super::*;
+
+    /// Beats and last beat of every heartbeat called `name`.
+    struct Heartbeats<'a> {
+        name: &'a str,
+        seen: Vec<(u64, Option<PTime>)>,
+    }
+
+    impl SnapshotSink for Heartbeats<'_> {
+        fn entity(&mut self, entity: &Entity) {
+            if let EntityBody::Heartbeat(heartbeat) = &entity.body
+                && entity.name == self.name
+            {
+                self.seen.push((heartbeat.beats, heartbeat.last_beat_at));
+            }
+        }
+        fn edge(&mut self, _edge: &Edge) {}
+        fn event(&mut self, _event: &Event) {}
+    }
+
+    fn beats(name: &str) -> Vec<(u64, Option<PTime>)> {
+        let mut heartbeats = Heartbeats {
+            name,
+            seen: Vec::new(),
+        };
+        write_snapshot_to(&mut heartbeats);
+        heartbeats.seen
+    }
+
+    #[tokio::test]
+    async fn watchdog_beats_once_per_tick_and_stops_with_its_loop() {
+        let mut watchdog = watchdog("liveness.test.loop", Duration::from_millis(20));
+        assert!(beats("liveness.test.loop").is_empty());
+
+        watchdog.tick().await;
+        let first = beats("liveness.test.loop");
+        assert_eq!(first.len(), 1);
+        assert_eq!(first[0].0, 1);
+        assert!(first[0].1.is_some());
+
+        watchdog.tick().await;
+        let second = beats("liveness.test.loop");
+        assert_eq!(second[0].0, 2);
+        assert!(second[0].1 >= first[0].1);
+
+        // Nothing beats while the loop is stuck between ticks.
+        tokio::time::sleep(Duration::from_millis(60)).await;
+        assert_eq!(beats("liveness.test.loop"), second);
+    }
+}
@@ -1,13 +1,23 @@
+pub mod actor;
 pub mod custom;
+pub mod liveness;
+pub mod net;
 pub mod process;
 pub mod rpc;
 pub mod sync;
 pub mod task;
 pub mod time;
 
+pub use moire_runtime::{
+    BacktraceCapture, DropArgs, HashStrings, Redactor, RuntimeConfig, clear_redactor, disable,
+    enable, is_enabled, render_prometheus, runtime_config, set_redactor,
+};
 pub use task::{spawn, spawn_blocking};
 
+#[cfg(feature = "http")]
+pub use moire_runtime::{http_router, serve_http};
+
 #[doc(hidden)]
 pub mod __internal {
-    pub use moire_runtime::{InstrumentedFuture, instrument_future};
+    pub use moire_runtime::{InstrumentedFuture, instrument_future, instrument_future_at};
 }
@@ -0,0 +1,327 @@
+// r[impl api.net]
+//! Instrumented networking, mirroring [`tokio::net`].
+//!
+//! This module mirrors the structure of `tokio::net` and can be used as a
+//! drop-in replacement. Connects, accepts, reads and writes are registered as
+//! `net_*` entities in the Moiré runtime graph, and a task blocked on a socket
+//! gets a `waiting_on` edge to it, so the dashboard can tell a task waiting on
+//! the network from one waiting on an internal resource.
+//!
+//! # Available items
+//!
+//! | Item | Tokio equivalent |
+//! |---|---|
+//! | [`TcpStream`] | `tokio::net::TcpStream` |
+//! | [`TcpListener`] | `tokio::net::TcpListener` |
+//! | [`UdpSocket`] | `tokio::net::UdpSocket` |
+use std::fmt;
+use std::io;
+use std::net::SocketAddr;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use moire_runtime::{EdgeHandle, EntityHandle, current_causal_target, instrument_future};
+use moire_types::{EdgeKind, NetAcceptEntity, NetConnectEntity, NetReadEntity, NetWriteEntity};
+use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
+use tokio::net::ToSocketAddrs;
+
+/// Instrumented equivalent of [`tokio::net::TcpStream`].
+pub struct TcpStream {
+    inner: tokio::net::TcpStream,
+    read: SocketWait<NetReadEntity>,
+    write: SocketWait<NetWriteEntity>,
+}
+
+impl TcpStream {
+    /// Opens a connection, matching [`tokio::net::TcpStream::connect`]. Each
+    /// address `addr` resolves to is tried in turn as its own `net_connect`
+    /// entity.
+    pub async fn connect(addr: impl ToSocketAddrs) -> io::Result<TcpStream> {
+        let mut last_err = None;
+        for addr in tokio::net::lookup_host(addr).await? {
+            let handle = EntityHandle::new(
+                format!("net.connect({addr})"),
+                NetConnectEntity {
+                    addr: addr.to_string(),
+                },
+            );
+            let connect = tokio::net::TcpStream::connect(addr);
+            match instrument_future("net.connect", connect, Some(handle.entity_ref()), None).await {
+                Ok(stream) => return Ok(TcpStream::from_tokio(stream)),
+                Err(err) => last_err = Some(err),
+            }
+        }
+        Err(last_err.unwrap_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "could not resolve to any address",
+            )
+        }))
+    }
+
+    fn from_tokio(inner: tokio::net::TcpStream) -> Self {
+        let peer = inner
+            .peer_addr()
+            .map_or_else(|_| String::from("unknown"), |addr| addr.to_string());
+        let fd = socket_fd(&inner);
+        Self {
+            read: SocketWait::new(EntityHandle::new(
+                format!("net.read({peer})"),
+                NetReadEntity {
+                    addr: peer.clone(),
+                    fd,
+                },
+            )),
+            write: SocketWait::new(EntityHandle::new(
+                format!("net.write({peer})"),
+                NetWriteEntity { addr: peer, fd },
+            )),
+            inner,
+        }
+    }
+
+    /// Returns the remote address, matching [`tokio::net::TcpStream::peer_addr`].
+    pub fn peer_addr(&self) -> io::Result<SocketAddr> {
+        self.inner.peer_addr()
+    }
+
+    /// Returns the local address, matching [`tokio::net::TcpStream::local_addr`].
+    pub fn local_addr(&self) -> io::Result<SocketAddr> {
+        self.inner.local_addr()
+    }
+
+    /// Reads `TCP_NODELAY`, matching [`tokio::net::TcpStream::nodelay`].
+    pub fn nodelay(&self) -> io::Result<bool> {
+        self.inner.nodelay()
+    }
+
+    /// Sets `TCP_NODELAY`, matching [`tokio::net::TcpStream::set_nodelay`].
+    pub fn set_nodelay(&self, nodelay: bool) -> io::Result<()> {
+        self.inner.set_nodelay(nodelay)
+    }
+}
+
+impl AsyncRead for TcpStream {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        let this = self.get_mut();
+        let poll = Pin::new(&mut this.inner).poll_read(cx, buf);
+        this.read.observe(poll.is_pending());
+        poll
+    }
+}
+
+impl AsyncWrite for TcpStream {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        let this = self.get_mut();
+        let poll = Pin::new(&mut this.inner).poll_write(cx, buf);
+        this.write.observe(poll.is_pending());
+        poll
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        let this = self.get_mut();
+        let poll = Pin::new(&mut this.inner).poll_flush(cx);
+        this.write.observe(poll.is_pending());
+        poll
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        let this = self.get_mut();
+        let poll = Pin::new(&mut this.inner).poll_shutdown(cx);
+        this.write.observe(poll.is_pending());
+        poll
+    }
+}
+
+impl fmt::Debug for TcpStream {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.inner.fmt(f)
+    }
+}
+
+/// Instrumented equivalent of [`tokio::net::TcpListener`].
+pub struct TcpListener {
+    inner: tokio::net::TcpListener,
+    handle: EntityHandle<NetAcceptEntity>,
+}
+
+impl TcpListener {
+    /// Binds a listener, matching [`tokio::net::TcpListener::bind`].
+    pub async fn bind(addr: impl ToSocketAddrs) -> io::Result<TcpListener> {
+        let inner = tokio::net::TcpListener::bind(addr).await?;
+        let local = inner.local_addr()?;
+        Ok(TcpListener {
+            handle: EntityHandle::new(
+                format!("net.accept({local})"),
+                NetAcceptEntity {
+                    addr: local.to_string(),
+                },
+            ),
+            inner,
+        })
+    }
+
+    /// Accepts a connection, matching [`tokio::net::TcpListener::accept`].
+    pub async fn accept(&self) -> io::Result<(TcpStream, SocketAddr)> {
+        let (stream, addr) = instrument_future(
+            "net.accept",
+            self.inner.accept(),
+            Some(self.handle.entity_ref()),
+            None,
+        )
+        .await?;
+        Ok((TcpStream::from_tokio(stream), addr))
+    }
+
+    /// Returns the bound address, matching [`tokio::net::TcpListener::local_addr`].
+    pub fn local_addr(&self) -> io::Result<SocketAddr> {
+        self.inner.local_addr()
+    }
+}
+
+impl fmt::Debug for TcpListener {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.inner.fmt(f)
+    }
+}
+
+/// Instrumented equivalent of [`tokio::net::UdpSocket`].
+pub struct UdpSocket {
+    inner: tokio::net::UdpSocket,
+    read: EntityHandle<NetReadEntity>,
+    write: EntityHandle<NetWriteEntity>,
+}
+
+impl UdpSocket {
+    /// Binds a socket, matching [`tokio::net::UdpSocket::bind`].
+    pub async fn bind(addr: impl ToSocketAddrs) -> io::Result<UdpSocket> {
+        let inner = tokio::net::UdpSocket::bind(addr).await?;
+        let local = inner.local_addr()?.to_string();
+        let fd = socket_fd(&inner);
+        Ok(UdpSocket {
+            read: EntityHandle::new(
+                format!("net.recv({local})"),
+                NetReadEntity {
+                    addr: local.clone(),
+                    fd,
+                },
+            ),
+            write: EntityHandle::new(
+                format!("net.send({local})"),
+                NetWriteEntity { addr: local, fd },
+            ),
+            inner,
+        })
+    }
+
+    /// Sets the default peer, matching [`tokio::net::UdpSocket::connect`].
+    pub async fn connect(&self, addr: impl ToSocketAddrs) -> io::Result<()> {
+        self.inner.connect(addr).await
+    }
+
+    /// Sends to the connected peer, matching [`tokio::net::UdpSocket::send`].
+    pub async fn send(&self, buf: &[u8]) -> io::Result<usize> {
+        instrument_future(
+            "net.send",
+            self.inner.send(buf),
+            Some(self.write.entity_ref()),
+            None,
+        )
+        .await
+    }
+
+    /// Receives from the connected peer, matching [`tokio::net::UdpSocket::recv`].
+    pub async fn recv(&self, buf: &mut [u8]) -> io::Result<usize> {
+        instrument_future(
+            "net.recv",
+            self.inner.recv(buf),
+            Some(self.read.entity_ref()),
+            None,
+        )
+        .await
+    }
+
+    /// Sends to `target`, matching [`tokio::net::UdpSocket::send_to`].
+    pub async fn send_to(&self, buf: &[u8], target: impl ToSocketAddrs) -> io::Result<usize> {
+        instrument_future(
+            "net.send_to",
+            self.inner.send_to(buf, target),
+            Some(self.write.entity_ref()),
+            None,
+        )
+        .await
+    }
+
+    /// Receives from any peer, matching [`tokio::net::UdpSocket::recv_from`].
+    pub async fn recv_from(&self, buf: &mut [u8]) -> io::Result<(usize, SocketAddr)> {
+        instrument_future(
+            "net.recv_from",
+            self.inner.recv_from(buf),
+            Some(self.read.entity_ref()),
+            None,
+        )
+        .await
+    }
+
+    /// Returns the bound address, matching [`tokio::net::UdpSocket::local_addr`].
+    pub fn local_addr(&self) -> io::Result<SocketAddr> {
+        self.inner.local_addr()
+    }
+
+    /// Returns the connected peer, matching [`tokio::net::UdpSocket::peer_addr`].
+    pub fn peer_addr(&self) -> io::Result<SocketAddr> {
+        self.inner.peer_addr()
+    }
+}
+
+impl fmt::Debug for UdpSocket {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.inner.fmt(f)
+    }
+}
+
+/// One direction of a stream socket, and the `waiting_on` edge of the future
+/// blocked on it, if any. Poll-based I/O has no future of its own to hang the
+/// edge on, so it is kept from the first `Pending` poll to the next ready one.
+struct SocketWait<S> {
+    handle: EntityHandle<S>,
+    edge: Option<EdgeHandle>,
+}
+
+impl<S> SocketWait<S> {
+    fn new(handle: EntityHandle<S>) -> Self {
+        Self { handle, edge: None }
+    }
+
+    fn observe(&mut self, pending: bool) {
+        if !pending {
+            self.edge = None;
+            return;
+        }
+        if self.edge.is_some() {
+            return;
+        }
+        let Some(waiter) = current_causal_target() else {
+            return;
+        };
+        self.edge = Some(waiter.link_to_owned(&self.handle, EdgeKind::WaitingOn));
+    }
+}
+
+#[cfg(unix)]
+fn socket_fd(socket: &impl std::os::fd::AsRawFd) -> Option<u64> {
+    u64::try_from(socket.as_raw_fd()).ok()
+}
+
+#[cfg(not(unix))]
+fn socket_fd<T>(_socket: &T) -> Option<u64> {
+    None
+}
@@ -7,9 +7,18 @@
 //!
 //! Unlike the other modules in this crate, `rpc` has no direct `tokio` equivalent —
 //! it is purpose-built for Roam's wire protocol.
-use moire_types::{EdgeKind, EntityId, RequestEntity, ResponseEntity, ResponseStatus};
+use moire_types::{
+    CONNECTION_CLOSED_EVENT, CONNECTION_FLAP_WINDOW_MS, CONNECTION_OPENED_EVENT,
+    ConnectionClosedPayload, ConnectionOpenedPayload, ConnectionScopeBody, ConnectionState,
+    EdgeKind, EntityId, EventTarget, Json, PTime, RequestEntity, ResponseEntity, ResponseStatus,
+    ScopeBody,
+};
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex as StdMutex, MutexGuard, PoisonError};
 
-use moire_runtime::{EntityHandle, EntityRef};
+use moire_runtime::{EntityHandle, EntityRef, ScopeHandle};
+
+pub use crate::args_capture::ArgsCapture;
 
 /// Instrumented request handle for a wrapped RPC request entity.
 #[derive(Clone)]
@@ -52,6 +61,7 @@ pub fn rpc_request(method: impl Into<String>, args_json: impl Into<String>) -> R
             service_name,
             method_name,
             args_json: moire_types::Json::new(args_json),
+            connection_generation: None,
         },
     )
 }
@@ -76,6 +86,7 @@ pub fn rpc_response(method: impl Into<String>) -> EntityHandle<moire_types::Resp
             service_name,
             method_name,
             status: ResponseStatus::Pending,
+            connection_generation: None,
         },
     )
 }
@@ -106,6 +117,7 @@ pub fn rpc_response_for(
             service_name,
             method_name,
             status: ResponseStatus::Pending,
+            connection_generation: None,
         },
     )
 }
@@ -122,6 +134,175 @@ pub fn rpc_response_for_with_body(
     response
 }
 
+/// Instrumented connection to one RPC peer, shown as a connection scope.
+///
+/// The transport calls [`RpcConnection::opened`] and [`RpcConnection::closed`]
+/// as the link goes up and down, and binds requests and responses to the
+/// connection they travel on so the dashboard never pairs a request with a
+/// response from an earlier generation of the connection.
+#[derive(Clone)]
+pub struct RpcConnection {
+    scope: ScopeHandle,
+    state: Arc<StdMutex<ConnectionTracking>>,
+}
+
+struct ConnectionTracking {
+    generation: u64,
+    /// When each open within the flap window happened, oldest first.
+    recent_opens: VecDeque<PTime>,
+    args_capture: ArgsCapture,
+}
+
+// r[impl api.rpc-connection]
+/// Registers a connection scope for the peer called `name`. The connection
+/// starts out closed; call [`RpcConnection::opened`] once it is established.
+pub fn rpc_connection(
+    name: impl Into<String>,
+    local_addr: Option<String>,
+    peer_addr: Option<String>,
+) -> RpcConnection {
+    let scope = ScopeHandle::new(
+        format!("connection.{}", name.into()),
+        ScopeBody::Connection(ConnectionScopeBody {
+            local_addr,
+            peer_addr,
+            state: Some(ConnectionState::Closed),
+            generation: None,
+            flaps: None,
+        }),
+    );
+    RpcConnection {
+        scope,
+        state: Arc::new(StdMutex::new(ConnectionTracking {
+            generation: 0,
+            recent_opens: VecDeque::new(),
+            args_capture: ArgsCapture::Full,
+        })),
+    }
+}
+
+impl RpcConnection {
+    #[doc(hidden)]
+    pub fn scope(&self) -> &ScopeHandle {
+        &self.scope
+    }
+
+    fn state(&self) -> MutexGuard<'_, ConnectionTracking> {
+        self.state.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Current generation: how many times the connection has been opened.
+    pub fn generation(&self) -> u64 {
+        self.state().generation
+    }
+
+    /// Marks the connection as established and starts a new generation,
+    /// which is returned.
+    pub fn opened(&self) -> u64 {
+        let now = PTime::now();
+        let mut state = self.state();
+        state.generation += 1;
+        let cutoff = now.as_millis().saturating_sub(CONNECTION_FLAP_WINDOW_MS);
+        while state
+            .recent_opens
+            .front()
+            .is_some_and(|at| at.as_millis() < cutoff)
+        {
+            state.recent_opens.pop_front();
+        }
+        state.recent_opens.push_back(now);
+        let generation = state.generation;
+        // The first open of the window is a connect, not a reconnect.
+        let flaps = (state.recent_opens.len() - 1).min(u32::MAX as usize) as u32;
+        drop(state);
+
+        self.scope.mutate(|body| {
+            if let ScopeBody::Connection(body) = body {
+                body.state = Some(ConnectionState::Open);
+                body.generation = Some(generation);
+                body.flaps = Some(flaps);
+            }
+        });
+        let payload = facet_json::to_string(&ConnectionOpenedPayload { generation, flaps })
+            .expect("invariant violated: connection opened payload must serialize");
+        moire_runtime::record_custom_event(
+            EventTarget::Scope(self.scope.id().clone()),
+            CONNECTION_OPENED_EVENT,
+            "Connection Opened",
+            Json::new(payload),
+        );
+        generation
+    }
+
+    /// Marks the connection as down. Requests still bound to the current
+    /// generation stay in flight until the next [`RpcConnection::opened`].
+    pub fn closed(&self, reason: Option<String>) {
+        let generation = self.generation();
+        self.scope.mutate(|body| {
+            if let ScopeBody::Connection(body) = body {
+                body.state = Some(ConnectionState::Closed);
+            }
+        });
+        let payload = facet_json::to_string(&ConnectionClosedPayload { generation, reason })
+            .expect("invariant violated: connection closed payload must serialize");
+        moire_runtime::record_custom_event(
+            EventTarget::Scope(self.scope.id().clone()),
+            CONNECTION_CLOSED_EVENT,
+            "Connection Closed",
+            Json::new(payload),
+        );
+    }
+
+    /// How much of the arguments of requests bound to this connection is
+    /// recorded. [`ArgsCapture::Full`] until set.
+    pub fn args_capture(&self) -> ArgsCapture {
+        self.state().args_capture
+    }
+
+    /// Sets how much of the arguments of requests bound from now on is
+    /// recorded.
+    pub fn set_args_capture(&self, capture: ArgsCapture) {
+        self.state().args_capture = capture;
+    }
+
+    /// Creates a request bound to this connection, its arguments captured
+    /// under [`RpcConnection::args_capture`] before they are ever recorded.
+    pub fn request(
+        &self,
+        method: impl Into<String>,
+        args_json: impl Into<String>,
+    ) -> RpcRequestHandle {
+        let request = rpc_request(method, self.args_capture().apply(&args_json.into()));
+        self.bind_request(&request);
+        request
+    }
+
+    /// Records that `request` was (re)sent on the current generation of this
+    /// connection, and applies the connection's [`ArgsCapture`] to its
+    /// arguments. Arguments recorded before binding may already have been
+    /// snapshotted; create requests with [`RpcConnection::request`] to avoid
+    /// that.
+    pub fn bind_request(&self, request: &RpcRequestHandle) {
+        let generation = self.generation();
+        let capture = self.args_capture();
+        let _ = request.handle.mutate(|body| {
+            body.connection_generation = Some(generation);
+            body.args_json = Json::new(capture.apply(body.args_json.as_str()));
+        });
+        self.scope.link_entity(&request.handle);
+    }
+
+    /// Records that `response` is being served for a request that arrived on
+    /// the current generation of this connection.
+    pub fn bind_response(&self, response: &EntityHandle<moire_types::Response>) {
+        let generation = self.generation();
+        let _ = response.mutate(|body| {
+            body.connection_generation = Some(generation);
+        });
+        self.scope.link_entity(response);
+    }
+}
+
 fn split_method_parts(full_method: &str) -> (&str, &str) {
     if let Some((service, method)) = full_method.rsplit_once('.') {
         (service, method)
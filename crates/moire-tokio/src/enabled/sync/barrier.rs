@@ -0,0 +1,130 @@
+// r[impl api.barrier]
+use moire_types::{BarrierEntity, EntityId, PTime};
+use std::fmt;
+use std::sync::{Arc, Mutex as StdMutex, PoisonError};
+
+use moire_runtime::{
+    EntityHandle, current_causal_target_with_task_fallback, instrument_operation_on,
+};
+
+pub use tokio::sync::BarrierWaitResult;
+
+/// Instrumented version of [`tokio::sync::Barrier`].
+#[derive(Clone)]
+pub struct Barrier {
+    inner: Arc<tokio::sync::Barrier>,
+    handle: EntityHandle<moire_types::Barrier>,
+    state: Arc<StdMutex<BarrierState>>,
+}
+
+struct BarrierState {
+    parties: u64,
+    /// Calls to `wait` since the barrier was created, cancelled ones included:
+    /// tokio counts those towards the generation too.
+    arrivals: u64,
+    parked: Vec<Parked>,
+}
+
+struct Parked {
+    arrival: u64,
+    task: Option<EntityId>,
+    since: PTime,
+}
+
+/// Unparks a waiter whose `wait` future is dropped before the barrier releases it.
+struct ParkedGuard<'a> {
+    barrier: &'a Barrier,
+    arrival: u64,
+}
+
+impl Drop for ParkedGuard<'_> {
+    fn drop(&mut self) {
+        self.barrier
+            .update_state(|state| state.parked.retain(|p| p.arrival != self.arrival));
+    }
+}
+
+impl Barrier {
+    /// Creates a new barrier, matching [`tokio::sync::Barrier::new`].
+    pub fn new(name: impl Into<String>, n: usize) -> Self {
+        // tokio treats a zero-party barrier like a one-party one.
+        let parties = n.max(1);
+        let handle = EntityHandle::new(
+            name.into(),
+            BarrierEntity {
+                parties: parties.min(u32::MAX as usize) as u32,
+                arrived: 0,
+                waiting: Vec::new(),
+                oldest_arrival_at: None,
+            },
+        );
+        Self {
+            inner: Arc::new(tokio::sync::Barrier::new(n)),
+            handle,
+            state: Arc::new(StdMutex::new(BarrierState {
+                parties: parties as u64,
+                arrivals: 0,
+                parked: Vec::new(),
+            })),
+        }
+    }
+
+    /// Waits for all parties to reach the barrier, matching [`tokio::sync::Barrier::wait`].
+    pub async fn wait(&self) -> BarrierWaitResult {
+        let task = current_causal_target_with_task_fallback().map(|target| target.id().clone());
+        let mut arrival = 0;
+        let mut releases = false;
+        self.update_state(|state| {
+            arrival = state.arrivals;
+            state.arrivals += 1;
+            releases = state.arrivals % state.parties == 0;
+            if !releases {
+                state.parked.push(Parked {
+                    arrival,
+                    task,
+                    since: PTime::now(),
+                });
+            }
+        });
+
+        if releases {
+            // The last arrival releases everyone parked in its generation.
+            self.update_state(|state| {
+                let generation = arrival / state.parties;
+                state
+                    .parked
+                    .retain(|p| p.arrival / state.parties != generation);
+            });
+            return self.inner.wait().await;
+        }
+
+        let _guard = ParkedGuard {
+            barrier: self,
+            arrival,
+        };
+        instrument_operation_on(&self.handle, self.inner.wait()).await
+    }
+
+    fn update_state(&self, f: impl FnOnce(&mut BarrierState)) {
+        let mut state = self.state.lock().unwrap_or_else(PoisonError::into_inner);
+        f(&mut state);
+        let arrived = (state.arrivals % state.parties).min(u32::MAX as u64) as u32;
+        let waiting = state
+            .parked
+            .iter()
+            .filter_map(|p| p.task.clone())
+            .collect::<Vec<_>>();
+        let oldest_arrival_at = state.parked.first().map(|p| p.since);
+        let _ = self.handle.mutate(|body| {
+            body.arrived = arrived;
+            body.waiting = waiting;
+            body.oldest_arrival_at = oldest_arrival_at;
+        });
+    }
+}
+
+impl fmt::Debug for Barrier {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.inner.fmt(f)
+    }
+}
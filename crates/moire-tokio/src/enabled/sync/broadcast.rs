@@ -1,7 +1,8 @@
 // r[impl api.broadcast]
 
 use moire_runtime::{
-    AsEntityRef, EntityHandle, EntityRef, WeakEntityHandle, new_event, record_event,
+    AsEntityRef, EntityHandle, EntityRef, WeakEntityHandle, instrument_operation_on, new_event,
+    record_event,
 };
 use moire_types::{BroadcastRxEntity, BroadcastTxEntity, EdgeKind, EventKind, EventTarget};
 use std::fmt;
@@ -26,6 +27,9 @@ pub struct Receiver<T> {
 
 impl<T> Clone for Sender<T> {
     fn clone(&self) -> Self {
+        let _ = self
+            .handle
+            .mutate(|body| body.sender_count = Some(body.sender_count.unwrap_or(0) + 1));
         Self {
             inner: self.inner.clone(),
             handle: self.handle.clone(),
@@ -35,6 +39,7 @@ impl<T> Clone for Sender<T> {
 
 impl<T: Clone> Clone for Receiver<T> {
     fn clone(&self) -> Self {
+        note_receiver_count(&self.tx_handle, 1);
         Self {
             inner: self.inner.resubscribe(),
             handle: self.handle.clone(),
@@ -50,8 +55,17 @@ impl<T: Clone> Sender<T> {
     }
     /// Subscribes a receiver, equivalent to [`tokio::sync::broadcast::Sender::subscribe`].
     pub fn subscribe(&self) -> Receiver<T> {
-        let handle = EntityHandle::new("broadcast:rx.subscribe", BroadcastRxEntity { lag: 0 });
+        let handle = EntityHandle::new(
+            "broadcast:rx.subscribe",
+            BroadcastRxEntity {
+                lag: 0,
+                dropped: None,
+            },
+        );
         self.handle.link_to_handle(&handle, EdgeKind::PairedWith);
+        let _ = self
+            .handle
+            .mutate(|body| body.receiver_count = Some(body.receiver_count.unwrap_or(0) + 1));
         Receiver {
             inner: self.inner.subscribe(),
             handle,
@@ -77,7 +91,7 @@ impl<T: Clone> Receiver<T> {
     }
     /// Receives the next broadcast value, equivalent to [`tokio::sync::broadcast::Receiver::recv`].
     pub async fn recv(&mut self) -> Result<T, broadcast::error::RecvError> {
-        match self.inner.recv().await {
+        match instrument_operation_on(&self.handle, self.inner.recv()).await {
             Ok(value) => {
                 let lag = self.inner.len().min(u32::MAX as usize) as u32;
                 let _ = self.handle.mutate(|body| body.lag = lag);
@@ -91,7 +105,10 @@ impl<T: Clone> Receiver<T> {
             Err(err) => {
                 if let broadcast::error::RecvError::Lagged(n) = err {
                     let lag = n.min(u32::MAX as u64) as u32;
-                    let _ = self.handle.mutate(|body| body.lag = lag);
+                    let _ = self.handle.mutate(|body| {
+                        body.lag = lag;
+                        body.dropped = Some(body.dropped.unwrap_or(0).saturating_add(n));
+                    });
                 }
                 let event = new_event(
                     EventTarget::Entity(self.handle.id().clone()),
@@ -114,10 +131,18 @@ pub fn channel<T: Clone>(name: impl Into<String>, capacity: usize) -> (Sender<T>
         format!("{name}:tx"),
         BroadcastTxEntity {
             capacity: capacity_u32,
+            sender_count: Some(1),
+            receiver_count: Some(1),
         },
     );
 
-    let rx_handle = EntityHandle::new(format!("{name}:rx"), BroadcastRxEntity { lag: 0 });
+    let rx_handle = EntityHandle::new(
+        format!("{name}:rx"),
+        BroadcastRxEntity {
+            lag: 0,
+            dropped: None,
+        },
+    );
 
     tx_handle.link_to_handle(&rx_handle, EdgeKind::PairedWith);
 
@@ -134,6 +159,27 @@ pub fn channel<T: Clone>(name: impl Into<String>, capacity: usize) -> (Sender<T>
     )
 }
 
+fn note_receiver_count(tx_handle: &WeakEntityHandle<moire_types::BroadcastTx>, delta: i64) {
+    let _ = tx_handle.mutate(|body| {
+        let count = i64::from(body.receiver_count.unwrap_or(0)) + delta;
+        body.receiver_count = Some(count.max(0) as u32);
+    });
+}
+
+impl<T> Drop for Sender<T> {
+    fn drop(&mut self) {
+        let _ = self.handle.mutate(|body| {
+            body.sender_count = Some(body.sender_count.unwrap_or(0).saturating_sub(1))
+        });
+    }
+}
+
+impl<T> Drop for Receiver<T> {
+    fn drop(&mut self) {
+        note_receiver_count(&self.tx_handle, -1);
+    }
+}
+
 impl<T: Clone> AsEntityRef for Sender<T> {
     fn as_entity_ref(&self) -> EntityRef {
         self.handle.entity_ref()
@@ -21,21 +21,29 @@
 //! | [`Semaphore`] | [`tokio::sync::Semaphore`] |
 //! | [`Notify`] | [`tokio::sync::Notify`] |
 //! | [`OnceCell`] | [`tokio::sync::OnceCell`] |
+//! | [`Barrier`] | [`tokio::sync::Barrier`] |
 
 pub mod broadcast;
 pub mod mpsc;
 pub mod oneshot;
 pub mod watch;
 
+mod barrier;
+pub use barrier::*;
+
 mod mutex;
 pub use mutex::*;
 
+pub use moire_runtime::{LockBacktracePolicy, lock_backtrace_policy, set_lock_backtrace_policy};
+
 mod notify;
 pub use notify::*;
 
 mod once_cell;
 pub use once_cell::*;
 
+mod owned_hold;
+
 mod rwlock;
 pub use rwlock::*;
 
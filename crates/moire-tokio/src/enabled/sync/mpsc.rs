@@ -1,10 +1,13 @@
 // r[impl api.mpsc]
 
 use moire_runtime::{
-    AsEntityRef, EntityHandle, EntityRef, WeakEntityHandle, instrument_operation_on, new_event,
-    record_event,
+    AsEntityRef, EntityHandle, EntityRef, WeakEntityHandle, current_causal_target,
+    current_causal_target_with_task_fallback, instrument_operation_on, new_event, record_event,
+    record_stat_sample,
+};
+use moire_types::{
+    EdgeKind, EntityId, EventKind, EventTarget, MpscRxEntity, MpscSendWaiter, MpscTxEntity, PTime,
 };
-use moire_types::{EdgeKind, EventKind, EventTarget, MpscRxEntity, MpscTxEntity};
 use std::fmt;
 use tokio::sync::mpsc;
 pub use tokio::sync::mpsc::error;
@@ -24,6 +27,9 @@ pub struct Receiver<T> {
     inner: tokio::sync::mpsc::Receiver<T>,
     handle: EntityHandle<moire_types::MpscRx>,
     tx_handle: WeakEntityHandle<moire_types::MpscTx>,
+    /// Last future recorded as `receiver`, so the entity is only rewritten
+    /// when another one takes over.
+    receiver: Option<EntityId>,
 }
 
 /// Instrumented version of [`tokio::sync::mpsc::UnboundedSender`].
@@ -39,6 +45,9 @@ pub struct UnboundedReceiver<T> {
     inner: tokio::sync::mpsc::UnboundedReceiver<T>,
     handle: EntityHandle<moire_types::MpscRx>,
     tx_handle: WeakEntityHandle<moire_types::MpscTx>,
+    /// Last future recorded as `receiver`, so the entity is only rewritten
+    /// when another one takes over.
+    receiver: Option<EntityId>,
 }
 
 /// Instrumented version of [`tokio::sync::mpsc::OwnedPermit`].
@@ -77,9 +86,7 @@ impl<T> Sender<T> {
     pub fn try_send(&self, value: T) -> Result<(), mpsc::error::TrySendError<T>> {
         match self.inner.try_send(value) {
             Ok(()) => {
-                let _ = self
-                    .handle
-                    .mutate(|body| body.queue_len = body.queue_len.saturating_add(1));
+                let _ = self.handle.mutate(|body| shift_queue_len(body, 1));
                 Ok(())
             }
             Err(err) => Err(err),
@@ -93,11 +100,10 @@ impl<T> Sender<T> {
 
     /// Sends a value and awaits slot availability, matching [`tokio::sync::mpsc::Sender::send`].
     pub async fn send(&self, value: T) -> Result<(), mpsc::error::SendError<T>> {
+        let _waiter = SendWaiterGuard::enter(&self.handle, self.inner.capacity());
         let result = instrument_operation_on(&self.handle, self.inner.send(value)).await;
         if result.is_ok() {
-            let _ = self
-                .handle
-                .mutate(|body| body.queue_len = body.queue_len.saturating_add(1));
+            let _ = self.handle.mutate(|body| shift_queue_len(body, 1));
         }
         let event = new_event(
             EventTarget::Entity(self.handle.id().clone()),
@@ -110,7 +116,10 @@ impl<T> Sender<T> {
     /// Reserves capacity and returns an owned permit, matching [`tokio::sync::mpsc::Sender::reserve_owned`].
     pub async fn reserve_owned(self) -> Result<OwnedPermit<T>, mpsc::error::SendError<()>> {
         let Self { inner, handle } = self;
-        let permit = instrument_operation_on(&handle, inner.reserve_owned()).await?;
+        let waiter = SendWaiterGuard::enter(&handle, inner.capacity());
+        let permit = instrument_operation_on(&handle, inner.reserve_owned()).await;
+        drop(waiter);
+        let permit = permit?;
         Ok(OwnedPermit {
             inner: permit,
             handle,
@@ -139,9 +148,7 @@ impl<T> OwnedPermit<T> {
     /// Sends a value using reserved capacity, matching [`tokio::sync::mpsc::OwnedPermit::send`].
     pub fn send(self, value: T) -> Sender<T> {
         let sender = self.inner.send(value);
-        let _ = self
-            .handle
-            .mutate(|body| body.queue_len = body.queue_len.saturating_add(1));
+        let _ = self.handle.mutate(|body| shift_queue_len(body, 1));
         let event = new_event(
             EventTarget::Entity(self.handle.id().clone()),
             EventKind::ChannelSent,
@@ -181,11 +188,10 @@ impl<T> Receiver<T> {
     }
     /// Receives the next message, matching [`tokio::sync::mpsc::Receiver::recv`].
     pub async fn recv(&mut self) -> Option<T> {
+        note_receiver(&self.handle, &mut self.receiver);
         let result = instrument_operation_on(&self.handle, self.inner.recv()).await;
         if result.is_some() {
-            let _ = self
-                .tx_handle
-                .mutate(|body| body.queue_len = body.queue_len.saturating_sub(1));
+            let _ = self.tx_handle.mutate(|body| shift_queue_len(body, -1));
         }
         let event = new_event(
             EventTarget::Entity(self.handle.id().clone()),
@@ -210,9 +216,7 @@ impl<T> UnboundedSender<T> {
     pub fn send(&self, value: T) -> Result<(), mpsc::error::SendError<T>> {
         match self.inner.send(value) {
             Ok(()) => {
-                let _ = self
-                    .handle
-                    .mutate(|body| body.queue_len = body.queue_len.saturating_add(1));
+                let _ = self.handle.mutate(|body| shift_queue_len(body, 1));
                 let event = new_event(
                     EventTarget::Entity(self.handle.id().clone()),
                     EventKind::ChannelSent,
@@ -244,11 +248,10 @@ impl<T> UnboundedReceiver<T> {
     }
     /// Receives the next unbounded message, matching [`tokio::sync::mpsc::UnboundedReceiver::recv`].
     pub async fn recv(&mut self) -> Option<T> {
+        note_receiver(&self.handle, &mut self.receiver);
         let result = instrument_operation_on(&self.handle, self.inner.recv()).await;
         if result.is_some() {
-            let _ = self
-                .tx_handle
-                .mutate(|body| body.queue_len = body.queue_len.saturating_sub(1));
+            let _ = self.tx_handle.mutate(|body| shift_queue_len(body, -1));
         }
         let event = new_event(
             EventTarget::Entity(self.handle.id().clone()),
@@ -264,6 +267,57 @@ impl<T> UnboundedReceiver<T> {
     }
 }
 
+/// Lists the calling future in `send_waiters` while it waits for room in a
+/// full channel. Whether it will wait is decided from the free capacity when
+/// the send starts, which is enough for diagnostics.
+struct SendWaiterGuard<'a> {
+    handle: &'a EntityHandle<moire_types::MpscTx>,
+    waiter: MpscSendWaiter,
+}
+
+impl<'a> SendWaiterGuard<'a> {
+    fn enter(handle: &'a EntityHandle<moire_types::MpscTx>, capacity: usize) -> Option<Self> {
+        if capacity > 0 {
+            return None;
+        }
+        let future = current_causal_target_with_task_fallback()?.id().clone();
+        let waiter = MpscSendWaiter {
+            future,
+            since: PTime::now(),
+        };
+        let _ = handle.mutate(|body| body.send_waiters.push(waiter.clone()));
+        Some(Self { handle, waiter })
+    }
+}
+
+impl Drop for SendWaiterGuard<'_> {
+    fn drop(&mut self) {
+        let _ = self.handle.mutate(|body| {
+            if let Some(index) = body.send_waiters.iter().position(|w| *w == self.waiter) {
+                body.send_waiters.remove(index);
+            }
+        });
+    }
+}
+
+/// Records the calling future as the one draining the channel.
+fn note_receiver(handle: &EntityHandle<moire_types::MpscRx>, receiver: &mut Option<EntityId>) {
+    let Some(current) = current_causal_target() else {
+        return;
+    };
+    if receiver.as_ref() == Some(current.id()) {
+        return;
+    }
+    *receiver = Some(current.id().clone());
+    let _ = handle.mutate(|body| body.receiver = Some(current.id().clone()));
+}
+
+/// Moves `queue_len` by `delta` and samples it into the stat history.
+fn shift_queue_len(body: &mut MpscTxEntity, delta: i32) {
+    body.queue_len = body.queue_len.saturating_add_signed(delta);
+    record_stat_sample(&mut body.history, body.queue_len);
+}
+
 /// Creates a bounded channel, equivalent to [`tokio::sync::mpsc::channel`].
 pub fn channel<T>(name: impl Into<String>, capacity: usize) -> (Sender<T>, Receiver<T>) {
     let name = name.into();
@@ -275,10 +329,12 @@ pub fn channel<T>(name: impl Into<String>, capacity: usize) -> (Sender<T>, Recei
         MpscTxEntity {
             queue_len: 0,
             capacity: Some(capacity_u32),
+            send_waiters: Vec::new(),
+            history: Vec::new(),
         },
     );
 
-    let rx_handle = EntityHandle::new(format!("{name}:rx"), MpscRxEntity {});
+    let rx_handle = EntityHandle::new(format!("{name}:rx"), MpscRxEntity { receiver: None });
 
     tx_handle.link_to_handle(&rx_handle, EdgeKind::PairedWith);
 
@@ -291,6 +347,7 @@ pub fn channel<T>(name: impl Into<String>, capacity: usize) -> (Sender<T>, Recei
             inner: rx,
             handle: rx_handle,
             tx_handle: tx_handle.downgrade(),
+            receiver: None,
         },
     )
 }
@@ -305,10 +362,12 @@ pub fn unbounded_channel<T>(name: impl Into<String>) -> (UnboundedSender<T>, Unb
         MpscTxEntity {
             queue_len: 0,
             capacity: None,
+            send_waiters: Vec::new(),
+            history: Vec::new(),
         },
     );
 
-    let rx_handle = EntityHandle::new(format!("{name}:rx"), MpscRxEntity {});
+    let rx_handle = EntityHandle::new(format!("{name}:rx"), MpscRxEntity { receiver: None });
 
     tx_handle.link_to_handle(&rx_handle, EdgeKind::PairedWith);
 
@@ -321,6 +380,7 @@ pub fn unbounded_channel<T>(name: impl Into<String>) -> (UnboundedSender<T>, Unb
             inner: rx,
             handle: rx_handle,
             tx_handle: tx_handle.downgrade(),
+            receiver: None,
         },
     )
 }
@@ -2,15 +2,19 @@
 use moire_types::{EdgeKind, LockEntity, LockKind};
 use std::fmt;
 use std::ops::{Deref, DerefMut};
+use std::sync::Arc;
 
 use moire_runtime::{
     AsEntityRef, EdgeHandle, EntityHandle, EntityRef, HELD_MUTEX_STACK,
-    current_causal_target_with_task_fallback, instrument_operation_on_with_actor,
+    current_causal_target_with_task_fallback, instrument_operation_on_with_actor_and_backtrace,
+    lock_edge_backtrace,
 };
 
+use super::owned_hold::OwnedHold;
+
 /// Instrumented version of [`tokio::sync::Mutex`].
 pub struct Mutex<T> {
-    inner: tokio::sync::Mutex<T>,
+    inner: Arc<tokio::sync::Mutex<T>>,
     handle: EntityHandle<moire_types::Lock>,
 }
 
@@ -21,6 +25,14 @@ pub struct MutexGuard<'a, T> {
     holds_edge: Option<EdgeHandle>,
 }
 
+/// Guard returned by [`Mutex::lock_owned`], equivalent to
+/// [`tokio::sync::OwnedMutexGuard`]. Its `held_by` edge follows the guard to
+/// whichever task uses it.
+pub struct OwnedMutexGuard<T> {
+    inner: tokio::sync::OwnedMutexGuard<T>,
+    hold: OwnedHold,
+}
+
 /// Instrumented version of [`parking_lot::Mutex`], preserving lock semantics with diagnostics.
 pub struct SyncMutex<T> {
     inner: parking_lot::Mutex<T>,
@@ -48,6 +60,22 @@ impl<'a, T> DerefMut for MutexGuard<'a, T> {
     }
 }
 
+impl<T> Deref for OwnedMutexGuard<T> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        self.hold.observe();
+        &self.inner
+    }
+}
+
+impl<T> DerefMut for OwnedMutexGuard<T> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.hold.observe();
+        &mut self.inner
+    }
+}
+
 impl<'a, T> Deref for SyncMutexGuard<'a, T> {
     type Target = T;
 
@@ -69,10 +97,12 @@ impl<T> Mutex<T> {
             name,
             LockEntity {
                 kind: LockKind::Mutex,
+                holds: None,
+                rwlock: None,
             },
         );
         Self {
-            inner: tokio::sync::Mutex::new(value),
+            inner: Arc::new(tokio::sync::Mutex::new(value)),
             handle,
         }
     }
@@ -80,10 +110,18 @@ impl<T> Mutex<T> {
     /// Acquires the lock asynchronously, matching [`tokio::sync::Mutex::lock`].
     pub async fn lock(&self) -> MutexGuard<'_, T> {
         let owner_ref = current_causal_target_with_task_fallback();
-        let inner =
-            instrument_operation_on_with_actor(&self.handle, owner_ref.as_ref(), self.inner.lock())
-                .await;
-        self.wrap_guard(inner, owner_ref.as_ref(), None)
+        if let Ok(inner) = self.inner.try_lock() {
+            return self.wrap_guard(inner, owner_ref.as_ref(), None, false);
+        }
+
+        let inner = instrument_operation_on_with_actor_and_backtrace(
+            &self.handle,
+            owner_ref.as_ref(),
+            self.inner.lock(),
+            lock_edge_backtrace(&self.handle, true),
+        )
+        .await;
+        self.wrap_guard(inner, owner_ref.as_ref(), None, true)
     }
 
     /// Attempts lock acquisition without waiting, matching [`tokio::sync::Mutex::try_lock`].
@@ -91,20 +129,79 @@ impl<T> Mutex<T> {
         let owner_ref = current_causal_target_with_task_fallback();
         self.inner
             .try_lock()
-            .map(|inner| self.wrap_guard(inner, owner_ref.as_ref(), Some(EdgeKind::Polls)))
+            .map(|inner| self.wrap_guard(inner, owner_ref.as_ref(), Some(EdgeKind::Polls), false))
     }
 
-    fn wrap_guard<'a>(
+    /// Acquires the lock asynchronously, matching [`tokio::sync::Mutex::lock_owned`].
+    pub async fn lock_owned(self: Arc<Self>) -> OwnedMutexGuard<T> {
+        let owner_ref = current_causal_target_with_task_fallback();
+        if let Ok(inner) = Arc::clone(&self.inner).try_lock_owned() {
+            return self.wrap_owned_guard(inner, owner_ref.as_ref(), None, false);
+        }
+
+        let inner = instrument_operation_on_with_actor_and_backtrace(
+            &self.handle,
+            owner_ref.as_ref(),
+            Arc::clone(&self.inner).lock_owned(),
+            lock_edge_backtrace(&self.handle, true),
+        )
+        .await;
+        self.wrap_owned_guard(inner, owner_ref.as_ref(), None, true)
+    }
+
+    /// Attempts lock acquisition without waiting, matching [`tokio::sync::Mutex::try_lock_owned`].
+    pub fn try_lock_owned(
+        self: Arc<Self>,
+    ) -> Result<OwnedMutexGuard<T>, tokio::sync::TryLockError> {
+        let owner_ref = current_causal_target_with_task_fallback();
+        Arc::clone(&self.inner).try_lock_owned().map(|inner| {
+            self.wrap_owned_guard(inner, owner_ref.as_ref(), Some(EdgeKind::Polls), false)
+        })
+    }
+
+    fn hold(
         &self,
-        inner: tokio::sync::MutexGuard<'a, T>,
         owner_ref: Option<&EntityRef>,
         pre_edge_kind: Option<EdgeKind>,
-    ) -> MutexGuard<'a, T> {
+        contended: bool,
+    ) -> Option<EdgeHandle> {
         if let (Some(owner), Some(kind)) = (owner_ref, pre_edge_kind) {
             self.handle.link_to(owner, kind);
         }
 
-        let holds_edge = owner_ref.map(|owner| self.handle.link_to_owned(owner, EdgeKind::HeldBy));
+        owner_ref.map(|owner| {
+            self.handle.link_to_owned_with_backtrace(
+                owner,
+                EdgeKind::HeldBy,
+                lock_edge_backtrace(&self.handle, contended),
+            )
+        })
+    }
+
+    fn wrap_owned_guard(
+        &self,
+        inner: tokio::sync::OwnedMutexGuard<T>,
+        owner_ref: Option<&EntityRef>,
+        pre_edge_kind: Option<EdgeKind>,
+        contended: bool,
+    ) -> OwnedMutexGuard<T> {
+        let holds_edge = self.hold(owner_ref, pre_edge_kind, contended);
+        // Owned guards can move across threads, so they stay off the
+        // thread-local held-mutex stack.
+        OwnedMutexGuard {
+            inner,
+            hold: OwnedHold::new(&self.handle, holds_edge),
+        }
+    }
+
+    fn wrap_guard<'a>(
+        &self,
+        inner: tokio::sync::MutexGuard<'a, T>,
+        owner_ref: Option<&EntityRef>,
+        pre_edge_kind: Option<EdgeKind>,
+        contended: bool,
+    ) -> MutexGuard<'a, T> {
+        let holds_edge = self.hold(owner_ref, pre_edge_kind, contended);
         let lock_id = self.handle.id().clone();
 
         HELD_MUTEX_STACK.with(|stack| {
@@ -126,6 +223,8 @@ impl<T> SyncMutex<T> {
             name,
             LockEntity {
                 kind: LockKind::Mutex,
+                holds: None,
+                rwlock: None,
             },
         );
         Self {
@@ -139,16 +238,20 @@ impl<T> SyncMutex<T> {
         let owner_ref = current_causal_target_with_task_fallback();
 
         if let Some(inner) = self.inner.try_lock() {
-            return self.wrap_guard(inner, owner_ref.as_ref(), None);
+            return self.wrap_guard(inner, owner_ref.as_ref(), None, false);
         }
 
-        let waiting_edge = owner_ref
-            .as_ref()
-            .map(|owner| owner.link_to_owned(&self.handle, EdgeKind::WaitingOn));
+        let waiting_edge = owner_ref.as_ref().map(|owner| {
+            owner.link_to_owned_with_backtrace(
+                &self.handle,
+                EdgeKind::WaitingOn,
+                lock_edge_backtrace(&self.handle, true),
+            )
+        });
         let inner = self.inner.lock();
         drop(waiting_edge);
 
-        self.wrap_guard(inner, owner_ref.as_ref(), None)
+        self.wrap_guard(inner, owner_ref.as_ref(), None, true)
     }
 
     /// Attempts lock acquisition without blocking, matching [`parking_lot::Mutex::try_lock`].
@@ -156,7 +259,7 @@ impl<T> SyncMutex<T> {
         let owner_ref = current_causal_target_with_task_fallback();
         self.inner
             .try_lock()
-            .map(|inner| self.wrap_guard(inner, owner_ref.as_ref(), Some(EdgeKind::Polls)))
+            .map(|inner| self.wrap_guard(inner, owner_ref.as_ref(), Some(EdgeKind::Polls), false))
     }
 
     fn wrap_guard<'a>(
@@ -164,12 +267,19 @@ impl<T> SyncMutex<T> {
         inner: parking_lot::MutexGuard<'a, T>,
         owner_ref: Option<&EntityRef>,
         pre_edge_kind: Option<EdgeKind>,
+        contended: bool,
     ) -> SyncMutexGuard<'a, T> {
         if let (Some(owner), Some(kind)) = (owner_ref, pre_edge_kind) {
             self.handle.link_to(owner, kind);
         }
 
-        let holds_edge = owner_ref.map(|owner| self.handle.link_to_owned(owner, EdgeKind::HeldBy));
+        let holds_edge = owner_ref.map(|owner| {
+            self.handle.link_to_owned_with_backtrace(
+                owner,
+                EdgeKind::HeldBy,
+                lock_edge_backtrace(&self.handle, contended),
+            )
+        });
         let lock_id = self.handle.id().clone();
 
         HELD_MUTEX_STACK.with(|stack| {
@@ -238,6 +348,12 @@ impl<T: fmt::Debug> fmt::Debug for MutexGuard<'_, T> {
     }
 }
 
+impl<T: fmt::Debug> fmt::Debug for OwnedMutexGuard<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.inner.fmt(f)
+    }
+}
+
 impl<T: fmt::Debug> fmt::Debug for SyncMutex<T> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         self.inner.fmt(f)
@@ -1,14 +1,40 @@
 // r[impl api.once-cell]
-use moire_types::{OnceCellEntity, OnceCellState};
+use facet::Facet;
+use moire_types::{EdgeKind, EntityId, Json, OnceCellEntity, OnceCellState};
 use std::fmt;
-use std::future::Future;
+use std::future::{Future, poll_fn};
+use std::pin::pin;
+use std::task::Poll;
+use std::time::{Duration, Instant};
 
-use moire_runtime::{EntityHandle, instrument_operation_on};
+use moire_runtime::{
+    EdgeHandle, EntityHandle, EntityRef, current_causal_target_with_task_fallback,
+};
 
 /// Instrumented version of [`tokio::sync::OnceCell`].
 pub struct OnceCell<T> {
     inner: tokio::sync::OnceCell<T>,
     handle: EntityHandle<moire_types::OnceCell>,
+    initializer: parking_lot::Mutex<Option<InitAttempt>>,
+}
+
+/// The init function currently running, as seen by the waiter that started it.
+///
+/// Only [`InitRunning`] sets it, and its drop clears it, so it never outlives
+/// the init function: completing, failing, panicking and being cancelled all
+/// drop the guard.
+struct InitAttempt {
+    task: Option<EntityId>,
+    started: Instant,
+}
+
+/// Payload of the `once_cell_init_slow` event.
+#[derive(Facet)]
+struct InitSlowPayload {
+    caller: String,
+    initializer: Option<String>,
+    init_running_ms: Option<u64>,
+    waited_ms: u64,
 }
 
 impl<T> OnceCell<T> {
@@ -19,11 +45,14 @@ impl<T> OnceCell<T> {
             OnceCellEntity {
                 waiter_count: 0,
                 state: OnceCellState::Empty,
+                initializer: None,
+                waiters: Vec::new(),
             },
         );
         Self {
             inner: tokio::sync::OnceCell::new(),
             handle,
+            initializer: parking_lot::Mutex::new(None),
         }
     }
 
@@ -48,7 +77,9 @@ impl<T> OnceCell<T> {
             body.state = OnceCellState::Initializing;
         });
 
-        let result = instrument_operation_on(&self.handle, self.inner.get_or_init(f)).await;
+        let result = self
+            .wait_for_init(self.inner.get_or_init(|| self.track_init(f)))
+            .await;
 
         let initialized = self.inner.initialized();
         let _ = self.handle.mutate(|body| {
@@ -75,7 +106,9 @@ impl<T> OnceCell<T> {
             body.state = OnceCellState::Initializing;
         });
 
-        let result = instrument_operation_on(&self.handle, self.inner.get_or_try_init(f)).await;
+        let result = self
+            .wait_for_init(self.inner.get_or_try_init(|| self.track_init(f)))
+            .await;
 
         let initialized = self.inner.initialized();
         let _ = self.handle.mutate(|body| {
@@ -92,6 +125,93 @@ impl<T> OnceCell<T> {
         result
     }
 
+    /// Like [`OnceCell::get_or_init`], but reports init functions that take
+    /// longer than `timeout`.
+    ///
+    /// Every time `timeout` elapses without the cell being initialized, a
+    /// `once_cell_init_slow` event is emitted on the cell naming `caller`, the
+    /// task running the init function, and how long it has been running. The
+    /// call keeps waiting afterwards: this only makes a hung init visible, it
+    /// does not abandon it.
+    pub async fn get_or_init_timeout<'a, F, Fut>(
+        &'a self,
+        caller: impl Into<String>,
+        timeout: Duration,
+        f: F,
+    ) -> &'a T
+    where
+        F: FnOnce() -> Fut + 'a,
+        Fut: Future<Output = T> + 'a,
+    {
+        let caller = caller.into();
+        let waiting_since = Instant::now();
+        let mut init = pin!(self.get_or_init(f));
+        loop {
+            match tokio::time::timeout(timeout, init.as_mut()).await {
+                Ok(value) => return value,
+                Err(_) => self.report_slow_init(&caller, waiting_since.elapsed()),
+            }
+        }
+    }
+
+    /// Drives a `get_or_init` call. While another task runs the init
+    /// function, the caller waits on the cell and is listed in its `waiters`;
+    /// the caller running it is not waiting on the cell, so it gets no edge.
+    async fn wait_for_init<R>(&self, init: impl Future<Output = R>) -> R {
+        let caller = current_causal_target_with_task_fallback();
+        let mut init = pin!(init);
+        let mut waiting: Option<Waiter<'_, T>> = None;
+        poll_fn(|cx| {
+            let poll = init.as_mut().poll(cx);
+            match (&poll, &caller) {
+                (Poll::Pending, Some(caller)) if !self.is_initializer(caller) => {
+                    waiting.get_or_insert_with(|| Waiter::enter(self, caller));
+                }
+                _ => waiting = None,
+            }
+            poll
+        })
+        .await
+    }
+
+    fn is_initializer(&self, caller: &EntityRef) -> bool {
+        self.initializer
+            .lock()
+            .as_ref()
+            .is_some_and(|attempt| attempt.task.as_ref() == Some(caller.id()))
+    }
+
+    /// Runs the init function under an [`InitRunning`] guard, which lives in
+    /// this future: it is dropped when `f` returns, and with the future when
+    /// the caller running it gives up.
+    async fn track_init<Fut: Future>(&self, f: impl FnOnce() -> Fut) -> Fut::Output {
+        let _running = InitRunning::enter(self);
+        f().await
+    }
+
+    fn report_slow_init(&self, caller: &str, waited: Duration) {
+        let (initializer, init_running_ms) = match self.initializer.lock().as_ref() {
+            Some(attempt) => (
+                attempt.task.as_ref().map(|id| String::from(id.as_str())),
+                Some(duration_ms(attempt.started.elapsed())),
+            ),
+            None => (None, None),
+        };
+        let payload = InitSlowPayload {
+            caller: String::from(caller),
+            initializer,
+            init_running_ms,
+            waited_ms: duration_ms(waited),
+        };
+        let payload = facet_json::to_string(&payload)
+            .expect("invariant violated: once-cell slow init payload must serialize");
+        self.handle.emit_event(
+            "once_cell_init_slow",
+            "OnceCell Init Slow",
+            Json::new(payload),
+        );
+    }
+
     /// Sets the value, matching [`tokio::sync::OnceCell::set`].
     pub fn set(&self, value: T) -> Result<(), T> {
         let result = self.inner.set(value).map_err(|e| match e {
@@ -112,6 +232,71 @@ impl<T> OnceCell<T> {
     }
 }
 
+/// The init function running, from its first poll until it completes or is
+/// cancelled. Meanwhile the cell is `held_by` the task running it.
+struct InitRunning<'a, T> {
+    cell: &'a OnceCell<T>,
+    _held_by: Option<EdgeHandle>,
+}
+
+impl<'a, T> InitRunning<'a, T> {
+    fn enter(cell: &'a OnceCell<T>) -> Self {
+        let task = current_causal_target_with_task_fallback();
+        *cell.initializer.lock() = Some(InitAttempt {
+            task: task.as_ref().map(|task| task.id().clone()),
+            started: Instant::now(),
+        });
+        let _ = cell
+            .handle
+            .mutate(|body| body.initializer = task.as_ref().map(|task| task.id().clone()));
+        Self {
+            cell,
+            _held_by: task.map(|task| cell.handle.link_to_owned(&task, EdgeKind::HeldBy)),
+        }
+    }
+}
+
+impl<T> Drop for InitRunning<'_, T> {
+    fn drop(&mut self) {
+        *self.cell.initializer.lock() = None;
+        let _ = self.cell.handle.mutate(|body| body.initializer = None);
+    }
+}
+
+/// A caller of `get_or_init` blocked on another task's init function.
+struct Waiter<'a, T> {
+    cell: &'a OnceCell<T>,
+    caller: EntityId,
+    _waiting_on: EdgeHandle,
+}
+
+impl<'a, T> Waiter<'a, T> {
+    fn enter(cell: &'a OnceCell<T>, caller: &EntityRef) -> Self {
+        let _ = cell
+            .handle
+            .mutate(|body| body.waiters.push(caller.id().clone()));
+        Self {
+            cell,
+            caller: caller.id().clone(),
+            _waiting_on: caller.link_to_owned(&cell.handle, EdgeKind::WaitingOn),
+        }
+    }
+}
+
+impl<T> Drop for Waiter<'_, T> {
+    fn drop(&mut self) {
+        let _ = self.cell.handle.mutate(|body| {
+            if let Some(index) = body.waiters.iter().position(|id| *id == self.caller) {
+                body.waiters.remove(index);
+            }
+        });
+    }
+}
+
+fn duration_ms(duration: Duration) -> u64 {
+    duration.as_millis().min(u64::MAX as u128) as u64
+}
+
 impl<T: fmt::Debug> fmt::Debug for OnceCell<T> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         self.inner.fmt(f)
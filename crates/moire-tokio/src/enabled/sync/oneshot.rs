@@ -3,7 +3,8 @@
 pub use tokio::sync::oneshot::error;
 
 use moire_runtime::{
-    EntityHandle, WeakEntityHandle, instrument_operation_on, new_event, record_event,
+    EntityHandle, WeakEntityHandle, current_causal_target_with_task_fallback,
+    instrument_operation_on, new_event, record_event,
 };
 use moire_types::{EdgeKind, EventKind, EventTarget, OneshotRxEntity, OneshotTxEntity};
 use std::fmt;
@@ -12,12 +13,16 @@ use std::pin::Pin;
 use std::task::{Context, Poll};
 use tokio::sync::oneshot;
 
+use super::owned_hold::OwnedHold;
+
 /// Instrumented version of [`tokio::sync::oneshot::Sender`].
 ///
-/// Tracks send outcome for diagnostics.
+/// Tracks send outcome for diagnostics, and which future holds the sender:
+/// the sender is `held_by` the last future that used it.
 pub struct Sender<T> {
     inner: Option<tokio::sync::oneshot::Sender<T>>,
     handle: EntityHandle<moire_types::OneshotTx>,
+    holder: OwnedHold<moire_types::OneshotTx>,
 }
 
 /// Instrumented version of [`tokio::sync::oneshot::Receiver`].
@@ -60,6 +65,11 @@ impl<T> IntoFuture for Receiver<T> {
     type IntoFuture = ReceiverFuture<T>;
 
     fn into_future(self) -> Self::IntoFuture {
+        if let Some(receiver) = current_causal_target_with_task_fallback() {
+            let _ = self
+                .handle
+                .mutate(|body| body.receiver = Some(receiver.id().clone()));
+        }
         ReceiverFuture {
             inner: instrument_operation_on(&self.handle, self.inner),
             handle: self.handle,
@@ -73,9 +83,34 @@ impl<T> Sender<T> {
     pub fn handle(&self) -> &EntityHandle<moire_types::OneshotTx> {
         &self.handle
     }
+
+    /// Waits for the receiver to be dropped, equivalent to
+    /// [`tokio::sync::oneshot::Sender::closed`].
+    pub async fn closed(&mut self) {
+        self.observe_holder();
+        if let Some(inner) = self.inner.as_mut() {
+            inner.closed().await;
+        }
+    }
+
+    /// Equivalent to [`tokio::sync::oneshot::Sender::is_closed`].
+    pub fn is_closed(&self) -> bool {
+        self.observe_holder();
+        self.inner.as_ref().is_none_or(|inner| inner.is_closed())
+    }
+
+    /// Senders are usually created by the task that awaits the receiver and
+    /// moved into another, so the `held_by` edge is drawn from the future
+    /// using the sender rather than the one creating it, and follows it.
+    fn observe_holder(&self) {
+        self.holder
+            .observe_with(|tx, holder| tx.link_to_owned(holder, EdgeKind::HeldBy));
+    }
+
     /// Sends a single value, equivalent to [`tokio::sync::oneshot::Sender::send`].
     /// Records a one-shot send event and consumption status.
     pub fn send(mut self, value: T) -> Result<(), T> {
+        self.observe_holder();
         let Some(inner) = self.inner.take() else {
             return Err(value);
         };
@@ -115,13 +150,14 @@ pub fn channel<T>(name: impl Into<String>) -> (Sender<T>, Receiver<T>) {
 
     let tx_handle = EntityHandle::new(format!("{name}:tx"), OneshotTxEntity { sent: false });
 
-    let rx_handle = EntityHandle::new(format!("{name}:rx"), OneshotRxEntity {});
+    let rx_handle = EntityHandle::new(format!("{name}:rx"), OneshotRxEntity { receiver: None });
 
     tx_handle.link_to_handle(&rx_handle, EdgeKind::PairedWith);
 
     (
         Sender {
             inner: Some(tx),
+            holder: OwnedHold::new(&tx_handle, None),
             handle: tx_handle.clone(),
         },
         Receiver {
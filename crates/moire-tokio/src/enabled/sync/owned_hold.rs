@@ -0,0 +1,77 @@
+use moire_types::EdgeKind;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Mutex as StdMutex, PoisonError};
+
+use moire_runtime::{
+    EdgeHandle, EntityHandle, EntityRef, FUTURE_CAUSAL_STACK, current_causal_target,
+    current_poll_epoch, lock_edge_backtrace,
+};
+
+/// `held_by` edge of something a future owns and can hand to another task: an
+/// owned lock guard or a oneshot sender. Whenever it is used from a future
+/// other than the one the edge names, the edge moves there.
+pub(crate) struct OwnedHold<S = moire_types::Lock> {
+    entity: EntityHandle<S>,
+    edge: StdMutex<Option<EdgeHandle>>,
+    /// Poll epoch (see `current_poll_epoch`) the holder was last checked in.
+    /// Guards are dereferenced many times per poll; only the first access of
+    /// each poll looks at the causal stack.
+    observed_epoch: AtomicU64,
+}
+
+impl<S> OwnedHold<S> {
+    pub(crate) fn new(entity: &EntityHandle<S>, edge: Option<EdgeHandle>) -> Self {
+        Self {
+            entity: entity.clone(),
+            edge: StdMutex::new(edge),
+            observed_epoch: AtomicU64::new(0),
+        }
+    }
+
+    /// Re-attributes the hold to the future currently being polled, if any.
+    /// `link` draws the edge when there is none yet.
+    pub(crate) fn observe_with(
+        &self,
+        link: impl FnOnce(&EntityHandle<S>, &EntityRef) -> EdgeHandle,
+    ) {
+        let epoch = current_poll_epoch();
+        if self.observed_epoch.swap(epoch, Ordering::Relaxed) == epoch {
+            return;
+        }
+        let mut edge = self.edge.lock().unwrap_or_else(PoisonError::into_inner);
+        let unchanged = FUTURE_CAUSAL_STACK
+            .try_with(|stack| match (stack.borrow().last(), edge.as_ref()) {
+                (None, _) => true,
+                (Some(current), Some(edge)) => current == edge.dst(),
+                (Some(_), None) => false,
+            })
+            .unwrap_or(true);
+        if unchanged {
+            return;
+        }
+        let Some(holder) = current_causal_target() else {
+            return;
+        };
+        match edge.as_mut() {
+            Some(edge) => edge.retarget(&holder),
+            None => *edge = Some(link(&self.entity, &holder)),
+        }
+    }
+}
+
+impl OwnedHold {
+    pub(crate) fn lock(&self) -> &EntityHandle<moire_types::Lock> {
+        &self.entity
+    }
+
+    /// Re-attributes the hold to the future currently being polled, if any.
+    pub(crate) fn observe(&self) {
+        self.observe_with(|lock, holder| {
+            lock.link_to_owned_with_backtrace(
+                holder,
+                EdgeKind::HeldBy,
+                lock_edge_backtrace(lock, false),
+            )
+        });
+    }
+}
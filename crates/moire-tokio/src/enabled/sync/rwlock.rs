@@ -1,37 +1,109 @@
 // r[impl api.rwlock]
-use moire_types::{EdgeKind, LockEntity, LockKind};
+use moire_types::{EdgeKind, EdgeReason, LockEntity, LockKind, RwLockState};
 use std::fmt;
 use std::ops::{Deref, DerefMut};
+use std::sync::Arc;
 
 use moire_runtime::{
     AsEntityRef, EdgeHandle, EntityHandle, EntityRef, current_causal_target_with_task_fallback,
-    instrument_operation_on_with_actor,
+    instrument_operation_on_with_actor_and_backtrace, lock_edge_backtrace,
 };
 
+use super::owned_hold::OwnedHold;
+
 /// Instrumented version of [`tokio::sync::RwLock`].
 pub struct RwLock<T> {
-    inner: tokio::sync::RwLock<T>,
+    inner: Arc<tokio::sync::RwLock<T>>,
     handle: EntityHandle<moire_types::Lock>,
 }
 
 /// Read guard returned by [`RwLock::read`].
 pub struct RwLockReadGuard<'a, T> {
     inner: tokio::sync::RwLockReadGuard<'a, T>,
+    handle: &'a EntityHandle<moire_types::Lock>,
     holds_edge: Option<EdgeHandle>,
 }
 
 /// Write guard returned by [`RwLock::write`].
 pub struct RwLockWriteGuard<'a, T> {
     inner: tokio::sync::RwLockWriteGuard<'a, T>,
+    handle: &'a EntityHandle<moire_types::Lock>,
     holds_edge: Option<EdgeHandle>,
 }
 
-/// Instrumented version of [`parking_lot::RwLock`].
+/// Read guard returned by [`RwLock::read_owned`]. Its `held_by` edge follows
+/// the guard to whichever task uses it.
+pub struct OwnedRwLockReadGuard<T> {
+    inner: tokio::sync::OwnedRwLockReadGuard<T>,
+    hold: OwnedHold,
+}
+
+/// Write guard returned by [`RwLock::write_owned`]. Its `held_by` edge follows
+/// the guard to whichever task uses it.
+pub struct OwnedRwLockWriteGuard<T> {
+    inner: tokio::sync::OwnedRwLockWriteGuard<T>,
+    hold: OwnedHold,
+}
+
+/// Counts a task as waiting on the lock until dropped, so a cancelled
+/// acquisition does not leave the count behind.
+struct Waiting<'a> {
+    handle: &'a EntityHandle<moire_types::Lock>,
+    write: bool,
+}
+
+impl<'a> Waiting<'a> {
+    fn new(handle: &'a EntityHandle<moire_types::Lock>, write: bool) -> Self {
+        update_state(handle, |state| {
+            if write {
+                state.waiting_writers += 1;
+            } else {
+                state.waiting_readers += 1;
+            }
+        });
+        Self { handle, write }
+    }
+}
+
+impl Drop for Waiting<'_> {
+    fn drop(&mut self) {
+        let write = self.write;
+        update_state(self.handle, |state| {
+            if write {
+                state.waiting_writers = state.waiting_writers.saturating_sub(1);
+            } else {
+                state.waiting_readers = state.waiting_readers.saturating_sub(1);
+            }
+        });
+    }
+}
+
+fn update_state(handle: &EntityHandle<moire_types::Lock>, f: impl FnOnce(&mut RwLockState)) {
+    let _ = handle.mutate(|body| f(body.rwlock.get_or_insert_with(RwLockState::default)));
+}
+
+/// Instrumented version of [`parking_lot::RwLock`], for blocking code.
 pub struct SyncRwLock<T> {
     inner: parking_lot::RwLock<T>,
     handle: EntityHandle<moire_types::Lock>,
 }
 
+/// Read guard returned by [`SyncRwLock::read`], equivalent to
+/// [`parking_lot::RwLockReadGuard`].
+pub struct SyncRwLockReadGuard<'a, T> {
+    inner: parking_lot::RwLockReadGuard<'a, T>,
+    handle: &'a EntityHandle<moire_types::Lock>,
+    holds_edge: Option<EdgeHandle>,
+}
+
+/// Write guard returned by [`SyncRwLock::write`], equivalent to
+/// [`parking_lot::RwLockWriteGuard`].
+pub struct SyncRwLockWriteGuard<'a, T> {
+    inner: parking_lot::RwLockWriteGuard<'a, T>,
+    handle: &'a EntityHandle<moire_types::Lock>,
+    holds_edge: Option<EdgeHandle>,
+}
+
 impl<'a, T> Deref for RwLockReadGuard<'a, T> {
     type Target = T;
 
@@ -54,6 +126,53 @@ impl<'a, T> DerefMut for RwLockWriteGuard<'a, T> {
     }
 }
 
+impl<T> Deref for OwnedRwLockReadGuard<T> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        self.hold.observe();
+        &self.inner
+    }
+}
+
+impl<T> Deref for OwnedRwLockWriteGuard<T> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        self.hold.observe();
+        &self.inner
+    }
+}
+
+impl<T> DerefMut for OwnedRwLockWriteGuard<T> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.hold.observe();
+        &mut self.inner
+    }
+}
+
+impl<'a, T> Deref for SyncRwLockReadGuard<'a, T> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        &self.inner
+    }
+}
+
+impl<'a, T> Deref for SyncRwLockWriteGuard<'a, T> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        &self.inner
+    }
+}
+
+impl<'a, T> DerefMut for SyncRwLockWriteGuard<'a, T> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.inner
+    }
+}
+
 impl<T> RwLock<T> {
     /// Creates a new instrumented async read-write lock, matching [`tokio::sync::RwLock::new`].
     pub fn new(name: &'static str, value: T) -> Self {
@@ -61,10 +180,12 @@ impl<T> RwLock<T> {
             name,
             LockEntity {
                 kind: LockKind::RwLock,
+                holds: None,
+                rwlock: Some(RwLockState::default()),
             },
         );
         Self {
-            inner: tokio::sync::RwLock::new(value),
+            inner: Arc::new(tokio::sync::RwLock::new(value)),
             handle,
         }
     }
@@ -72,64 +193,195 @@ impl<T> RwLock<T> {
     /// Acquires a shared read guard asynchronously, matching [`tokio::sync::RwLock::read`].
     pub async fn read(&self) -> RwLockReadGuard<'_, T> {
         let owner_ref = current_causal_target_with_task_fallback();
-        let inner =
-            instrument_operation_on_with_actor(&self.handle, owner_ref.as_ref(), self.inner.read())
-                .await;
-        self.wrap_read_guard(inner, owner_ref.as_ref(), None)
+        if let Ok(inner) = self.inner.try_read() {
+            return self.wrap_read_guard(inner, owner_ref.as_ref(), None, false);
+        }
+
+        let waiting = Waiting::new(&self.handle, false);
+        let inner = instrument_operation_on_with_actor_and_backtrace(
+            &self.handle,
+            owner_ref.as_ref(),
+            self.inner.read(),
+            lock_edge_backtrace(&self.handle, true),
+        )
+        .with_reason(EdgeReason::RwlockReadWait)
+        .await;
+        drop(waiting);
+        self.wrap_read_guard(inner, owner_ref.as_ref(), None, true)
     }
 
     /// Acquires an exclusive write guard asynchronously, matching [`tokio::sync::RwLock::write`].
     pub async fn write(&self) -> RwLockWriteGuard<'_, T> {
         let owner_ref = current_causal_target_with_task_fallback();
-        let inner = instrument_operation_on_with_actor(
+        if let Ok(inner) = self.inner.try_write() {
+            return self.wrap_write_guard(inner, owner_ref.as_ref(), None, false);
+        }
+
+        let waiting = Waiting::new(&self.handle, true);
+        let inner = instrument_operation_on_with_actor_and_backtrace(
             &self.handle,
             owner_ref.as_ref(),
             self.inner.write(),
+            lock_edge_backtrace(&self.handle, true),
         )
+        .with_reason(EdgeReason::RwlockWriteWait)
         .await;
-        self.wrap_write_guard(inner, owner_ref.as_ref(), None)
+        drop(waiting);
+        self.wrap_write_guard(inner, owner_ref.as_ref(), None, true)
     }
 
     /// Attempts a non-blocking read lock, matching [`tokio::sync::RwLock::try_read`].
     pub fn try_read(&self) -> Result<RwLockReadGuard<'_, T>, tokio::sync::TryLockError> {
         let owner_ref = current_causal_target_with_task_fallback();
-        self.inner
-            .try_read()
-            .map(|inner| self.wrap_read_guard(inner, owner_ref.as_ref(), Some(EdgeKind::Polls)))
+        self.inner.try_read().map(|inner| {
+            self.wrap_read_guard(inner, owner_ref.as_ref(), Some(EdgeKind::Polls), false)
+        })
     }
 
     /// Attempts a non-blocking write lock, matching [`tokio::sync::RwLock::try_write`].
     pub fn try_write(&self) -> Result<RwLockWriteGuard<'_, T>, tokio::sync::TryLockError> {
         let owner_ref = current_causal_target_with_task_fallback();
-        self.inner
-            .try_write()
-            .map(|inner| self.wrap_write_guard(inner, owner_ref.as_ref(), Some(EdgeKind::Polls)))
+        self.inner.try_write().map(|inner| {
+            self.wrap_write_guard(inner, owner_ref.as_ref(), Some(EdgeKind::Polls), false)
+        })
     }
 
-    fn wrap_read_guard<'a>(
+    /// Acquires a shared read guard asynchronously, matching [`tokio::sync::RwLock::read_owned`].
+    pub async fn read_owned(self: Arc<Self>) -> OwnedRwLockReadGuard<T> {
+        let owner_ref = current_causal_target_with_task_fallback();
+        if let Ok(inner) = Arc::clone(&self.inner).try_read_owned() {
+            let holds_edge = self.hold(owner_ref.as_ref(), None, false);
+            return self.wrap_owned_read_guard(inner, holds_edge);
+        }
+
+        let waiting = Waiting::new(&self.handle, false);
+        let inner = instrument_operation_on_with_actor_and_backtrace(
+            &self.handle,
+            owner_ref.as_ref(),
+            Arc::clone(&self.inner).read_owned(),
+            lock_edge_backtrace(&self.handle, true),
+        )
+        .with_reason(EdgeReason::RwlockReadWait)
+        .await;
+        drop(waiting);
+        let holds_edge = self.hold(owner_ref.as_ref(), None, true);
+        self.wrap_owned_read_guard(inner, holds_edge)
+    }
+
+    /// Acquires an exclusive write guard asynchronously, matching [`tokio::sync::RwLock::write_owned`].
+    pub async fn write_owned(self: Arc<Self>) -> OwnedRwLockWriteGuard<T> {
+        let owner_ref = current_causal_target_with_task_fallback();
+        if let Ok(inner) = Arc::clone(&self.inner).try_write_owned() {
+            let holds_edge = self.hold(owner_ref.as_ref(), None, false);
+            return self.wrap_owned_write_guard(inner, holds_edge);
+        }
+
+        let waiting = Waiting::new(&self.handle, true);
+        let inner = instrument_operation_on_with_actor_and_backtrace(
+            &self.handle,
+            owner_ref.as_ref(),
+            Arc::clone(&self.inner).write_owned(),
+            lock_edge_backtrace(&self.handle, true),
+        )
+        .with_reason(EdgeReason::RwlockWriteWait)
+        .await;
+        drop(waiting);
+        let holds_edge = self.hold(owner_ref.as_ref(), None, true);
+        self.wrap_owned_write_guard(inner, holds_edge)
+    }
+
+    /// Attempts a non-blocking read lock, matching [`tokio::sync::RwLock::try_read_owned`].
+    pub fn try_read_owned(
+        self: Arc<Self>,
+    ) -> Result<OwnedRwLockReadGuard<T>, tokio::sync::TryLockError> {
+        let owner_ref = current_causal_target_with_task_fallback();
+        let inner = Arc::clone(&self.inner).try_read_owned()?;
+        let holds_edge = self.hold(owner_ref.as_ref(), Some(EdgeKind::Polls), false);
+        Ok(self.wrap_owned_read_guard(inner, holds_edge))
+    }
+
+    /// Attempts a non-blocking write lock, matching [`tokio::sync::RwLock::try_write_owned`].
+    pub fn try_write_owned(
+        self: Arc<Self>,
+    ) -> Result<OwnedRwLockWriteGuard<T>, tokio::sync::TryLockError> {
+        let owner_ref = current_causal_target_with_task_fallback();
+        let inner = Arc::clone(&self.inner).try_write_owned()?;
+        let holds_edge = self.hold(owner_ref.as_ref(), Some(EdgeKind::Polls), false);
+        Ok(self.wrap_owned_write_guard(inner, holds_edge))
+    }
+
+    fn hold(
         &self,
-        inner: tokio::sync::RwLockReadGuard<'a, T>,
         owner_ref: Option<&EntityRef>,
         pre_edge_kind: Option<EdgeKind>,
-    ) -> RwLockReadGuard<'a, T> {
+        contended: bool,
+    ) -> Option<EdgeHandle> {
         if let (Some(owner), Some(kind)) = (owner_ref, pre_edge_kind) {
             self.handle.link_to(owner, kind);
         }
-        let holds_edge = owner_ref.map(|owner| self.handle.link_to_owned(owner, EdgeKind::HeldBy));
-        RwLockReadGuard { inner, holds_edge }
+        owner_ref.map(|owner| {
+            self.handle.link_to_owned_with_backtrace(
+                owner,
+                EdgeKind::HeldBy,
+                lock_edge_backtrace(&self.handle, contended),
+            )
+        })
+    }
+
+    fn wrap_read_guard<'a>(
+        &'a self,
+        inner: tokio::sync::RwLockReadGuard<'a, T>,
+        owner_ref: Option<&EntityRef>,
+        pre_edge_kind: Option<EdgeKind>,
+        contended: bool,
+    ) -> RwLockReadGuard<'a, T> {
+        let holds_edge = self.hold(owner_ref, pre_edge_kind, contended);
+        update_state(&self.handle, |state| state.readers += 1);
+        RwLockReadGuard {
+            inner,
+            handle: &self.handle,
+            holds_edge,
+        }
     }
 
     fn wrap_write_guard<'a>(
-        &self,
+        &'a self,
         inner: tokio::sync::RwLockWriteGuard<'a, T>,
         owner_ref: Option<&EntityRef>,
         pre_edge_kind: Option<EdgeKind>,
+        contended: bool,
     ) -> RwLockWriteGuard<'a, T> {
-        if let (Some(owner), Some(kind)) = (owner_ref, pre_edge_kind) {
-            self.handle.link_to(owner, kind);
+        let holds_edge = self.hold(owner_ref, pre_edge_kind, contended);
+        update_state(&self.handle, |state| state.writer = true);
+        RwLockWriteGuard {
+            inner,
+            handle: &self.handle,
+            holds_edge,
+        }
+    }
+
+    fn wrap_owned_read_guard(
+        &self,
+        inner: tokio::sync::OwnedRwLockReadGuard<T>,
+        holds_edge: Option<EdgeHandle>,
+    ) -> OwnedRwLockReadGuard<T> {
+        update_state(&self.handle, |state| state.readers += 1);
+        OwnedRwLockReadGuard {
+            inner,
+            hold: OwnedHold::new(&self.handle, holds_edge),
+        }
+    }
+
+    fn wrap_owned_write_guard(
+        &self,
+        inner: tokio::sync::OwnedRwLockWriteGuard<T>,
+        holds_edge: Option<EdgeHandle>,
+    ) -> OwnedRwLockWriteGuard<T> {
+        update_state(&self.handle, |state| state.writer = true);
+        OwnedRwLockWriteGuard {
+            inner,
+            hold: OwnedHold::new(&self.handle, holds_edge),
         }
-        let holds_edge = owner_ref.map(|owner| self.handle.link_to_owned(owner, EdgeKind::HeldBy));
-        RwLockWriteGuard { inner, holds_edge }
     }
 }
 
@@ -140,6 +392,8 @@ impl<T> SyncRwLock<T> {
             name,
             LockEntity {
                 kind: LockKind::RwLock,
+                holds: None,
+                rwlock: Some(RwLockState::default()),
             },
         );
         Self {
@@ -149,35 +403,110 @@ impl<T> SyncRwLock<T> {
     }
 
     /// Acquires a shared read guard, equivalent to [`parking_lot::RwLock::read`].
-    pub fn read(&self) -> parking_lot::RwLockReadGuard<'_, T> {
-        if let Some(caller) = current_causal_target_with_task_fallback() {
-            self.handle.link_to(&caller, EdgeKind::Polls);
+    pub fn read(&self) -> SyncRwLockReadGuard<'_, T> {
+        let owner_ref = current_causal_target_with_task_fallback();
+        if let Some(inner) = self.inner.try_read() {
+            return self.wrap_read_guard(inner, owner_ref.as_ref(), None, false);
         }
-        self.inner.read()
+
+        let waiting = Waiting::new(&self.handle, false);
+        let waiting_edge = self.wait(owner_ref.as_ref(), EdgeReason::RwlockReadWait);
+        let inner = self.inner.read();
+        drop(waiting_edge);
+        drop(waiting);
+        self.wrap_read_guard(inner, owner_ref.as_ref(), None, true)
     }
 
     /// Acquires an exclusive write guard, equivalent to [`parking_lot::RwLock::write`].
-    pub fn write(&self) -> parking_lot::RwLockWriteGuard<'_, T> {
-        if let Some(caller) = current_causal_target_with_task_fallback() {
-            self.handle.link_to(&caller, EdgeKind::Polls);
+    pub fn write(&self) -> SyncRwLockWriteGuard<'_, T> {
+        let owner_ref = current_causal_target_with_task_fallback();
+        if let Some(inner) = self.inner.try_write() {
+            return self.wrap_write_guard(inner, owner_ref.as_ref(), None, false);
         }
-        self.inner.write()
+
+        let waiting = Waiting::new(&self.handle, true);
+        let waiting_edge = self.wait(owner_ref.as_ref(), EdgeReason::RwlockWriteWait);
+        let inner = self.inner.write();
+        drop(waiting_edge);
+        drop(waiting);
+        self.wrap_write_guard(inner, owner_ref.as_ref(), None, true)
     }
 
     /// Attempts a non-blocking read lock, matching [`parking_lot::RwLock::try_read`].
-    pub fn try_read(&self) -> Option<parking_lot::RwLockReadGuard<'_, T>> {
-        if let Some(caller) = current_causal_target_with_task_fallback() {
-            self.handle.link_to(&caller, EdgeKind::Polls);
-        }
-        self.inner.try_read()
+    pub fn try_read(&self) -> Option<SyncRwLockReadGuard<'_, T>> {
+        let owner_ref = current_causal_target_with_task_fallback();
+        self.inner.try_read().map(|inner| {
+            self.wrap_read_guard(inner, owner_ref.as_ref(), Some(EdgeKind::Polls), false)
+        })
     }
 
     /// Attempts a non-blocking write lock, matching [`parking_lot::RwLock::try_write`].
-    pub fn try_write(&self) -> Option<parking_lot::RwLockWriteGuard<'_, T>> {
-        if let Some(caller) = current_causal_target_with_task_fallback() {
-            self.handle.link_to(&caller, EdgeKind::Polls);
+    pub fn try_write(&self) -> Option<SyncRwLockWriteGuard<'_, T>> {
+        let owner_ref = current_causal_target_with_task_fallback();
+        self.inner.try_write().map(|inner| {
+            self.wrap_write_guard(inner, owner_ref.as_ref(), Some(EdgeKind::Polls), false)
+        })
+    }
+
+    fn wait(&self, owner_ref: Option<&EntityRef>, reason: EdgeReason) -> Option<EdgeHandle> {
+        owner_ref.map(|owner| {
+            owner.link_to_owned_with_reason(
+                &self.handle,
+                EdgeKind::WaitingOn,
+                lock_edge_backtrace(&self.handle, true),
+                reason,
+            )
+        })
+    }
+
+    fn hold(
+        &self,
+        owner_ref: Option<&EntityRef>,
+        pre_edge_kind: Option<EdgeKind>,
+        contended: bool,
+    ) -> Option<EdgeHandle> {
+        if let (Some(owner), Some(kind)) = (owner_ref, pre_edge_kind) {
+            self.handle.link_to(owner, kind);
+        }
+        owner_ref.map(|owner| {
+            self.handle.link_to_owned_with_backtrace(
+                owner,
+                EdgeKind::HeldBy,
+                lock_edge_backtrace(&self.handle, contended),
+            )
+        })
+    }
+
+    fn wrap_read_guard<'a>(
+        &'a self,
+        inner: parking_lot::RwLockReadGuard<'a, T>,
+        owner_ref: Option<&EntityRef>,
+        pre_edge_kind: Option<EdgeKind>,
+        contended: bool,
+    ) -> SyncRwLockReadGuard<'a, T> {
+        let holds_edge = self.hold(owner_ref, pre_edge_kind, contended);
+        update_state(&self.handle, |state| state.readers += 1);
+        SyncRwLockReadGuard {
+            inner,
+            handle: &self.handle,
+            holds_edge,
+        }
+    }
+
+    fn wrap_write_guard<'a>(
+        &'a self,
+        inner: parking_lot::RwLockWriteGuard<'a, T>,
+        owner_ref: Option<&EntityRef>,
+        pre_edge_kind: Option<EdgeKind>,
+        contended: bool,
+    ) -> SyncRwLockWriteGuard<'a, T> {
+        let holds_edge = self.hold(owner_ref, pre_edge_kind, contended);
+        update_state(&self.handle, |state| state.writer = true);
+        SyncRwLockWriteGuard {
+            inner,
+            handle: &self.handle,
+            holds_edge,
         }
-        self.inner.try_write()
     }
 }
 
@@ -196,12 +525,46 @@ impl<T> AsEntityRef for SyncRwLock<T> {
 impl<T> Drop for RwLockReadGuard<'_, T> {
     fn drop(&mut self) {
         let _ = self.holds_edge.take();
+        update_state(self.handle, |state| {
+            state.readers = state.readers.saturating_sub(1)
+        });
     }
 }
 
 impl<T> Drop for RwLockWriteGuard<'_, T> {
     fn drop(&mut self) {
         let _ = self.holds_edge.take();
+        update_state(self.handle, |state| state.writer = false);
+    }
+}
+
+impl<T> Drop for SyncRwLockReadGuard<'_, T> {
+    fn drop(&mut self) {
+        let _ = self.holds_edge.take();
+        update_state(self.handle, |state| {
+            state.readers = state.readers.saturating_sub(1)
+        });
+    }
+}
+
+impl<T> Drop for SyncRwLockWriteGuard<'_, T> {
+    fn drop(&mut self) {
+        let _ = self.holds_edge.take();
+        update_state(self.handle, |state| state.writer = false);
+    }
+}
+
+impl<T> Drop for OwnedRwLockReadGuard<T> {
+    fn drop(&mut self) {
+        update_state(self.hold.lock(), |state| {
+            state.readers = state.readers.saturating_sub(1)
+        });
+    }
+}
+
+impl<T> Drop for OwnedRwLockWriteGuard<T> {
+    fn drop(&mut self) {
+        update_state(self.hold.lock(), |state| state.writer = false);
     }
 }
 
@@ -223,6 +586,30 @@ impl<T: fmt::Debug> fmt::Debug for RwLockWriteGuard<'_, T> {
     }
 }
 
+impl<T: fmt::Debug> fmt::Debug for OwnedRwLockReadGuard<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.inner.fmt(f)
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for OwnedRwLockWriteGuard<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.inner.fmt(f)
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for SyncRwLockReadGuard<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.inner.fmt(f)
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for SyncRwLockWriteGuard<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.inner.fmt(f)
+    }
+}
+
 impl<T: fmt::Debug> fmt::Debug for SyncRwLock<T> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         self.inner.fmt(f)
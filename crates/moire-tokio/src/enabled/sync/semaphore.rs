@@ -1,14 +1,15 @@
 // r[impl api.semaphore]
-use moire_types::{EdgeKind, SemaphoreEntity};
+use moire_types::{EdgeKind, PTime, SemaphoreEntity, SemaphoreHolder};
 use std::collections::BTreeMap;
 use std::fmt;
 use std::ops::{Deref, DerefMut};
 use std::sync::atomic::{AtomicU32, Ordering};
-use std::sync::{Arc, Mutex as StdMutex};
+use std::sync::{Arc, Mutex as StdMutex, PoisonError};
 
 use moire_runtime::{
     AsEntityRef, EdgeHandle, EntityHandle, EntityRef, WeakEntityHandle,
     current_causal_target_with_task_fallback, instrument_operation_on_with_actor,
+    record_stat_sample,
 };
 
 #[derive(Clone)]
@@ -21,7 +22,8 @@ pub struct Semaphore {
 }
 
 struct HolderEdge {
-    count: u32,
+    permits: u32,
+    since: PTime,
     _edge: EdgeHandle,
 }
 
@@ -31,6 +33,7 @@ pub struct SemaphorePermit<'a> {
     semaphore: Arc<tokio::sync::Semaphore>,
     semaphore_handle: WeakEntityHandle<moire_types::Semaphore>,
     holder_ref: Option<EntityRef>,
+    permits: u32,
     holder_counts: Arc<StdMutex<BTreeMap<EntityRef, HolderEdge>>>,
     max_permits: Arc<AtomicU32>,
 }
@@ -41,6 +44,7 @@ pub struct OwnedSemaphorePermit {
     semaphore: Arc<tokio::sync::Semaphore>,
     semaphore_handle: WeakEntityHandle<moire_types::Semaphore>,
     holder_ref: Option<EntityRef>,
+    permits: u32,
     holder_counts: Arc<StdMutex<BTreeMap<EntityRef, HolderEdge>>>,
     max_permits: Arc<AtomicU32>,
 }
@@ -54,6 +58,9 @@ impl Semaphore {
             SemaphoreEntity {
                 max_permits,
                 handed_out_permits: 0,
+                holds: None,
+                holders: Vec::new(),
+                history: Vec::new(),
             },
         );
         Self {
@@ -99,7 +106,7 @@ impl Semaphore {
         )
         .await?;
         if let Some(holder_ref) = holder_ref.as_ref() {
-            self.note_holder_acquired(holder_ref);
+            self.note_holder_acquired(holder_ref, 1);
         }
         self.sync_state(self.max_permits.load(Ordering::Relaxed));
         Ok(SemaphorePermit {
@@ -107,6 +114,7 @@ impl Semaphore {
             semaphore: Arc::clone(&self.inner),
             semaphore_handle: self.handle.downgrade(),
             holder_ref,
+            permits: 1,
             holder_counts: Arc::clone(&self.holder_counts),
             max_permits: Arc::clone(&self.max_permits),
         })
@@ -124,7 +132,7 @@ impl Semaphore {
         )
         .await?;
         if let Some(holder_ref) = holder_ref.as_ref() {
-            self.note_holder_acquired(holder_ref);
+            self.note_holder_acquired(holder_ref, n);
         }
         self.sync_state(self.max_permits.load(Ordering::Relaxed));
         Ok(SemaphorePermit {
@@ -132,6 +140,7 @@ impl Semaphore {
             semaphore: Arc::clone(&self.inner),
             semaphore_handle: self.handle.downgrade(),
             holder_ref,
+            permits: n,
             holder_counts: Arc::clone(&self.holder_counts),
             max_permits: Arc::clone(&self.max_permits),
         })
@@ -146,7 +155,7 @@ impl Semaphore {
         )
         .await?;
         if let Some(holder_ref) = holder_ref.as_ref() {
-            self.note_holder_acquired(holder_ref);
+            self.note_holder_acquired(holder_ref, 1);
         }
         self.sync_state(self.max_permits.load(Ordering::Relaxed));
         Ok(OwnedSemaphorePermit {
@@ -154,6 +163,7 @@ impl Semaphore {
             semaphore: Arc::clone(&self.inner),
             semaphore_handle: self.handle.downgrade(),
             holder_ref,
+            permits: 1,
             holder_counts: Arc::clone(&self.holder_counts),
             max_permits: Arc::clone(&self.max_permits),
         })
@@ -171,7 +181,7 @@ impl Semaphore {
         )
         .await?;
         if let Some(holder_ref) = holder_ref.as_ref() {
-            self.note_holder_acquired(holder_ref);
+            self.note_holder_acquired(holder_ref, n);
         }
         self.sync_state(self.max_permits.load(Ordering::Relaxed));
         Ok(OwnedSemaphorePermit {
@@ -179,6 +189,7 @@ impl Semaphore {
             semaphore: Arc::clone(&self.inner),
             semaphore_handle: self.handle.downgrade(),
             holder_ref,
+            permits: n,
             holder_counts: Arc::clone(&self.holder_counts),
             max_permits: Arc::clone(&self.max_permits),
         })
@@ -188,12 +199,16 @@ impl Semaphore {
     pub fn try_acquire(&self) -> Result<SemaphorePermit<'_>, tokio::sync::TryAcquireError> {
         let permit = self.inner.try_acquire()?;
         let holder_ref = current_causal_target_with_task_fallback();
+        if let Some(holder_ref) = holder_ref.as_ref() {
+            self.note_holder_acquired(holder_ref, 1);
+        }
         self.sync_state(self.max_permits.load(Ordering::Relaxed));
         Ok(SemaphorePermit {
             inner: Some(permit),
             semaphore: Arc::clone(&self.inner),
             semaphore_handle: self.handle.downgrade(),
             holder_ref,
+            permits: 1,
             holder_counts: Arc::clone(&self.holder_counts),
             max_permits: Arc::clone(&self.max_permits),
         })
@@ -206,12 +221,16 @@ impl Semaphore {
     ) -> Result<SemaphorePermit<'_>, tokio::sync::TryAcquireError> {
         let permit = self.inner.try_acquire_many(n)?;
         let holder_ref = current_causal_target_with_task_fallback();
+        if let Some(holder_ref) = holder_ref.as_ref() {
+            self.note_holder_acquired(holder_ref, n);
+        }
         self.sync_state(self.max_permits.load(Ordering::Relaxed));
         Ok(SemaphorePermit {
             inner: Some(permit),
             semaphore: Arc::clone(&self.inner),
             semaphore_handle: self.handle.downgrade(),
             holder_ref,
+            permits: n,
             holder_counts: Arc::clone(&self.holder_counts),
             max_permits: Arc::clone(&self.max_permits),
         })
@@ -221,12 +240,16 @@ impl Semaphore {
     pub fn try_acquire_owned(&self) -> Result<OwnedSemaphorePermit, tokio::sync::TryAcquireError> {
         let permit = Arc::clone(&self.inner).try_acquire_owned()?;
         let holder_ref = current_causal_target_with_task_fallback();
+        if let Some(holder_ref) = holder_ref.as_ref() {
+            self.note_holder_acquired(holder_ref, 1);
+        }
         self.sync_state(self.max_permits.load(Ordering::Relaxed));
         Ok(OwnedSemaphorePermit {
             inner: Some(permit),
             semaphore: Arc::clone(&self.inner),
             semaphore_handle: self.handle.downgrade(),
             holder_ref,
+            permits: 1,
             holder_counts: Arc::clone(&self.holder_counts),
             max_permits: Arc::clone(&self.max_permits),
         })
@@ -239,12 +262,16 @@ impl Semaphore {
     ) -> Result<OwnedSemaphorePermit, tokio::sync::TryAcquireError> {
         let permit = Arc::clone(&self.inner).try_acquire_many_owned(n)?;
         let holder_ref = current_causal_target_with_task_fallback();
+        if let Some(holder_ref) = holder_ref.as_ref() {
+            self.note_holder_acquired(holder_ref, n);
+        }
         self.sync_state(self.max_permits.load(Ordering::Relaxed));
         Ok(OwnedSemaphorePermit {
             inner: Some(permit),
             semaphore: Arc::clone(&self.inner),
             semaphore_handle: self.handle.downgrade(),
             holder_ref,
+            permits: n,
             holder_counts: Arc::clone(&self.holder_counts),
             max_permits: Arc::clone(&self.max_permits),
         })
@@ -253,27 +280,36 @@ impl Semaphore {
     fn sync_state(&self, max_permits: u32) {
         let available = self.inner.available_permits().min(u32::MAX as usize) as u32;
         let handed_out = max_permits.saturating_sub(available);
+        let holder_counts = self
+            .holder_counts
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
         let _ = self.handle.mutate(|body| {
             body.max_permits = max_permits;
             body.handed_out_permits = handed_out;
+            body.holders = holder_list(&holder_counts);
+            record_stat_sample(&mut body.history, handed_out);
         });
     }
 
-    fn note_holder_acquired(&self, holder_ref: &EntityRef) {
-        if let Ok(mut holder_counts) = self.holder_counts.lock() {
-            if let Some(entry) = holder_counts.get_mut(holder_ref) {
-                entry.count = entry.count.saturating_add(1);
-                return;
-            }
-            let edge = self.handle.link_to_owned(holder_ref, EdgeKind::HeldBy);
-            holder_counts.insert(
-                holder_ref.clone(),
-                HolderEdge {
-                    count: 1,
-                    _edge: edge,
-                },
-            );
+    fn note_holder_acquired(&self, holder_ref: &EntityRef, permits: u32) {
+        let mut holder_counts = self
+            .holder_counts
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        if let Some(entry) = holder_counts.get_mut(holder_ref) {
+            entry.permits = entry.permits.saturating_add(permits);
+            return;
         }
+        let edge = self.handle.link_to_owned(holder_ref, EdgeKind::HeldBy);
+        holder_counts.insert(
+            holder_ref.clone(),
+            HolderEdge {
+                permits,
+                since: PTime::now(),
+                _edge: edge,
+            },
+        );
     }
 }
 
@@ -303,33 +339,51 @@ impl fmt::Debug for OwnedSemaphorePermit {
 
 fn holder_released(
     holder_ref: &mut Option<EntityRef>,
+    permits: u32,
     holder_counts: &Arc<StdMutex<BTreeMap<EntityRef, HolderEdge>>>,
 ) {
     let Some(holder_ref) = holder_ref.take() else {
         return;
     };
-    if let Ok(mut counts) = holder_counts.lock()
-        && let Some(entry) = counts.get_mut(&holder_ref)
-    {
-        if entry.count > 1 {
-            entry.count -= 1;
+    let mut counts = holder_counts.lock().unwrap_or_else(PoisonError::into_inner);
+    if let Some(entry) = counts.get_mut(&holder_ref) {
+        if entry.permits > permits {
+            entry.permits -= permits;
         } else {
             counts.remove(&holder_ref);
         }
     }
 }
 
+/// Current holders for the entity body, longest-held first.
+fn holder_list(holder_counts: &BTreeMap<EntityRef, HolderEdge>) -> Vec<SemaphoreHolder> {
+    let mut holders = holder_counts
+        .iter()
+        .map(|(holder_ref, entry)| SemaphoreHolder {
+            holder: holder_ref.id().clone(),
+            permits: entry.permits,
+            since: entry.since,
+        })
+        .collect::<Vec<_>>();
+    holders.sort_by_key(|holder| holder.since);
+    holders
+}
+
 fn sync_state_from_permit(
     semaphore_handle: &WeakEntityHandle<moire_types::Semaphore>,
     semaphore: &Arc<tokio::sync::Semaphore>,
     max_permits: &Arc<AtomicU32>,
+    holder_counts: &Arc<StdMutex<BTreeMap<EntityRef, HolderEdge>>>,
 ) {
     let max = max_permits.load(Ordering::Relaxed);
     let available = semaphore.available_permits().min(u32::MAX as usize) as u32;
     let handed_out = max.saturating_sub(available);
+    let holder_counts = holder_counts.lock().unwrap_or_else(PoisonError::into_inner);
     let _ = semaphore_handle.mutate(|body| {
         body.max_permits = max;
         body.handed_out_permits = handed_out;
+        body.holders = holder_list(&holder_counts);
+        record_stat_sample(&mut body.history, handed_out);
     });
 }
 
@@ -354,8 +408,13 @@ impl<'a> DerefMut for SemaphorePermit<'a> {
 impl<'a> Drop for SemaphorePermit<'a> {
     fn drop(&mut self) {
         let _ = self.inner.take();
-        holder_released(&mut self.holder_ref, &self.holder_counts);
-        sync_state_from_permit(&self.semaphore_handle, &self.semaphore, &self.max_permits);
+        holder_released(&mut self.holder_ref, self.permits, &self.holder_counts);
+        sync_state_from_permit(
+            &self.semaphore_handle,
+            &self.semaphore,
+            &self.max_permits,
+            &self.holder_counts,
+        );
     }
 }
 
@@ -380,7 +439,12 @@ impl DerefMut for OwnedSemaphorePermit {
 impl Drop for OwnedSemaphorePermit {
     fn drop(&mut self) {
         let _ = self.inner.take();
-        holder_released(&mut self.holder_ref, &self.holder_counts);
-        sync_state_from_permit(&self.semaphore_handle, &self.semaphore, &self.max_permits);
+        holder_released(&mut self.holder_ref, self.permits, &self.holder_counts);
+        sync_state_from_permit(
+            &self.semaphore_handle,
+            &self.semaphore,
+            &self.max_permits,
+            &self.holder_counts,
+        );
     }
 }
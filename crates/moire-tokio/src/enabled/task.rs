@@ -11,22 +11,27 @@
 //! |---|---|
 //! | [`JoinSet`] | [`tokio::task::JoinSet`] |
 //! | [`JoinHandle`] | [`tokio::task::JoinHandle`] |
+//! | [`TaskScope`], [`scope`] | *(moire extension)* |
 //! | [`spawn`] | [`tokio::task::spawn`] |
 //! | [`spawn_blocking`] | [`tokio::task::spawn_blocking`] |
 //! | [`FutureExt`] | *(moire extension)* |
 
 pub mod join_handle;
 pub mod joinset;
+pub mod task_scope;
 
 pub use self::join_handle::*;
 pub use self::joinset::*;
+pub use self::task_scope::*;
+pub use moire_runtime::{long_poll_threshold, set_long_poll_threshold};
 
 use std::cell::RefCell;
 use std::future::{Future, IntoFuture};
+use std::panic::Location;
 
 use moire_runtime::{
-    EntityHandle, FUTURE_CAUSAL_STACK, InstrumentedFuture, instrument_future,
-    instrument_future_with_handle, register_current_task_scope,
+    EntityHandle, FUTURE_CAUSAL_STACK, InstrumentedFuture, current_task_target, instrument_future,
+    instrument_future_with_handle, logical_task_id, register_current_task_scope,
 };
 use moire_types::FutureEntity;
 
@@ -46,34 +51,59 @@ pub trait FutureExt: IntoFuture + Sized {
     fn named(self, name: impl Into<String>) -> InstrumentedFuture<Self::IntoFuture> {
         instrument_future(name, self.into_future(), None, None)
     }
+
+    /// Wraps this future as an intentional, possibly endless wait, named after
+    /// `reason`. See [`InstrumentedFuture::idle`].
+    fn idle(self, reason: impl Into<String>) -> InstrumentedFuture<Self::IntoFuture> {
+        let reason = reason.into();
+        instrument_future(reason.clone(), self.into_future(), None, None).idle(reason)
+    }
 }
 
 impl<F: IntoFuture + Sized> FutureExt for F {}
 
 /// Spawns a task, equivalent to [`tokio::task::spawn`].
+#[track_caller]
 pub fn spawn<T, F>(future: F) -> JoinHandle<T>
 where
     T: Send + 'static,
     F: Future<Output = T> + Send + 'static,
 {
-    let handle = EntityHandle::new("task.spawn", FutureEntity::default());
+    let logical_id = logical_task_id("task.spawn", Location::caller());
+    let handle = EntityHandle::new(
+        "task.spawn",
+        FutureEntity {
+            logical_id: Some(logical_id.clone()),
+            spawned_by: current_task_target().map(|task| task.id().clone()),
+            ..FutureEntity::default()
+        },
+    );
     let future_handle = handle.clone();
     let fut = FUTURE_CAUSAL_STACK.scope(RefCell::new(Vec::new()), async move {
-        let _task_scope = register_current_task_scope("spawn");
+        let _task_scope = register_current_task_scope("spawn", Some(logical_id));
         instrument_future_with_handle(future_handle, future, None, None).await
     });
     JoinHandle::new(tokio::spawn(fut), handle)
 }
 
 /// Spawns a blocking task, equivalent to [`tokio::task::spawn_blocking`].
+#[track_caller]
 pub fn spawn_blocking<T, F>(f: F) -> JoinHandle<T>
 where
     T: Send + 'static,
     F: FnOnce() -> T + Send + 'static,
 {
-    let handle = EntityHandle::new("task.spawn_blocking", FutureEntity::default());
+    let logical_id = logical_task_id("task.spawn_blocking", Location::caller());
+    let handle = EntityHandle::new(
+        "task.spawn_blocking",
+        FutureEntity {
+            logical_id: Some(logical_id.clone()),
+            spawned_by: current_task_target().map(|task| task.id().clone()),
+            ..FutureEntity::default()
+        },
+    );
     let inner = tokio::task::spawn_blocking(move || {
-        let _task_scope = register_current_task_scope("spawn_blocking");
+        let _task_scope = register_current_task_scope("spawn_blocking", Some(logical_id));
         f()
     });
     JoinHandle::new(inner, handle)
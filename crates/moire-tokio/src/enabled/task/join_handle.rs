@@ -3,12 +3,15 @@ use std::future::Future;
 use std::pin::Pin;
 use std::task::{Context, Poll};
 
-use moire_runtime::EntityHandle;
+use moire_runtime::{EntityHandle, OperationFuture, instrument_operation_on_at_poll};
 use moire_types::FutureEntity;
 
 /// Instrumented equivalent of [`tokio::task::JoinHandle`].
+///
+/// Awaiting the handle records a `waiting_on` edge from the awaiting task to
+/// the joined one.
 pub struct JoinHandle<T> {
-    inner: tokio::task::JoinHandle<T>,
+    inner: OperationFuture<tokio::task::JoinHandle<T>>,
     handle: EntityHandle<FutureEntity>,
 }
 
@@ -17,7 +20,10 @@ impl<T> JoinHandle<T> {
         inner: tokio::task::JoinHandle<T>,
         handle: EntityHandle<FutureEntity>,
     ) -> Self {
-        Self { inner, handle }
+        Self {
+            inner: instrument_operation_on_at_poll(&handle, inner),
+            handle,
+        }
     }
 
     /// Renames the underlying task entity.
@@ -28,17 +34,17 @@ impl<T> JoinHandle<T> {
 
     /// Aborts the associated task.
     pub fn abort(&self) {
-        self.inner.abort();
+        self.inner.get_ref().abort();
     }
 
     /// Returns `true` if this task has finished.
     pub fn is_finished(&self) -> bool {
-        self.inner.is_finished()
+        self.inner.get_ref().is_finished()
     }
 
     /// Returns the task identifier.
     pub fn id(&self) -> tokio::task::Id {
-        self.inner.id()
+        self.inner.get_ref().id()
     }
 
     /// Returns the tracked entity reference for this task.
@@ -48,6 +54,7 @@ impl<T> JoinHandle<T> {
     }
 }
 
+// r[impl api.spawn]
 impl<T> Future for JoinHandle<T> {
     type Output = Result<T, tokio::task::JoinError>;
 
@@ -1,9 +1,11 @@
 use std::cell::RefCell;
 use std::fmt;
 use std::future::Future;
+use std::panic::Location;
 
 use moire_runtime::{
-    EntityHandle, FUTURE_CAUSAL_STACK, instrument_future_with_handle, register_current_task_scope,
+    EntityHandle, FUTURE_CAUSAL_STACK, current_task_target, instrument_future_with_handle,
+    logical_task_id, register_current_task_scope,
 };
 use moire_types::FutureEntity;
 
@@ -37,15 +39,24 @@ where
     }
 
     /// Spawns a future into the set, matching [`tokio::task::JoinSet::spawn`].
+    #[track_caller]
     pub fn spawn<F>(&mut self, future: F)
     where
         F: Future<Output = T> + Send + 'static,
     {
         let joinset_handle = self.handle.clone();
-        let task_handle = EntityHandle::new("joinset.task", FutureEntity::default());
+        let logical_id = logical_task_id("joinset.task", Location::caller());
+        let task_handle = EntityHandle::new(
+            "joinset.task",
+            FutureEntity {
+                logical_id: Some(logical_id.clone()),
+                spawned_by: current_task_target().map(|task| task.id().clone()),
+                ..FutureEntity::default()
+            },
+        );
         self.inner.spawn(
             FUTURE_CAUSAL_STACK.scope(RefCell::new(Vec::new()), async move {
-                let _task_scope = register_current_task_scope("joinset.spawn");
+                let _task_scope = register_current_task_scope("joinset.spawn", Some(logical_id));
                 instrument_future_with_handle(
                     task_handle,
                     future,
@@ -0,0 +1,165 @@
+//! Structured task scopes: tasks that cannot outlive the code that spawned them.
+//!
+//! Tasks spawned with [`spawn`](super::spawn) record the task they were
+//! spawned from, but nothing ties their lifetime to it. A [`TaskScope`] owns
+//! its tasks: they all name the task that opened the scope as their parent,
+//! they are linked to one `task_group` scope so `moire-web` can render them
+//! together, and they are aborted if the scope is dropped before they finish.
+//!
+//! ```ignore
+//! use moire::task::scope;
+//!
+//! let pages = scope("crawl", async |tasks| {
+//!     for url in urls {
+//!         tasks.spawn(fetch(url));
+//!     }
+//! })
+//! .await;
+//! ```
+
+// r[impl api.task-scope]
+
+use std::cell::RefCell;
+use std::fmt;
+use std::future::Future;
+use std::panic::{Location, resume_unwind};
+
+use moire_runtime::{
+    EntityHandle, FUTURE_CAUSAL_STACK, ScopeHandle, current_task_target,
+    instrument_future_with_handle, logical_task_id, register_current_task_scope,
+};
+use moire_types::{EntityId, FutureEntity, ScopeBody, TaskGroupScopeBody};
+
+/// Runs `body` with a new [`TaskScope`], then waits for every task still in
+/// the scope before returning `body`'s result.
+///
+/// A task that panicked resumes its panic here. Dropping the returned future
+/// aborts the scope's tasks.
+pub async fn scope<T, R>(
+    name: impl Into<String>,
+    body: impl AsyncFnOnce(&mut TaskScope<T>) -> R,
+) -> R
+where
+    T: Send + 'static,
+{
+    let mut tasks = TaskScope::new(name);
+    let result = body(&mut tasks).await;
+    tasks.join_all().await;
+    result
+}
+
+/// A set of tasks that are all joined or aborted before the scope goes away.
+///
+/// Dropping the scope aborts the tasks still running in it.
+pub struct TaskScope<T> {
+    inner: tokio::task::JoinSet<T>,
+    name: String,
+    spawned_by: Option<EntityId>,
+    scope: ScopeHandle,
+    handle: EntityHandle<FutureEntity>,
+}
+
+impl<T> TaskScope<T>
+where
+    T: Send + 'static,
+{
+    /// Opens a scope whose tasks are children of the task calling this.
+    pub fn new(name: impl Into<String>) -> Self {
+        let name = name.into();
+        let spawned_by = current_task_target().map(|task| task.id().clone());
+        let scope = ScopeHandle::new(
+            format!("scope.{name}"),
+            ScopeBody::TaskGroup(TaskGroupScopeBody {
+                spawned_by: spawned_by.clone(),
+            }),
+        );
+        let handle = EntityHandle::new(format!("scope.{name}"), FutureEntity::default());
+        scope.link_entity(&handle);
+        Self {
+            inner: tokio::task::JoinSet::new(),
+            name,
+            spawned_by,
+            scope,
+            handle,
+        }
+    }
+
+    /// Spawns a task into the scope, named `<scope name>.task`.
+    #[track_caller]
+    pub fn spawn<F>(&mut self, future: F)
+    where
+        F: Future<Output = T> + Send + 'static,
+    {
+        let name = format!("{}.task", self.name);
+        let logical_id = logical_task_id(&name, Location::caller());
+        let task_handle = EntityHandle::new(
+            name,
+            FutureEntity {
+                logical_id: Some(logical_id.clone()),
+                spawned_by: self.spawned_by.clone(),
+                ..FutureEntity::default()
+            },
+        );
+        self.scope.link_entity(&task_handle);
+        self.inner.spawn(
+            FUTURE_CAUSAL_STACK.scope(RefCell::new(Vec::new()), async move {
+                let _task_scope = register_current_task_scope("scope.spawn", Some(logical_id));
+                instrument_future_with_handle(task_handle, future, None, None).await
+            }),
+        );
+    }
+
+    /// Returns whether no task is left in the scope.
+    pub fn is_empty(&self) -> bool {
+        self.inner.is_empty()
+    }
+
+    /// Returns the number of tasks not joined yet.
+    pub fn len(&self) -> usize {
+        self.inner.len()
+    }
+
+    /// Aborts every task still running in the scope.
+    pub fn abort_all(&mut self) {
+        self.inner.abort_all();
+    }
+
+    /// Waits for one task to complete, like [`tokio::task::JoinSet::join_next`].
+    pub fn join_next(
+        &mut self,
+    ) -> impl Future<Output = Option<Result<T, tokio::task::JoinError>>> + '_ {
+        let handle = self.handle.clone();
+        let fut_handle = EntityHandle::new(
+            format!("scope.{}.join_next", self.name),
+            FutureEntity::default(),
+        );
+        let fut = self.inner.join_next();
+        instrument_future_with_handle(fut_handle, fut, Some(handle.entity_ref()), None)
+    }
+
+    /// Waits for every task in the scope and returns their outputs in the
+    /// order they completed. A task that panicked resumes its panic here.
+    pub async fn join_all(mut self) -> Vec<T> {
+        let mut outputs = Vec::with_capacity(self.len());
+        while let Some(joined) = self.join_next().await {
+            match joined {
+                Ok(output) => outputs.push(output),
+                Err(e) if e.is_panic() => resume_unwind(e.into_panic()),
+                Err(_) => {}
+            }
+        }
+        outputs
+    }
+}
+
+impl<T> fmt::Debug for TaskScope<T>
+where
+    T: Send + 'static,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TaskScope")
+            .field("name", &self.name)
+            .field("len", &self.len())
+            .finish()
+    }
+}
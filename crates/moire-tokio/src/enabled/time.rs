@@ -2,33 +2,61 @@
 //! Instrumented time utilities, mirroring [`tokio::time`].
 //!
 //! This module mirrors the structure of `tokio::time` and can be used as a
-//! drop-in replacement. Sleeps and intervals are registered as named entities
-//! in the Moiré runtime graph so the dashboard can show which tasks are
-//! suspended waiting for a timer to fire.
+//! drop-in replacement. Sleeps, intervals and timeouts are registered as named
+//! entities in the Moiré runtime graph so the dashboard can show which tasks
+//! are suspended waiting for a timer to fire. Each entity is labelled with its
+//! duration, e.g. `time.sleep(1.5s)`, and records in `timer` how late its
+//! fires resumed their waiter, so timers firing late on an overloaded runtime
+//! show up in a snapshot.
 //!
 //! # Available items
 //!
 //! | Item | Tokio equivalent |
 //! |---|---|
 //! | [`sleep`] | `tokio::time::sleep` |
+//! | [`sleep_until`] | `tokio::time::sleep_until` |
 //! | [`timeout`] | `tokio::time::timeout` |
 //! | [`interval`] | `tokio::time::interval` |
 //! | [`Interval`] | `tokio::time::Interval` |
+//! | [`Instant`] | `tokio::time::Instant` |
 use std::fmt;
 use std::future::Future;
 use std::time::Duration;
 
 use moire_runtime::EntityHandle;
-use moire_types::FutureEntity;
+use moire_types::{FutureEntity, TimerStats};
+
+pub use tokio::time::Instant;
 
 use super::task::FutureExt as _;
 
 /// Instrumented equivalent of [`tokio::time::sleep`].
 pub fn sleep(duration: Duration) -> impl Future<Output = ()> {
-    let handle = EntityHandle::new("time.sleep", FutureEntity::default());
-    tokio::time::sleep(duration)
-        .named("time.sleep")
-        .on(handle.entity_ref())
+    instrument_sleep("time.sleep", tokio::time::sleep(duration), duration)
+}
+
+/// Instrumented equivalent of [`tokio::time::sleep_until`].
+pub fn sleep_until(deadline: Instant) -> impl Future<Output = ()> {
+    let duration = deadline.saturating_duration_since(Instant::now());
+    instrument_sleep(
+        "time.sleep_until",
+        tokio::time::sleep_until(deadline),
+        duration,
+    )
+}
+
+fn instrument_sleep(
+    kind: &'static str,
+    sleep: tokio::time::Sleep,
+    duration: Duration,
+) -> impl Future<Output = ()> {
+    let deadline = sleep.deadline();
+    let handle = timer_handle(kind, duration);
+    let sleep = sleep.named(kind).on(handle.entity_ref());
+    async move {
+        sleep.await;
+        record_fire(&handle, deadline);
+    }
 }
 
 /// Instrumented equivalent of [`tokio::time::Interval`].
@@ -39,11 +67,18 @@ pub struct Interval {
 
 impl Interval {
     /// Waits for the next tick, equivalent to [`tokio::time::Interval::tick`].
-    pub fn tick(&mut self) -> impl Future<Output = tokio::time::Instant> + '_ {
-        self.inner
+    pub fn tick(&mut self) -> impl Future<Output = Instant> + '_ {
+        let tick = self
+            .inner
             .tick()
             .named("time.interval.tick")
-            .on(self.handle.entity_ref())
+            .on(self.handle.entity_ref());
+        let handle = &self.handle;
+        async move {
+            let scheduled = tick.await;
+            record_fire(handle, scheduled);
+            scheduled
+        }
     }
 }
 
@@ -57,16 +92,66 @@ impl fmt::Debug for Interval {
 pub fn interval(period: Duration) -> Interval {
     Interval {
         inner: tokio::time::interval(period),
-        handle: EntityHandle::new("time.interval", FutureEntity::default()),
+        handle: timer_handle("time.interval", period),
     }
 }
 
 /// Run a future with a timeout.
 ///
-/// Equivalent to `tokio::time::timeout`.
+/// Equivalent to `tokio::time::timeout`. Only an elapsed timeout counts as a
+/// fire of the timer.
 pub async fn timeout<F, T>(duration: Duration, future: F) -> Result<T, tokio::time::error::Elapsed>
 where
     F: Future<Output = T>,
 {
-    tokio::time::timeout(duration, future).await
+    let deadline = Instant::now().checked_add(duration);
+    let handle = timer_handle("time.timeout", duration);
+    let result = tokio::time::timeout(duration, future)
+        .named("time.timeout")
+        .on(handle.entity_ref())
+        .await;
+    if result.is_err()
+        && let Some(deadline) = deadline
+    {
+        record_fire(&handle, deadline);
+    }
+    result
+}
+
+fn timer_handle(kind: &str, duration: Duration) -> EntityHandle<FutureEntity> {
+    EntityHandle::new(
+        format!("{kind}({})", format_duration(duration)),
+        FutureEntity {
+            timer: Some(TimerStats {
+                duration_ms: as_millis(duration),
+                ..TimerStats::default()
+            }),
+            ..FutureEntity::default()
+        },
+    )
+}
+
+/// Records a fire of the timer that was due at `deadline`, measuring how late
+/// it resumed the waiter.
+fn record_fire(handle: &EntityHandle<FutureEntity>, deadline: Instant) {
+    let late_ms = as_millis(Instant::now().saturating_duration_since(deadline));
+    let _ = handle.mutate(|body| {
+        let timer = body.timer.get_or_insert_with(TimerStats::default);
+        timer.fires += 1;
+        timer.last_late_ms = Some(late_ms);
+        timer.max_late_ms = Some(timer.max_late_ms.map_or(late_ms, |max| max.max(late_ms)));
+    });
+}
+
+fn as_millis(duration: Duration) -> u64 {
+    duration.as_millis().min(u128::from(u64::MAX)) as u64
+}
+
+/// `250ms` below a second, `1.5s` from there on.
+fn format_duration(duration: Duration) -> String {
+    if duration < Duration::from_secs(1) {
+        format!("{}ms", duration.as_millis())
+    } else {
+        format!("{}s", duration.as_secs_f64())
+    }
 }
@@ -3,6 +3,8 @@
 #[cfg(target_arch = "wasm32")]
 compile_error!("`moire-tokio` is native-only; use `moire-wasm` on wasm32");
 
+mod args_capture;
+
 // r[impl process.feature-gate]
 #[cfg(not(feature = "diagnostics"))]
 mod disabled;
@@ -3,6 +3,7 @@ use std::error::Error;
 use std::fmt;
 use std::sync::OnceLock;
 use std::sync::atomic::{AtomicU64, Ordering};
+#[cfg(not(target_arch = "wasm32"))]
 use std::time::{SystemTime, UNIX_EPOCH};
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -39,14 +40,24 @@ pub const JS_SAFE_INT_MAX_U64: u64 = (1u64 << 53) - 1;
 
 fn process_prefix_u16() -> u16 {
     static PROCESS_PREFIX: OnceLock<u16> = OnceLock::new();
-    *PROCESS_PREFIX.get_or_init(|| {
-        let pid = std::process::id() as u64;
-        let seed = SystemTime::now()
-            .duration_since(UNIX_EPOCH)
-            .map(|duration| duration.as_nanos() as u64)
-            .unwrap_or(0);
-        ((seed ^ pid) & 0xFFFF) as u16
-    })
+    *PROCESS_PREFIX.get_or_init(|| (process_seed() & 0xFFFF) as u16)
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn process_seed() -> u64 {
+    let pid = std::process::id() as u64;
+    let seed = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_nanos() as u64)
+        .unwrap_or(0);
+    seed ^ pid
+}
+
+/// A page has no pid and no clock `std` can read; its ids only have to be
+/// unique within the page.
+#[cfg(target_arch = "wasm32")]
+fn process_seed() -> u64 {
+    0
 }
 
 macro_rules! define_u64_id {
@@ -0,0 +1,466 @@
+//! What the viewer shows, rebuilt from every fetched snapshot.
+
+use std::collections::{HashMap, HashSet};
+
+use moire_types::{EdgeKind, Entity, EntityBody, EntityId, LockKind, Snapshot};
+use ratatui::widgets::TableState;
+
+use crate::fetch::Fetched;
+
+pub(crate) struct App {
+    pub(crate) base_url: String,
+    pub(crate) tables: [TableView; 3],
+    pub(crate) active: usize,
+    /// One line per wait cycle, longest-blocked first.
+    pub(crate) cycles: Vec<String>,
+    /// Why the last refresh is incomplete or failed, if it is.
+    pub(crate) problem: Option<String>,
+    pub(crate) refreshes: u64,
+}
+
+pub(crate) struct TableView {
+    pub(crate) title: &'static str,
+    pub(crate) headers: &'static [&'static str],
+    pub(crate) rows: Vec<Vec<Cell>>,
+    pub(crate) sort_column: usize,
+    pub(crate) descending: bool,
+    pub(crate) state: TableState,
+}
+
+pub(crate) struct Cell {
+    pub(crate) text: String,
+    key: SortKey,
+}
+
+#[derive(PartialEq, Eq, PartialOrd, Ord)]
+enum SortKey {
+    Number(u64),
+    Text(String),
+}
+
+impl Cell {
+    fn text(text: impl Into<String>) -> Self {
+        let text = text.into();
+        Self {
+            key: SortKey::Text(text.to_lowercase()),
+            text,
+        }
+    }
+
+    fn number(value: u64) -> Self {
+        Self {
+            text: value.to_string(),
+            key: SortKey::Number(value),
+        }
+    }
+
+    /// Shows `text` but sorts by `value`.
+    fn number_as(value: u64, text: impl Into<String>) -> Self {
+        Self {
+            text: text.into(),
+            key: SortKey::Number(value),
+        }
+    }
+
+    fn millis(ms: u64) -> Self {
+        Self::number_as(ms, format_millis(ms))
+    }
+}
+
+impl TableView {
+    fn new(
+        title: &'static str,
+        headers: &'static [&'static str],
+        sort_column: usize,
+        descending: bool,
+    ) -> Self {
+        Self {
+            title,
+            headers,
+            rows: Vec::new(),
+            sort_column,
+            descending,
+            state: TableState::default(),
+        }
+    }
+
+    fn replace_rows(&mut self, rows: Vec<Vec<Cell>>) {
+        self.rows = rows;
+        self.sort();
+    }
+
+    fn sort(&mut self) {
+        let column = self.sort_column;
+        let descending = self.descending;
+        self.rows.sort_by(|a, b| {
+            let order = a[column].key.cmp(&b[column].key);
+            let order = if descending { order.reverse() } else { order };
+            order.then_with(|| a[0].key.cmp(&b[0].key))
+        });
+        let selected = match (self.state.selected(), self.rows.len()) {
+            (_, 0) => None,
+            (Some(selected), len) => Some(selected.min(len - 1)),
+            (None, _) => Some(0),
+        };
+        self.state.select(selected);
+    }
+}
+
+impl App {
+    pub(crate) fn new(base_url: &str) -> Self {
+        Self {
+            base_url: base_url.to_string(),
+            tables: [
+                TableView::new(
+                    "Tasks",
+                    &["Task", "State", "Waiting on", "Age", "Polls", "Busy"],
+                    3,
+                    true,
+                ),
+                TableView::new(
+                    "Locks",
+                    &["Lock", "Kind", "Held", "Waiters", "Holds", "Avg hold"],
+                    3,
+                    true,
+                ),
+                TableView::new(
+                    "Channels",
+                    &["Channel", "Kind", "Depth", "Blocked senders", "Age"],
+                    2,
+                    true,
+                ),
+            ],
+            active: 0,
+            cycles: Vec::new(),
+            problem: Some(String::from("waiting for the first snapshot")),
+            refreshes: 0,
+        }
+    }
+
+    /// Replaces what is shown with a fresh fetch. A failed fetch keeps the
+    /// previous tables and says why.
+    pub(crate) fn update(&mut self, fetched: Result<Fetched, String>) {
+        let fetched = match fetched {
+            Ok(fetched) => fetched,
+            Err(e) => {
+                self.problem = Some(e);
+                return;
+            }
+        };
+        self.refreshes += 1;
+        let mut problems = Vec::new();
+        if let Some(sections) = &fetched.reply.timed_out_sections {
+            problems.push(format!("timed out: {}", sections.join(", ")));
+        }
+        match &fetched.reply.snapshot {
+            Some(snapshot) => {
+                let now_ms = fetched.reply.ptime_now_ms;
+                let index = Index::new(snapshot);
+                self.tables[0].replace_rows(task_rows(snapshot, &index, now_ms));
+                self.tables[1].replace_rows(lock_rows(snapshot, &index));
+                self.tables[2].replace_rows(channel_rows(snapshot, now_ms));
+            }
+            None => problems.push(String::from("no snapshot: runtime state stayed locked")),
+        }
+        match fetched.candidates {
+            Ok(candidates) => {
+                self.cycles = candidates
+                    .candidates
+                    .iter()
+                    .map(|candidate| {
+                        let mut path = candidate.names.clone();
+                        if let Some(first) = candidate.names.first() {
+                            path.push(first.clone());
+                        }
+                        format!(
+                            "{}  (blocked ≥ {})",
+                            path.join(" → "),
+                            format_millis(candidate.blocked_duration_hint_ms)
+                        )
+                    })
+                    .collect();
+            }
+            Err(e) => problems.push(e),
+        }
+        self.problem = (!problems.is_empty()).then(|| problems.join("; "));
+    }
+
+    pub(crate) fn next_table(&mut self) {
+        self.active = (self.active + 1) % self.tables.len();
+    }
+
+    pub(crate) fn previous_table(&mut self) {
+        self.active = (self.active + self.tables.len() - 1) % self.tables.len();
+    }
+
+    pub(crate) fn select_next(&mut self) {
+        let table = &mut self.tables[self.active];
+        if let Some(selected) = table.state.selected() {
+            table
+                .state
+                .select(Some((selected + 1).min(table.rows.len().saturating_sub(1))));
+        }
+    }
+
+    pub(crate) fn select_previous(&mut self) {
+        let table = &mut self.tables[self.active];
+        if let Some(selected) = table.state.selected() {
+            table.state.select(Some(selected.saturating_sub(1)));
+        }
+    }
+
+    pub(crate) fn next_sort_column(&mut self) {
+        let table = &mut self.tables[self.active];
+        table.sort_column = (table.sort_column + 1) % table.headers.len();
+        table.sort();
+    }
+
+    pub(crate) fn reverse_sort(&mut self) {
+        let table = &mut self.tables[self.active];
+        table.descending = !table.descending;
+        table.sort();
+    }
+}
+
+/// Edges of a snapshot, looked up by entity.
+struct Index<'a> {
+    names: HashMap<&'a EntityId, &'a str>,
+    polls: HashMap<&'a EntityId, Vec<&'a EntityId>>,
+    waits_on: HashMap<&'a EntityId, &'a EntityId>,
+    waiters: HashMap<&'a EntityId, u64>,
+    holders: HashMap<&'a EntityId, u64>,
+}
+
+impl<'a> Index<'a> {
+    fn new(snapshot: &'a Snapshot) -> Self {
+        let mut index = Self {
+            names: snapshot
+                .entities
+                .iter()
+                .map(|entity| (&entity.id, entity.name.as_str()))
+                .collect(),
+            polls: HashMap::new(),
+            waits_on: HashMap::new(),
+            waiters: HashMap::new(),
+            holders: HashMap::new(),
+        };
+        for edge in &snapshot.edges {
+            match edge.kind {
+                EdgeKind::Polls => index.polls.entry(&edge.src).or_default().push(&edge.dst),
+                EdgeKind::WaitingOn => {
+                    index.waits_on.entry(&edge.src).or_insert(&edge.dst);
+                    *index.waiters.entry(&edge.dst).or_default() += 1;
+                }
+                EdgeKind::HeldBy => *index.holders.entry(&edge.src).or_default() += 1,
+                EdgeKind::PairedWith => {}
+            }
+        }
+        index
+    }
+
+    fn name(&self, id: &EntityId) -> String {
+        self.names
+            .get(id)
+            .map_or_else(|| id.as_str().to_string(), |name| name.to_string())
+    }
+
+    /// What `task`, or a future it is polling, directly or not, waits on.
+    fn wait_of(&self, task: &'a EntityId) -> Option<&'a EntityId> {
+        let mut seen = HashSet::new();
+        let mut stack = vec![task];
+        while let Some(id) = stack.pop() {
+            if !seen.insert(id) {
+                continue;
+            }
+            if let Some(target) = self.waits_on.get(id) {
+                return Some(target);
+            }
+            stack.extend(self.polls.get(id).into_iter().flatten().copied());
+        }
+        None
+    }
+}
+
+fn task_rows(snapshot: &Snapshot, index: &Index<'_>, now_ms: u64) -> Vec<Vec<Cell>> {
+    live(snapshot)
+        .filter_map(|entity| match &entity.body {
+            EntityBody::Future(future) if future.logical_id.is_some() => {
+                let wait = index.wait_of(&entity.id);
+                let state = if future.wake_to_poll_gap_ms.is_some() {
+                    "woken"
+                } else if wait.is_some() && future.idle.is_some() {
+                    "idle"
+                } else if wait.is_some() {
+                    "waiting"
+                } else {
+                    "running"
+                };
+                Some(vec![
+                    Cell::text(entity.name.as_str()),
+                    Cell::text(state),
+                    Cell::text(wait.map(|target| index.name(target)).unwrap_or_default()),
+                    Cell::millis(now_ms.saturating_sub(entity.birth.as_millis())),
+                    Cell::number(future.poll_count.unwrap_or(0)),
+                    Cell::millis(future.busy_us.unwrap_or(0) / 1_000),
+                ])
+            }
+            _ => None,
+        })
+        .collect()
+}
+
+fn lock_rows(snapshot: &Snapshot, index: &Index<'_>) -> Vec<Vec<Cell>> {
+    live(snapshot)
+        .filter_map(|entity| {
+            let waiters = index.waiters.get(&entity.id).copied().unwrap_or(0);
+            let (kind, held, holds) = match &entity.body {
+                EntityBody::Lock(lock) => {
+                    let kind = match lock.kind {
+                        LockKind::Mutex => "mutex",
+                        LockKind::RwLock => "rwlock",
+                        LockKind::Other => "lock",
+                    };
+                    let held = index.holders.get(&entity.id).copied().unwrap_or(0);
+                    (kind, Cell::number(held), lock.holds.as_ref())
+                }
+                EntityBody::Semaphore(semaphore) => (
+                    "semaphore",
+                    Cell::number_as(
+                        u64::from(semaphore.handed_out_permits),
+                        format!("{}/{}", semaphore.handed_out_permits, semaphore.max_permits),
+                    ),
+                    semaphore.holds.as_ref(),
+                ),
+                _ => return None,
+            };
+            Some(vec![
+                Cell::text(entity.name.as_str()),
+                Cell::text(kind),
+                held,
+                Cell::number(waiters),
+                Cell::number(holds.map_or(0, |holds| holds.hold_count)),
+                Cell::millis(holds.map_or(0, |holds| holds.avg_hold_ms)),
+            ])
+        })
+        .collect()
+}
+
+fn channel_rows(snapshot: &Snapshot, now_ms: u64) -> Vec<Vec<Cell>> {
+    live(snapshot)
+        .filter_map(|entity| {
+            let (kind, depth, blocked) = match &entity.body {
+                EntityBody::MpscTx(tx) => {
+                    let depth = match tx.capacity {
+                        Some(capacity) => format!("{}/{capacity}", tx.queue_len),
+                        None => tx.queue_len.to_string(),
+                    };
+                    (
+                        "mpsc",
+                        Cell::number_as(u64::from(tx.queue_len), depth),
+                        tx.send_waiters.len() as u64,
+                    )
+                }
+                EntityBody::BroadcastRx(rx) => {
+                    ("broadcast lag", Cell::number(u64::from(rx.lag)), 0)
+                }
+                _ => return None,
+            };
+            Some(vec![
+                Cell::text(entity.name.as_str()),
+                Cell::text(kind),
+                depth,
+                Cell::number(blocked),
+                Cell::millis(now_ms.saturating_sub(entity.birth.as_millis())),
+            ])
+        })
+        .collect()
+}
+
+fn live(snapshot: &Snapshot) -> impl Iterator<Item = &Entity> {
+    snapshot
+        .entities
+        .iter()
+        .filter(|entity| entity.removed_at.is_none())
+}
+
+fn format_millis(ms: u64) -> String {
+    match ms {
+        0..1_000 => format!("{ms}ms"),
+        1_000..60_000 => format!("{:.1}s", ms as f64 / 1_000.0),
+        _ => format!("{}m{:02}s", ms / 60_000, ms / 1_000 % 60),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fetch::{Candidate, Candidates};
+    use moire_testkit::DumpBuilder;
+    use moire_wire::SnapshotReply;
+
+    fn texts(table: &TableView) -> Vec<Vec<&str>> {
+        table
+            .rows
+            .iter()
+            .map(|row| row.iter().map(|cell| cell.text.as_str()).collect())
+            .collect()
+    }
+
+    #[test]
+    fn snapshot_becomes_sorted_rows_and_a_failed_fetch_keeps_them() {
+        let mut dump = DumpBuilder::new()
+            .process("p", |p| {
+                p.task("worker")
+                    .task("reaper")
+                    .born_at("reaper", 30_000)
+                    .lock("cache")
+                    .waits_on("worker", "cache", 50_000)
+                    .channel("jobs", Some(4), 4)
+                    .blocked_sender("jobs", "worker", 55_000)
+            })
+            .build();
+        let process = dump.processes.remove(0);
+        let mut app = App::new("http://127.0.0.1:9131");
+        app.update(Ok(Fetched {
+            reply: SnapshotReply {
+                snapshot_id: 0,
+                ptime_now_ms: process.ptime_now_ms,
+                snapshot: Some(process.snapshot),
+                timed_out_sections: None,
+            },
+            candidates: Ok(Candidates {
+                candidates: vec![Candidate {
+                    names: vec![String::from("worker"), String::from("reaper")],
+                    blocked_duration_hint_ms: 4_000,
+                }],
+            }),
+        }));
+
+        assert_eq!(
+            texts(&app.tables[0]),
+            [
+                ["worker", "waiting", "cache", "1m00s", "0", "0ms"],
+                ["reaper", "running", "", "30.0s", "0", "0ms"],
+            ]
+        );
+        assert_eq!(
+            texts(&app.tables[1]),
+            [["cache", "mutex", "0", "1", "0", "0ms"]]
+        );
+        assert_eq!(
+            texts(&app.tables[2]),
+            [["jobs", "mpsc", "4/4", "1", "1m00s"]]
+        );
+        assert_eq!(app.cycles, ["worker → reaper → worker  (blocked ≥ 4.0s)"]);
+        assert_eq!(app.problem, None);
+
+        app.update(Err(String::from(
+            "GET http://127.0.0.1:9131/snapshot.json: refused",
+        )));
+        assert_eq!(texts(&app.tables[0]).len(), 2);
+        assert_eq!(
+            app.problem.as_deref(),
+            Some("GET http://127.0.0.1:9131/snapshot.json: refused")
+        );
+    }
+}
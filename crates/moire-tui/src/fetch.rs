@@ -0,0 +1,109 @@
+//! Reading a process's state over its HTTP routes.
+
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, Sender};
+use std::time::Duration;
+
+use facet::Facet;
+use moire_wire::SnapshotReply;
+
+/// Longer than the process's own snapshot budget, so a slow process still
+/// answers with what it could assemble.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// The process's `/candidates.json`: its wait cycles, longest-blocked first.
+#[derive(Facet, Debug)]
+pub(crate) struct Candidates {
+    pub(crate) candidates: Vec<Candidate>,
+}
+
+#[derive(Facet, Debug)]
+pub(crate) struct Candidate {
+    /// Names of the cycle's members, in cycle order.
+    pub(crate) names: Vec<String>,
+    pub(crate) blocked_duration_hint_ms: u64,
+}
+
+pub(crate) struct Fetched {
+    pub(crate) reply: SnapshotReply,
+    /// Cycles are only worth showing alongside a snapshot, but a process
+    /// can fail to build its graph while still serving one.
+    pub(crate) candidates: Result<Candidates, String>,
+}
+
+pub(crate) struct Source {
+    base_url: String,
+    agent: ureq::Agent,
+}
+
+impl Source {
+    pub(crate) fn new(base_url: &str) -> Self {
+        Self {
+            base_url: base_url.trim_end_matches('/').to_string(),
+            agent: ureq::AgentBuilder::new().timeout(REQUEST_TIMEOUT).build(),
+        }
+    }
+
+    pub(crate) fn base_url(&self) -> &str {
+        &self.base_url
+    }
+
+    pub(crate) fn fetch(&self) -> Result<Fetched, String> {
+        let reply = facet_json::from_str::<SnapshotReply>(&self.get("snapshot.json")?)
+            .map_err(|e| format!("decode snapshot: {e}"))?;
+        let candidates = self.get("candidates.json").and_then(|body| {
+            facet_json::from_str::<Candidates>(&body).map_err(|e| format!("decode candidates: {e}"))
+        });
+        Ok(Fetched { reply, candidates })
+    }
+
+    fn get(&self, route: &str) -> Result<String, String> {
+        let url = format!("{}/{route}", self.base_url);
+        self.agent
+            .get(&url)
+            .call()
+            .map_err(|e| format!("GET {url}: {e}"))?
+            .into_string()
+            .map_err(|e| format!("read GET {url} body: {e}"))
+    }
+}
+
+/// Fetches from a [`Source`] on a thread of its own, so a slow or unreachable
+/// process never freezes the terminal. Fetches follow each other `interval`
+/// apart, or sooner when asked with [`Poller::refresh_now`].
+pub(crate) struct Poller {
+    results: Receiver<Result<Fetched, String>>,
+    refresh: Sender<()>,
+}
+
+impl Poller {
+    pub(crate) fn spawn(source: Source, interval: Duration) -> Result<Self, String> {
+        let (results_tx, results) = mpsc::channel();
+        let (refresh, refresh_rx) = mpsc::channel();
+        std::thread::Builder::new()
+            .name(String::from("moire-tui-fetch"))
+            .spawn(move || {
+                loop {
+                    if results_tx.send(source.fetch()).is_err() {
+                        return;
+                    }
+                    match refresh_rx.recv_timeout(interval) {
+                        Ok(()) | Err(RecvTimeoutError::Timeout) => {}
+                        Err(RecvTimeoutError::Disconnected) => return,
+                    }
+                    // Refreshes asked for while fetching are served by this one.
+                    while refresh_rx.try_recv().is_ok() {}
+                }
+            })
+            .map_err(|e| format!("spawn fetch thread: {e}"))?;
+        Ok(Self { results, refresh })
+    }
+
+    /// The fetches finished since the last call, oldest first.
+    pub(crate) fn finished(&self) -> impl Iterator<Item = Result<Fetched, String>> + '_ {
+        self.results.try_iter()
+    }
+
+    pub(crate) fn refresh_now(&self) {
+        let _ = self.refresh.send(());
+    }
+}
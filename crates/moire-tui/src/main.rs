@@ -0,0 +1,119 @@
+// r[impl config.tui]
+//! Terminal viewer for one instrumented process.
+//!
+//! Polls the routes a process serves with the `http` feature (`MOIRE_HTTP`,
+//! see `moire::http_router`) and shows its tasks, locks and channels as
+//! sortable tables, with its wait cycles underneath. Meant for servers where
+//! running `moire-web` and a browser is not an option:
+//!
+//! ```text
+//! MOIRE_HTTP=127.0.0.1:9131 ./your-binary &
+//! moire-tui http://127.0.0.1:9131
+//! ```
+
+mod app;
+mod fetch;
+mod ui;
+
+use std::time::Duration;
+
+use facet::Facet;
+use figue as args;
+use ratatui::DefaultTerminal;
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEventKind};
+
+use crate::app::App;
+use crate::fetch::{Poller, Source};
+
+const DEFAULT_INTERVAL_MS: u64 = 1000;
+const MIN_INTERVAL_MS: u64 = 100;
+/// How often the screen is redrawn while no key is pressed, so finished
+/// fetches show up promptly.
+const REDRAW_INTERVAL: Duration = Duration::from_millis(50);
+
+#[derive(Facet, Debug)]
+struct Cli {
+    #[facet(flatten)]
+    builtins: args::FigueBuiltins,
+    /// Base URL of the process's `MOIRE_HTTP` server.
+    #[facet(args::positional)]
+    url: String,
+    /// Milliseconds between refreshes.
+    #[facet(args::named, default)]
+    interval_ms: Option<u64>,
+}
+
+fn main() {
+    let cli = match parse_cli() {
+        Ok(cli) => cli,
+        Err(e) => {
+            eprintln!("{e}");
+            std::process::exit(2);
+        }
+    };
+    let mut terminal = ratatui::init();
+    let result = run(&mut terminal, cli);
+    ratatui::restore();
+    if let Err(e) = result {
+        eprintln!("moire-tui: {e}");
+        std::process::exit(1);
+    }
+}
+
+fn parse_cli() -> Result<Cli, String> {
+    let figue_config = args::builder::<Cli>()
+        .map_err(|e| format!("failed to build CLI schema: {e}"))?
+        .cli(|cli| cli.strict())
+        .help(|h| {
+            h.program_name("moire-tui")
+                .description("Terminal viewer for a process serving MOIRE_HTTP")
+                .version(option_env!("CARGO_PKG_VERSION").unwrap_or("dev"))
+        })
+        .build();
+    let cli = args::Driver::new(figue_config)
+        .run()
+        .into_result()
+        .map_err(|e| e.to_string())?;
+    Ok(cli.value)
+}
+
+fn run(terminal: &mut DefaultTerminal, cli: Cli) -> Result<(), String> {
+    let interval = Duration::from_millis(
+        cli.interval_ms
+            .unwrap_or(DEFAULT_INTERVAL_MS)
+            .max(MIN_INTERVAL_MS),
+    );
+    let source = Source::new(&cli.url);
+    let mut app = App::new(source.base_url());
+    let poller = Poller::spawn(source, interval)?;
+    loop {
+        for fetched in poller.finished() {
+            app.update(fetched);
+        }
+        terminal
+            .draw(|frame| ui::draw(frame, &mut app))
+            .map_err(|e| format!("draw: {e}"))?;
+
+        if !event::poll(REDRAW_INTERVAL).map_err(|e| format!("poll terminal events: {e}"))? {
+            continue;
+        }
+        let Event::Key(key) = event::read().map_err(|e| format!("read terminal event: {e}"))?
+        else {
+            continue;
+        };
+        if key.kind != KeyEventKind::Press {
+            continue;
+        }
+        match key.code {
+            KeyCode::Char('q') | KeyCode::Esc => return Ok(()),
+            KeyCode::Tab | KeyCode::Right => app.next_table(),
+            KeyCode::BackTab | KeyCode::Left => app.previous_table(),
+            KeyCode::Down | KeyCode::Char('j') => app.select_next(),
+            KeyCode::Up | KeyCode::Char('k') => app.select_previous(),
+            KeyCode::Char('s') => app.next_sort_column(),
+            KeyCode::Char('r') => app.reverse_sort(),
+            KeyCode::Char('g') => poller.refresh_now(),
+            _ => {}
+        }
+    }
+}
@@ -0,0 +1,83 @@
+//! Drawing the viewer: table tabs on top, the active table, wait cycles, and
+//! a status line.
+
+use ratatui::Frame;
+use ratatui::layout::{Constraint, Layout};
+use ratatui::style::{Style, Stylize};
+use ratatui::text::Line;
+use ratatui::widgets::{Block, List, Paragraph, Row, Table, Tabs};
+
+use crate::app::App;
+
+const CYCLES_HEIGHT: u16 = 8;
+
+pub(crate) fn draw(frame: &mut Frame<'_>, app: &mut App) {
+    let [tabs_area, table_area, cycles_area, status_area] = Layout::vertical([
+        Constraint::Length(1),
+        Constraint::Min(5),
+        Constraint::Length(CYCLES_HEIGHT),
+        Constraint::Length(1),
+    ])
+    .areas(frame.area());
+
+    let titles = app
+        .tables
+        .iter()
+        .map(|table| format!("{} ({})", table.title, table.rows.len()));
+    frame.render_widget(
+        Tabs::new(titles)
+            .select(app.active)
+            .highlight_style(Style::new().bold().reversed()),
+        tabs_area,
+    );
+
+    let table = &mut app.tables[app.active];
+    let header = Row::new(table.headers.iter().enumerate().map(|(column, title)| {
+        if column == table.sort_column {
+            let arrow = if table.descending { '▼' } else { '▲' };
+            format!("{title} {arrow}")
+        } else {
+            title.to_string()
+        }
+    }))
+    .bold();
+    let rows = table
+        .rows
+        .iter()
+        .map(|row| Row::new(row.iter().map(|cell| cell.text.as_str())));
+    let mut widths = vec![Constraint::Fill(1); table.headers.len()];
+    widths[0] = Constraint::Fill(3);
+    frame.render_stateful_widget(
+        Table::new(rows, widths)
+            .header(header)
+            .block(Block::bordered().title(table.title))
+            .row_highlight_style(Style::new().reversed()),
+        table_area,
+        &mut table.state,
+    );
+
+    let cycles_block = Block::bordered().title(format!("Wait cycles ({})", app.cycles.len()));
+    if app.cycles.is_empty() {
+        frame.render_widget(
+            Paragraph::new("none").dim().block(cycles_block),
+            cycles_area,
+        );
+    } else {
+        frame.render_widget(
+            List::new(app.cycles.iter().map(String::as_str))
+                .red()
+                .block(cycles_block),
+            cycles_area,
+        );
+    }
+
+    let status = match &app.problem {
+        Some(problem) => Line::from(format!("{}  {problem}", app.base_url)).yellow(),
+        None => Line::from(format!(
+            "{}  refresh #{}  tab: table  s: sort column  r: reverse  g: refresh  q: quit",
+            app.base_url, app.refreshes
+        ))
+        .dim(),
+    };
+    frame.render_widget(Paragraph::new(status), status_area);
+}
@@ -64,6 +64,10 @@ pub struct SnapshotCutResponse {
     pub snapshot_id: i64,
     /// Wall-clock milliseconds (Unix epoch) when this cut was assembled server-side.
     pub captured_at_unix_ms: i64,
+    /// Largest `skew_ms` among `processes`: how far apart in time the
+    /// process snapshots of this cut may have been taken.
+    #[facet(skip_unless_truthy)]
+    pub max_skew_ms: Option<u64>,
     /// Processes that replied within the timeout window.
     pub processes: Vec<ProcessSnapshotView>,
     /// Processes connected at request time but timed out before response.
@@ -72,6 +76,146 @@ pub struct SnapshotCutResponse {
     pub backtraces: Vec<SnapshotBacktrace>,
     /// Deduplicated frame catalog keyed by frame_id.
     pub frames: Vec<SnapshotFrameRecord>,
+    /// Findings reported by server-side analyses (deadlock detection and any
+    /// registered custom analyses).
+    #[facet(default)]
+    pub findings: Vec<AnalysisFinding>,
+    /// Operator annotations whose target is a node, task or finding in this
+    /// snapshot.
+    #[facet(default)]
+    pub annotations: Vec<Annotation>,
+    /// Bounded channels and semaphores that have been running at capacity
+    /// over recent snapshots.
+    #[facet(default)]
+    pub sizing_hints: Vec<SizingHint>,
+    /// Version of the detection config `findings` were computed with; unset
+    /// when the collector runs with built-in defaults.
+    #[facet(skip_unless_truthy)]
+    pub detection_config_version: Option<String>,
+    /// Why the server-side analyses could not run over this snapshot, in
+    /// which case `findings` is empty.
+    #[facet(skip_unless_truthy)]
+    pub analysis_error: Option<String>,
+}
+
+/// One result reported by a server-side analysis over a snapshot.
+#[derive(Facet, Clone, Debug)]
+pub struct AnalysisFinding {
+    /// Name of the analysis that produced this finding.
+    pub analysis: String,
+    pub severity: FindingSeverity,
+    pub title: String,
+    /// Why the analysis flagged this.
+    pub rationale: String,
+    /// Entities the finding is about.
+    #[facet(default)]
+    pub subjects: Vec<FindingSubject>,
+    /// How the analysis ranked the finding, for analyses that score their
+    /// candidates.
+    #[facet(skip_unless_truthy)]
+    pub score: Option<ScoreBreakdown>,
+    /// Suggested next steps, for analyses that recognize the pattern.
+    #[facet(default)]
+    pub hints: Vec<String>,
+}
+
+/// Every rule that contributed to a candidate's score, in the order applied.
+#[derive(Facet, Clone, Debug, Default, PartialEq, Eq)]
+pub struct ScoreBreakdown {
+    #[facet(default)]
+    pub contributions: Vec<ScoreContribution>,
+}
+
+#[derive(Facet, Clone, Debug, PartialEq, Eq)]
+pub struct ScoreContribution {
+    /// Stable rule id, e.g. `wait_age`.
+    pub rule: String,
+    pub points: u32,
+}
+
+impl ScoreBreakdown {
+    pub fn add(&mut self, rule: &str, points: u32) {
+        self.contributions.push(ScoreContribution {
+            rule: String::from(rule),
+            points,
+        });
+    }
+
+    pub fn total(&self) -> u32 {
+        self.contributions.iter().map(|c| c.points).sum()
+    }
+}
+
+impl AnalysisFinding {
+    /// Identifies the finding across snapshots: the analysis name followed by
+    /// its sorted `process_id::entity_id` subjects.
+    pub fn fingerprint(&self) -> String {
+        let mut keys = self
+            .subjects
+            .iter()
+            .map(|subject| {
+                format!(
+                    "{}::{}",
+                    subject.process_id.as_str(),
+                    subject.entity_id.as_str()
+                )
+            })
+            .collect::<Vec<_>>();
+        keys.sort();
+        format!("{}:{}", self.analysis, keys.join(","))
+    }
+}
+
+#[derive(Facet, Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+#[repr(u8)]
+#[facet(rename_all = "snake_case")]
+pub enum FindingSeverity {
+    Info,
+    Warning,
+    Critical,
+}
+
+#[derive(Facet, Clone, Debug)]
+pub struct FindingSubject {
+    pub process_id: ProcessId,
+    pub entity_id: crate::EntityId,
+}
+
+impl FindingSubject {
+    pub fn new(process_id: &ProcessId, entity_id: &crate::EntityId) -> Self {
+        Self {
+            process_id: process_id.clone(),
+            entity_id: entity_id.clone(),
+        }
+    }
+}
+
+/// Utilization of one bounded resource over the collector's recent snapshots.
+#[derive(Facet, Clone, Debug)]
+pub struct SizingHint {
+    pub process_id: ProcessId,
+    pub entity_id: crate::EntityId,
+    pub resource: SizedResource,
+    /// Channel buffer size or semaphore permit count.
+    pub capacity: u32,
+    /// Queue depth or permits in use, 99th percentile over the samples.
+    pub p99_used: u32,
+    pub max_used: u32,
+    /// Snapshots the statistics are computed from.
+    pub samples: u32,
+    /// Human-readable advice, e.g. "bounded(16) but p99 depth=16 — consider
+    /// a larger buffer or a faster consumer".
+    pub hint: String,
+}
+
+#[derive(Facet, Clone, Copy, Debug, PartialEq, Eq)]
+#[repr(u8)]
+#[facet(rename_all = "snake_case")]
+pub enum SizedResource {
+    /// Sender side of a bounded mpsc channel; usage is queue depth.
+    MpscChannel,
+    /// Usage is permits handed out.
+    Semaphore,
 }
 
 #[derive(Facet, Clone, Debug)]
@@ -125,9 +269,22 @@ pub struct ProcessSnapshotView {
     pub process_name: String,
     pub pid: u32,
     pub ptime_now_ms: u64,
+    /// How long after the cut was requested this process's snapshot arrived.
+    /// Snapshots of one cut are taken up to this far apart, so waits compared
+    /// across processes are only this precise. Unset when unknown.
+    #[facet(skip_unless_truthy)]
+    pub skew_ms: Option<u64>,
     pub snapshot: crate::Snapshot,
     #[facet(default)]
     pub scope_entity_links: Vec<ScopeEntityLink>,
+    /// Sections left empty in `snapshot` because the process ran out of time
+    /// assembling them.
+    #[facet(skip_unless_truthy)]
+    pub timed_out_sections: Option<Vec<String>>,
+    /// Where `snapshot` breaks the data model contract. Empty for
+    /// well-formed processes.
+    #[facet(default)]
+    pub violations: Vec<crate::SnapshotViolation>,
 }
 
 #[derive(Facet)]
@@ -141,6 +298,88 @@ pub struct TimedOutProcess {
     pub process_id: ProcessId,
     pub process_name: String,
     pub pid: u32,
+    /// Sections the process reported it could not assemble in time. Absent
+    /// when the process did not reply at all.
+    #[facet(skip_unless_truthy)]
+    pub timed_out_sections: Option<Vec<String>>,
+}
+
+/// Headline statistics of a snapshot, for dashboards that do not need the
+/// whole snapshot.
+#[derive(Facet)]
+pub struct SnapshotSummaryResponse {
+    pub snapshot_id: i64,
+    pub captured_at_unix_ms: i64,
+    pub processes: Vec<ProcessSummary>,
+    #[facet(default)]
+    pub timed_out_processes: Vec<TimedOutProcess>,
+}
+
+/// One process's headline statistics. Lists hold at most the requested
+/// number of entries, worst first.
+#[derive(Facet)]
+pub struct ProcessSummary {
+    pub process_id: ProcessId,
+    pub process_name: String,
+    pub pid: u32,
+    pub entity_count: u32,
+    pub tasks: TaskStateCounts,
+    /// Longest-standing `waiting_on` edges.
+    #[facet(default)]
+    pub worst_waits: Vec<WaitSummary>,
+    /// Locks with the most waiters, then the most completed holds.
+    #[facet(default)]
+    pub busiest_locks: Vec<LockSummary>,
+    /// RPC methods by their slowest call, in flight or completed.
+    #[facet(default)]
+    pub slowest_rpc_methods: Vec<RpcMethodSummary>,
+}
+
+/// Live spawned tasks by state. A task counts under the first state that
+/// applies, in field order.
+#[derive(Facet, Default, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TaskStateCounts {
+    /// Woken and not polled since (see `wake_to_poll_gap_ms`).
+    pub woken: u32,
+    /// Waiting on another entity.
+    pub waiting: u32,
+    /// Waiting, but marked idle at instrumentation time.
+    pub idle: u32,
+    /// None of the above: running or about to be.
+    pub other: u32,
+}
+
+#[derive(Facet, Clone, Debug, PartialEq, Eq)]
+pub struct WaitSummary {
+    pub waiter: crate::EntityId,
+    pub waiter_name: String,
+    pub target: crate::EntityId,
+    pub target_name: String,
+    /// How long the wait had lasted when the snapshot was taken.
+    pub waited_ms: u64,
+}
+
+#[derive(Facet, Clone, Debug, PartialEq, Eq)]
+pub struct LockSummary {
+    pub entity_id: crate::EntityId,
+    pub name: String,
+    /// Futures waiting to acquire it.
+    pub waiters: u32,
+    /// Futures holding it.
+    pub holders: u32,
+    pub hold_count: u64,
+    pub avg_hold_ms: u64,
+}
+
+#[derive(Facet, Clone, Debug, PartialEq, Eq)]
+pub struct RpcMethodSummary {
+    pub service_name: String,
+    pub method_name: String,
+    /// Requests of this method in the snapshot, in flight or completed.
+    pub calls: u32,
+    pub in_flight: u32,
+    /// Longest call: elapsed time so far for calls in flight.
+    pub slowest_ms: u64,
 }
 
 #[derive(Facet)]
@@ -189,6 +428,75 @@ pub struct FrameSummary {
     pub capture_duration_ms: f64,
 }
 
+/// Request body for `POST /api/history`. Bounds are inclusive Unix epoch milliseconds.
+#[derive(Facet)]
+pub struct HistoryQueryRequest {
+    #[facet(skip_unless_truthy)]
+    pub from_unix_ms: Option<i64>,
+    #[facet(skip_unless_truthy)]
+    pub to_unix_ms: Option<i64>,
+    #[facet(skip_unless_truthy)]
+    pub limit: Option<u32>,
+    /// Only snapshots in which a task with this logical id was alive, in any
+    /// process generation.
+    #[facet(skip_unless_truthy)]
+    pub logical_id: Option<String>,
+}
+
+/// Response for `POST /api/history`.
+#[derive(Facet)]
+pub struct HistoryQueryResponse {
+    /// Recorded snapshots in the range, oldest first.
+    pub snapshots: Vec<HistorySnapshotSummary>,
+}
+
+/// One snapshot persisted by the history recorder.
+#[derive(Facet)]
+pub struct HistorySnapshotSummary {
+    /// Storage id, stable across server restarts; fetch the full snapshot with
+    /// `GET /api/history/{history_id}`.
+    pub history_id: i64,
+    /// Server-side snapshot id at capture time; restarts from 1 with the server.
+    pub snapshot_id: i64,
+    pub captured_at_unix_ms: i64,
+    pub process_count: u32,
+    /// Wait graph nodes recorded for this snapshot.
+    pub node_count: u32,
+    /// Wait graph edges recorded for this snapshot.
+    pub edge_count: u32,
+    /// Analysis findings recorded for this snapshot.
+    pub findings: Vec<AnalysisFinding>,
+}
+
+/// A note an operator attached to a node, task or finding.
+#[derive(Facet, Clone, Debug)]
+pub struct Annotation {
+    pub annotation_id: i64,
+    /// A wait graph node key (`process_id::entity_id`), a task logical id, or
+    /// a finding fingerprint.
+    pub target: String,
+    pub note: String,
+    #[facet(skip_unless_truthy)]
+    pub author: Option<String>,
+    pub created_at_unix_ms: i64,
+}
+
+/// Request body for `POST /api/annotations`.
+#[derive(Facet)]
+pub struct AnnotationCreateRequest {
+    pub target: String,
+    pub note: String,
+    #[facet(skip_unless_truthy)]
+    pub author: Option<String>,
+}
+
+/// Response for `GET /api/annotations`.
+#[derive(Facet)]
+pub struct AnnotationListResponse {
+    /// Every stored annotation, newest first.
+    pub annotations: Vec<Annotation>,
+}
+
 #[derive(Facet)]
 pub struct RecordingImportFrame {
     pub frame_index: u32,
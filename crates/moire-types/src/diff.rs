@@ -55,6 +55,7 @@ impl fmt::Display for CutId {
 #[derive(Facet)]
 #[repr(u8)]
 #[facet(rename_all = "snake_case")]
+#[allow(clippy::large_enum_variant)]
 pub enum Change {
     /// Insert or replace entity state.
     UpsertEntity(Entity),
@@ -0,0 +1,192 @@
+// r[impl api.snapshot.dump]
+//! Snapshot dumps: a [`SnapshotCutResponse`] saved to a file and read back by
+//! a later, possibly different, build.
+//!
+//! A dump is the snapshot wrapped in an envelope naming its schema version.
+//! Readers accept bare snapshots, as written before the envelope existed, and
+//! ignore fields they do not know, so dumps from newer builds of the same
+//! schema version still load.
+//!
+//! Dumps of large processes run to megabytes of JSON, so the same envelope
+//! can also be written as MessagePack, which keeps those properties at a
+//! fraction of the size. [`snapshot_from_dump`] reads either.
+use facet::Facet;
+
+use crate::SnapshotCutResponse;
+
+/// Schema version written into every dump. Bumped when a dump written by a
+/// newer build would be misread by an older one, not for added fields.
+pub const SNAPSHOT_DUMP_SCHEMA_VERSION: u32 = 1;
+
+#[derive(Facet)]
+struct SnapshotDump {
+    schema_version: u32,
+    snapshot: SnapshotCutResponse,
+}
+
+#[derive(Facet)]
+struct SnapshotDumpRef<'a> {
+    schema_version: u32,
+    snapshot: &'a SnapshotCutResponse,
+}
+
+#[derive(Facet)]
+struct SnapshotDumpHeader {
+    #[facet(default)]
+    schema_version: Option<u32>,
+}
+
+/// Encodes `snapshot` as a dump.
+pub fn snapshot_to_json(snapshot: &SnapshotCutResponse) -> Result<String, String> {
+    facet_json::to_string(&SnapshotDumpRef {
+        schema_version: SNAPSHOT_DUMP_SCHEMA_VERSION,
+        snapshot,
+    })
+    .map_err(|e| format!("encode snapshot dump: {e}"))
+}
+
+/// Decodes a dump, or a bare snapshot.
+pub fn snapshot_from_json(json: &str) -> Result<SnapshotCutResponse, String> {
+    let header = facet_json::from_str::<SnapshotDumpHeader>(json)
+        .map_err(|e| format!("decode snapshot dump: {e}"))?;
+    match header.schema_version {
+        None => facet_json::from_str::<SnapshotCutResponse>(json)
+            .map_err(|e| format!("decode snapshot: {e}")),
+        Some(version) => {
+            check_schema_version(version)?;
+            facet_json::from_str::<SnapshotDump>(json)
+                .map(|dump| dump.snapshot)
+                .map_err(|e| format!("decode snapshot dump: {e}"))
+        }
+    }
+}
+
+/// Encodes `snapshot` as a MessagePack dump.
+pub fn snapshot_to_msgpack(snapshot: &SnapshotCutResponse) -> Result<Vec<u8>, String> {
+    facet_msgpack::to_vec(&SnapshotDumpRef {
+        schema_version: SNAPSHOT_DUMP_SCHEMA_VERSION,
+        snapshot,
+    })
+    .map_err(|e| format!("encode snapshot dump msgpack: {e}"))
+}
+
+/// Decodes a MessagePack dump.
+pub fn snapshot_from_msgpack(bytes: &[u8]) -> Result<SnapshotCutResponse, String> {
+    let header = facet_msgpack::from_slice::<SnapshotDumpHeader>(bytes)
+        .map_err(|e| format!("decode snapshot dump msgpack: {e}"))?;
+    check_schema_version(header.schema_version.unwrap_or(0))?;
+    facet_msgpack::from_slice::<SnapshotDump>(bytes)
+        .map(|dump| dump.snapshot)
+        .map_err(|e| format!("decode snapshot dump msgpack: {e}"))
+}
+
+/// Decodes a dump in either encoding, telling them apart by the first byte:
+/// JSON dumps are objects, MessagePack ones are not text.
+pub fn snapshot_from_dump(bytes: &[u8]) -> Result<SnapshotCutResponse, String> {
+    match bytes.iter().find(|b| !b.is_ascii_whitespace()) {
+        Some(b'{') => {
+            let json = std::str::from_utf8(bytes)
+                .map_err(|e| format!("decode snapshot dump: not utf-8: {e}"))?;
+            snapshot_from_json(json)
+        }
+        _ => snapshot_from_msgpack(bytes),
+    }
+}
+
+fn check_schema_version(version: u32) -> Result<(), String> {
+    if version > SNAPSHOT_DUMP_SCHEMA_VERSION {
+        return Err(format!(
+            "snapshot dump has schema version {version}, this build reads up to {SNAPSHOT_DUMP_SCHEMA_VERSION}"
+        ));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn snapshot() -> SnapshotCutResponse {
+        SnapshotCutResponse {
+            snapshot_id: 7,
+            captured_at_unix_ms: 1_700_000_000_000,
+            max_skew_ms: Some(12),
+            processes: vec![],
+            timed_out_processes: vec![],
+            backtraces: vec![],
+            frames: vec![],
+            findings: vec![],
+            annotations: vec![],
+            sizing_hints: vec![],
+            detection_config_version: Some(String::from("v3")),
+            analysis_error: None,
+        }
+    }
+
+    fn assert_is_snapshot(read: &SnapshotCutResponse) {
+        assert_eq!(read.snapshot_id, 7);
+        assert_eq!(read.captured_at_unix_ms, 1_700_000_000_000);
+        assert_eq!(read.max_skew_ms, Some(12));
+        assert_eq!(read.detection_config_version.as_deref(), Some("v3"));
+    }
+
+    #[test]
+    fn bare_snapshots_are_still_read() {
+        let bare = facet_json::to_string(&snapshot()).unwrap();
+        assert_is_snapshot(&snapshot_from_json(&bare).unwrap());
+        assert_is_snapshot(&snapshot_from_dump(bare.as_bytes()).unwrap());
+    }
+
+    #[test]
+    fn newer_schema_versions_are_rejected() {
+        let newer = SNAPSHOT_DUMP_SCHEMA_VERSION + 1;
+        let assert_names_both_versions = |read: Result<SnapshotCutResponse, String>| {
+            let Err(err) = read else {
+                panic!("a schema version {newer} dump was read");
+            };
+            assert!(err.contains(&format!("schema version {newer}")), "{err}");
+            assert!(
+                err.contains(&format!("reads up to {SNAPSHOT_DUMP_SCHEMA_VERSION}")),
+                "{err}"
+            );
+        };
+        let snapshot = snapshot();
+        let dump = SnapshotDumpRef {
+            schema_version: newer,
+            snapshot: &snapshot,
+        };
+
+        assert_names_both_versions(snapshot_from_json(&facet_json::to_string(&dump).unwrap()));
+        assert_names_both_versions(snapshot_from_msgpack(
+            &facet_msgpack::to_vec(&dump).unwrap(),
+        ));
+    }
+
+    #[test]
+    fn dumps_round_trip_in_both_encodings() {
+        let json = snapshot_to_json(&snapshot()).unwrap();
+        assert!(json.contains(&format!(
+            "\"schema_version\":{SNAPSHOT_DUMP_SCHEMA_VERSION}"
+        )));
+        assert_is_snapshot(&snapshot_from_json(&json).unwrap());
+
+        let msgpack = snapshot_to_msgpack(&snapshot()).unwrap();
+        assert_is_snapshot(&snapshot_from_msgpack(&msgpack).unwrap());
+    }
+
+    #[test]
+    fn dumps_are_decoded_by_their_encoding() {
+        let json = format!("\n  {}", snapshot_to_json(&snapshot()).unwrap());
+        assert_is_snapshot(&snapshot_from_dump(json.as_bytes()).unwrap());
+
+        let msgpack = snapshot_to_msgpack(&snapshot()).unwrap();
+        assert_is_snapshot(&snapshot_from_dump(&msgpack).unwrap());
+
+        // A JSON dump is never handed to the MessagePack decoder, so its
+        // errors name the JSON decoder.
+        let Err(err) = snapshot_from_dump(b"{\"schema_version\":") else {
+            panic!("a truncated dump was read");
+        };
+        assert!(err.starts_with("decode snapshot dump:"), "{err}");
+    }
+}
@@ -106,6 +106,9 @@ macro_rules! define_entity_body {
         #[repr(u8)]
         #[facet(rename_all = "snake_case")]
         #[allow(dead_code)]
+        // Bodies are built, diffed and matched by value; boxing the large
+        // future body would put an allocation on the hottest path instead.
+        #[allow(clippy::large_enum_variant)]
         $vis enum EntityBody {
             $(
                 $(#[$variant_meta])*
@@ -318,16 +321,20 @@ macro_rules! declare_edge_kind_slots {
 
 pub(crate) mod api;
 pub(crate) mod diff;
+pub(crate) mod dump;
 pub(crate) mod objects;
 pub(crate) mod primitives;
 pub(crate) mod recording;
 pub(crate) mod snapshots;
 pub(crate) mod sources;
+pub(crate) mod validation;
 
 pub use api::*;
 pub use diff::*;
+pub use dump::*;
 pub use objects::*;
 pub use primitives::*;
 pub use recording::*;
 pub use snapshots::*;
 pub use sources::*;
+pub use validation::*;
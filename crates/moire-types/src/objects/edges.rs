@@ -1,7 +1,7 @@
 use facet::Facet;
 use moire_trace_types::BacktraceId;
 
-use crate::EntityId;
+use crate::{EntityBody, EntityId, LockKind, PTime};
 
 // r[impl model.edge.fields]
 /// Relationship between two entities.
@@ -18,16 +18,26 @@ pub struct Edge {
 
     /// Causal edge kind.
     pub kind: EdgeKind,
+
+    /// When the edge was created. Absent in recordings that predate it.
+    #[facet(skip_unless_truthy)]
+    pub since: Option<PTime>,
+
+    /// Why the source waits, for `waiting_on` edges whose reason is known.
+    #[facet(skip_unless_truthy)]
+    pub reason: Option<EdgeReason>,
 }
 
 impl Edge {
-    /// Builds a causal edge.
+    /// Builds a causal edge created now.
     pub fn new(src: EntityId, dst: EntityId, kind: EdgeKind, backtrace: BacktraceId) -> Self {
         Self {
             src,
             dst,
             backtrace,
             kind,
+            since: Some(PTime::now()),
+            reason: None,
         }
     }
 }
@@ -66,3 +76,107 @@ crate::declare_edge_kind_slots!(
     PairedWithEdgeKindSlot::PairedWith,
     HeldByEdgeKindSlot::HeldBy,
 );
+
+// r[impl model.edge.reason]
+/// Machine-readable reason of a `waiting_on` edge, for alerting rules and
+/// exports that should not parse entity names.
+#[derive(Facet, Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[repr(u8)]
+#[facet(rename_all = "snake_case")]
+pub enum EdgeReason {
+    /// Sending on a full bounded mpsc channel.
+    MpscFull,
+    /// Receiving from an empty mpsc channel.
+    MpscEmpty,
+    /// Receiving from a broadcast channel with nothing new.
+    BroadcastEmpty,
+    /// Waiting for a watch value to change.
+    WatchUnchanged,
+    /// Waiting for a oneshot value.
+    OneshotPending,
+    /// Acquiring a mutex.
+    MutexWait,
+    /// Acquiring an rwlock for reading.
+    RwlockReadWait,
+    /// Acquiring an rwlock for writing.
+    RwlockWriteWait,
+    /// Acquiring a lock of another or unknown kind.
+    LockWait,
+    /// Acquiring semaphore permits.
+    SemaphoreAcquire,
+    /// Waiting for a notification.
+    NotifyWait,
+    /// Waiting for another caller to initialize a once cell.
+    OncecellInitializing,
+    /// Waiting for the other parties of a barrier.
+    BarrierWait,
+    /// Waiting for an RPC response.
+    RpcAwaitingResponse,
+    /// Waiting for a child process.
+    CommandWait,
+    /// Waiting for a file operation.
+    FileOpWait,
+    /// Waiting for a network connect, accept, read or write.
+    NetIo,
+    /// Awaiting another future or task.
+    FutureAwait,
+}
+
+impl EdgeReason {
+    /// The reason implied by an edge's kind and the entity it points at.
+    /// Only `waiting_on` edges have one, and rwlock waits cannot tell reads
+    /// from writes here; the instrumented lock records those itself.
+    pub fn infer(kind: EdgeKind, dst: &EntityBody) -> Option<Self> {
+        if kind != EdgeKind::WaitingOn {
+            return None;
+        }
+        Some(match dst {
+            EntityBody::MpscTx(_) => Self::MpscFull,
+            EntityBody::MpscRx(_) => Self::MpscEmpty,
+            EntityBody::BroadcastRx(_) => Self::BroadcastEmpty,
+            EntityBody::WatchRx(_) => Self::WatchUnchanged,
+            EntityBody::OneshotRx(_) => Self::OneshotPending,
+            EntityBody::Lock(lock) => match lock.kind {
+                LockKind::Mutex => Self::MutexWait,
+                LockKind::RwLock | LockKind::Other => Self::LockWait,
+            },
+            EntityBody::Semaphore(_) => Self::SemaphoreAcquire,
+            EntityBody::Notify(_) => Self::NotifyWait,
+            EntityBody::OnceCell(_) => Self::OncecellInitializing,
+            EntityBody::Barrier(_) => Self::BarrierWait,
+            EntityBody::Request(_) | EntityBody::Response(_) => Self::RpcAwaitingResponse,
+            EntityBody::Command(_) => Self::CommandWait,
+            EntityBody::FileOp(_) => Self::FileOpWait,
+            EntityBody::NetConnect(_)
+            | EntityBody::NetAccept(_)
+            | EntityBody::NetRead(_)
+            | EntityBody::NetWrite(_) => Self::NetIo,
+            EntityBody::Future(_) => Self::FutureAwait,
+            _ => return None,
+        })
+    }
+
+    /// The snake_case name used on the wire.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::MpscFull => "mpsc_full",
+            Self::MpscEmpty => "mpsc_empty",
+            Self::BroadcastEmpty => "broadcast_empty",
+            Self::WatchUnchanged => "watch_unchanged",
+            Self::OneshotPending => "oneshot_pending",
+            Self::MutexWait => "mutex_wait",
+            Self::RwlockReadWait => "rwlock_read_wait",
+            Self::RwlockWriteWait => "rwlock_write_wait",
+            Self::LockWait => "lock_wait",
+            Self::SemaphoreAcquire => "semaphore_acquire",
+            Self::NotifyWait => "notify_wait",
+            Self::OncecellInitializing => "oncecell_initializing",
+            Self::BarrierWait => "barrier_wait",
+            Self::RpcAwaitingResponse => "rpc_awaiting_response",
+            Self::CommandWait => "command_wait",
+            Self::FileOpWait => "file_op_wait",
+            Self::NetIo => "net_io",
+            Self::FutureAwait => "future_await",
+        }
+    }
+}
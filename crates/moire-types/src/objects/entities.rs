@@ -30,9 +30,20 @@ pub struct Entity {
 impl Entity {
     /// Create a new entity: ID and birth time are generated automatically.
     pub fn new(backtrace: BacktraceId, name: impl Into<String>, body: EntityBody) -> Entity {
+        Self::new_at(backtrace, name, body, PTime::now())
+    }
+
+    /// Create a new entity born at `birth`, for runtimes that keep their own
+    /// clock.
+    pub fn new_at(
+        backtrace: BacktraceId,
+        name: impl Into<String>,
+        body: EntityBody,
+        birth: PTime,
+    ) -> Entity {
         Entity {
             id: next_entity_id(),
-            birth: PTime::now(),
+            birth,
             removed_at: None,
             backtrace,
             name: name.into(),
@@ -58,6 +69,10 @@ crate::define_entity_body! {
         Semaphore(SemaphoreEntity),
         Notify(NotifyEntity),
         OnceCell(OnceCellEntity),
+        Barrier(BarrierEntity),
+
+        // Liveness
+        Heartbeat(HeartbeatEntity),
 
         // System and I/O boundaries
         Command(CommandEntity),
@@ -88,12 +103,154 @@ pub struct FutureEntity {
     /// and the callsite is shown instead.
     #[facet(skip_unless_truthy)]
     pub skip_entry_frames: Option<u8>,
+    /// Number of times the future was polled again after returning `Pending`.
+    #[facet(skip_unless_truthy)]
+    pub wake_count: Option<u64>,
+    /// Wakes after which nothing under the future completed and it stayed `Pending`.
+    #[facet(skip_unless_truthy)]
+    pub unproductive_wake_count: Option<u64>,
+    /// Instrumented future that was being polled when this one was last woken.
+    #[facet(skip_unless_truthy)]
+    pub last_woken_by: Option<EntityId>,
+    /// How long ago, in milliseconds, the future was woken without being
+    /// polled since. Only set past the runtime's wake-gap threshold, and
+    /// refreshed when a snapshot is taken: a lost wakeup or a stalled runtime
+    /// rather than a wait.
+    #[facet(skip_unless_truthy)]
+    pub wake_to_poll_gap_ms: Option<u64>,
+    /// Number of times the future has been polled.
+    #[facet(skip_unless_truthy)]
+    pub poll_count: Option<u64>,
+    /// Thread that ran the most recent recorded poll (see `Snapshot::workers`).
+    #[facet(skip_unless_truthy)]
+    pub poll_worker: Option<u64>,
+    /// Polls that ran on a different thread than the poll before them.
+    #[facet(skip_unless_truthy)]
+    pub worker_migrations: Option<u64>,
+    /// Total time spent inside `poll`, in microseconds. Includes instrumented
+    /// futures polled from within it.
+    #[facet(skip_unless_truthy)]
+    pub busy_us: Option<u64>,
+    /// How long individual polls took.
+    #[facet(skip_unless_truthy)]
+    pub poll_histogram: Option<PollHistogram>,
+    /// Stable identity of a spawned task across process restarts: a hash of
+    /// its name and spawn callsite.
+    #[facet(skip_unless_truthy)]
+    pub logical_id: Option<String>,
+    /// Task future that was running when this task was spawned. Absent for
+    /// futures that are not tasks and for tasks spawned outside any task.
+    #[facet(skip_unless_truthy)]
+    pub spawned_by: Option<EntityId>,
+    /// Why this future is expected to wait indefinitely (for example
+    /// `shutdown-signal`). Idle waits are left out of severity ranking.
+    #[facet(skip_unless_truthy)]
+    pub idle: Option<String>,
+    /// Where the future was named, for futures named with `moire::named!` or
+    /// `#[moire::instrument]`.
+    #[facet(skip_unless_truthy)]
+    pub callsite: Option<Callsite>,
+    /// Timer statistics, for timers armed through `moire::time`.
+    #[facet(skip_unless_truthy)]
+    pub timer: Option<TimerStats>,
+}
+
+/// Source location a future was named at.
+#[derive(Facet, Clone, Debug, PartialEq, Eq)]
+pub struct Callsite {
+    /// Name of the crate, from `CARGO_PKG_NAME`.
+    pub crate_name: String,
+    /// Module path, from `module_path!()`.
+    pub module_path: String,
+    pub file: String,
+    pub line: u32,
+}
+
+/// How a `moire::time` timer has fired against its schedule.
+#[derive(Facet, Clone, Debug, Default, PartialEq, Eq)]
+pub struct TimerStats {
+    /// How long the timer waits (the period, for an interval), in
+    /// milliseconds.
+    pub duration_ms: u64,
+    /// Times the timer has fired.
+    pub fires: u64,
+    /// How long after its deadline the most recent fire resumed the waiter,
+    /// in milliseconds. Includes the time the waiter took to be polled.
+    #[facet(skip_unless_truthy)]
+    pub last_late_ms: Option<u64>,
+    /// The latest any fire has resumed its waiter, in milliseconds.
+    #[facet(skip_unless_truthy)]
+    pub max_late_ms: Option<u64>,
+}
+
+/// Upper bounds, in microseconds, of every [`PollHistogram`] bucket but the
+/// last.
+pub const POLL_HISTOGRAM_BOUNDS_US: [u64; 7] =
+    [10, 100, 1_000, 10_000, 100_000, 1_000_000, 10_000_000];
+
+/// Poll durations of a future, counted in log-scale buckets.
+#[derive(Facet, Clone, Debug, Default, PartialEq, Eq)]
+pub struct PollHistogram {
+    /// Polls per bucket: bucket `i` counts polls shorter than
+    /// `POLL_HISTOGRAM_BOUNDS_US[i]` that did not fit an earlier bucket, and
+    /// the last bucket counts polls of 10s or more.
+    pub buckets: Vec<u64>,
+}
+
+impl PollHistogram {
+    /// Counts one poll that took `duration_us`.
+    pub fn record(&mut self, duration_us: u64) {
+        self.buckets.resize(POLL_HISTOGRAM_BOUNDS_US.len() + 1, 0);
+        let bucket = POLL_HISTOGRAM_BOUNDS_US
+            .iter()
+            .position(|bound| duration_us < *bound)
+            .unwrap_or(POLL_HISTOGRAM_BOUNDS_US.len());
+        self.buckets[bucket] = self.buckets[bucket].saturating_add(1);
+    }
 }
 
 #[derive(Facet)]
 pub struct LockEntity {
     /// Kind of lock primitive.
     pub kind: LockKind,
+    /// Completed holds, folded in as `held_by` edges are released.
+    #[facet(skip_unless_truthy)]
+    pub holds: Option<HoldStats>,
+    /// Reader/writer breakdown, for read-write locks.
+    #[facet(skip_unless_truthy)]
+    pub rwlock: Option<RwLockState>,
+}
+
+/// Current readers, writer and waiters of a read-write lock.
+#[derive(Facet, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct RwLockState {
+    /// Read guards currently held.
+    pub readers: u32,
+    /// Whether a write guard is currently held.
+    pub writer: bool,
+    /// Tasks waiting for a read guard.
+    pub waiting_readers: u32,
+    /// Tasks waiting for a write guard.
+    pub waiting_writers: u32,
+}
+
+/// Running statistics over completed holds of a lock or semaphore.
+#[derive(Facet, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct HoldStats {
+    /// Number of holds released so far.
+    pub hold_count: u64,
+    /// Mean hold duration in milliseconds.
+    pub avg_hold_ms: u64,
+}
+
+impl HoldStats {
+    /// Folds one completed hold into the running mean.
+    pub fn record(&mut self, held_ms: u64) {
+        self.hold_count = self.hold_count.saturating_add(1);
+        let avg = self.avg_hold_ms as i128;
+        let delta = (held_ms as i128 - avg) / self.hold_count as i128;
+        self.avg_hold_ms = (avg + delta).max(0) as u64;
+    }
 }
 
 #[derive(Facet)]
@@ -111,19 +268,57 @@ pub struct MpscTxEntity {
     pub queue_len: u32,
     /// Configured capacity (`None` for unbounded).
     pub capacity: Option<u32>,
+    /// Futures blocked on the full channel in `send` or `reserve_owned`,
+    /// oldest first.
+    #[facet(default)]
+    pub send_waiters: Vec<MpscSendWaiter>,
+    /// Recent `queue_len` samples, oldest first. Empty unless the process
+    /// keeps stat history (`MOIRE_STAT_HISTORY`).
+    #[facet(default)]
+    pub history: Vec<StatSample>,
 }
 
+/// One sample of a primitive's stat history: the value it had at `at`, which
+/// it kept until the next sample.
 #[derive(Facet, Clone, Copy, Debug, PartialEq, Eq)]
-pub struct MpscRxEntity {}
+pub struct StatSample {
+    pub at: PTime,
+    pub value: u32,
+}
+
+#[derive(Facet, Clone, Debug, PartialEq, Eq)]
+pub struct MpscSendWaiter {
+    /// The blocked future, or its task when it is not instrumented.
+    pub future: EntityId,
+    /// When it started waiting.
+    pub since: PTime,
+}
+
+#[derive(Facet, Clone, Debug, PartialEq, Eq)]
+pub struct MpscRxEntity {
+    /// Instrumented future that last awaited `recv`, and so drains the
+    /// channel.
+    #[facet(skip_unless_truthy)]
+    pub receiver: Option<EntityId>,
+}
 
 #[derive(Facet)]
 pub struct BroadcastTxEntity {
     pub capacity: u32,
+    /// Live sender handles.
+    #[facet(skip_unless_truthy)]
+    pub sender_count: Option<u32>,
+    /// Live receiver handles.
+    #[facet(skip_unless_truthy)]
+    pub receiver_count: Option<u32>,
 }
 
 #[derive(Facet)]
 pub struct BroadcastRxEntity {
     pub lag: u32,
+    /// Messages this receiver missed because it fell more than `capacity` behind.
+    #[facet(skip_unless_truthy)]
+    pub dropped: Option<u64>,
 }
 
 #[derive(Facet)]
@@ -139,8 +334,12 @@ pub struct OneshotTxEntity {
     pub sent: bool,
 }
 
-#[derive(Facet, Clone, Copy, Debug, PartialEq, Eq)]
-pub struct OneshotRxEntity {}
+#[derive(Facet, Clone, Debug, PartialEq, Eq)]
+pub struct OneshotRxEntity {
+    /// Future that awaited the receiver, once it has been awaited.
+    #[facet(skip_unless_truthy)]
+    pub receiver: Option<EntityId>,
+}
 
 #[derive(Facet)]
 pub struct SemaphoreEntity {
@@ -148,6 +347,26 @@ pub struct SemaphoreEntity {
     pub max_permits: u32,
     /// Current number of permits acquired and not yet released.
     pub handed_out_permits: u32,
+    /// Completed holds, folded in as `held_by` edges are released.
+    #[facet(skip_unless_truthy)]
+    pub holds: Option<HoldStats>,
+    /// Futures currently holding permits, longest-held first.
+    #[facet(default)]
+    pub holders: Vec<SemaphoreHolder>,
+    /// Recent `handed_out_permits` samples, oldest first. Empty unless the
+    /// process keeps stat history (`MOIRE_STAT_HISTORY`).
+    #[facet(default)]
+    pub history: Vec<StatSample>,
+}
+
+#[derive(Facet, Clone, Debug, PartialEq, Eq)]
+pub struct SemaphoreHolder {
+    /// The holding future, or its task when it is not instrumented.
+    pub holder: EntityId,
+    /// Permits it holds, across all of its permit guards.
+    pub permits: u32,
+    /// When it acquired the first of them.
+    pub since: PTime,
 }
 
 #[derive(Facet)]
@@ -162,6 +381,12 @@ pub struct OnceCellEntity {
     pub waiter_count: u32,
     /// Current once-cell lifecycle state.
     pub state: OnceCellState,
+    /// Task running the init function, while one is running.
+    #[facet(skip_unless_truthy)]
+    pub initializer: Option<EntityId>,
+    /// Tasks blocked in `get_or_init` on the initializer, oldest first.
+    #[facet(default)]
+    pub waiters: Vec<EntityId>,
 }
 
 #[derive(Facet, Clone, Copy, Debug, PartialEq, Eq)]
@@ -173,6 +398,34 @@ pub enum OnceCellState {
     Initialized,
 }
 
+#[derive(Facet)]
+pub struct BarrierEntity {
+    /// Number of tasks that must call `wait` before all of them are released.
+    pub parties: u32,
+    /// Tasks that have called `wait` in the current generation.
+    pub arrived: u32,
+    /// Tasks currently parked at the barrier, oldest arrival first.
+    #[facet(default)]
+    pub waiting: Vec<EntityId>,
+    /// When the oldest parked task arrived.
+    #[facet(skip_unless_truthy)]
+    pub oldest_arrival_at: Option<PTime>,
+}
+
+/// A loop that reports it is still making progress by beating at a known
+/// interval.
+#[derive(Facet)]
+pub struct HeartbeatEntity {
+    /// How often the loop is expected to beat, in milliseconds.
+    pub interval_ms: u64,
+    /// Beats so far. Updated together with `last_beat_at`, so it may lag the
+    /// loop by the beats of a fraction of an interval.
+    pub beats: u64,
+    /// When the loop last beat. Absent until the first beat is recorded.
+    #[facet(skip_unless_truthy)]
+    pub last_beat_at: Option<PTime>,
+}
+
 #[derive(Facet)]
 pub struct CommandEntity {
     /// Executable path or program name.
@@ -221,12 +474,18 @@ pub struct NetAcceptEntity {
 pub struct NetReadEntity {
     /// Endpoint address string (for example `127.0.0.1:8080`).
     pub addr: String,
+    /// OS file descriptor of the socket, on Unix.
+    #[facet(skip_unless_truthy)]
+    pub fd: Option<u64>,
 }
 
 #[derive(Facet)]
 pub struct NetWriteEntity {
     /// Endpoint address string (for example `127.0.0.1:8080`).
     pub addr: String,
+    /// OS file descriptor of the socket, on Unix.
+    #[facet(skip_unless_truthy)]
+    pub fd: Option<u64>,
 }
 
 /// Correlation token for RPC is the request entity id propagated in metadata.
@@ -245,6 +504,9 @@ pub struct RequestEntity {
     ///
     /// This is always valid JSON and should be `[]` when the method has no args.
     pub args_json: Json,
+    /// Generation of the client's connection the request was last sent on.
+    #[facet(skip_unless_truthy)]
+    pub connection_generation: Option<u64>,
 }
 
 #[derive(Facet)]
@@ -255,6 +517,9 @@ pub struct ResponseEntity {
     pub method_name: String,
     /// Response status and payload/error details.
     pub status: ResponseStatus,
+    /// Generation of the server's connection the request arrived on.
+    #[facet(skip_unless_truthy)]
+    pub connection_generation: Option<u64>,
 }
 
 #[derive(Facet, Clone, Debug, PartialEq, Eq)]
@@ -299,13 +564,14 @@ pub struct CustomEntity {
     pub attrs: Json,
 }
 
-/// Synthetic entity for uninstrumented tasks.
+/// Synthetic entity for uninstrumented tasks and threads.
 ///
 /// Created automatically when a moire-instrumented primitive is used from a task
-/// that was not spawned via `moire::task::spawn`. Makes deadlocks in uninstrumented
-/// code visible on the dashboard.
+/// that was not spawned via `moire::task::spawn`, or from a thread outside any
+/// Tokio task. Makes deadlocks in uninstrumented code visible on the dashboard.
 #[derive(Facet)]
 pub struct AetherEntity {
-    /// Tokio task ID that this aether represents.
+    /// Tokio task ID that this aether represents, or outside any task `main`
+    /// or `thread-<n>[-<name>]` for the thread.
     pub task_id: String,
 }
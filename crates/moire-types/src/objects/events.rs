@@ -67,6 +67,43 @@ pub struct CustomEventKind {
     pub payload: Json,
 }
 
+/// Custom event kind recorded on a resource whose holder future was dropped
+/// before completing while still holding it. Payload: [`HolderCancelledPayload`].
+pub const HOLDER_CANCELLED_EVENT: &str = "holder_cancelled";
+
+/// Custom event kind recorded on a future dropped before completing while it
+/// still held resources. Payload: [`CancelledWhileHoldingPayload`].
+pub const CANCELLED_WHILE_HOLDING_EVENT: &str = "cancelled_while_holding";
+
+/// Custom event kind recorded on a connection scope each time the connection
+/// is established. Payload: [`ConnectionOpenedPayload`].
+pub const CONNECTION_OPENED_EVENT: &str = "connection_opened";
+
+/// Custom event kind recorded on a connection scope when the connection goes
+/// down. Payload: [`ConnectionClosedPayload`].
+pub const CONNECTION_CLOSED_EVENT: &str = "connection_closed";
+
+#[derive(Facet)]
+pub struct HolderCancelledPayload {
+    /// The cancelled future. Its entity stays in snapshots as a tombstone while
+    /// events reference it.
+    pub holder_id: EntityId,
+    pub holder_name: String,
+}
+
+#[derive(Facet)]
+pub struct CancelledWhileHoldingPayload {
+    pub held: Vec<HeldResource>,
+}
+
+#[derive(Facet)]
+pub struct HeldResource {
+    pub entity_id: EntityId,
+    pub name: String,
+    /// Entity kind name, e.g. `lock` or `semaphore`.
+    pub kind: String,
+}
+
 crate::impl_sqlite_json!(EventTarget);
 crate::impl_sqlite_json!(EventKind);
 
@@ -96,3 +133,17 @@ pub struct ChannelReceivedEvent {
     /// True if the recv failed because the other side was gone.
     pub closed: bool,
 }
+
+#[derive(Facet)]
+pub struct ConnectionOpenedPayload {
+    pub generation: u64,
+    /// Reconnects within the flap window, this one included.
+    pub flaps: u32,
+}
+
+#[derive(Facet)]
+pub struct ConnectionClosedPayload {
+    pub generation: u64,
+    #[facet(skip_unless_truthy)]
+    pub reason: Option<String>,
+}
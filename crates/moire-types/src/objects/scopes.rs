@@ -1,9 +1,9 @@
 use facet::Facet;
 
-use crate::{next_scope_id, BacktraceId, PTime, ScopeId};
+use crate::{next_scope_id, BacktraceId, EntityId, PTime, ScopeId};
 
 // r[impl model.scope.fields]
-/// A scope groups execution context over time (for example process/thread/task/connection).
+/// A scope groups execution context over time (for example process/thread/task/connection/actor).
 #[derive(Facet)]
 pub struct Scope {
     /// Opaque scope identifier.
@@ -44,6 +44,8 @@ pub enum ScopeBody {
     Thread(ThreadScopeBody),
     Task(TaskScopeBody),
     Connection(ConnectionScopeBody),
+    Actor(ActorScopeBody),
+    TaskGroup(TaskGroupScopeBody),
 }
 
 #[derive(Facet)]
@@ -59,12 +61,59 @@ pub struct ThreadScopeBody {
 #[derive(Facet)]
 pub struct TaskScopeBody {
     pub task_key: String,
+    /// Stable identity of the task across process restarts (name hash plus
+    /// spawn callsite). `task_key` is only unique within one process.
+    #[facet(skip_unless_truthy)]
+    pub logical_id: Option<String>,
 }
 
 #[derive(Facet)]
 pub struct ConnectionScopeBody {
     pub local_addr: Option<String>,
     pub peer_addr: Option<String>,
+    /// Whether the connection to the peer is currently up.
+    #[facet(skip_unless_truthy)]
+    pub state: Option<ConnectionState>,
+    /// How many times the connection has been established, counting the
+    /// first time. Requests and responses record the generation they ran on.
+    #[facet(skip_unless_truthy)]
+    pub generation: Option<u64>,
+    /// Reconnects within the last flap window (see
+    /// [`CONNECTION_FLAP_WINDOW_MS`]).
+    #[facet(skip_unless_truthy)]
+    pub flaps: Option<u32>,
+}
+
+/// Sliding window over which reconnects count as flaps.
+pub const CONNECTION_FLAP_WINDOW_MS: u64 = 60_000;
+
+#[derive(Facet, Clone, Copy, Debug, PartialEq, Eq)]
+#[repr(u8)]
+#[facet(rename_all = "snake_case")]
+pub enum ConnectionState {
+    Open,
+    Closed,
+}
+
+/// An actor ties a mailbox channel and the driver task that drains it together.
+///
+/// The mailbox endpoints and the driver future are linked to this scope, so
+/// consumers can render them as one composite node.
+#[derive(Facet)]
+pub struct ActorScopeBody {
+    /// Configured mailbox capacity (`None` for unbounded mailboxes).
+    pub mailbox_capacity: Option<u32>,
+}
+
+/// A task group holds the tasks spawned through one `moire::task::scope`,
+/// which all finish or are aborted before the scope exits.
+///
+/// Every task spawned in the group is linked to this scope.
+#[derive(Facet)]
+pub struct TaskGroupScopeBody {
+    /// Task that opened the scope, and so the parent of every task in it.
+    #[facet(skip_unless_truthy)]
+    pub spawned_by: Option<EntityId>,
 }
 
 crate::impl_sqlite_json!(ScopeBody);
@@ -74,4 +123,6 @@ crate::declare_scope_body_slots!(
     ThreadScopeSlot::Thread(ThreadScopeBody),
     TaskScopeSlot::Task(TaskScopeBody),
     ConnectionScopeSlot::Connection(ConnectionScopeBody),
+    ActorScopeSlot::Actor(ActorScopeBody),
+    TaskGroupScopeSlot::TaskGroup(TaskGroupScopeBody),
 );
@@ -4,7 +4,9 @@ use rusqlite::types::{FromSql, FromSqlError, FromSqlResult, ToSql, ToSqlOutput,
 use std::fmt;
 use std::sync::OnceLock;
 use std::sync::atomic::{AtomicU64, Ordering};
-use std::time::{Instant, SystemTime, UNIX_EPOCH};
+use std::time::Instant;
+#[cfg(not(target_arch = "wasm32"))]
+use std::time::{SystemTime, UNIX_EPOCH};
 
 #[derive(Facet, Clone, Copy, Debug, PartialEq, Eq)]
 #[repr(u8)]
@@ -77,6 +79,12 @@ impl PTime {
         Self(elapsed_ms)
     }
 
+    /// A time `ms` milliseconds after process start, for runtimes that keep
+    /// their own clock (`performance.now()` on wasm, where `now` panics).
+    pub fn from_millis(ms: u64) -> Self {
+        Self(ms)
+    }
+
     pub fn as_millis(&self) -> u64 {
         self.0
     }
@@ -302,14 +310,24 @@ pub fn next_process_id() -> ProcessId {
 
 pub fn process_prefix_u16() -> u16 {
     static PROCESS_PREFIX: OnceLock<u16> = OnceLock::new();
-    *PROCESS_PREFIX.get_or_init(|| {
-        let pid = std::process::id() as u64;
-        let seed = SystemTime::now()
-            .duration_since(UNIX_EPOCH)
-            .map(|d| d.as_nanos() as u64)
-            .unwrap_or(0);
-        ((seed ^ pid) & 0xFFFF) as u16
-    })
+    *PROCESS_PREFIX.get_or_init(|| (process_seed() & 0xFFFF) as u16)
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn process_seed() -> u64 {
+    let pid = std::process::id() as u64;
+    let seed = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_nanos() as u64)
+        .unwrap_or(0);
+    seed ^ pid
+}
+
+/// A page has no pid and no clock `std` can read; its ids only have to be
+/// unique within the page.
+#[cfg(target_arch = "wasm32")]
+fn process_seed() -> u64 {
+    0
 }
 
 // r[impl model.id.uniqueness]
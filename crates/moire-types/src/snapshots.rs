@@ -1,6 +1,6 @@
 use facet::Facet;
 
-use crate::{Edge, Entity, Event, Scope};
+use crate::{BacktraceId, Edge, Entity, EntityId, Event, PTime, Scope};
 
 /// A snapshot is a point-in-time process envelope of graph state.
 #[derive(Facet)]
@@ -13,4 +13,124 @@ pub struct Snapshot {
     pub edges: Vec<Edge>,
     /// Point-in-time events captured for this snapshot.
     pub events: Vec<Event>,
+    /// Threads that have polled instrumented futures, with their current load.
+    #[facet(default)]
+    pub workers: Vec<WorkerLoad>,
+    /// Recent polls that ran past the process's long-poll threshold, oldest
+    /// first. Usually a blocking call inside async code.
+    #[facet(default)]
+    pub long_polls: Vec<LongPoll>,
+    /// Latency of the RPC methods this process served, over their recent
+    /// completions, by service and method name.
+    #[facet(default)]
+    pub rpc_methods: Vec<RpcMethodStats>,
+    /// How much of the process's async work is instrumented.
+    #[facet(skip_unless_truthy)]
+    pub coverage: Option<Coverage>,
+    /// Runtime and OS resource usage of the process when the snapshot was
+    /// assembled.
+    #[facet(skip_unless_truthy)]
+    pub runtime_stats: Option<RuntimeStats>,
+}
+
+/// Tracked tasks against everything the runtime is running, so an empty wait
+/// graph can be told apart from one that cannot see the stuck work.
+#[derive(Facet, Clone, Debug, PartialEq, Eq)]
+pub struct Coverage {
+    /// Live tasks spawned through moire's spawn functions.
+    pub tracked_tasks: u64,
+    /// Live tasks of the tokio runtime the snapshot was assembled on, moire's
+    /// own included. Absent when assembled off a runtime.
+    #[facet(skip_unless_truthy)]
+    pub runtime_alive_tasks: Option<u64>,
+}
+
+/// Load of the tokio runtime and the process around it, so stuck tasks can be
+/// told apart from a runtime that is not getting to them. Every field is
+/// absent when it could not be read: off a runtime, or off Linux for the OS
+/// figures.
+#[derive(Facet, Clone, Debug, Default, PartialEq, Eq)]
+pub struct RuntimeStats {
+    /// Worker threads of the tokio runtime the snapshot was assembled on.
+    #[facet(skip_unless_truthy)]
+    pub workers: Option<u64>,
+    /// Tasks waiting in that runtime's global (injection) queue.
+    #[facet(skip_unless_truthy)]
+    pub global_queue_depth: Option<u64>,
+    /// Resident set size, in bytes.
+    #[facet(skip_unless_truthy)]
+    pub rss_bytes: Option<u64>,
+    /// Open file descriptors.
+    #[facet(skip_unless_truthy)]
+    pub open_fds: Option<u64>,
+    /// Scheduler counters of each worker of that runtime, by worker index.
+    #[facet(default)]
+    pub runtime_workers: Vec<RuntimeWorker>,
+}
+
+/// Scheduler counters of one tokio worker thread.
+#[derive(Facet, Clone, Debug, Default, PartialEq, Eq)]
+pub struct RuntimeWorker {
+    /// Worker index as numbered by tokio; unrelated to `WorkerLoad::worker`.
+    pub index: u64,
+    /// Times the worker parked for lack of work.
+    pub park_count: u64,
+    /// Time spent running tasks, in microseconds.
+    pub busy_us: u64,
+    /// Tasks in the worker's local run queue. Only reported by processes
+    /// built with `--cfg tokio_unstable`.
+    #[facet(skip_unless_truthy)]
+    pub local_queue_depth: Option<u64>,
+    /// Tasks the worker stole from other workers. Only reported by processes
+    /// built with `--cfg tokio_unstable`.
+    #[facet(skip_unless_truthy)]
+    pub steal_count: Option<u64>,
+}
+
+/// How much instrumented work one thread has picked up.
+#[derive(Facet, Clone, Debug, PartialEq, Eq)]
+pub struct WorkerLoad {
+    /// Process-unique thread ordinal, as recorded in `FutureEntity::poll_worker`.
+    pub worker: u64,
+    /// Thread name, when the thread has one.
+    #[facet(skip_unless_truthy)]
+    pub thread_name: Option<String>,
+    /// Live futures whose most recent poll ran on this thread.
+    pub task_count: u64,
+    /// Polls of instrumented futures run on this thread so far.
+    pub poll_count: u64,
+}
+
+/// Completions of one RPC method served by the process.
+#[derive(Facet, Clone, Debug, PartialEq, Eq)]
+pub struct RpcMethodStats {
+    pub service_name: String,
+    pub method_name: String,
+    /// Responses that left `pending` since the process started.
+    pub count: u64,
+    /// Of those, responses that ended in an error.
+    pub error_count: u64,
+    /// Percentiles of the time from the response's creation to its
+    /// completion, over the most recent completions only.
+    pub p50_ms: u64,
+    pub p95_ms: u64,
+    pub p99_ms: u64,
+    /// How many recent completions the percentiles cover.
+    pub window: u32,
+}
+
+/// One poll of an instrumented future that ran past the long-poll threshold.
+#[derive(Facet, Clone, Debug, PartialEq, Eq)]
+pub struct LongPoll {
+    /// Future whose poll ran long; the innermost one when instrumented futures
+    /// nest.
+    pub future_id: EntityId,
+    /// When the poll returned.
+    pub at: PTime,
+    pub duration_us: u64,
+    /// Thread the poll ran on, as in `workers`.
+    pub worker: u64,
+    /// Captured as the poll returned, so it shows where the future is polled
+    /// from rather than the blocking call itself.
+    pub backtrace: BacktraceId,
 }
@@ -0,0 +1,262 @@
+use std::collections::HashSet;
+
+use facet::Facet;
+use moire_trace_types::JS_SAFE_INT_MAX_U64;
+
+use crate::{BacktraceId, EntityBody, EventKind, Json, ResponseError, ResponseStatus, Snapshot};
+
+/// Categories a [`crate::CustomEntity`] may be grouped under.
+pub const CUSTOM_ENTITY_CATEGORIES: &[&str] = &[
+    "async", "sync", "channel", "rpc", "net", "fs", "time", "meta",
+];
+
+/// One way a [`Snapshot`] breaks the data model contract.
+#[derive(Facet, Clone, Debug, PartialEq, Eq)]
+pub struct SnapshotViolation {
+    pub kind: SnapshotViolationKind,
+    /// Offending object: an entity, scope or event id, or `src -> dst` for an
+    /// edge.
+    pub subject: String,
+    pub detail: String,
+}
+
+#[derive(Facet, Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[repr(u8)]
+#[facet(rename_all = "snake_case")]
+pub enum SnapshotViolationKind {
+    /// Id that is neither moire-hex nor, for aether entities, `AETHER#<task key>`.
+    IdFormat,
+    /// Two entities, scopes or events sharing an id.
+    DuplicateId,
+    /// Backtrace id that is zero or not JavaScript-safe.
+    BacktraceId,
+    /// Edge endpoint that is not an entity of the snapshot.
+    DanglingEdge,
+    SelfEdge,
+    /// Same `(src, dst, kind)` edge listed more than once.
+    DuplicateEdge,
+    /// JSON-typed field that does not parse.
+    InvalidJson,
+    /// Custom entity whose kind, category or attrs break their documented shape.
+    CustomEntity,
+}
+
+impl SnapshotViolationKind {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::IdFormat => "id_format",
+            Self::DuplicateId => "duplicate_id",
+            Self::BacktraceId => "backtrace_id",
+            Self::DanglingEdge => "dangling_edge",
+            Self::SelfEdge => "self_edge",
+            Self::DuplicateEdge => "duplicate_edge",
+            Self::InvalidJson => "invalid_json",
+            Self::CustomEntity => "custom_entity",
+        }
+    }
+}
+
+impl std::fmt::Display for SnapshotViolation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} {}: {}",
+            self.kind.as_str(),
+            self.subject,
+            self.detail
+        )
+    }
+}
+
+#[derive(Default)]
+struct Violations(Vec<SnapshotViolation>);
+
+impl Violations {
+    fn push(&mut self, kind: SnapshotViolationKind, subject: &str, detail: impl Into<String>) {
+        self.0.push(SnapshotViolation {
+            kind,
+            subject: String::from(subject),
+            detail: detail.into(),
+        });
+    }
+
+    fn backtrace(&mut self, subject: &str, backtrace: BacktraceId) {
+        let raw = backtrace.as_u64();
+        if raw == 0 || raw > JS_SAFE_INT_MAX_U64 {
+            self.push(
+                SnapshotViolationKind::BacktraceId,
+                subject,
+                format!("backtrace id {raw} is zero or not JavaScript-safe"),
+            );
+        }
+    }
+
+    fn json(&mut self, subject: &str, field: &str, json: &Json) {
+        if let Err(error) = facet_json::from_str::<facet_value::Value>(json.as_str()) {
+            self.push(
+                SnapshotViolationKind::InvalidJson,
+                subject,
+                format!("{field} is not valid JSON: {error}"),
+            );
+        }
+    }
+
+    fn entity_body(&mut self, subject: &str, body: &EntityBody) {
+        match body {
+            EntityBody::Request(request) => self.json(subject, "args_json", &request.args_json),
+            EntityBody::Response(response) => match &response.status {
+                ResponseStatus::Ok(json) => self.json(subject, "status.ok", json),
+                ResponseStatus::Error(ResponseError::UserJson(json)) => {
+                    self.json(subject, "status.error.user_json", json)
+                }
+                _ => {}
+            },
+            EntityBody::Custom(custom) => {
+                if !is_snake_case(&custom.kind) {
+                    self.push(
+                        SnapshotViolationKind::CustomEntity,
+                        subject,
+                        format!("kind {:?} is not snake_case", custom.kind),
+                    );
+                }
+                if !CUSTOM_ENTITY_CATEGORIES.contains(&custom.category.as_str()) {
+                    self.push(
+                        SnapshotViolationKind::CustomEntity,
+                        subject,
+                        format!("unknown category {:?}", custom.category),
+                    );
+                }
+                match facet_json::from_str::<facet_value::Value>(custom.attrs.as_str()) {
+                    Err(error) => self.push(
+                        SnapshotViolationKind::InvalidJson,
+                        subject,
+                        format!("attrs is not valid JSON: {error}"),
+                    ),
+                    Ok(_) if !custom.attrs.as_str().trim_start().starts_with('{') => self.push(
+                        SnapshotViolationKind::CustomEntity,
+                        subject,
+                        "attrs is not a JSON object",
+                    ),
+                    Ok(_) => {}
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+impl Snapshot {
+    // r[impl model.validation]
+    /// Checks the snapshot against the data model contract, so a producer that
+    /// emits malformed objects is caught before they are merged with other
+    /// processes. Events may target entities and scopes that are gone, so their
+    /// targets are not checked.
+    pub fn validate(&self) -> Vec<SnapshotViolation> {
+        let mut violations = Violations::default();
+
+        let mut entity_ids = HashSet::new();
+        for entity in &self.entities {
+            let id = entity.id.as_str();
+            let well_formed = match &entity.body {
+                EntityBody::Aether(_) => id
+                    .strip_prefix("AETHER#")
+                    .is_some_and(|task_key| !task_key.is_empty()),
+                _ => is_moire_hex(id),
+            };
+            if !well_formed {
+                violations.push(
+                    SnapshotViolationKind::IdFormat,
+                    id,
+                    format!("malformed {} entity id", entity.body.kind_name()),
+                );
+            }
+            if !entity_ids.insert(id) {
+                violations.push(
+                    SnapshotViolationKind::DuplicateId,
+                    id,
+                    "entity id listed more than once",
+                );
+            }
+            violations.backtrace(id, entity.backtrace);
+            violations.entity_body(id, &entity.body);
+        }
+
+        let mut scope_ids = HashSet::new();
+        for scope in &self.scopes {
+            let id = scope.id.as_str();
+            if !is_moire_hex(id) {
+                violations.push(SnapshotViolationKind::IdFormat, id, "malformed scope id");
+            }
+            if !scope_ids.insert(id) {
+                violations.push(
+                    SnapshotViolationKind::DuplicateId,
+                    id,
+                    "scope id listed more than once",
+                );
+            }
+            violations.backtrace(id, scope.backtrace);
+        }
+
+        let mut edges = HashSet::new();
+        for edge in &self.edges {
+            let subject = format!("{} -> {}", edge.src.as_str(), edge.dst.as_str());
+            for (end, id) in [("src", &edge.src), ("dst", &edge.dst)] {
+                if !entity_ids.contains(id.as_str()) {
+                    violations.push(
+                        SnapshotViolationKind::DanglingEdge,
+                        &subject,
+                        format!("{end} is not an entity of this snapshot"),
+                    );
+                }
+            }
+            if edge.src == edge.dst {
+                violations.push(
+                    SnapshotViolationKind::SelfEdge,
+                    &subject,
+                    "edge points at its own source",
+                );
+            }
+            if !edges.insert((edge.src.as_str(), edge.dst.as_str(), edge.kind)) {
+                violations.push(
+                    SnapshotViolationKind::DuplicateEdge,
+                    &subject,
+                    "edge of this kind listed more than once",
+                );
+            }
+            violations.backtrace(&subject, edge.backtrace);
+        }
+
+        let mut event_ids = HashSet::new();
+        for event in &self.events {
+            let id = event.id.as_str();
+            if !is_moire_hex(id) {
+                violations.push(SnapshotViolationKind::IdFormat, id, "malformed event id");
+            }
+            if !event_ids.insert(id) {
+                violations.push(
+                    SnapshotViolationKind::DuplicateId,
+                    id,
+                    "event id listed more than once",
+                );
+            }
+            violations.backtrace(id, event.backtrace);
+            if let EventKind::Custom(custom) = &event.kind {
+                violations.json(id, "payload", &custom.payload);
+            }
+        }
+
+        violations.0
+    }
+}
+
+/// 16 characters of the `moire-hex` alphabet, per `r[model.id.format]`.
+fn is_moire_hex(id: &str) -> bool {
+    id.len() == 16 && id.bytes().all(|b| b"0123456789pesPES".contains(&b))
+}
+
+fn is_snake_case(name: &str) -> bool {
+    name.starts_with(|c: char| c.is_ascii_lowercase())
+        && name
+            .bytes()
+            .all(|b| b.is_ascii_lowercase() || b.is_ascii_digit() || b == b'_')
+}
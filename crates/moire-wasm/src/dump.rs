@@ -0,0 +1,101 @@
+//! The page's state, in the shape the dashboard and `moire-web` read.
+//!
+//! A page cannot be connected to a dashboard, so it hands out its own
+//! dump instead: [`snapshot`] from Rust, or `moireSnapshotDump()` from the
+//! browser console, which returns a dump `moire-web` reads like one saved
+//! from a native process.
+use moire_types::{
+    Edge, EdgeKind, Entity, EntityBody, FutureEntity, ProcessSnapshotView, Snapshot,
+    SnapshotBacktrace, SnapshotCutResponse, snapshot_to_json,
+};
+use wasm_bindgen::prelude::wasm_bindgen;
+
+use crate::registry::{ptime_now, unix_now_ms, with_registry};
+
+/// Name the page reports itself under.
+const PROCESS_NAME: &str = "wasm";
+
+/// Cuts a snapshot of the page's instrumented futures and their waits.
+pub fn snapshot() -> SnapshotCutResponse {
+    let ptime_now_ms = ptime_now().as_millis();
+    with_registry(|registry| {
+        registry.snapshots_taken += 1;
+        let entities = registry.entities.values().map(copy_entity).collect();
+        let edges = registry
+            .edges
+            .iter()
+            .map(|((src, dst), since)| Edge {
+                src: src.clone(),
+                dst: dst.clone(),
+                backtrace: registry.backtrace,
+                kind: EdgeKind::WaitingOn,
+                since: Some(*since),
+                reason: None,
+            })
+            .collect();
+        SnapshotCutResponse {
+            snapshot_id: registry.snapshots_taken,
+            captured_at_unix_ms: unix_now_ms(),
+            max_skew_ms: None,
+            processes: vec![ProcessSnapshotView {
+                process_id: registry.process_id.clone(),
+                process_name: String::from(PROCESS_NAME),
+                pid: 0,
+                ptime_now_ms,
+                skew_ms: None,
+                snapshot: Snapshot {
+                    entities,
+                    scopes: Vec::new(),
+                    edges,
+                    events: Vec::new(),
+                    workers: Vec::new(),
+                    long_polls: Vec::new(),
+                    rpc_methods: Vec::new(),
+                    coverage: None,
+                    runtime_stats: None,
+                },
+                scope_entity_links: Vec::new(),
+                timed_out_sections: None,
+                violations: Vec::new(),
+            }],
+            timed_out_processes: Vec::new(),
+            backtraces: vec![SnapshotBacktrace {
+                backtrace_id: registry.backtrace,
+                frame_ids: Vec::new(),
+            }],
+            frames: Vec::new(),
+            findings: Vec::new(),
+            annotations: Vec::new(),
+            sizing_hints: Vec::new(),
+            detection_config_version: None,
+            analysis_error: None,
+        }
+    })
+}
+
+/// Encodes [`snapshot`] as a dump (see `moire_types::snapshot_to_json`).
+#[wasm_bindgen(js_name = moireSnapshotDump)]
+pub fn snapshot_dump_json() -> Result<String, String> {
+    snapshot_to_json(&snapshot())
+}
+
+/// The registry only holds futures, and only the fields it tracks are set.
+fn copy_entity(entity: &Entity) -> Entity {
+    let EntityBody::Future(future) = &entity.body else {
+        unreachable!("the wasm registry only holds futures")
+    };
+    Entity {
+        id: entity.id.clone(),
+        birth: entity.birth,
+        removed_at: entity.removed_at,
+        backtrace: entity.backtrace,
+        name: entity.name.clone(),
+        body: EntityBody::Future(FutureEntity {
+            skip_entry_frames: future.skip_entry_frames,
+            poll_count: future.poll_count,
+            spawned_by: future.spawned_by.clone(),
+            callsite: future.callsite.clone(),
+            ..FutureEntity::default()
+        }),
+    }
+}
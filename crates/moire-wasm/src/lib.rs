@@ -1,9 +1,17 @@
 //! WASM runtime surface for moire.
 //!
-//! Instrumentation is intentionally no-op on wasm.
+//! Sync primitives, channels and timers are not instrumented on wasm. With
+//! the `diagnostics` feature, spawned tasks and instrumented futures are
+//! tracked, with the `waiting_on` edges between them, and the page can read
+//! them back as a snapshot dump (see [`dump`]).
 
 use std::future::Future;
 
+#[cfg(feature = "diagnostics")]
+pub mod dump;
+#[cfg(feature = "diagnostics")]
+mod registry;
+
 #[cfg(feature = "diagnostics")]
 #[doc(hidden)]
 pub mod __internal {
@@ -11,23 +19,76 @@ pub mod __internal {
     use std::pin::Pin;
     use std::task::{Context, Poll};
 
-    pub struct InstrumentedFuture<F>(F);
+    use moire_types::{Callsite, EntityId, FutureEntity};
+
+    use crate::registry::{self, WaitingOn};
+
+    pub struct InstrumentedFuture<F> {
+        inner: F,
+        /// `None` once the future has completed.
+        id: Option<EntityId>,
+        is_task: bool,
+        /// Edge from the future that polled this one, while it is pending.
+        polled_by: Option<WaitingOn>,
+    }
 
     impl<F: Future> Future for InstrumentedFuture<F> {
         type Output = F::Output;
         fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
-            unsafe { Pin::new_unchecked(&mut self.get_unchecked_mut().0) }.poll(cx)
+            let this = unsafe { self.get_unchecked_mut() };
+            let inner = unsafe { Pin::new_unchecked(&mut this.inner) };
+            let Some(id) = this.id.clone() else {
+                return inner.poll(cx);
+            };
+            if this.polled_by.is_none() {
+                this.polled_by = registry::waiting_on(&id);
+            }
+            let poll = {
+                let _polling = registry::enter_poll(&id, this.is_task);
+                inner.poll(cx)
+            };
+            if poll.is_ready() {
+                this.polled_by = None;
+                this.id = None;
+                registry::remove_future(&id);
+            }
+            poll
+        }
+    }
+
+    impl<F> Drop for InstrumentedFuture<F> {
+        fn drop(&mut self) {
+            self.polled_by = None;
+            if let Some(id) = self.id.take() {
+                registry::remove_future(&id);
+            }
         }
     }
 
     impl<F> InstrumentedFuture<F> {
-        pub fn skip_entry_frames(self, _n: u8) -> Self {
+        fn new(inner: F, name: String, body: FutureEntity, is_task: bool) -> Self {
+            Self {
+                inner,
+                id: Some(registry::register_future(name, body)),
+                is_task,
+                polled_by: None,
+            }
+        }
+
+        pub(crate) fn entity_id(&self) -> Option<&EntityId> {
+            self.id.as_ref()
+        }
+
+        pub fn skip_entry_frames(self, n: u8) -> Self {
+            if let Some(id) = &self.id {
+                registry::with_future(id, |future| future.skip_entry_frames = Some(n));
+            }
             self
         }
     }
 
     pub fn instrument_future<F, O, M>(
-        _name: impl Into<String>,
+        name: impl Into<String>,
         fut: F,
         _on: Option<O>,
         _meta: Option<M>,
@@ -35,7 +96,54 @@ pub mod __internal {
     where
         F: IntoFuture,
     {
-        InstrumentedFuture(fut.into_future())
+        InstrumentedFuture::new(
+            fut.into_future(),
+            name.into(),
+            FutureEntity::default(),
+            false,
+        )
+    }
+
+    pub fn instrument_future_at<F>(
+        name: impl Into<String>,
+        fut: F,
+        crate_name: &str,
+        module_path: &str,
+        file: &str,
+        line: u32,
+    ) -> InstrumentedFuture<F::IntoFuture>
+    where
+        F: IntoFuture,
+    {
+        let callsite = Callsite {
+            crate_name: String::from(crate_name),
+            module_path: String::from(module_path),
+            file: String::from(file),
+            line,
+        };
+        InstrumentedFuture::new(
+            fut.into_future(),
+            name.into(),
+            FutureEntity {
+                callsite: Some(callsite),
+                ..FutureEntity::default()
+            },
+            false,
+        )
+    }
+
+    /// Tracks `future` as a spawned task: the root its instrumented futures
+    /// are polled under.
+    pub(crate) fn instrument_task<F: Future>(future: F) -> InstrumentedFuture<F> {
+        InstrumentedFuture::new(
+            future,
+            String::from("task.spawn"),
+            FutureEntity {
+                spawned_by: registry::current_task(),
+                ..FutureEntity::default()
+            },
+            true,
+        )
     }
 }
 
@@ -58,18 +166,33 @@ pub mod task {
     ///
     /// On wasm, `spawn_local` returns `()` so we wrap a oneshot receiver.
     /// The task sends `()` when it completes; awaiting the handle waits for that.
+    /// An aborted task drops its sender instead, so the handle resolves with
+    /// `Canceled`.
     pub struct JoinHandle<T> {
         rx: futures_channel::oneshot::Receiver<T>,
+        abort: futures_util::future::AbortHandle,
+        /// The spawned task.
+        #[cfg(feature = "diagnostics")]
+        task: Option<moire_types::EntityId>,
+        /// Edge from the future awaiting this handle to the task.
+        #[cfg(feature = "diagnostics")]
+        awaited_by: Option<crate::registry::WaitingOn>,
     }
 
     impl<T> JoinHandle<T> {
-        /// No-op rename for API parity.
+        /// Renames the spawned task.
         pub fn named(self, _name: impl Into<String>) -> Self {
+            #[cfg(feature = "diagnostics")]
+            if let Some(task) = &self.task {
+                crate::registry::rename(task, _name.into());
+            }
             self
         }
 
+        /// Stops the task the next time the executor would poll it. The task
+        /// is dropped without being polled again.
         pub fn abort(&self) {
-            // No abort support on wasm; tasks run to completion.
+            self.abort.abort();
         }
     }
 
@@ -77,8 +200,18 @@ pub mod task {
         type Output = Result<T, futures_channel::oneshot::Canceled>;
 
         fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
-            let rx = unsafe { Pin::new_unchecked(&mut self.get_unchecked_mut().rx) };
-            rx.poll(cx)
+            let this = unsafe { self.get_unchecked_mut() };
+            let rx = unsafe { Pin::new_unchecked(&mut this.rx) };
+            let poll = rx.poll(cx);
+            #[cfg(feature = "diagnostics")]
+            match (&poll, &this.task) {
+                (Poll::Pending, Some(task)) if this.awaited_by.is_none() => {
+                    this.awaited_by = crate::registry::waiting_on(task);
+                }
+                (Poll::Ready(_), _) => this.awaited_by = None,
+                _ => {}
+            }
+            poll
         }
     }
 
@@ -95,11 +228,24 @@ pub mod task {
         T: 'static,
     {
         let (tx, rx) = futures_channel::oneshot::channel();
+        #[cfg(feature = "diagnostics")]
+        let future = crate::__internal::instrument_task(future);
+        #[cfg(feature = "diagnostics")]
+        let task = future.entity_id().cloned();
+        let (future, abort) = futures_util::future::abortable(future);
         wasm_bindgen_futures::spawn_local(async move {
-            let result = future.await;
-            let _ = tx.send(result);
+            if let Ok(result) = future.await {
+                let _ = tx.send(result);
+            }
         });
-        JoinHandle { rx }
+        JoinHandle {
+            rx,
+            abort,
+            #[cfg(feature = "diagnostics")]
+            task,
+            #[cfg(feature = "diagnostics")]
+            awaited_by: None,
+        }
     }
 }
 
@@ -114,9 +260,13 @@ pub mod sync {
             Self(std::sync::Mutex::new(value))
         }
 
+        /// Never poisoned, like the native `parking_lot` mutex: a panic while
+        /// the lock was held leaves it usable.
         #[inline]
         pub fn lock(&self) -> std::sync::MutexGuard<'_, T> {
-            self.0.lock().expect("wasm mutex poisoned; cannot continue")
+            self.0
+                .lock()
+                .unwrap_or_else(std::sync::PoisonError::into_inner)
         }
     }
 
@@ -790,5 +940,7 @@ pub fn spawn<F>(future: F)
 where
     F: Future<Output = ()> + 'static,
 {
+    #[cfg(feature = "diagnostics")]
+    let future = __internal::instrument_task(future);
     wasm_bindgen_futures::spawn_local(future);
 }
@@ -0,0 +1,170 @@
+//! The page's instrumented futures and what they wait on.
+//!
+//! A page runs everything on one thread, so the registry is a thread local
+//! instead of the native runtime's locked database, and the time of day
+//! comes from `performance.now()` because `std`'s clocks panic on
+//! `wasm32-unknown-unknown`. There is no dashboard to push to: the page reads
+//! its own dump, see [`crate::dump`].
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+
+use moire_types::{
+    BacktraceId, Entity, EntityBody, EntityId, FutureEntity, PTime, ProcessId, next_process_id,
+};
+use wasm_bindgen::prelude::wasm_bindgen;
+
+#[wasm_bindgen]
+extern "C" {
+    #[wasm_bindgen(js_namespace = performance, js_name = now)]
+    fn performance_now() -> f64;
+    #[wasm_bindgen(js_namespace = Date, js_name = now)]
+    fn date_now() -> f64;
+}
+
+/// Milliseconds since the page's time origin.
+pub(crate) fn ptime_now() -> PTime {
+    PTime::from_millis(performance_now() as u64)
+}
+
+pub(crate) fn unix_now_ms() -> i64 {
+    date_now() as i64
+}
+
+pub(crate) struct Registry {
+    pub(crate) process_id: ProcessId,
+    /// Shared by every entity and edge: pages capture no backtraces.
+    pub(crate) backtrace: BacktraceId,
+    pub(crate) entities: BTreeMap<EntityId, Entity>,
+    /// `waiting_on` edges, from waiter to waited-on, with when they started.
+    pub(crate) edges: BTreeMap<(EntityId, EntityId), PTime>,
+    /// Instrumented futures being polled, innermost last.
+    polling: Vec<EntityId>,
+    /// Spawned task each polled future belongs to, innermost last.
+    tasks: Vec<EntityId>,
+    pub(crate) snapshots_taken: i64,
+}
+
+thread_local! {
+    static REGISTRY: RefCell<Registry> = RefCell::new(Registry {
+        process_id: next_process_id(),
+        backtrace: BacktraceId::next().expect("first backtrace id is in range"),
+        entities: BTreeMap::new(),
+        edges: BTreeMap::new(),
+        polling: Vec::new(),
+        tasks: Vec::new(),
+        snapshots_taken: 0,
+    });
+}
+
+pub(crate) fn with_registry<R>(f: impl FnOnce(&mut Registry) -> R) -> R {
+    REGISTRY.with(|registry| f(&mut registry.borrow_mut()))
+}
+
+/// Registers a future entity and returns its id. The entity lives until
+/// [`remove_future`].
+pub(crate) fn register_future(name: String, body: FutureEntity) -> EntityId {
+    with_registry(|registry| {
+        let entity = Entity::new_at(
+            registry.backtrace,
+            name,
+            EntityBody::Future(body),
+            ptime_now(),
+        );
+        let id = entity.id.clone();
+        registry.entities.insert(id.clone(), entity);
+        id
+    })
+}
+
+/// Removes a future entity and every edge touching it.
+pub(crate) fn remove_future(id: &EntityId) {
+    with_registry(|registry| {
+        registry.entities.remove(id);
+        registry
+            .edges
+            .retain(|(src, dst), _| src != id && dst != id);
+    });
+}
+
+pub(crate) fn with_future(id: &EntityId, f: impl FnOnce(&mut FutureEntity)) {
+    with_registry(|registry| {
+        if let Some(Entity {
+            body: EntityBody::Future(future),
+            ..
+        }) = registry.entities.get_mut(id)
+        {
+            f(future);
+        }
+    });
+}
+
+pub(crate) fn rename(id: &EntityId, name: String) {
+    with_registry(|registry| {
+        if let Some(entity) = registry.entities.get_mut(id) {
+            entity.name = name;
+        }
+    });
+}
+
+/// Task being polled right now, if any.
+pub(crate) fn current_task() -> Option<EntityId> {
+    with_registry(|registry| registry.tasks.last().cloned())
+}
+
+/// Marks `id` as being polled until the returned guard drops. `is_task`
+/// futures also become the task their inner futures belong to.
+pub(crate) fn enter_poll(id: &EntityId, is_task: bool) -> PollGuard {
+    with_future(id, |future| {
+        future.poll_count = Some(future.poll_count.unwrap_or(0) + 1)
+    });
+    with_registry(|registry| {
+        registry.polling.push(id.clone());
+        if is_task {
+            registry.tasks.push(id.clone());
+        }
+    });
+    PollGuard { is_task }
+}
+
+pub(crate) struct PollGuard {
+    is_task: bool,
+}
+
+impl Drop for PollGuard {
+    fn drop(&mut self) {
+        with_registry(|registry| {
+            registry.polling.pop();
+            if self.is_task {
+                registry.tasks.pop();
+            }
+        });
+    }
+}
+
+/// A `waiting_on` edge from the future being polled to `dst`, removed when
+/// dropped.
+pub(crate) struct WaitingOn {
+    key: (EntityId, EntityId),
+}
+
+/// Records that the future being polled waits on `dst`. `None` when nothing
+/// instrumented is being polled, or when `dst` is that future itself.
+pub(crate) fn waiting_on(dst: &EntityId) -> Option<WaitingOn> {
+    with_registry(|registry| {
+        let src = registry.polling.last()?.clone();
+        if &src == dst {
+            return None;
+        }
+        let key = (src, dst.clone());
+        registry.edges.entry(key.clone()).or_insert_with(ptime_now);
+        Some(WaitingOn { key })
+    })
+}
+
+impl Drop for WaitingOn {
+    fn drop(&mut self) {
+        with_registry(|registry| {
+            registry.edges.remove(&self.key);
+        });
+    }
+}
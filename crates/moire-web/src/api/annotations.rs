@@ -0,0 +1,136 @@
+use std::collections::HashSet;
+
+use axum::body::Bytes;
+use axum::extract::{Path as AxumPath, State};
+use axum::http::StatusCode;
+use axum::response::IntoResponse;
+use moire_types::{
+    Annotation, AnnotationCreateRequest, AnnotationListResponse, EntityBody, SnapshotCutResponse,
+};
+use tracing::warn;
+
+use crate::app::AppState;
+use crate::db::{
+    delete_annotation_blocking, insert_annotation_blocking, list_annotations_blocking,
+};
+use crate::util::http::{json_error, json_ok};
+use crate::util::time::now_ms;
+
+pub async fn api_annotations(State(state): State<AppState>) -> impl IntoResponse {
+    let db = state.db.clone();
+    match tokio::task::spawn_blocking(move || list_annotations_blocking(&db)).await {
+        Ok(Ok(annotations)) => json_ok(&AnnotationListResponse { annotations }),
+        Ok(Err(e)) => json_error(StatusCode::INTERNAL_SERVER_ERROR, e),
+        Err(e) => json_error(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            format!("annotations worker join error: {e}"),
+        ),
+    }
+}
+
+pub async fn api_annotation_create(
+    State(state): State<AppState>,
+    body: Bytes,
+) -> impl IntoResponse {
+    let request: AnnotationCreateRequest = match facet_json::from_slice(&body) {
+        Ok(request) => request,
+        Err(e) => {
+            return json_error(
+                StatusCode::BAD_REQUEST,
+                format!("invalid request json: {e}"),
+            );
+        }
+    };
+    if request.target.trim().is_empty() {
+        return json_error(StatusCode::BAD_REQUEST, "annotation target is empty");
+    }
+    if request.note.trim().is_empty() {
+        return json_error(StatusCode::BAD_REQUEST, "annotation note is empty");
+    }
+
+    let db = state.db.clone();
+    match tokio::task::spawn_blocking(move || {
+        insert_annotation_blocking(
+            &db,
+            request.target.trim(),
+            request.note.trim(),
+            request.author.as_deref(),
+            now_ms(),
+        )
+    })
+    .await
+    {
+        Ok(Ok(annotation)) => json_ok(&annotation),
+        Ok(Err(e)) => json_error(StatusCode::INTERNAL_SERVER_ERROR, e),
+        Err(e) => json_error(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            format!("annotations worker join error: {e}"),
+        ),
+    }
+}
+
+pub async fn api_annotation_delete(
+    State(state): State<AppState>,
+    AxumPath(annotation_id): AxumPath<i64>,
+) -> impl IntoResponse {
+    let db = state.db.clone();
+    match tokio::task::spawn_blocking(move || delete_annotation_blocking(&db, annotation_id)).await
+    {
+        Ok(Ok(true)) => StatusCode::NO_CONTENT.into_response(),
+        Ok(Ok(false)) => json_error(StatusCode::NOT_FOUND, "annotation not found"),
+        Ok(Err(e)) => json_error(StatusCode::INTERNAL_SERVER_ERROR, e),
+        Err(e) => json_error(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            format!("annotations worker join error: {e}"),
+        ),
+    }
+}
+
+// r[impl api.annotations]
+/// Stored annotations that target a node, task or finding of `snapshot`.
+/// Failing to read them leaves the snapshot unannotated rather than failing it.
+pub async fn load_snapshot_annotations(
+    state: &AppState,
+    snapshot: &SnapshotCutResponse,
+) -> Vec<Annotation> {
+    let db = state.db.clone();
+    let annotations =
+        match tokio::task::spawn_blocking(move || list_annotations_blocking(&db)).await {
+            Ok(Ok(annotations)) => annotations,
+            Ok(Err(e)) => {
+                warn!(%e, "failed to load annotations");
+                return vec![];
+            }
+            Err(e) => {
+                warn!(%e, "annotations worker join error");
+                return vec![];
+            }
+        };
+    let targets = snapshot_annotation_targets(snapshot);
+    annotations
+        .into_iter()
+        .filter(|annotation| targets.contains(annotation.target.as_str()))
+        .collect()
+}
+
+fn snapshot_annotation_targets(snapshot: &SnapshotCutResponse) -> HashSet<String> {
+    let mut targets = HashSet::new();
+    for process in &snapshot.processes {
+        for entity in &process.snapshot.entities {
+            targets.insert(format!(
+                "{}::{}",
+                process.process_id.as_str(),
+                entity.id.as_str()
+            ));
+            if let EntityBody::Future(future) = &entity.body
+                && let Some(logical_id) = &future.logical_id
+            {
+                targets.insert(logical_id.clone());
+            }
+        }
+    }
+    for finding in &snapshot.findings {
+        targets.insert(finding.fingerprint());
+    }
+    targets
+}
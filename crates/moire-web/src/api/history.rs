@@ -0,0 +1,67 @@
+use axum::body::Bytes;
+use axum::extract::{Path as AxumPath, State};
+use axum::http::{StatusCode, header};
+use axum::response::IntoResponse;
+use moire_types::{HistoryQueryRequest, HistoryQueryResponse};
+
+use crate::app::AppState;
+use crate::recording::history::{HistoryRange, load_history_snapshot_json_blocking, query_history};
+use crate::util::http::{json_error, json_ok};
+use crate::util::time::now_ms;
+
+const DEFAULT_HISTORY_LIMIT: u32 = 100;
+
+pub async fn api_history(State(state): State<AppState>, body: Bytes) -> impl IntoResponse {
+    let request: HistoryQueryRequest = if body.is_empty() {
+        HistoryQueryRequest {
+            from_unix_ms: None,
+            to_unix_ms: None,
+            limit: None,
+            logical_id: None,
+        }
+    } else {
+        match facet_json::from_slice(&body) {
+            Ok(request) => request,
+            Err(e) => {
+                return json_error(
+                    StatusCode::BAD_REQUEST,
+                    format!("invalid request json: {e}"),
+                );
+            }
+        }
+    };
+
+    let range = HistoryRange {
+        from_unix_ms: request.from_unix_ms.unwrap_or(i64::MIN),
+        to_unix_ms: request.to_unix_ms.unwrap_or_else(now_ms),
+        limit: request.limit.unwrap_or(DEFAULT_HISTORY_LIMIT),
+        logical_id: request.logical_id,
+    };
+    match query_history(state.db.clone(), range).await {
+        Ok(snapshots) => json_ok(&HistoryQueryResponse { snapshots }),
+        Err(e) => json_error(StatusCode::INTERNAL_SERVER_ERROR, e),
+    }
+}
+
+pub async fn api_history_snapshot(
+    State(state): State<AppState>,
+    AxumPath(history_id): AxumPath<i64>,
+) -> impl IntoResponse {
+    let db = state.db.clone();
+    match tokio::task::spawn_blocking(move || load_history_snapshot_json_blocking(&db, history_id))
+        .await
+    {
+        Ok(Ok(Some(snapshot_json))) => (
+            StatusCode::OK,
+            [(header::CONTENT_TYPE, "application/json; charset=utf-8")],
+            snapshot_json,
+        )
+            .into_response(),
+        Ok(Ok(None)) => json_error(StatusCode::NOT_FOUND, "history snapshot not found"),
+        Ok(Err(e)) => json_error(StatusCode::INTERNAL_SERVER_ERROR, e),
+        Err(e) => json_error(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            format!("history worker join error: {e}"),
+        ),
+    }
+}
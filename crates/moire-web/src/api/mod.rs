@@ -1,4 +1,6 @@
+pub mod annotations;
 pub mod connections;
+pub mod history;
 pub mod recording;
 pub mod snapshot;
 pub mod source;
@@ -1,9 +1,9 @@
 use std::collections::{BTreeMap, BTreeSet, HashMap};
 use std::sync::Arc;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
 use axum::extract::ws::{Message, WebSocket, WebSocketUpgrade};
-use axum::extract::{Path as AxumPath, State};
+use axum::extract::{Path as AxumPath, RawQuery, State};
 use axum::http::{StatusCode, header};
 use axum::response::IntoResponse;
 use moire_trace_types::FrameId;
@@ -11,27 +11,85 @@ use moire_types::{
     BacktraceFrameUnresolved, ProcessSnapshotView, SnapshotBacktraceFrame, SnapshotCutResponse,
     SnapshotFrameRecord, SnapshotSymbolicationUpdate, TimedOutProcess,
 };
-use moire_wire::{ServerMessage, SnapshotRequest, encode_server_message_default};
+use moire_wire::{ServerMessage, SnapshotEncoding, SnapshotRequest, encode_server_message_default};
 use tokio::sync::mpsc;
 use tracing::{error, info, warn};
 
+use crate::api::annotations::load_snapshot_annotations;
 use crate::app::{AppState, ConnectionId, SnapshotPending, SnapshotStreamState, remember_snapshot};
 use crate::db::fetch_scope_entity_links_blocking;
+use crate::snapshot::summary::{DEFAULT_TOP_N, summarize};
 use crate::snapshot::table::{
     SnapshotBacktraceTable, collect_snapshot_backtrace_ids, is_pending_frame,
     load_snapshot_backtrace_table,
 };
 use crate::symbolication::symbolicate_pending_frames_for_backtraces;
-use crate::util::http::{json_error, json_ok};
+use crate::util::http::{json_error, json_ok, parse_query_u64};
 use crate::util::time::now_ms;
 
 const SYMBOLICATION_STREAM_STALL_TICKS_LIMIT: u32 = 100;
 const SYMBOLICATION_UNRESOLVED_STALLED: &str =
     "symbolication stalled: no progress before stream timeout";
 
-pub async fn api_snapshot(State(state): State<AppState>) -> impl IntoResponse {
-    info!("snapshot requested via API");
-    json_ok(&take_snapshot_internal(&state).await)
+/// How long a snapshot waits for processes to reply, unless the request
+/// asks for another budget.
+const SNAPSHOT_TIMEOUT: Duration = Duration::from_millis(5000);
+const MIN_SNAPSHOT_BUDGET_MS: u64 = 100;
+const MAX_SNAPSHOT_BUDGET_MS: u64 = 60_000;
+
+// r[impl config.web.snapshot-encoding]
+/// Encoding to ask of processes for their snapshot replies, from
+/// `MOIRE_SNAPSHOT_ENCODING`. Unset means JSON, which every process speaks.
+pub fn snapshot_encoding_from_env() -> Result<Option<SnapshotEncoding>, String> {
+    let Ok(value) = std::env::var("MOIRE_SNAPSHOT_ENCODING") else {
+        return Ok(None);
+    };
+    parse_snapshot_encoding(&value)
+}
+
+fn parse_snapshot_encoding(value: &str) -> Result<Option<SnapshotEncoding>, String> {
+    match value.trim() {
+        "" | "json" => Ok(None),
+        "msgpack" => Ok(Some(SnapshotEncoding::Msgpack)),
+        other => Err(format!(
+            "invalid MOIRE_SNAPSHOT_ENCODING '{other}': expected json or msgpack"
+        )),
+    }
+}
+
+// r[impl api.snapshot.budget]
+pub async fn api_snapshot(
+    State(state): State<AppState>,
+    RawQuery(raw_query): RawQuery,
+) -> impl IntoResponse {
+    let budget = requested_budget(raw_query.as_deref().unwrap_or_default());
+    info!(
+        budget_ms = budget.as_millis() as u64,
+        "snapshot requested via API"
+    );
+    json_ok(&take_snapshot_with_budget(&state, budget).await)
+}
+
+/// Takes a snapshot and returns only its headline statistics, `top` entries
+/// per list.
+pub async fn api_snapshot_summary(
+    State(state): State<AppState>,
+    RawQuery(raw_query): RawQuery,
+) -> impl IntoResponse {
+    let raw_query = raw_query.unwrap_or_default();
+    let budget = requested_budget(&raw_query);
+    let top_n = parse_query_u64(&raw_query, "top").map_or(DEFAULT_TOP_N, |top| top as usize);
+    info!(top_n, "snapshot summary requested via API");
+    json_ok(&summarize(
+        &take_snapshot_with_budget(&state, budget).await,
+        top_n,
+    ))
+}
+
+fn requested_budget(raw_query: &str) -> Duration {
+    parse_query_u64(raw_query, "budget_ms")
+        .map(|ms| Duration::from_millis(ms.clamp(MIN_SNAPSHOT_BUDGET_MS, MAX_SNAPSHOT_BUDGET_MS)))
+        .unwrap_or(SNAPSHOT_TIMEOUT)
 }
 
 pub async fn api_snapshot_symbolication_ws(
@@ -266,8 +324,26 @@ async fn refresh_cached_snapshot_from_table(
 }
 
 pub async fn take_snapshot_internal(state: &AppState) -> SnapshotCutResponse {
-    const SNAPSHOT_TIMEOUT_MS: u64 = 5000;
+    take_snapshot_with_budget(state, SNAPSHOT_TIMEOUT).await
+}
 
+/// A process that replied with a snapshot: its id, name and pid, its ptime
+/// and clock skew at the reply, the snapshot, and the sections it gave up on.
+type RepliedProcess = (
+    moire_types::ProcessId,
+    String,
+    u32,
+    u64,
+    Option<u64>,
+    moire_types::Snapshot,
+    Option<Vec<String>>,
+);
+
+/// Snapshots every connected process, waiting at most `budget` for their
+/// replies. Each process gets the same budget and returns what it could
+/// assemble in half of it; processes that do not reply in time are listed
+/// under `timed_out_processes`.
+pub async fn take_snapshot_with_budget(state: &AppState, budget: Duration) -> SnapshotCutResponse {
     let snapshot_id;
     let notify;
     let txs: Vec<(ConnectionId, moire_types::ProcessId, mpsc::Sender<Vec<u8>>)>;
@@ -291,6 +367,8 @@ pub async fn take_snapshot_internal(state: &AppState) -> SnapshotCutResponse {
                 SnapshotPending {
                     pending_conn_ids,
                     replies: HashMap::new(),
+                    requested_at: Instant::now(),
+                    reply_skew_ms: HashMap::new(),
                     notify: notify.clone(),
                 },
             );
@@ -306,10 +384,16 @@ pub async fn take_snapshot_internal(state: &AppState) -> SnapshotCutResponse {
         let response = SnapshotCutResponse {
             snapshot_id,
             captured_at_unix_ms: now_ms(),
+            max_skew_ms: None,
             processes: vec![],
             timed_out_processes: vec![],
             backtraces: vec![],
             frames: vec![],
+            findings: vec![],
+            annotations: vec![],
+            sizing_hints: vec![],
+            detection_config_version: state.detection_config().version.clone(),
+            analysis_error: None,
         };
         let mut guard = state.inner.lock().await;
         guard.snapshot_streams.insert(
@@ -326,7 +410,8 @@ pub async fn take_snapshot_internal(state: &AppState) -> SnapshotCutResponse {
     let request_frame =
         match encode_server_message_default(&ServerMessage::SnapshotRequest(SnapshotRequest {
             snapshot_id,
-            timeout_ms: SNAPSHOT_TIMEOUT_MS as i64,
+            timeout_ms: i64::try_from(budget.as_millis()).unwrap_or(i64::MAX),
+            encoding: state.snapshot_encoding,
         })) {
             Ok(frame) => frame,
             Err(e) => {
@@ -340,10 +425,16 @@ pub async fn take_snapshot_internal(state: &AppState) -> SnapshotCutResponse {
                 let response = SnapshotCutResponse {
                     snapshot_id,
                     captured_at_unix_ms: now_ms(),
+                    max_skew_ms: None,
                     processes: vec![],
                     timed_out_processes: vec![],
                     backtraces: vec![],
                     frames: vec![],
+                    findings: vec![],
+                    annotations: vec![],
+                    sizing_hints: vec![],
+                    detection_config_version: state.detection_config().version.clone(),
+                    analysis_error: None,
                 };
                 let mut guard = state.inner.lock().await;
                 guard.snapshot_streams.insert(
@@ -364,11 +455,7 @@ pub async fn take_snapshot_internal(state: &AppState) -> SnapshotCutResponse {
         }
     }
 
-    let _ = tokio::time::timeout(
-        Duration::from_millis(SNAPSHOT_TIMEOUT_MS),
-        notify.notified(),
-    )
-    .await;
+    let _ = tokio::time::timeout(budget, notify.notified()).await;
 
     let captured_at_unix_ms = now_ms();
     let (pending, conn_info) = {
@@ -394,32 +481,48 @@ pub async fn take_snapshot_internal(state: &AppState) -> SnapshotCutResponse {
     let (processes, timed_out_processes) = match pending {
         None => (vec![], vec![]),
         Some(p) => {
-            let partial: Vec<(
-                moire_types::ProcessId,
-                String,
-                u32,
-                u64,
-                moire_types::Snapshot,
-            )> = p
-                .replies
-                .into_iter()
-                .filter_map(|(conn_id, reply)| {
-                    let snapshot = reply.snapshot?;
-                    let (process_id, process_name, pid) = conn_info
-                        .get(&conn_id)
-                        .map(|(process_id, name, pid)| (process_id.clone(), name.clone(), *pid))
-                        .unwrap_or_else(|| {
-                            panic!(
-                                "invariant violated: snapshot reply for conn {} has no conn_info row",
-                                conn_id
-                            )
-                        });
-                    Some((process_id, process_name, pid, reply.ptime_now_ms, snapshot))
-                })
-                .collect();
+            let mut partial: Vec<RepliedProcess> = Vec::with_capacity(p.replies.len());
+            // Processes that replied, but could not assemble a snapshot before
+            // the deadline they were given.
+            let mut stalled: Vec<(ConnectionId, Option<Vec<String>>)> = Vec::new();
+            for (conn_id, reply) in p.replies {
+                let Some(snapshot) = reply.snapshot else {
+                    if reply.timed_out_sections.is_some() {
+                        stalled.push((conn_id, reply.timed_out_sections));
+                    }
+                    continue;
+                };
+                let (process_id, process_name, pid) = conn_info
+                    .get(&conn_id)
+                    .map(|(process_id, name, pid)| (process_id.clone(), name.clone(), *pid))
+                    .unwrap_or_else(|| {
+                        panic!(
+                            "invariant violated: snapshot reply for conn {} has no conn_info row",
+                            conn_id
+                        )
+                    });
+                partial.push((
+                    process_id,
+                    process_name,
+                    pid,
+                    reply.ptime_now_ms,
+                    p.reply_skew_ms.get(&conn_id).copied(),
+                    snapshot,
+                    reply.timed_out_sections,
+                ));
+            }
 
             let mut processes = Vec::with_capacity(partial.len());
-            for (process_id, process_name, pid, ptime_now_ms, snapshot) in partial {
+            for (
+                process_id,
+                process_name,
+                pid,
+                ptime_now_ms,
+                skew_ms,
+                snapshot,
+                timed_out_sections,
+            ) in partial
+            {
                 let db = state.db.clone();
                 let process_id_for_links = process_id.clone();
                 let scope_entity_links = tokio::task::spawn_blocking(move || {
@@ -434,13 +537,26 @@ pub async fn take_snapshot_internal(state: &AppState) -> SnapshotCutResponse {
                     warn!(%e, "scope_entity_links query error");
                     vec![]
                 });
+                let violations = snapshot.validate();
+                if let Some(first) = violations.first() {
+                    warn!(
+                        snapshot_id,
+                        process_id = %process_id.as_str(),
+                        violation_count = violations.len(),
+                        %first,
+                        "process sent a malformed snapshot"
+                    );
+                }
                 processes.push(ProcessSnapshotView {
                     process_id,
                     process_name,
                     pid,
                     ptime_now_ms,
+                    skew_ms,
                     snapshot,
                     scope_entity_links,
+                    timed_out_sections,
+                    violations,
                 });
             }
             let processes = processes;
@@ -448,7 +564,9 @@ pub async fn take_snapshot_internal(state: &AppState) -> SnapshotCutResponse {
             let timed_out_processes = p
                 .pending_conn_ids
                 .into_iter()
-                .map(|conn_id| {
+                .map(|conn_id| (conn_id, None))
+                .chain(stalled)
+                .map(|(conn_id, timed_out_sections)| {
                     let (process_id, process_name, pid) = conn_info
                         .get(&conn_id)
                         .map(|(process_id, name, pid)| (process_id.clone(), name.clone(), *pid))
@@ -462,6 +580,7 @@ pub async fn take_snapshot_internal(state: &AppState) -> SnapshotCutResponse {
                         process_id,
                         process_name,
                         pid,
+                        timed_out_sections,
                     }
                 })
                 .collect();
@@ -470,13 +589,21 @@ pub async fn take_snapshot_internal(state: &AppState) -> SnapshotCutResponse {
         }
     };
 
+    // r[impl api.snapshot.skew]
+    let max_skew_ms = processes.iter().filter_map(|process| process.skew_ms).max();
     let mut response = SnapshotCutResponse {
         snapshot_id,
         captured_at_unix_ms,
+        max_skew_ms,
         processes,
         timed_out_processes,
         backtraces: vec![],
         frames: vec![],
+        findings: vec![],
+        annotations: vec![],
+        sizing_hints: vec![],
+        detection_config_version: None,
+        analysis_error: None,
     };
     info!(
         snapshot_id,
@@ -488,8 +615,11 @@ pub async fn take_snapshot_internal(state: &AppState) -> SnapshotCutResponse {
     let backtrace_table = load_snapshot_backtrace_table(state.db.clone(), &backtrace_ids).await;
     response.backtraces = backtrace_table.backtraces;
     response.frames = backtrace_table.frames;
+    let mut response = analyze_snapshot(state, response).await;
+    response.annotations = load_snapshot_annotations(state, &response).await;
     {
         let mut guard = state.inner.lock().await;
+        response.sizing_hints = guard.capacity_trends.observe(&response);
         guard.snapshot_streams.insert(
             snapshot_id,
             SnapshotStreamState {
@@ -506,3 +636,91 @@ pub async fn take_snapshot_internal(state: &AppState) -> SnapshotCutResponse {
     remember_snapshot(state, &response).await;
     response
 }
+
+/// Runs the configured analyses over `response`, filling in its findings, or
+/// its `analysis_error` when they fail. Analyses walk the whole wait graph, so
+/// they run off the async workers; one that panics costs the findings, not
+/// the snapshot.
+async fn analyze_snapshot(
+    state: &AppState,
+    mut response: SnapshotCutResponse,
+) -> SnapshotCutResponse {
+    let detection = state.detection_config();
+    response.detection_config_version = detection.version.clone();
+    let analyses = state.analyses.clone();
+    let response = Arc::new(response);
+    let analysed = response.clone();
+    let analysis =
+        tokio::task::spawn_blocking(move || analyses.run_configured(&analysed, &detection))
+            .await
+            .unwrap_or_else(|error| Err(format!("snapshot analyses panicked: {error}")));
+    let Ok(mut response) = Arc::try_unwrap(response) else {
+        panic!("invariant violated: snapshot analyses outlived their task");
+    };
+    match analysis {
+        Ok(findings) => response.findings = findings,
+        Err(e) => {
+            warn!(snapshot_id = response.snapshot_id, %e, "snapshot analyses failed");
+            response.analysis_error = Some(e);
+        }
+    }
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use moire_types::AnalysisFinding;
+
+    use super::*;
+    use crate::db::Db;
+    use crate::graph::WaitGraph;
+    use crate::graph::analysis::{Analysis, AnalysisRegistry};
+    use crate::graph::config::DetectionThresholds;
+
+    struct Panics;
+
+    impl Analysis for Panics {
+        fn name(&self) -> &str {
+            "panics"
+        }
+
+        fn run(
+            &self,
+            _graph: &WaitGraph,
+            _snapshot: &SnapshotCutResponse,
+            _thresholds: &DetectionThresholds,
+        ) -> Vec<AnalysisFinding> {
+            panic!("analysis blew up");
+        }
+    }
+
+    #[tokio::test]
+    async fn a_panicking_analysis_still_returns_the_snapshot() {
+        // Analyses never touch the database.
+        let db = Db::new(std::env::temp_dir().join("moire-unused.sqlite"));
+        let mut analyses = AnalysisRegistry::empty();
+        analyses.register(Panics);
+        let state = AppState::new(db, ConnectionId::new(1), None, None).with_analyses(analyses);
+
+        let snapshot = SnapshotCutResponse {
+            snapshot_id: 1,
+            captured_at_unix_ms: now_ms(),
+            max_skew_ms: None,
+            processes: vec![],
+            timed_out_processes: vec![],
+            backtraces: vec![],
+            frames: vec![],
+            findings: vec![],
+            annotations: vec![],
+            sizing_hints: vec![],
+            detection_config_version: None,
+            analysis_error: None,
+        };
+
+        let response = analyze_snapshot(&state, snapshot).await;
+        assert_eq!(response.snapshot_id, 1);
+        assert!(response.findings.is_empty());
+        let error = response.analysis_error.expect("analysis error");
+        assert!(error.contains("analysis blew up"), "{error}");
+    }
+}
@@ -10,11 +10,11 @@ use rusqlite_facet::ConnectionFacetExt;
 use crate::app::AppState;
 use crate::db::Db;
 use crate::snapshot::table::lookup_frame_source_by_raw;
-use crate::util::http::{json_error, json_ok};
+use crate::util::http::{json_error, json_ok, parse_query_u64};
 use crate::util::source_path::resolve_source_path;
 use moire_source_context::{
-    cut_source, cut_source_compact, extract_enclosing_fn, extract_target_statement,
-    highlighted_context_lines,
+    cut_source_compact_or_window, cut_source_or_window, extract_enclosing_fn,
+    extract_target_statement, highlighted_context_lines,
 };
 
 #[derive(Facet)]
@@ -147,17 +147,6 @@ pub async fn api_source_previews(State(state): State<AppState>, body: Bytes) ->
     }
 }
 
-fn parse_query_u64(query: &str, key: &str) -> Option<u64> {
-    query.split('&').find_map(|part| {
-        let (k, v) = part.split_once('=')?;
-        if k == key {
-            v.parse::<u64>().ok()
-        } else {
-            None
-        }
-    })
-}
-
 pub(crate) fn arborium_language(path: &str) -> Option<&'static str> {
     let ext = path.rsplit('.').next()?;
     match ext {
@@ -293,12 +282,13 @@ pub(crate) fn lookup_source_in_db(
     };
 
     let context_lines = lang.and_then(|lang_name| {
-        let cut_result = cut_source(&content, lang_name, target_line, target_col)?;
+        let cut_result = cut_source_or_window(&content, lang_name, target_line, target_col)?;
         Some(highlighted_context_lines(&cut_result, lang_name))
     });
 
     let compact_context_lines = lang.and_then(|lang_name| {
-        let cut_result = cut_source_compact(&content, lang_name, target_line, target_col)?;
+        let cut_result =
+            cut_source_compact_or_window(&content, lang_name, target_line, target_col)?;
         Some(highlighted_context_lines(&cut_result, lang_name))
     });
 
@@ -1,26 +1,34 @@
 use std::collections::{BTreeMap, BTreeSet, HashMap, VecDeque};
 use std::path::PathBuf;
-use std::sync::Arc;
+use std::sync::{Arc, RwLock};
+use std::time::Instant;
 
 use axum::Router;
-use axum::routing::{any, get, post};
+use axum::routing::{any, delete, get, post};
 use tower_http::services::{ServeDir, ServeFile};
 
+use crate::api::annotations::{api_annotation_create, api_annotation_delete, api_annotations};
 use crate::api::connections::{api_connections, api_cut_status, api_trigger_cut};
+use crate::api::history::{api_history, api_history_snapshot};
 use crate::api::recording::{
     api_record_current, api_record_export, api_record_frame, api_record_import, api_record_start,
     api_record_stop,
 };
-use crate::api::snapshot::{api_snapshot, api_snapshot_current, api_snapshot_symbolication_ws};
+use crate::api::snapshot::{
+    api_snapshot, api_snapshot_current, api_snapshot_summary, api_snapshot_symbolication_ws,
+};
 use crate::api::source::{api_source_preview, api_source_previews};
 use crate::api::sql::{api_query, api_sql};
 use crate::api::theme::api_arborium_theme_css;
 use crate::db::{Db, StoredModuleManifestEntry};
+use crate::graph::analysis::AnalysisRegistry;
+use crate::graph::config::DetectionConfig;
 use crate::proxy::proxy_vite;
 use crate::recording::session::RecordingState;
+use crate::snapshot::capacity::CapacityTrends;
 use moire_trace_types::BacktraceId;
 use moire_types::{ProcessId, SnapshotCutResponse};
-use moire_wire::SnapshotReply;
+use moire_wire::{SnapshotEncoding, SnapshotReply};
 use tokio::sync::{Mutex, Notify, mpsc};
 
 pub mod ids;
@@ -32,6 +40,12 @@ pub struct AppState {
     pub db: Arc<Db>,
     pub dev_proxy: Option<DevProxyState>,
     pub frontend_dist: Option<PathBuf>,
+    /// Analyses run over every snapshot; results land in `SnapshotCutResponse::findings`.
+    pub analyses: Arc<AnalysisRegistry>,
+    /// Swapped wholesale when the detection config file is reloaded.
+    pub detection: Arc<RwLock<Arc<DetectionConfig>>>,
+    /// Encoding asked of processes for snapshot replies; `None` is JSON.
+    pub snapshot_encoding: Option<SnapshotEncoding>,
 }
 
 #[derive(Clone)]
@@ -52,6 +66,8 @@ pub struct ServerState {
     pub snapshot_history_ids: VecDeque<i64>,
     pub snapshot_history_json: BTreeMap<i64, String>,
     pub recording: Option<RecordingState>,
+    /// Fed by every snapshot taken; produces `SnapshotCutResponse::sizing_hints`.
+    pub capacity_trends: CapacityTrends,
 }
 
 pub struct ConnectedProcess {
@@ -72,6 +88,11 @@ pub struct CutState {
 pub struct SnapshotPending {
     pub pending_conn_ids: BTreeSet<ConnectionId>,
     pub replies: HashMap<ConnectionId, SnapshotReply>,
+    /// When the request went out. Replies are aligned to this instant, the
+    /// start of the collection round.
+    pub requested_at: Instant,
+    /// How long after `requested_at` each reply arrived.
+    pub reply_skew_ms: HashMap<ConnectionId, u64>,
     pub notify: Arc<Notify>,
 }
 
@@ -94,6 +115,7 @@ impl ServerState {
             snapshot_history_ids: VecDeque::new(),
             snapshot_history_json: BTreeMap::new(),
             recording: None,
+            capacity_trends: CapacityTrends::default(),
         }
     }
 }
@@ -110,6 +132,43 @@ impl AppState {
             db: Arc::new(db),
             dev_proxy,
             frontend_dist,
+            analyses: Arc::new(AnalysisRegistry::default()),
+            detection: Arc::new(RwLock::new(Arc::new(DetectionConfig::default()))),
+            snapshot_encoding: None,
+        }
+    }
+
+    /// Replaces the built-in analyses of [`AnalysisRegistry::default()`].
+    pub fn with_analyses(mut self, analyses: AnalysisRegistry) -> Self {
+        self.analyses = Arc::new(analyses);
+        self
+    }
+
+    /// Starts with `config` instead of the built-in detection defaults.
+    pub fn with_detection_config(self, config: DetectionConfig) -> Self {
+        self.set_detection_config(config);
+        self
+    }
+
+    /// Asks processes to reply to snapshot requests in `encoding`.
+    pub fn with_snapshot_encoding(mut self, encoding: Option<SnapshotEncoding>) -> Self {
+        self.snapshot_encoding = encoding;
+        self
+    }
+
+    /// The detection config snapshots are analysed with right now.
+    pub fn detection_config(&self) -> Arc<DetectionConfig> {
+        match self.detection.read() {
+            Ok(config) => config.clone(),
+            Err(poisoned) => poisoned.into_inner().clone(),
+        }
+    }
+
+    pub fn set_detection_config(&self, config: DetectionConfig) {
+        let config = Arc::new(config);
+        match self.detection.write() {
+            Ok(mut current) => *current = config,
+            Err(poisoned) => *poisoned.into_inner() = config,
         }
     }
 }
@@ -124,6 +183,7 @@ pub fn build_router(state: AppState) -> Router {
         .route("/api/query", post(api_query))
         .route("/api/snapshot", post(api_snapshot))
         .route("/api/snapshot/current", get(api_snapshot_current))
+        .route("/api/snapshot/summary", post(api_snapshot_summary))
         .route(
             "/api/snapshot/{snapshot_id}/symbolication/ws",
             get(api_snapshot_symbolication_ws),
@@ -137,6 +197,16 @@ pub fn build_router(state: AppState) -> Router {
         )
         .route("/api/record/current/export", get(api_record_export))
         .route("/api/record/import", post(api_record_import))
+        .route("/api/history", post(api_history))
+        .route("/api/history/{history_id}", get(api_history_snapshot))
+        .route(
+            "/api/annotations",
+            get(api_annotations).post(api_annotation_create),
+        )
+        .route(
+            "/api/annotations/{annotation_id}",
+            delete(api_annotation_delete),
+        )
         .route("/api/source/preview", get(api_source_preview))
         .route("/api/source/previews", post(api_source_previews))
         .route("/api/arborium-theme.css", get(api_arborium_theme_css));
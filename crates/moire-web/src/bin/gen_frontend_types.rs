@@ -21,6 +21,10 @@ fn main() -> Result<(), String> {
     tsgen.add_type::<moire_types::SourcePreviewResponse>();
     tsgen.add_type::<moire_types::SourcePreviewBatchRequest>();
     tsgen.add_type::<moire_types::SourcePreviewBatchResponse>();
+    tsgen.add_type::<moire_types::HistoryQueryRequest>();
+    tsgen.add_type::<moire_types::HistoryQueryResponse>();
+    tsgen.add_type::<moire_types::AnnotationCreateRequest>();
+    tsgen.add_type::<moire_types::AnnotationListResponse>();
 
     let generated = tsgen.finish();
     let mut out = String::new();
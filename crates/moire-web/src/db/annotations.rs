@@ -0,0 +1,73 @@
+use facet::Facet;
+use moire_types::Annotation;
+use rusqlite_facet::ConnectionFacetExt;
+
+use crate::db::Db;
+
+#[derive(Facet)]
+struct NoParams;
+
+#[derive(Facet)]
+struct AnnotationInsertParams {
+    target: String,
+    note: String,
+    author: Option<String>,
+    created_at_unix_ms: i64,
+}
+
+#[derive(Facet)]
+struct AnnotationIdParams {
+    annotation_id: i64,
+}
+
+/// Stores a new annotation and returns it with its assigned id.
+pub fn insert_annotation_blocking(
+    db: &Db,
+    target: &str,
+    note: &str,
+    author: Option<&str>,
+    created_at_unix_ms: i64,
+) -> Result<Annotation, String> {
+    let conn = db.open()?;
+    conn.facet_execute_ref(
+        "INSERT INTO annotations (target, note, author, created_at_unix_ms)
+         VALUES (:target, :note, :author, :created_at_unix_ms)",
+        &AnnotationInsertParams {
+            target: String::from(target),
+            note: String::from(note),
+            author: author.map(String::from),
+            created_at_unix_ms,
+        },
+    )
+    .map_err(|error| format!("insert annotation: {error}"))?;
+    Ok(Annotation {
+        annotation_id: conn.last_insert_rowid(),
+        target: String::from(target),
+        note: String::from(note),
+        author: author.map(String::from),
+        created_at_unix_ms,
+    })
+}
+
+/// Every stored annotation, newest first.
+pub fn list_annotations_blocking(db: &Db) -> Result<Vec<Annotation>, String> {
+    let conn = db.open()?;
+    conn.facet_query_ref::<Annotation, _>(
+        "SELECT annotation_id, target, note, author, created_at_unix_ms FROM annotations
+         ORDER BY created_at_unix_ms DESC, annotation_id DESC",
+        &NoParams,
+    )
+    .map_err(|error| format!("query annotations: {error}"))
+}
+
+/// Removes one annotation. Returns whether it existed.
+pub fn delete_annotation_blocking(db: &Db, annotation_id: i64) -> Result<bool, String> {
+    let conn = db.open()?;
+    let deleted = conn
+        .facet_execute_ref(
+            "DELETE FROM annotations WHERE annotation_id = :annotation_id",
+            &AnnotationIdParams { annotation_id },
+        )
+        .map_err(|error| format!("delete annotation: {error}"))?;
+    Ok(deleted > 0)
+}
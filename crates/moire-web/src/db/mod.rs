@@ -2,10 +2,14 @@ use std::path::{Path, PathBuf};
 
 use rusqlite::Connection;
 
+mod annotations;
 mod persist;
 mod query;
 mod schema;
 
+pub use annotations::{
+    delete_annotation_blocking, insert_annotation_blocking, list_annotations_blocking,
+};
 pub use persist::{
     BacktraceFramePersist, StoredModuleManifestEntry, backtrace_frames_for_store,
     into_stored_module_manifest, persist_backtrace_record, persist_connection_closed,
@@ -5,7 +5,7 @@ use rusqlite_facet::ConnectionFacetExt;
 
 use crate::db::Db;
 
-const DB_SCHEMA_VERSION: i64 = 6;
+const DB_SCHEMA_VERSION: i64 = 9;
 
 #[derive(Facet)]
 struct NoParams;
@@ -76,6 +76,12 @@ pub fn load_next_connection_id(db: &Db) -> Result<ConnectionId, String> {
 fn reset_managed_schema(conn: &Connection) -> Result<(), String> {
     conn.execute_batch(
         "
+        DROP TABLE IF EXISTS annotations;
+        DROP TABLE IF EXISTS history_tasks;
+        DROP TABLE IF EXISTS history_findings;
+        DROP TABLE IF EXISTS history_edges;
+        DROP TABLE IF EXISTS history_nodes;
+        DROP TABLE IF EXISTS history_snapshots;
         DROP TABLE IF EXISTS events;
         DROP TABLE IF EXISTS edges;
         DROP TABLE IF EXISTS entities;
@@ -266,5 +272,64 @@ fn managed_schema_sql() -> &'static str {
         PRIMARY KEY (event_id),
         UNIQUE (process_id, seq_no)
     );
+
+    CREATE TABLE IF NOT EXISTS history_snapshots (
+        history_id INTEGER PRIMARY KEY AUTOINCREMENT,
+        snapshot_id INTEGER NOT NULL,
+        captured_at_unix_ms INTEGER NOT NULL,
+        process_count INTEGER NOT NULL,
+        snapshot_json TEXT NOT NULL
+    );
+    CREATE INDEX IF NOT EXISTS idx_history_snapshots_captured_at
+        ON history_snapshots (captured_at_unix_ms);
+
+    CREATE TABLE IF NOT EXISTS history_nodes (
+        history_id INTEGER NOT NULL,
+        process_id TEXT NOT NULL,
+        entity_id TEXT NOT NULL,
+        name TEXT NOT NULL,
+        kind TEXT NOT NULL,
+        age_ms INTEGER NOT NULL,
+        PRIMARY KEY (history_id, process_id, entity_id)
+    );
+
+    CREATE TABLE IF NOT EXISTS history_edges (
+        history_id INTEGER NOT NULL,
+        process_id TEXT NOT NULL,
+        src_key TEXT NOT NULL,
+        dst_key TEXT NOT NULL,
+        waited_ms INTEGER,
+        PRIMARY KEY (history_id, src_key, dst_key)
+    );
+
+    CREATE TABLE IF NOT EXISTS history_findings (
+        history_id INTEGER NOT NULL,
+        finding_index INTEGER NOT NULL,
+        analysis TEXT NOT NULL,
+        finding_json TEXT NOT NULL,
+        PRIMARY KEY (history_id, finding_index)
+    );
+
+    CREATE TABLE IF NOT EXISTS history_tasks (
+        history_id INTEGER NOT NULL,
+        logical_id TEXT NOT NULL,
+        process_id TEXT NOT NULL,
+        entity_id TEXT NOT NULL,
+        name TEXT NOT NULL,
+        age_ms INTEGER NOT NULL,
+        PRIMARY KEY (history_id, process_id, entity_id)
+    );
+    CREATE INDEX IF NOT EXISTS idx_history_tasks_logical_id
+        ON history_tasks (logical_id);
+
+    CREATE TABLE IF NOT EXISTS annotations (
+        annotation_id INTEGER PRIMARY KEY AUTOINCREMENT,
+        target TEXT NOT NULL,
+        note TEXT NOT NULL,
+        author TEXT,
+        created_at_unix_ms INTEGER NOT NULL
+    );
+    CREATE INDEX IF NOT EXISTS idx_annotations_target
+        ON annotations (target);
     "
 }
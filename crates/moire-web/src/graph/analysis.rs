@@ -0,0 +1,1360 @@
+//! Pluggable analyses that run over every snapshot alongside deadlock detection.
+//!
+//! Implement [`Analysis`] for a custom detector (for example "our scheduler queue
+//! never holds more than 10k messages"), register it on an [`AnalysisRegistry`],
+//! and hand the registry to [`AppState::with_analyses`](crate::app::AppState::with_analyses).
+//! Findings are attached to [`SnapshotCutResponse::findings`], next to the ones
+//! from the built-in [`DeadlockAnalysis`], [`NearCycleAnalysis`],
+//! [`LivelockAnalysis`], [`CancelledHolderAnalysis`], [`StarvationAnalysis`],
+//! [`TaskMigrationAnalysis`], [`SlowSubscriberAnalysis`],
+//! [`ReceiverNotDrainingAnalysis`], [`LostWakeupAnalysis`], [`StaleHeartbeatAnalysis`],
+//! [`StatGrowthAnalysis`] and [`BottleneckAnalysis`]. The collector runs
+//! them under its current [`DetectionConfig`], which can switch analyses off,
+//! suppress findings and tune the built-in thresholds. Wait edges are scored
+//! by the registry's [`SeverityPolicy`].
+
+// r[impl api.snapshot.findings]
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use moire_types::{
+    AnalysisFinding, EdgeKind, EntityBody, EntityId, EventKind, EventTarget, FindingSeverity,
+    FindingSubject, HOLDER_CANCELLED_EVENT, HolderCancelledPayload, ProcessId, SnapshotCutResponse,
+};
+use tracing::warn;
+
+use super::config::{DetectionConfig, DetectionThresholds};
+use super::detect::{
+    find_bottleneck_candidates, find_deadlock_candidates, find_livelock_candidates,
+    find_near_cycle_candidates, find_starvation_candidates,
+};
+use super::severity::{DefaultSeverityPolicy, SeverityPolicy};
+use super::{WaitGraph, compose_node_key};
+
+const DEADLOCK_ANALYSIS_BUDGET: Duration = Duration::from_millis(200);
+/// Bottlenecks reported per snapshot, so a wide fan-in does not flood the
+/// findings with every resource on the way.
+const MAX_BOTTLENECK_FINDINGS: usize = 10;
+
+/// A detector run over every snapshot.
+pub trait Analysis: Send + Sync {
+    /// Stable name, reported as [`AnalysisFinding::analysis`].
+    fn name(&self) -> &str;
+
+    /// Inspects one snapshot and its wait graph, under the thresholds of the
+    /// active [`DetectionConfig`]. Built-in analyses read theirs from
+    /// `thresholds`; custom ones are free to ignore them. The registry
+    /// overwrites `analysis` on every returned finding with
+    /// [`Analysis::name`].
+    fn run(
+        &self,
+        graph: &WaitGraph,
+        snapshot: &SnapshotCutResponse,
+        thresholds: &DetectionThresholds,
+    ) -> Vec<AnalysisFinding>;
+}
+
+/// Ordered set of analyses run by the collector for each snapshot.
+pub struct AnalysisRegistry {
+    analyses: Vec<Box<dyn Analysis>>,
+    severity_policy: Arc<dyn SeverityPolicy>,
+}
+
+impl AnalysisRegistry {
+    /// A registry with no analyses, not even deadlock detection.
+    pub fn empty() -> Self {
+        Self {
+            analyses: Vec::new(),
+            severity_policy: Arc::new(DefaultSeverityPolicy),
+        }
+    }
+
+    pub fn register(&mut self, analysis: impl Analysis + 'static) -> &mut Self {
+        self.analyses.push(Box::new(analysis));
+        self
+    }
+
+    /// Names of the registered analyses, in registration order.
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.analyses.iter().map(|analysis| analysis.name())
+    }
+
+    /// Scores wait edges with `policy` instead of [`DefaultSeverityPolicy`],
+    /// for every analysis in the registry.
+    pub fn set_severity_policy(&mut self, policy: Arc<dyn SeverityPolicy>) -> &mut Self {
+        self.severity_policy = policy;
+        self
+    }
+
+    /// Builds the wait graph once and runs every analysis over it in
+    /// registration order. Findings are sorted by descending severity, then
+    /// descending score, then [`AnalysisFinding::fingerprint`], so the same
+    /// snapshot always yields the same list. Fails when the wait graph cannot
+    /// be built, in which case no analysis ran.
+    pub fn run_all(&self, snapshot: &SnapshotCutResponse) -> Result<Vec<AnalysisFinding>, String> {
+        self.run_configured(snapshot, &DetectionConfig::default())
+    }
+
+    /// Like [`AnalysisRegistry::run_all`], with `config`'s thresholds,
+    /// skipping the analyses it disables and dropping the findings it
+    /// suppresses.
+    pub fn run_configured(
+        &self,
+        snapshot: &SnapshotCutResponse,
+        config: &DetectionConfig,
+    ) -> Result<Vec<AnalysisFinding>, String> {
+        if self.analyses.is_empty() {
+            return Ok(Vec::new());
+        }
+        let graph = WaitGraph::build_with_policy(snapshot, self.severity_policy.clone())
+            .map_err(|e| format!("build wait graph: {e}"))?;
+
+        let mut findings = Vec::new();
+        for analysis in &self.analyses {
+            let name = analysis.name();
+            if config.disabled_analyses.contains(name) {
+                continue;
+            }
+            findings.extend(
+                analysis
+                    .run(&graph, snapshot, &config.thresholds)
+                    .into_iter()
+                    .map(|mut finding| {
+                        finding.analysis = String::from(name);
+                        finding
+                    })
+                    .filter(|finding| !config.suppressed.contains(&finding.fingerprint())),
+            );
+        }
+        findings.sort_by(|a, b| {
+            let score = |finding: &AnalysisFinding| finding.score.as_ref().map_or(0, |s| s.total());
+            b.severity
+                .cmp(&a.severity)
+                .then_with(|| score(b).cmp(&score(a)))
+                .then_with(|| a.fingerprint().cmp(&b.fingerprint()))
+        });
+        Ok(findings)
+    }
+}
+
+impl Default for AnalysisRegistry {
+    /// A registry holding every built-in analysis.
+    fn default() -> Self {
+        let mut registry = Self::empty();
+        registry
+            .register(DeadlockAnalysis)
+            .register(NearCycleAnalysis)
+            .register(LivelockAnalysis)
+            .register(CancelledHolderAnalysis)
+            .register(StarvationAnalysis)
+            .register(TaskMigrationAnalysis)
+            .register(SlowSubscriberAnalysis)
+            .register(ReceiverNotDrainingAnalysis)
+            .register(LostWakeupAnalysis)
+            .register(StaleHeartbeatAnalysis)
+            .register(StatGrowthAnalysis)
+            .register(BottleneckAnalysis);
+        registry
+    }
+}
+
+/// Built-in analysis reporting wait cycles as deadlock findings.
+pub struct DeadlockAnalysis;
+
+impl Analysis for DeadlockAnalysis {
+    fn name(&self) -> &str {
+        "deadlock"
+    }
+
+    fn run(
+        &self,
+        graph: &WaitGraph,
+        _snapshot: &SnapshotCutResponse,
+        _thresholds: &DetectionThresholds,
+    ) -> Vec<AnalysisFinding> {
+        let scan = find_deadlock_candidates(graph, Some(Instant::now() + DEADLOCK_ANALYSIS_BUDGET));
+        if !scan.complete {
+            warn!("deadlock analysis ran out of budget; findings are partial");
+        }
+
+        scan.candidates
+            .into_iter()
+            .map(|candidate| {
+                let severity = if candidate.confidence == "high" {
+                    FindingSeverity::Critical
+                } else {
+                    FindingSeverity::Warning
+                };
+                let subjects = finding_subjects(graph, &candidate.node_keys);
+                let mut rationale = candidate.reasons.join(", ");
+                if let Some(ms) = candidate.blocked_duration_hint_ms {
+                    rationale.push_str(&format!("; blocked for at least {ms}ms"));
+                }
+                rationale.push_str(&format!(
+                    "; {} task(s) blocked",
+                    candidate.blocked_task_count
+                ));
+                AnalysisFinding {
+                    analysis: String::new(),
+                    severity,
+                    title: format!("wait cycle across {} entities", subjects.len()),
+                    rationale,
+                    subjects,
+                    score: Some(candidate.score),
+                    hints: candidate.hints,
+                }
+            })
+            .collect()
+    }
+}
+
+fn finding_subjects(graph: &WaitGraph, node_keys: &[String]) -> Vec<FindingSubject> {
+    node_keys
+        .iter()
+        .filter_map(|key| graph.nodes.get(key))
+        .map(|node| {
+            FindingSubject::new(
+                &ProcessId::new(node.process_id.as_str()),
+                &EntityId::new(node.entity_id.as_str()),
+            )
+        })
+        .collect()
+}
+
+// r[impl api.snapshot.near-cycle]
+/// Built-in analysis reporting wait paths one acquire short of a cycle, for
+/// snapshots taken just before the last edge of a deadlock formed.
+pub struct NearCycleAnalysis;
+
+impl Analysis for NearCycleAnalysis {
+    fn name(&self) -> &str {
+        "near_cycle"
+    }
+
+    fn run(
+        &self,
+        graph: &WaitGraph,
+        snapshot: &SnapshotCutResponse,
+        _thresholds: &DetectionThresholds,
+    ) -> Vec<AnalysisFinding> {
+        let mut subjects: HashMap<String, (FindingSubject, &str)> = HashMap::new();
+        let mut holders: HashMap<String, Vec<String>> = HashMap::new();
+        let mut acquiring = Vec::new();
+        for process in &snapshot.processes {
+            let entities = process
+                .snapshot
+                .entities
+                .iter()
+                .map(|entity| (&entity.id, entity))
+                .collect::<HashMap<_, _>>();
+            for entity in &process.snapshot.entities {
+                subjects.insert(
+                    compose_node_key(&process.process_id, &entity.id),
+                    (
+                        FindingSubject::new(&process.process_id, &entity.id),
+                        entity.name.as_str(),
+                    ),
+                );
+            }
+            for edge in &process.snapshot.edges {
+                let src = compose_node_key(&process.process_id, &edge.src);
+                let dst = compose_node_key(&process.process_id, &edge.dst);
+                match edge.kind {
+                    EdgeKind::HeldBy => holders.entry(src).or_default().push(dst),
+                    // A task polling a resource, rather than a future it
+                    // awaits, is acquiring it.
+                    EdgeKind::Polls
+                        if entities
+                            .get(&edge.dst)
+                            .is_some_and(|dst| !matches!(dst.body, EntityBody::Future(_))) =>
+                    {
+                        acquiring.push((src, dst));
+                    }
+                    _ => {}
+                }
+            }
+        }
+
+        find_near_cycle_candidates(graph, &holders, &acquiring)
+            .into_iter()
+            .filter_map(|candidate| {
+                let name = |key: &String| subjects.get(key).map(|(_, name)| *name);
+                let task = name(candidate.path.last()?)?;
+                let resource = name(candidate.path.first()?)?;
+                let path = candidate
+                    .path
+                    .iter()
+                    .map(|key| name(key).unwrap_or(key.as_str()))
+                    .collect::<Vec<_>>()
+                    .join(" -> ");
+                Some(AnalysisFinding {
+                    analysis: String::new(),
+                    severity: FindingSeverity::Warning,
+                    title: format!("{task} is one acquire away from a deadlock"),
+                    rationale: format!(
+                        "{task} is acquiring {resource} and is not waiting on it yet, but {path} already leads back to it; if the acquire blocks, this becomes a wait cycle"
+                    ),
+                    subjects: candidate
+                        .path
+                        .iter()
+                        .filter_map(|key| subjects.get(key).map(|(subject, _)| subject.clone()))
+                        .collect(),
+                    score: Some(candidate.score),
+                    hints: Vec::new(),
+                })
+            })
+            .collect()
+    }
+}
+
+/// Built-in analysis reporting futures that keep waking each other without
+/// making progress.
+pub struct LivelockAnalysis;
+
+impl Analysis for LivelockAnalysis {
+    fn name(&self) -> &str {
+        "livelock"
+    }
+
+    fn run(
+        &self,
+        graph: &WaitGraph,
+        _snapshot: &SnapshotCutResponse,
+        thresholds: &DetectionThresholds,
+    ) -> Vec<AnalysisFinding> {
+        find_livelock_candidates(graph, thresholds)
+            .into_iter()
+            .map(|candidate| AnalysisFinding {
+                analysis: String::new(),
+                severity: FindingSeverity::Warning,
+                title: String::from("tasks waking each other without progress"),
+                rationale: format!(
+                    "{}; at least {} unproductive wakes/s each",
+                    candidate.reasons.join(", "),
+                    candidate.wakes_per_sec
+                ),
+                subjects: finding_subjects(graph, &candidate.node_keys),
+                score: Some(candidate.score),
+                hints: Vec::new(),
+            })
+            .collect()
+    }
+}
+
+/// Built-in analysis reporting resources that are still waited on after the
+/// future holding them was cancelled, and that no live future holds any more.
+///
+/// Relies on the runtime's [`HOLDER_CANCELLED_EVENT`]; the holder itself is gone
+/// from the wait graph by the time waiters hang.
+pub struct CancelledHolderAnalysis;
+
+impl Analysis for CancelledHolderAnalysis {
+    fn name(&self) -> &str {
+        "cancelled_holder"
+    }
+
+    fn run(
+        &self,
+        graph: &WaitGraph,
+        snapshot: &SnapshotCutResponse,
+        _thresholds: &DetectionThresholds,
+    ) -> Vec<AnalysisFinding> {
+        let mut findings = Vec::new();
+        for process in &snapshot.processes {
+            for event in &process.snapshot.events {
+                let EventKind::Custom(custom) = &event.kind else {
+                    continue;
+                };
+                if custom.kind != HOLDER_CANCELLED_EVENT {
+                    continue;
+                }
+                let EventTarget::Entity(resource_id) = &event.target else {
+                    continue;
+                };
+                let key = compose_node_key(&process.process_id, resource_id);
+                let Some(resource) = graph.nodes.get(&key) else {
+                    continue;
+                };
+                let waiters = graph.indegree.get(&key).copied().unwrap_or(0);
+                if waiters == 0 {
+                    continue;
+                }
+                // A guard that outlived its future keeps the cancelled
+                // holder listed, as a tombstone; only a live holder counts.
+                let still_held = graph.holders.get(&key).is_some_and(|holders| {
+                    holders.iter().any(|holder| holder.removed_ms.is_none())
+                });
+                if still_held {
+                    continue;
+                }
+                let Ok(payload) =
+                    facet_json::from_str::<HolderCancelledPayload>(custom.payload.as_str())
+                else {
+                    warn!(
+                        kind = HOLDER_CANCELLED_EVENT,
+                        "skipping event with malformed payload"
+                    );
+                    continue;
+                };
+
+                findings.push(AnalysisFinding {
+                    analysis: String::new(),
+                    severity: FindingSeverity::Warning,
+                    title: format!("{} awaited after its holder was cancelled", resource.name),
+                    rationale: format!(
+                        "{} was held by {} ({}) when it was dropped before completing; {waiters} waiter(s) remain",
+                        resource.name,
+                        payload.holder_name,
+                        payload.holder_id.as_str()
+                    ),
+                    subjects: vec![
+                        FindingSubject::new(&process.process_id, resource_id),
+                        FindingSubject::new(&process.process_id, &payload.holder_id),
+                    ],
+                    score: None,
+                    hints: Vec::new(),
+                });
+            }
+        }
+        findings
+    }
+}
+
+/// Built-in analysis reporting locks and semaphores whose waiters have waited
+/// far longer than the resource is usually held.
+pub struct StarvationAnalysis;
+
+impl Analysis for StarvationAnalysis {
+    fn name(&self) -> &str {
+        "starvation"
+    }
+
+    fn run(
+        &self,
+        graph: &WaitGraph,
+        _snapshot: &SnapshotCutResponse,
+        thresholds: &DetectionThresholds,
+    ) -> Vec<AnalysisFinding> {
+        find_starvation_candidates(graph, thresholds)
+            .into_iter()
+            .filter_map(|candidate| {
+                let resource = graph.nodes.get(&candidate.resource_key)?;
+                let mut keys = vec![candidate.resource_key.clone()];
+                keys.extend(candidate.waiter_keys.iter().cloned());
+                Some(AnalysisFinding {
+                    analysis: String::new(),
+                    severity: FindingSeverity::Warning,
+                    title: format!(
+                        "{} waiter(s) starved on {}",
+                        candidate.waiter_keys.len(),
+                        resource.name
+                    ),
+                    rationale: format!(
+                        "{}; waited {}ms against an average hold of {}ms over {} holds; {}",
+                        candidate.reasons.join(", "),
+                        candidate.longest_wait_ms,
+                        candidate.avg_hold_ms,
+                        candidate.hold_count,
+                        graph.waiters_of(&candidate.resource_key).describe(graph)
+                    ),
+                    subjects: finding_subjects(graph, &keys),
+                    score: Some(candidate.score),
+                    hints: Vec::new(),
+                })
+            })
+            .collect()
+    }
+}
+
+/// Built-in analysis reporting the resources with the most tasks blocked
+/// behind them, directly or through other waits.
+pub struct BottleneckAnalysis;
+
+impl Analysis for BottleneckAnalysis {
+    fn name(&self) -> &str {
+        "bottleneck"
+    }
+
+    fn run(
+        &self,
+        graph: &WaitGraph,
+        _snapshot: &SnapshotCutResponse,
+        thresholds: &DetectionThresholds,
+    ) -> Vec<AnalysisFinding> {
+        find_bottleneck_candidates(graph, thresholds)
+            .into_iter()
+            .take(MAX_BOTTLENECK_FINDINGS)
+            .filter_map(|candidate| {
+                let resource = graph.nodes.get(&candidate.resource_key)?;
+                Some(AnalysisFinding {
+                    analysis: String::new(),
+                    severity: FindingSeverity::Info,
+                    title: format!(
+                        "{} task(s) blocked behind {}",
+                        candidate.blocked_task_count, resource.name
+                    ),
+                    rationale: format!(
+                        "{} waiting on it directly, {} through other waits; {}",
+                        candidate.direct_waiters,
+                        candidate.blocked_task_count - candidate.direct_waiters,
+                        graph.waiters_of(&candidate.resource_key).describe(graph)
+                    ),
+                    subjects: finding_subjects(graph, &[candidate.resource_key]),
+                    score: Some(candidate.score),
+                    hints: Vec::new(),
+                })
+            })
+            .collect()
+    }
+}
+
+/// Built-in analysis reporting futures that keep being polled on a different
+/// worker thread than the time before, which defeats cache locality and
+/// usually points at an imbalanced runtime.
+pub struct TaskMigrationAnalysis;
+
+impl Analysis for TaskMigrationAnalysis {
+    fn name(&self) -> &str {
+        "task_migration"
+    }
+
+    fn run(
+        &self,
+        _graph: &WaitGraph,
+        snapshot: &SnapshotCutResponse,
+        thresholds: &DetectionThresholds,
+    ) -> Vec<AnalysisFinding> {
+        let mut findings = Vec::new();
+        for process in &snapshot.processes {
+            for entity in &process.snapshot.entities {
+                let EntityBody::Future(future) = &entity.body else {
+                    continue;
+                };
+                let (Some(poll_count), Some(migrations)) =
+                    (future.poll_count, future.worker_migrations)
+                else {
+                    continue;
+                };
+                if poll_count == 0
+                    || poll_count < thresholds.task_migration_min_polls
+                    || migrations * 100 < poll_count * thresholds.task_migration_min_percent
+                {
+                    continue;
+                }
+                let last_worker = future.poll_worker.and_then(|worker| {
+                    process
+                        .snapshot
+                        .workers
+                        .iter()
+                        .find(|load| load.worker == worker)
+                });
+                let last_worker = match last_worker {
+                    Some(load) => format!(
+                        "; last polled on worker {} ({}, {} live task(s))",
+                        load.worker,
+                        load.thread_name.as_deref().unwrap_or("unnamed"),
+                        load.task_count
+                    ),
+                    None => String::new(),
+                };
+
+                findings.push(AnalysisFinding {
+                    analysis: String::new(),
+                    severity: FindingSeverity::Info,
+                    title: format!("{} bounces between worker threads", entity.name),
+                    rationale: format!(
+                        "moved to another thread on {migrations} of {poll_count} polls ({}%){last_worker}",
+                        migrations * 100 / poll_count
+                    ),
+                    subjects: vec![FindingSubject::new(&process.process_id, &entity.id)],
+                    score: None,
+                    hints: Vec::new(),
+                });
+            }
+        }
+        findings
+    }
+}
+
+/// Built-in analysis reporting broadcast receivers that have missed messages
+/// or are close to doing so. Senders never wait on a broadcast channel, so a
+/// slow subscriber does not show up as a wait edge.
+pub struct SlowSubscriberAnalysis;
+
+impl Analysis for SlowSubscriberAnalysis {
+    fn name(&self) -> &str {
+        "slow_subscriber"
+    }
+
+    fn run(
+        &self,
+        _graph: &WaitGraph,
+        snapshot: &SnapshotCutResponse,
+        thresholds: &DetectionThresholds,
+    ) -> Vec<AnalysisFinding> {
+        let mut findings = Vec::new();
+        for process in &snapshot.processes {
+            let entities = &process.snapshot.entities;
+            for rx in entities {
+                if rx.removed_at.is_some() {
+                    continue;
+                }
+                let EntityBody::BroadcastRx(body) = &rx.body else {
+                    continue;
+                };
+                let capacity = process
+                    .snapshot
+                    .edges
+                    .iter()
+                    .filter(|edge| edge.kind == EdgeKind::PairedWith && edge.dst == rx.id)
+                    .find_map(|edge| {
+                        entities
+                            .iter()
+                            .find(|tx| tx.id == edge.src)
+                            .and_then(|tx| match &tx.body {
+                                EntityBody::BroadcastTx(tx) => Some(tx.capacity),
+                                _ => None,
+                            })
+                    });
+                let dropped = body.dropped.unwrap_or(0);
+                let nearly_full = capacity.is_some_and(|capacity| {
+                    capacity > 0
+                        && u64::from(body.lag) * 100
+                            >= u64::from(capacity) * thresholds.slow_subscriber_lag_percent
+                });
+                if dropped == 0 && !nearly_full {
+                    continue;
+                }
+
+                let backlog = match capacity {
+                    Some(capacity) => format!("{} of {capacity} slots behind", body.lag),
+                    None => format!("{} message(s) behind", body.lag),
+                };
+                findings.push(AnalysisFinding {
+                    analysis: String::new(),
+                    severity: if dropped > 0 {
+                        FindingSeverity::Warning
+                    } else {
+                        FindingSeverity::Info
+                    },
+                    title: format!("{} is falling behind its broadcast channel", rx.name),
+                    rationale: format!("missed {dropped} message(s); {backlog}"),
+                    subjects: vec![FindingSubject::new(&process.process_id, &rx.id)],
+                    score: None,
+                    hints: Vec::new(),
+                });
+            }
+        }
+        findings
+    }
+}
+
+// r[impl api.snapshot.receiver-not-draining]
+/// Built-in analysis reporting mpsc channels with queued messages whose
+/// receiver is blocked on something else. Senders only wait on a full
+/// channel, so until it fills up the backlog shows up as no wait edge at all.
+pub struct ReceiverNotDrainingAnalysis;
+
+impl Analysis for ReceiverNotDrainingAnalysis {
+    fn name(&self) -> &str {
+        "receiver_not_draining"
+    }
+
+    fn run(
+        &self,
+        graph: &WaitGraph,
+        snapshot: &SnapshotCutResponse,
+        _thresholds: &DetectionThresholds,
+    ) -> Vec<AnalysisFinding> {
+        let mut findings = Vec::new();
+        for process in &snapshot.processes {
+            let entities = &process.snapshot.entities;
+            for rx in entities {
+                if rx.removed_at.is_some() {
+                    continue;
+                }
+                let EntityBody::MpscRx(body) = &rx.body else {
+                    continue;
+                };
+                let Some(receiver_id) = &body.receiver else {
+                    continue;
+                };
+                let Some((tx, queue_len, capacity)) = process
+                    .snapshot
+                    .edges
+                    .iter()
+                    .filter(|edge| edge.kind == EdgeKind::PairedWith && edge.dst == rx.id)
+                    .find_map(|edge| {
+                        let tx = entities.iter().find(|tx| tx.id == edge.src)?;
+                        match &tx.body {
+                            EntityBody::MpscTx(body) => Some((tx, body.queue_len, body.capacity)),
+                            _ => None,
+                        }
+                    })
+                else {
+                    continue;
+                };
+                if queue_len == 0 {
+                    continue;
+                }
+                let rx_key = compose_node_key(&process.process_id, &rx.id);
+                let receiver_key = compose_node_key(&process.process_id, receiver_id);
+                let Some(waits_on) = graph.adjacency.get(&receiver_key) else {
+                    continue;
+                };
+                if waits_on.contains(&rx_key) {
+                    continue;
+                }
+                let Some(receiver) = graph.nodes.get(&receiver_key) else {
+                    continue;
+                };
+
+                let elsewhere = waits_on
+                    .iter()
+                    .map(|key| {
+                        graph
+                            .nodes
+                            .get(key)
+                            .map_or(key.as_str(), |node| node.name.as_str())
+                    })
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                let backlog = match capacity {
+                    Some(capacity) => format!("{queue_len} of {capacity} slots queued"),
+                    None => format!("{queue_len} message(s) queued"),
+                };
+                let tx_key = compose_node_key(&process.process_id, &tx.id);
+                let blocked_senders = graph.indegree.get(&tx_key).copied().unwrap_or(0);
+                let mut rationale = format!(
+                    "{backlog}; its receiver {} is waiting on {elsewhere} instead of receiving",
+                    receiver.name
+                );
+                if blocked_senders > 0 {
+                    rationale.push_str(&format!(
+                        "; {blocked_senders} sender(s) blocked on the full channel"
+                    ));
+                }
+                findings.push(AnalysisFinding {
+                    analysis: String::new(),
+                    severity: if blocked_senders > 0 {
+                        FindingSeverity::Warning
+                    } else {
+                        FindingSeverity::Info
+                    },
+                    title: format!("{} is not being drained", rx.name),
+                    rationale,
+                    subjects: [&rx.id, &tx.id, receiver_id]
+                        .into_iter()
+                        .map(|entity_id| FindingSubject::new(&process.process_id, entity_id))
+                        .collect(),
+                    score: None,
+                    hints: Vec::new(),
+                });
+            }
+        }
+        findings
+    }
+}
+
+/// Built-in analysis reporting futures that were woken and never polled
+/// afterwards (`wake_to_poll_gap_ms`). From the wait graph alone these look
+/// like ordinary waits, but the future is ready to run: either the wakeup was
+/// lost on the way to the runtime or the runtime stopped scheduling it. When
+/// several of them last ran on the same worker thread, the rationale says so:
+/// that worker is more likely saturated than the wakeups lost.
+pub struct LostWakeupAnalysis;
+
+impl Analysis for LostWakeupAnalysis {
+    fn name(&self) -> &str {
+        "lost_wakeup"
+    }
+
+    fn run(
+        &self,
+        _graph: &WaitGraph,
+        snapshot: &SnapshotCutResponse,
+        _thresholds: &DetectionThresholds,
+    ) -> Vec<AnalysisFinding> {
+        let mut findings = Vec::new();
+        for process in &snapshot.processes {
+            // A dropped future keeps the gap it was last seen with, but
+            // nothing will ever poll it again.
+            let live = || {
+                process
+                    .snapshot
+                    .entities
+                    .iter()
+                    .filter(|entity| entity.removed_at.is_none())
+            };
+            let mut stalled_per_worker: HashMap<u64, usize> = HashMap::new();
+            for entity in live() {
+                if let EntityBody::Future(future) = &entity.body
+                    && future.wake_to_poll_gap_ms.is_some()
+                    && let Some(worker) = future.poll_worker
+                {
+                    *stalled_per_worker.entry(worker).or_default() += 1;
+                }
+            }
+            for entity in live() {
+                let EntityBody::Future(future) = &entity.body else {
+                    continue;
+                };
+                let Some(gap_ms) = future.wake_to_poll_gap_ms else {
+                    continue;
+                };
+                let same_worker = future
+                    .poll_worker
+                    .and_then(|worker| Some((worker, *stalled_per_worker.get(&worker)?)))
+                    .filter(|(_, stalled)| *stalled > 1)
+                    .map(|(worker, stalled)| {
+                        format!(
+                            "; {} other woken future(s) last ran on worker {worker} too, which points at a saturated worker",
+                            stalled - 1
+                        )
+                    })
+                    .unwrap_or_default();
+                let woken_by = future
+                    .last_woken_by
+                    .as_ref()
+                    .and_then(|id| process.snapshot.entities.iter().find(|e| &e.id == id))
+                    .map(|waker| format!("; last woken by {}", waker.name))
+                    .unwrap_or_default();
+                findings.push(AnalysisFinding {
+                    analysis: String::new(),
+                    severity: FindingSeverity::Warning,
+                    title: format!("{} was woken but never polled", entity.name),
+                    rationale: format!(
+                        "woken {gap_ms}ms before the snapshot and not polled since{woken_by}{same_worker}"
+                    ),
+                    subjects: vec![FindingSubject::new(&process.process_id, &entity.id)],
+                    score: None,
+                    hints: Vec::new(),
+                });
+            }
+        }
+        findings
+    }
+}
+
+/// Built-in analysis reporting heartbeats (see `moire::liveness::heartbeat`)
+/// that have gone quiet for several intervals: the loop behind them stopped
+/// iterating, whatever the wait graph says it is doing.
+pub struct StaleHeartbeatAnalysis;
+
+impl Analysis for StaleHeartbeatAnalysis {
+    fn name(&self) -> &str {
+        "stale_heartbeat"
+    }
+
+    fn run(
+        &self,
+        _graph: &WaitGraph,
+        snapshot: &SnapshotCutResponse,
+        thresholds: &DetectionThresholds,
+    ) -> Vec<AnalysisFinding> {
+        let mut findings = Vec::new();
+        for process in &snapshot.processes {
+            for entity in &process.snapshot.entities {
+                let EntityBody::Heartbeat(heartbeat) = &entity.body else {
+                    continue;
+                };
+                if entity.removed_at.is_some() || heartbeat.interval_ms == 0 {
+                    continue;
+                }
+                let last_beat_ms = heartbeat.last_beat_at.unwrap_or(entity.birth).as_millis();
+                let age_ms = process.ptime_now_ms.saturating_sub(last_beat_ms);
+                let stale_after_ms = heartbeat
+                    .interval_ms
+                    .saturating_mul(thresholds.heartbeat_stale_intervals);
+                if age_ms <= stale_after_ms {
+                    continue;
+                }
+                findings.push(AnalysisFinding {
+                    analysis: String::new(),
+                    severity: FindingSeverity::Warning,
+                    title: format!("{} stopped beating", entity.name),
+                    rationale: format!(
+                        "last beat {age_ms}ms ago, expected every {}ms; {} beat(s) so far",
+                        heartbeat.interval_ms, heartbeat.beats
+                    ),
+                    subjects: vec![FindingSubject::new(&process.process_id, &entity.id)],
+                    score: None,
+                    hints: Vec::new(),
+                });
+            }
+        }
+        findings
+    }
+}
+
+// r[impl model.stat-history]
+/// Built-in analysis reporting channels whose depth, and semaphores whose
+/// handed-out permits, rose over their most recent stat history samples
+/// without ever falling: a consumer that cannot keep up or permits that leak.
+/// Needs processes that keep stat history (`MOIRE_STAT_HISTORY`).
+pub struct StatGrowthAnalysis;
+
+impl Analysis for StatGrowthAnalysis {
+    fn name(&self) -> &str {
+        "stat_growth"
+    }
+
+    fn run(
+        &self,
+        _graph: &WaitGraph,
+        snapshot: &SnapshotCutResponse,
+        thresholds: &DetectionThresholds,
+    ) -> Vec<AnalysisFinding> {
+        let mut findings = Vec::new();
+        for process in &snapshot.processes {
+            for entity in &process.snapshot.entities {
+                if entity.removed_at.is_some() {
+                    continue;
+                }
+                let (stat, history, limit) = match &entity.body {
+                    EntityBody::MpscTx(body) => ("queue length", &body.history, body.capacity),
+                    EntityBody::Semaphore(body) => {
+                        ("permits handed out", &body.history, Some(body.max_permits))
+                    }
+                    _ => continue,
+                };
+                let window = thresholds.growth_min_samples.max(2) as usize;
+                if history.len() < window {
+                    continue;
+                }
+                let recent = &history[history.len() - window..];
+                let (Some(first), Some(last)) = (recent.first(), recent.last()) else {
+                    continue;
+                };
+                let never_fell = recent.windows(2).all(|pair| pair[1].value >= pair[0].value);
+                if !never_fell || last.value <= first.value {
+                    continue;
+                }
+                let over_secs = last.at.as_millis().saturating_sub(first.at.as_millis()) / 1_000;
+                let mut rationale = format!(
+                    "{stat} rose from {} to {} over {} samples ({over_secs}s) without falling",
+                    first.value,
+                    last.value,
+                    recent.len()
+                );
+                if let Some(limit) = limit {
+                    rationale.push_str(&format!("; limit {limit}"));
+                }
+                findings.push(AnalysisFinding {
+                    analysis: String::new(),
+                    severity: if limit.is_some_and(|limit| last.value >= limit) {
+                        FindingSeverity::Warning
+                    } else {
+                        FindingSeverity::Info
+                    },
+                    title: format!("{} keeps growing", entity.name),
+                    rationale,
+                    subjects: vec![FindingSubject::new(&process.process_id, &entity.id)],
+                    score: None,
+                    hints: Vec::new(),
+                });
+            }
+        }
+        findings
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use moire_testkit::DumpBuilder;
+    use moire_types::{
+        BacktraceId, BroadcastRxEntity, BroadcastTxEntity, CustomEventKind, Event, FutureEntity,
+        HeartbeatEntity, Json, PTime, StatSample, WorkerLoad,
+    };
+
+    use super::*;
+
+    fn woken(worker: u64) -> impl FnOnce(&mut EntityBody) {
+        move |body| {
+            *body = EntityBody::Future(FutureEntity {
+                wake_to_poll_gap_ms: Some(500),
+                poll_worker: Some(worker),
+                ..FutureEntity::default()
+            });
+        }
+    }
+
+    #[test]
+    fn removed_futures_are_not_lost_wakeups() {
+        let snapshot = DumpBuilder::new()
+            .process("app", |p| {
+                p.future("stuck")
+                    .update("stuck", woken(1))
+                    .future("dropped")
+                    .update("dropped", woken(1))
+                    .removed_at("dropped", 59_000)
+            })
+            .build();
+        let graph = WaitGraph::build(&snapshot).unwrap();
+
+        let findings = LostWakeupAnalysis.run(&graph, &snapshot, &DetectionThresholds::default());
+        assert_eq!(findings.len(), 1);
+        assert_eq!(findings[0].subjects[0].entity_id.as_str(), "stuck");
+        assert!(
+            !findings[0].rationale.contains("other woken future"),
+            "{}",
+            findings[0].rationale
+        );
+    }
+
+    fn heartbeat(last_beat_ms: Option<u64>) -> EntityBody {
+        EntityBody::Heartbeat(HeartbeatEntity {
+            interval_ms: 1_000,
+            beats: if last_beat_ms.is_some() { 12 } else { 0 },
+            last_beat_at: last_beat_ms.map(PTime::from_millis),
+        })
+    }
+
+    #[test]
+    fn heartbeats_quiet_for_several_intervals_are_stale() {
+        let snapshot = DumpBuilder::new()
+            .process("app", |p| {
+                p.entity("stalled", heartbeat(Some(50_000)))
+                    .entity("beating", heartbeat(Some(59_500)))
+                    // Not beaten yet, but created less than three intervals ago.
+                    .entity("starting", heartbeat(None))
+                    .born_at("starting", 58_000)
+                    .entity("finished", heartbeat(Some(10_000)))
+                    .removed_at("finished", 11_000)
+            })
+            .build();
+        let graph = WaitGraph::build(&snapshot).unwrap();
+
+        let findings =
+            StaleHeartbeatAnalysis.run(&graph, &snapshot, &DetectionThresholds::default());
+        assert_eq!(findings.len(), 1);
+        assert_eq!(findings[0].subjects[0].entity_id.as_str(), "stalled");
+        assert_eq!(
+            findings[0].rationale,
+            "last beat 10000ms ago, expected every 1000ms; 12 beat(s) so far"
+        );
+    }
+
+    /// `alpha` holds `a` and is acquiring `b`; `beta` holds `b` and waits on
+    /// `a`. With `closed`, `alpha` already waits on `b` too.
+    fn crossed_locks(closed: bool) -> SnapshotCutResponse {
+        DumpBuilder::new()
+            .process("app", |p| {
+                let p = p
+                    .task("alpha")
+                    .task("beta")
+                    .lock("a")
+                    .lock("b")
+                    .held_by("a", "alpha", 1_000)
+                    .held_by("b", "beta", 1_000)
+                    .waits_on("beta", "a", 2_000)
+                    .polls("alpha", "b");
+                if closed {
+                    p.waits_on("alpha", "b", 3_000)
+                } else {
+                    p
+                }
+            })
+            .build()
+    }
+
+    #[test]
+    fn acquire_that_would_close_a_cycle_is_a_near_cycle() {
+        let snapshot = crossed_locks(false);
+        let graph = WaitGraph::build(&snapshot).unwrap();
+
+        let findings = NearCycleAnalysis.run(&graph, &snapshot, &DetectionThresholds::default());
+        assert_eq!(findings.len(), 1);
+        assert_eq!(
+            findings[0].title,
+            "alpha is one acquire away from a deadlock"
+        );
+        assert_eq!(
+            findings[0]
+                .subjects
+                .iter()
+                .map(|subject| subject.entity_id.as_str())
+                .collect::<Vec<_>>(),
+            ["b", "beta", "a", "alpha"]
+        );
+    }
+
+    #[test]
+    fn closed_cycle_is_left_to_deadlock_detection() {
+        let snapshot = crossed_locks(true);
+        let graph = WaitGraph::build(&snapshot).unwrap();
+        let thresholds = DetectionThresholds::default();
+
+        assert!(
+            NearCycleAnalysis
+                .run(&graph, &snapshot, &thresholds)
+                .is_empty()
+        );
+        assert_eq!(
+            DeadlockAnalysis.run(&graph, &snapshot, &thresholds).len(),
+            1
+        );
+    }
+
+    /// `worker` was cancelled while holding `cache`, which `waiter` waits on.
+    /// Its guard then went to `holder`.
+    fn cancelled_holder(holder: &str) -> SnapshotCutResponse {
+        let mut snapshot = DumpBuilder::new()
+            .process("app", |p| {
+                p.task("worker")
+                    .removed_at("worker", 50_000)
+                    .task("heir")
+                    .task("waiter")
+                    .lock("cache")
+                    .held_by("cache", holder, 40_000)
+                    .waits_on("waiter", "cache", 55_000)
+            })
+            .build();
+        let payload = HolderCancelledPayload {
+            holder_id: EntityId::new("worker"),
+            holder_name: String::from("worker"),
+        };
+        snapshot.processes[0].snapshot.events.push(Event::new(
+            EventTarget::Entity(EntityId::new("cache")),
+            EventKind::Custom(CustomEventKind {
+                kind: String::from(HOLDER_CANCELLED_EVENT),
+                display_name: String::from("Holder Cancelled"),
+                payload: Json::new(facet_json::to_string(&payload).unwrap()),
+            }),
+            BacktraceId::next().unwrap(),
+        ));
+        snapshot
+    }
+
+    #[test]
+    fn waits_on_a_resource_only_its_cancelled_holder_holds_are_reported() {
+        let snapshot = cancelled_holder("worker");
+        let graph = WaitGraph::build(&snapshot).unwrap();
+        assert!(graph.holders["app::cache"][0].removed_ms.is_some());
+
+        let findings =
+            CancelledHolderAnalysis.run(&graph, &snapshot, &DetectionThresholds::default());
+        assert_eq!(findings.len(), 1);
+        assert_eq!(
+            findings[0].title,
+            "cache awaited after its holder was cancelled"
+        );
+        assert_eq!(
+            findings[0]
+                .subjects
+                .iter()
+                .map(|subject| subject.entity_id.as_str())
+                .collect::<Vec<_>>(),
+            ["cache", "worker"]
+        );
+    }
+
+    #[test]
+    fn resources_a_live_future_took_over_are_not_reported() {
+        let snapshot = cancelled_holder("heir");
+        let graph = WaitGraph::build(&snapshot).unwrap();
+
+        let findings =
+            CancelledHolderAnalysis.run(&graph, &snapshot, &DetectionThresholds::default());
+        assert!(findings.is_empty());
+    }
+
+    /// `jobs` is full, with `producer` blocked sending to it; its receiver
+    /// `consumer` waits on `waits_on`.
+    fn full_channel(waits_on: &str) -> SnapshotCutResponse {
+        DumpBuilder::new()
+            .process("app", |p| {
+                p.task("producer")
+                    .task("consumer")
+                    .lock("db")
+                    .channel("jobs", Some(4), 4)
+                    .receiver("jobs", "consumer")
+                    .blocked_sender("jobs", "producer", 50_000)
+                    .waits_on("consumer", waits_on, 45_000)
+            })
+            .build()
+    }
+
+    #[test]
+    fn full_channel_whose_receiver_waits_elsewhere_is_not_draining() {
+        let snapshot = full_channel("db");
+        let graph = WaitGraph::build(&snapshot).unwrap();
+
+        let findings =
+            ReceiverNotDrainingAnalysis.run(&graph, &snapshot, &DetectionThresholds::default());
+        assert_eq!(findings.len(), 1);
+        assert_eq!(findings[0].severity, FindingSeverity::Warning);
+        assert_eq!(findings[0].title, "jobs:rx is not being drained");
+        assert_eq!(
+            findings[0].rationale,
+            "4 of 4 slots queued; its receiver consumer is waiting on db instead of receiving; 1 sender(s) blocked on the full channel"
+        );
+    }
+
+    #[test]
+    fn full_channel_whose_receiver_is_receiving_is_fine() {
+        let snapshot = full_channel("jobs:rx");
+        let graph = WaitGraph::build(&snapshot).unwrap();
+
+        let findings =
+            ReceiverNotDrainingAnalysis.run(&graph, &snapshot, &DetectionThresholds::default());
+        assert!(findings.is_empty());
+    }
+
+    #[test]
+    fn subscribers_that_dropped_or_nearly_fill_the_channel_are_slow() {
+        let subscriber = |lag, dropped| EntityBody::BroadcastRx(BroadcastRxEntity { lag, dropped });
+        let snapshot = DumpBuilder::new()
+            .process("app", |p| {
+                let p = p.entity(
+                    "news",
+                    EntityBody::BroadcastTx(BroadcastTxEntity {
+                        capacity: 16,
+                        sender_count: Some(1),
+                        receiver_count: Some(4),
+                    }),
+                );
+                [
+                    ("lossy", 2, Some(5)),
+                    ("behind", 12, None),
+                    ("keeping_up", 3, None),
+                    ("gone", 16, Some(9)),
+                ]
+                .into_iter()
+                .fold(p, |p, (id, lag, dropped)| {
+                    p.entity(id, subscriber(lag, dropped)).edge(
+                        "news",
+                        id,
+                        EdgeKind::PairedWith,
+                        None,
+                    )
+                })
+                .removed_at("gone", 30_000)
+            })
+            .build();
+        let graph = WaitGraph::build(&snapshot).unwrap();
+
+        let findings =
+            SlowSubscriberAnalysis.run(&graph, &snapshot, &DetectionThresholds::default());
+        let reported = findings
+            .iter()
+            .map(|finding| {
+                (
+                    finding.subjects[0].entity_id.as_str(),
+                    finding.severity,
+                    finding.rationale.as_str(),
+                )
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            reported,
+            [
+                (
+                    "lossy",
+                    FindingSeverity::Warning,
+                    "missed 5 message(s); 2 of 16 slots behind"
+                ),
+                (
+                    "behind",
+                    FindingSeverity::Info,
+                    "missed 0 message(s); 12 of 16 slots behind"
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn tasks_moved_on_most_of_their_polls_are_migrating() {
+        let polled = |poll_count, worker_migrations| {
+            move |body: &mut EntityBody| {
+                *body = EntityBody::Future(FutureEntity {
+                    poll_count: Some(poll_count),
+                    worker_migrations: Some(worker_migrations),
+                    poll_worker: Some(2),
+                    ..FutureEntity::default()
+                });
+            }
+        };
+        let mut snapshot = DumpBuilder::new()
+            .process("app", |p| {
+                p.task("bouncy")
+                    .update("bouncy", polled(100, 80))
+                    .task("settled")
+                    .update("settled", polled(100, 10))
+                    // Too few polls to tell.
+                    .task("young")
+                    .update("young", polled(10, 10))
+            })
+            .build();
+        snapshot.processes[0].snapshot.workers.push(WorkerLoad {
+            worker: 2,
+            thread_name: Some(String::from("tokio-runtime-worker")),
+            task_count: 7,
+            poll_count: 4_000,
+        });
+        let graph = WaitGraph::build(&snapshot).unwrap();
+
+        let findings =
+            TaskMigrationAnalysis.run(&graph, &snapshot, &DetectionThresholds::default());
+        assert_eq!(findings.len(), 1);
+        assert_eq!(findings[0].subjects[0].entity_id.as_str(), "bouncy");
+        assert_eq!(
+            findings[0].rationale,
+            "moved to another thread on 80 of 100 polls (80%); last polled on worker 2 (tokio-runtime-worker, 7 live task(s))"
+        );
+    }
+
+    /// One sample per second, ending at the process's clock.
+    fn history(values: &[u32]) -> Vec<StatSample> {
+        let first_ms = 60_000 - 1_000 * (values.len() as u64 - 1);
+        values
+            .iter()
+            .zip(0..)
+            .map(|(&value, i)| StatSample {
+                at: PTime::from_millis(first_ms + 1_000 * i),
+                value,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn stats_that_only_rise_over_the_window_keep_growing() {
+        let rising = history(&[1, 2, 2, 3, 5, 6, 6, 8, 9, 10]);
+        let dipping = history(&[1, 2, 3, 4, 5, 3, 4, 5, 6, 7]);
+        let short = history(&[1, 2, 3]);
+        let snapshot = DumpBuilder::new()
+            .process("app", |p| {
+                p.channel("queue", Some(10), 10)
+                    .update("queue", |body| {
+                        if let EntityBody::MpscTx(tx) = body {
+                            tx.history = rising;
+                        }
+                    })
+                    .semaphore("permits", 16)
+                    .update("permits", |body| {
+                        if let EntityBody::Semaphore(semaphore) = body {
+                            semaphore.history = dipping;
+                        }
+                    })
+                    .channel("fresh", None, 3)
+                    .update("fresh", |body| {
+                        if let EntityBody::MpscTx(tx) = body {
+                            tx.history = short;
+                        }
+                    })
+            })
+            .build();
+        let graph = WaitGraph::build(&snapshot).unwrap();
+
+        let findings = StatGrowthAnalysis.run(&graph, &snapshot, &DetectionThresholds::default());
+        assert_eq!(findings.len(), 1);
+        assert_eq!(findings[0].subjects[0].entity_id.as_str(), "queue");
+        assert_eq!(findings[0].severity, FindingSeverity::Warning);
+        assert_eq!(
+            findings[0].rationale,
+            "queue length rose from 1 to 10 over 10 samples (9s) without falling; limit 10"
+        );
+    }
+}
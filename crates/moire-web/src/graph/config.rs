@@ -0,0 +1,338 @@
+//! Detection configuration, reloadable without restarting the collector.
+//!
+//! `MOIRE_DETECTION_CONFIG` names a JSON file holding detector thresholds,
+//! finding suppressions and analyses to switch off. The collector re-reads it
+//! when the file changes and on `SIGHUP`; a file that fails to parse, has a
+//! key it does not know, or names an analysis the collector does not have,
+//! leaves the previous configuration in place. Every snapshot carries the version of the
+//! configuration its findings were computed with, so a change in what gets
+//! reported can be traced back to a config push.
+//!
+//! ```json
+//! {
+//!   "version": "2026-10-16.1",
+//!   "thresholds": { "starvation_min_wait_ms": 5000 },
+//!   "suppress": ["deadlock:p1::e3,p1::e7"],
+//!   "disabled_analyses": ["task_migration"]
+//! }
+//! ```
+
+// r[impl config.web.detection]
+
+use std::collections::BTreeSet;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+use facet::Facet;
+use tracing::{info, warn};
+
+use super::analysis::AnalysisRegistry;
+use crate::app::AppState;
+
+/// How often the config file's modification time is checked.
+const RELOAD_POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Declares [`DetectionThresholds`], its built-in values and the file form in
+/// which every threshold is optional, from one list so they cannot drift apart.
+macro_rules! detection_thresholds {
+    ($($(#[$meta:meta])* $field:ident = $default:expr,)*) => {
+        /// Thresholds of the built-in detectors.
+        #[derive(Clone, Debug, PartialEq, Eq)]
+        pub struct DetectionThresholds {
+            $($(#[$meta])* pub $field: u64,)*
+        }
+
+        impl Default for DetectionThresholds {
+            fn default() -> Self {
+                Self {
+                    $($field: $default,)*
+                }
+            }
+        }
+
+        /// Thresholds left out keep their built-in value.
+        #[derive(Facet, Default)]
+        #[facet(deny_unknown_fields)]
+        struct DetectionThresholdsFile {
+            $(
+                #[facet(skip_unless_truthy)]
+                $field: Option<u64>,
+            )*
+        }
+
+        impl DetectionThresholdsFile {
+            fn resolve(self) -> DetectionThresholds {
+                let defaults = DetectionThresholds::default();
+                DetectionThresholds {
+                    $($field: self.$field.unwrap_or(defaults.$field),)*
+                }
+            }
+        }
+    };
+}
+
+detection_thresholds! {
+    /// Unproductive wakes a future needs before it counts as spinning.
+    livelock_min_unproductive_wakes = 256,
+    /// Unproductive wakes per second, over the future's lifetime, that count
+    /// as spinning.
+    livelock_min_wakes_per_sec = 50,
+    /// Completed holds a resource needs before its average hold time is
+    /// trusted.
+    starvation_min_holds = 8,
+    /// How many average hold times a waiter must have waited to count as
+    /// starving.
+    starvation_wait_to_hold_ratio = 20,
+    /// Waits shorter than this are never reported as starvation, however short
+    /// the holds are.
+    starvation_min_wait_ms = 1_000,
+    /// Futures polled fewer times than this are too young to judge for
+    /// migration.
+    task_migration_min_polls = 64,
+    /// Share of polls, in percent, that must land on a different thread than
+    /// the previous one before a future counts as bouncing between workers.
+    task_migration_min_percent = 50,
+    /// Backlog, in percent of channel capacity, at which a broadcast receiver
+    /// is about to start missing messages.
+    slow_subscriber_lag_percent = 75,
+    /// Intervals a heartbeat may miss before its loop counts as stalled.
+    heartbeat_stale_intervals = 3,
+    /// Tasks that must be blocked behind a resource, directly or through
+    /// other waits, before it is reported as a bottleneck.
+    bottleneck_min_blocked_tasks = 3,
+    /// Consecutive stat history samples a channel's depth or a semaphore's
+    /// handed-out permits must have risen over, without ever falling, to
+    /// count as growing.
+    growth_min_samples = 10,
+}
+
+/// What the analyses run with. The default is the built-in behavior.
+#[derive(Clone, Debug, Default)]
+pub struct DetectionConfig {
+    /// `None` when no config file is in use. Otherwise the file's `version`,
+    /// or a hash of its contents when it does not set one.
+    pub version: Option<String>,
+    pub thresholds: DetectionThresholds,
+    /// Finding fingerprints (see `AnalysisFinding::fingerprint`) to drop.
+    pub suppressed: BTreeSet<String>,
+    /// Names of registered analyses to skip.
+    pub disabled_analyses: BTreeSet<String>,
+}
+
+#[derive(Facet, Default)]
+#[facet(deny_unknown_fields)]
+struct DetectionConfigFile {
+    #[facet(skip_unless_truthy)]
+    version: Option<String>,
+    #[facet(default)]
+    thresholds: DetectionThresholdsFile,
+    #[facet(default)]
+    suppress: Vec<String>,
+    #[facet(default)]
+    disabled_analyses: Vec<String>,
+}
+
+impl DetectionConfig {
+    /// Reads the file named by `MOIRE_DETECTION_CONFIG`. Returns `None` when
+    /// the variable is unset or empty.
+    pub fn from_env(analyses: &AnalysisRegistry) -> Result<Option<(PathBuf, Self)>, String> {
+        let Some(path) = std::env::var_os("MOIRE_DETECTION_CONFIG")
+            .filter(|path| !path.is_empty())
+            .map(PathBuf::from)
+        else {
+            return Ok(None);
+        };
+        let config = Self::load(&path, analyses)?;
+        Ok(Some((path, config)))
+    }
+
+    pub fn load(path: &Path, analyses: &AnalysisRegistry) -> Result<Self, String> {
+        let json = std::fs::read_to_string(path)
+            .map_err(|e| format!("read detection config {}: {e}", path.display()))?;
+        Self::parse(&json, analyses).map_err(|e| format!("{}: {e}", path.display()))
+    }
+
+    /// Parses a config file. Every name in `disabled_analyses` and every
+    /// `suppress` fingerprint must belong to an analysis of `analyses`, so a
+    /// typo fails loudly instead of silently disabling or suppressing nothing.
+    pub fn parse(json: &str, analyses: &AnalysisRegistry) -> Result<Self, String> {
+        let file = facet_json::from_str::<DetectionConfigFile>(json)
+            .map_err(|e| format!("decode detection config: {e}"))?;
+        let config = Self {
+            version: Some(file.version.unwrap_or_else(|| content_version(json))),
+            thresholds: file.thresholds.resolve(),
+            suppressed: file.suppress.into_iter().collect(),
+            disabled_analyses: file.disabled_analyses.into_iter().collect(),
+        };
+        config.check_names(analyses)?;
+        Ok(config)
+    }
+
+    fn check_names(&self, analyses: &AnalysisRegistry) -> Result<(), String> {
+        let known = analyses.names().collect::<BTreeSet<_>>();
+        let unknown = |name: &str| {
+            format!(
+                "unknown analysis {name:?} (registered: {})",
+                known.iter().copied().collect::<Vec<_>>().join(", ")
+            )
+        };
+        for name in &self.disabled_analyses {
+            if !known.contains(name.as_str()) {
+                return Err(format!("disabled_analyses: {}", unknown(name)));
+            }
+        }
+        for fingerprint in &self.suppressed {
+            let Some((name, subjects)) = fingerprint.split_once(':') else {
+                return Err(format!(
+                    "suppress: {fingerprint:?} is not a finding fingerprint (analysis:process::entity,...)"
+                ));
+            };
+            if !known.contains(name) {
+                return Err(format!("suppress: {}", unknown(name)));
+            }
+            if subjects.is_empty() {
+                continue;
+            }
+            let subjects = subjects.split(',').collect::<Vec<_>>();
+            let is_node_key = |subject: &&str| {
+                subject
+                    .split_once("::")
+                    .is_some_and(|(process, entity)| !process.is_empty() && !entity.is_empty())
+            };
+            if !subjects.iter().all(is_node_key) {
+                return Err(format!(
+                    "suppress: {fingerprint:?} has a subject that is not a process_id::entity_id key"
+                ));
+            }
+            if !subjects.is_sorted() {
+                return Err(format!(
+                    "suppress: {fingerprint:?} lists its subjects out of order and can never match"
+                ));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// FNV-1a of the file contents, so an unversioned file still gets an id that
+/// changes with every edit and nothing else.
+fn content_version(json: &str) -> String {
+    let hash = json.bytes().fold(0xcbf2_9ce4_8422_2325_u64, |hash, byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(0x0000_0100_0000_01b3)
+    });
+    format!("fnv1a:{hash:016x}")
+}
+
+/// Re-reads `path` whenever its modification time changes and, on Unix, on
+/// `SIGHUP`. Runs for the lifetime of the server.
+pub fn spawn_detection_config_reloader(state: AppState, path: PathBuf) {
+    info!(path = %path.display(), "detection config reloader started");
+
+    #[cfg(unix)]
+    {
+        let state = state.clone();
+        let path = path.clone();
+        tokio::spawn(async move {
+            use tokio::signal::unix::{SignalKind, signal};
+            let mut hangups = match signal(SignalKind::hangup()) {
+                Ok(hangups) => hangups,
+                Err(e) => {
+                    warn!(%e, "cannot listen for SIGHUP; detection config reloads on change only");
+                    return;
+                }
+            };
+            while hangups.recv().await.is_some() {
+                reload(&state, &path).await;
+            }
+        });
+    }
+
+    tokio::spawn(async move {
+        let mut last_modified = modified_at(&path);
+        loop {
+            tokio::time::sleep(RELOAD_POLL_INTERVAL).await;
+            let modified = modified_at(&path);
+            if modified == last_modified {
+                continue;
+            }
+            last_modified = modified;
+            reload(&state, &path).await;
+        }
+    });
+}
+
+fn modified_at(path: &Path) -> Option<SystemTime> {
+    std::fs::metadata(path).and_then(|m| m.modified()).ok()
+}
+
+async fn reload(state: &AppState, path: &Path) {
+    let path_for_load = path.to_path_buf();
+    let analyses = state.analyses.clone();
+    match tokio::task::spawn_blocking(move || DetectionConfig::load(&path_for_load, &analyses))
+        .await
+    {
+        Ok(Ok(config)) => {
+            let previous = state.detection_config().version.clone();
+            info!(
+                previous = previous.as_deref().unwrap_or("built-in"),
+                version = config.version.as_deref().unwrap_or("built-in"),
+                "detection config reloaded"
+            );
+            state.set_detection_config(config);
+        }
+        Ok(Err(e)) => warn!(%e, "keeping previous detection config"),
+        Err(e) => warn!(%e, "detection config reload join error"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn partial_config_keeps_defaults_and_gets_a_content_version() {
+        let json = r#"{"thresholds":{"starvation_min_wait_ms":5000},"disabled_analyses":["task_migration"]}"#;
+        let analyses = AnalysisRegistry::default();
+        let config = DetectionConfig::parse(json, &analyses).expect("valid config");
+        assert_eq!(config.thresholds.starvation_min_wait_ms, 5_000);
+        assert_eq!(
+            config.thresholds.livelock_min_wakes_per_sec,
+            DetectionThresholds::default().livelock_min_wakes_per_sec
+        );
+        assert!(config.disabled_analyses.contains("task_migration"));
+
+        let version = config.version.expect("content version");
+        let edited =
+            DetectionConfig::parse(&json.replace("5000", "6000"), &analyses).expect("valid config");
+        assert_ne!(edited.version, Some(version.clone()));
+        assert_eq!(
+            DetectionConfig::parse(json, &analyses).unwrap().version,
+            Some(version)
+        );
+    }
+
+    #[test]
+    fn names_that_match_no_analysis_are_rejected() {
+        let analyses = AnalysisRegistry::default();
+        let parse = |json: &str| DetectionConfig::parse(json, &analyses);
+
+        assert!(parse(r#"{"disabled_analyses":["task_migraton"]}"#).is_err());
+        assert!(parse(r#"{"suppress":["deadlok:p1::e3,p1::e7"]}"#).is_err());
+        assert!(parse(r#"{"suppress":["deadlock"]}"#).is_err());
+        assert!(parse(r#"{"suppress":["deadlock:p1:e3"]}"#).is_err());
+        assert!(parse(r#"{"suppress":["deadlock:p1::e7,p1::e3"]}"#).is_err());
+
+        let config = parse(r#"{"suppress":["deadlock:p1::e3,p1::e7"]}"#).expect("valid config");
+        assert!(config.suppressed.contains("deadlock:p1::e3,p1::e7"));
+    }
+
+    #[test]
+    fn misspelled_keys_are_rejected() {
+        let analyses = AnalysisRegistry::default();
+        let parse = |json: &str| DetectionConfig::parse(json, &analyses);
+
+        assert!(parse(r#"{"thresholds":{"starvation_min_wait":5000}}"#).is_err());
+        assert!(parse(r#"{"threshold":{"starvation_min_wait_ms":5000}}"#).is_err());
+        assert!(parse(r#"{"thresholds":{"starvation_min_wait_ms":5000}}"#).is_ok());
+    }
+}
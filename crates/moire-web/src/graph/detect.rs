@@ -0,0 +1,1092 @@
+//! Deadlock, livelock and starvation candidate detection over a [`WaitGraph`].
+//!
+//! Deadlock detection is anytime: given a deadline it returns whatever it found so
+//! far and says so through [`DeadlockScan::complete`]. Components reachable from
+//! the most severe wait edges are explored first, so a truncated scan still
+//! carries the candidates most worth looking at.
+//!
+//! A cycle may close through a resource's holder: two tasks or threads that
+//! each hold a lock the other waits for only wait on the locks, so deadlock
+//! detection also steps from a resource to its holders.
+//!
+//! A snapshot can land just before the last edge of a deadlock forms, while
+//! one task is still acquiring the lock that closes it. Such near-cycles are
+//! found from acquires in progress and the holders of every resource.
+//!
+//! Livelocks do not show up as wait cycles: the tasks involved keep waking each
+//! other, so each one only waits on its own wake source. They are found from the
+//! wake counters futures report instead.
+//!
+//! Starvation is a lock or semaphore that keeps changing hands while some
+//! waiter never gets it. It is found by comparing how long each waiter has been
+//! waiting against the resource's average hold time.
+//!
+//! Bottlenecks are resources with many tasks stuck behind them, directly or
+//! through other waits: hot locks and full channels. They are ranked by how
+//! many tasks they block, which deadlock candidates report for cycles too.
+//!
+//! Output is deterministic: candidates are ordered by descending score, then by
+//! their sorted node keys, and nothing depends on map iteration order. A
+//! complete deadlock scan of the same graph always yields the same list.
+
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::time::Instant;
+
+use moire_graph_core::{has_external_wake_source, wait_age_bonus};
+use moire_types::{EdgeReason, ScoreBreakdown};
+
+use super::config::DetectionThresholds;
+use super::severity::SeverityPolicy;
+use super::{WaitGraph, WaitNode, strongly_connected_components};
+
+pub(crate) struct DeadlockCandidate {
+    /// Node keys (`process_id::entity_id`) forming the strongly connected component.
+    pub(crate) node_keys: Vec<String>,
+    pub(crate) confidence: &'static str,
+    pub(crate) reasons: Vec<&'static str>,
+    /// Score of the most severe wait edge inside the component.
+    pub(crate) score: ScoreBreakdown,
+    pub(crate) blocked_duration_hint_ms: Option<u64>,
+    /// Tasks in the cycle or waiting on it, directly or transitively.
+    pub(crate) blocked_task_count: usize,
+    /// Suggested next steps, from [`deadlock_hints`].
+    pub(crate) hints: Vec<String>,
+}
+
+pub(crate) struct LivelockCandidate {
+    /// The two future node keys waking each other, sorted.
+    pub(crate) node_keys: Vec<String>,
+    pub(crate) reasons: Vec<&'static str>,
+    pub(crate) score: ScoreBreakdown,
+    /// Lower of the two unproductive wake rates.
+    pub(crate) wakes_per_sec: u64,
+}
+
+pub(crate) struct StarvationCandidate {
+    /// Node key of the lock or semaphore.
+    pub(crate) resource_key: String,
+    /// Waiters past the starvation threshold, sorted.
+    pub(crate) waiter_keys: Vec<String>,
+    pub(crate) reasons: Vec<&'static str>,
+    pub(crate) score: ScoreBreakdown,
+    pub(crate) longest_wait_ms: u64,
+    pub(crate) avg_hold_ms: u64,
+    pub(crate) hold_count: u64,
+}
+
+pub(crate) struct BottleneckCandidate {
+    /// Node key of the resource.
+    pub(crate) resource_key: String,
+    /// Tasks waiting on the resource directly.
+    pub(crate) direct_waiters: usize,
+    /// Tasks waiting on the resource directly or transitively.
+    pub(crate) blocked_task_count: usize,
+    pub(crate) score: ScoreBreakdown,
+}
+
+pub(crate) struct NearCycleCandidate {
+    /// Node keys from the resource being acquired, through its holder and
+    /// whatever that holder waits on, to the task acquiring it.
+    pub(crate) path: Vec<String>,
+    pub(crate) score: ScoreBreakdown,
+}
+
+impl DeadlockCandidate {
+    pub(crate) fn severity(&self) -> u32 {
+        self.score.total()
+    }
+}
+
+impl LivelockCandidate {
+    pub(crate) fn severity(&self) -> u32 {
+        self.score.total()
+    }
+}
+
+impl StarvationCandidate {
+    pub(crate) fn severity(&self) -> u32 {
+        self.score.total()
+    }
+}
+
+impl NearCycleCandidate {
+    pub(crate) fn severity(&self) -> u32 {
+        self.score.total()
+    }
+}
+
+fn node_is_task(node: &WaitNode) -> bool {
+    matches!(node.kind.as_str(), "future" | "actor" | "aether")
+}
+
+/// Who waits on each node: the wait graph with its edges reversed.
+fn waiters_by_node(graph: &WaitGraph) -> HashMap<&str, Vec<&str>> {
+    let mut waiters: HashMap<&str, Vec<&str>> = HashMap::new();
+    for (src, dsts) in &graph.adjacency {
+        for dst in dsts {
+            waiters.entry(dst.as_str()).or_default().push(src.as_str());
+        }
+    }
+    waiters
+}
+
+/// Tasks among `roots` and everything waiting on them, directly or
+/// transitively.
+fn count_blocked_tasks<'g>(
+    graph: &'g WaitGraph,
+    waiters: &HashMap<&'g str, Vec<&'g str>>,
+    roots: impl IntoIterator<Item = &'g str>,
+) -> usize {
+    let mut seen = HashSet::new();
+    let mut queue = VecDeque::new();
+    for root in roots {
+        if seen.insert(root) {
+            queue.push_back(root);
+        }
+    }
+    let mut tasks = 0;
+    while let Some(key) = queue.pop_front() {
+        if graph.nodes.get(key).is_some_and(node_is_task) {
+            tasks += 1;
+        }
+        for &waiter in waiters.get(key).into_iter().flatten() {
+            if seen.insert(waiter) {
+                queue.push_back(waiter);
+            }
+        }
+    }
+    tasks
+}
+
+/// Resources with at least `bottleneck_min_blocked_tasks` tasks behind them,
+/// most blocked first.
+pub(crate) fn find_bottleneck_candidates(
+    graph: &WaitGraph,
+    thresholds: &DetectionThresholds,
+) -> Vec<BottleneckCandidate> {
+    let waiters = waiters_by_node(graph);
+    let mut candidates = Vec::new();
+    for (&key, direct) in &waiters {
+        let Some(resource) = graph.nodes.get(key) else {
+            continue;
+        };
+        if node_is_task(resource) {
+            continue;
+        }
+        let blocked_task_count = count_blocked_tasks(graph, &waiters, [key]);
+        if (blocked_task_count as u64) < thresholds.bottleneck_min_blocked_tasks {
+            continue;
+        }
+        let direct_waiters = direct
+            .iter()
+            .filter(|waiter| graph.nodes.get(**waiter).is_some_and(node_is_task))
+            .count();
+        let mut score = ScoreBreakdown::default();
+        score.add(
+            "blocked_tasks",
+            match blocked_task_count {
+                0..10 => 1,
+                10..100 => 2,
+                _ => 3,
+            },
+        );
+        if blocked_task_count > direct_waiters {
+            score.add("transitive_waiters", 1);
+        }
+        candidates.push(BottleneckCandidate {
+            resource_key: String::from(key),
+            direct_waiters,
+            blocked_task_count,
+            score,
+        });
+    }
+
+    candidates.sort_by(|a, b| {
+        b.blocked_task_count
+            .cmp(&a.blocked_task_count)
+            .then_with(|| a.resource_key.cmp(&b.resource_key))
+    });
+    candidates
+}
+
+pub(crate) struct DeadlockScan {
+    /// Candidates ordered by descending severity.
+    pub(crate) candidates: Vec<DeadlockCandidate>,
+    /// `false` when the deadline expired before every node was examined.
+    pub(crate) complete: bool,
+}
+
+pub(crate) fn age_bonus(node: &WaitNode) -> u32 {
+    wait_age_bonus(node.ptime_now_ms.saturating_sub(node.birth_ms))
+}
+
+/// Unproductive wake rate of `node`, if it is high enough to look like spinning.
+fn spinning_wake_rate(node: &WaitNode, thresholds: &DetectionThresholds) -> Option<u64> {
+    let wakes = node.wakes.unproductive_wake_count;
+    if wakes < thresholds.livelock_min_unproductive_wakes {
+        return None;
+    }
+    let age_ms = node.ptime_now_ms.saturating_sub(node.birth_ms).max(1);
+    let rate = wakes.saturating_mul(1_000) / age_ms;
+    (rate >= thresholds.livelock_min_wakes_per_sec).then_some(rate)
+}
+
+/// Pairs of pending futures that were last woken by each other and keep waking
+/// without anything under them completing.
+pub(crate) fn find_livelock_candidates(
+    graph: &WaitGraph,
+    thresholds: &DetectionThresholds,
+) -> Vec<LivelockCandidate> {
+    let mut candidates = Vec::new();
+    for (key, node) in &graph.nodes {
+        let Some(peer_key) = node.wakes.last_woken_by.as_deref() else {
+            continue;
+        };
+        // Each pair is visited once, from its lower key.
+        if peer_key <= key.as_str() {
+            continue;
+        }
+        let Some(peer) = graph.nodes.get(peer_key) else {
+            continue;
+        };
+        if peer.wakes.last_woken_by.as_deref() != Some(key.as_str()) {
+            continue;
+        }
+        if !graph.adjacency.contains_key(key) || !graph.adjacency.contains_key(peer_key) {
+            continue;
+        }
+        let (Some(rate), Some(peer_rate)) = (
+            spinning_wake_rate(node, thresholds),
+            spinning_wake_rate(peer, thresholds),
+        ) else {
+            continue;
+        };
+
+        let mut score = ScoreBreakdown::default();
+        score.add("mutual_wake_loop", 2);
+        score.add("wait_age", age_bonus(node).min(age_bonus(peer)));
+        candidates.push(LivelockCandidate {
+            node_keys: vec![key.clone(), String::from(peer_key)],
+            reasons: vec!["mutual_wake_loop", "both_pending", "wakes_without_progress"],
+            score,
+            wakes_per_sec: rate.min(peer_rate),
+        });
+    }
+
+    candidates.sort_by(|a, b| {
+        b.severity()
+            .cmp(&a.severity())
+            .then_with(|| a.node_keys.cmp(&b.node_keys))
+    });
+    candidates
+}
+
+/// Locks and semaphores with waiters that have waited far longer than the
+/// resource is usually held.
+///
+/// Needs edge timestamps and hold statistics; recordings without them yield no
+/// candidates.
+pub(crate) fn find_starvation_candidates(
+    graph: &WaitGraph,
+    thresholds: &DetectionThresholds,
+) -> Vec<StarvationCandidate> {
+    let mut starving: HashMap<&str, Vec<(&str, u64, u64)>> = HashMap::new();
+    for edge in &graph.edges {
+        let Some(since_ms) = edge.since_ms else {
+            continue;
+        };
+        let Some(resource) = graph.nodes.get(&edge.dst_key) else {
+            continue;
+        };
+        let holds = &resource.holds;
+        if holds.hold_count < thresholds.starvation_min_holds {
+            continue;
+        }
+        let wait_ms = resource.ptime_now_ms.saturating_sub(since_ms);
+        let threshold = holds
+            .avg_hold_ms
+            .saturating_mul(thresholds.starvation_wait_to_hold_ratio)
+            .max(thresholds.starvation_min_wait_ms);
+        if wait_ms < threshold {
+            continue;
+        }
+        starving.entry(edge.dst_key.as_str()).or_default().push((
+            edge.src_key.as_str(),
+            since_ms,
+            wait_ms,
+        ));
+    }
+
+    let mut candidates = Vec::new();
+    for (resource_key, waiters) in starving {
+        let Some(resource) = graph.nodes.get(resource_key) else {
+            continue;
+        };
+        let holds = &resource.holds;
+        let longest_wait_ms = waiters
+            .iter()
+            .map(|(_, _, wait_ms)| *wait_ms)
+            .max()
+            .unwrap_or(0);
+        let mut reasons = vec!["wait_exceeds_avg_hold"];
+        // Someone acquired the resource after a starving waiter had queued up.
+        let overtaken = holds.latest_acquired_ms.is_some_and(|acquired_ms| {
+            waiters
+                .iter()
+                .any(|(_, since_ms, _)| acquired_ms > *since_ms)
+        });
+        if overtaken {
+            reasons.push("overtaken_by_later_holder");
+        }
+        // Readers keep the lock shared while a writer is queued behind them.
+        if holds.waiting_writers > 0 && holds.readers > 0 {
+            reasons.push("writer_behind_readers");
+        }
+        let mut score = ScoreBreakdown::default();
+        score.add(
+            "wait_to_hold_ratio",
+            match longest_wait_ms / holds.avg_hold_ms.max(1) {
+                0..100 => 1,
+                100..1_000 => 2,
+                _ => 3,
+            },
+        );
+        if overtaken {
+            score.add("overtaken_by_later_holder", 1);
+        }
+
+        let mut waiter_keys = waiters
+            .into_iter()
+            .map(|(key, _, _)| String::from(key))
+            .collect::<Vec<_>>();
+        waiter_keys.sort();
+        candidates.push(StarvationCandidate {
+            resource_key: String::from(resource_key),
+            waiter_keys,
+            reasons,
+            score,
+            longest_wait_ms,
+            avg_hold_ms: holds.avg_hold_ms,
+            hold_count: holds.hold_count,
+        });
+    }
+
+    candidates.sort_by(|a, b| {
+        b.severity()
+            .cmp(&a.severity())
+            .then_with(|| b.longest_wait_ms.cmp(&a.longest_wait_ms))
+            .then_with(|| a.resource_key.cmp(&b.resource_key))
+    });
+    candidates
+}
+
+/// The `waiting_on` adjacency plus a hop from each resource to those of its
+/// holders that are waiting on something themselves. Two tasks or threads
+/// that each hold a lock the other waits for never wait on each other
+/// directly; this is what closes their cycle.
+pub(crate) fn adjacency_with_holds(graph: &WaitGraph) -> HashMap<String, Vec<String>> {
+    let mut adjacency = graph.adjacency.clone();
+    for (resource, holders) in &graph.holders {
+        let waiting_holders = holders
+            .iter()
+            .map(|holder| holder.key())
+            .filter(|key| graph.adjacency.contains_key(key))
+            .collect::<Vec<_>>();
+        if waiting_holders.is_empty() {
+            continue;
+        }
+        let outs = adjacency.entry(resource.clone()).or_default();
+        outs.extend(waiting_holders);
+        outs.sort();
+        outs.dedup();
+    }
+    adjacency
+}
+
+/// Wait paths one wait short of a cycle: a task acquiring a resource (a
+/// `polls` edge to it, not yet `waiting_on`) while the resource's holder
+/// waits, directly or through other holders, on something the acquiring task
+/// holds. A snapshot taken just before the last acquire blocks shows no cycle
+/// yet, only this.
+///
+/// `holders` maps every held resource to its holders' keys, whether or not
+/// anyone waits on it yet; `acquiring` lists `(task key, resource key)` for
+/// each acquire in progress. Paths span at least three nodes, so a task
+/// re-acquiring what it holds is not reported. Only the shortest path per
+/// acquire is kept.
+pub(crate) fn find_near_cycle_candidates(
+    graph: &WaitGraph,
+    holders: &HashMap<String, Vec<String>>,
+    acquiring: &[(String, String)],
+) -> Vec<NearCycleCandidate> {
+    let mut acquiring = acquiring.iter().collect::<Vec<_>>();
+    acquiring.sort();
+    acquiring.dedup();
+
+    let mut candidates = Vec::new();
+    for (task, resource) in acquiring {
+        let already_waiting = graph
+            .adjacency
+            .get(task)
+            .is_some_and(|outs| outs.contains(resource));
+        if already_waiting {
+            continue;
+        }
+
+        // Breadth-first from the resource, stepping from a resource to its
+        // holders and from a waiter to what it waits on.
+        let mut parent: HashMap<&str, &str> = HashMap::new();
+        let mut queue = VecDeque::from([resource.as_str()]);
+        let mut found = false;
+        while let Some(key) = queue.pop_front() {
+            if key == task.as_str() {
+                found = true;
+                break;
+            }
+            let mut next = holders
+                .get(key)
+                .into_iter()
+                .flatten()
+                .chain(graph.adjacency.get(key).into_iter().flatten())
+                .map(String::as_str)
+                .collect::<Vec<_>>();
+            next.sort();
+            for next in next {
+                if next != resource.as_str() && !parent.contains_key(next) {
+                    parent.insert(next, key);
+                    queue.push_back(next);
+                }
+            }
+        }
+        if !found {
+            continue;
+        }
+
+        let mut path = vec![task.clone()];
+        let mut key = task.as_str();
+        while let Some(&prev) = parent.get(key) {
+            path.push(String::from(prev));
+            key = prev;
+        }
+        path.reverse();
+        if path.len() < 3 {
+            continue;
+        }
+
+        let mut score = ScoreBreakdown::default();
+        score.add("one_acquire_from_cycle", 2);
+        score.add(
+            "wait_age",
+            path.iter()
+                .filter(|key| graph.adjacency.contains_key(*key))
+                .filter_map(|key| graph.nodes.get(key))
+                .map(age_bonus)
+                .max()
+                .unwrap_or(0),
+        );
+        candidates.push(NearCycleCandidate { path, score });
+    }
+
+    candidates.sort_by(|a, b| {
+        b.severity()
+            .cmp(&a.severity())
+            .then_with(|| a.path.cmp(&b.path))
+    });
+    candidates
+}
+
+pub(crate) fn find_deadlock_candidates(
+    graph: &WaitGraph,
+    deadline: Option<Instant>,
+) -> DeadlockScan {
+    find_deadlock_candidates_with_policy(graph, graph.severity_policy.as_ref(), deadline)
+}
+
+/// Like [`find_deadlock_candidates`], scoring wait edges with `policy` rather
+/// than the policy `graph` was built with.
+// r[impl api.snapshot.findings-order]
+pub(crate) fn find_deadlock_candidates_with_policy(
+    graph: &WaitGraph,
+    policy: &dyn SeverityPolicy,
+    deadline: Option<Instant>,
+) -> DeadlockScan {
+    let edge_weight = |src: &str, dst: &str| -> u32 {
+        match (graph.nodes.get(src), graph.nodes.get(dst)) {
+            (Some(src), Some(dst)) => policy.edge_score(src, dst).total(),
+            _ => 0,
+        }
+    };
+
+    let adjacency = adjacency_with_holds(graph);
+    let mut node_priority: HashMap<&str, u32> = HashMap::new();
+    for (src, outs) in &adjacency {
+        let max = outs
+            .iter()
+            .map(|dst| edge_weight(src, dst))
+            .max()
+            .unwrap_or(0);
+        node_priority.insert(src.as_str(), max);
+    }
+
+    let mut roots = adjacency.keys().cloned().collect::<Vec<_>>();
+    roots.sort_by(|a, b| {
+        let pa = node_priority.get(a.as_str()).copied().unwrap_or(0);
+        let pb = node_priority.get(b.as_str()).copied().unwrap_or(0);
+        pb.cmp(&pa).then_with(|| a.cmp(b))
+    });
+
+    let (sccs, mut complete) = strongly_connected_components(roots, &adjacency, deadline);
+    let waiters = waiters_by_node(graph);
+
+    let mut candidates = Vec::new();
+    for scc in sccs {
+        if deadline.is_some_and(|deadline| Instant::now() >= deadline) {
+            complete = false;
+            break;
+        }
+        if scc.len() <= 1 {
+            let Some(node_id) = scc.first() else {
+                continue;
+            };
+            let self_loop = adjacency
+                .get(node_id)
+                .is_some_and(|outs| outs.iter().any(|dst| dst == node_id));
+            if !self_loop {
+                continue;
+            }
+        }
+
+        let mut reasons = vec!["strongly_connected_wait_cycle"];
+        let has_external_wake_source = scc
+            .iter()
+            .filter_map(|id| graph.nodes.get(id))
+            .any(|node| has_external_wake_source(node.kind.as_str()));
+        if !has_external_wake_source {
+            reasons.push("no_obvious_external_wake_source");
+        }
+        let confidence = if has_external_wake_source {
+            "medium"
+        } else {
+            "high"
+        };
+
+        let mut node_keys = scc;
+        node_keys.sort();
+        let closed_by_hold = node_keys.iter().any(|key| {
+            graph.holders.get(key).into_iter().flatten().any(|holder| {
+                let holder_key = holder.key();
+                node_keys.binary_search(&holder_key).is_ok()
+            })
+        });
+        if closed_by_hold {
+            reasons.push("resource_held_by_waiter");
+        }
+
+        // Edges are visited in key order so that, among equally scored edges,
+        // the same one is picked whatever order the component came out in.
+        let mut score = ScoreBreakdown::default();
+        for src in &node_keys {
+            let mut outs = adjacency
+                .get(src)
+                .into_iter()
+                .flatten()
+                .filter(|dst| node_keys.binary_search(dst).is_ok())
+                .collect::<Vec<_>>();
+            outs.sort();
+            for dst in outs {
+                let (Some(src), Some(dst)) = (graph.nodes.get(src), graph.nodes.get(dst)) else {
+                    continue;
+                };
+                let edge = policy.edge_score(src, dst);
+                if edge.total() > score.total() {
+                    score = edge;
+                }
+            }
+        }
+
+        let blocked_duration_hint_ms = node_keys
+            .iter()
+            .filter_map(|key| graph.nodes.get(key))
+            .map(|node| node.ptime_now_ms.saturating_sub(node.birth_ms))
+            .min();
+        let blocked_task_count =
+            count_blocked_tasks(graph, &waiters, node_keys.iter().map(String::as_str));
+
+        let hints = deadlock_hints(graph, &node_keys);
+        candidates.push(DeadlockCandidate {
+            node_keys,
+            confidence,
+            reasons,
+            score,
+            blocked_duration_hint_ms,
+            blocked_task_count,
+            hints,
+        });
+    }
+
+    candidates.sort_by(|a, b| {
+        b.severity()
+            .cmp(&a.severity())
+            .then_with(|| a.node_keys.cmp(&b.node_keys))
+    });
+
+    DeadlockScan {
+        candidates,
+        complete,
+    }
+}
+
+/// What to try next for the cycle through `node_keys`, sorted, one line per
+/// pattern spotted:
+///
+/// - a task in the cycle holds a lock while it waits on something else: the
+///   guard lives across an await;
+/// - the cycle takes more than one lock: the locks are taken in inconsistent
+///   orders;
+/// - a send in the cycle is blocked on a full bounded channel;
+/// - a wait in the cycle is for an RPC response.
+pub(crate) fn deadlock_hints(graph: &WaitGraph, node_keys: &[String]) -> Vec<String> {
+    let name = |key: &str| {
+        graph
+            .nodes
+            .get(key)
+            .map_or_else(|| key.to_owned(), |node| node.name.clone())
+    };
+    let in_cycle = |key: &str| node_keys.binary_search_by(|k| k.as_str().cmp(key)).is_ok();
+    let mut edges = graph
+        .edges
+        .iter()
+        .filter(|edge| in_cycle(&edge.src_key) && in_cycle(&edge.dst_key))
+        .collect::<Vec<_>>();
+    edges.sort_by(|a, b| (&a.src_key, &a.dst_key).cmp(&(&b.src_key, &b.dst_key)));
+
+    let mut hints = Vec::new();
+
+    let mut held_locks = graph
+        .holders
+        .iter()
+        .filter(|(resource, _)| {
+            graph
+                .nodes
+                .get(*resource)
+                .is_some_and(|node| node.kind == "lock")
+        })
+        .flat_map(|(resource, holders)| {
+            holders
+                .iter()
+                .map(move |holder| (holder.key(), resource.as_str()))
+        })
+        .filter(|(holder, _)| in_cycle(holder))
+        .collect::<Vec<_>>();
+    held_locks.sort();
+    if let Some((holder, lock, waited)) = held_locks.iter().find_map(|(holder, lock)| {
+        let edge = edges
+            .iter()
+            .find(|edge| edge.src_key == *holder && edge.dst_key != *lock)?;
+        Some((holder, *lock, *edge))
+    }) {
+        let before = match waited.reason {
+            Some(EdgeReason::RpcAwaitingResponse) => String::from("the RPC"),
+            _ => format!("awaiting {}", name(&waited.dst_key)),
+        };
+        hints.push(format!(
+            "{} holds {} across an await: drop the guard before {before}",
+            name(holder),
+            name(lock),
+        ));
+    }
+
+    let locks = node_keys
+        .iter()
+        .filter(|key| {
+            graph
+                .nodes
+                .get(*key)
+                .is_some_and(|node| node.kind == "lock")
+        })
+        .map(|key| name(key))
+        .collect::<Vec<_>>();
+    if locks.len() > 1 {
+        hints.push(format!(
+            "the cycle takes {}: acquire them in one fixed order everywhere",
+            locks.join(" and ")
+        ));
+    }
+
+    if let Some(edge) = edges
+        .iter()
+        .find(|edge| edge.reason == Some(EdgeReason::MpscFull))
+    {
+        hints.push(format!(
+            "{} is full: check that the task draining it is alive and not itself waiting on {}",
+            name(&edge.dst_key),
+            name(&edge.src_key),
+        ));
+    }
+
+    if let Some(edge) = edges
+        .iter()
+        .find(|edge| edge.reason == Some(EdgeReason::RpcAwaitingResponse))
+    {
+        hints.push(format!(
+            "{} waits on the RPC {}: make sure its handler does not need anything {} holds, and put a timeout on the call",
+            name(&edge.src_key),
+            name(&edge.dst_key),
+            name(&edge.src_key),
+        ));
+    }
+
+    hints
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::*;
+    use crate::graph::severity::DefaultSeverityPolicy;
+    use crate::graph::{HoldCounts, WakeCounts};
+
+    fn node(kind: &str, age_ms: u64) -> WaitNode {
+        WaitNode::test("p", "e", kind).at(100_000).aged(age_ms)
+    }
+
+    fn graph(edges: &[(&str, &str, &str, u64)]) -> WaitGraph {
+        let mut nodes = HashMap::new();
+        for (src, dst, kind, age_ms) in edges {
+            nodes.insert(*src, node(kind, *age_ms));
+            nodes.entry(*dst).or_insert_with(|| node(kind, *age_ms));
+        }
+        WaitGraph::test(nodes, edges.iter().map(|(src, dst, ..)| (*src, *dst)))
+    }
+
+    #[test]
+    fn candidates_are_ranked_by_severity() {
+        let graph = graph(&[
+            ("a", "b", "mpsc_rx", 10),
+            ("b", "a", "mpsc_rx", 10),
+            ("c", "d", "lock", 120_000),
+            ("d", "c", "lock", 120_000),
+        ]);
+        let scan = find_deadlock_candidates(&graph, None);
+        assert!(scan.complete);
+        assert_eq!(scan.candidates.len(), 2);
+        assert_eq!(scan.candidates[0].node_keys, vec!["c", "d"]);
+        assert_eq!(scan.candidates[0].confidence, "high");
+        assert_eq!(scan.candidates[1].node_keys, vec!["a", "b"]);
+        assert_eq!(scan.candidates[1].confidence, "medium");
+
+        let rules = scan.candidates[0]
+            .score
+            .contributions
+            .iter()
+            .map(|c| (c.rule.as_str(), c.points))
+            .collect::<Vec<_>>();
+        assert_eq!(rules, vec![("graph_only_wake_source", 3), ("wait_age", 3)]);
+    }
+
+    #[test]
+    fn idle_waits_have_no_severity() {
+        let lock = node("lock", 0);
+        let mut waiter = node("future", 120_000);
+        assert_eq!(DefaultSeverityPolicy.edge_score(&waiter, &lock).total(), 6);
+        waiter.idle = Some(String::from("shutdown-signal"));
+        assert_eq!(DefaultSeverityPolicy.edge_score(&waiter, &lock).total(), 0);
+    }
+
+    #[test]
+    fn an_acquire_that_would_close_a_cycle_is_a_near_cycle() {
+        // a holds l1 and waits on l2, held by b, which is acquiring l1.
+        let holders = HashMap::from([
+            (String::from("l1"), vec![String::from("a")]),
+            (String::from("l2"), vec![String::from("b")]),
+        ]);
+        let acquiring = [(String::from("b"), String::from("l1"))];
+        let open = graph(&[("a", "l2", "lock", 5_000)]);
+
+        let candidates = find_near_cycle_candidates(&open, &holders, &acquiring);
+        assert_eq!(candidates.len(), 1);
+        assert_eq!(candidates[0].path, vec!["l1", "a", "l2", "b"]);
+
+        // Once b waits on l1 the cycle is complete and deadlock detection
+        // takes over.
+        let closed = graph(&[("a", "l2", "lock", 5_000), ("b", "l1", "lock", 10)]);
+        assert!(find_near_cycle_candidates(&closed, &holders, &acquiring).is_empty());
+    }
+
+    #[test]
+    fn a_custom_policy_reranks_candidates() {
+        struct LocksAreFine;
+
+        impl SeverityPolicy for LocksAreFine {
+            fn edge_score(&self, src: &WaitNode, dst: &WaitNode) -> ScoreBreakdown {
+                if dst.kind == "lock" {
+                    return ScoreBreakdown::default();
+                }
+                DefaultSeverityPolicy.edge_score(src, dst)
+            }
+        }
+
+        let mut graph = graph(&[
+            ("a", "b", "mpsc_rx", 10),
+            ("b", "a", "mpsc_rx", 10),
+            ("c", "d", "lock", 120_000),
+            ("d", "c", "lock", 120_000),
+        ]);
+        let scan = find_deadlock_candidates_with_policy(&graph, &LocksAreFine, None);
+        assert_eq!(scan.candidates[0].node_keys, vec!["a", "b"]);
+        assert_eq!(scan.candidates[1].severity(), 0);
+
+        graph.severity_policy = Arc::new(LocksAreFine);
+        let scan = find_deadlock_candidates(&graph, None);
+        assert_eq!(scan.candidates[0].node_keys, vec!["a", "b"]);
+    }
+
+    #[test]
+    fn mutual_unproductive_wakes_are_livelock_candidates() {
+        let mut graph = graph(&[
+            ("a", "na", "notify", 10_000),
+            ("b", "nb", "notify", 10_000),
+            ("c", "nc", "notify", 10_000),
+        ]);
+        for (key, peer, wakes) in [("a", "b", 5_000), ("b", "a", 5_000), ("c", "a", 5_000)] {
+            let node = graph.nodes.get_mut(key).unwrap();
+            node.kind = String::from("future");
+            node.wakes = WakeCounts {
+                wake_count: wakes,
+                unproductive_wake_count: wakes,
+                last_woken_by: Some(String::from(peer)),
+            };
+        }
+
+        let candidates = find_livelock_candidates(&graph, &DetectionThresholds::default());
+        assert_eq!(candidates.len(), 1);
+        assert_eq!(candidates[0].node_keys, vec!["a", "b"]);
+        assert_eq!(candidates[0].wakes_per_sec, 500);
+        assert!(find_deadlock_candidates(&graph, None).candidates.is_empty());
+    }
+
+    #[test]
+    fn waits_far_past_average_hold_are_starvation_candidates() {
+        let mut graph = graph(&[
+            ("a", "lock", "lock", 10),
+            ("b", "lock", "lock", 10),
+            ("c", "sem", "semaphore", 10),
+        ]);
+        for (edge, since_ms) in graph.edges.iter_mut().zip([20_000, 99_500, 20_000]) {
+            edge.since_ms = Some(since_ms);
+        }
+        graph.nodes.get_mut("lock").unwrap().holds = HoldCounts {
+            hold_count: 500,
+            avg_hold_ms: 5,
+            latest_acquired_ms: Some(99_990),
+            readers: 3,
+            waiting_writers: 1,
+        };
+        // Too few holds to know what a normal hold looks like.
+        graph.nodes.get_mut("sem").unwrap().holds = HoldCounts {
+            hold_count: 2,
+            avg_hold_ms: 5,
+            latest_acquired_ms: None,
+            ..HoldCounts::default()
+        };
+
+        let candidates = find_starvation_candidates(&graph, &DetectionThresholds::default());
+        assert_eq!(candidates.len(), 1);
+        assert_eq!(candidates[0].resource_key, "lock");
+        assert_eq!(candidates[0].waiter_keys, vec!["a"]);
+        assert_eq!(candidates[0].longest_wait_ms, 80_000);
+        assert_eq!(
+            candidates[0].reasons,
+            vec![
+                "wait_exceeds_avg_hold",
+                "overtaken_by_later_holder",
+                "writer_behind_readers"
+            ]
+        );
+        assert_eq!(candidates[0].severity(), 4);
+    }
+
+    #[test]
+    fn resources_are_ranked_by_tasks_blocked_behind_them() {
+        let mut graph = graph(&[
+            ("a", "l", "lock", 10),
+            ("b", "l", "lock", 10),
+            ("c", "rx", "mpsc_rx", 10),
+            ("d", "a", "future", 10),
+            ("e", "d", "future", 10),
+            ("f", "rx", "mpsc_rx", 10),
+        ]);
+        for key in ["a", "b", "c", "f"] {
+            graph.nodes.get_mut(key).unwrap().kind = String::from("future");
+        }
+
+        let candidates = find_bottleneck_candidates(&graph, &DetectionThresholds::default());
+        let ranked = candidates
+            .iter()
+            .map(|c| {
+                (
+                    c.resource_key.as_str(),
+                    c.direct_waiters,
+                    c.blocked_task_count,
+                )
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(ranked, vec![("l", 2, 4)]);
+
+        let lenient = DetectionThresholds {
+            bottleneck_min_blocked_tasks: 1,
+            ..DetectionThresholds::default()
+        };
+        let ranked = find_bottleneck_candidates(&graph, &lenient)
+            .into_iter()
+            .map(|c| (c.resource_key, c.blocked_task_count))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            ranked,
+            vec![(String::from("l"), 4), (String::from("rx"), 2)]
+        );
+    }
+
+    #[test]
+    fn expired_deadline_reports_incomplete_scan() {
+        let graph = graph(&[("a", "b", "lock", 10), ("b", "a", "lock", 10)]);
+        let scan = find_deadlock_candidates(&graph, Some(Instant::now()));
+        assert!(!scan.complete);
+        assert!(scan.candidates.is_empty());
+    }
+
+    #[test]
+    fn deadlock_candidates_do_not_depend_on_insertion_order() {
+        // xorshift64, so the fixture is large and irregular but reproducible.
+        let mut state = 0x2545_f491_4f6c_dd1d_u64;
+        let mut next = move |bound: usize| {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            (state % bound as u64) as usize
+        };
+        const NODES: usize = 400;
+        const KINDS: [&str; 4] = ["lock", "mpsc_rx", "semaphore", "notify"];
+        let mut edges = (0..480)
+            .map(|_| (next(NODES), next(NODES)))
+            .collect::<Vec<_>>();
+
+        let build = |edges: &[(usize, usize)]| {
+            let nodes = (0..NODES).map(|i| {
+                // Few distinct ages, so many edges tie on score.
+                let age_ms = (i as u64 % 3) * 60_000;
+                (format!("n{i:03}"), node(KINDS[i % KINDS.len()], age_ms))
+            });
+            let graph = WaitGraph::test(
+                nodes,
+                edges
+                    .iter()
+                    .map(|(src, dst)| (format!("n{src:03}"), format!("n{dst:03}"))),
+            );
+            let scan = find_deadlock_candidates(&graph, None);
+            assert!(scan.complete);
+            scan.candidates
+                .into_iter()
+                .map(|c| {
+                    (
+                        c.node_keys,
+                        c.confidence,
+                        c.score,
+                        c.blocked_duration_hint_ms,
+                    )
+                })
+                .collect::<Vec<_>>()
+        };
+
+        let expected = build(&edges);
+        assert!(expected.len() > 1);
+        for _ in 0..8 {
+            for i in (1..edges.len()).rev() {
+                edges.swap(i, next(i + 1));
+            }
+            assert_eq!(build(&edges), expected);
+        }
+    }
+
+    #[test]
+    fn lock_held_across_an_rpc_in_a_cycle_gets_hints() {
+        let named = |key: &str, kind: &str| WaitNode {
+            entity_id: String::from(key),
+            name: String::from(key),
+            ..node(kind, 1_000)
+        };
+        let nodes = [
+            ("cache", "lock"),
+            ("handler", "future"),
+            ("lookup", "request"),
+            ("worker", "future"),
+        ]
+        .map(|(key, kind)| (format!("p::{key}"), named(key, kind)));
+        let mut graph = WaitGraph::test(
+            nodes,
+            [
+                ("p::worker", "p::lookup"),
+                ("p::lookup", "p::handler"),
+                ("p::handler", "p::worker"),
+            ],
+        );
+        let reasons = [
+            EdgeReason::RpcAwaitingResponse,
+            EdgeReason::FutureAwait,
+            EdgeReason::FutureAwait,
+        ];
+        for (edge, reason) in graph.edges.iter_mut().zip(reasons) {
+            edge.reason = Some(reason);
+        }
+        graph
+            .holders
+            .insert(String::from("p::cache"), vec![named("worker", "future")]);
+
+        let cycle = ["p::handler", "p::lookup", "p::worker"].map(String::from);
+        assert_eq!(
+            deadlock_hints(&graph, &cycle),
+            vec![
+                String::from("worker holds cache across an await: drop the guard before the RPC"),
+                String::from(
+                    "worker waits on the RPC lookup: make sure its handler does not need anything worker holds, and put a timeout on the call"
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn threads_holding_each_others_locks_form_a_cycle() {
+        let named = |key: &str, kind: &str| WaitNode {
+            entity_id: String::from(key),
+            name: String::from(key),
+            ..node(kind, 30_000)
+        };
+        let threads = [
+            ("AETHER#thread-1", "stock", "orders"),
+            ("AETHER#thread-2", "orders", "stock"),
+        ];
+        let nodes = threads.iter().flat_map(|(thread, _, holds)| {
+            [
+                (format!("p::{thread}"), named(thread, "aether")),
+                (format!("p::{holds}"), named(holds, "lock")),
+            ]
+        });
+        let edges = threads
+            .iter()
+            .map(|(thread, waits_on, _)| (format!("p::{thread}"), format!("p::{waits_on}")));
+        let mut graph = WaitGraph::test(nodes, edges);
+        for (thread, _, holds) in threads {
+            graph
+                .holders
+                .insert(format!("p::{holds}"), vec![named(thread, "aether")]);
+        }
+
+        let scan = find_deadlock_candidates(&graph, None);
+        assert_eq!(scan.candidates.len(), 1);
+        let candidate = &scan.candidates[0];
+        assert_eq!(candidate.node_keys.len(), 4);
+        assert!(candidate.reasons.contains(&"resource_held_by_waiter"));
+        assert_eq!(candidate.blocked_task_count, 2);
+    }
+}
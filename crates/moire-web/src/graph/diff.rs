@@ -0,0 +1,208 @@
+//! Structured delta between two wait graphs, e.g. consecutive snapshots.
+//!
+//! Edges carry no timestamps, so an edge's wait duration is approximated by the
+//! age of its waiting node, the same estimate deadlock detection uses.
+
+use std::collections::BTreeSet;
+
+use super::WaitGraph;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum NodeChange {
+    Added,
+    Removed,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct NodeDiff {
+    /// Node key (`process_id::entity_id`).
+    pub key: String,
+    pub change: NodeChange,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum EdgeChange {
+    Added,
+    Removed,
+    /// Present in both graphs, and more severe or waited on for longer in
+    /// the newer one.
+    Escalated,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct EdgeDiff {
+    pub src_key: String,
+    pub dst_key: String,
+    pub change: EdgeChange,
+    /// Severity in the older graph; `None` for added edges.
+    pub severity_before: Option<u32>,
+    /// Severity in the newer graph; `None` for removed edges.
+    pub severity_after: Option<u32>,
+    pub wait_ms_before: Option<u64>,
+    pub wait_ms_after: Option<u64>,
+}
+
+/// Changes from one wait graph to a newer one. Unchanged nodes, and edges that
+/// neither grew more severe nor waited longer, are left out.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct GraphDiff {
+    /// Sorted by key.
+    pub nodes: Vec<NodeDiff>,
+    /// Sorted by `(src_key, dst_key)`.
+    pub edges: Vec<EdgeDiff>,
+}
+
+impl GraphDiff {
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty() && self.edges.is_empty()
+    }
+}
+
+struct EdgeStats {
+    severity: u32,
+    wait_ms: u64,
+}
+
+fn edge_stats(graph: &WaitGraph, src_key: &str, dst_key: &str) -> Option<EdgeStats> {
+    let src = graph.nodes.get(src_key)?;
+    let dst = graph.nodes.get(dst_key)?;
+    Some(EdgeStats {
+        severity: graph.edge_severity(src, dst),
+        wait_ms: src.ptime_now_ms.saturating_sub(src.birth_ms),
+    })
+}
+
+fn edge_keys(graph: &WaitGraph) -> BTreeSet<(&str, &str)> {
+    graph
+        .adjacency
+        .iter()
+        .flat_map(|(src, dsts)| dsts.iter().map(move |dst| (src.as_str(), dst.as_str())))
+        .collect()
+}
+
+impl WaitGraph {
+    /// Classifies what changed from `self` (older) to `newer`.
+    pub fn diff(&self, newer: &WaitGraph) -> GraphDiff {
+        let before_nodes = self.nodes.keys().collect::<BTreeSet<_>>();
+        let after_nodes = newer.nodes.keys().collect::<BTreeSet<_>>();
+        let mut nodes = Vec::new();
+        for key in after_nodes.difference(&before_nodes) {
+            nodes.push(NodeDiff {
+                key: (*key).clone(),
+                change: NodeChange::Added,
+            });
+        }
+        for key in before_nodes.difference(&after_nodes) {
+            nodes.push(NodeDiff {
+                key: (*key).clone(),
+                change: NodeChange::Removed,
+            });
+        }
+        nodes.sort_by(|a, b| a.key.cmp(&b.key));
+
+        let before_edges = edge_keys(self);
+        let after_edges = edge_keys(newer);
+        let mut edges = Vec::new();
+        for &(src_key, dst_key) in before_edges.union(&after_edges) {
+            let before = before_edges
+                .contains(&(src_key, dst_key))
+                .then(|| edge_stats(self, src_key, dst_key))
+                .flatten();
+            let after = after_edges
+                .contains(&(src_key, dst_key))
+                .then(|| edge_stats(newer, src_key, dst_key))
+                .flatten();
+            let change = match (&before, &after) {
+                (None, Some(_)) => EdgeChange::Added,
+                (Some(_), None) => EdgeChange::Removed,
+                (Some(before), Some(after))
+                    if after.severity > before.severity || after.wait_ms > before.wait_ms =>
+                {
+                    EdgeChange::Escalated
+                }
+                _ => continue,
+            };
+            edges.push(EdgeDiff {
+                src_key: String::from(src_key),
+                dst_key: String::from(dst_key),
+                change,
+                severity_before: before.as_ref().map(|stats| stats.severity),
+                severity_after: after.as_ref().map(|stats| stats.severity),
+                wait_ms_before: before.as_ref().map(|stats| stats.wait_ms),
+                wait_ms_after: after.as_ref().map(|stats| stats.wait_ms),
+            });
+        }
+
+        GraphDiff { nodes, edges }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph::WaitNode;
+
+    fn graph(now_ms: u64, edges: &[(&str, &str)]) -> WaitGraph {
+        let nodes = edges
+            .iter()
+            .flat_map(|(src, dst)| [*src, *dst])
+            .map(|key| (key, WaitNode::test("p", key, "lock").at(now_ms)));
+        WaitGraph::test(nodes, edges.iter().copied())
+    }
+
+    #[test]
+    fn diff_classifies_added_removed_and_escalated() {
+        let before = graph(500, &[("a", "b"), ("c", "d")]);
+        let after = graph(30_000, &[("a", "b"), ("a", "e")]);
+        let diff = before.diff(&after);
+
+        assert_eq!(
+            diff.nodes,
+            vec![
+                NodeDiff {
+                    key: String::from("c"),
+                    change: NodeChange::Removed,
+                },
+                NodeDiff {
+                    key: String::from("d"),
+                    change: NodeChange::Removed,
+                },
+                NodeDiff {
+                    key: String::from("e"),
+                    change: NodeChange::Added,
+                },
+            ]
+        );
+        let changes = diff
+            .edges
+            .iter()
+            .map(|edge| (edge.src_key.as_str(), edge.dst_key.as_str(), edge.change))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            changes,
+            vec![
+                ("a", "b", EdgeChange::Escalated),
+                ("a", "e", EdgeChange::Added),
+                ("c", "d", EdgeChange::Removed),
+            ]
+        );
+        assert_eq!(diff.edges[0].wait_ms_before, Some(500));
+        assert_eq!(diff.edges[0].wait_ms_after, Some(30_000));
+    }
+
+    #[test]
+    fn diff_escalates_edges_that_waited_longer_at_the_same_severity() {
+        let before = graph(1_000, &[("a", "b")]);
+        let after = graph(1_200, &[("a", "b")]);
+        let diff = before.diff(&after);
+
+        assert_eq!(diff.edges.len(), 1);
+        let edge = &diff.edges[0];
+        assert_eq!(edge.change, EdgeChange::Escalated);
+        assert_eq!(edge.severity_before, edge.severity_after);
+        assert_eq!(edge.wait_ms_before, Some(1_000));
+        assert_eq!(edge.wait_ms_after, Some(1_200));
+
+        assert!(after.diff(&after).is_empty());
+    }
+}
@@ -0,0 +1,296 @@
+//! Why one node is blocked, as step-by-step paths to a root cause.
+//!
+//! Deadlock detection explains cycles, but most stuck tasks sit at the end of
+//! a linear chain: a task waits on a channel whose sender waits on a lock held
+//! by a task stuck in a slow RPC. [`WaitGraph::explain_blockage`] follows
+//! `waiting_on` edges and, past a resource nobody is shown waiting beyond,
+//! that resource's holders, until it reaches something that waits on nothing
+//! the graph knows about.
+
+use std::collections::{HashMap, HashSet};
+
+use moire_graph_core::has_external_wake_source;
+use moire_types::EdgeReason;
+
+use super::{WaitEdgeRuntime, WaitGraph, WaitNode};
+
+/// Paths returned at most, so a resource with thousands of waiters upstream
+/// of many holders does not explode the answer.
+const MAX_BLOCKAGE_PATHS: usize = 32;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BlockagePath {
+    pub steps: Vec<BlockageStep>,
+    /// Key of the node the path ends at.
+    pub root_key: String,
+    pub root: BlockageRoot,
+    /// The root cause, in words.
+    pub root_text: String,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BlockageStep {
+    pub from_key: String,
+    pub to_key: String,
+    pub link: BlockageLink,
+    /// The step in words, e.g. `worker [future] waits on jobs [mpsc_rx] for
+    /// 1200ms (mpsc_empty)`.
+    pub text: String,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum BlockageLink {
+    /// A `waiting_on` edge.
+    WaitsOn {
+        waited_ms: Option<u64>,
+        reason: Option<EdgeReason>,
+    },
+    /// The resource is held by the next node.
+    HeldBy,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BlockageRoot {
+    /// A task that waits on nothing instrumented: it is running, or blocked
+    /// somewhere moire does not see.
+    Task,
+    /// A resource only something outside the graph can wake: a channel
+    /// receiver, a socket, an RPC.
+    External,
+    /// A resource nobody is shown holding.
+    Resource,
+    /// The path reached a node it already passed through.
+    Cycle,
+}
+
+impl BlockagePath {
+    /// The steps and the root cause, one per line.
+    pub fn describe(&self) -> String {
+        let mut lines = self
+            .steps
+            .iter()
+            .map(|step| step.text.clone())
+            .collect::<Vec<_>>();
+        lines.push(self.root_text.clone());
+        lines.join("\n")
+    }
+}
+
+impl WaitGraph {
+    /// Every path from `key` to what ultimately blocks it, at most
+    /// [`MAX_BLOCKAGE_PATHS`] of them. Empty when `key` is not in the graph or
+    /// waits on nothing.
+    pub fn explain_blockage(&self, key: &str) -> Vec<BlockagePath> {
+        let holder_nodes: HashMap<String, &WaitNode> = self
+            .holders
+            .values()
+            .flatten()
+            .map(|node| (node.key(), node))
+            .collect();
+        let walk = BlockageWalk {
+            graph: self,
+            holder_nodes,
+            edges: self
+                .edges
+                .iter()
+                .map(|edge| ((edge.src_key.as_str(), edge.dst_key.as_str()), edge))
+                .collect(),
+        };
+
+        let mut paths = Vec::new();
+        if self.nodes.contains_key(key) && !walk.next_hops(key).is_empty() {
+            let mut on_path = HashSet::from([key.to_owned()]);
+            walk.visit(key, &mut Vec::new(), &mut on_path, &mut paths);
+        }
+        paths
+    }
+}
+
+struct BlockageWalk<'a> {
+    graph: &'a WaitGraph,
+    holder_nodes: HashMap<String, &'a WaitNode>,
+    edges: HashMap<(&'a str, &'a str), &'a WaitEdgeRuntime>,
+}
+
+impl BlockageWalk<'_> {
+    fn node(&self, key: &str) -> Option<&WaitNode> {
+        self.graph
+            .nodes
+            .get(key)
+            .or_else(|| self.holder_nodes.get(key).copied())
+    }
+
+    fn label(&self, key: &str) -> String {
+        match self.node(key) {
+            Some(node) => format!("{} [{}]", node.name, node.kind),
+            None => key.to_owned(),
+        }
+    }
+
+    /// What `key` waits on, or failing that, who holds it.
+    fn next_hops(&self, key: &str) -> Vec<(String, BlockageLink)> {
+        if let Some(dsts) = self
+            .graph
+            .adjacency
+            .get(key)
+            .filter(|dsts| !dsts.is_empty())
+        {
+            return dsts
+                .iter()
+                .map(|dst| {
+                    let edge = self.edges.get(&(key, dst.as_str()));
+                    let waited_ms = edge.and_then(|edge| {
+                        let since_ms = edge.since_ms?;
+                        let now_ms = self.node(key)?.ptime_now_ms;
+                        Some(now_ms.saturating_sub(since_ms))
+                    });
+                    let link = BlockageLink::WaitsOn {
+                        waited_ms,
+                        reason: edge.and_then(|edge| edge.reason),
+                    };
+                    (dst.clone(), link)
+                })
+                .collect();
+        }
+        self.graph
+            .holders
+            .get(key)
+            .into_iter()
+            .flatten()
+            .map(|holder| (holder.key(), BlockageLink::HeldBy))
+            .collect()
+    }
+
+    fn visit(
+        &self,
+        key: &str,
+        steps: &mut Vec<BlockageStep>,
+        on_path: &mut HashSet<String>,
+        paths: &mut Vec<BlockagePath>,
+    ) {
+        let hops = self.next_hops(key);
+        if hops.is_empty() {
+            let root = match self.node(key).map(|node| node.kind.as_str()) {
+                Some("future" | "actor" | "aether") => BlockageRoot::Task,
+                Some(kind) if has_external_wake_source(kind) => BlockageRoot::External,
+                _ => BlockageRoot::Resource,
+            };
+            paths.push(self.path(steps, key, root));
+            return;
+        }
+
+        for (next, link) in hops {
+            if paths.len() >= MAX_BLOCKAGE_PATHS {
+                return;
+            }
+            steps.push(self.step(key, &next, link));
+            if on_path.contains(&next) {
+                paths.push(self.path(steps, &next, BlockageRoot::Cycle));
+            } else {
+                on_path.insert(next.clone());
+                self.visit(&next, steps, on_path, paths);
+                on_path.remove(&next);
+            }
+            steps.pop();
+        }
+    }
+
+    fn step(&self, from: &str, to: &str, link: BlockageLink) -> BlockageStep {
+        let text = match &link {
+            BlockageLink::WaitsOn { waited_ms, reason } => {
+                let mut text = format!("{} waits on {}", self.label(from), self.label(to));
+                if let Some(waited_ms) = waited_ms {
+                    text.push_str(&format!(" for {waited_ms}ms"));
+                }
+                if let Some(reason) = reason {
+                    text.push_str(&format!(" ({})", reason.as_str()));
+                }
+                text
+            }
+            BlockageLink::HeldBy => {
+                format!("{} is held by {}", self.label(from), self.label(to))
+            }
+        };
+        BlockageStep {
+            from_key: from.to_owned(),
+            to_key: to.to_owned(),
+            link,
+            text,
+        }
+    }
+
+    fn path(&self, steps: &[BlockageStep], root_key: &str, root: BlockageRoot) -> BlockagePath {
+        let label = self.label(root_key);
+        let root_text = match root {
+            BlockageRoot::Task => {
+                format!("{label} waits on nothing instrumented: it is running or blocked elsewhere")
+            }
+            BlockageRoot::External => format!("{label} is waiting for an event from outside"),
+            BlockageRoot::Resource => format!("{label} has no known holder"),
+            BlockageRoot::Cycle => format!("{label} is already on this path: a wait cycle"),
+        };
+        BlockagePath {
+            steps: steps.to_vec(),
+            root_key: root_key.to_owned(),
+            root,
+            root_text,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn node(key: &str, kind: &str) -> WaitNode {
+        WaitNode::test("p", key, kind).at(10_000)
+    }
+
+    #[test]
+    fn linear_chain_ends_at_the_busy_lock_holder() {
+        // handler waits on jobs, whose sender waits on a lock held by a
+        // task that is not waiting on anything.
+        let nodes = [
+            ("handler", "future"),
+            ("jobs", "mpsc_tx"),
+            ("producer", "future"),
+            ("cache", "lock"),
+        ]
+        .map(|(key, kind)| (format!("p::{key}"), node(key, kind)));
+        let mut graph = WaitGraph::test(
+            nodes,
+            [
+                ("p::handler", "p::jobs"),
+                ("p::jobs", "p::producer"),
+                ("p::producer", "p::cache"),
+            ],
+        );
+        let reasons = [
+            EdgeReason::MpscFull,
+            EdgeReason::FutureAwait,
+            EdgeReason::MutexWait,
+        ];
+        for (edge, reason) in graph.edges.iter_mut().zip(reasons) {
+            edge.since_ms = Some(7_500);
+            edge.reason = Some(reason);
+        }
+        graph
+            .holders
+            .insert(String::from("p::cache"), vec![node("flusher", "future")]);
+
+        let paths = graph.explain_blockage("p::handler");
+        assert_eq!(paths.len(), 1);
+        let path = &paths[0];
+        assert_eq!(path.root_key, "p::flusher");
+        assert_eq!(path.root, BlockageRoot::Task);
+        assert_eq!(path.steps.len(), 4);
+        assert_eq!(
+            path.steps[0].text,
+            "handler [future] waits on jobs [mpsc_tx] for 2500ms (mpsc_full)"
+        );
+        assert_eq!(path.steps[3].link, BlockageLink::HeldBy);
+
+        assert!(graph.explain_blockage("p::flusher").is_empty());
+        assert!(graph.explain_blockage("p::missing").is_empty());
+    }
+}
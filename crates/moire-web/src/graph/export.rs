@@ -0,0 +1,324 @@
+//! Graphviz and plain-text export of a [`WaitGraph`].
+//!
+//! `moire dot --file snapshot.json | dot -Tsvg > graph.svg` renders a snapshot
+//! dump offline. It is built on [`WaitGraph::visit`] like any other adapter.
+//! Node shapes follow the entity kind; edge color and width follow
+//! [`EdgeView::severity`](super::visit::EdgeView::severity), and edges are
+//! labelled with their reason when known.
+//!
+//! [`cycle_to_ascii`] draws one wait cycle as boxes and arrows for log files
+//! and terminals, where Graphviz is not at hand.
+
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::fmt::Write as _;
+
+use moire_graph_core::has_external_wake_source;
+
+use super::{WaitGraph, WaitNode};
+
+/// Renders `graph` as a Graphviz digraph. Output is sorted, so the same graph
+/// always yields the same text.
+pub fn to_dot(graph: &WaitGraph) -> String {
+    let mut out = String::new();
+    let _ = writeln!(out, "digraph moire {{");
+    let _ = writeln!(out, "  rankdir=LR;");
+    let _ = writeln!(
+        out,
+        "  node [fontname=\"Helvetica\", fontsize=10, style=filled];"
+    );
+    let _ = writeln!(out, "  edge [fontname=\"Helvetica\", fontsize=9];");
+
+    let mut edges = String::new();
+    graph.visit(
+        |node| {
+            let (shape, fill) = node_style(node.kind());
+            // Expected waits are drawn greyed out, with the reason in place of the age.
+            let (detail, fill, style) = match node.idle_reason() {
+                Some(reason) => (
+                    format!("idle: {}", escape(reason)),
+                    "gray90",
+                    "\"filled,dashed\"",
+                ),
+                None => (format!("{}ms", node.age_ms()), fill, "filled"),
+            };
+            let _ = writeln!(
+                out,
+                "  \"{}\" [label=\"{}\\n{} · {}\\n{detail}\", shape={shape}, style={style}, fillcolor=\"{fill}\"];",
+                escape(node.key()),
+                escape(node.label()),
+                escape(node.kind()),
+                escape(node.process_id()),
+            );
+        },
+        |edge| {
+            let severity = edge.severity();
+            let (color, style) = match severity {
+                0..=1 => ("gray50", "dashed"),
+                2..=3 => ("darkorange", "solid"),
+                _ => ("red3", "bold"),
+            };
+            let _ = writeln!(
+                edges,
+                "  \"{}\" -> \"{}\" [label=\"{}\", color=\"{color}\", style={style}, penwidth={}];",
+                escape(edge.src_key()),
+                escape(edge.dst_key()),
+                edge.reason().unwrap_or("waiting_on"),
+                1 + severity.min(5),
+            );
+        },
+    );
+    out.push_str(&edges);
+
+    let _ = writeln!(out, "}}");
+    out
+}
+
+/// Shape and fill color for an entity kind. Resources that only the graph
+/// itself can release are drawn warmer than ones an external event can wake.
+fn node_style(kind: &str) -> (&'static str, &'static str) {
+    let shape = match kind {
+        "future" => "ellipse",
+        "actor" => "box3d",
+        "lock" | "semaphore" | "once_cell" | "barrier" => "octagon",
+        "notify" => "diamond",
+        "request" | "response" => "component",
+        "net_connect" | "net_accept" | "net_read" | "net_write" => "cds",
+        "command" | "file_op" => "note",
+        "run_queue" => "cylinder",
+        kind if kind.starts_with("mpsc_")
+            || kind.starts_with("broadcast_")
+            || kind.starts_with("watch_")
+            || kind.starts_with("oneshot_") =>
+        {
+            "box"
+        }
+        _ => "ellipse",
+    };
+    let fill = match kind {
+        "future" => "lightblue",
+        "actor" => "plum",
+        _ if has_external_wake_source(kind) => "palegreen",
+        _ => "lightsalmon",
+    };
+    (shape, fill)
+}
+
+/// Draws the wait cycle through `node_keys` (a deadlock candidate's component)
+/// as a column of boxes, each arrow labelled with how long its waiter has
+/// waited, and a rail from the last box back to the first:
+///
+/// ```text
+/// +--------------+
+/// | worker       |<------------+
+/// | future in p1 |             |
+/// +--------------+             |
+///         |                    |
+///         | 1200ms mutex_wait  |
+///         v                    |
+/// +--------------+             |
+/// | state        |             |
+/// | lock in p1   |             |
+/// +--------------+             |
+///         |                    |
+///         | 950ms              |
+///         +--------------------+
+/// ```
+///
+/// The cycle is the shortest one through the first key; members of the
+/// component off that cycle are listed underneath. `None` when the keys do
+/// not form a cycle in `graph`.
+pub fn cycle_to_ascii(graph: &WaitGraph, node_keys: &[String]) -> Option<String> {
+    let cycle = shortest_cycle(graph, node_keys)?;
+    let nodes = cycle
+        .iter()
+        .map(|key| graph.nodes.get(*key))
+        .collect::<Option<Vec<&WaitNode>>>()?;
+    let labels = nodes
+        .iter()
+        .map(|node| {
+            [
+                node.name.clone(),
+                format!("{} in {}", node.kind, node.process_id),
+            ]
+        })
+        .collect::<Vec<_>>();
+    let arrows = (0..cycle.len())
+        .map(|i| wait_label(graph, nodes[i], cycle[i], cycle[(i + 1) % cycle.len()]))
+        .collect::<Vec<_>>();
+
+    let inner = labels
+        .iter()
+        .flatten()
+        .map(|line| line.chars().count())
+        .max()
+        .unwrap_or(0);
+    let box_width = inner + 4;
+    let stem = box_width / 2;
+    let longest_arrow = arrows
+        .iter()
+        .map(|label| label.chars().count())
+        .max()
+        .unwrap_or(0);
+    let rail = (box_width + 3).max(stem + longest_arrow + 4);
+    let border = format!("+{}+", "-".repeat(box_width - 2));
+
+    let mut lines = Vec::new();
+    for (i, [name, detail]) in labels.iter().enumerate() {
+        lines.push(border.clone());
+        lines.push(format!("| {} |", pad(name, inner)));
+        lines.push(format!("| {} |", pad(detail, inner)));
+        lines.push(border.clone());
+        lines.push(format!("{}|", " ".repeat(stem)));
+        lines.push(format!("{}| {}", " ".repeat(stem), arrows[i]));
+        if i + 1 < labels.len() {
+            lines.push(format!("{}v", " ".repeat(stem)));
+        }
+    }
+    // Replaced by the rail's bottom corner.
+    lines.push(String::new());
+
+    let mut out = String::new();
+    let last = lines.len() - 1;
+    for (i, line) in lines.iter().enumerate() {
+        let width = line.chars().count();
+        let _ = match i {
+            0 => writeln!(out, "{line}"),
+            1 => writeln!(out, "{line}<{}+", "-".repeat(rail - width - 1)),
+            _ if i == last => {
+                writeln!(out, "{}+{}+", " ".repeat(stem), "-".repeat(rail - stem - 1))
+            }
+            _ => writeln!(out, "{line}{}|", " ".repeat(rail - width)),
+        };
+    }
+
+    let on_cycle = cycle.iter().copied().collect::<HashSet<_>>();
+    let mut off_cycle = node_keys
+        .iter()
+        .filter(|key| !on_cycle.contains(key.as_str()))
+        .filter_map(|key| graph.nodes.get(key))
+        .map(|node| node.name.as_str())
+        .collect::<Vec<_>>();
+    if !off_cycle.is_empty() {
+        off_cycle.sort_unstable();
+        let _ = writeln!(out, "also in this component: {}", off_cycle.join(", "));
+    }
+    Some(out)
+}
+
+/// Shortest path from the first of `node_keys` back to itself, staying
+/// inside `node_keys`.
+fn shortest_cycle<'g>(graph: &'g WaitGraph, node_keys: &[String]) -> Option<Vec<&'g str>> {
+    let start = graph.nodes.get_key_value(node_keys.first()?)?.0.as_str();
+    let members = node_keys.iter().map(String::as_str).collect::<HashSet<_>>();
+    let mut previous: HashMap<&str, &str> = HashMap::new();
+    let mut queue = VecDeque::from([start]);
+    while let Some(key) = queue.pop_front() {
+        for next in graph.adjacency.get(key).into_iter().flatten() {
+            if !members.contains(next.as_str()) {
+                continue;
+            }
+            if next == start {
+                let mut cycle = vec![key];
+                while let Some(&before) = cycle.last().and_then(|last| previous.get(last)) {
+                    cycle.push(before);
+                }
+                cycle.reverse();
+                return Some(cycle);
+            }
+            if !previous.contains_key(next.as_str()) {
+                previous.insert(next.as_str(), key);
+                queue.push_back(next.as_str());
+            }
+        }
+    }
+    None
+}
+
+/// How long `src` has waited on `dst`, with the reason when known.
+fn wait_label(graph: &WaitGraph, src: &WaitNode, src_key: &str, dst_key: &str) -> String {
+    let edge = graph
+        .edges
+        .iter()
+        .find(|edge| edge.src_key == src_key && edge.dst_key == dst_key);
+    let since_ms = edge.and_then(|edge| edge.since_ms).unwrap_or(src.birth_ms);
+    let waited_ms = src.ptime_now_ms.saturating_sub(since_ms);
+    match edge.and_then(|edge| edge.reason) {
+        Some(reason) => format!("{waited_ms}ms {}", reason.as_str()),
+        None => format!("{waited_ms}ms"),
+    }
+}
+
+fn pad(text: &str, width: usize) -> String {
+    format!("{text}{}", " ".repeat(width - text.chars().count()))
+}
+
+fn escape(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph::WaitNode;
+
+    fn node(entity_id: &str, kind: &str) -> WaitNode {
+        WaitNode {
+            name: format!("{entity_id} \"quoted\""),
+            ..WaitNode::test("p", entity_id, kind).at(120_000)
+        }
+    }
+
+    #[test]
+    fn dot_output_styles_nodes_and_edges() {
+        let graph = WaitGraph::test(
+            [
+                ("p::task", node("task", "future")),
+                ("p::lock", node("lock", "lock")),
+            ],
+            [("p::task", "p::lock")],
+        );
+
+        let dot = to_dot(&graph);
+        assert!(dot.starts_with("digraph moire {"));
+        assert!(dot.contains("\"p::lock\" [label=\"lock \\\"quoted\\\""));
+        assert!(dot.contains("shape=octagon, style=filled, fillcolor=\"lightsalmon\""));
+        assert!(dot.contains("\"p::task\" -> \"p::lock\""));
+        assert!(dot.contains("color=\"red3\", style=bold, penwidth=6"));
+        assert_eq!(dot, to_dot(&graph));
+    }
+
+    #[test]
+    fn ascii_cycle_labels_arrows_with_wait_durations() {
+        let mut graph = WaitGraph::test(
+            [
+                ("p::worker", node("worker", "future")),
+                ("p::state", node("state", "lock")),
+                ("p::other", node("other", "future")),
+            ],
+            [
+                ("p::worker", "p::state"),
+                ("p::state", "p::worker"),
+                ("p::state", "p::other"),
+                ("p::other", "p::state"),
+            ],
+        );
+        graph.edges[0].since_ms = Some(118_800);
+        graph.edges[1].since_ms = Some(119_050);
+
+        let keys = [
+            String::from("p::worker"),
+            String::from("p::state"),
+            String::from("p::other"),
+        ];
+        let diagram = cycle_to_ascii(&graph, &keys).expect("a cycle");
+        let lines = diagram.lines().collect::<Vec<_>>();
+        assert!(lines[1].starts_with("| worker \"quoted\" |<"));
+        assert!(lines[1].ends_with('+'));
+        assert!(lines[5].contains("| 1200ms"));
+        assert!(lines[8].starts_with("| state \"quoted\""));
+        assert!(lines[12].contains("| 950ms"));
+        assert!(lines[13].trim_start().starts_with("+---"));
+        assert_eq!(lines[14], "also in this component: other \"quoted\"");
+        assert!(cycle_to_ascii(&graph, &[String::from("p::missing")]).is_none());
+    }
+}
@@ -0,0 +1,236 @@
+//! Cutting a [`WaitGraph`] down to the part worth looking at.
+//!
+//! Dumps of large multi-process deployments hold tens of thousands of nodes.
+//! [`WaitGraph::filter`] drops nodes by what they are; [`WaitGraph::prune`]
+//! keeps only the neighbourhood of something interesting. Both return a new
+//! graph whose edges, adjacency, indegree and RPC links only mention nodes
+//! that survived.
+
+use std::collections::{BTreeSet, HashMap, HashSet, VecDeque};
+
+use super::detect::find_deadlock_candidates;
+use super::{WaitGraph, WaitNode};
+
+/// Which nodes [`WaitGraph::filter`] keeps. The default keeps everything.
+#[derive(Clone, Debug, Default)]
+pub struct GraphFilter {
+    /// Keep only nodes of these processes. `None` keeps every process.
+    pub process_ids: Option<BTreeSet<String>>,
+    /// Drop entities the snapshot still retains after they completed or were
+    /// dropped.
+    pub drop_completed: bool,
+}
+
+/// Which part of the graph [`WaitGraph::prune`] keeps.
+#[derive(Clone, Debug)]
+pub enum GraphPrune {
+    /// Nodes at most `hops` edges away from `key`, following edges in either
+    /// direction.
+    WithinHops { key: String, hops: usize },
+    /// Nodes of deadlock candidates and everything they wait on, directly or
+    /// transitively. Detection runs without a deadline.
+    ReachableFromDeadlocks,
+}
+
+impl WaitGraph {
+    /// The subgraph of nodes matching `filter`. RPC links to or from a
+    /// filtered-out process are dropped too.
+    pub fn filter(&self, filter: &GraphFilter) -> WaitGraph {
+        let in_scope = |process_id: &str| {
+            filter
+                .process_ids
+                .as_ref()
+                .is_none_or(|process_ids| process_ids.contains(process_id))
+        };
+        let mut graph = self.retain(|_, node| {
+            in_scope(&node.process_id) && !(filter.drop_completed && node.removed_ms.is_some())
+        });
+        graph
+            .inflight_rpcs
+            .retain(|link| in_scope(&link.server_process_id) && in_scope(&link.client_process_id));
+        graph
+    }
+
+    /// The subgraph `prune` selects. Pruning around a key that is not in the
+    /// graph yields an empty graph.
+    pub fn prune(&self, prune: &GraphPrune) -> WaitGraph {
+        let keep = match prune {
+            GraphPrune::WithinHops { key, hops } => self.within_hops(key, *hops),
+            GraphPrune::ReachableFromDeadlocks => {
+                let roots = find_deadlock_candidates(self, None)
+                    .candidates
+                    .into_iter()
+                    .flat_map(|candidate| candidate.node_keys)
+                    .collect::<Vec<_>>();
+                self.reachable_from(roots)
+            }
+        };
+        self.retain(|key, _| keep.contains(key))
+    }
+
+    fn within_hops(&self, key: &str, hops: usize) -> HashSet<String> {
+        let mut neighbours: HashMap<&str, Vec<&str>> = HashMap::new();
+        for edge in &self.edges {
+            neighbours
+                .entry(edge.src_key.as_str())
+                .or_default()
+                .push(edge.dst_key.as_str());
+            neighbours
+                .entry(edge.dst_key.as_str())
+                .or_default()
+                .push(edge.src_key.as_str());
+        }
+
+        let mut seen = HashSet::new();
+        if !self.nodes.contains_key(key) {
+            return seen;
+        }
+        seen.insert(key.to_owned());
+        let mut queue = VecDeque::from([(key, 0)]);
+        while let Some((current, depth)) = queue.pop_front() {
+            if depth == hops {
+                continue;
+            }
+            for next in neighbours.get(current).into_iter().flatten() {
+                if seen.insert((*next).to_owned()) {
+                    queue.push_back((next, depth + 1));
+                }
+            }
+        }
+        seen
+    }
+
+    fn reachable_from(&self, roots: Vec<String>) -> HashSet<String> {
+        let mut seen: HashSet<String> = roots.iter().cloned().collect();
+        let mut stack = roots;
+        while let Some(current) = stack.pop() {
+            for next in self.adjacency.get(&current).into_iter().flatten() {
+                if seen.insert(next.clone()) {
+                    stack.push(next.clone());
+                }
+            }
+        }
+        seen
+    }
+
+    /// Copies the nodes `keep` accepts, the edges between them and the
+    /// holders of kept resources, and recomputes adjacency and indegree. RPC
+    /// links naming a dropped node are left out.
+    fn retain(&self, keep: impl Fn(&str, &WaitNode) -> bool) -> WaitGraph {
+        let nodes = self
+            .nodes
+            .iter()
+            .filter(|(key, node)| keep(key, node))
+            .map(|(key, node)| (key.clone(), node.clone()))
+            .collect::<HashMap<_, _>>();
+
+        let edges = self
+            .edges
+            .iter()
+            .filter(|edge| nodes.contains_key(&edge.src_key) && nodes.contains_key(&edge.dst_key))
+            .cloned()
+            .collect::<Vec<_>>();
+        let mut adjacency: HashMap<String, Vec<String>> = HashMap::new();
+        let mut indegree: HashMap<String, usize> =
+            nodes.keys().map(|key| (key.clone(), 0)).collect();
+        for edge in &edges {
+            adjacency
+                .entry(edge.src_key.clone())
+                .or_default()
+                .push(edge.dst_key.clone());
+            *indegree.entry(edge.dst_key.clone()).or_insert(0) += 1;
+        }
+
+        let holders = self
+            .holders
+            .iter()
+            .filter(|(key, _)| nodes.contains_key(*key))
+            .map(|(key, held_by)| (key.clone(), held_by.clone()))
+            .collect();
+
+        let dropped = |key: &str| self.nodes.contains_key(key) && !nodes.contains_key(key);
+        let inflight_rpcs = self
+            .inflight_rpcs
+            .iter()
+            .filter(|link| !dropped(&link.response_key) && !dropped(&link.request_key))
+            .cloned()
+            .collect();
+
+        WaitGraph {
+            nodes,
+            edges,
+            adjacency,
+            indegree,
+            holders,
+            inflight_rpcs,
+            severity_policy: self.severity_policy.clone(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn node(process_id: &str, key: &str, kind: &str) -> WaitNode {
+        WaitNode::test(process_id, key, kind).at(60_000)
+    }
+
+    /// `a` and `b` deadlock through lock `l`; `c` waits on `a`; `d` waits on
+    /// `e` in another process, and `e` has already completed.
+    fn graph() -> WaitGraph {
+        let nodes = [
+            ("p", "a", "future"),
+            ("p", "b", "future"),
+            ("p", "l", "lock"),
+            ("p", "c", "future"),
+            ("q", "d", "future"),
+            ("q", "e", "future"),
+        ]
+        .map(|(process_id, key, kind)| (key, node(process_id, key, kind)));
+        let mut graph = WaitGraph::test(
+            nodes,
+            [("a", "l"), ("l", "b"), ("b", "a"), ("c", "a"), ("d", "e")],
+        );
+        graph.nodes.get_mut("e").unwrap().removed_ms = Some(50_000);
+        for edge in &mut graph.edges {
+            edge.since_ms = Some(30_000);
+        }
+        graph
+    }
+
+    fn keys(graph: &WaitGraph) -> Vec<&str> {
+        let mut keys = graph.nodes.keys().map(String::as_str).collect::<Vec<_>>();
+        keys.sort();
+        keys
+    }
+
+    #[test]
+    fn filters_and_prunes_keep_only_matching_nodes_and_their_edges() {
+        let graph = graph();
+
+        let only_q = graph.filter(&GraphFilter {
+            process_ids: Some(BTreeSet::from([String::from("q")])),
+            ..GraphFilter::default()
+        });
+        assert_eq!(keys(&only_q), ["d", "e"]);
+        assert_eq!(only_q.edges.len(), 1);
+
+        let live = graph.filter(&GraphFilter {
+            drop_completed: true,
+            ..GraphFilter::default()
+        });
+        assert!(!live.nodes.contains_key("e"));
+        assert!(!live.adjacency.contains_key("d"));
+
+        let around_c = graph.prune(&GraphPrune::WithinHops {
+            key: String::from("c"),
+            hops: 1,
+        });
+        assert_eq!(keys(&around_c), ["a", "c"]);
+        assert_eq!(around_c.indegree["a"], 1);
+
+        let deadlocked = graph.prune(&GraphPrune::ReachableFromDeadlocks);
+        assert_eq!(keys(&deadlocked), ["a", "b", "l"]);
+    }
+}
@@ -0,0 +1,343 @@
+// r[impl api.snapshot.fuzz]
+//! Entry points for fuzzing the wait graph pipeline.
+//!
+//! [`snapshot_from_bytes`] turns arbitrary bytes into a well-formed snapshot,
+//! and [`check_invariants`] builds its wait graph, runs deadlock detection and
+//! checks what every graph and scan must satisfy. [`fuzz_one`] chains the two
+//! and panics on a violation, which is all a `cargo fuzz` target needs:
+//!
+//! ```ignore
+//! fuzz_target!(|data: &[u8]| moire_web::graph::fuzz::fuzz_one(data));
+//! ```
+//!
+//! A proptest strategy can produce byte vectors for [`check_invariants`] the
+//! same way.
+
+use std::collections::{HashMap, HashSet, VecDeque};
+
+use moire_types::{
+    BacktraceId, Edge, EdgeKind, EdgeReason, Entity, EntityBody, EntityId, FutureEntity, Json,
+    LockEntity, LockKind, MpscTxEntity, PTime, ProcessId, ProcessSnapshotView, RequestEntity,
+    SemaphoreEntity, Snapshot, SnapshotCutResponse,
+};
+
+use super::WaitGraph;
+use super::detect::{adjacency_with_holds, find_deadlock_candidates};
+
+/// Entities a decoded snapshot has at most, so inputs stay small enough for
+/// cycles between them to be likely.
+const MAX_FUZZ_ENTITIES: usize = 16;
+/// Where decoded processes' clocks stand.
+const FUZZ_NOW_MS: u64 = 60_000;
+/// How much later [`check_invariants`] looks at the same snapshot to check
+/// that waiting longer never makes a cycle less severe.
+const AGING_STEP_MS: u64 = 30_000;
+
+/// Decodes `data` into a single-process snapshot: the first byte picks how
+/// many entities there are, one byte each picks an entity's kind and age, and
+/// every following triple adds a `waiting_on`, `held_by` or `polls` edge
+/// between two of them. Any input decodes, and the same input always decodes
+/// to the same entities and edges.
+pub fn snapshot_from_bytes(data: &[u8]) -> SnapshotCutResponse {
+    let backtrace = BacktraceId::next().expect("backtrace id");
+    let mut bytes = data.iter().copied();
+    let count = bytes
+        .next()
+        .map_or(0, |b| usize::from(b) % MAX_FUZZ_ENTITIES + 1);
+
+    let mut entities = Vec::with_capacity(count);
+    for index in 0..count {
+        let b = bytes.next().unwrap_or(0);
+        let body = match b % 5 {
+            0 => EntityBody::Future(FutureEntity {
+                logical_id: Some(format!("{index:016x}")),
+                ..FutureEntity::default()
+            }),
+            1 => EntityBody::Future(FutureEntity::default()),
+            2 => EntityBody::Lock(LockEntity {
+                kind: LockKind::Mutex,
+                holds: None,
+                rwlock: None,
+            }),
+            3 => EntityBody::MpscTx(MpscTxEntity {
+                queue_len: u32::from(b >> 4),
+                capacity: Some(u32::from(b >> 4).max(1)),
+                send_waiters: Vec::new(),
+                history: Vec::new(),
+            }),
+            _ => EntityBody::Semaphore(SemaphoreEntity {
+                max_permits: 1,
+                handed_out_permits: 0,
+                holds: None,
+                holders: Vec::new(),
+                history: Vec::new(),
+            }),
+        };
+        let birth_ms = FUZZ_NOW_MS.saturating_sub(u64::from(b >> 3) * 2_000);
+        let mut entity = Entity::new_at(
+            backtrace,
+            format!("e{index}"),
+            body,
+            PTime::from_millis(birth_ms),
+        );
+        entity.id = EntityId::new(format!("e{index}"));
+        entities.push(entity);
+    }
+    // An RPC request gives cycles an external wake source to find.
+    if count > 1 && data.len().is_multiple_of(7) {
+        entities[count - 1].body = EntityBody::Request(RequestEntity {
+            service_name: String::from("fuzz"),
+            method_name: String::from("call"),
+            args_json: Json::new("[]"),
+            connection_generation: None,
+        });
+    }
+
+    let mut edges = Vec::new();
+    let mut seen = HashSet::new();
+    while let (Some(op), Some(a), Some(b)) = (bytes.next(), bytes.next(), bytes.next()) {
+        let (src, dst) = (usize::from(a) % count, usize::from(b) % count);
+        let kind = match op % 3 {
+            0 => EdgeKind::WaitingOn,
+            1 => EdgeKind::HeldBy,
+            _ => EdgeKind::Polls,
+        };
+        if !seen.insert((src, dst, kind)) {
+            continue;
+        }
+        edges.push(Edge {
+            src: entities[src].id.clone(),
+            dst: entities[dst].id.clone(),
+            backtrace,
+            kind,
+            since: Some(PTime::from_millis(
+                FUZZ_NOW_MS.saturating_sub(u64::from(op) * 200),
+            )),
+            reason: EdgeReason::infer(kind, &entities[dst].body),
+        });
+    }
+
+    SnapshotCutResponse {
+        snapshot_id: 1,
+        captured_at_unix_ms: 0,
+        max_skew_ms: None,
+        processes: vec![ProcessSnapshotView {
+            process_id: ProcessId::new("fuzz"),
+            process_name: String::from("fuzz"),
+            pid: 1,
+            ptime_now_ms: FUZZ_NOW_MS,
+            skew_ms: None,
+            snapshot: Snapshot {
+                entities,
+                scopes: Vec::new(),
+                edges,
+                events: Vec::new(),
+                workers: Vec::new(),
+                long_polls: Vec::new(),
+                rpc_methods: Vec::new(),
+                coverage: None,
+                runtime_stats: None,
+            },
+            scope_entity_links: Vec::new(),
+            timed_out_sections: None,
+            violations: Vec::new(),
+        }],
+        timed_out_processes: Vec::new(),
+        backtraces: Vec::new(),
+        frames: Vec::new(),
+        findings: Vec::new(),
+        annotations: Vec::new(),
+        sizing_hints: Vec::new(),
+        detection_config_version: None,
+        analysis_error: None,
+    }
+}
+
+/// Builds the wait graph of `snapshot`, runs a complete deadlock scan and
+/// checks that:
+///
+/// - every edge, adjacency entry and holder list names nodes of the graph,
+///   and indegrees count the edges into each node;
+/// - every candidate is a closed wait cycle: each of its nodes reaches every
+///   other through the waits and holds detection follows;
+/// - looking at the snapshot later, with every process's clock moved forward,
+///   yields the same candidates, none with a lower score.
+pub fn check_invariants(snapshot: &SnapshotCutResponse) -> Result<(), String> {
+    let graph = WaitGraph::build(snapshot)?;
+    check_graph(&graph)?;
+    let scan = find_deadlock_candidates(&graph, None);
+    if !scan.complete {
+        return Err(String::from(
+            "deadlock scan without a deadline is incomplete",
+        ));
+    }
+    let adjacency = adjacency_with_holds(&graph);
+    for candidate in &scan.candidates {
+        check_closed(&adjacency, &candidate.node_keys)?;
+    }
+
+    let mut later = snapshot_clone(snapshot)?;
+    for process in &mut later.processes {
+        process.ptime_now_ms += AGING_STEP_MS;
+    }
+    let later_graph = WaitGraph::build(&later)?;
+    let later_scores = find_deadlock_candidates(&later_graph, None)
+        .candidates
+        .into_iter()
+        .map(|candidate| (candidate.node_keys, candidate.score.total()))
+        .collect::<HashMap<_, _>>();
+    if later_scores.len() != scan.candidates.len() {
+        return Err(format!(
+            "{} candidate(s) became {} after {AGING_STEP_MS}ms",
+            scan.candidates.len(),
+            later_scores.len()
+        ));
+    }
+    for candidate in &scan.candidates {
+        let Some(&later_score) = later_scores.get(&candidate.node_keys) else {
+            return Err(format!(
+                "cycle {} disappeared after {AGING_STEP_MS}ms",
+                candidate.node_keys.join(" -> ")
+            ));
+        };
+        if later_score < candidate.score.total() {
+            return Err(format!(
+                "cycle {} scored {} and then {later_score} after waiting {AGING_STEP_MS}ms longer",
+                candidate.node_keys.join(" -> "),
+                candidate.score.total()
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Decodes `data` with [`snapshot_from_bytes`] and panics if
+/// [`check_invariants`] finds a violation.
+pub fn fuzz_one(data: &[u8]) {
+    let snapshot = snapshot_from_bytes(data);
+    if let Err(violation) = check_invariants(&snapshot) {
+        panic!("wait graph invariant violated for input {data:?}: {violation}");
+    }
+}
+
+fn check_graph(graph: &WaitGraph) -> Result<(), String> {
+    let node = |key: &str, what: &str| {
+        if graph.nodes.contains_key(key) {
+            Ok(())
+        } else {
+            Err(format!("{what} names {key}, which is not a node"))
+        }
+    };
+    let mut indegree: HashMap<&str, usize> = HashMap::new();
+    for edge in &graph.edges {
+        node(&edge.src_key, "a wait edge")?;
+        node(&edge.dst_key, "a wait edge")?;
+        *indegree.entry(edge.dst_key.as_str()).or_default() += 1;
+    }
+    for (src, dsts) in &graph.adjacency {
+        node(src, "the adjacency")?;
+        for dst in dsts {
+            node(dst, "the adjacency")?;
+        }
+    }
+    for (key, &count) in &graph.indegree {
+        node(key, "the indegree map")?;
+        let edges = indegree.get(key.as_str()).copied().unwrap_or(0);
+        if count != edges {
+            return Err(format!(
+                "{key} has indegree {count} but {edges} edge(s) into it"
+            ));
+        }
+    }
+    for key in graph.holders.keys() {
+        node(key, "the holders map")?;
+    }
+    Ok(())
+}
+
+/// Checks that every node of `node_keys` reaches every other within the
+/// component, following `adjacency` forwards and backwards from the first.
+fn check_closed(
+    adjacency: &HashMap<String, Vec<String>>,
+    node_keys: &[String],
+) -> Result<(), String> {
+    let Some(start) = node_keys.first() else {
+        return Err(String::from("empty deadlock candidate"));
+    };
+    let mut forward: HashMap<&str, Vec<&str>> = HashMap::new();
+    let mut reverse: HashMap<&str, Vec<&str>> = HashMap::new();
+    for (src, dsts) in adjacency {
+        for dst in dsts {
+            forward.entry(src.as_str()).or_default().push(dst.as_str());
+            reverse.entry(dst.as_str()).or_default().push(src.as_str());
+        }
+    }
+    if node_keys.len() == 1
+        && !forward
+            .get(start.as_str())
+            .is_some_and(|dsts| dsts.contains(&start.as_str()))
+    {
+        return Err(format!("single-node cycle {start} has no self-loop"));
+    }
+
+    let members = node_keys.iter().map(String::as_str).collect::<HashSet<_>>();
+    for (direction, next) in [("reach", &forward), ("be reached from", &reverse)] {
+        let mut seen = HashSet::from([start.as_str()]);
+        let mut queue = VecDeque::from([start.as_str()]);
+        while let Some(key) = queue.pop_front() {
+            for &dst in next.get(key).into_iter().flatten() {
+                if members.contains(dst) && seen.insert(dst) {
+                    queue.push_back(dst);
+                }
+            }
+        }
+        if let Some(missing) = node_keys.iter().find(|key| !seen.contains(key.as_str())) {
+            return Err(format!(
+                "cycle {} is not closed: {start} does not {direction} {missing}",
+                node_keys.join(" -> ")
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Snapshots are not `Clone`; a round trip through the dump encoding copies
+/// one.
+fn snapshot_clone(snapshot: &SnapshotCutResponse) -> Result<SnapshotCutResponse, String> {
+    moire_types::snapshot_from_json(&moire_types::snapshot_to_json(snapshot)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // r[verify api.snapshot.fuzz]
+    #[test]
+    fn decoded_lock_order_inversion_is_a_closed_cycle() {
+        // Tasks e0 and e1 hold locks e2 and e3 respectively, then each waits
+        // on the other's lock.
+        let data = [3, 0, 0, 2, 2, 1, 2, 0, 1, 3, 1, 3, 0, 3, 3, 1, 2];
+        let snapshot = snapshot_from_bytes(&data[..]);
+        let graph = WaitGraph::build(&snapshot).unwrap();
+        let scan = find_deadlock_candidates(&graph, None);
+        assert_eq!(scan.candidates.len(), 1);
+        check_invariants(&snapshot).unwrap();
+    }
+
+    #[test]
+    fn invariants_hold_over_generated_inputs() {
+        // xorshift64, so the sweep is the same on every run.
+        let mut state = 0x9e37_79b9_7f4a_7c15_u64;
+        let mut next = move || {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            state
+        };
+        for _ in 0..500 {
+            let len = (next() % 64) as usize;
+            let data = (0..len).map(|_| next() as u8).collect::<Vec<_>>();
+            fuzz_one(&data);
+        }
+    }
+}
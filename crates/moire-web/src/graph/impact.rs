@@ -0,0 +1,207 @@
+//! What would break if one process restarted right now.
+//!
+//! Everything inside the process goes away with it, so the report is about the
+//! rest of the system: RPCs it is serving and the client tasks waiting on them,
+//! nodes of the process that other processes are currently waiting on, and
+//! deadlock candidates the process participates in.
+
+use std::collections::{BTreeSet, HashMap};
+
+use super::WaitGraph;
+use super::detect::find_deadlock_candidates;
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ImpactReport {
+    pub process_id: String,
+    /// Incoming RPCs the process has not answered yet.
+    pub incoming_rpcs: Vec<InflightRpc>,
+    /// Nodes of the process that something in another process is waiting on.
+    pub depended_on: Vec<DependedOn>,
+    /// Deadlock candidates with at least one node in the process, most severe first.
+    pub deadlock_candidates: Vec<Vec<String>>,
+    /// Other processes with an RPC in flight to this one, sorted.
+    pub affected_processes: Vec<String>,
+}
+
+impl ImpactReport {
+    /// Whether the graph shows nothing that depends on the process.
+    pub fn is_empty(&self) -> bool {
+        self.incoming_rpcs.is_empty()
+            && self.depended_on.is_empty()
+            && self.deadlock_candidates.is_empty()
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct InflightRpc {
+    pub method: String,
+    pub response_key: String,
+    pub client_process_id: String,
+    pub request_key: String,
+    /// Client-side nodes waiting on the request, sorted.
+    pub client_waiters: Vec<String>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DependedOn {
+    pub key: String,
+    pub name: String,
+    pub kind: String,
+    /// Nodes of other processes waiting on this one, sorted.
+    pub waiters: Vec<String>,
+}
+
+impl WaitGraph {
+    /// Estimates what depends on `process_id` staying up. Deadlock detection runs
+    /// without a deadline.
+    pub fn restart_impact(&self, process_id: &str) -> ImpactReport {
+        let mut waiters_by_node: HashMap<&str, Vec<&str>> = HashMap::new();
+        for (src, outs) in &self.adjacency {
+            for dst in outs {
+                waiters_by_node
+                    .entry(dst.as_str())
+                    .or_default()
+                    .push(src.as_str());
+            }
+        }
+        let waiters_of = |key: &str, outside: Option<&str>| {
+            let mut waiters = waiters_by_node
+                .get(key)
+                .into_iter()
+                .flatten()
+                .filter(|waiter| {
+                    outside.is_none_or(|process_id| {
+                        self.nodes
+                            .get(**waiter)
+                            .is_some_and(|node| node.process_id != process_id)
+                    })
+                })
+                .map(|waiter| String::from(*waiter))
+                .collect::<Vec<_>>();
+            waiters.sort();
+            waiters.dedup();
+            waiters
+        };
+
+        let mut incoming_rpcs = Vec::new();
+        let mut affected_processes = BTreeSet::new();
+        for link in &self.inflight_rpcs {
+            if link.server_process_id != process_id {
+                continue;
+            }
+            if link.client_process_id != process_id {
+                affected_processes.insert(link.client_process_id.clone());
+            }
+            incoming_rpcs.push(InflightRpc {
+                method: link.method.clone(),
+                response_key: link.response_key.clone(),
+                client_process_id: link.client_process_id.clone(),
+                request_key: link.request_key.clone(),
+                client_waiters: waiters_of(&link.request_key, None),
+            });
+        }
+
+        let mut depended_on = self
+            .nodes
+            .iter()
+            .filter(|(_, node)| node.process_id == process_id)
+            .filter_map(|(key, node)| {
+                // Waiters inside the process go down with it.
+                let waiters = waiters_of(key, Some(process_id));
+                (!waiters.is_empty()).then(|| DependedOn {
+                    key: key.clone(),
+                    name: node.name.clone(),
+                    kind: node.kind.clone(),
+                    waiters,
+                })
+            })
+            .collect::<Vec<_>>();
+        depended_on.sort_by(|a, b| {
+            b.waiters
+                .len()
+                .cmp(&a.waiters.len())
+                .then_with(|| a.key.cmp(&b.key))
+        });
+
+        let deadlock_candidates = find_deadlock_candidates(self, None)
+            .candidates
+            .into_iter()
+            .filter(|candidate| {
+                candidate.node_keys.iter().any(|key| {
+                    self.nodes
+                        .get(key)
+                        .is_some_and(|node| node.process_id == process_id)
+                })
+            })
+            .map(|candidate| candidate.node_keys)
+            .collect();
+
+        ImpactReport {
+            process_id: String::from(process_id),
+            incoming_rpcs,
+            depended_on,
+            deadlock_candidates,
+            affected_processes: affected_processes.into_iter().collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph::{RpcLink, WaitNode};
+
+    fn node(process_id: &str, entity_id: &str, kind: &str) -> WaitNode {
+        WaitNode::test(process_id, entity_id, kind).at(1_000)
+    }
+
+    #[test]
+    fn restart_impact_follows_rpcs_to_client_waiters() {
+        let nodes = [
+            ("client", "task", "future"),
+            ("client", "req", "request"),
+            ("server", "handler", "future"),
+            ("server", "resp", "response"),
+            ("server", "lock", "lock"),
+        ]
+        .map(|(process_id, entity_id, kind)| {
+            (
+                format!("{process_id}::{entity_id}"),
+                node(process_id, entity_id, kind),
+            )
+        });
+        let mut graph = WaitGraph::test(
+            nodes,
+            [
+                ("client::task", "client::req"),
+                ("client::req", "server::resp"),
+                // Only the server waits on its own lock, so it does not outlive a restart.
+                ("server::handler", "server::lock"),
+            ],
+        );
+        graph.inflight_rpcs.push(RpcLink {
+            method: String::from("vfs.lookupItem"),
+            server_process_id: String::from("server"),
+            response_key: String::from("server::resp"),
+            client_process_id: String::from("client"),
+            request_key: String::from("client::req"),
+        });
+
+        let report = graph.restart_impact("server");
+        assert_eq!(report.affected_processes, vec!["client"]);
+        assert_eq!(report.incoming_rpcs.len(), 1);
+        assert_eq!(report.incoming_rpcs[0].client_waiters, vec!["client::task"]);
+        assert_eq!(report.depended_on.len(), 1);
+        assert_eq!(report.depended_on[0].key, "server::resp");
+        assert_eq!(report.depended_on[0].waiters, vec!["client::req"]);
+        assert!(
+            report
+                .depended_on
+                .iter()
+                .all(|depended_on| depended_on.key != "server::lock")
+        );
+        assert!(report.deadlock_candidates.is_empty());
+
+        assert!(graph.restart_impact("client").incoming_rpcs.is_empty());
+    }
+}
@@ -0,0 +1,1615 @@
+//! Wait graph model built from a snapshot cut.
+//!
+//! Only `waiting_on` edges participate. Nodes are keyed by `process_id::entity_id`
+//! so entities from different processes never collide; actor members collapse into
+//! one composite node keyed by their driver future. Futures woken but not
+//! polled since also wait on a `run_queue` pseudo-resource for the worker
+//! thread that last polled them, and senders an mpsc channel lists as blocked
+//! wait on it even without a `waiting_on` edge.
+//! The holders of a oneshot's sender count as holders of its receiver.
+//!
+//! [`WaitGraph::build`] takes a whole cut; [`WaitGraphBuilder`] keeps a graph
+//! current as dumps of single processes arrive.
+
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+use std::time::Instant;
+
+use moire_graph_core::{
+    EdgeKind as CoreEdgeKind, EdgeRow, NodeId, NodeRow, WaitGraphCore, entity_kind_wire_name,
+};
+use moire_trace_types::FrameId;
+use moire_types::{
+    EdgeKind, EdgeReason, Entity, EntityBody, EntityId, EventKind, EventTarget, ProcessId,
+    ProcessSnapshotView, ResponseStatus, Scope, ScopeBody, SnapshotBacktrace,
+    SnapshotBacktraceFrame, SnapshotCutResponse,
+};
+
+use self::severity::{DefaultSeverityPolicy, SeverityPolicy};
+
+pub mod analysis;
+pub mod config;
+pub(crate) mod detect;
+pub mod diff;
+pub mod explain;
+pub mod export;
+pub mod filter;
+pub mod fuzz;
+pub mod impact;
+pub mod report;
+pub mod severity;
+pub mod sqlite;
+pub mod visit;
+pub mod waiters;
+
+pub(crate) const SOURCE_FRAMES_PER_ITEM: usize = 3;
+const SYSTEM_CRATES: &[&str] = &[
+    "std",
+    "core",
+    "alloc",
+    "tokio",
+    "tokio_util",
+    "futures",
+    "futures_core",
+    "futures_util",
+    "moire",
+    "moire_trace_capture",
+    "moire_runtime",
+    "moire_tokio",
+];
+
+#[derive(Clone)]
+pub struct WaitNode {
+    pub process_id: String,
+    pub ptime_now_ms: u64,
+    pub entity_id: String,
+    pub name: String,
+    pub kind: String,
+    pub birth_ms: u64,
+    pub frame_ids: Vec<FrameId>,
+    pub wakes: WakeCounts,
+    pub holds: HoldCounts,
+    /// Reason the node was marked as an expected, possibly endless wait.
+    pub idle: Option<String>,
+    /// When the entity was removed, for entities the snapshot still retains
+    /// after they completed or were dropped.
+    pub removed_ms: Option<u64>,
+}
+
+impl WaitNode {
+    /// This node's key in [`WaitGraph::nodes`], as [`compose_node_key`]
+    /// builds it.
+    pub(crate) fn key(&self) -> String {
+        node_key(&self.process_id, &self.entity_id)
+    }
+}
+
+#[cfg(test)]
+impl WaitNode {
+    /// A bare node for test fixtures: named after its entity, born and observed
+    /// at 0, with no frames, wakes or holds.
+    pub(crate) fn test(process_id: &str, entity_id: &str, kind: &str) -> Self {
+        Self {
+            process_id: String::from(process_id),
+            ptime_now_ms: 0,
+            entity_id: String::from(entity_id),
+            name: String::from(entity_id),
+            kind: String::from(kind),
+            birth_ms: 0,
+            frame_ids: Vec::new(),
+            wakes: WakeCounts::default(),
+            holds: HoldCounts::default(),
+            idle: None,
+            removed_ms: None,
+        }
+    }
+
+    /// Observes the node at `ptime_now_ms` on its process clock.
+    pub(crate) fn at(mut self, ptime_now_ms: u64) -> Self {
+        self.ptime_now_ms = ptime_now_ms;
+        self
+    }
+
+    /// Backdates the birth so the node is `age_ms` old when observed.
+    pub(crate) fn aged(mut self, age_ms: u64) -> Self {
+        self.birth_ms = self.ptime_now_ms.saturating_sub(age_ms);
+        self
+    }
+}
+
+#[cfg(test)]
+impl WaitGraph {
+    /// A graph for test fixtures over keyed `nodes`, with one wait edge per
+    /// `(src, dst)` key pair. Both ends of every edge must be in `nodes`; edges
+    /// carry no frames, timestamp or reason, and nothing holds anything.
+    pub(crate) fn test(
+        nodes: impl IntoIterator<Item = (impl Into<String>, WaitNode)>,
+        edges: impl IntoIterator<Item = (impl Into<String>, impl Into<String>)>,
+    ) -> Self {
+        let mut graph = Self::empty(Arc::new(DefaultSeverityPolicy));
+        graph.nodes = nodes
+            .into_iter()
+            .map(|(key, node)| (key.into(), node))
+            .collect();
+        for (src_key, dst_key) in edges {
+            let (src_key, dst_key) = (src_key.into(), dst_key.into());
+            graph.edges.push(WaitEdgeRuntime {
+                process_id: graph.nodes[&src_key].process_id.clone(),
+                src_key: src_key.clone(),
+                dst_key: dst_key.clone(),
+                dst_entity_id: graph.nodes[&dst_key].entity_id.clone(),
+                edge_frame_ids: Vec::new(),
+                since_ms: None,
+                reason: None,
+            });
+            graph
+                .adjacency
+                .entry(src_key.clone())
+                .or_default()
+                .push(dst_key.clone());
+            *graph.indegree.entry(dst_key).or_insert(0) += 1;
+            graph.indegree.entry(src_key).or_insert(0);
+        }
+        graph
+    }
+}
+
+/// Wake counters reported by instrumented futures; zero for other kinds.
+#[derive(Clone, Default)]
+pub struct WakeCounts {
+    pub wake_count: u64,
+    pub unproductive_wake_count: u64,
+    /// Node key of the future that was being polled when this one was last woken.
+    pub last_woken_by: Option<String>,
+}
+
+/// Hold statistics reported by locks and semaphores; zero for other kinds.
+#[derive(Clone, Default)]
+pub struct HoldCounts {
+    pub hold_count: u64,
+    pub avg_hold_ms: u64,
+    /// When the most recent of the current holders acquired the resource.
+    pub latest_acquired_ms: Option<u64>,
+    /// Read guards currently held; read-write locks only.
+    pub readers: u32,
+    /// Tasks queued for a write guard; read-write locks only.
+    pub waiting_writers: u32,
+}
+
+#[derive(Clone)]
+pub struct WaitEdgeRuntime {
+    pub process_id: String,
+    pub src_key: String,
+    pub dst_key: String,
+    pub dst_entity_id: String,
+    pub edge_frame_ids: Vec<FrameId>,
+    /// When the waiter started waiting, if the recording carries edge timestamps.
+    pub since_ms: Option<u64>,
+    /// Why the waiter waits, if the recording carries edge reasons.
+    pub reason: Option<EdgeReason>,
+}
+
+/// An RPC still being served: a pending response paired with the request it answers.
+///
+/// Requests and responses usually live in different processes; entity ids are
+/// unique across processes, so the pairing edge resolves without process hints.
+/// Either side bound to an older generation of its connection than the current
+/// one is left out: it belongs to a connection that has since been replaced.
+#[derive(Clone)]
+pub struct RpcLink {
+    pub method: String,
+    pub server_process_id: String,
+    pub response_key: String,
+    pub client_process_id: String,
+    pub request_key: String,
+}
+
+/// Entities of one actor scope, resolved against a process snapshot.
+pub(crate) struct ActorMembers<'a> {
+    pub(crate) scope: &'a Scope,
+    pub(crate) mailbox_capacity: Option<u32>,
+    pub(crate) driver: Option<&'a Entity>,
+    pub(crate) mailbox_tx: Option<&'a Entity>,
+    pub(crate) mailbox_rx: Option<&'a Entity>,
+}
+
+/// Waiting-on graph across every process of one snapshot.
+pub struct WaitGraph {
+    /// Nodes keyed by `process_id::entity_id`.
+    pub nodes: HashMap<String, WaitNode>,
+    pub edges: Vec<WaitEdgeRuntime>,
+    /// Waiter key to the keys it waits on.
+    pub adjacency: HashMap<String, Vec<String>>,
+    pub indegree: HashMap<String, usize>,
+    /// Resource key to the nodes currently holding it, from `held_by` edges,
+    /// sorted by entity id. Only resources in `nodes` are listed; holders
+    /// need not wait on anything, so they may be missing from `nodes`.
+    pub holders: HashMap<String, Vec<WaitNode>>,
+    /// Pending RPCs, sorted by response key.
+    pub inflight_rpcs: Vec<RpcLink>,
+    /// Scores every wait edge of the graph.
+    pub severity_policy: Arc<dyn SeverityPolicy>,
+}
+
+impl WaitGraph {
+    pub fn build(snapshot: &SnapshotCutResponse) -> Result<Self, String> {
+        Self::build_with_policy(snapshot, Arc::new(DefaultSeverityPolicy))
+    }
+
+    /// Like [`WaitGraph::build`], scoring wait edges with `policy` instead of
+    /// [`DefaultSeverityPolicy`].
+    pub fn build_with_policy(
+        snapshot: &SnapshotCutResponse,
+        severity_policy: Arc<dyn SeverityPolicy>,
+    ) -> Result<Self, String> {
+        let backtrace_index = backtrace_index(snapshot);
+        let frame_catalog = frame_catalog(snapshot);
+
+        let mut graph = Self::empty(severity_policy);
+        let mut rpc_ends = Vec::with_capacity(snapshot.processes.len());
+        for process in &snapshot.processes {
+            let part = process_part(process, &backtrace_index, &frame_catalog)?;
+            rpc_ends.push(graph.insert_process(part));
+        }
+        graph.inflight_rpcs = link_rpcs(&rpc_ends);
+        Ok(graph)
+    }
+
+    pub(crate) fn empty(severity_policy: Arc<dyn SeverityPolicy>) -> Self {
+        Self {
+            nodes: HashMap::new(),
+            edges: Vec::new(),
+            adjacency: HashMap::new(),
+            indegree: HashMap::new(),
+            holders: HashMap::new(),
+            inflight_rpcs: Vec::new(),
+            severity_policy,
+        }
+    }
+
+    /// Adds the nodes and wait edges of `part`, whose process must not be in
+    /// the graph yet, and hands back its RPC ends for [`link_rpcs`].
+    fn insert_process(&mut self, part: ProcessPart) -> RpcEnds {
+        self.nodes.extend(part.nodes);
+        self.edges.extend(part.edges);
+        self.adjacency.extend(part.adjacency);
+        self.indegree.extend(part.indegree);
+        self.holders.extend(part.holders);
+        part.rpc
+    }
+
+    /// Drops every node and wait edge of process `process_id`. Wait edges
+    /// never cross processes, so nothing left refers to them; RPC links are
+    /// the caller's to recompute.
+    fn remove_process(&mut self, process_id: &str) {
+        self.nodes.retain(|_, node| node.process_id != process_id);
+        self.edges.retain(|edge| edge.process_id != process_id);
+        let nodes = &self.nodes;
+        self.adjacency.retain(|key, _| nodes.contains_key(key));
+        self.indegree.retain(|key, _| nodes.contains_key(key));
+        self.holders.retain(|key, _| nodes.contains_key(key));
+    }
+
+    /// Severity of the wait edge from `src` to `dst` under the graph's
+    /// [`SeverityPolicy`].
+    pub fn edge_severity(&self, src: &WaitNode, dst: &WaitNode) -> u32 {
+        self.severity_policy.edge_score(src, dst).total()
+    }
+}
+
+// r[impl api.snapshot.incremental]
+/// Keeps a [`WaitGraph`] current as dumps arrive one process at a time, for
+/// collectors that would otherwise rebuild the whole graph on every dump.
+///
+/// Applying a dump replaces what each of its processes contributed before and
+/// walks only that dump's entities and edges. Wait edges never cross
+/// processes, so the rest of the graph stands; RPC links are paired again
+/// from every process's latest requests and responses. The result has the
+/// nodes, edges and links [`WaitGraph::build`] finds in a cut of each
+/// process's latest dump.
+pub struct WaitGraphBuilder {
+    graph: WaitGraph,
+    /// RPC ends of every process applied, in the order they first arrived.
+    rpc_ends: Vec<RpcEnds>,
+}
+
+impl Default for WaitGraphBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl WaitGraphBuilder {
+    pub fn new() -> Self {
+        Self::with_policy(Arc::new(DefaultSeverityPolicy))
+    }
+
+    /// Like [`WaitGraphBuilder::new`], scoring wait edges with `policy`
+    /// instead of [`DefaultSeverityPolicy`].
+    pub fn with_policy(severity_policy: Arc<dyn SeverityPolicy>) -> Self {
+        Self {
+            graph: WaitGraph::empty(severity_policy),
+            rpc_ends: Vec::new(),
+        }
+    }
+
+    /// Upserts every process of `dump`, dropping what it contributed before.
+    /// Processes `dump` leaves out keep their last contribution. Fails, and
+    /// changes nothing, on the errors [`WaitGraph::build`] reports.
+    pub fn apply(&mut self, dump: &SnapshotCutResponse) -> Result<(), String> {
+        let backtrace_index = backtrace_index(dump);
+        let frame_catalog = frame_catalog(dump);
+        let parts = dump
+            .processes
+            .iter()
+            .map(|process| process_part(process, &backtrace_index, &frame_catalog))
+            .collect::<Result<Vec<_>, String>>()?;
+        for part in parts {
+            let process_id = part.rpc.process_id.clone();
+            self.graph.remove_process(&process_id);
+            let ends = self.graph.insert_process(part);
+            match self
+                .rpc_ends
+                .iter_mut()
+                .find(|ends| ends.process_id == process_id)
+            {
+                Some(slot) => *slot = ends,
+                None => self.rpc_ends.push(ends),
+            }
+        }
+        self.graph.inflight_rpcs = link_rpcs(&self.rpc_ends);
+        Ok(())
+    }
+
+    /// Drops everything process `process_id` contributed, as when it
+    /// disconnects. Returns `false` if no dump of it was applied.
+    pub fn remove(&mut self, process_id: &str) -> bool {
+        let Some(index) = self
+            .rpc_ends
+            .iter()
+            .position(|ends| ends.process_id == process_id)
+        else {
+            return false;
+        };
+        self.rpc_ends.remove(index);
+        self.graph.remove_process(process_id);
+        self.graph.inflight_rpcs = link_rpcs(&self.rpc_ends);
+        true
+    }
+
+    pub fn graph(&self) -> &WaitGraph {
+        &self.graph
+    }
+
+    pub fn into_graph(self) -> WaitGraph {
+        self.graph
+    }
+}
+
+impl WaitGraph {
+    /// Rebuilds a wait graph from a canonical graph shipped by a lightweight
+    /// agent. Frames, wake and hold counters, holders, actors and RPC links
+    /// are not part of the core model and come out empty.
+    pub fn from_core(core: &WaitGraphCore) -> Self {
+        let nodes = core
+            .nodes
+            .values()
+            .map(|row| {
+                let node = WaitNode {
+                    process_id: row.id.process_id.clone(),
+                    ptime_now_ms: row.ptime_now_ms,
+                    entity_id: row.id.entity_id.clone(),
+                    name: row.name.clone(),
+                    kind: row.kind.clone(),
+                    birth_ms: row.birth_ms,
+                    frame_ids: Vec::new(),
+                    wakes: WakeCounts::default(),
+                    holds: HoldCounts::default(),
+                    idle: None,
+                    removed_ms: None,
+                };
+                (row.id.key(), node)
+            })
+            .collect();
+
+        let mut edges = Vec::with_capacity(core.edges.len());
+        let mut adjacency: HashMap<String, Vec<String>> = HashMap::new();
+        let mut indegree: HashMap<String, usize> = HashMap::new();
+        for edge in &core.edges {
+            let src_key = edge.src.key();
+            let dst_key = edge.dst.key();
+            edges.push(WaitEdgeRuntime {
+                process_id: edge.src.process_id.clone(),
+                src_key: src_key.clone(),
+                dst_key: dst_key.clone(),
+                dst_entity_id: edge.dst.entity_id.clone(),
+                edge_frame_ids: Vec::new(),
+                since_ms: edge.since_ms,
+                reason: None,
+            });
+            adjacency
+                .entry(src_key.clone())
+                .or_default()
+                .push(dst_key.clone());
+            *indegree.entry(dst_key).or_insert(0) += 1;
+            indegree.entry(src_key).or_insert(0);
+        }
+
+        Self {
+            nodes,
+            edges,
+            adjacency,
+            indegree,
+            holders: HashMap::new(),
+            inflight_rpcs: Vec::new(),
+            severity_policy: Arc::new(DefaultSeverityPolicy),
+        }
+    }
+
+    /// Canonical form of the graph, for tools written against the core
+    /// model. A graph from [`WaitGraph::from_core`] converts back to the core
+    /// graph it came from; other graphs lose frames, counters, holders, RPC
+    /// links and nodes no wait edge touches. Fails if an edge crosses
+    /// processes, which the core model does not allow.
+    pub fn to_core(&self) -> Result<WaitGraphCore, String> {
+        let node_id = |key: &str| {
+            self.nodes
+                .get(key)
+                .map(|node| NodeId::new(node.process_id.as_str(), node.entity_id.as_str()))
+                .ok_or_else(|| format!("wait edge endpoint {key} is not a node"))
+        };
+        let nodes = self
+            .nodes
+            .values()
+            .map(|node| NodeRow {
+                id: NodeId::new(node.process_id.as_str(), node.entity_id.as_str()),
+                name: node.name.clone(),
+                kind: node.kind.clone(),
+                birth_ms: node.birth_ms,
+                ptime_now_ms: node.ptime_now_ms,
+            })
+            .collect::<Vec<_>>();
+        let edges = self
+            .edges
+            .iter()
+            .map(|edge| {
+                Ok(EdgeRow {
+                    src: node_id(&edge.src_key)?,
+                    dst: node_id(&edge.dst_key)?,
+                    kind: CoreEdgeKind::WaitingOn,
+                    since_ms: edge.since_ms,
+                })
+            })
+            .collect::<Result<Vec<_>, String>>()?;
+        WaitGraphCore::from_rows(nodes, edges)
+    }
+}
+
+/// What one process contributes to a [`WaitGraph`]. Every key in it is one
+/// of the process's nodes.
+struct ProcessPart {
+    nodes: HashMap<String, WaitNode>,
+    edges: Vec<WaitEdgeRuntime>,
+    adjacency: HashMap<String, Vec<String>>,
+    indegree: HashMap<String, usize>,
+    holders: HashMap<String, Vec<WaitNode>>,
+    rpc: RpcEnds,
+}
+
+fn process_part(
+    process: &ProcessSnapshotView,
+    backtrace_index: &HashMap<u64, &SnapshotBacktrace>,
+    frame_catalog: &HashMap<u64, &SnapshotBacktraceFrame>,
+) -> Result<ProcessPart, String> {
+    let mut nodes: HashMap<String, WaitNode> = HashMap::new();
+    let mut edges: Vec<WaitEdgeRuntime> = Vec::new();
+    let mut adjacency: HashMap<String, Vec<String>> = HashMap::new();
+    let mut indegree: HashMap<String, usize> = HashMap::new();
+    let mut seen_edges: HashSet<(String, String)> = HashSet::new();
+    let mut holders: HashMap<String, Vec<WaitNode>> = HashMap::new();
+
+    let local_entities: HashMap<String, &Entity> = process
+        .snapshot
+        .entities
+        .iter()
+        .map(|entity| (entity.id.as_str().to_owned(), entity))
+        .collect();
+    let actor_of = actor_composites(process);
+
+    for edge in &process.snapshot.edges {
+        if edge.kind != EdgeKind::WaitingOn {
+            continue;
+        }
+
+        let Some(src) = local_entities.get(edge.src.as_str()) else {
+            return Err(format!(
+                "invariant violated: missing src entity {} for waiting_on edge in process {}",
+                edge.src.as_str(),
+                process.process_id.as_str()
+            ));
+        };
+        let Some(dst) = local_entities.get(edge.dst.as_str()) else {
+            return Err(format!(
+                "invariant violated: missing dst entity {} for waiting_on edge in process {}",
+                edge.dst.as_str(),
+                process.process_id.as_str()
+            ));
+        };
+
+        let src_actor = actor_of.get(src.id.as_str());
+        let dst_actor = actor_of.get(dst.id.as_str());
+        let src_key = compose_node_key(
+            &process.process_id,
+            src_actor.map_or(&src.id, |(_, driver)| &driver.id),
+        );
+        let dst_key = compose_node_key(
+            &process.process_id,
+            dst_actor.map_or(&dst.id, |(_, driver)| &driver.id),
+        );
+        // A driver waiting on its own mailbox is the actor idling, not a wait edge.
+        if src_actor.is_some() && src_key == dst_key {
+            continue;
+        }
+
+        nodes
+            .entry(src_key.clone())
+            .or_insert_with(|| match src_actor {
+                Some((scope, driver)) => {
+                    actor_wait_node(process, scope, driver, backtrace_index, frame_catalog)
+                }
+                None => wait_node(process, src, backtrace_index, frame_catalog),
+            });
+        nodes
+            .entry(dst_key.clone())
+            .or_insert_with(|| match dst_actor {
+                Some((scope, driver)) => {
+                    actor_wait_node(process, scope, driver, backtrace_index, frame_catalog)
+                }
+                None => wait_node(process, dst, backtrace_index, frame_catalog),
+            });
+
+        if seen_edges.insert((src_key.clone(), dst_key.clone())) {
+            edges.push(WaitEdgeRuntime {
+                process_id: process.process_id.as_str().to_owned(),
+                src_key: src_key.clone(),
+                dst_key: dst_key.clone(),
+                dst_entity_id: dst.id.as_str().to_owned(),
+                edge_frame_ids: selected_frames_for_backtrace_id(
+                    edge.backtrace.as_u64(),
+                    backtrace_index,
+                    frame_catalog,
+                    frame_start_index_for_entity(src),
+                    SOURCE_FRAMES_PER_ITEM,
+                ),
+                since_ms: edge.since.map(|since| since.as_millis()),
+                reason: edge.reason,
+            });
+            adjacency
+                .entry(src_key.clone())
+                .or_default()
+                .push(dst_key.clone());
+            *indegree.entry(dst_key).or_insert(0) += 1;
+            indegree.entry(src_key).or_insert(0);
+        }
+    }
+
+    // Whoever holds a oneshot's sender is what its receiver waits on.
+    let oneshot_rx_of: HashMap<&str, &EntityId> = process
+        .snapshot
+        .edges
+        .iter()
+        .filter(|edge| edge.kind == EdgeKind::PairedWith)
+        .filter(|edge| {
+            local_entities
+                .get(edge.src.as_str())
+                .is_some_and(|src| matches!(src.body, EntityBody::OneshotTx(_)))
+        })
+        .map(|edge| (edge.src.as_str(), &edge.dst))
+        .collect();
+
+    for edge in &process.snapshot.edges {
+        if edge.kind != EdgeKind::HeldBy {
+            continue;
+        }
+        let (Some(resource), Some(holder)) = (
+            local_entities.get(edge.src.as_str()),
+            local_entities.get(edge.dst.as_str()),
+        ) else {
+            continue;
+        };
+        let resource_id = oneshot_rx_of
+            .get(resource.id.as_str())
+            .copied()
+            .unwrap_or(&resource.id);
+        let resource_key = compose_node_key(
+            &process.process_id,
+            actor_of
+                .get(resource_id.as_str())
+                .map_or(resource_id, |(_, driver)| &driver.id),
+        );
+        let holder = match actor_of.get(holder.id.as_str()) {
+            Some((scope, driver)) => {
+                actor_wait_node(process, scope, driver, backtrace_index, frame_catalog)
+            }
+            None => wait_node(process, holder, backtrace_index, frame_catalog),
+        };
+        holders.entry(resource_key).or_default().push(holder);
+    }
+
+    // r[impl model.future.run-queue]
+    for entity in &process.snapshot.entities {
+        let EntityBody::Future(future) = &entity.body else {
+            continue;
+        };
+        let (Some(gap_ms), Some(worker)) = (future.wake_to_poll_gap_ms, future.poll_worker) else {
+            continue;
+        };
+        if entity.removed_at.is_some() {
+            continue;
+        }
+        let src_actor = actor_of.get(entity.id.as_str());
+        let src_key = compose_node_key(
+            &process.process_id,
+            src_actor.map_or(&entity.id, |(_, driver)| &driver.id),
+        );
+        let queue = run_queue_node(process, worker);
+        let queue_id = EntityId::new(queue.entity_id.as_str());
+        let dst_key = compose_node_key(&process.process_id, &queue_id);
+        nodes
+            .entry(src_key.clone())
+            .or_insert_with(|| match src_actor {
+                Some((scope, driver)) => {
+                    actor_wait_node(process, scope, driver, backtrace_index, frame_catalog)
+                }
+                None => wait_node(process, entity, backtrace_index, frame_catalog),
+            });
+        nodes.entry(dst_key.clone()).or_insert(queue);
+        if seen_edges.insert((src_key.clone(), dst_key.clone())) {
+            edges.push(WaitEdgeRuntime {
+                process_id: process.process_id.as_str().to_owned(),
+                src_key: src_key.clone(),
+                dst_key: dst_key.clone(),
+                dst_entity_id: queue_id.as_str().to_owned(),
+                edge_frame_ids: Vec::new(),
+                since_ms: Some(process.ptime_now_ms.saturating_sub(gap_ms)),
+                reason: None,
+            });
+            adjacency
+                .entry(src_key.clone())
+                .or_default()
+                .push(dst_key.clone());
+            *indegree.entry(dst_key).or_insert(0) += 1;
+            indegree.entry(src_key).or_insert(0);
+        }
+    }
+
+    // Senders blocked on a full channel outside any instrumented
+    // future have no `waiting_on` edge; the channel lists them itself.
+    for tx in &process.snapshot.entities {
+        let EntityBody::MpscTx(body) = &tx.body else {
+            continue;
+        };
+        if tx.removed_at.is_some() {
+            continue;
+        }
+        let dst_actor = actor_of.get(tx.id.as_str());
+        let dst_key = compose_node_key(
+            &process.process_id,
+            dst_actor.map_or(&tx.id, |(_, driver)| &driver.id),
+        );
+        for waiter in &body.send_waiters {
+            let Some(src) = local_entities.get(waiter.future.as_str()) else {
+                continue;
+            };
+            let src_actor = actor_of.get(src.id.as_str());
+            let src_key = compose_node_key(
+                &process.process_id,
+                src_actor.map_or(&src.id, |(_, driver)| &driver.id),
+            );
+            if src_key == dst_key || !seen_edges.insert((src_key.clone(), dst_key.clone())) {
+                continue;
+            }
+            nodes
+                .entry(src_key.clone())
+                .or_insert_with(|| match src_actor {
+                    Some((scope, driver)) => {
+                        actor_wait_node(process, scope, driver, backtrace_index, frame_catalog)
+                    }
+                    None => wait_node(process, src, backtrace_index, frame_catalog),
+                });
+            nodes
+                .entry(dst_key.clone())
+                .or_insert_with(|| match dst_actor {
+                    Some((scope, driver)) => {
+                        actor_wait_node(process, scope, driver, backtrace_index, frame_catalog)
+                    }
+                    None => wait_node(process, tx, backtrace_index, frame_catalog),
+                });
+            edges.push(WaitEdgeRuntime {
+                process_id: process.process_id.as_str().to_owned(),
+                src_key: src_key.clone(),
+                dst_key: dst_key.clone(),
+                dst_entity_id: tx.id.as_str().to_owned(),
+                edge_frame_ids: Vec::new(),
+                since_ms: Some(waiter.since.as_millis()),
+                reason: Some(EdgeReason::MpscFull),
+            });
+            adjacency
+                .entry(src_key.clone())
+                .or_default()
+                .push(dst_key.clone());
+            *indegree.entry(dst_key.clone()).or_insert(0) += 1;
+            indegree.entry(src_key).or_insert(0);
+        }
+    }
+
+    for outs in adjacency.values_mut() {
+        outs.sort();
+        outs.dedup();
+    }
+    holders.retain(|key, _| nodes.contains_key(key));
+    for held_by in holders.values_mut() {
+        held_by.sort_by(|a, b| a.entity_id.cmp(&b.entity_id));
+        held_by.dedup_by(|a, b| a.entity_id == b.entity_id);
+    }
+
+    Ok(ProcessPart {
+        nodes,
+        edges,
+        adjacency,
+        indegree,
+        holders,
+        rpc: rpc_ends(process),
+    })
+}
+
+/// One process's sides of the RPCs in flight, paired up across processes by
+/// [`link_rpcs`].
+struct RpcEnds {
+    process_id: String,
+    /// Live requests the process made: entity id and node key.
+    requests: Vec<(String, String)>,
+    /// Pending responses the process is serving.
+    responses: Vec<PendingResponse>,
+}
+
+struct PendingResponse {
+    method: String,
+    response_key: String,
+    /// Entity id of the request the response answers, in whichever process
+    /// made it.
+    request_id: String,
+}
+
+fn rpc_ends(process: &ProcessSnapshotView) -> RpcEnds {
+    let stale = stale_rpc_entities(process);
+    let live = |entity: &&Entity| !stale.contains(entity.id.as_str());
+    let requests = process
+        .snapshot
+        .entities
+        .iter()
+        .filter(|entity| matches!(entity.body, EntityBody::Request(_)))
+        .filter(live)
+        .map(|entity| {
+            (
+                entity.id.as_str().to_owned(),
+                compose_node_key(&process.process_id, &entity.id),
+            )
+        })
+        .collect();
+    let pending_responses: HashMap<&str, &Entity> = process
+        .snapshot
+        .entities
+        .iter()
+        .filter(|entity| entity.removed_at.is_none())
+        .filter(live)
+        .filter(|entity| {
+            matches!(&entity.body, EntityBody::Response(response) if response.status == ResponseStatus::Pending)
+        })
+        .map(|entity| (entity.id.as_str(), entity))
+        .collect();
+    let responses = process
+        .snapshot
+        .edges
+        .iter()
+        .filter(|edge| edge.kind == EdgeKind::PairedWith)
+        .filter_map(|edge| {
+            let response = pending_responses.get(edge.src.as_str())?;
+            Some(PendingResponse {
+                method: response.name.clone(),
+                response_key: compose_node_key(&process.process_id, &response.id),
+                request_id: edge.dst.as_str().to_owned(),
+            })
+        })
+        .collect();
+    RpcEnds {
+        process_id: process.process_id.as_str().to_owned(),
+        requests,
+        responses,
+    }
+}
+
+/// Pairs are looked up through maps keyed by entity id, so this stays linear
+/// in the number of entities and edges however many RPCs are in flight.
+// r[impl api.rpc-pairing]
+fn link_rpcs(ends: &[RpcEnds]) -> Vec<RpcLink> {
+    let requests: HashMap<&str, (&str, &str)> = ends
+        .iter()
+        .flat_map(|end| {
+            end.requests
+                .iter()
+                .map(move |(id, key)| (id.as_str(), (end.process_id.as_str(), key.as_str())))
+        })
+        .collect();
+
+    let mut out = Vec::new();
+    for end in ends {
+        for response in &end.responses {
+            let Some(&(client_process_id, request_key)) =
+                requests.get(response.request_id.as_str())
+            else {
+                continue;
+            };
+            out.push(RpcLink {
+                method: response.method.clone(),
+                server_process_id: end.process_id.clone(),
+                response_key: response.response_key.clone(),
+                client_process_id: client_process_id.to_owned(),
+                request_key: request_key.to_owned(),
+            });
+        }
+    }
+    out.sort_by(|a, b| a.response_key.cmp(&b.response_key));
+    out
+}
+
+/// Requests and responses of `process` bound to an older generation of the
+/// connection scope they are linked to. Generations are only meaningful
+/// within the process that counted them.
+fn stale_rpc_entities(process: &ProcessSnapshotView) -> HashSet<&str> {
+    let generations: HashMap<&str, u64> = process
+        .snapshot
+        .scopes
+        .iter()
+        .filter_map(|scope| match &scope.body {
+            ScopeBody::Connection(connection) => Some((scope.id.as_str(), connection.generation?)),
+            _ => None,
+        })
+        .collect();
+    let bound: HashMap<&str, u64> = process
+        .snapshot
+        .entities
+        .iter()
+        .filter_map(|entity| {
+            let generation = match &entity.body {
+                EntityBody::Request(request) => request.connection_generation,
+                EntityBody::Response(response) => response.connection_generation,
+                _ => None,
+            }?;
+            Some((entity.id.as_str(), generation))
+        })
+        .collect();
+    process
+        .scope_entity_links
+        .iter()
+        .filter_map(|link| {
+            let current = generations.get(link.scope_id.as_str())?;
+            let (&id, &generation) = bound.get_key_value(link.entity_id.as_str())?;
+            (generation < *current).then_some(id)
+        })
+        .collect()
+}
+
+fn wait_node(
+    process: &ProcessSnapshotView,
+    entity: &Entity,
+    backtrace_index: &HashMap<u64, &SnapshotBacktrace>,
+    frame_catalog: &HashMap<u64, &SnapshotBacktraceFrame>,
+) -> WaitNode {
+    let frame_ids = selected_frames_for_entity(
+        entity,
+        backtrace_index,
+        frame_catalog,
+        SOURCE_FRAMES_PER_ITEM,
+    );
+    WaitNode {
+        process_id: process.process_id.as_str().to_owned(),
+        ptime_now_ms: process.ptime_now_ms,
+        entity_id: entity.id.as_str().to_owned(),
+        name: entity.name.clone(),
+        kind: entity_kind_name(&entity.body),
+        birth_ms: entity.birth.as_millis(),
+        frame_ids,
+        wakes: wake_counts(process, entity),
+        holds: hold_counts(process, entity),
+        idle: match &entity.body {
+            EntityBody::Future(future) => future.idle.clone(),
+            _ => None,
+        },
+        removed_ms: entity.removed_at.map(|at| at.as_millis()),
+    }
+}
+
+/// Pseudo-resource standing for the run queue of one worker thread, keyed
+/// `run_queue:<worker>`. Futures woken and not polled since wait on the queue
+/// of the thread that last polled them, so a saturated worker shows up as one
+/// resource with many waiters rather than as many unrelated stalls.
+fn run_queue_node(process: &ProcessSnapshotView, worker: u64) -> WaitNode {
+    let thread_name = process
+        .snapshot
+        .workers
+        .iter()
+        .find(|load| load.worker == worker)
+        .and_then(|load| load.thread_name.as_deref());
+    WaitNode {
+        process_id: process.process_id.as_str().to_owned(),
+        ptime_now_ms: process.ptime_now_ms,
+        entity_id: format!("run_queue:{worker}"),
+        name: match thread_name {
+            Some(thread_name) => format!("run queue of {thread_name} (worker {worker})"),
+            None => format!("run queue of worker {worker}"),
+        },
+        kind: String::from("run_queue"),
+        birth_ms: process.ptime_now_ms,
+        frame_ids: Vec::new(),
+        wakes: WakeCounts::default(),
+        holds: HoldCounts::default(),
+        idle: None,
+        removed_ms: None,
+    }
+}
+
+fn wake_counts(process: &ProcessSnapshotView, entity: &Entity) -> WakeCounts {
+    let EntityBody::Future(future) = &entity.body else {
+        return WakeCounts::default();
+    };
+    WakeCounts {
+        wake_count: future.wake_count.unwrap_or(0),
+        unproductive_wake_count: future.unproductive_wake_count.unwrap_or(0),
+        last_woken_by: future
+            .last_woken_by
+            .as_ref()
+            .map(|id| compose_node_key(&process.process_id, id)),
+    }
+}
+
+fn hold_counts(process: &ProcessSnapshotView, entity: &Entity) -> HoldCounts {
+    let (holds, rwlock) = match &entity.body {
+        EntityBody::Lock(lock) => (lock.holds, lock.rwlock),
+        EntityBody::Semaphore(semaphore) => (semaphore.holds, None),
+        _ => return HoldCounts::default(),
+    };
+    let holds = holds.unwrap_or_default();
+    let rwlock = rwlock.unwrap_or_default();
+    HoldCounts {
+        hold_count: holds.hold_count,
+        avg_hold_ms: holds.avg_hold_ms,
+        latest_acquired_ms: process
+            .snapshot
+            .edges
+            .iter()
+            .filter(|edge| edge.kind == EdgeKind::HeldBy && edge.src == entity.id)
+            .filter_map(|edge| edge.since.map(|since| since.as_millis()))
+            .max(),
+        readers: rwlock.readers,
+        waiting_writers: rwlock.waiting_writers,
+    }
+}
+
+fn actor_wait_node(
+    process: &ProcessSnapshotView,
+    scope: &Scope,
+    driver: &Entity,
+    backtrace_index: &HashMap<u64, &SnapshotBacktrace>,
+    frame_catalog: &HashMap<u64, &SnapshotBacktraceFrame>,
+) -> WaitNode {
+    WaitNode {
+        name: actor_display_name(scope).to_owned(),
+        kind: String::from("actor"),
+        birth_ms: scope.birth.as_millis(),
+        ..wait_node(process, driver, backtrace_index, frame_catalog)
+    }
+}
+
+pub(crate) fn actor_display_name(scope: &Scope) -> &str {
+    scope.name.strip_prefix("actor.").unwrap_or(&scope.name)
+}
+
+pub(crate) fn actor_members(process: &ProcessSnapshotView) -> Vec<ActorMembers<'_>> {
+    let entities: HashMap<&str, &Entity> = process
+        .snapshot
+        .entities
+        .iter()
+        .filter(|entity| entity.removed_at.is_none())
+        .map(|entity| (entity.id.as_str(), entity))
+        .collect();
+
+    let mut out = Vec::new();
+    for scope in &process.snapshot.scopes {
+        let ScopeBody::Actor(body) = &scope.body else {
+            continue;
+        };
+        let mut members = ActorMembers {
+            scope,
+            mailbox_capacity: body.mailbox_capacity,
+            driver: None,
+            mailbox_tx: None,
+            mailbox_rx: None,
+        };
+        for link in &process.scope_entity_links {
+            if link.scope_id != scope.id.as_str() {
+                continue;
+            }
+            let Some(entity) = entities.get(link.entity_id.as_str()) else {
+                continue;
+            };
+            match &entity.body {
+                EntityBody::Future(_) => members.driver = Some(entity),
+                EntityBody::MpscTx(_) => members.mailbox_tx = Some(entity),
+                EntityBody::MpscRx(_) => members.mailbox_rx = Some(entity),
+                _ => {}
+            }
+        }
+        out.push(members);
+    }
+    out
+}
+
+/// Maps every member entity of a live actor to its scope and driver future.
+fn actor_composites(process: &ProcessSnapshotView) -> HashMap<&str, (&Scope, &Entity)> {
+    let mut out = HashMap::new();
+    for members in actor_members(process) {
+        let Some(driver) = members.driver else {
+            continue;
+        };
+        for member in [Some(driver), members.mailbox_tx, members.mailbox_rx]
+            .into_iter()
+            .flatten()
+        {
+            out.insert(member.id.as_str(), (members.scope, driver));
+        }
+    }
+    out
+}
+
+pub(crate) fn actor_mailbox_depth(members: &ActorMembers<'_>) -> u32 {
+    match members.mailbox_tx.map(|tx| &tx.body) {
+        Some(EntityBody::MpscTx(tx)) => tx.queue_len,
+        _ => 0,
+    }
+}
+
+/// Age of the oldest message still sitting in the mailbox, from `channel_sent` events.
+///
+/// The last `depth` sends are the ones still queued. Returns `None` when the
+/// mailbox is empty or the event ring no longer holds the oldest pending send.
+pub(crate) fn actor_oldest_message_age_ms(
+    process: &ProcessSnapshotView,
+    members: &ActorMembers<'_>,
+) -> Option<u64> {
+    let tx = members.mailbox_tx?;
+    let depth = actor_mailbox_depth(members) as usize;
+    if depth == 0 {
+        return None;
+    }
+    let mut sent_at = process
+        .snapshot
+        .events
+        .iter()
+        .filter(|event| matches!(event.kind, EventKind::ChannelSent))
+        .filter(|event| matches!(&event.target, EventTarget::Entity(id) if *id == tx.id))
+        .map(|event| event.at.as_millis())
+        .collect::<Vec<_>>();
+    if sent_at.len() < depth {
+        return None;
+    }
+    sent_at.sort_unstable();
+    let oldest_pending = sent_at[sent_at.len() - depth];
+    Some(process.ptime_now_ms.saturating_sub(oldest_pending))
+}
+
+/// `idle` when the driver only waits on its own mailbox, `blocked` when it waits
+/// on anything else, `processing` when it is not waiting at all.
+pub(crate) fn actor_processing_state(
+    process: &ProcessSnapshotView,
+    members: &ActorMembers<'_>,
+) -> (&'static str, Vec<String>) {
+    let Some(driver) = members.driver else {
+        return ("stopped", Vec::new());
+    };
+    let mut waiting_on_mailbox = false;
+    let mut blocked_on = Vec::new();
+    for edge in &process.snapshot.edges {
+        if edge.kind != EdgeKind::WaitingOn || edge.src != driver.id {
+            continue;
+        }
+        if members.mailbox_rx.is_some_and(|rx| rx.id == edge.dst) {
+            waiting_on_mailbox = true;
+        } else {
+            blocked_on.push(edge.dst.as_str().to_owned());
+        }
+    }
+    blocked_on.sort();
+    blocked_on.dedup();
+    if !blocked_on.is_empty() {
+        ("blocked", blocked_on)
+    } else if waiting_on_mailbox {
+        ("idle", blocked_on)
+    } else {
+        ("processing", blocked_on)
+    }
+}
+
+pub(crate) fn compose_node_key(process_id: &ProcessId, entity_id: &EntityId) -> String {
+    node_key(process_id.as_str(), entity_id.as_str())
+}
+
+fn node_key(process_id: &str, entity_id: &str) -> String {
+    format!("{process_id}::{entity_id}")
+}
+
+pub(crate) fn backtrace_index(snapshot: &SnapshotCutResponse) -> HashMap<u64, &SnapshotBacktrace> {
+    snapshot
+        .backtraces
+        .iter()
+        .map(|bt| (bt.backtrace_id.as_u64(), bt))
+        .collect()
+}
+
+pub(crate) fn frame_catalog(
+    snapshot: &SnapshotCutResponse,
+) -> HashMap<u64, &SnapshotBacktraceFrame> {
+    snapshot
+        .frames
+        .iter()
+        .map(|record| (record.frame_id.as_u64(), &record.frame))
+        .collect()
+}
+
+pub(crate) fn frame_start_index_for_entity(entity: &Entity) -> usize {
+    match &entity.body {
+        EntityBody::Future(fut) => fut.skip_entry_frames.unwrap_or(0) as usize,
+        _ => 0,
+    }
+}
+
+pub(crate) fn selected_frames_for_entity(
+    entity: &Entity,
+    backtrace_index: &HashMap<u64, &SnapshotBacktrace>,
+    frame_catalog: &HashMap<u64, &SnapshotBacktraceFrame>,
+    frame_count: usize,
+) -> Vec<FrameId> {
+    let Some(backtrace) = backtrace_index.get(&entity.backtrace.as_u64()) else {
+        return Vec::new();
+    };
+    select_frames_for_backtrace(
+        backtrace,
+        frame_catalog,
+        frame_start_index_for_entity(entity),
+        frame_count,
+    )
+}
+
+pub(crate) fn selected_frames_for_backtrace_id(
+    backtrace_id: u64,
+    backtrace_index: &HashMap<u64, &SnapshotBacktrace>,
+    frame_catalog: &HashMap<u64, &SnapshotBacktraceFrame>,
+    app_skip_count: usize,
+    frame_count: usize,
+) -> Vec<FrameId> {
+    let Some(backtrace) = backtrace_index.get(&backtrace_id) else {
+        return Vec::new();
+    };
+    select_frames_for_backtrace(backtrace, frame_catalog, app_skip_count, frame_count)
+}
+
+fn select_frames_for_backtrace(
+    backtrace: &SnapshotBacktrace,
+    frame_catalog: &HashMap<u64, &SnapshotBacktraceFrame>,
+    app_skip_count: usize,
+    frame_count: usize,
+) -> Vec<FrameId> {
+    if backtrace.frame_ids.is_empty() || frame_count == 0 {
+        return Vec::new();
+    }
+
+    let mut application_resolved = Vec::<FrameId>::new();
+    let mut resolved_all = Vec::<FrameId>::new();
+    for frame_id in &backtrace.frame_ids {
+        let Some(frame) = frame_catalog.get(&frame_id.as_u64()) else {
+            continue;
+        };
+        let SnapshotBacktraceFrame::Resolved(resolved) = frame else {
+            continue;
+        };
+        resolved_all.push(*frame_id);
+        let is_system =
+            crate_from_function_name(resolved.function_name.as_str()).is_some_and(is_system_crate);
+        if !is_system {
+            application_resolved.push(*frame_id);
+        }
+    }
+
+    let mut out = Vec::<FrameId>::new();
+    for frame_id in application_resolved.into_iter().skip(app_skip_count) {
+        if !out.contains(&frame_id) {
+            out.push(frame_id);
+        }
+        if out.len() >= frame_count {
+            return out;
+        }
+    }
+    for frame_id in resolved_all {
+        if !out.contains(&frame_id) {
+            out.push(frame_id);
+        }
+        if out.len() >= frame_count {
+            return out;
+        }
+    }
+    for frame_id in &backtrace.frame_ids {
+        if !out.contains(frame_id) {
+            out.push(*frame_id);
+        }
+        if out.len() >= frame_count {
+            return out;
+        }
+    }
+    out
+}
+
+pub(crate) fn crate_from_function_name(function_name: &str) -> Option<&str> {
+    let trimmed = function_name.trim();
+    if trimmed.is_empty() {
+        return None;
+    }
+    let bytes = trimmed.as_bytes();
+    let mut index = 0usize;
+    while index < bytes.len() {
+        let ch = bytes[index] as char;
+        if ch == '<' || ch == ' ' || ch == '&' || ch == '*' {
+            index += 1;
+            continue;
+        }
+        break;
+    }
+
+    let rest = &trimmed[index..];
+    let mut chars = rest.char_indices();
+    let (_, first) = chars.next()?;
+    if !(first == '_' || first.is_ascii_alphabetic()) {
+        return None;
+    }
+    let mut end = first.len_utf8();
+    for (idx, ch) in chars {
+        if ch == '_' || ch.is_ascii_alphanumeric() {
+            end = idx + ch.len_utf8();
+            continue;
+        }
+        break;
+    }
+    Some(&rest[..end])
+}
+
+fn is_system_crate(krate: &str) -> bool {
+    SYSTEM_CRATES.contains(&krate)
+}
+
+pub(crate) fn entity_kind_name(body: &EntityBody) -> String {
+    entity_kind_wire_name(body.kind_name())
+}
+
+/// Node visits between deadline checks in [`strongly_connected_components`].
+const SCC_DEADLINE_CHECK_INTERVAL: usize = 256;
+
+/// Tarjan's SCC over `adjacency`, starting DFS trees from `roots` in order.
+///
+/// The DFS runs on an explicit frame stack so long wait chains cannot overflow
+/// the thread stack. `deadline` is checked every [`SCC_DEADLINE_CHECK_INTERVAL`]
+/// node visits; once it has passed the walk stops and the returned flag is
+/// `false`. Components that were returned are complete either way.
+pub(crate) fn strongly_connected_components(
+    roots: Vec<String>,
+    adjacency: &HashMap<String, Vec<String>>,
+    deadline: Option<Instant>,
+) -> (Vec<Vec<String>>, bool) {
+    struct Tarjan<'a> {
+        index: usize,
+        stack: Vec<&'a str>,
+        on_stack: HashSet<&'a str>,
+        index_map: HashMap<&'a str, usize>,
+        lowlink_map: HashMap<&'a str, usize>,
+        components: Vec<Vec<String>>,
+        /// DFS frames: the node and the position of its next unexplored neighbor.
+        frames: Vec<(&'a str, usize)>,
+    }
+
+    impl<'a> Tarjan<'a> {
+        fn enter(&mut self, node: &'a str) {
+            self.index_map.insert(node, self.index);
+            self.lowlink_map.insert(node, self.index);
+            self.index += 1;
+            self.stack.push(node);
+            self.on_stack.insert(node);
+            self.frames.push((node, 0));
+        }
+
+        fn lower(&mut self, node: &'a str, to: usize) {
+            if let Some(low) = self.lowlink_map.get_mut(node) {
+                *low = (*low).min(to);
+            }
+        }
+    }
+
+    let past_deadline = || deadline.is_some_and(|deadline| Instant::now() >= deadline);
+
+    let mut st = Tarjan {
+        index: 0,
+        stack: Vec::new(),
+        on_stack: HashSet::new(),
+        index_map: HashMap::new(),
+        lowlink_map: HashMap::new(),
+        components: Vec::new(),
+        frames: Vec::new(),
+    };
+    let mut visits = 0usize;
+
+    for root in &roots {
+        if st.index_map.contains_key(root.as_str()) {
+            continue;
+        }
+        visits += 1;
+        if visits.is_multiple_of(SCC_DEADLINE_CHECK_INTERVAL) && past_deadline() {
+            return (st.components, false);
+        }
+        st.enter(root);
+
+        while let Some(frame) = st.frames.last_mut() {
+            let node = frame.0;
+            let next = adjacency
+                .get(node)
+                .and_then(|neighbors| neighbors.get(frame.1));
+            if let Some(next) = next {
+                frame.1 += 1;
+                match st.index_map.get(next.as_str()).copied() {
+                    None => {
+                        visits += 1;
+                        if visits.is_multiple_of(SCC_DEADLINE_CHECK_INTERVAL) && past_deadline() {
+                            return (st.components, false);
+                        }
+                        st.enter(next);
+                    }
+                    Some(next_idx) if st.on_stack.contains(next.as_str()) => {
+                        st.lower(node, next_idx);
+                    }
+                    Some(_) => {}
+                }
+                continue;
+            }
+
+            st.frames.pop();
+            let node_idx = st.index_map.get(node).copied().unwrap_or(usize::MAX);
+            let node_low = st.lowlink_map.get(node).copied().unwrap_or(usize::MAX);
+            if node_low == node_idx {
+                let mut component = Vec::new();
+                while let Some(w) = st.stack.pop() {
+                    st.on_stack.remove(w);
+                    component.push(w.to_owned());
+                    if w == node {
+                        break;
+                    }
+                }
+                st.components.push(component);
+            }
+            if let Some(&(parent, _)) = st.frames.last() {
+                st.lower(parent, node_low);
+            }
+        }
+    }
+
+    (st.components, true)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeSet;
+
+    use moire_testkit::DumpBuilder;
+
+    use super::report::merge_snapshots;
+    use super::*;
+
+    #[test]
+    fn core_graph_round_trips_through_wait_graph() {
+        let row = |entity_id: &str, kind: &str| NodeRow {
+            id: NodeId::new("p1", entity_id),
+            name: format!("{kind} {entity_id}"),
+            kind: String::from(kind),
+            birth_ms: 10,
+            ptime_now_ms: 500,
+        };
+        let edge = |src: &str, dst: &str, since_ms| EdgeRow {
+            src: NodeId::new("p1", src),
+            dst: NodeId::new("p1", dst),
+            kind: CoreEdgeKind::WaitingOn,
+            since_ms,
+        };
+        let core = WaitGraphCore::from_rows(
+            [row("a", "future"), row("b", "lock"), row("c", "future")],
+            [
+                edge("a", "b", Some(40)),
+                edge("c", "b", None),
+                edge("b", "c", Some(7)),
+            ],
+        )
+        .unwrap();
+
+        let graph = WaitGraph::from_core(&core);
+        assert_eq!(graph.to_core().unwrap(), core);
+    }
+
+    type Shape = (
+        BTreeSet<String>,
+        BTreeSet<(String, String)>,
+        Vec<(String, String)>,
+    );
+
+    fn shape(graph: &WaitGraph) -> Shape {
+        (
+            graph.nodes.keys().cloned().collect(),
+            graph
+                .edges
+                .iter()
+                .map(|edge| (edge.src_key.clone(), edge.dst_key.clone()))
+                .collect(),
+            graph
+                .inflight_rpcs
+                .iter()
+                .map(|link| (link.response_key.clone(), link.request_key.clone()))
+                .collect(),
+        )
+    }
+
+    // r[verify api.snapshot.incremental]
+    #[test]
+    fn builder_upserts_one_process_at_a_time() {
+        let server = || {
+            DumpBuilder::new()
+                .process("server", |p| {
+                    p.task("handler")
+                        .lock("db")
+                        .response("resp", "svc.get", "req")
+                        .waits_on("handler", "db", 2_000)
+                })
+                .build()
+        };
+        let calling = || {
+            DumpBuilder::new()
+                .process("client", |p| {
+                    p.task("caller")
+                        .request("req", "svc.get")
+                        .waits_on("caller", "req", 1_000)
+                })
+                .build()
+        };
+        let caching = || {
+            DumpBuilder::new()
+                .process("client", |p| {
+                    p.task("caller")
+                        .lock("cache")
+                        .waits_on("caller", "cache", 3_000)
+                })
+                .build()
+        };
+
+        let mut builder = WaitGraphBuilder::new();
+        builder.apply(&calling()).unwrap();
+        builder.apply(&server()).unwrap();
+        let full = WaitGraph::build(&merge_snapshots(vec![calling(), server()])).unwrap();
+        assert_eq!(shape(builder.graph()), shape(&full));
+        assert_eq!(builder.graph().inflight_rpcs.len(), 1);
+
+        builder.apply(&caching()).unwrap();
+        let full = WaitGraph::build(&merge_snapshots(vec![caching(), server()])).unwrap();
+        assert_eq!(shape(builder.graph()), shape(&full));
+        assert!(!builder.graph().nodes.contains_key("client::req"));
+        assert!(builder.graph().inflight_rpcs.is_empty());
+
+        assert!(builder.remove("server"));
+        assert!(!builder.remove("server"));
+        assert_eq!(
+            shape(builder.graph()),
+            shape(&WaitGraph::build(&caching()).unwrap())
+        );
+    }
+
+    #[test]
+    fn actors_fold_into_one_node_that_does_not_wait_on_its_own_mailbox() {
+        let dump = DumpBuilder::new()
+            .process("p", |p| {
+                p.task("session")
+                    .channel("inbox", Some(4), 4)
+                    .actor("sessions", "session", "inbox")
+                    .waits_on("session", "inbox:rx", 1_000)
+                    .task("client")
+                    .waits_on("client", "inbox", 2_000)
+            })
+            .build();
+        let graph = WaitGraph::build(&dump).unwrap();
+
+        let (nodes, edges, _) = shape(&graph);
+        assert_eq!(
+            nodes,
+            BTreeSet::from([String::from("p::client"), String::from("p::session")])
+        );
+        assert_eq!(
+            edges,
+            BTreeSet::from([(String::from("p::client"), String::from("p::session"))])
+        );
+        let actor = &graph.nodes["p::session"];
+        assert_eq!(actor.kind, "actor");
+        assert_eq!(actor.name, "sessions");
+    }
+
+    #[test]
+    fn strongly_connected_components_finds_cycle_cluster() {
+        let mut adjacency: HashMap<String, Vec<String>> = HashMap::new();
+        adjacency.insert(String::from("a"), vec![String::from("b")]);
+        adjacency.insert(String::from("b"), vec![String::from("c")]);
+        adjacency.insert(String::from("c"), vec![String::from("a")]);
+        adjacency.insert(String::from("d"), vec![String::from("e")]);
+        adjacency.insert(String::from("e"), vec![]);
+        let keys = vec![
+            String::from("a"),
+            String::from("b"),
+            String::from("c"),
+            String::from("d"),
+            String::from("e"),
+        ];
+
+        let (mut components, _) = strongly_connected_components(keys, &adjacency, None);
+        components.iter_mut().for_each(|c| c.sort());
+        components.sort_by_key(|c| c.first().cloned().unwrap_or_default());
+
+        assert_eq!(components.len(), 3);
+        assert_eq!(
+            components[0],
+            vec![String::from("a"), String::from("b"), String::from("c")]
+        );
+        assert_eq!(components[1], vec![String::from("d")]);
+        assert_eq!(components[2], vec![String::from("e")]);
+    }
+
+    #[test]
+    fn strongly_connected_components_walks_long_chains_without_recursion() {
+        const LEN: usize = 200_000;
+        let keys: Vec<String> = (0..LEN).map(|i| format!("n{i}")).collect();
+        let adjacency: HashMap<String, Vec<String>> = keys
+            .iter()
+            .zip(keys.iter().skip(1))
+            .map(|(from, to)| (from.clone(), vec![to.clone()]))
+            .collect();
+
+        let (components, complete) = strongly_connected_components(keys.clone(), &adjacency, None);
+        assert!(complete);
+        assert_eq!(components.len(), LEN);
+
+        let expired = Some(Instant::now());
+        let (components, complete) = strongly_connected_components(keys, &adjacency, expired);
+        assert!(!complete);
+        // The walk stopped partway down the chain, where no node has finished yet.
+        assert!(components.is_empty());
+    }
+
+    #[test]
+    fn crate_parser_handles_trait_impl_style_names() {
+        assert_eq!(
+            crate_from_function_name("<alloc::vec::Vec<u8> as core::fmt::Debug>::fmt"),
+            Some("alloc")
+        );
+        assert_eq!(
+            crate_from_function_name("tokio::runtime::context::enter"),
+            Some("tokio")
+        );
+    }
+}
@@ -0,0 +1,392 @@
+//! Plain-text report over snapshot dumps.
+//!
+//! `moire analyze a.json b.json` merges the dumps, runs every built-in
+//! analysis and prints the findings (wait cycles drawn as diagrams), the
+//! longest waits, how much of each process is instrumented and each
+//! process's task spawn lineage, so a dump someone sent over can be triaged
+//! without a dashboard.
+
+use std::collections::{BTreeMap, HashSet};
+use std::fmt::Write as _;
+
+use moire_types::{
+    AnalysisFinding, EntityBody, FindingSeverity, ProcessSnapshotView, SnapshotBacktraceFrame,
+    SnapshotCutResponse,
+};
+
+use super::export::cycle_to_ascii;
+use super::{WaitGraph, compose_node_key};
+
+/// Function path fragments of primitives moire does not instrument, with the
+/// name the coverage section reports them under.
+const RAW_PRIMITIVES: &[(&str, &str)] = &[
+    ("tokio::task::spawn::spawn", "tokio::spawn"),
+    ("tokio::sync::mpsc::", "tokio mpsc channel"),
+    ("tokio::sync::oneshot::", "tokio oneshot channel"),
+    ("tokio::sync::broadcast::", "tokio broadcast channel"),
+    ("tokio::sync::watch::", "tokio watch channel"),
+    ("tokio::sync::notify::", "tokio Notify"),
+    ("tokio::sync::mutex::", "tokio Mutex"),
+    ("tokio::sync::rwlock::", "tokio RwLock"),
+    ("tokio::sync::semaphore::", "tokio Semaphore"),
+    ("std::sync::mpsc::", "std mpsc channel"),
+    ("mutex::Mutex<", "std Mutex"),
+    ("rwlock::RwLock<", "std RwLock"),
+    ("parking_lot::", "parking_lot lock"),
+];
+
+// r[impl api.snapshot.skew]
+/// Combines dumps into one snapshot. Processes are concatenated; backtraces
+/// and frames are deduplicated by id. Findings are dropped, since they are
+/// recomputed for the merged result, and so are annotations and sizing hints,
+/// which only a live collector produces.
+///
+/// Dumps captured at different times are aligned to the earliest of them:
+/// each process's `skew_ms` grows by how much later its dump was captured,
+/// and `max_skew_ms` covers the merged processes.
+pub fn merge_snapshots(snapshots: Vec<SnapshotCutResponse>) -> SnapshotCutResponse {
+    let epoch_start_ms = snapshots
+        .iter()
+        .map(|snapshot| snapshot.captured_at_unix_ms)
+        .min()
+        .unwrap_or(0);
+    let mut merged = SnapshotCutResponse {
+        snapshot_id: 0,
+        captured_at_unix_ms: 0,
+        max_skew_ms: None,
+        processes: Vec::new(),
+        timed_out_processes: Vec::new(),
+        backtraces: Vec::new(),
+        frames: Vec::new(),
+        findings: Vec::new(),
+        annotations: Vec::new(),
+        sizing_hints: Vec::new(),
+        detection_config_version: None,
+        analysis_error: None,
+    };
+    let mut backtrace_ids = HashSet::new();
+    let mut frame_ids = HashSet::new();
+    for snapshot in snapshots {
+        merged.snapshot_id = merged.snapshot_id.max(snapshot.snapshot_id);
+        merged.captured_at_unix_ms = merged.captured_at_unix_ms.max(snapshot.captured_at_unix_ms);
+        let offset_ms = u64::try_from(snapshot.captured_at_unix_ms - epoch_start_ms).unwrap_or(0);
+        merged
+            .processes
+            .extend(snapshot.processes.into_iter().map(|mut process| {
+                if offset_ms > 0 {
+                    process.skew_ms = Some(process.skew_ms.unwrap_or(0) + offset_ms);
+                }
+                process
+            }));
+        merged
+            .timed_out_processes
+            .extend(snapshot.timed_out_processes);
+        merged.backtraces.extend(
+            snapshot
+                .backtraces
+                .into_iter()
+                .filter(|backtrace| backtrace_ids.insert(backtrace.backtrace_id)),
+        );
+        merged.frames.extend(
+            snapshot
+                .frames
+                .into_iter()
+                .filter(|frame| frame_ids.insert(frame.frame_id)),
+        );
+    }
+    merged.max_skew_ms = merged
+        .processes
+        .iter()
+        .filter_map(|process| process.skew_ms)
+        .max();
+    merged
+}
+
+/// Renders findings, the `top` longest waits and the spawn lineage of every
+/// process. Output is sorted, so the same input always yields the same text.
+pub fn to_report(
+    snapshot: &SnapshotCutResponse,
+    graph: &WaitGraph,
+    findings: &[AnalysisFinding],
+    top: usize,
+) -> String {
+    let mut out = String::new();
+
+    let _ = writeln!(out, "Findings ({})", findings.len());
+    for finding in findings {
+        let severity = match finding.severity {
+            FindingSeverity::Info => "info",
+            FindingSeverity::Warning => "warning",
+            FindingSeverity::Critical => "critical",
+        };
+        let score = finding
+            .score
+            .as_ref()
+            .map(|score| format!(" (score {})", score.total()))
+            .unwrap_or_default();
+        let _ = writeln!(
+            out,
+            "  [{severity}] {}: {}{score}",
+            finding.analysis, finding.title
+        );
+        let _ = writeln!(out, "    {}", finding.rationale);
+        for hint in &finding.hints {
+            let _ = writeln!(out, "    hint: {hint}");
+        }
+        let keys = finding
+            .subjects
+            .iter()
+            .map(|subject| compose_node_key(&subject.process_id, &subject.entity_id))
+            .collect::<Vec<_>>();
+        for key in &keys {
+            let name = graph
+                .nodes
+                .get(key)
+                .map(|node| node.name.as_str())
+                .unwrap_or("?");
+            let _ = writeln!(out, "    - {name} ({key})");
+        }
+        if finding.analysis == "deadlock"
+            && let Some(diagram) = cycle_to_ascii(graph, &keys)
+        {
+            for line in diagram.lines() {
+                let _ = writeln!(out, "      {line}");
+            }
+        }
+    }
+
+    let mut waits = graph
+        .edges
+        .iter()
+        .filter_map(|edge| {
+            let src = graph.nodes.get(&edge.src_key)?;
+            let dst = graph.nodes.get(&edge.dst_key)?;
+            let since_ms = edge.since_ms.unwrap_or(src.birth_ms);
+            Some((src.ptime_now_ms.saturating_sub(since_ms), src, dst))
+        })
+        .collect::<Vec<_>>();
+    waits.sort_by(|a, b| {
+        b.0.cmp(&a.0)
+            .then_with(|| a.1.entity_id.cmp(&b.1.entity_id))
+            .then_with(|| a.2.entity_id.cmp(&b.2.entity_id))
+    });
+    let _ = writeln!(out);
+    let _ = writeln!(out, "Worst waits");
+    for (wait_ms, src, dst) in waits.into_iter().take(top) {
+        let _ = writeln!(
+            out,
+            "  {wait_ms:>8}ms  {} ({}) -> {} ({})  [{}]",
+            src.name, src.kind, dst.name, dst.kind, src.process_id
+        );
+    }
+
+    let mut processes = snapshot.processes.iter().collect::<Vec<_>>();
+    processes.sort_by(|a, b| a.process_id.as_str().cmp(b.process_id.as_str()));
+    let _ = writeln!(out);
+    write_coverage(&mut out, snapshot, &processes);
+    let _ = writeln!(out);
+    write_runtime_stats(&mut out, &processes);
+    for process in processes {
+        let _ = writeln!(out);
+        let _ = writeln!(
+            out,
+            "Task lineage: {} (pid {}, {})",
+            process.process_name,
+            process.pid,
+            process.process_id.as_str()
+        );
+        write_lineage(&mut out, process);
+    }
+    out
+}
+
+/// Tracked against running tasks per process, then uninstrumented primitives
+/// whose functions show up in resolved frames. Frames only come from stacks
+/// moire captured, so the second part points at blind spots without counting
+/// them; frames of moire's own wrappers are skipped.
+fn write_coverage(
+    out: &mut String,
+    snapshot: &SnapshotCutResponse,
+    processes: &[&ProcessSnapshotView],
+) {
+    let _ = writeln!(out, "Coverage");
+    for process in processes {
+        let Some(coverage) = &process.snapshot.coverage else {
+            let _ = writeln!(out, "  {}: not reported", process.process_name);
+            continue;
+        };
+        match coverage.runtime_alive_tasks {
+            Some(alive) => {
+                let _ = writeln!(
+                    out,
+                    "  {}: {} of {alive} tasks tracked, {} untracked",
+                    process.process_name,
+                    coverage.tracked_tasks,
+                    alive.saturating_sub(coverage.tracked_tasks)
+                );
+            }
+            None => {
+                let _ = writeln!(
+                    out,
+                    "  {}: {} tasks tracked, runtime task count unknown",
+                    process.process_name, coverage.tracked_tasks
+                );
+            }
+        }
+    }
+
+    let mut raw_frames: BTreeMap<&str, usize> = BTreeMap::new();
+    for record in &snapshot.frames {
+        let SnapshotBacktraceFrame::Resolved(frame) = &record.frame else {
+            continue;
+        };
+        if frame.function_name.contains("moire") {
+            continue;
+        }
+        if let Some((_, primitive)) = RAW_PRIMITIVES
+            .iter()
+            .find(|(fragment, _)| frame.function_name.contains(fragment))
+        {
+            *raw_frames.entry(primitive).or_default() += 1;
+        }
+    }
+    for (primitive, frames) in raw_frames {
+        let _ = writeln!(out, "  uninstrumented {primitive}: {frames} frame(s)");
+    }
+}
+
+/// One line per process, with whichever figures it reported.
+fn write_runtime_stats(out: &mut String, processes: &[&ProcessSnapshotView]) {
+    let _ = writeln!(out, "Runtime");
+    for process in processes {
+        let stats = process.snapshot.runtime_stats.clone().unwrap_or_default();
+        let parts = [
+            stats.workers.map(|n| format!("{n} workers")),
+            stats
+                .global_queue_depth
+                .map(|n| format!("{n} queued in the global queue")),
+            stats
+                .rss_bytes
+                .map(|n| format!("rss {} MiB", n / (1024 * 1024))),
+            stats.open_fds.map(|n| format!("{n} open fds")),
+        ]
+        .into_iter()
+        .flatten()
+        .collect::<Vec<_>>();
+        if parts.is_empty() {
+            let _ = writeln!(out, "  {}: not reported", process.process_name);
+        } else {
+            let _ = writeln!(out, "  {}: {}", process.process_name, parts.join(", "));
+        }
+    }
+}
+
+/// Tasks are futures with a logical id. A task whose spawner is not a task of
+/// the same process is a root.
+fn write_lineage(out: &mut String, process: &ProcessSnapshotView) {
+    let tasks: BTreeMap<&str, (&str, Option<&str>)> = process
+        .snapshot
+        .entities
+        .iter()
+        .filter_map(|entity| match &entity.body {
+            EntityBody::Future(future) if future.logical_id.is_some() => Some((
+                entity.id.as_str(),
+                (
+                    entity.name.as_str(),
+                    future.spawned_by.as_ref().map(|id| id.as_str()),
+                ),
+            )),
+            _ => None,
+        })
+        .collect();
+    let mut children: BTreeMap<&str, Vec<&str>> = BTreeMap::new();
+    let mut roots = Vec::new();
+    for (&id, &(_, spawned_by)) in &tasks {
+        match spawned_by.filter(|parent| tasks.contains_key(parent)) {
+            Some(parent) => children.entry(parent).or_default().push(id),
+            None => roots.push(id),
+        }
+    }
+
+    let mut stack = roots
+        .into_iter()
+        .rev()
+        .map(|id| (id, 1))
+        .collect::<Vec<_>>();
+    while let Some((id, depth)) = stack.pop() {
+        let name = tasks.get(id).map(|(name, _)| *name).unwrap_or("?");
+        let _ = writeln!(out, "{:indent$}{name} ({id})", "", indent = depth * 2);
+        for &child in children.get(id).into_iter().flatten().rev() {
+            stack.push((child, depth + 1));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn merging_dedups_backtraces_and_keeps_latest_capture() {
+        let backtrace_id = moire_types::BacktraceId::next().unwrap();
+        let dump = |snapshot_id: i64| SnapshotCutResponse {
+            snapshot_id,
+            captured_at_unix_ms: snapshot_id * 1_000,
+            max_skew_ms: None,
+            processes: Vec::new(),
+            timed_out_processes: Vec::new(),
+            backtraces: vec![moire_types::SnapshotBacktrace {
+                backtrace_id,
+                frame_ids: Vec::new(),
+            }],
+            frames: Vec::new(),
+            findings: Vec::new(),
+            annotations: Vec::new(),
+            sizing_hints: Vec::new(),
+            detection_config_version: None,
+            analysis_error: None,
+        };
+        let merged = merge_snapshots(vec![dump(1), dump(2)]);
+        assert_eq!(merged.snapshot_id, 2);
+        assert_eq!(merged.captured_at_unix_ms, 2_000);
+        assert_eq!(merged.backtraces.len(), 1);
+    }
+
+    #[test]
+    fn coverage_counts_raw_primitives_outside_moire_wrappers() {
+        let frame = |function_name: &str| moire_types::SnapshotFrameRecord {
+            frame_id: moire_trace_types::FrameId::next().unwrap(),
+            frame: SnapshotBacktraceFrame::Resolved(moire_types::BacktraceFrameResolved {
+                module_path: String::from("/bin/app"),
+                function_name: String::from(function_name),
+                source_file: String::from("src/main.rs"),
+                line: None,
+            }),
+        };
+        let snapshot = SnapshotCutResponse {
+            snapshot_id: 1,
+            captured_at_unix_ms: 0,
+            max_skew_ms: None,
+            processes: Vec::new(),
+            timed_out_processes: Vec::new(),
+            backtraces: Vec::new(),
+            frames: vec![
+                frame("tokio::sync::mpsc::bounded::Sender<T>::send::{{closure}}"),
+                frame("moire_tokio::sync::mpsc::Sender<T>::send"),
+                frame("tokio::sync::mutex::Mutex<T>::lock::{{closure}}"),
+                frame("app::main"),
+            ],
+            findings: Vec::new(),
+            annotations: Vec::new(),
+            sizing_hints: Vec::new(),
+            detection_config_version: None,
+            analysis_error: None,
+        };
+        let mut out = String::new();
+        write_coverage(&mut out, &snapshot, &[]);
+        assert_eq!(
+            out,
+            "Coverage\n  uninstrumented tokio Mutex: 1 frame(s)\n  uninstrumented tokio mpsc channel: 1 frame(s)\n"
+        );
+    }
+}
@@ -0,0 +1,64 @@
+//! How severe a wait edge is.
+//!
+//! Every severity the graph reports, from edge weights in exports to the
+//! ranking of deadlock candidates, comes from the [`SeverityPolicy`] the
+//! [`WaitGraph`](super::WaitGraph) was built with. [`DefaultSeverityPolicy`]
+//! is the built-in scoring. A service whose RPCs are naturally slow can wrap
+//! it to stop old RPC waits from outranking everything else:
+//!
+//! ```ignore
+//! struct PatientRpcs;
+//!
+//! impl SeverityPolicy for PatientRpcs {
+//!     fn edge_score(&self, src: &WaitNode, dst: &WaitNode) -> ScoreBreakdown {
+//!         if matches!(dst.kind.as_str(), "request" | "response") {
+//!             let mut score = ScoreBreakdown::default();
+//!             score.add("slow_rpc_expected", 1);
+//!             return score;
+//!         }
+//!         DefaultSeverityPolicy.edge_score(src, dst)
+//!     }
+//! }
+//!
+//! let graph = WaitGraph::build_with_policy(&snapshot, Arc::new(PatientRpcs))?;
+//! ```
+
+use moire_graph_core::has_external_wake_source;
+use moire_types::ScoreBreakdown;
+
+use super::WaitNode;
+use super::detect::age_bonus;
+
+/// Scores one wait edge, from the node waiting to the node it waits on.
+///
+/// The score's total is the edge's severity. Its parts are reported alongside
+/// findings, so each should name the rule that contributed it.
+pub trait SeverityPolicy: Send + Sync {
+    fn edge_score(&self, src: &WaitNode, dst: &WaitNode) -> ScoreBreakdown;
+}
+
+// r[impl api.idle-wait]
+/// The built-in scoring.
+///
+/// Waits on something only the graph itself can release rank above waits an
+/// external event could resolve, and old waits rank above fresh ones. Waits
+/// marked idle at instrumentation time rank zero.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct DefaultSeverityPolicy;
+
+impl SeverityPolicy for DefaultSeverityPolicy {
+    fn edge_score(&self, src: &WaitNode, dst: &WaitNode) -> ScoreBreakdown {
+        let mut score = ScoreBreakdown::default();
+        if src.idle.is_some() {
+            score.add("idle_wait", 0);
+            return score;
+        }
+        if has_external_wake_source(dst.kind.as_str()) {
+            score.add("external_wake_source", 1);
+        } else {
+            score.add("graph_only_wake_source", 3);
+        }
+        score.add("wait_age", age_bonus(src));
+        score
+    }
+}
@@ -0,0 +1,274 @@
+//! SQLite export of a [`WaitGraph`] for ad-hoc SQL.
+//!
+//! `moire sqlite --file snapshot.json --out graph.db` writes one snapshot's
+//! graph into a standalone database, so questions like "locks with more than
+//! five waiters, by process" are a query away:
+//!
+//! ```sql
+//! SELECT process_id, name, waiters FROM nodes
+//! WHERE kind = 'lock' AND waiters > 5 ORDER BY process_id, waiters DESC;
+//! ```
+
+use std::collections::HashMap;
+
+use facet::Facet;
+use rusqlite_facet::StatementFacetExt;
+
+use super::WaitGraph;
+use super::config::DetectionThresholds;
+use super::detect::{
+    find_deadlock_candidates, find_livelock_candidates, find_starvation_candidates,
+};
+
+const SCHEMA: &str = "
+    DROP TABLE IF EXISTS candidate_nodes;
+    DROP TABLE IF EXISTS candidates;
+    DROP TABLE IF EXISTS edges;
+    DROP TABLE IF EXISTS nodes;
+
+    CREATE TABLE nodes (
+        node_key TEXT PRIMARY KEY,
+        process_id TEXT NOT NULL,
+        entity_id TEXT NOT NULL,
+        name TEXT NOT NULL,
+        kind TEXT NOT NULL,
+        age_ms INTEGER NOT NULL,
+        waiters INTEGER NOT NULL,
+        waits_on INTEGER NOT NULL,
+        idle TEXT
+    );
+    CREATE INDEX idx_nodes_kind ON nodes (kind);
+
+    CREATE TABLE edges (
+        src_key TEXT NOT NULL,
+        dst_key TEXT NOT NULL,
+        process_id TEXT NOT NULL,
+        waited_ms INTEGER,
+        reason TEXT
+    );
+    CREATE INDEX idx_edges_dst_key ON edges (dst_key);
+
+    CREATE TABLE candidates (
+        candidate_id INTEGER PRIMARY KEY,
+        analysis TEXT NOT NULL,
+        score INTEGER NOT NULL,
+        reasons TEXT NOT NULL
+    );
+
+    CREATE TABLE candidate_nodes (
+        candidate_id INTEGER NOT NULL,
+        node_key TEXT NOT NULL,
+        PRIMARY KEY (candidate_id, node_key)
+    );
+";
+
+#[derive(Facet)]
+struct NodeInsertParams {
+    node_key: String,
+    process_id: String,
+    entity_id: String,
+    name: String,
+    kind: String,
+    age_ms: i64,
+    waiters: i64,
+    waits_on: i64,
+    idle: Option<String>,
+}
+
+#[derive(Facet)]
+struct EdgeInsertParams {
+    src_key: String,
+    dst_key: String,
+    process_id: String,
+    waited_ms: Option<i64>,
+    reason: Option<String>,
+}
+
+#[derive(Facet)]
+struct CandidateInsertParams {
+    candidate_id: i64,
+    analysis: String,
+    score: i64,
+    reasons: String,
+}
+
+#[derive(Facet)]
+struct CandidateNodeInsertParams {
+    candidate_id: i64,
+    node_key: String,
+}
+
+impl WaitGraph {
+    /// Replaces the `nodes`, `edges`, `candidates` and `candidate_nodes` tables
+    /// of `conn` with this graph. `waiters` and `waits_on` count `waiting_on`
+    /// edges into and out of a node; an edge's `reason` is its `EdgeReason`
+    /// in snake_case, when the recording has one. Candidates are the
+    /// deadlock, livelock and starvation candidates under the default
+    /// detection thresholds, with comma-separated reasons.
+    pub fn write_sqlite(&self, conn: &rusqlite::Connection) -> Result<(), String> {
+        let tx = conn
+            .unchecked_transaction()
+            .map_err(|error| format!("start transaction: {error}"))?;
+        tx.execute_batch(SCHEMA)
+            .map_err(|error| format!("create wait graph tables: {error}"))?;
+
+        let mut waiters: HashMap<&str, i64> = HashMap::new();
+        for dsts in self.adjacency.values() {
+            for dst in dsts {
+                *waiters.entry(dst.as_str()).or_default() += 1;
+            }
+        }
+
+        {
+            let mut insert_node_stmt = tx
+                .prepare(
+                    "INSERT INTO nodes (node_key, process_id, entity_id, name, kind, age_ms, waiters, waits_on, idle)
+                     VALUES (:node_key, :process_id, :entity_id, :name, :kind, :age_ms, :waiters, :waits_on, :idle)",
+                )
+                .map_err(|error| format!("prepare node insert: {error}"))?;
+            for (key, node) in &self.nodes {
+                insert_node_stmt
+                    .facet_execute_ref(&NodeInsertParams {
+                        node_key: key.clone(),
+                        process_id: node.process_id.clone(),
+                        entity_id: node.entity_id.clone(),
+                        name: node.name.clone(),
+                        kind: node.kind.clone(),
+                        age_ms: node.ptime_now_ms.saturating_sub(node.birth_ms) as i64,
+                        waiters: waiters.get(key.as_str()).copied().unwrap_or(0),
+                        waits_on: self.adjacency.get(key).map_or(0, |dsts| dsts.len() as i64),
+                        idle: node.idle.clone(),
+                    })
+                    .map_err(|error| format!("insert node: {error}"))?;
+            }
+
+            let mut insert_edge_stmt = tx
+                .prepare(
+                    "INSERT INTO edges (src_key, dst_key, process_id, waited_ms, reason)
+                     VALUES (:src_key, :dst_key, :process_id, :waited_ms, :reason)",
+                )
+                .map_err(|error| format!("prepare edge insert: {error}"))?;
+            for edge in &self.edges {
+                let waited_ms = edge.since_ms.and_then(|since_ms| {
+                    let src = self.nodes.get(&edge.src_key)?;
+                    Some(src.ptime_now_ms.saturating_sub(since_ms) as i64)
+                });
+                insert_edge_stmt
+                    .facet_execute_ref(&EdgeInsertParams {
+                        src_key: edge.src_key.clone(),
+                        dst_key: edge.dst_key.clone(),
+                        process_id: edge.process_id.clone(),
+                        waited_ms,
+                        reason: edge.reason.map(|reason| String::from(reason.as_str())),
+                    })
+                    .map_err(|error| format!("insert edge: {error}"))?;
+            }
+
+            let thresholds = DetectionThresholds::default();
+            let mut candidates = Vec::new();
+            for candidate in find_deadlock_candidates(self, None).candidates {
+                candidates.push((
+                    "deadlock",
+                    candidate.score.total(),
+                    candidate.reasons,
+                    candidate.node_keys,
+                ));
+            }
+            for candidate in find_livelock_candidates(self, &thresholds) {
+                candidates.push((
+                    "livelock",
+                    candidate.score.total(),
+                    candidate.reasons,
+                    candidate.node_keys,
+                ));
+            }
+            for candidate in find_starvation_candidates(self, &thresholds) {
+                let mut node_keys = vec![candidate.resource_key];
+                node_keys.extend(candidate.waiter_keys);
+                candidates.push((
+                    "starvation",
+                    candidate.score.total(),
+                    candidate.reasons,
+                    node_keys,
+                ));
+            }
+
+            let mut insert_candidate_stmt = tx
+                .prepare(
+                    "INSERT INTO candidates (candidate_id, analysis, score, reasons)
+                     VALUES (:candidate_id, :analysis, :score, :reasons)",
+                )
+                .map_err(|error| format!("prepare candidate insert: {error}"))?;
+            let mut insert_candidate_node_stmt = tx
+                .prepare(
+                    "INSERT OR IGNORE INTO candidate_nodes (candidate_id, node_key)
+                     VALUES (:candidate_id, :node_key)",
+                )
+                .map_err(|error| format!("prepare candidate node insert: {error}"))?;
+            for (index, (analysis, score, reasons, node_keys)) in candidates.into_iter().enumerate()
+            {
+                let candidate_id = index as i64 + 1;
+                insert_candidate_stmt
+                    .facet_execute_ref(&CandidateInsertParams {
+                        candidate_id,
+                        analysis: String::from(analysis),
+                        score: i64::from(score),
+                        reasons: reasons.join(","),
+                    })
+                    .map_err(|error| format!("insert candidate: {error}"))?;
+                for node_key in node_keys {
+                    insert_candidate_node_stmt
+                        .facet_execute_ref(&CandidateNodeInsertParams {
+                            candidate_id,
+                            node_key,
+                        })
+                        .map_err(|error| format!("insert candidate node: {error}"))?;
+                }
+            }
+        }
+
+        tx.commit()
+            .map_err(|error| format!("commit wait graph tables: {error}"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::WaitNode;
+    use super::*;
+
+    fn node(key: &str, kind: &str) -> WaitNode {
+        WaitNode::test("p", key, kind).at(60_000)
+    }
+
+    #[test]
+    fn waiters_and_cycles_are_queryable() {
+        let nodes = [("a", "future"), ("b", "future"), ("l", "lock")]
+            .map(|(key, kind)| (key, node(key, kind)));
+        let mut graph = WaitGraph::test(nodes, [("a", "l"), ("b", "l"), ("l", "a")]);
+        for edge in &mut graph.edges {
+            edge.since_ms = Some(30_000);
+        }
+
+        let conn = rusqlite::Connection::open_in_memory().expect("in-memory db");
+        graph.write_sqlite(&conn).expect("export");
+        // Exporting again replaces the tables instead of failing on them.
+        graph.write_sqlite(&conn).expect("re-export");
+
+        let waiters: i64 = conn
+            .query_row("SELECT waiters FROM nodes WHERE kind = 'lock'", [], |row| {
+                row.get(0)
+            })
+            .expect("lock row");
+        assert_eq!(waiters, 2);
+        let deadlock_members: i64 = conn
+            .query_row(
+                "SELECT COUNT(*) FROM candidate_nodes JOIN candidates USING (candidate_id)
+                 WHERE analysis = 'deadlock'",
+                [],
+                |row| row.get(0),
+            )
+            .expect("candidate count");
+        assert_eq!(deadlock_members, 2);
+    }
+}
@@ -0,0 +1,120 @@
+//! Read-only walk over a [`WaitGraph`] for visualization adapters.
+//!
+//! [`WaitGraph::visit`] hands out [`NodeView`]s and [`EdgeView`]s instead of
+//! the graph's own structs. Their accessors return plain strings and numbers,
+//! so an adapter keeps compiling when the graph model grows fields or entity
+//! kinds; the kind is the snake_case wire name, and an adapter should draw
+//! kinds it does not recognize with a fallback style.
+
+use super::{WaitEdgeRuntime, WaitGraph, WaitNode};
+
+/// One node of the graph, as seen by [`WaitGraph::visit`].
+#[derive(Clone, Copy)]
+pub struct NodeView<'a> {
+    key: &'a str,
+    node: &'a WaitNode,
+}
+
+/// One `waiting_on` edge, as seen by [`WaitGraph::visit`].
+#[derive(Clone, Copy)]
+pub struct EdgeView<'a> {
+    edge: &'a WaitEdgeRuntime,
+    severity: u32,
+}
+
+impl<'a> NodeView<'a> {
+    /// `process_id::entity_id`; unique across the graph.
+    pub fn key(&self) -> &'a str {
+        self.key
+    }
+
+    pub fn process_id(&self) -> &'a str {
+        &self.node.process_id
+    }
+
+    pub fn entity_id(&self) -> &'a str {
+        &self.node.entity_id
+    }
+
+    /// Human-readable name the entity was registered with.
+    pub fn label(&self) -> &'a str {
+        &self.node.name
+    }
+
+    /// Entity kind, e.g. `future`, `lock` or `mpsc_tx`; `actor` for a
+    /// collapsed actor.
+    pub fn kind(&self) -> &'a str {
+        &self.node.kind
+    }
+
+    /// Time since the entity was created, in its process's clock.
+    pub fn age_ms(&self) -> u64 {
+        self.node.ptime_now_ms.saturating_sub(self.node.birth_ms)
+    }
+
+    /// Why the node is an expected, possibly endless wait, if it is one.
+    pub fn idle_reason(&self) -> Option<&'a str> {
+        self.node.idle.as_deref()
+    }
+}
+
+impl<'a> EdgeView<'a> {
+    /// Key of the waiting node.
+    pub fn src_key(&self) -> &'a str {
+        &self.edge.src_key
+    }
+
+    /// Key of the node waited on.
+    pub fn dst_key(&self) -> &'a str {
+        &self.edge.dst_key
+    }
+
+    pub fn process_id(&self) -> &'a str {
+        &self.edge.process_id
+    }
+
+    /// Why the waiter waits, e.g. `mutex_wait`, when the recording says.
+    pub fn reason(&self) -> Option<&'static str> {
+        self.edge.reason.map(|reason| reason.as_str())
+    }
+
+    /// How bad the wait looks, from 0 (an expected wait) upwards. The same
+    /// score the Graphviz export colors edges by.
+    pub fn severity(&self) -> u32 {
+        self.severity
+    }
+
+    /// When the wait started, if the recording carries edge timestamps.
+    pub fn since_ms(&self) -> Option<u64> {
+        self.edge.since_ms
+    }
+}
+
+impl WaitGraph {
+    /// Calls `on_node` for every node, sorted by key, then `on_edge` for every
+    /// edge, sorted by source then destination key. The order only depends on
+    /// the graph's contents.
+    pub fn visit<'a>(
+        &'a self,
+        mut on_node: impl FnMut(NodeView<'a>),
+        mut on_edge: impl FnMut(EdgeView<'a>),
+    ) {
+        let mut nodes = self.nodes.iter().collect::<Vec<_>>();
+        nodes.sort_by_key(|(key, _)| *key);
+        for (key, node) in nodes {
+            on_node(NodeView { key, node });
+        }
+
+        let mut edges = self.edges.iter().collect::<Vec<_>>();
+        edges.sort_by(|a, b| {
+            (a.src_key.as_str(), a.dst_key.as_str()).cmp(&(b.src_key.as_str(), b.dst_key.as_str()))
+        });
+        for edge in edges {
+            let severity = match (self.nodes.get(&edge.src_key), self.nodes.get(&edge.dst_key)) {
+                (Some(src), Some(dst)) => self.edge_severity(src, dst),
+                _ => 0,
+            };
+            on_edge(EdgeView { edge, severity });
+        }
+    }
+}
@@ -0,0 +1,135 @@
+//! Everything waiting on one resource, as a single logical edge.
+//!
+//! When forty tasks wait on one lock the graph holds forty `waiting_on`
+//! edges, and anything that describes the lock by picking one of them picks
+//! arbitrarily. [`WaitGraph::waiters_of`] folds them into a [`WaiterGroup`]
+//! ordered oldest wait first, so the description can lead with the waiter
+//! that has been stuck longest.
+
+use moire_types::EdgeReason;
+
+use super::WaitGraph;
+
+/// The waiters of one resource, oldest wait first.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct WaiterGroup {
+    pub resource_key: String,
+    pub waiters: Vec<Waiter>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Waiter {
+    pub key: String,
+    /// How long the waiter has waited, if the recording carries edge
+    /// timestamps.
+    pub waited_ms: Option<u64>,
+    pub reason: Option<EdgeReason>,
+}
+
+impl WaiterGroup {
+    pub fn oldest(&self) -> Option<&Waiter> {
+        self.waiters.first()
+    }
+
+    /// The group in words, e.g. `worker (oldest, 94000ms) and 39 others wait
+    /// on cache`.
+    pub fn describe(&self, graph: &WaitGraph) -> String {
+        let name = |key: &str| {
+            graph
+                .nodes
+                .get(key)
+                .map_or_else(|| key.to_owned(), |node| node.name.clone())
+        };
+        let resource = name(&self.resource_key);
+        let Some(oldest) = self.oldest() else {
+            return format!("nothing waits on {resource}");
+        };
+        let mut text = name(&oldest.key);
+        match (self.waiters.len(), oldest.waited_ms) {
+            (1, Some(ms)) => text.push_str(&format!(" ({ms}ms)")),
+            (_, Some(ms)) => text.push_str(&format!(" (oldest, {ms}ms)")),
+            (_, None) => {}
+        }
+        match self.waiters.len() - 1 {
+            0 => text.push_str(" waits on "),
+            1 => text.push_str(" and 1 other wait on "),
+            others => text.push_str(&format!(" and {others} others wait on ")),
+        }
+        text.push_str(&resource);
+        text
+    }
+}
+
+impl WaitGraph {
+    /// Every node waiting on `key` directly. Waiters with a known wait age
+    /// come first, longest first; the rest follow, sorted by key.
+    pub fn waiters_of(&self, key: &str) -> WaiterGroup {
+        let mut waiters = self
+            .edges
+            .iter()
+            .filter(|edge| edge.dst_key == key)
+            .map(|edge| Waiter {
+                key: edge.src_key.clone(),
+                waited_ms: edge.since_ms.and_then(|since_ms| {
+                    let now_ms = self.nodes.get(&edge.src_key)?.ptime_now_ms;
+                    Some(now_ms.saturating_sub(since_ms))
+                }),
+                reason: edge.reason,
+            })
+            .collect::<Vec<_>>();
+        waiters.sort_by(|a, b| {
+            b.waited_ms
+                .cmp(&a.waited_ms)
+                .then_with(|| a.key.cmp(&b.key))
+        });
+        WaiterGroup {
+            resource_key: key.to_owned(),
+            waiters,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph::WaitNode;
+
+    fn node(key: &str, kind: &str) -> WaitNode {
+        WaitNode::test("p", key, kind).at(100_000)
+    }
+
+    #[test]
+    fn waiters_are_folded_into_one_group_oldest_first() {
+        let waiters = [
+            ("fresh", Some(99_000)),
+            ("untimed", None),
+            ("stuck", Some(6_000)),
+        ];
+        let nodes = waiters
+            .iter()
+            .map(|(waiter, _)| (format!("p::{waiter}"), node(waiter, "future")))
+            .chain([(String::from("p::cache"), node("cache", "lock"))]);
+        let edges = waiters
+            .iter()
+            .map(|(waiter, _)| (format!("p::{waiter}"), "p::cache"));
+        let mut graph = WaitGraph::test(nodes, edges);
+        for (edge, (_, since_ms)) in graph.edges.iter_mut().zip(waiters) {
+            edge.since_ms = since_ms;
+            edge.reason = Some(EdgeReason::MutexWait);
+        }
+
+        let group = graph.waiters_of("p::cache");
+        let keys = group
+            .waiters
+            .iter()
+            .map(|waiter| waiter.key.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(keys, ["p::stuck", "p::fresh", "p::untimed"]);
+        assert_eq!(group.oldest().and_then(|w| w.waited_ms), Some(94_000));
+        assert_eq!(
+            group.describe(&graph),
+            "stuck (oldest, 94000ms) and 2 others wait on cache"
+        );
+        assert!(graph.waiters_of("p::stuck").waiters.is_empty());
+    }
+}
@@ -1,6 +1,7 @@
 pub mod api;
 pub mod app;
 pub mod db;
+pub mod graph;
 pub mod mcp;
 pub mod proxy;
 pub mod recording;
@@ -2,13 +2,27 @@ use std::path::{Path, PathBuf};
 use std::sync::Arc;
 use std::time::{Duration, Instant};
 
-use facet::Facet;
+use facet::{Facet, Type, UserType};
 use figue as args;
-use moire_types::{CutStatusResponse, QueryRequest, SqlRequest, TriggerCutResponse};
+use moire_types::{
+    CutStatusResponse, QueryRequest, SnapshotCutResponse, SqlRequest, TriggerCutResponse,
+    snapshot_from_dump, snapshot_from_json, snapshot_to_json, snapshot_to_msgpack,
+};
+use moire_web::api::snapshot::snapshot_encoding_from_env;
 use moire_web::app::{AppState, DevProxyState, build_router};
 use moire_web::db::{Db, init_sqlite, load_next_connection_id};
+use moire_web::graph::WaitGraph;
+use moire_web::graph::analysis::AnalysisRegistry;
+use moire_web::graph::config::{DetectionConfig, spawn_detection_config_reloader};
+use moire_web::graph::export::to_dot;
+use moire_web::graph::report::{merge_snapshots, to_report};
 use moire_web::mcp::run_mcp_server;
 use moire_web::proxy::{DEFAULT_VITE_ADDR, start_vite_dev_server};
+use moire_web::recording::history::{HistoryConfig, spawn_history_recorder};
+use moire_web::snapshot::otlp::to_otlp_json;
+use moire_web::snapshot::trace_event::to_trace_event_json;
+#[cfg(unix)]
+use moire_web::tcp::run_socket_puller;
 use moire_web::tcp::run_tcp_acceptor;
 use tokio::net::TcpListener;
 use tracing::{error, info};
@@ -54,9 +68,69 @@ enum ClientCommand {
         #[facet(args::named, default)]
         limit: Option<u32>,
     },
+    /// Print the current snapshot as a dump the other commands can read
+    /// back with `--file`.
     Snapshot {
         #[facet(args::named, default)]
         url: Option<String>,
+        /// Write the dump to this file instead; a `.msgpack` extension writes
+        /// the compact MessagePack encoding.
+        #[facet(args::named, default)]
+        out: Option<String>,
+    },
+    /// Print a dump, in either encoding, as JSON.
+    Convert {
+        #[facet(args::positional)]
+        file: String,
+    },
+    Dot {
+        #[facet(args::named, default)]
+        url: Option<String>,
+        /// Snapshot JSON dump to render instead of fetching the current one.
+        #[facet(args::named, default)]
+        file: Option<String>,
+    },
+    /// Analyze snapshot JSON dumps offline: findings, worst waits and task
+    /// spawn lineage.
+    Analyze {
+        #[facet(args::positional)]
+        files: Vec<String>,
+        /// How many of the longest waits to list.
+        #[facet(args::named, default)]
+        top: Option<usize>,
+    },
+    /// Export poll and wait timelines as Chrome trace events, for
+    /// `chrome://tracing` or Perfetto.
+    Trace {
+        #[facet(args::named, default)]
+        url: Option<String>,
+        /// Snapshot JSON dump to export instead of fetching the current one.
+        #[facet(args::named, default)]
+        file: Option<String>,
+    },
+    /// Export RPCs and tracked tasks as OpenTelemetry spans. Prints the
+    /// OTLP/JSON request unless an endpoint is given.
+    Otel {
+        #[facet(args::named, default)]
+        url: Option<String>,
+        /// Snapshot JSON dump to export instead of fetching the current one.
+        #[facet(args::named, default)]
+        file: Option<String>,
+        /// OTLP/HTTP collector base URL, e.g. `http://127.0.0.1:4318`.
+        #[facet(args::named, default)]
+        endpoint: Option<String>,
+    },
+    /// Write the wait graph and its candidates to a SQLite database for
+    /// ad-hoc SQL.
+    Sqlite {
+        #[facet(args::named, default)]
+        url: Option<String>,
+        /// Snapshot JSON dump to export instead of fetching the current one.
+        #[facet(args::named, default)]
+        file: Option<String>,
+        /// Database file to write; its wait graph tables are replaced.
+        #[facet(args::named)]
+        out: String,
     },
 }
 
@@ -68,6 +142,7 @@ const DEFAULT_BASE_URL: &str = "http://127.0.0.1:9130";
 const DEFAULT_POLL_MS: u64 = 100;
 const DEFAULT_TIMEOUT_MS: u64 = 5_000;
 const DEFAULT_QUERY_LIMIT: u32 = 50;
+const DEFAULT_WORST_WAITS: usize = 10;
 
 fn main() {
     // Reaper mode: watch the pipe, kill the process group when it closes.
@@ -110,8 +185,16 @@ fn main() {
         });
 }
 
+/// Whether `value` names a `ClientCommand`, read off the enum's variants so
+/// new subcommands can't fall through to starting the server.
 fn is_client_command(value: &str) -> bool {
-    matches!(value, "cut" | "sql" | "query" | "snapshot")
+    let Type::User(UserType::Enum(commands)) = ClientCommand::SHAPE.ty else {
+        unreachable!("ClientCommand is an enum");
+    };
+    commands
+        .variants
+        .iter()
+        .any(|variant| variant.name.eq_ignore_ascii_case(value))
 }
 
 #[cfg(unix)]
@@ -176,7 +259,26 @@ async fn run_server() -> Result<(), String> {
         None
     };
 
-    let state = AppState::new(db, next_conn_id, dev_proxy, frontend_dist.clone());
+    let mut state = AppState::new(db, next_conn_id, dev_proxy, frontend_dist.clone())
+        .with_snapshot_encoding(snapshot_encoding_from_env()?);
+    if let Some((path, detection)) = DetectionConfig::from_env(&state.analyses)? {
+        state = state.with_detection_config(detection);
+        spawn_detection_config_reloader(state.clone(), path);
+    }
+    if let Some(history) = HistoryConfig::from_env()? {
+        spawn_history_recorder(state.clone(), history);
+    }
+    // r[impl config.web.pull-sockets]
+    #[cfg(unix)]
+    for path in std::env::var("MOIRE_PULL_SOCKETS")
+        .unwrap_or_default()
+        .split(',')
+        .map(str::trim)
+        .filter(|path| !path.is_empty())
+    {
+        info!(path, "moire-web pulling from process socket");
+        tokio::spawn(run_socket_puller(PathBuf::from(path), state.clone()));
+    }
 
     let tcp_listener = TcpListener::bind(&tcp_addr)
         .await
@@ -256,7 +358,17 @@ fn run_client() -> Result<(), String> {
         } => run_cut(url, poll_ms, timeout_ms),
         ClientCommand::Sql { url, query } => run_sql(url, query),
         ClientCommand::Query { url, name, limit } => run_query_pack(url, name, limit),
-        ClientCommand::Snapshot { url } => run_snapshot(url),
+        ClientCommand::Snapshot { url, out } => run_snapshot(url, out),
+        ClientCommand::Convert { file } => run_convert(file),
+        ClientCommand::Dot { url, file } => run_dot(url, file),
+        ClientCommand::Analyze { files, top } => run_analyze(files, top),
+        ClientCommand::Trace { url, file } => run_trace(url, file),
+        ClientCommand::Otel {
+            url,
+            file,
+            endpoint,
+        } => run_otel(url, file, endpoint),
+        ClientCommand::Sqlite { url, file, out } => run_sqlite(url, file, out),
     }
 }
 
@@ -354,29 +466,135 @@ fn run_query_pack(url: Option<String>, name: String, limit: Option<u32>) -> Resu
     Ok(())
 }
 
-fn run_snapshot(url: Option<String>) -> Result<(), String> {
+fn run_snapshot(url: Option<String>, out: Option<String>) -> Result<(), String> {
+    let snapshot = snapshot_from_json(&fetch_snapshot_json(url)?)?;
+    let Some(out) = out else {
+        return print_dump_json(&snapshot);
+    };
+    let dump = if Path::new(&out)
+        .extension()
+        .is_some_and(|ext| ext == "msgpack")
+    {
+        snapshot_to_msgpack(&snapshot)?
+    } else {
+        snapshot_to_json(&snapshot)?.into_bytes()
+    };
+    std::fs::write(&out, &dump).map_err(|e| format!("write {out}: {e}"))?;
+    eprintln!("wrote {} bytes to {out}", dump.len());
+    Ok(())
+}
+
+fn run_convert(file: String) -> Result<(), String> {
+    print_dump_json(&read_dump(&file)?)
+}
+
+fn print_dump_json(snapshot: &SnapshotCutResponse) -> Result<(), String> {
+    let pretty = facet_json::to_string_pretty(
+        &facet_json::from_str::<facet_value::Value>(&snapshot_to_json(snapshot)?)
+            .map_err(|e| format!("decode snapshot dump as json: {e}"))?,
+    )
+    .map_err(|e| format!("pretty snapshot dump: {e}"))?;
+    println!("{pretty}");
+    Ok(())
+}
+
+fn read_dump(path: &str) -> Result<SnapshotCutResponse, String> {
+    let bytes = std::fs::read(path).map_err(|e| format!("read snapshot {path}: {e}"))?;
+    snapshot_from_dump(&bytes).map_err(|e| format!("{path}: {e}"))
+}
+
+/// The dump at `file`, or the current snapshot fetched from the server.
+fn load_snapshot(url: Option<String>, file: Option<String>) -> Result<SnapshotCutResponse, String> {
+    match file {
+        Some(path) => read_dump(&path),
+        None => snapshot_from_json(&fetch_snapshot_json(url)?),
+    }
+}
+
+fn run_dot(url: Option<String>, file: Option<String>) -> Result<(), String> {
+    let snapshot = load_snapshot(url, file)?;
+    let graph = WaitGraph::build(&snapshot)?;
+    print!("{}", to_dot(&graph));
+    Ok(())
+}
+
+fn run_trace(url: Option<String>, file: Option<String>) -> Result<(), String> {
+    let snapshot = load_snapshot(url, file)?;
+    println!("{}", to_trace_event_json(&snapshot)?);
+    Ok(())
+}
+
+fn run_otel(
+    url: Option<String>,
+    file: Option<String>,
+    endpoint: Option<String>,
+) -> Result<(), String> {
+    let snapshot = load_snapshot(url, file)?;
+    let body = to_otlp_json(&snapshot)?;
+    match endpoint {
+        Some(endpoint) => {
+            let traces_url = format!("{}/v1/traces", endpoint.trim_end_matches('/'));
+            http_post_json(&traces_url, &body)?;
+        }
+        None => println!("{body}"),
+    }
+    Ok(())
+}
+
+fn run_sqlite(url: Option<String>, file: Option<String>, out: String) -> Result<(), String> {
+    let snapshot = load_snapshot(url, file)?;
+    let graph = WaitGraph::build(&snapshot)?;
+    let conn = rusqlite::Connection::open(&out).map_err(|e| format!("open {out}: {e}"))?;
+    graph.write_sqlite(&conn)?;
+    println!(
+        "wrote {} nodes and {} edges to {out}",
+        graph.nodes.len(),
+        graph.edges.len()
+    );
+    Ok(())
+}
+
+fn run_analyze(files: Vec<String>, top: Option<usize>) -> Result<(), String> {
+    if files.is_empty() {
+        return Err(String::from(
+            "analyze: pass at least one snapshot JSON file",
+        ));
+    }
+    let dumps = files
+        .iter()
+        .map(|path| read_dump(path))
+        .collect::<Result<Vec<_>, _>>()?;
+    let snapshot = merge_snapshots(dumps);
+    let graph = WaitGraph::build(&snapshot)?;
+    let findings = AnalysisRegistry::default().run_all(&snapshot)?;
+    print!(
+        "{}",
+        to_report(
+            &snapshot,
+            &graph,
+            &findings,
+            top.unwrap_or(DEFAULT_WORST_WAITS)
+        )
+    );
+    Ok(())
+}
+
+/// Fetches the current snapshot, taking a fresh one if there is none yet.
+fn fetch_snapshot_json(url: Option<String>) -> Result<String, String> {
     let base_url = url.unwrap_or_else(|| DEFAULT_BASE_URL.to_string());
     let base_url = base_url.trim_end_matches('/');
     let current_url = format!("{base_url}/api/snapshot/current");
 
-    let response = match ureq::get(&current_url).call() {
+    match ureq::get(&current_url).call() {
         Ok(response) => response
             .into_string()
-            .map_err(|e| format!("read GET response body: {e}"))?,
+            .map_err(|e| format!("read GET response body: {e}")),
         Err(ureq::Error::Status(404, _)) => {
             let snapshot_url = format!("{base_url}/api/snapshot");
-            http_post_json(&snapshot_url, "{}")?
+            http_post_json(&snapshot_url, "{}")
         }
-        Err(e) => return Err(format!("GET {current_url}: {e}")),
-    };
-
-    let pretty = facet_json::to_string_pretty(
-        &facet_json::from_str::<facet_value::Value>(&response)
-            .map_err(|e| format!("decode snapshot response as json: {e}"))?,
-    )
-    .map_err(|e| format!("pretty snapshot response: {e}"))?;
-    println!("{pretty}");
-    Ok(())
+        Err(e) => Err(format!("GET {current_url}: {e}")),
+    }
 }
 
 fn http_get_text(url: &str) -> Result<String, String> {
@@ -492,3 +710,22 @@ fn validate_frontend_dist(path: PathBuf, source: &str) -> Result<PathBuf, String
     }
     Ok(path)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::is_client_command;
+
+    #[test]
+    fn every_subcommand_runs_the_client() {
+        for command in [
+            "cut", "sql", "query", "snapshot", "convert", "dot", "analyze", "trace", "otel",
+            "sqlite",
+        ] {
+            assert!(
+                is_client_command(command),
+                "{command} would start the server"
+            );
+        }
+        assert!(!is_client_command("--dev"));
+    }
+}
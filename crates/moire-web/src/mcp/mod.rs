@@ -1,7 +1,8 @@
+use std::cmp::Reverse;
 use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
 use std::fmt::Write as _;
 use std::sync::Arc;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
 use async_trait::async_trait;
 use axum::extract::State;
@@ -13,8 +14,8 @@ use facet::Facet;
 use moire_trace_types::{BacktraceId, FrameId};
 use moire_types::{
     BacktraceFrameResolved, BacktraceFrameUnresolved, CutId, EdgeKind, Entity, EntityBody,
-    EntityId, ProcessId, ProcessSnapshotView, SnapshotBacktrace, SnapshotBacktraceFrame,
-    SnapshotCutResponse, TriggerCutResponse,
+    ProcessSnapshotView, ScoreBreakdown, SnapshotBacktraceFrame, SnapshotCutResponse,
+    TriggerCutResponse,
 };
 use moire_wire::{ServerMessage, encode_server_message_default};
 use rust_mcp_sdk::id_generator::{FastIdGenerator, UuidGenerator};
@@ -38,35 +39,32 @@ use crate::api::snapshot::take_snapshot_internal;
 use crate::api::source::lookup_source_text_location_in_db;
 use crate::app::{AppState, CutState, remember_snapshot};
 use crate::db::persist_cut_request;
+use crate::graph::detect::{find_deadlock_candidates, find_livelock_candidates};
+use crate::graph::diff::EdgeChange;
+use crate::graph::explain::BlockagePath;
+use crate::graph::{
+    SOURCE_FRAMES_PER_ITEM, WaitEdgeRuntime, WaitGraph, WaitNode, actor_display_name,
+    actor_mailbox_depth, actor_members, actor_oldest_message_age_ms, actor_processing_state,
+    backtrace_index, compose_node_key, entity_kind_name, frame_catalog,
+    frame_start_index_for_entity, selected_frames_for_backtrace_id, selected_frames_for_entity,
+};
 use crate::snapshot::table::{
     is_pending_frame, load_snapshot_backtrace_table, lookup_frame_source_by_raw,
 };
 use crate::symbolication::symbolicate_pending_frames_for_backtraces;
 use crate::util::time::now_nanos;
-use moire_source_context::{cut_source_compact, extract_enclosing_fn, extract_target_statement};
+use moire_source_context::{
+    cut_source_compact_or_window, extract_enclosing_fn, extract_target_statement,
+};
 
 const DEFAULT_MCP_ENDPOINT: &str = "/mcp";
 const DEFAULT_MCP_PING_INTERVAL: Duration = Duration::from_secs(12);
 const DEFAULT_WAIT_CHAIN_MAX_DEPTH: usize = 16;
 const DEFAULT_WAIT_CHAIN_MAX_RESULTS: usize = 200;
+const DEFAULT_DEADLOCK_SCAN_BUDGET: Duration = Duration::from_millis(500);
 const DEFAULT_SYMBOLICATION_WAIT_TIMEOUT: Duration = Duration::from_secs(3);
 const DEFAULT_SYMBOLICATION_WAIT_TICK: Duration = Duration::from_millis(100);
 const MAX_RENDERED_SOURCE_LINES: usize = 24;
-const SOURCE_FRAMES_PER_ITEM: usize = 3;
-const SYSTEM_CRATES: &[&str] = &[
-    "std",
-    "core",
-    "alloc",
-    "tokio",
-    "tokio_util",
-    "futures",
-    "futures_core",
-    "futures_util",
-    "moire",
-    "moire_trace_capture",
-    "moire_runtime",
-    "moire_tokio",
-];
 
 #[mcp_tool(
     name = "moire_help",
@@ -108,12 +106,14 @@ pub struct WaitChainsTool {
 
 #[mcp_tool(
     name = "moire_deadlock_candidates",
-    description = "Return SCC/cycle-based deadlock candidates with confidence and reason tags."
+    description = "Return SCC/cycle-based deadlock candidates and mutual-wake livelock candidates with confidence and reason tags, most severe first. Time-bounded by budget_ms; partial results are marked incomplete."
 )]
 #[derive(Debug, Deserialize, Serialize, JsonSchema)]
 pub struct DeadlockCandidatesTool {
     #[serde(default)]
     pub snapshot_id: Option<i64>,
+    #[serde(default)]
+    pub budget_ms: Option<u32>,
 }
 
 #[mcp_tool(
@@ -151,6 +151,18 @@ pub struct TaskStateTool {
     pub entity_id: Option<String>,
 }
 
+#[mcp_tool(
+    name = "moire_actor_state",
+    description = "Return actor-oriented state (mailbox depth, oldest message age, processing state) for one actor or all actors, with driver source context."
+)]
+#[derive(Debug, Deserialize, Serialize, JsonSchema)]
+pub struct ActorStateTool {
+    #[serde(default)]
+    pub snapshot_id: Option<i64>,
+    #[serde(default)]
+    pub actor: Option<String>,
+}
+
 #[mcp_tool(
     name = "moire_source_context",
     description = "Lookup frame source context in text/plain format (statement/enclosing fn/compact scope)."
@@ -186,6 +198,29 @@ pub struct DiffSnapshotsTool {
     pub to_snapshot_id: i64,
 }
 
+#[mcp_tool(
+    name = "moire_restart_impact",
+    description = "Estimate what breaks if one process restarts now: incoming RPCs it is serving and their clients, entities other processes wait on, and deadlock candidates it is part of."
+)]
+#[derive(Debug, Deserialize, Serialize, JsonSchema)]
+pub struct RestartImpactTool {
+    #[serde(default)]
+    pub snapshot_id: Option<i64>,
+    pub process_id: String,
+}
+
+#[mcp_tool(
+    name = "moire_explain_blockage",
+    description = "Explain why one entity is blocked: every chain of waits and lock holders from it to a root cause, step by step."
+)]
+#[derive(Debug, Deserialize, Serialize, JsonSchema)]
+pub struct ExplainBlockageTool {
+    #[serde(default)]
+    pub snapshot_id: Option<i64>,
+    pub process_id: String,
+    pub entity_id: String,
+}
+
 tool_box!(
     MoireTools,
     [
@@ -197,9 +232,12 @@ tool_box!(
         EntityTool,
         ChannelStateTool,
         TaskStateTool,
+        ActorStateTool,
         SourceContextTool,
         BacktraceTool,
-        DiffSnapshotsTool
+        DiffSnapshotsTool,
+        RestartImpactTool,
+        ExplainBlockageTool
     ]
 );
 
@@ -323,6 +361,7 @@ struct McpNodeSummary {
 #[derive(Facet)]
 struct McpDeadlockCandidatesResponse {
     pub snapshot_id: i64,
+    pub complete: bool,
     pub candidate_count: usize,
     pub candidates: Vec<McpDeadlockCandidate>,
 }
@@ -330,14 +369,52 @@ struct McpDeadlockCandidatesResponse {
 #[derive(Facet)]
 struct McpDeadlockCandidate {
     pub candidate_id: String,
+    /// `deadlock` or `livelock`.
+    pub kind: String,
     pub confidence: String,
+    pub severity: u32,
+    /// Rules behind `severity`, in the order applied.
+    pub score: ScoreBreakdown,
     pub reasons: Vec<String>,
     pub entity_ids: Vec<String>,
     #[facet(skip_unless_truthy)]
     pub blocked_duration_hint_ms: Option<u64>,
+    #[facet(skip_unless_truthy)]
+    pub wakes_per_sec: Option<u64>,
+    /// Suggested next steps; deadlocks only.
+    #[facet(default)]
+    pub hints: Vec<String>,
     pub cycle_nodes: Vec<McpNodeSummary>,
 }
 
+#[derive(Facet)]
+struct McpRestartImpactResponse {
+    pub snapshot_id: i64,
+    pub process_id: String,
+    pub process_name: String,
+    pub affected_processes: Vec<String>,
+    pub incoming_rpcs: Vec<McpInflightRpc>,
+    pub depended_on: Vec<McpDependedOn>,
+    pub deadlock_candidates: Vec<Vec<String>>,
+}
+
+#[derive(Facet)]
+struct McpInflightRpc {
+    pub method: String,
+    pub response_entity_id: String,
+    pub client_process_id: String,
+    pub request_entity_id: String,
+    pub client_waiters: Vec<String>,
+}
+
+#[derive(Facet)]
+struct McpDependedOn {
+    pub entity_id: String,
+    pub name: String,
+    pub kind: String,
+    pub waiters: Vec<String>,
+}
+
 #[derive(Facet)]
 struct McpEntityResponse {
     pub snapshot_id: i64,
@@ -407,6 +484,36 @@ struct McpTaskState {
     pub sources: Vec<McpSourceContext>,
 }
 
+#[derive(Facet)]
+struct McpActorStateResponse {
+    pub snapshot_id: i64,
+    pub actors: Vec<McpActorSnapshot>,
+}
+
+#[derive(Facet)]
+struct McpActorSnapshot {
+    pub process_id: String,
+    pub scope_id: String,
+    pub name: String,
+    #[facet(skip_unless_truthy)]
+    pub driver_entity_id: Option<String>,
+    #[facet(skip_unless_truthy)]
+    pub mailbox_tx_entity_id: Option<String>,
+    #[facet(skip_unless_truthy)]
+    pub mailbox_rx_entity_id: Option<String>,
+    pub mailbox_depth: u32,
+    #[facet(skip_unless_truthy)]
+    pub mailbox_capacity: Option<u32>,
+    #[facet(skip_unless_truthy)]
+    pub oldest_message_age_ms: Option<u64>,
+    pub processing_state: String,
+    pub blocked_on_entity_ids: Vec<String>,
+    #[facet(skip_unless_truthy)]
+    pub source: Option<McpSourceContext>,
+    #[facet(skip_unless_truthy)]
+    pub sources: Vec<McpSourceContext>,
+}
+
 #[derive(Facet)]
 struct McpSourceContextResponse {
     pub snapshot_id: i64,
@@ -450,6 +557,7 @@ struct McpDiffSnapshotsResponse {
     pub entity_removed: Vec<String>,
     pub waiting_on_added: Vec<String>,
     pub waiting_on_removed: Vec<String>,
+    pub waiting_on_escalated: Vec<McpWaitEscalation>,
     pub channel_changes: Vec<McpChannelDiff>,
     pub task_changes: Vec<McpTaskDiff>,
 }
@@ -461,6 +569,20 @@ struct McpChannelDiff {
     pub after: String,
 }
 
+#[derive(Facet)]
+struct McpWaitEscalation {
+    pub src: String,
+    pub dst: String,
+    #[facet(skip_unless_truthy)]
+    pub severity_before: Option<u32>,
+    #[facet(skip_unless_truthy)]
+    pub severity_after: Option<u32>,
+    #[facet(skip_unless_truthy)]
+    pub wait_ms_before: Option<u64>,
+    #[facet(skip_unless_truthy)]
+    pub wait_ms_after: Option<u64>,
+}
+
 #[derive(Facet)]
 struct McpTaskDiff {
     pub entity_id: String,
@@ -495,33 +617,6 @@ struct McpLineRange {
     pub end: u32,
 }
 
-#[derive(Clone)]
-struct WaitNode {
-    process_id: String,
-    ptime_now_ms: u64,
-    entity_id: String,
-    name: String,
-    kind: String,
-    birth_ms: u64,
-    frame_ids: Vec<FrameId>,
-}
-
-#[derive(Clone)]
-struct WaitEdgeRuntime {
-    process_id: String,
-    src_key: String,
-    dst_key: String,
-    dst_entity_id: String,
-    edge_frame_ids: Vec<FrameId>,
-}
-
-type WaitGraph = (
-    HashMap<String, WaitNode>,
-    Vec<WaitEdgeRuntime>,
-    HashMap<String, Vec<String>>,
-    HashMap<String, usize>,
-);
-
 #[derive(Clone)]
 struct MoireMcpHandler {
     state: AppState,
@@ -552,7 +647,8 @@ impl MoireMcpHandler {
             }
             "moire_deadlock_candidates" => {
                 let snapshot_id = optional_i64(args, "snapshot_id")?;
-                self.tool_deadlock_candidates(snapshot_id).await
+                let budget_ms = optional_u32(args, "budget_ms")?;
+                self.tool_deadlock_candidates(snapshot_id, budget_ms).await
             }
             "moire_entity" => {
                 let snapshot_id = optional_i64(args, "snapshot_id")?;
@@ -569,6 +665,11 @@ impl MoireMcpHandler {
                 let entity_id = optional_non_empty_string(args, "entity_id")?;
                 self.tool_task_state(snapshot_id, entity_id).await
             }
+            "moire_actor_state" => {
+                let snapshot_id = optional_i64(args, "snapshot_id")?;
+                let actor = optional_non_empty_string(args, "actor")?;
+                self.tool_actor_state(snapshot_id, actor).await
+            }
             "moire_source_context" => {
                 let snapshot_id = optional_i64(args, "snapshot_id")?;
                 let frame_ids = required_u64_list(args, "frame_ids")?;
@@ -589,6 +690,18 @@ impl MoireMcpHandler {
                 self.tool_diff_snapshots(from_snapshot_id, to_snapshot_id)
                     .await
             }
+            "moire_restart_impact" => {
+                let snapshot_id = optional_i64(args, "snapshot_id")?;
+                let process_id = required_non_empty_string(args, "process_id")?;
+                self.tool_restart_impact(snapshot_id, process_id).await
+            }
+            "moire_explain_blockage" => {
+                let snapshot_id = optional_i64(args, "snapshot_id")?;
+                let process_id = required_non_empty_string(args, "process_id")?;
+                let entity_id = required_non_empty_string(args, "entity_id")?;
+                self.tool_explain_blockage(snapshot_id, process_id, entity_id)
+                    .await
+            }
             other => Err(format!("unknown tool: {other}")),
         }
     }
@@ -603,7 +716,7 @@ Use the returned snapshot_id for all follow-up calls to stay on one coherent cut
                 String::from("1) moire_help"),
                 String::from("2) moire_cut_fresh"),
                 String::from("3) moire_wait_chains { snapshot_id }"),
-                String::from("4) moire_deadlock_candidates { snapshot_id }"),
+                String::from("4) moire_deadlock_candidates { snapshot_id, budget_ms? }"),
                 String::from(
                     "5) moire_entity / moire_channel_state / moire_task_state on interesting nodes",
                 ),
@@ -632,7 +745,9 @@ Use the returned snapshot_id for all follow-up calls to stay on one coherent cut
                 },
                 McpHelpToolGuide {
                     tool: String::from("moire_deadlock_candidates"),
-                    purpose: String::from("SCC-based deadlock candidates with confidence/reasons."),
+                    purpose: String::from(
+                        "SCC-based deadlock and mutual-wake livelock candidates with confidence/reasons, most severe first; time-bounded by budget_ms.",
+                    ),
                     when_to_use: String::from("Need probable root-cause candidates quickly."),
                     typical_args: String::from("{ snapshot_id }"),
                 },
@@ -658,6 +773,16 @@ Use the returned snapshot_id for all follow-up calls to stay on one coherent cut
                     when_to_use: String::from("Suspected task/future parking issue."),
                     typical_args: String::from("{ snapshot_id, entity_id? }"),
                 },
+                McpHelpToolGuide {
+                    tool: String::from("moire_actor_state"),
+                    purpose: String::from(
+                        "Inspect actors as one unit: mailbox depth, oldest message age, processing state.",
+                    ),
+                    when_to_use: String::from(
+                        "Suspected actor mailbox backlog or actor-to-actor cycle.",
+                    ),
+                    typical_args: String::from("{ snapshot_id, actor? }"),
+                },
                 McpHelpToolGuide {
                     tool: String::from("moire_source_context"),
                     purpose: String::from("Direct frame source lookup in text/plain."),
@@ -678,6 +803,22 @@ Use the returned snapshot_id for all follow-up calls to stay on one coherent cut
                     when_to_use: String::from("Need to prove stasis or identify transitions."),
                     typical_args: String::from("{ from_snapshot_id, to_snapshot_id }"),
                 },
+                McpHelpToolGuide {
+                    tool: String::from("moire_restart_impact"),
+                    purpose: String::from(
+                        "Incoming RPCs, waited-on entities, and deadlock candidates tied to one process.",
+                    ),
+                    when_to_use: String::from("Deciding whether bouncing a process is safe."),
+                    typical_args: String::from("{ snapshot_id, process_id }"),
+                },
+                McpHelpToolGuide {
+                    tool: String::from("moire_explain_blockage"),
+                    purpose: String::from(
+                        "Step-by-step paths from one entity through waits and lock holders to what ultimately blocks it.",
+                    ),
+                    when_to_use: String::from("A task is stuck but not in a deadlock cycle."),
+                    typical_args: String::from("{ snapshot_id, process_id, entity_id }"),
+                },
             ],
             entity_kinds: vec![
                 McpHelpEntityKind {
@@ -718,6 +859,15 @@ Use the returned snapshot_id for all follow-up calls to stay on one coherent cut
                         "Cycles through holders/waiters or no external wake source.",
                     ),
                 },
+                McpHelpEntityKind {
+                    kind: String::from("actor"),
+                    means: String::from(
+                        "Composite wait-graph node: an actor's mailbox plus its driver task.",
+                    ),
+                    hang_signal: String::from(
+                        "Mailbox depth growing while the driver is blocked on another actor.",
+                    ),
+                },
                 McpHelpEntityKind {
                     kind: String::from("net_* / request / response"),
                     means: String::from("I/O and RPC boundary operations."),
@@ -842,7 +992,7 @@ Use the returned snapshot_id for all follow-up calls to stay on one coherent cut
         let snapshot = self
             .ensure_symbolication_ready(self.load_snapshot(snapshot_id).await?)
             .await?;
-        let (nodes, edges, _, _) = self.build_wait_graph(&snapshot)?;
+        let WaitGraph { nodes, edges, .. } = WaitGraph::build(&snapshot)?;
         let sources = self
             .load_source_for_graph(&snapshot, nodes.values(), &edges)
             .await?;
@@ -895,7 +1045,13 @@ Use the returned snapshot_id for all follow-up calls to stay on one coherent cut
         let snapshot = self
             .ensure_symbolication_ready(self.load_snapshot(snapshot_id).await?)
             .await?;
-        let (nodes, edges, adjacency, indegree) = self.build_wait_graph(&snapshot)?;
+        let WaitGraph {
+            nodes,
+            edges,
+            adjacency,
+            indegree,
+            ..
+        } = WaitGraph::build(&snapshot)?;
         let sources = self
             .load_source_for_graph(&snapshot, nodes.values(), &edges)
             .await?;
@@ -965,56 +1121,32 @@ Use the returned snapshot_id for all follow-up calls to stay on one coherent cut
         Ok(render_wait_chains_markdown(&response))
     }
 
-    async fn tool_deadlock_candidates(&self, snapshot_id: Option<i64>) -> Result<String, String> {
+    async fn tool_deadlock_candidates(
+        &self,
+        snapshot_id: Option<i64>,
+        budget_ms: Option<u32>,
+    ) -> Result<String, String> {
         let snapshot = self
             .ensure_symbolication_ready(self.load_snapshot(snapshot_id).await?)
             .await?;
-        let (nodes, _edges, adjacency, _indegree) = self.build_wait_graph(&snapshot)?;
+        let graph = WaitGraph::build(&snapshot)?;
+        let budget = budget_ms
+            .map(|ms| Duration::from_millis(u64::from(ms)))
+            .unwrap_or(DEFAULT_DEADLOCK_SCAN_BUDGET);
+        let scan = find_deadlock_candidates(&graph, Some(Instant::now() + budget));
+        let livelocks = find_livelock_candidates(&graph, &self.state.detection_config().thresholds);
         let sources = self
-            .load_source_for_nodes(&snapshot, nodes.values())
+            .load_source_for_nodes(&snapshot, graph.nodes.values())
             .await?;
 
-        let mut scc_input = adjacency.keys().cloned().collect::<Vec<_>>();
-        scc_input.sort();
-        let sccs = strongly_connected_components(scc_input, &adjacency);
-        let mut candidates = Vec::new();
-        for (idx, scc) in sccs.into_iter().enumerate() {
-            if scc.len() <= 1 {
-                let Some(node_id) = scc.first() else {
-                    continue;
-                };
-                let self_loop = adjacency
-                    .get(node_id)
-                    .is_some_and(|outs| outs.iter().any(|dst| dst == node_id));
-                if !self_loop {
-                    continue;
-                }
-            }
-
-            let mut reasons = vec![String::from("strongly_connected_wait_cycle")];
-            let has_external_wake_source = scc
-                .iter()
-                .filter_map(|id| nodes.get(id))
-                .any(|node| node_has_external_wake_source(node.kind.as_str()));
-            if !has_external_wake_source {
-                reasons.push(String::from("no_obvious_external_wake_source"));
-            }
-            let confidence = if !has_external_wake_source {
-                String::from("high")
-            } else {
-                String::from("medium")
-            };
-
-            let mut entity_ids = Vec::with_capacity(scc.len());
-            let mut cycle_nodes = Vec::with_capacity(scc.len());
-            let mut min_age_hint: Option<u64> = None;
-            for key in &scc {
-                let Some(node) = nodes.get(key) else {
+        let summarize = |node_keys: &[String]| {
+            let mut entity_ids = Vec::with_capacity(node_keys.len());
+            let mut cycle_nodes = Vec::with_capacity(node_keys.len());
+            for key in node_keys {
+                let Some(node) = graph.nodes.get(key) else {
                     continue;
                 };
                 entity_ids.push(node.entity_id.clone());
-                let age_hint = node.ptime_now_ms.saturating_sub(node.birth_ms);
-                min_age_hint = Some(min_age_hint.map_or(age_hint, |curr| curr.min(age_hint)));
                 cycle_nodes.push(McpNodeSummary {
                     process_id: node.process_id.clone(),
                     entity_id: node.entity_id.clone(),
@@ -1024,19 +1156,51 @@ Use the returned snapshot_id for all follow-up calls to stay on one coherent cut
                     sources: sources_for_node(node, &sources),
                 });
             }
+            (entity_ids, cycle_nodes)
+        };
 
+        let mut candidates = Vec::with_capacity(scan.candidates.len() + livelocks.len());
+        for candidate in scan.candidates {
+            let (entity_ids, cycle_nodes) = summarize(&candidate.node_keys);
+            candidates.push(McpDeadlockCandidate {
+                candidate_id: String::new(),
+                kind: String::from("deadlock"),
+                confidence: String::from(candidate.confidence),
+                severity: candidate.severity(),
+                score: candidate.score,
+                reasons: candidate.reasons.into_iter().map(String::from).collect(),
+                entity_ids,
+                blocked_duration_hint_ms: candidate.blocked_duration_hint_ms,
+                wakes_per_sec: None,
+                hints: candidate.hints,
+                cycle_nodes,
+            });
+        }
+        for candidate in livelocks {
+            let (entity_ids, cycle_nodes) = summarize(&candidate.node_keys);
             candidates.push(McpDeadlockCandidate {
-                candidate_id: format!("candidate-{}", idx + 1),
-                confidence,
-                reasons,
+                candidate_id: String::new(),
+                kind: String::from("livelock"),
+                confidence: String::from("medium"),
+                severity: candidate.severity(),
+                score: candidate.score,
+                reasons: candidate.reasons.into_iter().map(String::from).collect(),
                 entity_ids,
-                blocked_duration_hint_ms: min_age_hint,
+                blocked_duration_hint_ms: None,
+                wakes_per_sec: Some(candidate.wakes_per_sec),
+                hints: Vec::new(),
                 cycle_nodes,
             });
         }
+        // Stable, so deadlocks stay ahead of livelocks of the same severity.
+        candidates.sort_by_key(|candidate| Reverse(candidate.severity));
+        for (idx, candidate) in candidates.iter_mut().enumerate() {
+            candidate.candidate_id = format!("candidate-{}", idx + 1);
+        }
 
         let response = McpDeadlockCandidatesResponse {
             snapshot_id: snapshot.snapshot_id,
+            complete: scan.complete,
             candidate_count: candidates.len(),
             candidates,
         };
@@ -1182,7 +1346,7 @@ Use the returned snapshot_id for all follow-up calls to stay on one coherent cut
             pid: located.0.pid,
             entity_id: located.1.id.as_str().to_owned(),
             entity_name: located.1.name.clone(),
-            entity_kind: entity_kind_name(&located.1.body).to_owned(),
+            entity_kind: entity_kind_name(&located.1.body),
             entity_body_json: facet_json::to_string(&located.1.body)
                 .map_err(|error| format!("encode entity body json: {error}"))?,
             incoming_wait_edges: incoming,
@@ -1202,7 +1366,7 @@ Use the returned snapshot_id for all follow-up calls to stay on one coherent cut
         let snapshot = self
             .ensure_symbolication_ready(self.load_snapshot(snapshot_id).await?)
             .await?;
-        let (nodes, edges, _adjacency, _indegree) = self.build_wait_graph(&snapshot)?;
+        let WaitGraph { nodes, edges, .. } = WaitGraph::build(&snapshot)?;
         let sources = self
             .load_source_for_nodes(&snapshot, nodes.values())
             .await?;
@@ -1269,7 +1433,7 @@ Use the returned snapshot_id for all follow-up calls to stay on one coherent cut
         let snapshot = self
             .ensure_symbolication_ready(self.load_snapshot(snapshot_id).await?)
             .await?;
-        let (nodes, _edges, _adjacency, _indegree) = self.build_wait_graph(&snapshot)?;
+        let WaitGraph { nodes, .. } = WaitGraph::build(&snapshot)?;
         let backtrace_index = backtrace_index(&snapshot);
         let frame_catalog = frame_catalog(&snapshot);
         let sources = self
@@ -1351,6 +1515,76 @@ Use the returned snapshot_id for all follow-up calls to stay on one coherent cut
         Ok(render_task_state_markdown(&response))
     }
 
+    async fn tool_actor_state(
+        &self,
+        snapshot_id: Option<i64>,
+        actor: Option<String>,
+    ) -> Result<String, String> {
+        let snapshot = self
+            .ensure_symbolication_ready(self.load_snapshot(snapshot_id).await?)
+            .await?;
+        let WaitGraph { nodes, .. } = WaitGraph::build(&snapshot)?;
+        let sources = self
+            .load_source_for_nodes(&snapshot, nodes.values())
+            .await?;
+
+        let mut actors = Vec::new();
+        for process in &snapshot.processes {
+            for members in actor_members(process) {
+                if let Some(ref wanted) = actor
+                    && members.scope.id.as_str() != wanted
+                    && members.scope.name != *wanted
+                    && actor_display_name(members.scope) != wanted
+                {
+                    continue;
+                }
+
+                let node = members.driver.and_then(|driver| {
+                    nodes.get(&compose_node_key(&process.process_id, &driver.id))
+                });
+                let (processing_state, blocked_on_entity_ids) =
+                    actor_processing_state(process, &members);
+                actors.push(McpActorSnapshot {
+                    process_id: process.process_id.as_str().to_owned(),
+                    scope_id: members.scope.id.as_str().to_owned(),
+                    name: actor_display_name(members.scope).to_owned(),
+                    driver_entity_id: members.driver.map(|e| e.id.as_str().to_owned()),
+                    mailbox_tx_entity_id: members.mailbox_tx.map(|e| e.id.as_str().to_owned()),
+                    mailbox_rx_entity_id: members.mailbox_rx.map(|e| e.id.as_str().to_owned()),
+                    mailbox_depth: actor_mailbox_depth(&members),
+                    mailbox_capacity: members.mailbox_capacity,
+                    oldest_message_age_ms: actor_oldest_message_age_ms(process, &members),
+                    processing_state: processing_state.to_owned(),
+                    blocked_on_entity_ids,
+                    source: node.and_then(|n| source_for_node(n, &sources)),
+                    sources: node
+                        .map(|n| sources_for_node(n, &sources))
+                        .unwrap_or_default(),
+                });
+            }
+        }
+
+        if let Some(wanted) = actor
+            && actors.is_empty()
+        {
+            return Err(format!(
+                "unknown actor `{wanted}` (expected actor scope id or name)"
+            ));
+        }
+        actors.sort_by(|a, b| {
+            a.process_id
+                .cmp(&b.process_id)
+                .then_with(|| a.name.cmp(&b.name))
+                .then_with(|| a.scope_id.cmp(&b.scope_id))
+        });
+
+        let response = McpActorStateResponse {
+            snapshot_id: snapshot.snapshot_id,
+            actors,
+        };
+        Ok(render_actor_state_markdown(&response))
+    }
+
     async fn tool_source_context(
         &self,
         snapshot_id: Option<i64>,
@@ -1524,6 +1758,21 @@ Call moire_backtrace first to list frame_ids for a backtrace."
             .cloned()
             .collect::<Vec<_>>();
 
+        let graph_diff = WaitGraph::build(&from)?.diff(&WaitGraph::build(&to)?);
+        let waiting_on_escalated = graph_diff
+            .edges
+            .into_iter()
+            .filter(|edge| edge.change == EdgeChange::Escalated)
+            .map(|edge| McpWaitEscalation {
+                src: edge.src_key,
+                dst: edge.dst_key,
+                severity_before: edge.severity_before,
+                severity_after: edge.severity_after,
+                wait_ms_before: edge.wait_ms_before,
+                wait_ms_after: edge.wait_ms_after,
+            })
+            .collect::<Vec<_>>();
+
         let from_channel = snapshot_channel_fingerprint(&from);
         let to_channel = snapshot_channel_fingerprint(&to);
         let mut channel_changes = Vec::new();
@@ -1560,12 +1809,88 @@ Call moire_backtrace first to list frame_ids for a backtrace."
             entity_removed,
             waiting_on_added,
             waiting_on_removed,
+            waiting_on_escalated,
             channel_changes,
             task_changes,
         };
         Ok(render_diff_snapshots_markdown(&response))
     }
 
+    async fn tool_restart_impact(
+        &self,
+        snapshot_id: Option<i64>,
+        process_id: String,
+    ) -> Result<String, String> {
+        let snapshot = self.load_snapshot(snapshot_id).await?;
+        let Some(process) = snapshot
+            .processes
+            .iter()
+            .find(|process| process.process_id.as_str() == process_id)
+        else {
+            return Err(format!(
+                "process {process_id} not found in snapshot {}",
+                snapshot.snapshot_id
+            ));
+        };
+        let process_name = process.process_name.clone();
+        let graph = WaitGraph::build(&snapshot)?;
+        let report = graph.restart_impact(&process_id);
+
+        let entity_id_of = |key: &str| {
+            key.split_once("::")
+                .map_or(key, |(_, entity_id)| entity_id)
+                .to_owned()
+        };
+        let response = McpRestartImpactResponse {
+            snapshot_id: snapshot.snapshot_id,
+            process_id,
+            process_name,
+            affected_processes: report.affected_processes,
+            incoming_rpcs: report
+                .incoming_rpcs
+                .into_iter()
+                .map(|rpc| McpInflightRpc {
+                    method: rpc.method,
+                    response_entity_id: entity_id_of(&rpc.response_key),
+                    client_process_id: rpc.client_process_id,
+                    request_entity_id: entity_id_of(&rpc.request_key),
+                    client_waiters: rpc.client_waiters,
+                })
+                .collect(),
+            depended_on: report
+                .depended_on
+                .into_iter()
+                .map(|node| McpDependedOn {
+                    entity_id: entity_id_of(&node.key),
+                    name: node.name,
+                    kind: node.kind,
+                    waiters: node.waiters,
+                })
+                .collect(),
+            deadlock_candidates: report.deadlock_candidates,
+        };
+        Ok(render_restart_impact_markdown(&response))
+    }
+
+    async fn tool_explain_blockage(
+        &self,
+        snapshot_id: Option<i64>,
+        process_id: String,
+        entity_id: String,
+    ) -> Result<String, String> {
+        let snapshot = self.load_snapshot(snapshot_id).await?;
+        let graph = WaitGraph::build(&snapshot)?;
+        let key = format!("{process_id}::{entity_id}");
+        if !graph.nodes.contains_key(&key) {
+            return Err(format!(
+                "entity {entity_id} of process {process_id} is not in the wait graph of snapshot {}",
+                snapshot.snapshot_id
+            ));
+        }
+        let paths = graph.explain_blockage(&key);
+        Ok(render_blockage_markdown(snapshot.snapshot_id, &key, &paths))
+    }
+
     async fn trigger_cut(&self) -> Result<TriggerCutResponse, String> {
         let (cut_id, cut_id_string, now_ns, requested_connections, outbound) = {
             let mut guard = self.state.inner.lock().await;
@@ -1703,86 +2028,6 @@ Call moire_backtrace first to list frame_ids for a backtrace."
         Ok(snapshot)
     }
 
-    fn build_wait_graph(&self, snapshot: &SnapshotCutResponse) -> Result<WaitGraph, String> {
-        let backtrace_index = backtrace_index(snapshot);
-        let frame_catalog = frame_catalog(snapshot);
-
-        let mut nodes: HashMap<String, WaitNode> = HashMap::new();
-        let mut edges: Vec<WaitEdgeRuntime> = Vec::new();
-        let mut adjacency: HashMap<String, Vec<String>> = HashMap::new();
-        let mut indegree: HashMap<String, usize> = HashMap::new();
-        let mut seen_edges: HashSet<(String, String)> = HashSet::new();
-
-        for process in &snapshot.processes {
-            let local_entities: HashMap<String, &Entity> = process
-                .snapshot
-                .entities
-                .iter()
-                .map(|entity| (entity.id.as_str().to_owned(), entity))
-                .collect();
-
-            for edge in &process.snapshot.edges {
-                if edge.kind != EdgeKind::WaitingOn {
-                    continue;
-                }
-
-                let Some(src) = local_entities.get(edge.src.as_str()) else {
-                    return Err(format!(
-                        "invariant violated: missing src entity {} for waiting_on edge in process {}",
-                        edge.src.as_str(),
-                        process.process_id.as_str()
-                    ));
-                };
-                let Some(dst) = local_entities.get(edge.dst.as_str()) else {
-                    return Err(format!(
-                        "invariant violated: missing dst entity {} for waiting_on edge in process {}",
-                        edge.dst.as_str(),
-                        process.process_id.as_str()
-                    ));
-                };
-
-                let src_key = compose_node_key(&process.process_id, &src.id);
-                let dst_key = compose_node_key(&process.process_id, &dst.id);
-
-                nodes
-                    .entry(src_key.clone())
-                    .or_insert_with(|| wait_node(process, src, &backtrace_index, &frame_catalog));
-                nodes
-                    .entry(dst_key.clone())
-                    .or_insert_with(|| wait_node(process, dst, &backtrace_index, &frame_catalog));
-
-                if seen_edges.insert((src_key.clone(), dst_key.clone())) {
-                    edges.push(WaitEdgeRuntime {
-                        process_id: process.process_id.as_str().to_owned(),
-                        src_key: src_key.clone(),
-                        dst_key: dst_key.clone(),
-                        dst_entity_id: dst.id.as_str().to_owned(),
-                        edge_frame_ids: selected_frames_for_backtrace_id(
-                            edge.backtrace.as_u64(),
-                            &backtrace_index,
-                            &frame_catalog,
-                            frame_start_index_for_entity(src),
-                            SOURCE_FRAMES_PER_ITEM,
-                        ),
-                    });
-                    adjacency
-                        .entry(src_key.clone())
-                        .or_default()
-                        .push(dst_key.clone());
-                    *indegree.entry(dst_key).or_insert(0) += 1;
-                    indegree.entry(src_key).or_insert(0);
-                }
-            }
-        }
-
-        for outs in adjacency.values_mut() {
-            outs.sort();
-            outs.dedup();
-        }
-
-        Ok((nodes, edges, adjacency, indegree))
-    }
-
     async fn load_source_for_nodes<'a>(
         &self,
         snapshot: &SnapshotCutResponse,
@@ -1883,7 +2128,7 @@ Call moire_backtrace first to list frame_ids for a backtrace."
                 let (compact_scope_text, compact_scope_range) = location
                     .language
                     .and_then(|lang| {
-                        cut_source_compact(
+                        cut_source_compact_or_window(
                             &location.content,
                             lang,
                             location.target_line,
@@ -2311,18 +2556,37 @@ fn render_deadlock_candidates_markdown(response: &McpDeadlockCandidatesResponse)
     let mut out = String::new();
     let _ = writeln!(out, "snapshot_id: {}", response.snapshot_id);
     let _ = writeln!(out, "candidates: {}", response.candidate_count);
+    if !response.complete {
+        let _ = writeln!(
+            out,
+            "scan: incomplete (budget exhausted; most severe candidates listed first)"
+        );
+    }
 
     for candidate in &response.candidates {
         let _ = writeln!(
             out,
-            "\n{}: confidence={}",
-            candidate.candidate_id, candidate.confidence
+            "\n{}: kind={} confidence={} severity={}",
+            candidate.candidate_id, candidate.kind, candidate.confidence, candidate.severity
         );
         let _ = writeln!(out, "reasons: {}", candidate.reasons.join(", "));
+        let score = candidate
+            .score
+            .contributions
+            .iter()
+            .map(|c| format!("{}=+{}", c.rule, c.points))
+            .collect::<Vec<_>>();
+        let _ = writeln!(out, "score: {}", score.join(", "));
         let _ = writeln!(out, "entity_ids: {}", candidate.entity_ids.join(", "));
         if let Some(duration) = candidate.blocked_duration_hint_ms {
             let _ = writeln!(out, "blocked_duration_hint_ms: {duration}");
         }
+        if let Some(rate) = candidate.wakes_per_sec {
+            let _ = writeln!(out, "unproductive_wakes_per_sec: {rate}");
+        }
+        for hint in &candidate.hints {
+            let _ = writeln!(out, "hint: {hint}");
+        }
         for node in &candidate.cycle_nodes {
             let _ = writeln!(out, "- {} [{}] id={}", node.name, node.kind, node.entity_id);
             append_source_set(
@@ -2464,6 +2728,57 @@ fn render_task_state_markdown(response: &McpTaskStateResponse) -> String {
     out.trim_end().to_string()
 }
 
+fn render_actor_state_markdown(response: &McpActorStateResponse) -> String {
+    let mut out = String::new();
+    let _ = writeln!(out, "snapshot_id: {}", response.snapshot_id);
+    let _ = writeln!(out, "actors: {}", response.actors.len());
+
+    for actor in &response.actors {
+        let _ = writeln!(
+            out,
+            "\n- {} scope={} process={}",
+            actor.name, actor.scope_id, actor.process_id
+        );
+        let _ = writeln!(out, "  processing_state: {}", actor.processing_state);
+        match actor.mailbox_capacity {
+            Some(capacity) => {
+                let _ = writeln!(out, "  mailbox: {}/{capacity}", actor.mailbox_depth);
+            }
+            None => {
+                let _ = writeln!(out, "  mailbox: {}", actor.mailbox_depth);
+            }
+        }
+        if let Some(age) = actor.oldest_message_age_ms {
+            let _ = writeln!(out, "  oldest_message_age_ms: {age}");
+        }
+        if !actor.blocked_on_entity_ids.is_empty() {
+            let _ = writeln!(
+                out,
+                "  blocked_on: {}",
+                actor.blocked_on_entity_ids.join(", ")
+            );
+        }
+        let members = [
+            ("driver", actor.driver_entity_id.as_ref()),
+            ("mailbox_tx", actor.mailbox_tx_entity_id.as_ref()),
+            ("mailbox_rx", actor.mailbox_rx_entity_id.as_ref()),
+        ]
+        .into_iter()
+        .filter_map(|(label, id)| id.map(|id| format!("{label}={id}")))
+        .collect::<Vec<_>>();
+        let _ = writeln!(out, "  members: {}", members.join(" "));
+        append_source_set(
+            &mut out,
+            "  driver sources",
+            actor.source.as_ref(),
+            &actor.sources,
+            "  ",
+        );
+    }
+
+    out.trim_end().to_string()
+}
+
 fn render_source_context_markdown(response: &McpSourceContextResponse) -> String {
     let mut out = String::new();
     let _ = writeln!(out, "snapshot_id: {}", response.snapshot_id);
@@ -2541,6 +2856,24 @@ fn render_diff_snapshots_markdown(response: &McpDiffSnapshotsResponse) -> String
         response.waiting_on_removed.join(", ")
     );
 
+    let _ = writeln!(out, "\nwaiting_on_escalated:");
+    for edge in &response.waiting_on_escalated {
+        let _ = write!(out, "- {} -> {}:", edge.src, edge.dst);
+        if let (Some(before), Some(after)) = (edge.severity_before, edge.severity_after) {
+            let _ = write!(out, " severity {before} -> {after}");
+        }
+        match (edge.wait_ms_before, edge.wait_ms_after) {
+            (Some(before), Some(after)) => {
+                let _ = write!(out, " waiting {before}ms -> {after}ms");
+            }
+            (None, Some(after)) => {
+                let _ = write!(out, " waiting={after}ms");
+            }
+            _ => {}
+        }
+        let _ = writeln!(out);
+    }
+
     let _ = writeln!(out, "\nchannel_changes:");
     for change in &response.channel_changes {
         let _ = writeln!(
@@ -2560,6 +2893,66 @@ fn render_diff_snapshots_markdown(response: &McpDiffSnapshotsResponse) -> String
     out.trim_end().to_string()
 }
 
+fn render_restart_impact_markdown(response: &McpRestartImpactResponse) -> String {
+    let mut out = String::new();
+    let _ = writeln!(
+        out,
+        "snapshot_id: {}\nprocess: {} ({})",
+        response.snapshot_id, response.process_name, response.process_id
+    );
+    let _ = writeln!(
+        out,
+        "affected_processes: {}",
+        response.affected_processes.join(", ")
+    );
+
+    let _ = writeln!(out, "\nincoming_rpcs:");
+    for rpc in &response.incoming_rpcs {
+        let _ = writeln!(
+            out,
+            "- {} response={} client={} request={}",
+            rpc.method, rpc.response_entity_id, rpc.client_process_id, rpc.request_entity_id
+        );
+        if !rpc.client_waiters.is_empty() {
+            let _ = writeln!(out, "  client_waiters: {}", rpc.client_waiters.join(", "));
+        }
+    }
+
+    let _ = writeln!(out, "\ndepended_on:");
+    for node in &response.depended_on {
+        let _ = writeln!(
+            out,
+            "- {} [{}] id={} waiters: {}",
+            node.name,
+            node.kind,
+            node.entity_id,
+            node.waiters.join(", ")
+        );
+    }
+
+    let _ = writeln!(out, "\ndeadlock_candidates:");
+    for candidate in &response.deadlock_candidates {
+        let _ = writeln!(out, "- {}", candidate.join(", "));
+    }
+
+    out.trim_end().to_string()
+}
+
+fn render_blockage_markdown(snapshot_id: i64, key: &str, paths: &[BlockagePath]) -> String {
+    let mut out = String::new();
+    let _ = writeln!(out, "snapshot_id: {snapshot_id}\nentity: {key}");
+    if paths.is_empty() {
+        let _ = writeln!(out, "\nnot waiting on anything");
+    }
+    for (index, path) in paths.iter().enumerate() {
+        let _ = writeln!(out, "\npath {} (root {}):", index + 1, path.root_key);
+        for line in path.describe().lines() {
+            let _ = writeln!(out, "- {line}");
+        }
+    }
+    out.trim_end().to_string()
+}
+
 fn append_source_set(
     out: &mut String,
     label: &str,
@@ -2696,178 +3089,6 @@ fn sources_for_frame_ids(
         .collect()
 }
 
-fn wait_node(
-    process: &ProcessSnapshotView,
-    entity: &Entity,
-    backtrace_index: &HashMap<u64, &SnapshotBacktrace>,
-    frame_catalog: &HashMap<u64, &SnapshotBacktraceFrame>,
-) -> WaitNode {
-    let frame_ids = selected_frames_for_entity(
-        entity,
-        backtrace_index,
-        frame_catalog,
-        SOURCE_FRAMES_PER_ITEM,
-    );
-    WaitNode {
-        process_id: process.process_id.as_str().to_owned(),
-        ptime_now_ms: process.ptime_now_ms,
-        entity_id: entity.id.as_str().to_owned(),
-        name: entity.name.clone(),
-        kind: entity_kind_name(&entity.body).to_owned(),
-        birth_ms: entity.birth.as_millis(),
-        frame_ids,
-    }
-}
-
-fn compose_node_key(process_id: &ProcessId, entity_id: &EntityId) -> String {
-    format!("{}::{}", process_id.as_str(), entity_id.as_str())
-}
-
-fn backtrace_index(snapshot: &SnapshotCutResponse) -> HashMap<u64, &SnapshotBacktrace> {
-    snapshot
-        .backtraces
-        .iter()
-        .map(|bt| (bt.backtrace_id.as_u64(), bt))
-        .collect()
-}
-
-fn frame_catalog(snapshot: &SnapshotCutResponse) -> HashMap<u64, &SnapshotBacktraceFrame> {
-    snapshot
-        .frames
-        .iter()
-        .map(|record| (record.frame_id.as_u64(), &record.frame))
-        .collect()
-}
-
-fn frame_start_index_for_entity(entity: &Entity) -> usize {
-    match &entity.body {
-        EntityBody::Future(fut) => fut.skip_entry_frames.unwrap_or(0) as usize,
-        _ => 0,
-    }
-}
-
-fn selected_frames_for_entity(
-    entity: &Entity,
-    backtrace_index: &HashMap<u64, &SnapshotBacktrace>,
-    frame_catalog: &HashMap<u64, &SnapshotBacktraceFrame>,
-    frame_count: usize,
-) -> Vec<FrameId> {
-    let Some(backtrace) = backtrace_index.get(&entity.backtrace.as_u64()) else {
-        return Vec::new();
-    };
-    select_frames_for_backtrace(
-        backtrace,
-        frame_catalog,
-        frame_start_index_for_entity(entity),
-        frame_count,
-    )
-}
-
-fn selected_frames_for_backtrace_id(
-    backtrace_id: u64,
-    backtrace_index: &HashMap<u64, &SnapshotBacktrace>,
-    frame_catalog: &HashMap<u64, &SnapshotBacktraceFrame>,
-    app_skip_count: usize,
-    frame_count: usize,
-) -> Vec<FrameId> {
-    let Some(backtrace) = backtrace_index.get(&backtrace_id) else {
-        return Vec::new();
-    };
-    select_frames_for_backtrace(backtrace, frame_catalog, app_skip_count, frame_count)
-}
-
-fn select_frames_for_backtrace(
-    backtrace: &SnapshotBacktrace,
-    frame_catalog: &HashMap<u64, &SnapshotBacktraceFrame>,
-    app_skip_count: usize,
-    frame_count: usize,
-) -> Vec<FrameId> {
-    if backtrace.frame_ids.is_empty() || frame_count == 0 {
-        return Vec::new();
-    }
-
-    let mut application_resolved = Vec::<FrameId>::new();
-    let mut resolved_all = Vec::<FrameId>::new();
-    for frame_id in &backtrace.frame_ids {
-        let Some(frame) = frame_catalog.get(&frame_id.as_u64()) else {
-            continue;
-        };
-        let SnapshotBacktraceFrame::Resolved(resolved) = frame else {
-            continue;
-        };
-        resolved_all.push(*frame_id);
-        let is_system =
-            crate_from_function_name(resolved.function_name.as_str()).is_some_and(is_system_crate);
-        if !is_system {
-            application_resolved.push(*frame_id);
-        }
-    }
-
-    let mut out = Vec::<FrameId>::new();
-    for frame_id in application_resolved.into_iter().skip(app_skip_count) {
-        if !out.contains(&frame_id) {
-            out.push(frame_id);
-        }
-        if out.len() >= frame_count {
-            return out;
-        }
-    }
-    for frame_id in resolved_all {
-        if !out.contains(&frame_id) {
-            out.push(frame_id);
-        }
-        if out.len() >= frame_count {
-            return out;
-        }
-    }
-    for frame_id in &backtrace.frame_ids {
-        if !out.contains(frame_id) {
-            out.push(*frame_id);
-        }
-        if out.len() >= frame_count {
-            return out;
-        }
-    }
-    out
-}
-
-fn crate_from_function_name(function_name: &str) -> Option<&str> {
-    let trimmed = function_name.trim();
-    if trimmed.is_empty() {
-        return None;
-    }
-    let bytes = trimmed.as_bytes();
-    let mut index = 0usize;
-    while index < bytes.len() {
-        let ch = bytes[index] as char;
-        if ch == '<' || ch == ' ' || ch == '&' || ch == '*' {
-            index += 1;
-            continue;
-        }
-        break;
-    }
-
-    let rest = &trimmed[index..];
-    let mut chars = rest.char_indices();
-    let (_, first) = chars.next()?;
-    if !(first == '_' || first.is_ascii_alphabetic()) {
-        return None;
-    }
-    let mut end = first.len_utf8();
-    for (idx, ch) in chars {
-        if ch == '_' || ch.is_ascii_alphanumeric() {
-            end = idx + ch.len_utf8();
-            continue;
-        }
-        break;
-    }
-    Some(&rest[..end])
-}
-
-fn is_system_crate(krate: &str) -> bool {
-    SYSTEM_CRATES.contains(&krate)
-}
-
 #[allow(clippy::too_many_arguments)]
 fn make_chain(
     chain_num: usize,
@@ -2919,7 +3140,7 @@ fn make_chain(
 
     let has_external_wake_source = chain_nodes
         .iter()
-        .any(|node| node_has_external_wake_source(node.kind.as_str()));
+        .any(|node| moire_graph_core::has_external_wake_source(node.kind.as_str()));
 
     let summary = if is_cycle {
         format!("cycle of {} nodes", chain_nodes.len())
@@ -2943,22 +3164,6 @@ fn make_chain(
     }
 }
 
-fn node_has_external_wake_source(kind: &str) -> bool {
-    matches!(
-        kind,
-        "mpsc_rx"
-            | "broadcast_rx"
-            | "watch_rx"
-            | "oneshot_rx"
-            | "notify"
-            | "semaphore"
-            | "net_accept"
-            | "net_read"
-            | "request"
-            | "response"
-    )
-}
-
 fn count_waiters(
     edges: &[WaitEdgeRuntime],
     nodes: &HashMap<String, WaitNode>,
@@ -2984,34 +3189,6 @@ fn count_waiters(
     (sender_waiters, receiver_waiters)
 }
 
-fn entity_kind_name(body: &EntityBody) -> &'static str {
-    match body {
-        EntityBody::Future(_) => "future",
-        EntityBody::Lock(_) => "lock",
-        EntityBody::MpscTx(_) => "mpsc_tx",
-        EntityBody::MpscRx(_) => "mpsc_rx",
-        EntityBody::BroadcastTx(_) => "broadcast_tx",
-        EntityBody::BroadcastRx(_) => "broadcast_rx",
-        EntityBody::WatchTx(_) => "watch_tx",
-        EntityBody::WatchRx(_) => "watch_rx",
-        EntityBody::OneshotTx(_) => "oneshot_tx",
-        EntityBody::OneshotRx(_) => "oneshot_rx",
-        EntityBody::Semaphore(_) => "semaphore",
-        EntityBody::Notify(_) => "notify",
-        EntityBody::OnceCell(_) => "once_cell",
-        EntityBody::Command(_) => "command",
-        EntityBody::FileOp(_) => "file_op",
-        EntityBody::NetConnect(_) => "net_connect",
-        EntityBody::NetAccept(_) => "net_accept",
-        EntityBody::NetRead(_) => "net_read",
-        EntityBody::NetWrite(_) => "net_write",
-        EntityBody::Request(_) => "request",
-        EntityBody::Response(_) => "response",
-        EntityBody::Custom(_) => "custom",
-        EntityBody::Aether(_) => "aether",
-    }
-}
-
 fn is_channel_entity(body: &EntityBody) -> bool {
     matches!(
         body,
@@ -3138,76 +3315,6 @@ fn snapshot_task_wait_target(snapshot: &SnapshotCutResponse) -> HashMap<String,
     out
 }
 
-fn strongly_connected_components(
-    keys: Vec<String>,
-    adjacency: &HashMap<String, Vec<String>>,
-) -> Vec<Vec<String>> {
-    struct TarjanState {
-        index: usize,
-        stack: Vec<String>,
-        on_stack: HashSet<String>,
-        index_map: HashMap<String, usize>,
-        lowlink_map: HashMap<String, usize>,
-        components: Vec<Vec<String>>,
-    }
-
-    fn strongconnect(node: String, adjacency: &HashMap<String, Vec<String>>, st: &mut TarjanState) {
-        st.index_map.insert(node.clone(), st.index);
-        st.lowlink_map.insert(node.clone(), st.index);
-        st.index += 1;
-        st.stack.push(node.clone());
-        st.on_stack.insert(node.clone());
-
-        if let Some(neighbors) = adjacency.get(&node) {
-            for next in neighbors {
-                if !st.index_map.contains_key(next) {
-                    strongconnect(next.clone(), adjacency, st);
-                    let next_low = st.lowlink_map.get(next).copied().unwrap_or(usize::MAX);
-                    if let Some(node_low) = st.lowlink_map.get_mut(&node) {
-                        *node_low = (*node_low).min(next_low);
-                    }
-                } else if st.on_stack.contains(next) {
-                    let next_idx = st.index_map.get(next).copied().unwrap_or(usize::MAX);
-                    if let Some(node_low) = st.lowlink_map.get_mut(&node) {
-                        *node_low = (*node_low).min(next_idx);
-                    }
-                }
-            }
-        }
-
-        let node_idx = st.index_map.get(&node).copied().unwrap_or(usize::MAX);
-        let node_low = st.lowlink_map.get(&node).copied().unwrap_or(usize::MAX);
-        if node_low == node_idx {
-            let mut component = Vec::new();
-            while let Some(w) = st.stack.pop() {
-                st.on_stack.remove(&w);
-                component.push(w.clone());
-                if w == node {
-                    break;
-                }
-            }
-            st.components.push(component);
-        }
-    }
-
-    let mut state = TarjanState {
-        index: 0,
-        stack: Vec::new(),
-        on_stack: HashSet::new(),
-        index_map: HashMap::new(),
-        lowlink_map: HashMap::new(),
-        components: Vec::new(),
-    };
-
-    for node in keys {
-        if !state.index_map.contains_key(&node) {
-            strongconnect(node, adjacency, &mut state);
-        }
-    }
-
-    state.components
-}
-
 fn required_non_empty_string(
     args: &JsonMap<String, JsonValue>,
     field: &str,
@@ -3368,55 +3475,6 @@ fn convert_response(response: axum::http::Response<GenericBody>) -> Response {
 mod tests {
     use super::*;
 
-    #[test]
-    fn strongly_connected_components_finds_cycle_cluster() {
-        let mut adjacency: HashMap<String, Vec<String>> = HashMap::new();
-        adjacency.insert(String::from("a"), vec![String::from("b")]);
-        adjacency.insert(String::from("b"), vec![String::from("c")]);
-        adjacency.insert(String::from("c"), vec![String::from("a")]);
-        adjacency.insert(String::from("d"), vec![String::from("e")]);
-        adjacency.insert(String::from("e"), vec![]);
-        let keys = vec![
-            String::from("a"),
-            String::from("b"),
-            String::from("c"),
-            String::from("d"),
-            String::from("e"),
-        ];
-
-        let mut components = strongly_connected_components(keys, &adjacency);
-        components.iter_mut().for_each(|c| c.sort());
-        components.sort_by_key(|c| c.first().cloned().unwrap_or_default());
-
-        assert_eq!(components.len(), 3);
-        assert_eq!(
-            components[0],
-            vec![String::from("a"), String::from("b"), String::from("c")]
-        );
-        assert_eq!(components[1], vec![String::from("d")]);
-        assert_eq!(components[2], vec![String::from("e")]);
-    }
-
-    #[test]
-    fn external_wake_source_kind_classification_is_strict() {
-        assert!(node_has_external_wake_source("mpsc_rx"));
-        assert!(node_has_external_wake_source("net_read"));
-        assert!(!node_has_external_wake_source("future"));
-        assert!(!node_has_external_wake_source("mpsc_tx"));
-    }
-
-    #[test]
-    fn crate_parser_handles_trait_impl_style_names() {
-        assert_eq!(
-            crate_from_function_name("<alloc::vec::Vec<u8> as core::fmt::Debug>::fmt"),
-            Some("alloc")
-        );
-        assert_eq!(
-            crate_from_function_name("tokio::runtime::context::enter"),
-            Some("tokio")
-        );
-    }
-
     #[test]
     fn source_snippet_renders_line_prefixed_text() {
         let rendered = render_source_snippet(&McpSourceContext {
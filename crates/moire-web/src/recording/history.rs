@@ -0,0 +1,550 @@
+//! Continuous snapshot history persisted to SQLite.
+//!
+//! Unlike a recording session, which keeps frames in memory until it is
+//! exported, the history recorder writes every snapshot it takes to the server
+//! database along with its wait graph and analysis findings. The state of a
+//! process can then be inspected after the process is gone, by time range.
+
+// r[impl config.web.history]
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use facet::Facet;
+use moire_types::{AnalysisFinding, EntityBody, HistorySnapshotSummary, SnapshotCutResponse};
+use rusqlite_facet::{ConnectionFacetExt, StatementFacetExt};
+use tracing::{info, warn};
+
+use crate::api::snapshot::take_snapshot_internal;
+use crate::app::AppState;
+use crate::db::Db;
+use crate::graph::WaitGraph;
+use crate::util::time::{now_ms, to_i64_u64};
+
+const DEFAULT_HISTORY_RETENTION_SECS: u64 = 24 * 60 * 60;
+
+/// How often the recorder snapshots and how long it keeps what it wrote.
+#[derive(Clone, Copy, Debug)]
+pub struct HistoryConfig {
+    pub interval: Duration,
+    pub retention: Duration,
+}
+
+impl HistoryConfig {
+    /// Reads `MOIRE_HISTORY_INTERVAL_MS` and `MOIRE_HISTORY_RETENTION_SECS`.
+    /// Returns `None` when the interval is unset or zero.
+    pub fn from_env() -> Result<Option<Self>, String> {
+        let Ok(interval_ms) = std::env::var("MOIRE_HISTORY_INTERVAL_MS") else {
+            return Ok(None);
+        };
+        let interval_ms = interval_ms
+            .parse::<u64>()
+            .map_err(|e| format!("invalid MOIRE_HISTORY_INTERVAL_MS '{interval_ms}': {e}"))?;
+        if interval_ms == 0 {
+            return Ok(None);
+        }
+        let retention_secs = match std::env::var("MOIRE_HISTORY_RETENTION_SECS") {
+            Ok(value) => value
+                .parse::<u64>()
+                .map_err(|e| format!("invalid MOIRE_HISTORY_RETENTION_SECS '{value}': {e}"))?,
+            Err(_) => DEFAULT_HISTORY_RETENTION_SECS,
+        };
+        Ok(Some(Self {
+            interval: Duration::from_millis(interval_ms),
+            retention: Duration::from_secs(retention_secs),
+        }))
+    }
+}
+
+/// Spawns the recorder loop. It runs for the lifetime of the server.
+pub fn spawn_history_recorder(state: AppState, config: HistoryConfig) {
+    info!(
+        interval_ms = config.interval.as_millis() as u64,
+        retention_secs = config.retention.as_secs(),
+        "snapshot history recorder started"
+    );
+    tokio::spawn(async move {
+        loop {
+            tokio::time::sleep(config.interval).await;
+            let snapshot = take_snapshot_internal(&state).await;
+            let db = state.db.clone();
+            let before_unix_ms =
+                snapshot.captured_at_unix_ms - to_i64_u64(config.retention.as_millis() as u64);
+            let result = tokio::task::spawn_blocking(move || {
+                persist_history_snapshot_blocking(&db, &snapshot)?;
+                prune_history_blocking(&db, before_unix_ms)
+            })
+            .await;
+            match result {
+                Ok(Ok(())) => {}
+                Ok(Err(e)) => warn!(%e, "failed to persist history snapshot"),
+                Err(e) => warn!(%e, "history persist join error"),
+            }
+        }
+    });
+}
+
+/// Time range for [`query_history_blocking`]. Bounds are inclusive Unix epoch
+/// milliseconds.
+#[derive(Facet, Clone, Debug)]
+pub struct HistoryRange {
+    pub from_unix_ms: i64,
+    pub to_unix_ms: i64,
+    pub limit: u32,
+    /// Keep only snapshots in which a task with this logical id was alive.
+    pub logical_id: Option<String>,
+}
+
+impl HistoryRange {
+    /// Everything recorded so far, capped at `limit` snapshots.
+    pub fn all(limit: u32) -> Self {
+        Self {
+            from_unix_ms: i64::MIN,
+            to_unix_ms: now_ms(),
+            limit,
+            logical_id: None,
+        }
+    }
+}
+
+/// One wait graph node of a recorded snapshot.
+#[derive(Facet, Clone, Debug)]
+pub struct HistoryNodeRow {
+    pub process_id: String,
+    pub entity_id: String,
+    pub name: String,
+    pub kind: String,
+    pub age_ms: i64,
+}
+
+/// One `waiting_on` edge of a recorded snapshot, keyed like [`WaitGraph`] nodes.
+#[derive(Facet, Clone, Debug)]
+pub struct HistoryEdgeRow {
+    pub process_id: String,
+    pub src_key: String,
+    pub dst_key: String,
+    /// How long the waiter had been waiting, when the edge carried a timestamp.
+    pub waited_ms: Option<i64>,
+}
+
+#[derive(Facet)]
+struct HistorySnapshotInsertParams {
+    snapshot_id: i64,
+    captured_at_unix_ms: i64,
+    process_count: u32,
+    snapshot_json: String,
+}
+
+#[derive(Facet)]
+struct HistoryNodeInsertParams {
+    history_id: i64,
+    process_id: String,
+    entity_id: String,
+    name: String,
+    kind: String,
+    age_ms: i64,
+}
+
+#[derive(Facet)]
+struct HistoryEdgeInsertParams {
+    history_id: i64,
+    process_id: String,
+    src_key: String,
+    dst_key: String,
+    waited_ms: Option<i64>,
+}
+
+#[derive(Facet)]
+struct HistoryTaskInsertParams {
+    history_id: i64,
+    logical_id: String,
+    process_id: String,
+    entity_id: String,
+    name: String,
+    age_ms: i64,
+}
+
+#[derive(Facet)]
+struct HistoryFindingInsertParams {
+    history_id: i64,
+    finding_index: u32,
+    analysis: String,
+    finding_json: String,
+}
+
+#[derive(Facet)]
+struct HistoryIdParams {
+    history_id: i64,
+}
+
+#[derive(Facet)]
+struct HistoryPruneParams {
+    before_unix_ms: i64,
+}
+
+#[derive(Facet)]
+struct HistorySnapshotRow {
+    history_id: i64,
+    snapshot_id: i64,
+    captured_at_unix_ms: i64,
+    process_count: u32,
+    node_count: u32,
+    edge_count: u32,
+}
+
+#[derive(Facet)]
+struct HistoryFindingRow {
+    finding_json: String,
+}
+
+#[derive(Facet)]
+struct HistorySnapshotJsonRow {
+    snapshot_json: String,
+}
+
+/// Writes `snapshot`, its wait graph and its findings. Returns the new history id.
+pub fn persist_history_snapshot_blocking(
+    db: &Db,
+    snapshot: &SnapshotCutResponse,
+) -> Result<i64, String> {
+    let snapshot_json =
+        facet_json::to_string(snapshot).map_err(|error| format!("encode snapshot: {error}"))?;
+    let graph = match WaitGraph::build(snapshot) {
+        Ok(graph) => Some(graph),
+        Err(e) => {
+            warn!(snapshot_id = snapshot.snapshot_id, %e, "recording history snapshot without wait graph");
+            None
+        }
+    };
+
+    let mut conn = db.open()?;
+    let tx = conn
+        .transaction()
+        .map_err(|error| format!("start transaction: {error}"))?;
+    tx.facet_execute_ref(
+        "INSERT INTO history_snapshots (snapshot_id, captured_at_unix_ms, process_count, snapshot_json)
+         VALUES (:snapshot_id, :captured_at_unix_ms, :process_count, :snapshot_json)",
+        &HistorySnapshotInsertParams {
+            snapshot_id: snapshot.snapshot_id,
+            captured_at_unix_ms: snapshot.captured_at_unix_ms,
+            process_count: snapshot.processes.len() as u32,
+            snapshot_json,
+        },
+    )
+    .map_err(|error| format!("insert history snapshot: {error}"))?;
+    let history_id = tx.last_insert_rowid();
+
+    {
+        let mut insert_node_stmt = tx
+            .prepare(
+                "INSERT OR IGNORE INTO history_nodes (history_id, process_id, entity_id, name, kind, age_ms)
+                 VALUES (:history_id, :process_id, :entity_id, :name, :kind, :age_ms)",
+            )
+            .map_err(|error| format!("prepare history node insert: {error}"))?;
+        let mut insert_edge_stmt = tx
+            .prepare(
+                "INSERT OR IGNORE INTO history_edges (history_id, process_id, src_key, dst_key, waited_ms)
+                 VALUES (:history_id, :process_id, :src_key, :dst_key, :waited_ms)",
+            )
+            .map_err(|error| format!("prepare history edge insert: {error}"))?;
+        let mut insert_finding_stmt = tx
+            .prepare(
+                "INSERT INTO history_findings (history_id, finding_index, analysis, finding_json)
+                 VALUES (:history_id, :finding_index, :analysis, :finding_json)",
+            )
+            .map_err(|error| format!("prepare history finding insert: {error}"))?;
+        let mut insert_task_stmt = tx
+            .prepare(
+                "INSERT OR IGNORE INTO history_tasks (history_id, logical_id, process_id, entity_id, name, age_ms)
+                 VALUES (:history_id, :logical_id, :process_id, :entity_id, :name, :age_ms)",
+            )
+            .map_err(|error| format!("prepare history task insert: {error}"))?;
+
+        if let Some(graph) = &graph {
+            for node in graph.nodes.values() {
+                insert_node_stmt
+                    .facet_execute_ref(&HistoryNodeInsertParams {
+                        history_id,
+                        process_id: node.process_id.clone(),
+                        entity_id: node.entity_id.clone(),
+                        name: node.name.clone(),
+                        kind: node.kind.clone(),
+                        age_ms: to_i64_u64(node.ptime_now_ms.saturating_sub(node.birth_ms)),
+                    })
+                    .map_err(|error| format!("insert history node: {error}"))?;
+            }
+            for edge in &graph.edges {
+                let waited_ms = edge.since_ms.and_then(|since_ms| {
+                    let src = graph.nodes.get(&edge.src_key)?;
+                    Some(to_i64_u64(src.ptime_now_ms.saturating_sub(since_ms)))
+                });
+                insert_edge_stmt
+                    .facet_execute_ref(&HistoryEdgeInsertParams {
+                        history_id,
+                        process_id: edge.process_id.clone(),
+                        src_key: edge.src_key.clone(),
+                        dst_key: edge.dst_key.clone(),
+                        waited_ms,
+                    })
+                    .map_err(|error| format!("insert history edge: {error}"))?;
+            }
+        }
+        for process in &snapshot.processes {
+            for entity in &process.snapshot.entities {
+                let EntityBody::Future(future) = &entity.body else {
+                    continue;
+                };
+                let Some(logical_id) = &future.logical_id else {
+                    continue;
+                };
+                insert_task_stmt
+                    .facet_execute_ref(&HistoryTaskInsertParams {
+                        history_id,
+                        logical_id: logical_id.clone(),
+                        process_id: String::from(process.process_id.as_str()),
+                        entity_id: String::from(entity.id.as_str()),
+                        name: entity.name.clone(),
+                        age_ms: to_i64_u64(
+                            process
+                                .ptime_now_ms
+                                .saturating_sub(entity.birth.as_millis()),
+                        ),
+                    })
+                    .map_err(|error| format!("insert history task: {error}"))?;
+            }
+        }
+        for (finding_index, finding) in snapshot.findings.iter().enumerate() {
+            let finding_json = facet_json::to_string(finding)
+                .map_err(|error| format!("encode finding: {error}"))?;
+            insert_finding_stmt
+                .facet_execute_ref(&HistoryFindingInsertParams {
+                    history_id,
+                    finding_index: finding_index as u32,
+                    analysis: finding.analysis.clone(),
+                    finding_json,
+                })
+                .map_err(|error| format!("insert history finding: {error}"))?;
+        }
+    }
+
+    tx.commit()
+        .map_err(|error| format!("commit history snapshot: {error}"))?;
+    Ok(history_id)
+}
+
+/// Deletes every snapshot captured before `before_unix_ms`.
+pub fn prune_history_blocking(db: &Db, before_unix_ms: i64) -> Result<(), String> {
+    let mut conn = db.open()?;
+    let tx = conn
+        .transaction()
+        .map_err(|error| format!("start transaction: {error}"))?;
+    let params = HistoryPruneParams { before_unix_ms };
+    for table in [
+        "history_nodes",
+        "history_edges",
+        "history_findings",
+        "history_tasks",
+    ] {
+        tx.facet_execute_ref(
+            &format!(
+                "DELETE FROM {table} WHERE history_id IN (
+                   SELECT history_id FROM history_snapshots WHERE captured_at_unix_ms < :before_unix_ms
+                 )"
+            ),
+            &params,
+        )
+        .map_err(|error| format!("prune {table}: {error}"))?;
+    }
+    tx.facet_execute_ref(
+        "DELETE FROM history_snapshots WHERE captured_at_unix_ms < :before_unix_ms",
+        &params,
+    )
+    .map_err(|error| format!("prune history_snapshots: {error}"))?;
+    tx.commit()
+        .map_err(|error| format!("commit history prune: {error}"))
+}
+
+/// Snapshots recorded within `range`, oldest first, with their findings.
+pub fn query_history_blocking(
+    db: &Db,
+    range: &HistoryRange,
+) -> Result<Vec<HistorySnapshotSummary>, String> {
+    let conn = db.open()?;
+    let rows = conn
+        .facet_query_ref::<HistorySnapshotRow, _>(
+            "SELECT s.history_id, s.snapshot_id, s.captured_at_unix_ms, s.process_count,
+                    (SELECT COUNT(*) FROM history_nodes n WHERE n.history_id = s.history_id) AS node_count,
+                    (SELECT COUNT(*) FROM history_edges e WHERE e.history_id = s.history_id) AS edge_count
+             FROM history_snapshots s
+             WHERE s.captured_at_unix_ms BETWEEN :from_unix_ms AND :to_unix_ms
+               AND (:logical_id IS NULL OR EXISTS (
+                 SELECT 1 FROM history_tasks t
+                 WHERE t.history_id = s.history_id AND t.logical_id = :logical_id
+               ))
+             ORDER BY s.captured_at_unix_ms ASC, s.history_id ASC
+             LIMIT :limit",
+            range,
+        )
+        .map_err(|error| format!("query history_snapshots: {error}"))?;
+
+    let mut finding_stmt = conn
+        .prepare(
+            "SELECT finding_json FROM history_findings
+             WHERE history_id = :history_id
+             ORDER BY finding_index ASC",
+        )
+        .map_err(|error| format!("prepare history_findings read: {error}"))?;
+    let mut summaries = Vec::with_capacity(rows.len());
+    for row in rows {
+        let findings = finding_stmt
+            .facet_query_ref::<HistoryFindingRow, _>(&HistoryIdParams {
+                history_id: row.history_id,
+            })
+            .map_err(|error| format!("query history_findings: {error}"))?
+            .into_iter()
+            .map(|finding| {
+                facet_json::from_str::<AnalysisFinding>(&finding.finding_json)
+                    .map_err(|error| format!("decode history finding: {error}"))
+            })
+            .collect::<Result<Vec<_>, String>>()?;
+        summaries.push(HistorySnapshotSummary {
+            history_id: row.history_id,
+            snapshot_id: row.snapshot_id,
+            captured_at_unix_ms: row.captured_at_unix_ms,
+            process_count: row.process_count,
+            node_count: row.node_count,
+            edge_count: row.edge_count,
+            findings,
+        });
+    }
+    Ok(summaries)
+}
+
+/// The full snapshot JSON as it was served at capture time.
+pub fn load_history_snapshot_json_blocking(
+    db: &Db,
+    history_id: i64,
+) -> Result<Option<String>, String> {
+    let conn = db.open()?;
+    let rows = conn
+        .facet_query_ref::<HistorySnapshotJsonRow, _>(
+            "SELECT snapshot_json FROM history_snapshots WHERE history_id = :history_id",
+            &HistoryIdParams { history_id },
+        )
+        .map_err(|error| format!("query history snapshot: {error}"))?;
+    Ok(rows.into_iter().next().map(|row| row.snapshot_json))
+}
+
+/// Wait graph nodes and edges recorded for one snapshot, sorted by key.
+pub fn load_history_graph_blocking(
+    db: &Db,
+    history_id: i64,
+) -> Result<(Vec<HistoryNodeRow>, Vec<HistoryEdgeRow>), String> {
+    let conn = db.open()?;
+    let params = HistoryIdParams { history_id };
+    let nodes = conn
+        .facet_query_ref::<HistoryNodeRow, _>(
+            "SELECT process_id, entity_id, name, kind, age_ms FROM history_nodes
+             WHERE history_id = :history_id
+             ORDER BY process_id ASC, entity_id ASC",
+            &params,
+        )
+        .map_err(|error| format!("query history_nodes: {error}"))?;
+    let edges = conn
+        .facet_query_ref::<HistoryEdgeRow, _>(
+            "SELECT process_id, src_key, dst_key, waited_ms FROM history_edges
+             WHERE history_id = :history_id
+             ORDER BY src_key ASC, dst_key ASC",
+            &params,
+        )
+        .map_err(|error| format!("query history_edges: {error}"))?;
+    Ok((nodes, edges))
+}
+
+/// Async wrapper over [`query_history_blocking`].
+pub async fn query_history(
+    db: Arc<Db>,
+    range: HistoryRange,
+) -> Result<Vec<HistorySnapshotSummary>, String> {
+    tokio::task::spawn_blocking(move || query_history_blocking(&db, &range))
+        .await
+        .map_err(|error| format!("join sqlite: {error}"))?
+}
+
+#[cfg(test)]
+mod tests {
+    use moire_types::{FindingSeverity, SnapshotCutResponse};
+
+    use super::*;
+    use crate::db::init_sqlite;
+
+    fn snapshot(snapshot_id: i64, captured_at_unix_ms: i64) -> SnapshotCutResponse {
+        SnapshotCutResponse {
+            snapshot_id,
+            captured_at_unix_ms,
+            max_skew_ms: None,
+            processes: vec![],
+            timed_out_processes: vec![],
+            backtraces: vec![],
+            frames: vec![],
+            findings: vec![AnalysisFinding {
+                analysis: String::from("deadlock"),
+                severity: FindingSeverity::Critical,
+                title: format!("finding {snapshot_id}"),
+                rationale: String::new(),
+                subjects: vec![],
+                score: None,
+                hints: Vec::new(),
+            }],
+            annotations: vec![],
+            sizing_hints: vec![],
+            detection_config_version: None,
+            analysis_error: None,
+        }
+    }
+
+    #[test]
+    fn history_round_trips_by_time_range() {
+        let path = std::env::temp_dir().join(format!(
+            "moire-history-{}-{}.sqlite",
+            std::process::id(),
+            now_ms()
+        ));
+        let db = Db::new(path.clone());
+        init_sqlite(&db).unwrap();
+
+        let first = persist_history_snapshot_blocking(&db, &snapshot(1, 1_000)).unwrap();
+        let second = persist_history_snapshot_blocking(&db, &snapshot(2, 2_000)).unwrap();
+
+        let range = HistoryRange {
+            from_unix_ms: 1_500,
+            to_unix_ms: 2_500,
+            limit: 10,
+            logical_id: None,
+        };
+        let found = query_history_blocking(&db, &range).unwrap();
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].history_id, second);
+        assert_eq!(found[0].findings[0].title, "finding 2");
+        assert!(
+            load_history_snapshot_json_blocking(&db, first)
+                .unwrap()
+                .is_some()
+        );
+
+        prune_history_blocking(&db, 1_500).unwrap();
+        assert_eq!(
+            query_history_blocking(&db, &HistoryRange::all(10))
+                .unwrap()
+                .len(),
+            1
+        );
+        assert!(
+            load_history_snapshot_json_blocking(&db, first)
+                .unwrap()
+                .is_none()
+        );
+
+        let _ = std::fs::remove_file(path);
+    }
+}
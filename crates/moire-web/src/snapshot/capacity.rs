@@ -0,0 +1,166 @@
+//! Capacity-planning statistics for bounded channels and semaphores.
+//!
+//! A single snapshot only says how full a channel is right now. The collector
+//! keeps the queue depth of every bounded mpsc channel and the permits in use
+//! of every semaphore across the snapshots it takes, and attaches a
+//! [`SizingHint`] to each snapshot for resources whose 99th percentile sits at
+//! or near capacity: a consumer that cannot keep up or a pool that is too
+//! small, which the raw counters only hint at.
+
+// r[impl api.snapshot.sizing-hints]
+
+use std::collections::{HashMap, HashSet, VecDeque};
+
+use moire_types::{
+    EntityBody, EntityId, ProcessId, SizedResource, SizingHint, SnapshotCutResponse,
+};
+
+use crate::graph::compose_node_key;
+
+/// Samples kept per resource; older ones are dropped first.
+const SAMPLES_KEPT: usize = 256;
+/// Fewer samples than this say nothing about a percentile.
+const MIN_SAMPLES: usize = 16;
+/// p99 usage, in percent of capacity, from which a resource gets a hint.
+const SATURATED_PERCENT: u64 = 90;
+
+/// Per-resource usage samples, one per snapshot the resource appeared in.
+#[derive(Default)]
+pub struct CapacityTrends {
+    trends: HashMap<String, Trend>,
+}
+
+struct Trend {
+    process_id: ProcessId,
+    entity_id: EntityId,
+    resource: SizedResource,
+    capacity: u32,
+    samples: VecDeque<u32>,
+}
+
+impl CapacityTrends {
+    /// Samples every bounded resource of `snapshot` and returns hints for the
+    /// ones running at capacity, sorted by node key. Resources that are gone
+    /// from a process that replied are forgotten; those of processes that
+    /// timed out are kept for the next snapshot.
+    pub fn observe(&mut self, snapshot: &SnapshotCutResponse) -> Vec<SizingHint> {
+        let mut seen = HashSet::new();
+        for process in &snapshot.processes {
+            for entity in &process.snapshot.entities {
+                let (resource, capacity, used) = match &entity.body {
+                    EntityBody::MpscTx(tx) => match tx.capacity {
+                        Some(capacity) => (SizedResource::MpscChannel, capacity, tx.queue_len),
+                        None => continue,
+                    },
+                    EntityBody::Semaphore(semaphore) => (
+                        SizedResource::Semaphore,
+                        semaphore.max_permits,
+                        semaphore.handed_out_permits,
+                    ),
+                    _ => continue,
+                };
+                let key = compose_node_key(&process.process_id, &entity.id);
+                let trend = self.trends.entry(key.clone()).or_insert_with(|| Trend {
+                    process_id: process.process_id.clone(),
+                    entity_id: entity.id.clone(),
+                    resource,
+                    capacity,
+                    samples: VecDeque::new(),
+                });
+                if trend.capacity != capacity {
+                    // Resized: earlier samples describe a different resource.
+                    trend.capacity = capacity;
+                    trend.samples.clear();
+                }
+                if trend.samples.len() == SAMPLES_KEPT {
+                    trend.samples.pop_front();
+                }
+                trend.samples.push_back(used);
+                seen.insert(key);
+            }
+        }
+
+        let replied = snapshot
+            .processes
+            .iter()
+            .map(|process| process.process_id.as_str())
+            .collect::<HashSet<_>>();
+        self.trends.retain(|key, trend| {
+            seen.contains(key) || !replied.contains(trend.process_id.as_str())
+        });
+
+        let mut keys = seen.into_iter().collect::<Vec<_>>();
+        keys.sort();
+        keys.iter()
+            .filter_map(|key| self.trends.get(key)?.sizing_hint())
+            .collect()
+    }
+}
+
+impl Trend {
+    fn sizing_hint(&self) -> Option<SizingHint> {
+        if self.samples.len() < MIN_SAMPLES || self.capacity == 0 {
+            return None;
+        }
+        let mut sorted = self.samples.iter().copied().collect::<Vec<_>>();
+        sorted.sort_unstable();
+        let p99_used = sorted[(sorted.len() * 99).div_ceil(100) - 1];
+        let max_used = sorted[sorted.len() - 1];
+        if u64::from(p99_used) * 100 < u64::from(self.capacity) * SATURATED_PERCENT {
+            return None;
+        }
+        let hint = match self.resource {
+            SizedResource::MpscChannel => format!(
+                "bounded({}) but p99 depth={p99_used} — consider a larger buffer or a faster consumer",
+                self.capacity
+            ),
+            SizedResource::Semaphore => format!(
+                "{} permits but p99 in use={p99_used} — consider more permits or shorter holds",
+                self.capacity
+            ),
+        };
+        Some(SizingHint {
+            process_id: self.process_id.clone(),
+            entity_id: self.entity_id.clone(),
+            resource: self.resource,
+            capacity: self.capacity,
+            p99_used,
+            max_used,
+            samples: self.samples.len() as u32,
+            hint,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn trend(capacity: u32, samples: impl IntoIterator<Item = u32>) -> Trend {
+        Trend {
+            process_id: ProcessId::new("p"),
+            entity_id: EntityId::new("tx"),
+            resource: SizedResource::MpscChannel,
+            capacity,
+            samples: samples.into_iter().collect(),
+        }
+    }
+
+    #[test]
+    fn hints_only_when_p99_reaches_capacity() {
+        // One full buffer in a hundred samples is a burst, not a sizing problem.
+        let bursty = trend(16, (0..99).map(|i| i % 4).chain([16]));
+        assert!(bursty.sizing_hint().is_none());
+
+        let saturated = trend(16, (0..100).map(|i| if i % 10 == 0 { 3 } else { 16 }));
+        let hint = saturated
+            .sizing_hint()
+            .expect("saturated channel gets a hint");
+        assert_eq!(hint.p99_used, 16);
+        assert_eq!(hint.max_used, 16);
+        assert_eq!(hint.samples, 100);
+        assert!(hint.hint.starts_with("bounded(16) but p99 depth=16"));
+
+        assert!(trend(16, [16; 4]).sizing_hint().is_none());
+    }
+}
@@ -1,2 +1,6 @@
+pub mod capacity;
 pub(crate) mod repository;
+pub mod otlp;
+pub mod summary;
 pub mod table;
+pub mod trace_event;
@@ -0,0 +1,327 @@
+//! OpenTelemetry (OTLP/JSON) export of a snapshot's RPCs and tasks.
+//!
+//! `moire otel --endpoint http://collector:4318` posts the current snapshot to
+//! an OTLP/HTTP collector, so moire data lands next to the traces a team
+//! already has. Each process becomes a resource; every RPC request, RPC
+//! response and tracked task (a future with a logical id) becomes a span:
+//!
+//! - requests are client spans, responses server spans. A response links to
+//!   the request it answers, which usually lives in another process;
+//! - tasks are internal spans, parented to the task that spawned them, so a
+//!   spawn tree shares one trace;
+//! - entities still alive end at capture time and carry `moire.in_flight`.
+//!
+//! Trace and span ids are hashes of `process_id::entity_id`, so exporting the
+//! same entity twice yields the same span and collectors can deduplicate.
+
+use std::collections::HashMap;
+
+use facet::Facet;
+use moire_types::{
+    EdgeKind, Entity, EntityBody, ProcessSnapshotView, ResponseError, ResponseStatus,
+    SnapshotCutResponse,
+};
+
+use crate::graph::compose_node_key;
+
+const SPAN_KIND_INTERNAL: u8 = 1;
+const SPAN_KIND_SERVER: u8 = 2;
+const SPAN_KIND_CLIENT: u8 = 3;
+const STATUS_CODE_ERROR: u8 = 2;
+
+#[derive(Facet)]
+struct ExportTraceServiceRequest {
+    #[facet(rename = "resourceSpans")]
+    resource_spans: Vec<ResourceSpans>,
+}
+
+#[derive(Facet)]
+struct ResourceSpans {
+    resource: Resource,
+    #[facet(rename = "scopeSpans")]
+    scope_spans: Vec<ScopeSpans>,
+}
+
+#[derive(Facet)]
+struct Resource {
+    attributes: Vec<KeyValue>,
+}
+
+#[derive(Facet)]
+struct ScopeSpans {
+    scope: InstrumentationScope,
+    spans: Vec<Span>,
+}
+
+#[derive(Facet)]
+struct InstrumentationScope {
+    name: String,
+}
+
+#[derive(Facet)]
+struct Span {
+    #[facet(rename = "traceId")]
+    trace_id: String,
+    #[facet(rename = "spanId")]
+    span_id: String,
+    #[facet(rename = "parentSpanId", skip_unless_truthy)]
+    parent_span_id: Option<String>,
+    name: String,
+    kind: u8,
+    /// Decimal, as OTLP/JSON encodes 64-bit integers.
+    #[facet(rename = "startTimeUnixNano")]
+    start_time_unix_nano: String,
+    #[facet(rename = "endTimeUnixNano")]
+    end_time_unix_nano: String,
+    attributes: Vec<KeyValue>,
+    #[facet(default)]
+    links: Vec<Link>,
+    #[facet(skip_unless_truthy)]
+    status: Option<Status>,
+}
+
+#[derive(Facet)]
+struct Link {
+    #[facet(rename = "traceId")]
+    trace_id: String,
+    #[facet(rename = "spanId")]
+    span_id: String,
+}
+
+#[derive(Facet)]
+struct Status {
+    code: u8,
+    #[facet(skip_unless_truthy)]
+    message: Option<String>,
+}
+
+#[derive(Facet)]
+struct KeyValue {
+    key: String,
+    value: AnyValue,
+}
+
+#[derive(Facet, Default)]
+struct AnyValue {
+    #[facet(rename = "stringValue", skip_unless_truthy)]
+    string_value: Option<String>,
+    #[facet(rename = "boolValue", skip_unless_truthy)]
+    bool_value: Option<bool>,
+}
+
+fn string_attr(key: &str, value: impl Into<String>) -> KeyValue {
+    KeyValue {
+        key: String::from(key),
+        value: AnyValue {
+            string_value: Some(value.into()),
+            ..AnyValue::default()
+        },
+    }
+}
+
+fn true_attr(key: &str) -> KeyValue {
+    KeyValue {
+        key: String::from(key),
+        value: AnyValue {
+            bool_value: Some(true),
+            ..AnyValue::default()
+        },
+    }
+}
+
+fn fnv1a(seed: u64, text: &str) -> u64 {
+    text.bytes().fold(seed, |hash, byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(0x0000_0100_0000_01b3)
+    })
+}
+
+/// 16 hex digits; never all zeros, which OTLP reserves for "no span".
+fn span_id(key: &str) -> String {
+    format!("{:016x}", fnv1a(0xcbf2_9ce4_8422_2325, key).max(1))
+}
+
+/// 32 hex digits, from two differently seeded hashes of `key`.
+fn trace_id(key: &str) -> String {
+    format!(
+        "{:016x}{:016x}",
+        fnv1a(0xcbf2_9ce4_8422_2325, key),
+        fnv1a(0x8422_2325_cbf2_9ce4, key).max(1)
+    )
+}
+
+/// Renders the RPCs and tracked tasks of `snapshot` as an OTLP/JSON
+/// `ExportTraceServiceRequest`, the body of `POST /v1/traces`.
+pub fn to_otlp_json(snapshot: &SnapshotCutResponse) -> Result<String, String> {
+    // Requests are looked up across processes, like `WaitGraph` pairs RPCs.
+    let request_keys: HashMap<&str, String> = snapshot
+        .processes
+        .iter()
+        .flat_map(|process| {
+            process
+                .snapshot
+                .entities
+                .iter()
+                .filter(|entity| matches!(entity.body, EntityBody::Request(_)))
+                .map(move |entity| {
+                    (
+                        entity.id.as_str(),
+                        compose_node_key(&process.process_id, &entity.id),
+                    )
+                })
+        })
+        .collect();
+
+    let mut processes = snapshot.processes.iter().collect::<Vec<_>>();
+    processes.sort_by(|a, b| a.process_id.as_str().cmp(b.process_id.as_str()));
+    let resource_spans = processes
+        .into_iter()
+        .map(|process| ResourceSpans {
+            resource: Resource {
+                attributes: vec![
+                    string_attr("service.name", process.process_name.clone()),
+                    string_attr("process.pid", process.pid.to_string()),
+                    string_attr("moire.process_id", process.process_id.as_str()),
+                ],
+            },
+            scope_spans: vec![ScopeSpans {
+                scope: InstrumentationScope {
+                    name: String::from("moire"),
+                },
+                spans: process_spans(snapshot, process, &request_keys),
+            }],
+        })
+        .collect();
+
+    facet_json::to_string(&ExportTraceServiceRequest { resource_spans })
+        .map_err(|e| format!("encode otlp spans: {e}"))
+}
+
+fn process_spans(
+    snapshot: &SnapshotCutResponse,
+    process: &ProcessSnapshotView,
+    request_keys: &HashMap<&str, String>,
+) -> Vec<Span> {
+    let birth_unix_ms = snapshot
+        .captured_at_unix_ms
+        .saturating_sub(process.ptime_now_ms as i64);
+    let unix_nanos = |ptime_ms: u64| {
+        (birth_unix_ms.saturating_add(ptime_ms as i64).max(0) as u64)
+            .saturating_mul(1_000_000)
+            .to_string()
+    };
+    let key_of = |entity: &Entity| compose_node_key(&process.process_id, &entity.id);
+
+    let tasks: HashMap<&str, &Entity> = process
+        .snapshot
+        .entities
+        .iter()
+        .filter(|entity| {
+            matches!(&entity.body, EntityBody::Future(future) if future.logical_id.is_some())
+        })
+        .map(|entity| (entity.id.as_str(), entity))
+        .collect();
+    // A task's trace is named after the root of its spawn tree.
+    let task_root = |start: &Entity| {
+        let mut entity = start;
+        for _ in 0..tasks.len() {
+            let EntityBody::Future(future) = &entity.body else {
+                break;
+            };
+            match future
+                .spawned_by
+                .as_ref()
+                .and_then(|parent| tasks.get(parent.as_str()).copied())
+            {
+                Some(parent) => entity = parent,
+                None => break,
+            }
+        }
+        key_of(entity)
+    };
+
+    let mut spans = Vec::new();
+    for entity in &process.snapshot.entities {
+        let key = key_of(entity);
+        let mut attributes = vec![string_attr("moire.entity_id", entity.id.as_str())];
+        if entity.removed_at.is_none() {
+            attributes.push(true_attr("moire.in_flight"));
+        }
+        let end_ms = entity
+            .removed_at
+            .map(|at| at.as_millis())
+            .unwrap_or(process.ptime_now_ms);
+
+        let (kind, trace, parent, links, status) = match &entity.body {
+            EntityBody::Future(future) => {
+                let Some(logical_id) = &future.logical_id else {
+                    continue;
+                };
+                attributes.push(string_attr("moire.logical_id", logical_id.clone()));
+                let parent = future
+                    .spawned_by
+                    .as_ref()
+                    .filter(|parent| tasks.contains_key(parent.as_str()))
+                    .map(|parent| span_id(&compose_node_key(&process.process_id, parent)));
+                (
+                    SPAN_KIND_INTERNAL,
+                    trace_id(&task_root(entity)),
+                    parent,
+                    Vec::new(),
+                    None,
+                )
+            }
+            EntityBody::Request(request) => {
+                attributes.push(string_attr("rpc.service", request.service_name.clone()));
+                attributes.push(string_attr("rpc.method", request.method_name.clone()));
+                (SPAN_KIND_CLIENT, trace_id(&key), None, Vec::new(), None)
+            }
+            EntityBody::Response(response) => {
+                attributes.push(string_attr("rpc.service", response.service_name.clone()));
+                attributes.push(string_attr("rpc.method", response.method_name.clone()));
+                let links = process
+                    .snapshot
+                    .edges
+                    .iter()
+                    .filter(|edge| edge.kind == EdgeKind::PairedWith && edge.src == entity.id)
+                    .filter_map(|edge| request_keys.get(edge.dst.as_str()))
+                    .map(|request_key| Link {
+                        trace_id: trace_id(request_key),
+                        span_id: span_id(request_key),
+                    })
+                    .collect();
+                let status = match &response.status {
+                    ResponseStatus::Error(ResponseError::Internal(message)) => Some(Status {
+                        code: STATUS_CODE_ERROR,
+                        message: Some(message.clone()),
+                    }),
+                    ResponseStatus::Error(ResponseError::UserJson(json)) => Some(Status {
+                        code: STATUS_CODE_ERROR,
+                        message: Some(String::from(json.as_str())),
+                    }),
+                    ResponseStatus::Cancelled => Some(Status {
+                        code: STATUS_CODE_ERROR,
+                        message: Some(String::from("cancelled")),
+                    }),
+                    ResponseStatus::Pending | ResponseStatus::Ok(_) => None,
+                };
+                (SPAN_KIND_SERVER, trace_id(&key), None, links, status)
+            }
+            _ => continue,
+        };
+
+        spans.push(Span {
+            trace_id: trace,
+            span_id: span_id(&key),
+            parent_span_id: parent,
+            name: entity.name.clone(),
+            kind,
+            start_time_unix_nano: unix_nanos(entity.birth.as_millis()),
+            end_time_unix_nano: unix_nanos(end_ms),
+            attributes,
+            links,
+            status,
+        });
+    }
+    spans.sort_by(|a, b| a.span_id.cmp(&b.span_id));
+    spans
+}
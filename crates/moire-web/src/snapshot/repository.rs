@@ -35,11 +35,6 @@ pub(crate) struct BacktraceFrameBatch {
     pub(crate) symbolicated_by_index: BTreeMap<u32, SymbolicatedFrameRow>,
 }
 
-#[derive(Facet)]
-struct BacktraceFrameParams {
-    backtrace_id: BacktraceId,
-}
-
 pub(crate) fn load_backtrace_frame_batches(
     db: &Db,
     backtrace_ids: &[BacktraceId],
@@ -50,7 +45,7 @@ pub(crate) fn load_backtrace_frame_batches(
         .prepare(
             "SELECT process_id, frame_index, module_path, module_identity, rel_pc
              FROM backtrace_frames
-             WHERE backtrace_id = :backtrace_id
+             WHERE backtrace_id = ?1
              ORDER BY frame_index ASC",
         )
         .map_err(|error| format!("prepare backtrace_frames read: {error}"))?;
@@ -58,17 +53,14 @@ pub(crate) fn load_backtrace_frame_batches(
         .prepare(
             "SELECT process_id, frame_index, module_path, rel_pc, status, function_name, source_file_path, source_line, unresolved_reason
              FROM symbolicated_frames
-             WHERE backtrace_id = :backtrace_id",
+             WHERE backtrace_id = ?1",
         )
         .map_err(|error| format!("prepare symbolicated_frames read: {error}"))?;
 
     let mut batches = Vec::with_capacity(backtrace_ids.len());
     for backtrace_id in backtrace_ids {
-        let params = BacktraceFrameParams {
-            backtrace_id: *backtrace_id,
-        };
         let raw_rows = raw_stmt
-            .facet_query_ref::<StoredBacktraceFrameRow, _>(&params)
+            .facet_query_ref::<StoredBacktraceFrameRow, _>(backtrace_id)
             .map_err(|error| format!("query backtrace_frames: {error}"))?;
         if raw_rows.is_empty() {
             return Err(format!(
@@ -88,7 +80,7 @@ pub(crate) fn load_backtrace_frame_batches(
         }
 
         let symbolicated_by_index = symbol_stmt
-            .facet_query_ref::<SymbolicatedFrameRow, _>(&params)
+            .facet_query_ref::<SymbolicatedFrameRow, _>(backtrace_id)
             .map_err(|error| format!("query symbolicated_frames: {error}"))?
             .into_iter()
             .map(|row| {
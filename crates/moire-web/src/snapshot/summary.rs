@@ -0,0 +1,290 @@
+//! Headline statistics of a snapshot.
+//!
+//! A full snapshot of a busy process runs to megabytes; a status page only
+//! wants how many tasks are stuck and the few worst offenders. [`summarize`]
+//! boils each process down to a [`ProcessSummary`].
+
+// r[impl api.snapshot.summary]
+
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+use moire_types::{
+    EdgeKind, Entity, EntityBody, EntityId, LockSummary, ProcessSnapshotView, ProcessSummary,
+    RpcMethodSummary, SnapshotCutResponse, SnapshotSummaryResponse, TaskStateCounts, WaitSummary,
+};
+
+/// Entries kept per list unless the request asks for another number.
+pub const DEFAULT_TOP_N: usize = 5;
+
+pub fn summarize(snapshot: &SnapshotCutResponse, top_n: usize) -> SnapshotSummaryResponse {
+    SnapshotSummaryResponse {
+        snapshot_id: snapshot.snapshot_id,
+        captured_at_unix_ms: snapshot.captured_at_unix_ms,
+        processes: snapshot
+            .processes
+            .iter()
+            .map(|process| summarize_process(process, top_n))
+            .collect(),
+        timed_out_processes: snapshot
+            .timed_out_processes
+            .iter()
+            .map(|process| moire_types::TimedOutProcess {
+                process_id: process.process_id.clone(),
+                process_name: process.process_name.clone(),
+                pid: process.pid,
+                timed_out_sections: process.timed_out_sections.clone(),
+            })
+            .collect(),
+    }
+}
+
+fn summarize_process(process: &ProcessSnapshotView, top_n: usize) -> ProcessSummary {
+    let now_ms = process.ptime_now_ms;
+    let entities: HashMap<&EntityId, &Entity> = process
+        .snapshot
+        .entities
+        .iter()
+        .map(|entity| (&entity.id, entity))
+        .collect();
+    let name_of = |id: &EntityId| {
+        entities
+            .get(id)
+            .map(|entity| entity.name.clone())
+            .unwrap_or_else(|| String::from(id.as_str()))
+    };
+
+    let mut polls: HashMap<&EntityId, Vec<&EntityId>> = HashMap::new();
+    let mut waiting: HashSet<&EntityId> = HashSet::new();
+    let mut lock_waiters: HashMap<&EntityId, u32> = HashMap::new();
+    let mut lock_holders: HashMap<&EntityId, u32> = HashMap::new();
+    let mut worst_waits = Vec::new();
+    for edge in &process.snapshot.edges {
+        match edge.kind {
+            EdgeKind::Polls => polls.entry(&edge.src).or_default().push(&edge.dst),
+            EdgeKind::WaitingOn => {
+                waiting.insert(&edge.src);
+                *lock_waiters.entry(&edge.dst).or_default() += 1;
+                if let Some(since) = edge.since {
+                    worst_waits.push(WaitSummary {
+                        waiter: edge.src.clone(),
+                        waiter_name: name_of(&edge.src),
+                        target: edge.dst.clone(),
+                        target_name: name_of(&edge.dst),
+                        waited_ms: now_ms.saturating_sub(since.as_millis()),
+                    });
+                }
+            }
+            EdgeKind::HeldBy => *lock_holders.entry(&edge.src).or_default() += 1,
+            EdgeKind::PairedWith => {}
+        }
+    }
+    worst_waits.sort_by_key(|wait| Reverse(wait.waited_ms));
+    worst_waits.truncate(top_n);
+
+    let mut tasks = TaskStateCounts::default();
+    let mut busiest_locks = Vec::new();
+    let mut rpc_methods: BTreeMap<(&str, &str), RpcMethodSummary> = BTreeMap::new();
+    for entity in &process.snapshot.entities {
+        let live = entity.removed_at.is_none();
+        match &entity.body {
+            EntityBody::Future(future) if live && future.logical_id.is_some() => {
+                if future.wake_to_poll_gap_ms.is_some() {
+                    tasks.woken += 1;
+                } else if waits_through_polls(&entity.id, &polls, &waiting) {
+                    if future.idle.is_some() {
+                        tasks.idle += 1;
+                    } else {
+                        tasks.waiting += 1;
+                    }
+                } else {
+                    tasks.other += 1;
+                }
+            }
+            EntityBody::Lock(lock) if live => {
+                let holds = lock.holds.as_ref();
+                busiest_locks.push(LockSummary {
+                    entity_id: entity.id.clone(),
+                    name: entity.name.clone(),
+                    waiters: lock_waiters.get(&entity.id).copied().unwrap_or(0),
+                    holders: lock_holders.get(&entity.id).copied().unwrap_or(0),
+                    hold_count: holds.map_or(0, |holds| holds.hold_count),
+                    avg_hold_ms: holds.map_or(0, |holds| holds.avg_hold_ms),
+                });
+            }
+            EntityBody::Request(request) => {
+                let end_ms = entity.removed_at.map_or(now_ms, |at| at.as_millis());
+                let elapsed_ms = end_ms.saturating_sub(entity.birth.as_millis());
+                let method = rpc_methods
+                    .entry((&request.service_name, &request.method_name))
+                    .or_insert_with(|| RpcMethodSummary {
+                        service_name: request.service_name.clone(),
+                        method_name: request.method_name.clone(),
+                        calls: 0,
+                        in_flight: 0,
+                        slowest_ms: 0,
+                    });
+                method.calls += 1;
+                method.in_flight += u32::from(live);
+                method.slowest_ms = method.slowest_ms.max(elapsed_ms);
+            }
+            _ => {}
+        }
+    }
+    busiest_locks.sort_by(|a, b| {
+        (b.waiters, b.hold_count)
+            .cmp(&(a.waiters, a.hold_count))
+            .then_with(|| a.name.cmp(&b.name))
+    });
+    busiest_locks.truncate(top_n);
+    let mut slowest_rpc_methods: Vec<_> = rpc_methods.into_values().collect();
+    slowest_rpc_methods.sort_by_key(|method| Reverse(method.slowest_ms));
+    slowest_rpc_methods.truncate(top_n);
+
+    ProcessSummary {
+        process_id: process.process_id.clone(),
+        process_name: process.process_name.clone(),
+        pid: process.pid,
+        entity_count: process.snapshot.entities.len() as u32,
+        tasks,
+        worst_waits,
+        busiest_locks,
+        slowest_rpc_methods,
+    }
+}
+
+/// Whether `task`, or a future it is polling, directly or not, waits on
+/// something.
+fn waits_through_polls(
+    task: &EntityId,
+    polls: &HashMap<&EntityId, Vec<&EntityId>>,
+    waiting: &HashSet<&EntityId>,
+) -> bool {
+    let mut seen = HashSet::new();
+    let mut stack = vec![task];
+    while let Some(id) = stack.pop() {
+        if !seen.insert(id) {
+            continue;
+        }
+        if waiting.contains(id) {
+            return true;
+        }
+        stack.extend(polls.get(id).into_iter().flatten().copied());
+    }
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use moire_types::{
+        BacktraceId, Edge, FutureEntity, LockEntity, LockKind, PTime, ProcessId, Snapshot,
+    };
+
+    use super::*;
+
+    fn snapshot(entities: Vec<Entity>, edges: Vec<Edge>) -> SnapshotCutResponse {
+        SnapshotCutResponse {
+            snapshot_id: 7,
+            captured_at_unix_ms: 0,
+            max_skew_ms: None,
+            processes: vec![ProcessSnapshotView {
+                process_id: ProcessId::new("p"),
+                process_name: String::from("app"),
+                pid: 1,
+                ptime_now_ms: 10_000,
+                skew_ms: None,
+                snapshot: Snapshot {
+                    entities,
+                    scopes: Vec::new(),
+                    edges,
+                    events: Vec::new(),
+                    workers: Vec::new(),
+                    long_polls: Vec::new(),
+                    rpc_methods: Vec::new(),
+                    coverage: None,
+                    runtime_stats: None,
+                },
+                scope_entity_links: Vec::new(),
+                timed_out_sections: None,
+                violations: Vec::new(),
+            }],
+            timed_out_processes: Vec::new(),
+            backtraces: Vec::new(),
+            frames: Vec::new(),
+            findings: Vec::new(),
+            annotations: Vec::new(),
+            sizing_hints: Vec::new(),
+            detection_config_version: None,
+            analysis_error: None,
+        }
+    }
+
+    fn entity(id: &str, body: EntityBody) -> Entity {
+        let mut entity = Entity::new(BacktraceId::next().expect("backtrace id"), id, body);
+        entity.id = EntityId::new(id);
+        entity
+    }
+
+    fn edge(src: &str, dst: &str, kind: EdgeKind, since_ms: Option<u64>) -> Edge {
+        Edge {
+            src: EntityId::new(src),
+            dst: EntityId::new(dst),
+            backtrace: BacktraceId::next().expect("backtrace id"),
+            kind,
+            since: since_ms.map(PTime::from_millis),
+            reason: None,
+        }
+    }
+
+    fn task() -> EntityBody {
+        EntityBody::Future(FutureEntity {
+            logical_id: Some(String::from("0000000000000000")),
+            ..FutureEntity::default()
+        })
+    }
+
+    fn lock() -> EntityBody {
+        EntityBody::Lock(LockEntity {
+            kind: LockKind::Mutex,
+            holds: None,
+            rwlock: None,
+        })
+    }
+
+    // r[verify api.snapshot.summary]
+    #[test]
+    fn summary_counts_tasks_waiting_through_their_futures_and_ranks_waits() {
+        let summary = summarize(
+            &snapshot(
+                vec![
+                    entity("outer", task()),
+                    entity("inner", EntityBody::Future(FutureEntity::default())),
+                    entity("busy", task()),
+                    entity("state", lock()),
+                    entity("quiet", lock()),
+                ],
+                vec![
+                    edge("outer", "inner", EdgeKind::Polls, None),
+                    edge("inner", "state", EdgeKind::WaitingOn, Some(2_000)),
+                    edge("busy", "quiet", EdgeKind::WaitingOn, Some(9_000)),
+                ],
+            ),
+            1,
+        );
+        let process = &summary.processes[0];
+        assert_eq!(
+            process.tasks,
+            TaskStateCounts {
+                woken: 0,
+                waiting: 2,
+                idle: 0,
+                other: 0,
+            }
+        );
+        assert_eq!(process.worst_waits.len(), 1);
+        assert_eq!(process.worst_waits[0].waiter.as_str(), "inner");
+        assert_eq!(process.worst_waits[0].waited_ms, 8_000);
+        assert_eq!(process.busiest_locks.len(), 1);
+        assert_eq!(process.busiest_locks[0].name, "quiet");
+    }
+}
@@ -0,0 +1,176 @@
+//! Chrome `trace_event` export of a snapshot's poll and wait timelines.
+//!
+//! `moire trace --file snapshot.json > trace.json` produces a file that
+//! `chrome://tracing` and the Perfetto UI open directly. Each process becomes a
+//! trace process and each worker thread a track: long polls are slices on the
+//! worker that ran them, and every `waiting_on` edge is an async slice from
+//! when the wait began to the moment the snapshot was taken.
+//!
+//! Processes count time from their own birth, so timestamps are shifted onto
+//! the wall clock through `captured_at_unix_ms` and `ptime_now_ms`, keeping
+//! processes of one snapshot aligned with each other.
+
+use std::collections::HashMap;
+
+use facet::Facet;
+use moire_types::{EdgeKind, SnapshotCutResponse};
+
+/// Track for waits, kept apart from worker tracks.
+const WAITS_TID: u64 = u64::MAX;
+
+#[derive(Facet)]
+struct TraceFile {
+    #[facet(rename = "traceEvents")]
+    trace_events: Vec<TraceEvent>,
+    #[facet(rename = "displayTimeUnit")]
+    display_time_unit: String,
+}
+
+#[derive(Facet)]
+struct TraceEvent {
+    name: String,
+    #[facet(skip_unless_truthy)]
+    cat: Option<String>,
+    ph: String,
+    /// Microseconds.
+    ts: u64,
+    #[facet(skip_unless_truthy)]
+    dur: Option<u64>,
+    pid: u64,
+    tid: u64,
+    /// Pairs the begin and end of an async slice.
+    #[facet(skip_unless_truthy)]
+    id: Option<String>,
+    args: TraceArgs,
+}
+
+#[derive(Facet, Default)]
+struct TraceArgs {
+    /// Process or thread name, on metadata events.
+    #[facet(skip_unless_truthy)]
+    name: Option<String>,
+    #[facet(skip_unless_truthy)]
+    entity_id: Option<String>,
+    #[facet(skip_unless_truthy)]
+    waiting_on: Option<String>,
+}
+
+/// Renders the long polls and waits of `snapshot` as Chrome trace JSON.
+/// Events are sorted, so the same snapshot always yields the same text.
+pub fn to_trace_event_json(snapshot: &SnapshotCutResponse) -> Result<String, String> {
+    // Wall-clock milliseconds at each process's birth, relative to the
+    // earliest one.
+    let births = snapshot
+        .processes
+        .iter()
+        .map(|process| {
+            (snapshot.captured_at_unix_ms.max(0) as u64).saturating_sub(process.ptime_now_ms)
+        })
+        .collect::<Vec<_>>();
+    let base_ms = births.iter().copied().min().unwrap_or(0);
+
+    let mut events = Vec::new();
+    for ((index, process), birth_ms) in snapshot.processes.iter().enumerate().zip(births) {
+        let pid = index as u64 + 1;
+        let ts = |ptime_ms: u64| (birth_ms - base_ms + ptime_ms).saturating_mul(1_000);
+        let metadata = |name: &'static str, tid: u64, value: String| TraceEvent {
+            name: String::from(name),
+            cat: None,
+            ph: String::from("M"),
+            ts: 0,
+            dur: None,
+            pid,
+            tid,
+            id: None,
+            args: TraceArgs {
+                name: Some(value),
+                ..TraceArgs::default()
+            },
+        };
+
+        events.push(metadata(
+            "process_name",
+            0,
+            format!("{} (pid {})", process.process_name, process.pid),
+        ));
+        for worker in &process.snapshot.workers {
+            let label = match &worker.thread_name {
+                Some(thread_name) => format!("worker {} ({thread_name})", worker.worker),
+                None => format!("worker {}", worker.worker),
+            };
+            events.push(metadata("thread_name", worker.worker, label));
+        }
+        events.push(metadata("thread_name", WAITS_TID, String::from("waits")));
+
+        let names: HashMap<&str, &str> = process
+            .snapshot
+            .entities
+            .iter()
+            .map(|entity| (entity.id.as_str(), entity.name.as_str()))
+            .collect();
+        let name_of = |id: &str| names.get(id).copied().unwrap_or(id).to_owned();
+
+        for long_poll in &process.snapshot.long_polls {
+            let end_us = ts(long_poll.at.as_millis());
+            events.push(TraceEvent {
+                name: name_of(long_poll.future_id.as_str()),
+                cat: Some(String::from("poll")),
+                ph: String::from("X"),
+                ts: end_us.saturating_sub(long_poll.duration_us),
+                dur: Some(long_poll.duration_us),
+                pid,
+                tid: long_poll.worker,
+                id: None,
+                args: TraceArgs {
+                    entity_id: Some(String::from(long_poll.future_id.as_str())),
+                    ..TraceArgs::default()
+                },
+            });
+        }
+
+        for edge in &process.snapshot.edges {
+            if edge.kind != EdgeKind::WaitingOn {
+                continue;
+            }
+            let Some(since) = edge.since else {
+                continue;
+            };
+            let id = format!("{}->{}", edge.src.as_str(), edge.dst.as_str());
+            let waiter = name_of(edge.src.as_str());
+            let resource = name_of(edge.dst.as_str());
+            for (ph, at_ms) in [("b", since.as_millis()), ("e", process.ptime_now_ms)] {
+                events.push(TraceEvent {
+                    name: waiter.clone(),
+                    cat: Some(String::from("wait")),
+                    ph: String::from(ph),
+                    ts: ts(at_ms),
+                    dur: None,
+                    pid,
+                    tid: WAITS_TID,
+                    id: Some(id.clone()),
+                    args: TraceArgs {
+                        entity_id: Some(String::from(edge.src.as_str())),
+                        waiting_on: Some(resource.clone()),
+                        ..TraceArgs::default()
+                    },
+                });
+            }
+        }
+    }
+
+    events.sort_by(|a, b| {
+        (a.pid, a.ph != "M", a.ts, a.tid, &a.id, &a.name).cmp(&(
+            b.pid,
+            b.ph != "M",
+            b.ts,
+            b.tid,
+            &b.id,
+            &b.name,
+        ))
+    });
+    facet_json::to_string(&TraceFile {
+        trace_events: events,
+        display_time_unit: String::from("ms"),
+    })
+    .map_err(|e| format!("encode trace events: {e}"))
+}
@@ -1,8 +1,8 @@
 use std::path::Path as FsPath;
 use std::sync::Arc;
 
-use tokio::io::{AsyncReadExt, AsyncWriteExt};
-use tokio::net::{TcpListener, TcpStream};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tokio::net::TcpListener;
 use tokio::sync::{Notify, mpsc};
 use tracing::{debug, error, info, warn};
 
@@ -31,8 +31,37 @@ pub async fn run_tcp_acceptor(listener: TcpListener, state: AppState) {
     }
 }
 
-async fn handle_conn(stream: TcpStream, state: AppState) -> Result<(), String> {
-    let (mut reader, mut writer) = stream.into_split();
+/// How long to wait before reconnecting to a pull socket that refused or
+/// dropped the connection.
+#[cfg(unix)]
+const PULL_SOCKET_RETRY: std::time::Duration = std::time::Duration::from_secs(2);
+
+/// Keeps a connection open to the process agent listening on `path` (see
+/// `MOIRE_SOCKET` in the instrumented process), reconnecting whenever it
+/// drops. The connection then takes part in cuts and snapshots like a pushing
+/// one; it only never sends changes.
+// r[impl config.web.pull-sockets]
+#[cfg(unix)]
+pub async fn run_socket_puller(path: std::path::PathBuf, state: AppState) {
+    loop {
+        match tokio::net::UnixStream::connect(&path).await {
+            Ok(stream) => {
+                info!(path = %path.display(), "pull socket connected");
+                if let Err(e) = handle_conn(stream, state.clone()).await {
+                    error!(path = %path.display(), %e, "pull socket connection error");
+                }
+            }
+            Err(e) => debug!(path = %path.display(), %e, "pull socket not reachable"),
+        }
+        tokio::time::sleep(PULL_SOCKET_RETRY).await;
+    }
+}
+
+async fn handle_conn(
+    stream: impl AsyncRead + AsyncWrite + Send + 'static,
+    state: AppState,
+) -> Result<(), String> {
+    let (mut reader, mut writer) = tokio::io::split(stream);
     let (msg_tx, mut msg_rx) = mpsc::channel::<Vec<u8>>(32);
 
     let conn_id = {
@@ -96,7 +125,7 @@ async fn handle_conn(stream: TcpStream, state: AppState) -> Result<(), String> {
 
 async fn read_messages(
     conn_id: ConnectionId,
-    reader: &mut tokio::net::tcp::OwnedReadHalf,
+    reader: &mut (impl AsyncRead + Unpin),
     state: &AppState,
 ) -> Result<(), String> {
     let mut magic = [0u8; 4];
@@ -188,6 +217,8 @@ async fn read_messages(
                     let mut guard = state.inner.lock().await;
                     if let Some(pending) = guard.pending_snapshots.get_mut(&reply.snapshot_id) {
                         pending.pending_conn_ids.remove(&conn_id);
+                        let skew_ms = pending.requested_at.elapsed().as_millis() as u64;
+                        pending.reply_skew_ms.insert(conn_id, skew_ms);
                         pending.replies.insert(conn_id, reply);
                         if pending.pending_conn_ids.is_empty() {
                             Some(pending.notify.clone())
@@ -87,3 +87,15 @@ fn is_hop_by_hop(lowercase_name: &str) -> bool {
             | "upgrade"
     )
 }
+
+/// The value of `key` in a raw query string, if present and numeric.
+pub fn parse_query_u64(query: &str, key: &str) -> Option<u64> {
+    query.split('&').find_map(|part| {
+        let (k, v) = part.split_once('=')?;
+        if k == key {
+            v.parse::<u64>().ok()
+        } else {
+            None
+        }
+    })
+}
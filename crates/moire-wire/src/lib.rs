@@ -40,6 +40,7 @@ impl std::error::Error for FrameCodecError {}
 pub enum WireError {
     Frame(FrameCodecError),
     Json(String),
+    Msgpack(String),
     MagicMismatch { expected: u32, actual: u32 },
 }
 
@@ -48,6 +49,7 @@ impl fmt::Display for WireError {
         match self {
             Self::Frame(err) => write!(f, "{err}"),
             Self::Json(err) => write!(f, "{err}"),
+            Self::Msgpack(err) => write!(f, "{err}"),
             Self::MagicMismatch { expected, actual } => {
                 write!(
                     f,
@@ -169,6 +171,23 @@ pub fn decode_protocol_magic(bytes: [u8; 4]) -> Result<(), WireError> {
 pub struct SnapshotRequest {
     pub snapshot_id: i64,
     pub timeout_ms: i64,
+    /// Encoding the reply is asked for in. JSON when absent, and from
+    /// processes that predate the field.
+    #[facet(skip_unless_truthy)]
+    pub encoding: Option<SnapshotEncoding>,
+}
+
+// r[impl wire.snapshot-encoding]
+/// Payload encoding of a `SnapshotReply` frame. Every other message is JSON.
+#[derive(Facet, Clone, Copy, Debug, PartialEq, Eq)]
+#[repr(u8)]
+#[facet(rename_all = "snake_case")]
+pub enum SnapshotEncoding {
+    Json,
+    /// MessagePack: self-describing like JSON, so optional and unknown
+    /// fields behave the same, but several times smaller and faster to
+    /// produce for large processes.
+    Msgpack,
 }
 
 #[derive(Facet)]
@@ -178,6 +197,10 @@ pub struct SnapshotReply {
     pub ptime_now_ms: u64,
     #[facet(skip_unless_truthy)]
     pub snapshot: Option<Snapshot>,
+    /// Sections the process could not assemble before the request deadline
+    /// (`runtime_db`, `workers`). Without `runtime_db` there is no snapshot.
+    #[facet(skip_unless_truthy)]
+    pub timed_out_sections: Option<Vec<String>>,
 }
 
 #[derive(Facet)]
@@ -225,11 +248,16 @@ pub fn encode_client_message_default(message: &ClientMessage) -> Result<Vec<u8>,
     encode_client_message(message, DEFAULT_MAX_FRAME_BYTES)
 }
 
+/// Decodes a client frame. A payload that does not start with `{` is a
+/// MessagePack `SnapshotReply`.
 pub fn decode_client_message(
     frame: &[u8],
     max_payload_bytes: usize,
 ) -> Result<ClientMessage, WireError> {
     let payload = decode_frame(frame, max_payload_bytes)?;
+    if payload.first().is_some_and(|&b| b != b'{') {
+        return facet_msgpack::from_slice(payload).map_err(|e| WireError::Msgpack(e.to_string()));
+    }
     facet_json::from_slice(payload).map_err(|e| WireError::Json(e.to_string()))
 }
 
@@ -326,11 +354,17 @@ mod tests {
                 scopes: vec![],
                 edges: vec![],
                 events: vec![],
+                workers: vec![],
+                long_polls: vec![],
+                rpc_methods: vec![],
+                coverage: None,
+                runtime_stats: None,
             }),
+            timed_out_sections: None,
         }));
         assert_eq!(
             json,
-            r#"{"snapshot_reply":{"snapshot_id":7,"ptime_now_ms":1234,"snapshot":{"entities":[],"scopes":[],"edges":[],"events":[]}}}"#
+            r#"{"snapshot_reply":{"snapshot_id":7,"ptime_now_ms":1234,"snapshot":{"entities":[],"scopes":[],"edges":[],"events":[],"workers":[],"long_polls":[],"rpc_methods":[]}}}"#
         );
     }
 
@@ -377,11 +411,44 @@ mod tests {
         let json = server_payload_json(&ServerMessage::SnapshotRequest(SnapshotRequest {
             snapshot_id: 7,
             timeout_ms: 5000,
+            encoding: None,
         }));
         assert_eq!(
             json,
             r#"{"snapshot_request":{"snapshot_id":7,"timeout_ms":5000}}"#
         );
+
+        let json = server_payload_json(&ServerMessage::SnapshotRequest(SnapshotRequest {
+            snapshot_id: 7,
+            timeout_ms: 5000,
+            encoding: Some(SnapshotEncoding::Msgpack),
+        }));
+        assert_eq!(
+            json,
+            r#"{"snapshot_request":{"snapshot_id":7,"timeout_ms":5000,"encoding":"msgpack"}}"#
+        );
+    }
+
+    #[test]
+    fn msgpack_snapshot_reply_decodes_like_json() {
+        let payload = facet_msgpack::to_vec(&ClientMessage::SnapshotReply(SnapshotReply {
+            snapshot_id: 7,
+            ptime_now_ms: 1234,
+            snapshot: None,
+            timed_out_sections: Some(vec![String::from("runtime_db")]),
+        }))
+        .expect("msgpack reply should encode");
+        let frame = encode_frame_default(&payload).expect("frame should encode");
+        let Ok(ClientMessage::SnapshotReply(reply)) = decode_client_message_default(&frame) else {
+            panic!("msgpack frame should decode as a snapshot reply");
+        };
+        assert_eq!(reply.snapshot_id, 7);
+        assert_eq!(reply.ptime_now_ms, 1234);
+        assert!(reply.snapshot.is_none());
+        assert_eq!(
+            reply.timed_out_sections,
+            Some(vec![String::from("runtime_db")])
+        );
     }
 
     #[test]
@@ -40,22 +40,57 @@
 //! MOIRE_DASHBOARD=127.0.0.1:9119 ./your-binary
 //! ```
 //!
+//! Or, on Unix, have the process wait for a collector to pull snapshots from
+//! a socket instead of pushing them:
+//!
+//! ```text
+//! MOIRE_SOCKET=/run/user/1000/your-binary.moire ./your-binary
+//! ```
+//!
+//! A collector on another host can pull over TCP, with a token it sends
+//! first, or have snapshots pushed to it periodically:
+//!
+//! ```text
+//! MOIRE_SOCKET=tcp:0.0.0.0:9120 MOIRE_SOCKET_TOKEN=... ./your-binary
+//! MOIRE_PUSH=collector.internal:9121 MOIRE_PUSH_SECS=30 ./your-binary
+//! ```
+//!
+//! To have an unattended process dump its state when it stops making
+//! progress (no [`liveness::heartbeat`] beat, or no instrumented future
+//! completing, for 30 seconds):
+//!
+//! ```text
+//! MOIRE_WATCHDOG=30 MOIRE_WATCHDOG_DIR=/var/tmp ./your-binary
+//! ```
+//!
+//! To scrub RPC payloads, command lines and the like before anything leaves
+//! the process, install a redactor, such as the built-in [`HashStrings`]:
+//!
+//! ```rust,no_run
+//! moire::set_redactor(moire::HashStrings);
+//! ```
+//!
 //! # Cargo features
 //!
 //! | Feature | Effect |
 //! |---------|--------|
 //! | *(default, none)* | All wrappers compile to pass-throughs; no instrumentation overhead. |
-//! | `diagnostics` | Enables backtrace capture, entity tracking, and live dashboard push. |
+//! | `diagnostics` | Enables backtrace capture, entity tracking, and live dashboard push. Collection can still be switched off and on at runtime with [`disable`]/[`enable`]. |
+//! | `http` | Native only. Implies `diagnostics` and adds [`http_router`]/[`serve_http`], serving `/snapshot.json`, `/graph.json`, `/candidates.json` and Prometheus `/metrics`; set `MOIRE_HTTP=<addr>` to serve them without code changes. |
+//! | `tracing` | Native only. Implies `diagnostics`; set `MOIRE_LOG_CANDIDATES=<seconds>` to have wait cycles reported as `tracing` events with target `moire`, for processes whose logs are watched but which no dashboard sees. |
 //!
 //! Without `diagnostics`, setting `MOIRE_DASHBOARD` emits a warning and does not connect.
 //!
 //! # What is instrumented
 //!
-//! - **Tasks**: [`task::JoinSet`]
+//! - **Tasks**: [`task::JoinSet`], [`task::scope`] (tasks grouped under the task that opened the scope)
 //! - **Channels**: [`sync::mpsc`], [`sync::broadcast`], [`sync::oneshot`], [`sync::watch`]
-//! - **Synchronization**: [`sync::Mutex`], [`sync::RwLock`], [`sync::Semaphore`], [`sync::Notify`], [`sync::OnceCell`]
+//! - **Synchronization**: [`sync::Mutex`], [`sync::RwLock`], [`sync::Semaphore`], [`sync::Notify`], [`sync::OnceCell`], [`sync::Barrier`]
+//! - **Actors**: [`actor::spawn_actor`], [`actor::spawn_unbounded_actor`] (mailbox + driver task as one composite node)
 //! - **Processes**: [`process::Command`]
+//! - **Network**: [`net::TcpStream`], [`net::TcpListener`], [`net::UdpSocket`]
 //! - **Time**: [`time::sleep`], [`time::interval`]
+//! - **Liveness**: [`liveness::heartbeat`], [`liveness::watchdog`]
 //! - **RPC**: [`rpc::rpc_request`], [`rpc::rpc_response_for`] (used by Roam)
 //!
 //! # Platform backends
@@ -63,7 +98,7 @@
 //! This crate re-exports the right backend for the current target:
 //!
 //! - **native** (`not(target_arch = "wasm32")`) → `moire-tokio`
-//! - **wasm32** → `moire-wasm` (all instrumentation is a no-op; API surface is identical)
+//! - **wasm32** → `moire-wasm` (tasks and instrumented futures only; API surface is identical)
 
 #[cfg(feature = "diagnostics")]
 pub use moire_macros::instrument;
@@ -79,6 +114,49 @@ pub mod __internal {
     pub use moire_wasm::__internal::*;
 }
 
+/// Names a future after its own source text and records where it was named.
+///
+/// `named!(fetch_user(id))` registers a `future` entity labelled
+/// `fetch_user(id)`; `named!(fetch_user(id), "fetch user")` picks the label.
+/// Either way the entity carries the crate, module, file and line of the
+/// macro call. Without `diagnostics` the macro expands to the future itself.
+// r[impl api.named-macro]
+#[cfg(feature = "diagnostics")]
+#[macro_export]
+macro_rules! named {
+    ($fut:expr $(,)?) => {
+        $crate::named!($fut, stringify!($fut))
+    };
+    ($fut:expr, $name:expr $(,)?) => {
+        $crate::__internal::instrument_future_at(
+            $name,
+            $fut,
+            env!("CARGO_PKG_NAME"),
+            module_path!(),
+            file!(),
+            line!(),
+        )
+    };
+}
+
+/// Names a future after its own source text and records where it was named.
+///
+/// `named!(fetch_user(id))` registers a `future` entity labelled
+/// `fetch_user(id)`; `named!(fetch_user(id), "fetch user")` picks the label.
+/// Either way the entity carries the crate, module, file and line of the
+/// macro call. Without `diagnostics` the macro expands to the future itself.
+#[cfg(not(feature = "diagnostics"))]
+#[macro_export]
+macro_rules! named {
+    ($fut:expr $(,)?) => {
+        $fut
+    };
+    ($fut:expr, $name:expr $(,)?) => {{
+        let _ = $name;
+        $fut
+    }};
+}
+
 // r[impl api.backend.native]
 #[cfg(not(target_arch = "wasm32"))]
 pub use moire_tokio::*;
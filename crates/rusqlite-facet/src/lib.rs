@@ -45,6 +45,10 @@ pub enum Error {
         sql: String,
         source: Box<Error>,
     },
+    WithQueryPlan {
+        plan: String,
+        source: Box<Error>,
+    },
     OutOfRange {
         field: String,
         source: i128,
@@ -99,6 +103,9 @@ impl core::fmt::Display for Error {
             Error::WithSqlContext { sql, source } => {
                 write!(f, "{source} (sql: {sql})")
             }
+            Error::WithQueryPlan { plan, source } => {
+                write!(f, "{source} (query plan: {plan})")
+            }
             Error::OutOfRange {
                 field,
                 source,
@@ -131,6 +138,7 @@ impl std::error::Error for Error {
             Error::Alloc(err) => Some(err),
             Error::ShapeMismatch(err) => Some(err),
             Error::WithSqlContext { source, .. } => Some(source),
+            Error::WithQueryPlan { source, .. } => Some(source),
             _ => None,
         }
     }
@@ -215,8 +223,30 @@ pub trait StatementFacetExt {
     fn facet_query_row<T: Facet<'static>, P: Facet<'static>>(&mut self, params: P) -> Result<T>;
 }
 
+/// How [`ConnectionFacetExt`] methods prepare and report statements. The
+/// default prepares every statement afresh and attaches only the SQL to
+/// errors.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct FacetOptions {
+    /// Reuse prepared statements from the connection's statement cache, keyed
+    /// by SQL. The cache holds 16 statements unless resized with
+    /// `Connection::set_prepared_statement_cache_capacity`.
+    pub cache_statements: bool,
+    /// When a statement fails, run `EXPLAIN QUERY PLAN` on it and attach the
+    /// plan to the error.
+    pub explain_on_error: bool,
+}
+
+/// A connection whose [`ConnectionFacetExt`] methods follow [`FacetOptions`].
+/// Obtained with [`ConnectionFacetExt::facet_with`].
+pub struct FacetConnection<'conn> {
+    conn: &'conn Connection,
+    options: FacetOptions,
+}
+
 pub trait ConnectionFacetExt {
     fn facet_prepare_cached(&self, sql: &str) -> rusqlite::Result<rusqlite::CachedStatement<'_>>;
+    fn facet_with(&self, options: FacetOptions) -> FacetConnection<'_>;
     fn facet_execute_ref<'p, P: Facet<'p> + ?Sized>(
         &self,
         sql: &str,
@@ -379,13 +409,20 @@ impl ConnectionFacetExt for Connection {
         self.prepare_cached(sql)
     }
 
+    fn facet_with(&self, options: FacetOptions) -> FacetConnection<'_> {
+        FacetConnection {
+            conn: self,
+            options,
+        }
+    }
+
     fn facet_execute_ref<'p, P: Facet<'p> + ?Sized>(
         &self,
         sql: &str,
         params: &'p P,
     ) -> Result<usize> {
-        let mut stmt = self.prepare(sql)?;
-        with_sql_context(sql, stmt.facet_execute_ref(params))
+        self.facet_with(FacetOptions::default())
+            .facet_execute_ref(sql, params)
     }
 
     fn facet_query_ref<'p, T: Facet<'static>, P: Facet<'p> + ?Sized>(
@@ -393,8 +430,8 @@ impl ConnectionFacetExt for Connection {
         sql: &str,
         params: &'p P,
     ) -> Result<Vec<T>> {
-        let mut stmt = self.prepare(sql)?;
-        with_sql_context(sql, stmt.facet_query_ref::<T, P>(params))
+        self.facet_with(FacetOptions::default())
+            .facet_query_ref::<T, P>(sql, params)
     }
 
     fn facet_query_optional_ref<'p, T: Facet<'static>, P: Facet<'p> + ?Sized>(
@@ -402,8 +439,8 @@ impl ConnectionFacetExt for Connection {
         sql: &str,
         params: &'p P,
     ) -> Result<Option<T>> {
-        let mut stmt = self.prepare(sql)?;
-        with_sql_context(sql, stmt.facet_query_optional_ref::<T, P>(params))
+        self.facet_with(FacetOptions::default())
+            .facet_query_optional_ref::<T, P>(sql, params)
     }
 
     fn facet_query_one_ref<'p, T: Facet<'static>, P: Facet<'p> + ?Sized>(
@@ -411,13 +448,13 @@ impl ConnectionFacetExt for Connection {
         sql: &str,
         params: &'p P,
     ) -> Result<T> {
-        let mut stmt = self.prepare(sql)?;
-        with_sql_context(sql, stmt.facet_query_one_ref::<T, P>(params))
+        self.facet_with(FacetOptions::default())
+            .facet_query_one_ref::<T, P>(sql, params)
     }
 
     fn facet_execute<P: Facet<'static>>(&self, sql: &str, params: P) -> Result<usize> {
-        let mut stmt = self.prepare(sql)?;
-        with_sql_context(sql, stmt.facet_execute(params))
+        self.facet_with(FacetOptions::default())
+            .facet_execute(sql, params)
     }
 
     fn facet_query<T: Facet<'static>, P: Facet<'static>>(
@@ -425,8 +462,8 @@ impl ConnectionFacetExt for Connection {
         sql: &str,
         params: P,
     ) -> Result<Vec<T>> {
-        let mut stmt = self.prepare(sql)?;
-        with_sql_context(sql, stmt.facet_query::<T, P>(params))
+        self.facet_with(FacetOptions::default())
+            .facet_query::<T, P>(sql, params)
     }
 
     fn facet_query_optional<T: Facet<'static>, P: Facet<'static>>(
@@ -434,8 +471,8 @@ impl ConnectionFacetExt for Connection {
         sql: &str,
         params: P,
     ) -> Result<Option<T>> {
-        let mut stmt = self.prepare(sql)?;
-        with_sql_context(sql, stmt.facet_query_optional::<T, P>(params))
+        self.facet_with(FacetOptions::default())
+            .facet_query_optional::<T, P>(sql, params)
     }
 
     fn facet_query_one<T: Facet<'static>, P: Facet<'static>>(
@@ -443,11 +480,127 @@ impl ConnectionFacetExt for Connection {
         sql: &str,
         params: P,
     ) -> Result<T> {
-        let mut stmt = self.prepare(sql)?;
-        with_sql_context(sql, stmt.facet_query_one::<T, P>(params))
+        self.facet_with(FacetOptions::default())
+            .facet_query_one::<T, P>(sql, params)
     }
 }
 
+impl FacetConnection<'_> {
+    /// Prepares `sql` as the options say and runs `f` on it, attaching the
+    /// SQL and, if asked for, the query plan to any error `f` returns.
+    fn run<R>(&self, sql: &str, f: impl FnOnce(&mut Statement<'_>) -> Result<R>) -> Result<R> {
+        let result = if self.options.cache_statements {
+            let mut stmt = self.conn.prepare_cached(sql)?;
+            f(&mut stmt)
+        } else {
+            let mut stmt = self.conn.prepare(sql)?;
+            f(&mut stmt)
+        };
+        let error = match with_sql_context(sql, result) {
+            Ok(value) => return Ok(value),
+            Err(error) => error,
+        };
+        match self
+            .options
+            .explain_on_error
+            .then(|| query_plan(self.conn, sql))
+            .flatten()
+        {
+            Some(plan) => Err(Error::WithQueryPlan {
+                plan,
+                source: Box::new(error),
+            }),
+            None => Err(error),
+        }
+    }
+}
+
+impl ConnectionFacetExt for FacetConnection<'_> {
+    fn facet_prepare_cached(&self, sql: &str) -> rusqlite::Result<rusqlite::CachedStatement<'_>> {
+        self.conn.prepare_cached(sql)
+    }
+
+    fn facet_with(&self, options: FacetOptions) -> FacetConnection<'_> {
+        FacetConnection {
+            conn: self.conn,
+            options,
+        }
+    }
+
+    fn facet_execute_ref<'p, P: Facet<'p> + ?Sized>(
+        &self,
+        sql: &str,
+        params: &'p P,
+    ) -> Result<usize> {
+        self.run(sql, |stmt| stmt.facet_execute_ref(params))
+    }
+
+    fn facet_query_ref<'p, T: Facet<'static>, P: Facet<'p> + ?Sized>(
+        &self,
+        sql: &str,
+        params: &'p P,
+    ) -> Result<Vec<T>> {
+        self.run(sql, |stmt| stmt.facet_query_ref::<T, P>(params))
+    }
+
+    fn facet_query_optional_ref<'p, T: Facet<'static>, P: Facet<'p> + ?Sized>(
+        &self,
+        sql: &str,
+        params: &'p P,
+    ) -> Result<Option<T>> {
+        self.run(sql, |stmt| stmt.facet_query_optional_ref::<T, P>(params))
+    }
+
+    fn facet_query_one_ref<'p, T: Facet<'static>, P: Facet<'p> + ?Sized>(
+        &self,
+        sql: &str,
+        params: &'p P,
+    ) -> Result<T> {
+        self.run(sql, |stmt| stmt.facet_query_one_ref::<T, P>(params))
+    }
+
+    fn facet_execute<P: Facet<'static>>(&self, sql: &str, params: P) -> Result<usize> {
+        self.run(sql, |stmt| stmt.facet_execute(params))
+    }
+
+    fn facet_query<T: Facet<'static>, P: Facet<'static>>(
+        &self,
+        sql: &str,
+        params: P,
+    ) -> Result<Vec<T>> {
+        self.run(sql, |stmt| stmt.facet_query::<T, P>(params))
+    }
+
+    fn facet_query_optional<T: Facet<'static>, P: Facet<'static>>(
+        &self,
+        sql: &str,
+        params: P,
+    ) -> Result<Option<T>> {
+        self.run(sql, |stmt| stmt.facet_query_optional::<T, P>(params))
+    }
+
+    fn facet_query_one<T: Facet<'static>, P: Facet<'static>>(
+        &self,
+        sql: &str,
+        params: P,
+    ) -> Result<T> {
+        self.run(sql, |stmt| stmt.facet_query_one::<T, P>(params))
+    }
+}
+
+/// The `detail` column of `EXPLAIN QUERY PLAN`, one step per line. `None` if
+/// the plan itself cannot be produced, e.g. because the SQL does not parse.
+fn query_plan(conn: &Connection, sql: &str) -> Option<String> {
+    let mut stmt = conn.prepare(&format!("EXPLAIN QUERY PLAN {sql}")).ok()?;
+    // Parameters stay unbound (NULL); the plan does not depend on their values.
+    let mut rows = stmt.raw_query();
+    let mut details = Vec::new();
+    while let Some(row) = rows.next().ok()? {
+        details.push(row.get::<_, String>("detail").ok()?);
+    }
+    Some(details.join("\n"))
+}
+
 fn with_sql_context<T>(sql: &str, result: Result<T>) -> Result<T> {
     result.map_err(|source| Error::WithSqlContext {
         sql: sql.to_string(),
@@ -487,6 +640,29 @@ fn bind_facet_params_impl(
         return bind_list_like_params(stmt, peek);
     }
 
+    // A top-level option binds its value, or a single NULL.
+    if let Ok(option) = peek.into_option() {
+        return match option.value() {
+            Some(inner) => bind_facet_params_impl(stmt, inner, inner.shape()),
+            None => bind_positional_values(stmt, vec![SqlValue::Null]),
+        };
+    }
+
+    match &peek.shape().ty {
+        Type::User(UserType::Struct(s)) if s.kind == StructKind::Struct => {}
+        // A unit struct binds no parameters.
+        Type::User(UserType::Struct(s)) if s.kind == StructKind::Unit => {
+            return bind_positional_values(stmt, Vec::new());
+        }
+        Type::User(UserType::Struct(s))
+            if matches!(s.kind, StructKind::Tuple | StructKind::TupleStruct) =>
+        {
+            return bind_tuple_params(stmt, peek, shape);
+        }
+        // Anything else is a single positional parameter.
+        _ => return bind_positional_values(stmt, vec![peek_to_sql_value(peek, "param")?]),
+    }
+
     let struct_peek = peek
         .into_struct()
         .map_err(|_| Error::NotAStruct { shape })?;
@@ -575,7 +751,26 @@ fn bind_list_like_params(stmt: &mut Statement<'_>, peek: Peek<'_, '_>) -> Result
     for value in list_like.iter() {
         values.push(peek_to_sql_value(value, "positional_param")?);
     }
+    bind_positional_values(stmt, values)
+}
+
+/// Binds tuple and tuple-struct fields positionally, in declaration order.
+fn bind_tuple_params(
+    stmt: &mut Statement<'_>,
+    peek: Peek<'_, '_>,
+    shape: &'static Shape,
+) -> Result<()> {
+    let struct_peek = peek
+        .into_struct()
+        .map_err(|_| Error::NotAStruct { shape })?;
+    let mut values = Vec::new();
+    for (field, value) in struct_peek.fields() {
+        values.push(peek_to_sql_value(value, field.name)?);
+    }
+    bind_positional_values(stmt, values)
+}
 
+fn bind_positional_values(stmt: &mut Statement<'_>, values: Vec<SqlValue>) -> Result<()> {
     let mut used = vec![false; values.len()];
     let mut positional_cursor = 0usize;
     for param_index in 1..=stmt.parameter_count() {
@@ -821,7 +1016,7 @@ where
 
 #[cfg(test)]
 mod tests {
-    use super::{ConnectionFacetExt, Error, StatementFacetExt};
+    use super::{ConnectionFacetExt, Error, FacetOptions, StatementFacetExt};
     use facet::Facet;
     use rusqlite::Connection;
 
@@ -943,6 +1138,66 @@ mod tests {
         );
     }
 
+    #[test]
+    fn facet_query_accepts_tuple_and_top_level_option_params() {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute(
+            "CREATE TABLE items (conn_id INTEGER NOT NULL, label TEXT)",
+            (),
+        )
+        .unwrap();
+
+        let mut insert = conn
+            .prepare("INSERT INTO items (conn_id, label) VALUES (?1, ?2)")
+            .unwrap();
+        insert
+            .facet_execute((1_i64, Some("alpha".to_string())))
+            .unwrap();
+        insert.facet_execute((2_i64, None::<String>)).unwrap();
+
+        let mut by_label = conn
+            .prepare("SELECT conn_id, label FROM items WHERE label IS ?1")
+            .unwrap();
+        let rows = by_label
+            .facet_query::<MaybeConn, _>(None::<String>)
+            .unwrap();
+        assert_eq!(
+            rows,
+            vec![MaybeConn {
+                conn_id: 2,
+                label: None
+            }]
+        );
+        let rows = by_label
+            .facet_query::<MaybeConn, _>(Some("alpha".to_string()))
+            .unwrap();
+        assert_eq!(rows.len(), 1);
+
+        #[derive(Facet)]
+        struct ConnId(i64);
+
+        let mut by_id = conn
+            .prepare("SELECT conn_id, label FROM items WHERE conn_id = ?")
+            .unwrap();
+        let row = by_id.facet_query_one::<MaybeConn, _>(ConnId(1)).unwrap();
+        assert_eq!(row.label.as_deref(), Some("alpha"));
+        assert!(matches!(
+            by_id.facet_query::<MaybeConn, _>((1_i64, 2_i64)),
+            Err(Error::UnusedPositionalParams {
+                provided: 2,
+                used: 1
+            })
+        ));
+
+        #[derive(Facet)]
+        struct NoParams;
+
+        let mut all = conn
+            .prepare("SELECT conn_id, label FROM items ORDER BY conn_id")
+            .unwrap();
+        assert_eq!(all.facet_query::<MaybeConn, _>(NoParams).unwrap().len(), 2);
+    }
+
     #[test]
     fn facet_query_ref_accepts_slice_params() {
         let conn = Connection::open_in_memory().unwrap();
@@ -1213,6 +1468,47 @@ mod tests {
         assert_eq!(row, IdRow { id: 5 });
     }
 
+    #[test]
+    fn facet_with_caches_statements_and_attaches_query_plans() {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute("CREATE TABLE ids (id INTEGER NOT NULL)", ())
+            .unwrap();
+        conn.execute("INSERT INTO ids (id) VALUES (5)", ()).unwrap();
+
+        #[derive(Facet)]
+        struct QueryId {
+            id: i64,
+        }
+
+        #[derive(Debug, Facet, PartialEq)]
+        struct IdRow {
+            id: i64,
+        }
+
+        let sql = "SELECT id FROM ids WHERE id = :id";
+        let facet = conn.facet_with(FacetOptions {
+            cache_statements: true,
+            explain_on_error: true,
+        });
+        for _ in 0..3 {
+            let row = facet
+                .facet_query_one::<IdRow, _>(sql, QueryId { id: 5 })
+                .unwrap();
+            assert_eq!(row, IdRow { id: 5 });
+        }
+
+        let err = facet
+            .facet_query_one::<IdRow, _>(sql, QueryId { id: 6 })
+            .unwrap_err();
+        match err {
+            Error::WithQueryPlan { plan, source } => {
+                assert!(plan.contains("SCAN"), "plan: {plan}");
+                assert!(matches!(*source, Error::WithSqlContext { .. }));
+            }
+            _ => panic!("expected query plan wrapper"),
+        }
+    }
+
     #[test]
     fn transparent_wrapper_works_for_params_and_rows() {
         let conn = Connection::open_in_memory().unwrap();
@@ -1,6 +1,59 @@
 // @generated by `cargo run -p moire-web --bin gen_frontend_types`
 // Do not edit by hand.
 
+/**
+ * Response for `POST /api/history`.
+ */
+export interface HistoryQueryResponse {
+  /**
+   * Recorded snapshots in the range, oldest first.
+   */
+  snapshots: HistorySnapshotSummary[];
+}
+
+/**
+ * One snapshot persisted by the history recorder.
+ */
+export interface HistorySnapshotSummary {
+  /**
+   * Storage id, stable across server restarts; fetch the full snapshot with
+   * `GET /api/history/{history_id}`.
+   */
+  history_id: number;
+  /**
+   * Server-side snapshot id at capture time; restarts from 1 with the server.
+   */
+  snapshot_id: number;
+  captured_at_unix_ms: number;
+  process_count: number;
+  /**
+   * Wait graph nodes recorded for this snapshot.
+   */
+  node_count: number;
+  /**
+   * Wait graph edges recorded for this snapshot.
+   */
+  edge_count: number;
+  /**
+   * Analysis findings recorded for this snapshot.
+   */
+  findings: AnalysisFinding[];
+}
+
+/**
+ * Request body for `POST /api/history`. Bounds are inclusive Unix epoch milliseconds.
+ */
+export interface HistoryQueryRequest {
+  from_unix_ms?: number;
+  to_unix_ms?: number;
+  limit?: number;
+  /**
+   * Only snapshots in which a task with this logical id was alive, in any
+   * process generation.
+   */
+  logical_id?: string;
+}
+
 /**
  * Response for `POST /api/source/previews`.
  */
@@ -174,6 +227,11 @@ export interface SnapshotCutResponse {
    * Wall-clock milliseconds (Unix epoch) when this cut was assembled server-side.
    */
   captured_at_unix_ms: number;
+  /**
+   * Largest `skew_ms` among `processes`: how far apart in time the
+   * process snapshots of this cut may have been taken.
+   */
+  max_skew_ms?: number;
   /**
    * Processes that replied within the timeout window.
    */
@@ -190,8 +248,128 @@ export interface SnapshotCutResponse {
    * Deduplicated frame catalog keyed by frame_id.
    */
   frames: SnapshotFrameRecord[];
+  /**
+   * Findings reported by server-side analyses (deadlock detection and any
+   * registered custom analyses).
+   */
+  findings?: AnalysisFinding[];
+  /**
+   * Operator annotations whose target is a node, task or finding in this
+   * snapshot.
+   */
+  annotations?: Annotation[];
+  /**
+   * Bounded channels and semaphores that have been running at capacity
+   * over recent snapshots.
+   */
+  sizing_hints?: SizingHint[];
+  /**
+   * Version of the detection config `findings` were computed with; unset
+   * when the collector runs with built-in defaults.
+   */
+  detection_config_version?: string;
+  /**
+   * Why the server-side analyses could not run over this snapshot, in
+   * which case `findings` is empty.
+   */
+  analysis_error?: string;
+}
+
+/**
+ * A note an operator attached to a node, task or finding.
+ */
+export interface Annotation {
+  annotation_id: number;
+  /**
+   * A wait graph node key (`process_id::entity_id`), a task logical id, or
+   * a finding fingerprint.
+   */
+  target: string;
+  note: string;
+  author?: string;
+  created_at_unix_ms: number;
+}
+
+/**
+ * One result reported by a server-side analysis over a snapshot.
+ */
+export interface AnalysisFinding {
+  /**
+   * Name of the analysis that produced this finding.
+   */
+  analysis: string;
+  severity: FindingSeverity;
+  title: string;
+  /**
+   * Why the analysis flagged this.
+   */
+  rationale: string;
+  /**
+   * Entities the finding is about.
+   */
+  subjects?: FindingSubject[];
+  /**
+   * How the analysis ranked the finding, for analyses that score their
+   * candidates.
+   */
+  score?: ScoreBreakdown;
+  /**
+   * Suggested next steps, for analyses that recognize the pattern.
+   */
+  hints?: string[];
+}
+
+export type FindingSeverity = "info" | "warning" | "critical";
+
+/**
+ * Every rule that contributed to a candidate's score, in the order applied.
+ */
+export interface ScoreBreakdown {
+  contributions?: ScoreContribution[];
+}
+
+export interface ScoreContribution {
+  /**
+   * Stable rule id, e.g. `wait_age`.
+   */
+  rule: string;
+  points: number;
+}
+
+export interface FindingSubject {
+  process_id: ProcessId;
+  entity_id: EntityId;
+}
+
+/**
+ * Utilization of one bounded resource over the collector's recent snapshots.
+ */
+export interface SizingHint {
+  process_id: ProcessId;
+  entity_id: EntityId;
+  resource: SizedResource;
+  /**
+   * Channel buffer size or semaphore permit count.
+   */
+  capacity: number;
+  /**
+   * Queue depth or permits in use, 99th percentile over the samples.
+   */
+  p99_used: number;
+  max_used: number;
+  /**
+   * Snapshots the statistics are computed from.
+   */
+  samples: number;
+  /**
+   * Human-readable advice, e.g. "bounded(16) but p99 depth=16 — consider
+   * a larger buffer or a faster consumer".
+   */
+  hint: string;
 }
 
+export type SizedResource = "mpsc_channel" | "semaphore";
+
 export interface SnapshotBacktrace {
   backtrace_id: BacktraceId;
   frame_ids: FrameId[];
@@ -203,10 +381,113 @@ export interface TimedOutProcess {
   process_id: ProcessId;
   process_name: string;
   pid: number;
+  /**
+   * Sections the process reported it could not assemble in time. Absent
+   * when the process did not reply at all.
+   */
+  timed_out_sections?: string[];
 }
 
 export type ProcessId = string;
 
+/**
+ * Headline statistics of a snapshot, for dashboards that do not need the
+ * whole snapshot.
+ */
+export interface SnapshotSummaryResponse {
+  snapshot_id: number;
+  captured_at_unix_ms: number;
+  processes: ProcessSummary[];
+  timed_out_processes?: TimedOutProcess[];
+}
+
+/**
+ * One process's headline statistics. Lists hold at most the requested
+ * number of entries, worst first.
+ */
+export interface ProcessSummary {
+  process_id: ProcessId;
+  process_name: string;
+  pid: number;
+  entity_count: number;
+  tasks: TaskStateCounts;
+  /**
+   * Longest-standing `waiting_on` edges.
+   */
+  worst_waits?: WaitSummary[];
+  /**
+   * Locks with the most waiters, then the most completed holds.
+   */
+  busiest_locks?: LockSummary[];
+  /**
+   * RPC methods by their slowest call, in flight or completed.
+   */
+  slowest_rpc_methods?: RpcMethodSummary[];
+}
+
+/**
+ * Live spawned tasks by state. A task counts under the first state that
+ * applies, in field order.
+ */
+export interface TaskStateCounts {
+  /**
+   * Woken and not polled since (see `wake_to_poll_gap_ms`).
+   */
+  woken: number;
+  /**
+   * Waiting on another entity.
+   */
+  waiting: number;
+  /**
+   * Waiting, but marked idle at instrumentation time.
+   */
+  idle: number;
+  /**
+   * None of the above: running or about to be.
+   */
+  other: number;
+}
+
+export interface WaitSummary {
+  waiter: EntityId;
+  waiter_name: string;
+  target: EntityId;
+  target_name: string;
+  /**
+   * How long the wait had lasted when the snapshot was taken.
+   */
+  waited_ms: number;
+}
+
+export interface LockSummary {
+  entity_id: EntityId;
+  name: string;
+  /**
+   * Futures waiting to acquire it.
+   */
+  waiters: number;
+  /**
+   * Futures holding it.
+   */
+  holders: number;
+  hold_count: number;
+  avg_hold_ms: number;
+}
+
+export interface RpcMethodSummary {
+  service_name: string;
+  method_name: string;
+  /**
+   * Requests of this method in the snapshot, in flight or completed.
+   */
+  calls: number;
+  in_flight: number;
+  /**
+   * Longest call: elapsed time so far for calls in flight.
+   */
+  slowest_ms: number;
+}
+
 /**
  * Per-process envelope inside a snapshot cut.
  */
@@ -215,10 +496,41 @@ export interface ProcessSnapshotView {
   process_name: string;
   pid: number;
   ptime_now_ms: number;
+  /**
+   * How long after the cut was requested this process's snapshot arrived.
+   * Snapshots of one cut are taken up to this far apart, so waits compared
+   * across processes are only this precise. Unset when unknown.
+   */
+  skew_ms?: number;
   snapshot: Snapshot;
   scope_entity_links?: ScopeEntityLink[];
+  /**
+   * Sections left empty in `snapshot` because the process ran out of time
+   * assembling them.
+   */
+  timed_out_sections?: string[];
+  /**
+   * Where `snapshot` breaks the data model contract. Empty for
+   * well-formed processes.
+   */
+  violations?: SnapshotViolation[];
 }
 
+/**
+ * One way a [`Snapshot`] breaks the data model contract.
+ */
+export interface SnapshotViolation {
+  kind: SnapshotViolationKind;
+  /**
+   * Offending object: an entity, scope or event id, or `src -> dst` for an
+   * edge.
+   */
+  subject: string;
+  detail: string;
+}
+
+export type SnapshotViolationKind = "id_format" | "duplicate_id" | "backtrace_id" | "dangling_edge" | "self_edge" | "duplicate_edge" | "invalid_json" | "custom_entity";
+
 export interface ScopeEntityLink {
   scope_id: string;
   entity_id: string;
@@ -244,6 +556,176 @@ export interface Snapshot {
    * Point-in-time events captured for this snapshot.
    */
   events: Event[];
+  /**
+   * Threads that have polled instrumented futures, with their current load.
+   */
+  workers?: WorkerLoad[];
+  /**
+   * Recent polls that ran past the process's long-poll threshold, oldest
+   * first. Usually a blocking call inside async code.
+   */
+  long_polls?: LongPoll[];
+  /**
+   * Latency of the RPC methods this process served, over their recent
+   * completions, by service and method name.
+   */
+  rpc_methods?: RpcMethodStats[];
+  /**
+   * How much of the process's async work is instrumented.
+   */
+  coverage?: Coverage;
+  /**
+   * Runtime and OS resource usage of the process when the snapshot was
+   * assembled.
+   */
+  runtime_stats?: RuntimeStats;
+}
+
+/**
+ * Tracked tasks against everything the runtime is running, so an empty wait
+ * graph can be told apart from one that cannot see the stuck work.
+ */
+export interface Coverage {
+  /**
+   * Live tasks spawned through moire's spawn functions.
+   */
+  tracked_tasks: number;
+  /**
+   * Live tasks of the tokio runtime the snapshot was assembled on, moire's
+   * own included. Absent when assembled off a runtime.
+   */
+  runtime_alive_tasks?: number;
+}
+
+/**
+ * Load of the tokio runtime and the process around it, so stuck tasks can be
+ * told apart from a runtime that is not getting to them. Every field is
+ * absent when it could not be read: off a runtime, or off Linux for the OS
+ * figures.
+ */
+export interface RuntimeStats {
+  /**
+   * Worker threads of the tokio runtime the snapshot was assembled on.
+   */
+  workers?: number;
+  /**
+   * Tasks waiting in that runtime's global (injection) queue.
+   */
+  global_queue_depth?: number;
+  /**
+   * Resident set size, in bytes.
+   */
+  rss_bytes?: number;
+  /**
+   * Open file descriptors.
+   */
+  open_fds?: number;
+  /**
+   * Scheduler counters of each worker of that runtime, by worker index.
+   */
+  runtime_workers?: RuntimeWorker[];
+}
+
+/**
+ * Scheduler counters of one tokio worker thread.
+ */
+export interface RuntimeWorker {
+  /**
+   * Worker index as numbered by tokio; unrelated to `WorkerLoad::worker`.
+   */
+  index: number;
+  /**
+   * Times the worker parked for lack of work.
+   */
+  park_count: number;
+  /**
+   * Time spent running tasks, in microseconds.
+   */
+  busy_us: number;
+  /**
+   * Tasks in the worker's local run queue. Only reported by processes
+   * built with `--cfg tokio_unstable`.
+   */
+  local_queue_depth?: number;
+  /**
+   * Tasks the worker stole from other workers. Only reported by processes
+   * built with `--cfg tokio_unstable`.
+   */
+  steal_count?: number;
+}
+
+/**
+ * How much instrumented work one thread has picked up.
+ */
+export interface WorkerLoad {
+  /**
+   * Process-unique thread ordinal, as recorded in `FutureEntity::poll_worker`.
+   */
+  worker: number;
+  /**
+   * Thread name, when the thread has one.
+   */
+  thread_name?: string;
+  /**
+   * Live futures whose most recent poll ran on this thread.
+   */
+  task_count: number;
+  /**
+   * Polls of instrumented futures run on this thread so far.
+   */
+  poll_count: number;
+}
+
+/**
+ * Completions of one RPC method served by the process.
+ */
+export interface RpcMethodStats {
+  service_name: string;
+  method_name: string;
+  /**
+   * Responses that left `pending` since the process started.
+   */
+  count: number;
+  /**
+   * Of those, responses that ended in an error.
+   */
+  error_count: number;
+  /**
+   * Percentiles of the time from the response's creation to its
+   * completion, over the most recent completions only.
+   */
+  p50_ms: number;
+  p95_ms: number;
+  p99_ms: number;
+  /**
+   * How many recent completions the percentiles cover.
+   */
+  window: number;
+}
+
+/**
+ * One poll of an instrumented future that ran past the long-poll threshold.
+ */
+export interface LongPoll {
+  /**
+   * Future whose poll ran long; the innermost one when instrumented futures
+   * nest.
+   */
+  future_id: EntityId;
+  /**
+   * When the poll returned.
+   */
+  at: PTime;
+  duration_us: number;
+  /**
+   * Thread the poll ran on, as in `workers`.
+   */
+  worker: number;
+  /**
+   * Captured as the poll returned, so it shows where the future is polled
+   * from rather than the blocking call itself.
+   */
+  backtrace: BacktraceId;
 }
 
 export interface Event {
@@ -329,10 +811,20 @@ export interface Edge {
    * Causal edge kind.
    */
   kind: EdgeKind;
+  /**
+   * When the edge was created. Absent in recordings that predate it.
+   */
+  since?: PTime;
+  /**
+   * Why the source waits, for `waiting_on` edges whose reason is known.
+   */
+  reason?: EdgeReason;
 }
 
 export type EdgeKind = "polls" | "waiting_on" | "paired_with" | "held_by";
 
+export type EdgeReason = "mpsc_full" | "mpsc_empty" | "broadcast_empty" | "watch_unchanged" | "oneshot_pending" | "mutex_wait" | "rwlock_read_wait" | "rwlock_write_wait" | "lock_wait" | "semaphore_acquire" | "notify_wait" | "oncecell_initializing" | "barrier_wait" | "rpc_awaiting_response" | "command_wait" | "file_op_wait" | "net_io" | "future_await";
+
 /**
  * A scope groups execution context over time (for example process/thread/task/connection).
  */
@@ -363,15 +855,64 @@ export type ScopeBody =
   | { process: ProcessScopeBody }
   | { thread: ThreadScopeBody }
   | { task: TaskScopeBody }
-  | { connection: ConnectionScopeBody };
+  | { connection: ConnectionScopeBody }
+  | { actor: ActorScopeBody }
+  | { task_group: TaskGroupScopeBody };
+
+/**
+ * An actor ties a mailbox channel and the driver task that drains it together.
+ *
+ * The mailbox endpoints and the driver future are linked to this scope, so
+ * consumers can render them as one composite node.
+ */
+export interface ActorScopeBody {
+  /**
+   * Configured mailbox capacity (`None` for unbounded mailboxes).
+   */
+  mailbox_capacity?: number;
+}
+
+/**
+ * A task group holds the tasks spawned through one `moire::task::scope`,
+ * which all finish or are aborted before the scope exits.
+ *
+ * Every task spawned in the group is linked to this scope.
+ */
+export interface TaskGroupScopeBody {
+  /**
+   * Task that opened the scope, and so the parent of every task in it.
+   */
+  spawned_by?: EntityId;
+}
 
 export interface ConnectionScopeBody {
   local_addr?: string;
   peer_addr?: string;
+  /**
+   * Whether the connection to the peer is currently up.
+   */
+  state?: ConnectionState;
+  /**
+   * How many times the connection has been established, counting the
+   * first time. Requests and responses record the generation they ran on.
+   */
+  generation?: number;
+  /**
+   * Reconnects within the last flap window (see
+   * [`CONNECTION_FLAP_WINDOW_MS`]).
+   */
+  flaps?: number;
 }
 
+export type ConnectionState = "open" | "closed";
+
 export interface TaskScopeBody {
   task_key: string;
+  /**
+   * Stable identity of the task across process restarts (name hash plus
+   * spawn callsite). `task_key` is only unique within one process.
+   */
+  logical_id?: string;
 }
 
 export interface ThreadScopeBody {
@@ -427,6 +968,8 @@ export type EntityBody =
   | { semaphore: SemaphoreEntity }
   | { notify: NotifyEntity }
   | { once_cell: OnceCellEntity }
+  | { barrier: BarrierEntity }
+  | { heartbeat: HeartbeatEntity }
   | { command: CommandEntity }
   | { file_op: FileOpEntity }
   | { net_connect: NetConnectEntity }
@@ -439,15 +982,16 @@ export type EntityBody =
   | { aether: AetherEntity };
 
 /**
- * Synthetic entity for uninstrumented tasks.
+ * Synthetic entity for uninstrumented tasks and threads.
  *
  * Created automatically when a moire-instrumented primitive is used from a task
- * that was not spawned via `moire::task::spawn`. Makes deadlocks in uninstrumented
- * code visible on the dashboard.
+ * that was not spawned via `moire::task::spawn`, or from a thread outside any
+ * Tokio task. Makes deadlocks in uninstrumented code visible on the dashboard.
  */
 export interface AetherEntity {
   /**
-   * Tokio task ID that this aether represents.
+   * Tokio task ID that this aether represents, or outside any task `main`
+   * or `thread-<n>[-<name>]` for the thread.
    */
   task_id: string;
 }
@@ -494,6 +1038,10 @@ export interface ResponseEntity {
    * Response status and payload/error details.
    */
   status: ResponseStatus;
+  /**
+   * Generation of the server's connection the request arrived on.
+   */
+  connection_generation?: number;
 }
 
 export type ResponseStatus =
@@ -529,6 +1077,10 @@ export interface RequestEntity {
    * This is always valid JSON and should be `[]` when the method has no args.
    */
   args_json: Json;
+  /**
+   * Generation of the client's connection the request was last sent on.
+   */
+  connection_generation?: number;
 }
 
 export interface NetWriteEntity {
@@ -536,6 +1088,10 @@ export interface NetWriteEntity {
    * Endpoint address string (for example `127.0.0.1:8080`).
    */
   addr: string;
+  /**
+   * OS file descriptor of the socket, on Unix.
+   */
+  fd?: number;
 }
 
 export interface NetReadEntity {
@@ -543,6 +1099,10 @@ export interface NetReadEntity {
    * Endpoint address string (for example `127.0.0.1:8080`).
    */
   addr: string;
+  /**
+   * OS file descriptor of the socket, on Unix.
+   */
+  fd?: number;
 }
 
 export interface NetAcceptEntity {
@@ -587,6 +1147,45 @@ export interface CommandEntity {
   env: string[];
 }
 
+export interface BarrierEntity {
+  /**
+   * Number of tasks that must call `wait` before all of them are released.
+   */
+  parties: number;
+  /**
+   * Tasks that have called `wait` in the current generation.
+   */
+  arrived: number;
+  /**
+   * Tasks currently parked at the barrier, oldest arrival first.
+   */
+  waiting?: EntityId[];
+  /**
+   * When the oldest parked task arrived.
+   */
+  oldest_arrival_at?: PTime;
+}
+
+/**
+ * A loop that reports it is still making progress by beating at a known
+ * interval.
+ */
+export interface HeartbeatEntity {
+  /**
+   * How often the loop is expected to beat, in milliseconds.
+   */
+  interval_ms: number;
+  /**
+   * Beats so far. Updated together with `last_beat_at`, so it may lag the
+   * loop by the beats of a fraction of an interval.
+   */
+  beats: number;
+  /**
+   * When the loop last beat. Absent until the first beat is recorded.
+   */
+  last_beat_at?: PTime;
+}
+
 export interface OnceCellEntity {
   /**
    * Number of tasks currently waiting for initialization.
@@ -596,6 +1195,14 @@ export interface OnceCellEntity {
    * Current once-cell lifecycle state.
    */
   state: OnceCellState;
+  /**
+   * Task running the init function, while one is running.
+   */
+  initializer?: EntityId;
+  /**
+   * Tasks blocked in `get_or_init` on the initializer, oldest first.
+   */
+  waiters?: EntityId[];
 }
 
 export type OnceCellState = "empty" | "initializing" | "initialized";
@@ -616,9 +1223,42 @@ export interface SemaphoreEntity {
    * Current number of permits acquired and not yet released.
    */
   handed_out_permits: number;
+  /**
+   * Completed holds, folded in as `held_by` edges are released.
+   */
+  holds?: HoldStats;
+  /**
+   * Futures currently holding permits, longest-held first.
+   */
+  holders?: SemaphoreHolder[];
+  /**
+   * Recent `handed_out_permits` samples, oldest first. Empty unless the
+   * process keeps stat history (`MOIRE_STAT_HISTORY`).
+   */
+  history?: StatSample[];
 }
 
-export type OneshotRxEntity = object;
+export interface SemaphoreHolder {
+  /**
+   * The holding future, or its task when it is not instrumented.
+   */
+  holder: EntityId;
+  /**
+   * Permits it holds, across all of its permit guards.
+   */
+  permits: number;
+  /**
+   * When it acquired the first of them.
+   */
+  since: PTime;
+}
+
+export interface OneshotRxEntity {
+  /**
+   * Future that awaited the receiver, once it has been awaited.
+   */
+  receiver?: EntityId;
+}
 
 export interface OneshotTxEntity {
   sent: boolean;
@@ -632,13 +1272,31 @@ export interface WatchTxEntity {
 
 export interface BroadcastRxEntity {
   lag: number;
+  /**
+   * Messages this receiver missed because it fell more than `capacity` behind.
+   */
+  dropped?: number;
 }
 
 export interface BroadcastTxEntity {
   capacity: number;
+  /**
+   * Live sender handles.
+   */
+  sender_count?: number;
+  /**
+   * Live receiver handles.
+   */
+  receiver_count?: number;
 }
 
-export type MpscRxEntity = object;
+export interface MpscRxEntity {
+  /**
+   * Instrumented future that last awaited `recv`, and so drains the
+   * channel.
+   */
+  receiver?: EntityId;
+}
 
 export interface MpscTxEntity {
   /**
@@ -649,6 +1307,36 @@ export interface MpscTxEntity {
    * Configured capacity (`None` for unbounded).
    */
   capacity?: number;
+  /**
+   * Futures blocked on the full channel in `send` or `reserve_owned`,
+   * oldest first.
+   */
+  send_waiters?: MpscSendWaiter[];
+  /**
+   * Recent `queue_len` samples, oldest first. Empty unless the process
+   * keeps stat history (`MOIRE_STAT_HISTORY`).
+   */
+  history?: StatSample[];
+}
+
+/**
+ * One sample of a primitive's stat history: the value it had at `at`, which
+ * it kept until the next sample.
+ */
+export interface StatSample {
+  at: PTime;
+  value: number;
+}
+
+export interface MpscSendWaiter {
+  /**
+   * The blocked future, or its task when it is not instrumented.
+   */
+  future: EntityId;
+  /**
+   * When it started waiting.
+   */
+  since: PTime;
 }
 
 export interface LockEntity {
@@ -656,6 +1344,62 @@ export interface LockEntity {
    * Kind of lock primitive.
    */
   kind: LockKind;
+  /**
+   * Completed holds, folded in as `held_by` edges are released.
+   */
+  holds?: HoldStats;
+  /**
+   * Reader/writer breakdown, for read-write locks.
+   */
+  rwlock?: RwLockState;
+}
+
+/**
+ * Current readers, writer and waiters of a read-write lock.
+ */
+export interface RwLockState {
+  /**
+   * Read guards currently held.
+   */
+  readers: number;
+  /**
+   * Whether a write guard is currently held.
+   */
+  writer: boolean;
+  /**
+   * Tasks waiting for a read guard.
+   */
+  waiting_readers: number;
+  /**
+   * Tasks waiting for a write guard.
+   */
+  waiting_writers: number;
+}
+
+/**
+ * Poll durations of a future, counted in log-scale buckets.
+ */
+export interface PollHistogram {
+  /**
+   * Polls per bucket: bucket `i` counts polls shorter than
+   * `POLL_HISTOGRAM_BOUNDS_US[i]` that did not fit an earlier bucket, and
+   * the last bucket counts polls of 10s or more.
+   */
+  buckets: number[];
+}
+
+/**
+ * Running statistics over completed holds of a lock or semaphore.
+ */
+export interface HoldStats {
+  /**
+   * Number of holds released so far.
+   */
+  hold_count: number;
+  /**
+   * Mean hold duration in milliseconds.
+   */
+  avg_hold_ms: number;
 }
 
 export type LockKind = "mutex" | "rw_lock" | "other";
@@ -667,6 +1411,110 @@ export interface FutureEntity {
    * and the callsite is shown instead.
    */
   skip_entry_frames?: number;
+  /**
+   * Number of times the future was polled again after returning `Pending`.
+   */
+  wake_count?: number;
+  /**
+   * Wakes after which nothing under the future completed and it stayed `Pending`.
+   */
+  unproductive_wake_count?: number;
+  /**
+   * Instrumented future that was being polled when this one was last woken.
+   */
+  last_woken_by?: EntityId;
+  /**
+   * How long ago, in milliseconds, the future was woken without being
+   * polled since. Only set past the runtime's wake-gap threshold, and
+   * refreshed when a snapshot is taken: a lost wakeup or a stalled runtime
+   * rather than a wait.
+   */
+  wake_to_poll_gap_ms?: number;
+  /**
+   * Number of times the future has been polled.
+   */
+  poll_count?: number;
+  /**
+   * Thread that ran the most recent recorded poll (see `Snapshot::workers`).
+   */
+  poll_worker?: number;
+  /**
+   * Polls that ran on a different thread than the poll before them.
+   */
+  worker_migrations?: number;
+  /**
+   * Total time spent inside `poll`, in microseconds. Includes instrumented
+   * futures polled from within it.
+   */
+  busy_us?: number;
+  /**
+   * How long individual polls took.
+   */
+  poll_histogram?: PollHistogram;
+  /**
+   * Stable identity of a spawned task across process restarts: a hash of
+   * its name and spawn callsite.
+   */
+  logical_id?: string;
+  /**
+   * Task future that was running when this task was spawned. Absent for
+   * futures that are not tasks and for tasks spawned outside any task.
+   */
+  spawned_by?: EntityId;
+  /**
+   * Why this future is expected to wait indefinitely (for example
+   * `shutdown-signal`). Idle waits are left out of severity ranking.
+   */
+  idle?: string;
+  /**
+   * Where the future was named, for futures named with `moire::named!` or
+   * `#[moire::instrument]`.
+   */
+  callsite?: Callsite;
+  /**
+   * Timer statistics, for timers armed through `moire::time`.
+   */
+  timer?: TimerStats;
+}
+
+/**
+ * How a `moire::time` timer has fired against its schedule.
+ */
+export interface TimerStats {
+  /**
+   * How long the timer waits (the period, for an interval), in
+   * milliseconds.
+   */
+  duration_ms: number;
+  /**
+   * Times the timer has fired.
+   */
+  fires: number;
+  /**
+   * How long after its deadline the most recent fire resumed the waiter,
+   * in milliseconds. Includes the time the waiter took to be polled.
+   */
+  last_late_ms?: number;
+  /**
+   * The latest any fire has resumed its waiter, in milliseconds.
+   */
+  max_late_ms?: number;
+}
+
+/**
+ * Source location a future was named at.
+ */
+export interface Callsite {
+  /**
+   * Name of the crate, from `CARGO_PKG_NAME`.
+   */
+  crate_name: string;
+  /**
+   * Module path, from `module_path!()`.
+   */
+  module_path: string;
+  file: string;
+  line: number;
 }
 
 export interface SqlResponse {
@@ -721,3 +1569,21 @@ export interface ConnectionsResponse {
   processes: ConnectedProcessInfo[];
 }
 
+/**
+ * Request body for `POST /api/annotations`.
+ */
+export interface AnnotationCreateRequest {
+  target: string;
+  note: string;
+  author?: string;
+}
+
+/**
+ * Response for `GET /api/annotations`.
+ */
+export interface AnnotationListResponse {
+  /**
+   * Every stored annotation, newest first.
+   */
+  annotations: Annotation[];
+}
@@ -7,7 +7,7 @@ import type { ScopeDef } from "../../snapshot";
 import "./ScopeTablePanel.css";
 
 // Sidebar kind ordering — known kinds first, then whatever else appears.
-const KIND_ORDER = ["connection", "process", "task", "thread"];
+const KIND_ORDER = ["actor", "connection", "process", "task", "task_group", "thread"];
 
 type SortDir = "asc" | "desc";
 
@@ -18,6 +18,7 @@ import {
   Gear,
   Ghost,
   Globe,
+  Heartbeat,
   HourglassSimple,
   Key,
   Lightning,
@@ -193,6 +194,18 @@ export const NODE_KIND_SPECS: Record<string, NodeKindSpec> = {
     category: "sync",
     icon: iconFactory(Cube),
   },
+  barrier: {
+    canonical: "barrier",
+    displayName: "Barrier",
+    category: "sync",
+    icon: iconFactory(Gauge),
+  },
+  heartbeat: {
+    canonical: "heartbeat",
+    displayName: "Heartbeat",
+    category: "time",
+    icon: iconFactory(Heartbeat),
+  },
   request: {
     canonical: "request",
     displayName: "Request",
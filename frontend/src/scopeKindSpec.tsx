@@ -2,9 +2,11 @@ import type { ComponentType, ReactNode } from "react";
 import {
   ArrowsLeftRight,
   BracketsCurly,
+  Envelope,
   LinkSimple,
   StackSimple,
   Terminal,
+  TreeStructure,
   Warning,
 } from "@phosphor-icons/react";
 
@@ -43,6 +45,14 @@ const SCOPE_KIND_SPECS: Record<string, ScopeKindSpec> = {
     displayName: "connection",
     icon: iconFactory(LinkSimple),
   },
+  actor: {
+    displayName: "actor",
+    icon: iconFactory(Envelope),
+  },
+  task_group: {
+    displayName: "task group",
+    icon: iconFactory(TreeStructure),
+  },
   cycle: {
     displayName: "deadlock",
     icon: iconFactory(Warning),
@@ -462,6 +462,14 @@ export function deriveStatus(body: EntityBody): { label: string; tone: Tone } {
     if (s === "initializing") return { label: "initializing", tone: "warn" };
     return { label: "empty", tone: "neutral" };
   }
+  if ("barrier" in body) {
+    const { arrived, parties } = body.barrier;
+    return {
+      label: `${arrived}/${parties} arrived`,
+      tone: arrived > 0 ? "warn" : "ok",
+    };
+  }
+  if ("heartbeat" in body) return { label: `${body.heartbeat.beats} beats`, tone: "neutral" };
   if ("command" in body) return { label: "running", tone: "neutral" };
   if ("file_op" in body) return { label: body.file_op.op, tone: "ok" };
   if ("net_connect" in body || "net_accept" in body || "net_read" in body || "net_write" in body) {
@@ -487,6 +495,9 @@ export function deriveStat(body: EntityBody): string | undefined {
   if ("once_cell" in body) {
     return body.once_cell.waiter_count > 0 ? `${body.once_cell.waiter_count} waiter` : undefined;
   }
+  if ("barrier" in body) {
+    return `${body.barrier.arrived}/${body.barrier.parties}`;
+  }
   return undefined;
 }
 